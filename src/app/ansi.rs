@@ -0,0 +1,297 @@
+//! ANSI escape code handling for the Zack text editor.
+//!
+//! Captured logs often contain terminal escape sequences for colors. This module
+//! detects them ([`contains_escapes`]) and splits such text into its printable content
+//! and the [`ColorSpan`]s describing how it was colored ([`parse`]), so a file can be
+//! shown either with its colors or stripped of the escape codes.
+//!
+//! Only SGR sequences (`ESC [ ... m`) produce styles; every other escape sequence is
+//! dropped. Going the other way, [`sgr`] writes the sequence setting a style, to export
+//! colored text.
+
+use ratatui::style::{Color, Modifier, Style};
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+/// A styled run of chars within one line of the stripped text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorSpan {
+    pub line: usize,
+    /// First char column of the run.
+    pub start: usize,
+    /// Column just after the last char of the run.
+    pub end: usize,
+    pub style: Style,
+}
+
+/// Returns `true` if `text` contains a control sequence introducer (`ESC [`).
+pub fn contains_escapes(text: &str) -> bool {
+    text.contains("\x1b[")
+}
+
+/// Removes every escape sequence from `text`.
+pub fn strip(text: &str) -> String {
+    parse(text).0
+}
+
+/// Splits `text` into its printable content and the color spans of its SGR sequences.
+pub fn parse(text: &str) -> (String, Vec<ColorSpan>) {
+    let mut stripped = String::with_capacity(text.len());
+    let mut spans: Vec<ColorSpan> = vec![];
+    let mut style = Style::default();
+    let (mut line, mut col) = (0, 0);
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            stripped.push(c);
+
+            if c == '\n' {
+                line += 1;
+                col = 0;
+                continue;
+            }
+
+            if style != Style::default() {
+                match spans.last_mut() {
+                    Some(span) if span.line == line && span.end == col && span.style == style => {
+                        span.end += 1
+                    }
+                    _ => spans.push(ColorSpan {
+                        line,
+                        start: col,
+                        end: col + 1,
+                        style,
+                    }),
+                }
+            }
+
+            col += 1;
+            continue;
+        }
+
+        match chars.next() {
+            Some('[') => {
+                let mut parameters = String::new();
+                let mut terminator = None;
+
+                for next in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&next) {
+                        terminator = Some(next);
+                        break;
+                    }
+                    parameters.push(next);
+                }
+
+                if terminator == Some('m') {
+                    style = apply_sgr(style, &parameters);
+                }
+            }
+            Some(']') => {
+                // OSC sequences end with BEL or with ESC \.
+                while let Some(next) = chars.next() {
+                    if next == BEL || (next == ESC && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (stripped, spans)
+}
+
+/// Applies the `;`-separated SGR parameters to `style`.
+fn apply_sgr(mut style: Style, parameters: &str) -> Style {
+    let codes: Vec<u16> = parameters
+        .split([';', ':'])
+        .map(|code| code.parse().unwrap_or(0))
+        .collect();
+    let mut codes = codes.iter().copied();
+
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            7 => style.add_modifier(Modifier::REVERSED),
+            22 => without(style, Modifier::BOLD | Modifier::DIM),
+            23 => without(style, Modifier::ITALIC),
+            24 => without(style, Modifier::UNDERLINED),
+            27 => without(style, Modifier::REVERSED),
+            30..=37 => style.fg(Color::Indexed((code - 30) as u8)),
+            90..=97 => style.fg(Color::Indexed((code - 90 + 8) as u8)),
+            40..=47 => style.bg(Color::Indexed((code - 40) as u8)),
+            100..=107 => style.bg(Color::Indexed((code - 100 + 8) as u8)),
+            39 => Style { fg: None, ..style },
+            49 => Style { bg: None, ..style },
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            _ => style,
+        };
+    }
+
+    style
+}
+
+/// Returns the SGR sequence switching from the default style to `style`, or nothing
+/// for the default style.
+pub fn sgr(style: Style) -> String {
+    let mut codes: Vec<String> = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| style.add_modifier.contains(*modifier))
+    .map(|(_, code)| code.to_string())
+    .collect();
+
+    codes.extend(style.fg.and_then(|color| color_codes(color, 38)));
+    codes.extend(style.bg.and_then(|color| color_codes(color, 48)));
+
+    if codes.is_empty() {
+        return String::new();
+    }
+
+    format!("{ESC}[{}m", codes.join(";"))
+}
+
+/// Returns the palette index of the 16 named colors.
+pub fn named_index(color: Color) -> Option<u8> {
+    let index = match color {
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        Color::Indexed(index) => index,
+        Color::Reset | Color::Rgb(..) => return None,
+    };
+
+    Some(index)
+}
+
+/// Returns the `38` (foreground) or `48` (background) parameters of `color`, or `None`
+/// for the terminal's default color.
+fn color_codes(color: Color, extended: u16) -> Option<String> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some(format!("{extended};2;{r};{g};{b}")),
+        color => Some(format!("{extended};5;{}", named_index(color)?)),
+    }
+}
+
+/// Clears `modifier` from `style`, so fully reset styles compare equal to the default.
+fn without(style: Style, modifier: Modifier) -> Style {
+    Style {
+        add_modifier: style.add_modifier.difference(modifier),
+        ..style
+    }
+}
+
+/// Reads the `5;n` (indexed) or `2;r;g;b` (true color) tail of a 38/48 parameter.
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()? as u8)),
+        2 => Some(Color::Rgb(
+            codes.next()? as u8,
+            codes.next()? as u8,
+            codes.next()? as u8,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_detect_escape_sequences() {
+        assert!(contains_escapes("\x1b[31merror\x1b[0m"));
+        assert!(!contains_escapes("plain text"));
+    }
+
+    #[test]
+    fn should_strip_all_escape_sequences() {
+        let text = "\x1b]0;title\x07\x1b[1;32mok\x1b[0m done\x1b[2K\n";
+
+        assert_eq!(strip(text), "ok done\n");
+    }
+
+    #[test]
+    fn should_build_color_spans_per_line() {
+        let (text, spans) = parse("a \x1b[31mred\nmore\x1b[0m plain");
+
+        assert_eq!(text, "a red\nmore plain");
+        assert_eq!(
+            spans,
+            vec![
+                ColorSpan {
+                    line: 0,
+                    start: 2,
+                    end: 5,
+                    style: Style::default().fg(Color::Indexed(1)),
+                },
+                ColorSpan {
+                    line: 1,
+                    start: 0,
+                    end: 4,
+                    style: Style::default().fg(Color::Indexed(1)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_write_the_sequence_of_a_style_back() {
+        let style = Style::default()
+            .add_modifier(Modifier::BOLD)
+            .fg(Color::Red)
+            .bg(Color::Rgb(1, 2, 3));
+
+        assert_eq!(sgr(style), "\x1b[1;38;5;1;48;2;1;2;3m");
+        assert_eq!(
+            apply_sgr(Style::default(), "1;38;5;1;48;2;1;2;3"),
+            style.fg(Color::Indexed(1))
+        );
+        assert_eq!(sgr(Style::default()), "");
+    }
+
+    #[test]
+    fn should_parse_extended_colors_and_modifiers() {
+        let style = apply_sgr(Style::default(), "1;38;5;208;48;2;1;2;3");
+
+        assert_eq!(
+            style,
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Indexed(208))
+                .bg(Color::Rgb(1, 2, 3))
+        );
+    }
+}
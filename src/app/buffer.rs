@@ -8,18 +8,33 @@
 //!
 //! It also defines [`BufferEvent`], an enum representing edit operations that
 //! can be dispatched to the buffer. These events are translated into
-//! [`AppEvent`]s to propagate changes and trigger UI or cursor updates.
+//! [`AppEvent`]s to propagate changes and trigger UI or cursor updates. Every edit is
+//! recorded in the buffer's [`UndoHistory`], and reported in its [`ChangeLog`] for the
+//! consumers updating incrementally.
 
 use crate::{
+    app::{
+        changes::{self, Change, ChangeLog},
+        count::Counts,
+        undo::{CharEdit, Snapshot, UndoHistory, UndoState},
+    },
     event::{AppEvent, CursorEvent},
     types::position::Position,
 };
 use ropey::{Rope, iter::Lines};
+use std::time::Instant;
 
 /// Represents the main text buffer for editing, backed by a `Rope` for efficient operations.
 #[derive(Debug)]
 pub struct Buffer {
     rope: Rope,
+    /// Changed on every edit, so saved states can be compared against the current one.
+    /// Undoing brings back the revision of the restored state.
+    revision: u64,
+    /// Newest revision given out, so a new edit never reuses an undone one.
+    last_revision: u64,
+    history: UndoHistory,
+    changes: ChangeLog,
 }
 
 /// Describes high-level buffer modification events.
@@ -33,6 +48,61 @@ pub enum BufferEvent {
 
     /// Inserts a new line at a given position.
     InsertNewline { position: Position },
+
+    /// Inserts a string at a given position, leaving the cursor after it.
+    InsertText { text: String, position: Position },
+
+    /// Replaces the lines `start..=end` with `text`, leaving the cursor on `start`.
+    ReplaceLines {
+        start: usize,
+        end: usize,
+        text: String,
+    },
+
+    /// Replaces the text from `start` up to `end`, exclusive, with `text`, leaving the
+    /// cursor on `start`.
+    ReplaceRange {
+        start: Position,
+        end: Position,
+        text: String,
+    },
+
+    /// Wraps the text between `start` and `end`, both inclusive, in `open` and `close`,
+    /// leaving the cursor on `open`.
+    Surround {
+        open: char,
+        close: char,
+        start: Position,
+        end: Position,
+    },
+
+    /// Goes back to the state before the last undo step; `position` is the cursor.
+    Undo { position: Position },
+
+    /// Goes forward to the state after the last undone step; `position` is the cursor.
+    Redo { position: Position },
+
+    /// Undoes or redoes steps until the numbered state of the history; `position` is the
+    /// cursor.
+    UndoTo { state: usize, position: Position },
+}
+
+impl BufferEvent {
+    /// Returns where an edit happens, or `None` for events that do not edit the text.
+    pub fn edit_position(&self) -> Option<Position> {
+        match self {
+            BufferEvent::InsertChar { position, .. }
+            | BufferEvent::DeleteChar { position }
+            | BufferEvent::InsertNewline { position }
+            | BufferEvent::InsertText { position, .. } => Some(*position),
+            BufferEvent::ReplaceLines { start, .. } => Some(Position::new(*start, 0)),
+            BufferEvent::ReplaceRange { start, .. } => Some(*start),
+            BufferEvent::Surround { start, .. } => Some(*start),
+            BufferEvent::Undo { .. } | BufferEvent::Redo { .. } | BufferEvent::UndoTo { .. } => {
+                None
+            }
+        }
+    }
 }
 
 impl Default for Buffer {
@@ -46,6 +116,10 @@ impl Buffer {
     pub fn new(initial_text: String) -> Self {
         Self {
             rope: Rope::from_str(&initial_text),
+            revision: 0,
+            last_revision: 0,
+            history: UndoHistory::new(),
+            changes: ChangeLog::new(),
         }
     }
 
@@ -53,6 +127,11 @@ impl Buffer {
     pub fn handle_event(&mut self, event: BufferEvent) -> Vec<AppEvent> {
         let mut events = vec![];
 
+        let before = event
+            .edit_position()
+            .map(|position| self.snapshot(position));
+        let edit = CharEdit::of(&event);
+
         match event {
             BufferEvent::InsertChar { char, position } => {
                 events.extend(self.insert_char(char, position))
@@ -61,11 +140,82 @@ impl Buffer {
             BufferEvent::InsertNewline { position } => {
                 events.extend(self.insert_new_line(position))
             }
+            BufferEvent::InsertText { text, position } => {
+                events.extend(self.insert_text(&text, position))
+            }
+            BufferEvent::ReplaceLines { start, end, text } => {
+                events.extend(self.replace_lines(start, end, &text))
+            }
+            BufferEvent::ReplaceRange { start, end, text } => {
+                events.extend(self.replace_range(start, end, &text))
+            }
+            BufferEvent::Surround {
+                open,
+                close,
+                start,
+                end,
+            } => events.extend(self.surround(open, close, start, end)),
+            BufferEvent::Undo { position } => {
+                let restored = self.history.undo(self.snapshot(position));
+                events.extend(self.restore(restored))
+            }
+            BufferEvent::Redo { position } => {
+                let restored = self.history.redo(self.snapshot(position));
+                events.extend(self.restore(restored))
+            }
+            BufferEvent::UndoTo { state, position } => {
+                let restored = self.history.jump(state, self.snapshot(position));
+                if restored.is_some() {
+                    events.extend(self.restore(restored))
+                }
+            }
+        }
+
+        // Edits that change nothing, like deleting at the start, leave no undo step.
+        if let Some(before) = before
+            && before.revision != self.revision
+        {
+            self.history.record(before, edit, Instant::now());
         }
 
         events
     }
 
+    /// Returns the undo history of the buffer.
+    pub fn history(&self) -> &UndoHistory {
+        &self.history
+    }
+
+    /// Returns the log of the changes made to the text.
+    pub fn changes(&self) -> &ChangeLog {
+        &self.changes
+    }
+
+    /// Lists the states of the undo history, oldest first, marking the current one.
+    pub fn undo_states(&self) -> Vec<UndoState> {
+        self.history.states(&self.snapshot(Position::default()))
+    }
+
+    /// Closes the current undo step, so the next edit starts a new one.
+    pub fn break_undo(&mut self) {
+        self.history.break_point();
+    }
+
+    /// Returns the character right before `position` on its line, if any.
+    pub fn char_before(&self, position: Position) -> Option<char> {
+        if position.col == 0 || position.line >= self.rope.len_lines() {
+            return None;
+        }
+
+        let char_index = self.position_to_char(position);
+        (char_index > 0).then(|| self.rope.char(char_index - 1))
+    }
+
+    /// Returns the current revision, which changes every time the content is edited.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     /// Returns the total number of lines in the buffer.
     pub fn len_lines(&self) -> usize {
         self.rope.len_lines()
@@ -100,24 +250,275 @@ impl Buffer {
         &self.rope
     }
 
-    /// Calculates the character index in the rope from a `Position`.
-    fn calculate_char_index(&self, position: Position) -> usize {
+    /// Returns the text between `start` and `end`, both inclusive.
+    ///
+    /// Columns past the end of a line are clamped, so a selection ending on an empty
+    /// line or after the last character never panics.
+    pub fn text_between(&self, start: Position, end: Position) -> String {
+        let (from, to) = self.chars_between(start, end);
+
+        self.rope.slice(from..to).to_string()
+    }
+
+    /// Converts `position` into a char index in the rope. Columns past the end of a line
+    /// are clamped to it, its line break included, and lines past the last to the end of
+    /// the buffer.
+    pub fn position_to_char(&self, position: Position) -> usize {
+        if position.line >= self.rope.len_lines() {
+            return self.rope.len_chars();
+        }
+
         let line_start = self.rope.line_to_char(position.line);
         let line_len = self.rope.line(position.line).len_chars();
 
-        let clamped_col = position.col.min(line_len);
+        line_start + position.col.min(line_len)
+    }
+
+    /// Converts the char index `index` into a `Position`, clamped to the end of the buffer.
+    pub fn char_to_position(&self, index: usize) -> Position {
+        let index = index.min(self.rope.len_chars());
+        let line = self.rope.char_to_line(index);
+
+        Position::new(line, index - self.rope.line_to_char(line))
+    }
+
+    /// Returns the line, word and char counts of the text between `start` and `end`,
+    /// both inclusive, or of the whole buffer without them.
+    pub fn counts(&self, range: Option<(Position, Position)>) -> Counts {
+        let (from, to) = match range {
+            Some((start, end)) => self.chars_between(start, end),
+            None => (0, self.rope.len_chars()),
+        };
+
+        Counts::of(self.rope.slice(from..to))
+    }
+
+    /// Returns the char range from `start` to past `end`, empty when `end` comes first.
+    fn chars_between(&self, start: Position, end: Position) -> (usize, usize) {
+        let end = Position::new(end.line, self.clamp_col_position(&end));
+        let from = self.position_to_char(start);
+        let to = (self.position_to_char(end) + 1).min(self.rope.len_chars());
+
+        (from, to.max(from))
+    }
+
+    /// Returns the whole lines `start..=end`, including the line break of the last one.
+    pub fn lines_text(&self, start: usize, end: usize) -> String {
+        let (from, to) = self.line_range_chars(start, end);
+
+        self.rope.slice(from..to).to_string()
+    }
+
+    /// Finds the first occurrence of `pattern` starting after `from`, wrapping around
+    /// to the top of the buffer when there is no match below.
+    pub fn find_next(&self, pattern: &str, from: Position) -> Option<Position> {
+        let from_byte = self.rope.char_to_byte(self.position_to_char(from));
+        let matches = self.match_byte_offsets(pattern);
+
+        matches
+            .iter()
+            .find(|&&byte| byte > from_byte)
+            .or(matches.first())
+            .map(|&byte| self.byte_to_position(byte))
+    }
+
+    /// Finds the last occurrence of `pattern` starting before `from`, wrapping around
+    /// to the bottom of the buffer when there is no match above.
+    pub fn find_previous(&self, pattern: &str, from: Position) -> Option<Position> {
+        let from_byte = self.rope.char_to_byte(self.position_to_char(from));
+        let matches = self.match_byte_offsets(pattern);
+
+        matches
+            .iter()
+            .rev()
+            .find(|&&byte| byte < from_byte)
+            .or(matches.last())
+            .map(|&byte| self.byte_to_position(byte))
+    }
+
+    /// Returns the byte offsets of every literal occurrence of `pattern` in the buffer.
+    fn match_byte_offsets(&self, pattern: &str) -> Vec<usize> {
+        if pattern.is_empty() {
+            return vec![];
+        }
+
+        self.rope
+            .to_string()
+            .match_indices(pattern)
+            .map(|(byte, _)| byte)
+            .collect()
+    }
+
+    /// Converts a byte offset in the rope into a `Position`.
+    fn byte_to_position(&self, byte: usize) -> Position {
+        self.char_to_position(self.rope.byte_to_char(byte))
+    }
+
+    /// Captures the current state, with the cursor at `cursor`.
+    fn snapshot(&self, cursor: Position) -> Snapshot {
+        Snapshot {
+            rope: self.rope.clone(),
+            revision: self.revision,
+            cursor,
+            time: Instant::now(),
+        }
+    }
+
+    /// Brings back a state taken from the undo history and moves the cursor where it was.
+    fn restore(&mut self, snapshot: Option<Snapshot>) -> Vec<AppEvent> {
+        let Some(snapshot) = snapshot else {
+            tracing::info!("Nothing to undo or redo");
+            return vec![];
+        };
+
+        // Report the smallest range that differs, not the whole text.
+        let old = &self.rope;
+        let new = &snapshot.rope;
+        let (prefix, suffix) = changes::common_ends(old, new);
+        let new_text = new.slice(prefix..new.len_chars() - suffix).to_string();
+        self.report(prefix, old.len_chars() - suffix, &new_text);
+
+        self.rope = snapshot.rope;
+        self.revision = snapshot.revision;
+
+        let line = snapshot
+            .cursor
+            .line
+            .min(self.rope.len_lines().saturating_sub(1));
+        let col = self.clamp_col_position(&Position::new(line, snapshot.cursor.col));
+
+        vec![AppEvent::Cursor(CursorEvent::SetPosition { line, col })]
+    }
+
+    /// Replaces the chars `from..to` with `text`, reporting the change.
+    fn splice(&mut self, from: usize, to: usize, text: &str) {
+        self.report(from, to, text);
+        self.rope.remove(from..to);
+        self.rope.insert(from, text);
+    }
+
+    /// Reports in the change log that the chars `from..to` are replaced with `text`.
+    fn report(&mut self, from: usize, to: usize, text: &str) {
+        let start = self.char_to_position(from);
+        let line_start = self.rope.line_to_char(start.line);
+
+        self.changes.push(Change {
+            start_char: from,
+            start,
+            start_utf16: self
+                .rope
+                .slice(line_start..from)
+                .chars()
+                .map(char::len_utf16)
+                .sum(),
+            old_text: self.rope.slice(from..to).to_string(),
+            new_text: text.to_string(),
+        });
+    }
 
-        line_start + clamped_col
+    /// Gives the content a new revision after an edit.
+    fn bump_revision(&mut self) {
+        self.last_revision += 1;
+        self.revision = self.last_revision;
     }
 
     /// Inserts a character at the given position and emits a cursor move.
     fn insert_char(&mut self, char: char, position: Position) -> Vec<AppEvent> {
-        let char_index = self.calculate_char_index(position);
-        self.rope.insert_char(char_index, char);
+        let char_index = self.position_to_char(position);
+        self.splice(char_index, char_index, char.encode_utf8(&mut [0; 4]));
+        self.bump_revision();
 
         vec![AppEvent::Cursor(CursorEvent::MoveRight)]
     }
 
+    /// Inserts `close` after `end` and `open` at `start`, then moves the cursor to `open`.
+    /// Columns past the end of a line are clamped, keeping the line break.
+    fn surround(
+        &mut self,
+        open: char,
+        close: char,
+        start: Position,
+        end: Position,
+    ) -> Vec<AppEvent> {
+        let after = Position::new(
+            end.line,
+            end.col.saturating_add(1).min(self.max_visible_col(&end)),
+        );
+        let start = Position::new(start.line, self.clamp_col_position(&start));
+
+        let close_index = self.position_to_char(after);
+        self.splice(close_index, close_index, close.encode_utf8(&mut [0; 4]));
+        let open_index = self.position_to_char(start);
+        self.splice(open_index, open_index, open.encode_utf8(&mut [0; 4]));
+        self.bump_revision();
+
+        vec![AppEvent::Cursor(CursorEvent::SetPosition {
+            line: start.line,
+            col: start.col,
+        })]
+    }
+
+    /// Inserts a string at the given position and moves the cursor to its end.
+    fn insert_text(&mut self, text: &str, position: Position) -> Vec<AppEvent> {
+        let char_index = self.position_to_char(position);
+        self.splice(char_index, char_index, text);
+        self.bump_revision();
+
+        let end = char_index + text.chars().count();
+        let line = self.rope.char_to_line(end);
+        let col = end - self.rope.line_to_char(line);
+
+        vec![AppEvent::Cursor(CursorEvent::SetPosition { line, col })]
+    }
+
+    /// Replaces whole lines with `text`, keeping the line break after them as it was.
+    fn replace_lines(&mut self, start: usize, end: usize, text: &str) -> Vec<AppEvent> {
+        let (from, to) = self.line_range_chars(start, end);
+        let ends_with_newline = to > from && self.rope.char(to - 1) == '\n';
+
+        let mut replacement = text.to_string();
+        if ends_with_newline && !replacement.is_empty() && !replacement.ends_with('\n') {
+            replacement.push('\n');
+        } else if !ends_with_newline && replacement.ends_with('\n') {
+            replacement.pop();
+        }
+
+        self.splice(from, to, &replacement);
+        self.bump_revision();
+
+        vec![AppEvent::Cursor(CursorEvent::SetPosition {
+            line: start,
+            col: 0,
+        })]
+    }
+
+    /// Replaces the text from `start` up to `end`, exclusive, with `text`, and moves the
+    /// cursor to `start`. Columns past the end of a line are clamped.
+    fn replace_range(&mut self, start: Position, end: Position, text: &str) -> Vec<AppEvent> {
+        let from = self.position_to_char(start);
+        let to = self.position_to_char(end).max(from);
+
+        self.splice(from, to, text);
+        self.bump_revision();
+
+        vec![AppEvent::Cursor(CursorEvent::SetPosition {
+            line: start.line,
+            col: start.col,
+        })]
+    }
+
+    /// Returns the char range covering the whole lines `start..=end`, clamped to the buffer.
+    fn line_range_chars(&self, start: usize, end: usize) -> (usize, usize) {
+        let last = self.rope.len_lines().saturating_sub(1);
+
+        let from = self.rope.line_to_char(start.min(last));
+        let to = self
+            .rope
+            .line_to_char((end.min(last) + 1).min(self.rope.len_lines()));
+
+        (from, to.max(from))
+    }
+
     /// Deletes a character at the given position and emits appropriate cursor events.
     fn delete_char(&mut self, position: Position) -> Vec<AppEvent> {
         let mut events = vec![];
@@ -125,10 +526,11 @@ impl Buffer {
         if position.col == 0 && position.line > 0 {
             self.merge_with_line_above(position, &mut events);
         } else if position.col > 0 {
-            let char_index = self.calculate_char_index(position);
+            let char_index = self.position_to_char(position);
 
             if char_index > 0 {
-                self.rope.remove(char_index - 1..char_index);
+                self.splice(char_index - 1, char_index, "");
+                self.bump_revision();
                 events.push(AppEvent::Cursor(CursorEvent::MoveLeft));
             }
         }
@@ -140,10 +542,11 @@ impl Buffer {
     fn merge_with_line_above(&mut self, position: Position, events: &mut Vec<AppEvent>) {
         let prev_line_len = self.rope.line(position.line - 1).len_chars();
 
-        let char_index = self.calculate_char_index(Position::new(position.line, 0));
+        let char_index = self.position_to_char(Position::new(position.line, 0));
 
         if char_index > 0 {
-            self.rope.remove(char_index - 1..char_index);
+            self.splice(char_index - 1, char_index, "");
+            self.bump_revision();
 
             let col_pos = if prev_line_len == 0 {
                 0
@@ -162,9 +565,10 @@ impl Buffer {
     fn insert_new_line(&mut self, position: Position) -> Vec<AppEvent> {
         let mut events = vec![];
 
-        let char_index = self.calculate_char_index(position);
+        let char_index = self.position_to_char(position);
 
-        self.rope.insert(char_index, "\n");
+        self.splice(char_index, char_index, "\n");
+        self.bump_revision();
 
         events.push(AppEvent::Cursor(CursorEvent::MoveDown));
         events.push(AppEvent::Cursor(CursorEvent::MoveToLineStart));
@@ -177,6 +581,7 @@ impl Buffer {
 mod tests {
     use super::*;
     use crate::event::CursorEvent;
+    use proptest::prelude::*;
 
     fn pos(line: usize, col: usize) -> Position {
         Position::new(line, col)
@@ -218,6 +623,91 @@ mod tests {
         assert!(events.contains(&AppEvent::Cursor(CursorEvent::MoveToLineStart)));
     }
 
+    #[test]
+    fn should_insert_text_and_move_cursor_to_its_end() {
+        let mut buffer = create_buffer_with_text("fn ma() {}");
+
+        let events = buffer.handle_event(BufferEvent::InsertText {
+            text: String::from("in"),
+            position: pos(0, 5),
+        });
+
+        assert_eq!(buffer.as_rope().to_string(), "fn main() {}");
+        assert_eq!(
+            events,
+            vec![AppEvent::Cursor(CursorEvent::SetPosition {
+                line: 0,
+                col: 7
+            })]
+        );
+    }
+
+    #[test]
+    fn should_replace_lines_keeping_trailing_newline_state() {
+        let mut buffer = create_buffer_with_text("c\nb\na\nend");
+
+        let events = buffer.handle_event(BufferEvent::ReplaceLines {
+            start: 0,
+            end: 2,
+            text: String::from("a\nb\nc"),
+        });
+
+        assert_eq!(buffer.as_rope().to_string(), "a\nb\nc\nend");
+        assert_eq!(
+            events,
+            vec![AppEvent::Cursor(CursorEvent::SetPosition {
+                line: 0,
+                col: 0
+            })]
+        );
+
+        buffer.handle_event(BufferEvent::ReplaceLines {
+            start: 3,
+            end: 3,
+            text: String::from("END\n"),
+        });
+
+        assert_eq!(buffer.as_rope().to_string(), "a\nb\nc\nEND");
+    }
+
+    #[test]
+    fn should_surround_text_and_move_cursor_to_the_opening_char() {
+        let mut buffer = create_buffer_with_text("let a = b;\n\nc");
+
+        let events = buffer.handle_event(BufferEvent::Surround {
+            open: '(',
+            close: ')',
+            start: pos(0, 8),
+            end: pos(0, 8),
+        });
+
+        assert_eq!(buffer.as_rope().to_string(), "let a = (b);\n\nc");
+        assert_eq!(
+            events,
+            vec![AppEvent::Cursor(CursorEvent::SetPosition {
+                line: 0,
+                col: 8
+            })]
+        );
+
+        buffer.handle_event(BufferEvent::Surround {
+            open: '"',
+            close: '"',
+            start: pos(0, 4),
+            end: pos(1, 0),
+        });
+
+        assert_eq!(buffer.as_rope().to_string(), "let \"a = (b);\n\"\nc");
+    }
+
+    #[test]
+    fn should_return_whole_lines_text() {
+        let buffer = create_buffer_with_text("one\ntwo\nthree");
+
+        assert_eq!(buffer.lines_text(1, 1), "two\n");
+        assert_eq!(buffer.lines_text(1, 9), "two\nthree");
+    }
+
     #[test]
     fn should_delete_character_before_cursor() {
         let mut buffer = create_buffer_with_text("Hello, Zack!");
@@ -256,14 +746,188 @@ mod tests {
     }
 
     #[test]
-    fn should_calculate_correct_char_index() {
+    fn should_convert_position_to_char_index() {
         let buffer = create_buffer_with_text("abc\ndef");
-        let index = buffer.calculate_char_index(Position::new(1, 2));
+        let index = buffer.position_to_char(Position::new(1, 2));
 
         // Index 0–2 = "abc" (line 0, +1 for \n), line 1 starts at char 4
         assert_eq!(index, 6); // "abc\n" = 4, "de" = index 4 + 2
     }
 
+    #[test]
+    fn should_clamp_positions_and_indices_to_the_buffer() {
+        let buffer = create_buffer_with_text("abc\ndef");
+
+        assert_eq!(buffer.position_to_char(pos(0, 9)), 4);
+        assert_eq!(buffer.position_to_char(pos(5, 0)), 7);
+        assert_eq!(buffer.char_to_position(4), pos(1, 0));
+        assert_eq!(buffer.char_to_position(99), pos(1, 3));
+    }
+
+    proptest! {
+        #[test]
+        fn should_round_trip_char_indices(
+            text in "[aé\t\n]{0,40}",
+            index in 0usize..64,
+        ) {
+            let buffer = create_buffer_with_text(&text);
+            let index = index % (buffer.as_rope().len_chars() + 1);
+
+            prop_assert_eq!(buffer.position_to_char(buffer.char_to_position(index)), index);
+        }
+
+        #[test]
+        fn should_round_trip_positions(
+            text in "[aé\t\n]{0,40}",
+            line in 0usize..64,
+            col in 0usize..64,
+        ) {
+            let buffer = create_buffer_with_text(&text);
+            let line = line % buffer.len_lines();
+            let len = buffer.max_visible_col(&pos(line, 0));
+            let position = pos(line, col % (len + 1));
+
+            prop_assert_eq!(buffer.char_to_position(buffer.position_to_char(position)), position);
+        }
+    }
+
+    #[test]
+    fn should_return_text_between_positions_inclusive() {
+        let buffer = create_buffer_with_text("Hello\nWorld");
+
+        assert_eq!(buffer.text_between(pos(0, 1), pos(0, 3)), "ell");
+        assert_eq!(buffer.text_between(pos(0, 3), pos(1, 1)), "lo\nWo");
+        assert_eq!(buffer.text_between(pos(1, 3), pos(1, 99)), "ld");
+    }
+
+    #[test]
+    fn should_find_next_match_and_wrap_around() {
+        let buffer = create_buffer_with_text("foo bar\nbar foo");
+
+        assert_eq!(buffer.find_next("foo", pos(0, 0)), Some(pos(1, 4)));
+        assert_eq!(buffer.find_next("foo", pos(1, 4)), Some(pos(0, 0)));
+        assert_eq!(buffer.find_next("baz", pos(0, 0)), None);
+        assert_eq!(buffer.find_next("", pos(0, 0)), None);
+    }
+
+    #[test]
+    fn should_find_previous_match_and_wrap_around() {
+        let buffer = create_buffer_with_text("foo bar\nbar foo");
+
+        assert_eq!(buffer.find_previous("bar", pos(1, 4)), Some(pos(1, 0)));
+        assert_eq!(buffer.find_previous("bar", pos(0, 0)), Some(pos(1, 0)));
+        assert_eq!(buffer.find_previous("bar", pos(0, 4)), Some(pos(1, 0)));
+    }
+
+    #[test]
+    fn should_find_matches_after_multibyte_characters() {
+        let buffer = create_buffer_with_text("ção ção");
+
+        assert_eq!(buffer.find_next("ção", pos(0, 0)), Some(pos(0, 4)));
+    }
+
+    #[test]
+    fn should_bump_revision_only_on_edits() {
+        let mut buffer = create_buffer_with_text("ab");
+
+        buffer.handle_event(BufferEvent::DeleteChar {
+            position: pos(0, 0),
+        });
+        assert_eq!(buffer.revision(), 0);
+
+        buffer.handle_event(BufferEvent::InsertChar {
+            char: 'c',
+            position: pos(0, 2),
+        });
+        assert_eq!(buffer.revision(), 1);
+    }
+
+    #[test]
+    fn should_report_edits_and_undos_as_changed_ranges() {
+        let mut buffer = create_buffer_with_text("one\ntwo\nthree");
+
+        buffer.handle_event(BufferEvent::ReplaceRange {
+            start: pos(1, 1),
+            end: pos(2, 2),
+            text: String::from("W"),
+        });
+        buffer.handle_event(BufferEvent::Undo {
+            position: pos(1, 1),
+        });
+
+        let changes: Vec<_> = buffer.changes().since(0).unwrap().cloned().collect();
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    start_char: 5,
+                    start: pos(1, 1),
+                    start_utf16: 1,
+                    old_text: String::from("wo\nth"),
+                    new_text: String::from("W"),
+                },
+                Change {
+                    start_char: 5,
+                    start: pos(1, 1),
+                    start_utf16: 1,
+                    old_text: String::from("W"),
+                    new_text: String::from("wo\nth"),
+                },
+            ]
+        );
+        assert_eq!(changes[0].old_end(), pos(2, 2));
+    }
+
+    #[test]
+    fn should_undo_and_redo_edits_restoring_the_revision() {
+        let mut buffer = create_buffer_with_text("ab");
+
+        buffer.handle_event(BufferEvent::InsertChar {
+            char: 'c',
+            position: pos(0, 2),
+        });
+        buffer.break_undo();
+        buffer.handle_event(BufferEvent::InsertText {
+            text: String::from("de"),
+            position: pos(0, 3),
+        });
+
+        let events = buffer.handle_event(BufferEvent::Undo {
+            position: pos(0, 5),
+        });
+        assert_eq!(buffer.as_rope().to_string(), "abc");
+        assert_eq!(
+            events,
+            vec![AppEvent::Cursor(CursorEvent::SetPosition {
+                line: 0,
+                col: 3
+            })]
+        );
+
+        buffer.handle_event(BufferEvent::Undo {
+            position: pos(0, 3),
+        });
+        assert_eq!(buffer.as_rope().to_string(), "ab");
+        assert_eq!(buffer.revision(), 0);
+
+        buffer.handle_event(BufferEvent::Redo {
+            position: pos(0, 2),
+        });
+        assert_eq!(buffer.as_rope().to_string(), "abc");
+        assert_eq!(buffer.revision(), 1);
+    }
+
+    #[test]
+    fn should_not_record_edits_that_change_nothing() {
+        let mut buffer = create_buffer_with_text("ab");
+
+        buffer.handle_event(BufferEvent::DeleteChar {
+            position: pos(0, 0),
+        });
+
+        assert!(buffer.history().is_empty());
+    }
+
     #[test]
     fn should_return_number_of_lines() {
         let buffer = create_buffer_with_text("line1\nline2\nline3");
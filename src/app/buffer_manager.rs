@@ -0,0 +1,1500 @@
+//! Buffer manager module for the Zack text editor.
+//!
+//! This module provides the [`BufferManager`] struct, which owns every open
+//! [`Document`] (a buffer together with its cursor, file, language server and
+//! diagnostics) and tracks which one is active. Files containing ANSI escape codes
+//! trigger a prompt to either show their colors read-only or strip the codes. It defines [`BufferManagerEvent`] for
+//! opening and switching documents and for operations that span all of them, like
+//! writing every modified buffer at once.
+//!
+//! Operations on several documents never stop at the first failure: each document is
+//! attempted, and every failure is reported individually.
+//!
+//! Two documents can be compared with each other, as by `zack -d`: the hunks of each
+//! one are then its differences from the other, kept up to date as either is edited.
+
+use crate::{
+    app::{
+        ansi::{self, ColorSpan},
+        buffer::{Buffer, BufferEvent},
+        count::Counts,
+        cursor::Cursor,
+        diagnostics::{self, Diagnostic, Sign},
+        editorconfig::{self, FileSettings},
+        file::{self, File},
+        filetype::{FileType, FiletypeSettings},
+        folds::Folds,
+        git::{self, Hunk},
+        guard::{Guard, Limits},
+        keymap::{KeymapMode, Layer},
+        license::CommentStyle,
+        options::Options,
+        pretty,
+        tail::Tail,
+        template,
+    },
+    event::{AppEvent, CursorEvent, Event},
+    lsp::{LspClient, protocol},
+    types::position::Position,
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+/// An open file: its text, cursor, on-disk location, language server and diagnostics.
+#[derive(Debug)]
+pub struct Document {
+    pub buffer: Buffer,
+    pub cursor: Cursor,
+    pub file: File,
+    /// Language of the text, from the file name or the `#!` line.
+    pub filetype: Option<FileType>,
+    pub lsp: Option<LspClient>,
+    /// Diagnostics last published for this document, with columns counted in chars.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Colors of the escape codes stripped from the text, sorted by line.
+    pub colors: Vec<ColorSpan>,
+    /// Refuses edits, e.g. while showing a colored view of the file.
+    pub read_only: bool,
+    /// First line shown in the editor.
+    pub scroll: usize,
+    /// Folds closed in the editor, hiding the lines they hold.
+    pub folds: Folds,
+    /// Watches the file for appended content while in follow mode.
+    pub follow: Option<Tail>,
+    /// The file as committed at `HEAD`, when tracked by git.
+    pub git_base: Option<String>,
+    /// The git branch checked out where the file is, for the status line.
+    pub branch: Option<String>,
+    /// Text compared with instead of `git_base`, like the other file of `zack -d`.
+    pub diff_base: Option<String>,
+    /// Lines that differ from `diff_base` or `git_base`, in buffer order.
+    pub hunks: Vec<Hunk>,
+    /// Buffer revision `hunks` were computed for.
+    hunks_revision: Option<u64>,
+    /// Blame of the cursor line, as last requested.
+    pub blame: Option<LineBlame>,
+    /// Set while a blame request runs, so at most one is in flight.
+    blame_pending: bool,
+    /// Why per-edit work like git diffing is skipped, when the document is too big.
+    pub guard: Option<Guard>,
+    /// Shows long lines split at synthetic breaks, for minified files.
+    pub pretty: bool,
+    /// Bindings local to this buffer, shadowing those of the user and the defaults.
+    pub keymaps: HashMap<KeymapMode, Layer>,
+    /// Directory set with `:lcd`, which relative paths opened or saved from this
+    /// document are taken from instead of the working directory of the editor.
+    pub directory: Option<PathBuf>,
+    /// Set with `:guard` to do the per-edit work even though the document is guarded.
+    pub guard_override: bool,
+    /// Buffer revision and limits `guard` was checked for.
+    guard_checked: Option<(u64, Limits)>,
+    /// Counts of the whole buffer, with the revision they were made for.
+    counts: Cell<Option<(u64, Counts)>>,
+    /// Lines with a match of a search pattern, with the revision and pattern they were
+    /// found for.
+    match_lines: RefCell<Option<(u64, String, Vec<usize>)>>,
+}
+
+/// The blame annotation of a line, for a given buffer revision.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineBlame {
+    pub line: usize,
+    pub revision: u64,
+    /// The annotation, or `None` until it arrives or when the line cannot be blamed.
+    pub text: Option<String>,
+}
+
+/// What a document holds, which the theme gives a distinct background.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferKind {
+    /// A file that can be edited.
+    File,
+    /// Text that is not backed by a file yet.
+    Scratch,
+    /// A document refusing edits, like a colored view of a file.
+    ReadOnly,
+    /// A file being followed for appended content.
+    Following,
+}
+
+/// A document that could not be written, with the reason.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteFailure {
+    /// Display name of the document.
+    pub name: String,
+    /// Why the write failed.
+    pub reason: String,
+}
+
+/// Events that open, switch or act on several documents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BufferManagerEvent {
+    /// Opens `path` (or switches to it if already open) and makes it active.
+    Open(PathBuf),
+    /// Makes the next document active, wrapping around.
+    Next,
+    /// Makes the previous document active, wrapping around.
+    Previous,
+    /// Opens a scratch copy of the active document, with the same text and cursor, and
+    /// makes it active.
+    Clone,
+    /// Writes every modified document, then quits if `quit` is set and all writes succeeded.
+    WriteAll { quit: bool },
+    /// Replaces the diagnostics of the document open at `path`.
+    ///
+    /// Columns are in UTF-16 code units, as published by the language server.
+    PublishDiagnostics {
+        path: PathBuf,
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// Replaces the escape codes of a document with their colors, making it read-only.
+    ShowAnsiColors(usize),
+    /// Removes the escape codes of a document, leaving it editable.
+    StripAnsi(usize),
+    /// Starts or stops following the file of the active document.
+    ToggleFollow,
+    /// Shows a document in the pretty view, splitting long lines at synthetic breaks.
+    ShowPretty(usize),
+    /// Turns the pretty view of the active document on or off.
+    TogglePretty,
+    /// Turns the per-edit work of the active document back on while it is guarded, or
+    /// leaves it to the limits again.
+    ToggleGuard,
+    /// Reads the file of the active document again, after another program changed it.
+    Reload,
+    /// Content appended to the followed file at `path`.
+    TailAppend { path: PathBuf, text: String },
+    /// The followed file at `path` shrank and must be read again.
+    TailReload(PathBuf),
+    /// Moves the cursor to the next hunk changed since `HEAD`, wrapping around.
+    NextHunk,
+    /// Moves the cursor to the previous hunk changed since `HEAD`, wrapping around.
+    PreviousHunk,
+    /// Sets the local directory of the active document, or drops it when `None`.
+    SetDirectory(Option<PathBuf>),
+    /// The blame of `line` of the document at `path`, for buffer `revision`.
+    BlameLoaded {
+        path: PathBuf,
+        line: usize,
+        revision: u64,
+        text: Option<String>,
+    },
+}
+
+/// Owns all open documents and tracks the active one.
+#[derive(Debug)]
+pub struct BufferManager {
+    documents: Vec<Document>,
+    active: usize,
+    /// Used by language servers to deliver their responses.
+    sender: mpsc::Sender<Event>,
+    /// Two documents compared with each other.
+    comparison: Option<Comparison>,
+    /// Settings of the file types from the configuration file, by name.
+    filetypes: HashMap<String, FiletypeSettings>,
+    /// Whether documents get a missing final line break when written, as `:set fixeol`.
+    fixeol: bool,
+}
+
+/// Two documents compared with each other, and their buffer revisions when last compared.
+#[derive(Debug)]
+struct Comparison {
+    documents: [usize; 2],
+    revisions: Option<[u64; 2]>,
+}
+
+impl Document {
+    /// Creates a document with the given content and optional path, without a language server.
+    pub fn new(initial_text: String, maybe_path: Option<PathBuf>) -> Self {
+        let settings = maybe_path
+            .as_deref()
+            .map(|path| FileSettings::load(path, &initial_text))
+            .unwrap_or_default();
+        let filetype = FileType::detect(maybe_path.as_deref(), &initial_text);
+        let mut document = Self {
+            buffer: Buffer::new(initial_text),
+            cursor: Cursor::new(),
+            file: File::new(maybe_path),
+            filetype,
+            lsp: None,
+            diagnostics: vec![],
+            colors: vec![],
+            read_only: false,
+            scroll: 0,
+            follow: None,
+            git_base: None,
+            branch: None,
+            diff_base: None,
+            hunks: vec![],
+            hunks_revision: None,
+            blame: None,
+            blame_pending: false,
+            pretty: false,
+            keymaps: HashMap::new(),
+            directory: None,
+            folds: Folds::new(),
+            guard: None,
+            guard_override: false,
+            guard_checked: None,
+            counts: Cell::new(None),
+            match_lines: RefCell::new(None),
+        };
+
+        document.file.settings = settings;
+        document.file.mark_saved(&document.buffer);
+        document.update_guard(Limits::default());
+        document
+    }
+
+    /// Returns the directory relative paths are taken from: the one set with `:lcd`, or
+    /// the working directory of the editor.
+    pub fn working_directory(&self) -> PathBuf {
+        self.directory
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns `path` made absolute: taken from the directory set with `:lcd` when it is
+    /// relative, or else from the working directory of the editor.
+    pub fn resolve(&self, path: PathBuf) -> PathBuf {
+        file::absolute(match &self.directory {
+            Some(directory) if path.is_relative() => directory.join(path),
+            _ => path,
+        })
+    }
+
+    /// Fills an empty document for a file that does not exist yet from its template, if
+    /// one matches, as an edit so the new file is modified and `u` empties it again.
+    pub fn apply_template(&mut self) {
+        if self.buffer.as_rope().len_chars() > 0 {
+            return;
+        }
+        let Some(text) = self.file.path.as_deref().and_then(template::for_new_file) else {
+            return;
+        };
+
+        self.buffer.handle_event(BufferEvent::InsertText {
+            text,
+            position: Position::new(0, 0),
+        });
+        self.buffer.break_undo();
+    }
+
+    /// Checks the document against `limits` again when it or they changed since the
+    /// last check.
+    pub fn update_guard(&mut self, limits: Limits) {
+        let checked = (self.buffer.revision(), limits);
+        if self.guard_checked == Some(checked) {
+            return;
+        }
+
+        self.guard = Guard::check(&self.buffer, limits);
+        self.guard_checked = Some(checked);
+    }
+
+    /// Returns the line, word and char counts of the whole buffer, counted again only
+    /// after an edit.
+    pub fn counts(&self) -> Counts {
+        let revision = self.buffer.revision();
+        if let Some((counted, counts)) = self.counts.get()
+            && counted == revision
+        {
+            return counts;
+        }
+
+        let counts = self.buffer.counts(None);
+        self.counts.set(Some((revision, counts)));
+        counts
+    }
+
+    /// Returns the lines where a match of `pattern` starts, looked for again only after an
+    /// edit or for another pattern.
+    pub fn match_lines(&self, pattern: &str) -> Vec<usize> {
+        let revision = self.buffer.revision();
+        if let Some((found, known, lines)) = &*self.match_lines.borrow()
+            && *found == revision
+            && known == pattern
+        {
+            return lines.clone();
+        }
+
+        let rope = self.buffer.as_rope();
+        let mut lines: Vec<usize> = rope
+            .to_string()
+            .match_indices(pattern)
+            .map(|(byte, _)| rope.byte_to_line(byte))
+            .collect();
+        lines.dedup();
+
+        *self.match_lines.borrow_mut() = Some((revision, pattern.to_string(), lines.clone()));
+        lines
+    }
+
+    /// Returns `true` when per-edit work is skipped for this document.
+    pub fn is_guarded(&self) -> bool {
+        self.guard.is_some() && !self.guard_override
+    }
+
+    /// Returns `true` when the buffer has unsaved changes.
+    pub fn is_modified(&self) -> bool {
+        self.file.is_modified(&self.buffer)
+    }
+
+    /// Returns what the document holds; following a file wins over being read-only,
+    /// either refusing edits or with a file that cannot be written.
+    pub fn kind(&self) -> BufferKind {
+        if self.follow.is_some() {
+            BufferKind::Following
+        } else if self.read_only || self.file.read_only {
+            BufferKind::ReadOnly
+        } else if self.file.path.is_none() {
+            BufferKind::Scratch
+        } else {
+            BufferKind::File
+        }
+    }
+
+    /// Scrolls just enough for the cursor to be visible in a view of `height` lines,
+    /// closed folds taking a single line.
+    pub fn scroll_to_cursor(&mut self, height: usize) {
+        let line = self.cursor.position.line;
+
+        if line < self.scroll {
+            self.scroll = line;
+        } else if height > 0 {
+            // The top line that still shows the cursor on the last line of the view.
+            let mut top = line;
+            for _ in 1..height {
+                match self.folds.previous_visible(top) {
+                    Some(above) if above >= self.scroll => top = above,
+                    _ => break,
+                }
+            }
+            self.scroll = top;
+        }
+
+        if let Some((start, _)) = self.folds.hiding(self.scroll) {
+            self.scroll = start;
+        }
+        self.scroll = self.scroll.min(self.buffer.len_lines().saturating_sub(1));
+    }
+
+    /// Handles a cursor `event`, stepping over closed folds when moving up or down and
+    /// opening those the cursor lands in otherwise. Returns any follow-up `AppEvent`s.
+    pub fn move_cursor(&mut self, event: CursorEvent) -> Vec<AppEvent> {
+        let from = self.cursor.position.line;
+        let vertical = matches!(event, CursorEvent::MoveUp | CursorEvent::MoveDown);
+        let events = self.cursor.handle_event(event, &self.buffer);
+        let line = self.cursor.position.line;
+
+        match self.folds.hiding(line) {
+            Some((start, _)) if vertical && line < from => {
+                self.cursor
+                    .handle_event(CursorEvent::SetLinePosition(start), &self.buffer);
+            }
+            Some((_, end)) if vertical => {
+                let below = match end + 1 < self.buffer.len_lines() {
+                    true => end + 1,
+                    false => from,
+                };
+                self.cursor
+                    .handle_event(CursorEvent::SetLinePosition(below), &self.buffer);
+            }
+            Some(_) => self.folds.reveal(line),
+            None => {}
+        }
+
+        events
+    }
+
+    /// Returns the gutter signs of the document, diagnostics first.
+    pub fn signs(&self) -> Vec<Sign> {
+        let mut signs = diagnostics::diagnostic_signs(&self.diagnostics);
+        signs.extend(git::hunk_signs(&self.hunks, self.buffer.len_lines()));
+
+        signs
+    }
+
+    /// Reads the file as committed at `HEAD` and the branch again, e.g. after saving,
+    /// and recomputes the hunks.
+    pub fn refresh_git_base(&mut self) {
+        self.git_base = self.file.path.as_deref().and_then(git::head_text);
+        self.branch = self.file.path.as_deref().and_then(git::branch);
+        self.hunks_revision = None;
+        self.update_hunks();
+    }
+
+    /// Recomputes the hunks if the buffer changed since they were last computed.
+    pub fn update_hunks(&mut self) {
+        if self.is_guarded() {
+            self.hunks.clear();
+            self.hunks_revision = None;
+            return;
+        }
+
+        let revision = self.buffer.revision();
+        if self.hunks_revision == Some(revision) {
+            return;
+        }
+
+        self.hunks = match self.diff_base.as_ref().or(self.git_base.as_ref()) {
+            Some(base) => git::diff(base, &self.buffer.as_rope().to_string()),
+            None => vec![],
+        };
+        self.hunks_revision = Some(revision);
+    }
+
+    /// Compares the document with `text` instead of its `HEAD` version, or with `HEAD`
+    /// again for `None`, and recomputes the hunks.
+    pub fn set_diff_base(&mut self, text: Option<String>) {
+        self.diff_base = text;
+        self.hunks_revision = None;
+        self.update_hunks();
+    }
+
+    /// Returns the color spans of `line`.
+    pub fn line_colors(&self, line: usize) -> &[ColorSpan] {
+        let start = self.colors.partition_point(|span| span.line < line);
+        let end = self.colors.partition_point(|span| span.line <= line);
+
+        &self.colors[start..end]
+    }
+
+    /// Shows the text without its escape codes, keeping their colors for rendering.
+    ///
+    /// The document becomes read-only, since editing would misplace the colors.
+    fn show_ansi_colors(&mut self) {
+        let (text, colors) = ansi::parse(&self.buffer.as_rope().to_string());
+
+        self.buffer = Buffer::new(text);
+        self.file.mark_saved(&self.buffer);
+        self.cursor.position = Default::default();
+        self.colors = colors;
+        self.read_only = true;
+    }
+
+    /// Removes the escape codes from the text, as an edit that can be saved.
+    fn strip_ansi(&mut self) {
+        let text = ansi::strip(&self.buffer.as_rope().to_string());
+        let end = self.buffer.len_lines().saturating_sub(1);
+
+        self.buffer.handle_event(BufferEvent::ReplaceLines {
+            start: 0,
+            end,
+            text,
+        });
+        self.cursor.position = Default::default();
+    }
+
+    /// Appends `text` to the end of the buffer without marking it modified.
+    ///
+    /// A cursor on the last line moves to the new last line, so the view stays pinned
+    /// to the bottom; a cursor further up is left where the user put it.
+    fn append_tail(&mut self, text: &str) {
+        let last_line = self.buffer.len_lines().saturating_sub(1);
+        let pinned = self.cursor.position.line >= last_line;
+
+        let text = if self.colors.is_empty() {
+            text.to_string()
+        } else {
+            let (stripped, colors) = ansi::parse(text);
+            let last_col = self.buffer.as_rope().line(last_line).len_chars();
+
+            self.colors.extend(colors.into_iter().map(|mut span| {
+                if span.line == 0 {
+                    span.start += last_col;
+                    span.end += last_col;
+                }
+                span.line += last_line;
+                span
+            }));
+
+            stripped
+        };
+
+        let end = self.buffer.as_rope().len_chars();
+        let line = self.buffer.as_rope().char_to_line(end);
+        let col = end - self.buffer.as_rope().line_to_char(line);
+        self.buffer.handle_event(BufferEvent::InsertText {
+            text,
+            position: Position::new(line, col),
+        });
+        self.file.mark_saved(&self.buffer);
+        self.file.refresh_disk_info();
+
+        if pinned {
+            self.cursor.position = Position::new(self.buffer.len_lines().saturating_sub(1), 0);
+        }
+    }
+
+    /// Reads the file again after another program changed it, replacing the text as an
+    /// edit `u` undoes, and keeps the cursor inside the new text.
+    fn reload(&mut self) -> io::Result<()> {
+        let Some(path) = self.file.path.as_ref() else {
+            return Ok(());
+        };
+        let text = editorconfig::read(path)?;
+
+        let rope = self.buffer.as_rope();
+        let last = rope.len_lines().saturating_sub(1);
+        let end = Position::new(last, rope.line(last).len_chars());
+        self.buffer.handle_event(BufferEvent::ReplaceRange {
+            start: Position::new(0, 0),
+            end,
+            text,
+        });
+        self.buffer.break_undo();
+
+        self.file.mark_saved(&self.buffer);
+        self.file.refresh_disk_info();
+        let line = self
+            .cursor
+            .position
+            .line
+            .min(self.buffer.len_lines().saturating_sub(1));
+        self.cursor.position.line = line;
+        self.cursor.clamp(&self.buffer);
+
+        Ok(())
+    }
+
+    /// Reads the whole file again after it shrank, keeping the colored view if shown.
+    fn reload_tail(&mut self) -> io::Result<()> {
+        let Some(path) = self.file.path.as_ref() else {
+            return Ok(());
+        };
+        let text = editorconfig::read(path)?;
+
+        if self.colors.is_empty() {
+            self.buffer = Buffer::new(text);
+        } else {
+            let (text, colors) = ansi::parse(&text);
+            self.buffer = Buffer::new(text);
+            self.colors = colors;
+        }
+
+        self.file.mark_saved(&self.buffer);
+        self.file.refresh_disk_info();
+        self.cursor.position = Position::new(self.buffer.len_lines().saturating_sub(1), 0);
+
+        Ok(())
+    }
+
+    /// Replaces the diagnostics, converting their UTF-16 columns into char columns.
+    fn set_diagnostics(&mut self, diagnostics: Vec<Diagnostic>) {
+        let rope = self.buffer.as_rope();
+        let line_text = |line: usize| {
+            if line < rope.len_lines() {
+                rope.line(line).to_string()
+            } else {
+                String::new()
+            }
+        };
+
+        self.diagnostics = diagnostics
+            .into_iter()
+            .map(|mut diagnostic| {
+                diagnostic.start.col =
+                    protocol::char_col(&line_text(diagnostic.start.line), diagnostic.start.col);
+                diagnostic.end.col =
+                    protocol::char_col(&line_text(diagnostic.end.line), diagnostic.end.col);
+                diagnostic
+            })
+            .collect();
+    }
+}
+
+impl BufferManager {
+    /// Creates a manager whose only (and active) document is `first`.
+    pub fn new(first: Document, sender: mpsc::Sender<Event>) -> Self {
+        let mut manager = Self {
+            documents: vec![],
+            active: 0,
+            sender,
+            comparison: None,
+            filetypes: HashMap::new(),
+            fixeol: true,
+        };
+        manager.push(first);
+
+        manager
+    }
+
+    /// Returns the active document.
+    pub fn active(&self) -> &Document {
+        &self.documents[self.active]
+    }
+
+    /// Returns the active document mutably.
+    pub fn active_mut(&mut self) -> &mut Document {
+        &mut self.documents[self.active]
+    }
+
+    /// Returns the settings the configuration file gives the file type of `document`.
+    pub fn filetype_settings(&self, document: &Document) -> Option<&FiletypeSettings> {
+        self.filetypes.get(document.filetype?.name())
+    }
+
+    /// Returns the tab stop `document` is drawn with: the one of its file, of its file
+    /// type, or else of the global `options`.
+    pub fn tabstop(&self, document: &Document, options: &Options) -> usize {
+        let filetype = self.filetype_settings(document);
+        document.file.settings.tabstop(filetype, options)
+    }
+
+    /// Returns the line comment marker of `document`: the one set for its file type in the
+    /// configuration file, or the known one for its file name.
+    pub fn comment_marker(&self, document: &Document) -> Option<String> {
+        self.filetype_settings(document)
+            .and_then(|settings| settings.comment.clone())
+            .or_else(|| match CommentStyle::of(document.file.path.as_deref()?)? {
+                CommentStyle::Line(marker) => Some(marker.into_owned()),
+                CommentStyle::Block { .. } => None,
+            })
+    }
+
+    /// Makes every document, open or opened later, add a missing final line break when
+    /// written, or not.
+    pub fn set_fixeol(&mut self, fixeol: bool) {
+        self.fixeol = fixeol;
+        for document in &mut self.documents {
+            document.file.fixeol = fixeol;
+        }
+    }
+
+    /// Replaces the settings of the file types, restarting the language servers of the
+    /// open documents whose type now has another one.
+    pub fn set_filetypes(&mut self, filetypes: HashMap<String, FiletypeSettings>) {
+        let previous = std::mem::replace(&mut self.filetypes, filetypes);
+
+        for index in 0..self.documents.len() {
+            let document = &self.documents[index];
+            let Some(filetype) = document.filetype else {
+                continue;
+            };
+            let lsp = |filetypes: &HashMap<String, FiletypeSettings>| {
+                filetypes
+                    .get(filetype.name())
+                    .and_then(|settings| settings.lsp.clone())
+            };
+            if lsp(&previous) == lsp(&self.filetypes) {
+                continue;
+            }
+
+            let lsp = self.start_lsp(document);
+            self.documents[index].lsp = lsp;
+        }
+    }
+
+    /// Starts the language server of `document`: the one of the configuration file for
+    /// its type, or else the default one.
+    fn start_lsp(&self, document: &Document) -> Option<LspClient> {
+        let path = document.file.path.as_deref()?;
+        let command = self
+            .filetype_settings(document)
+            .and_then(|settings| settings.lsp.as_deref());
+
+        LspClient::start(
+            path,
+            &document.buffer,
+            document.filetype?,
+            command,
+            self.sender.clone(),
+        )
+    }
+
+    /// Returns the index of the active document.
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    /// Returns the document at `index` mutably, if there is one.
+    pub fn document_mut(&mut self, index: usize) -> Option<&mut Document> {
+        self.documents.get_mut(index)
+    }
+
+    /// Compares the documents at `first` and `second` with each other, instead of with
+    /// their `HEAD` versions.
+    pub fn compare(&mut self, first: usize, second: usize) {
+        if first == second || second >= self.documents.len() || first >= self.documents.len() {
+            return;
+        }
+
+        self.comparison = Some(Comparison {
+            documents: [first, second],
+            revisions: None,
+        });
+        self.update_comparison();
+    }
+
+    /// Compares the documents of the comparison again when either was edited since.
+    pub fn update_comparison(&mut self) {
+        let Some(comparison) = &mut self.comparison else {
+            return;
+        };
+
+        let [first, second] = comparison.documents;
+        let revisions = [
+            self.documents[first].buffer.revision(),
+            self.documents[second].buffer.revision(),
+        ];
+        if comparison.revisions == Some(revisions) {
+            return;
+        }
+        comparison.revisions = Some(revisions);
+
+        let text = |index: usize| self.documents[index].buffer.as_rope().to_string();
+        let (first_text, second_text) = (text(first), text(second));
+        self.documents[first].set_diff_base(Some(second_text));
+        self.documents[second].set_diff_base(Some(first_text));
+    }
+
+    /// Makes the document at `index` active, if there is one.
+    pub fn set_active(&mut self, index: usize) {
+        if index < self.documents.len() {
+            self.active = index;
+        }
+    }
+
+    /// Returns all open documents, in the order they were opened.
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    /// Returns the display names of the documents with unsaved changes.
+    pub fn modified_names(&self) -> Vec<String> {
+        self.documents
+            .iter()
+            .filter(|document| document.is_modified())
+            .map(|document| document.file.display_name())
+            .collect()
+    }
+
+    /// Handles a `BufferManagerEvent` and returns any follow-up `AppEvent`s.
+    pub fn handle_event(&mut self, event: BufferManagerEvent) -> Vec<AppEvent> {
+        let mut events = vec![];
+
+        match event {
+            BufferManagerEvent::Open(path) => match self.load(path) {
+                Ok(index) => self.active = index,
+                Err(err) => tracing::error!("Failed to open file: {err}"),
+            },
+            BufferManagerEvent::Next => self.active = (self.active + 1) % self.documents.len(),
+            BufferManagerEvent::Previous => {
+                self.active = (self.active + self.documents.len() - 1) % self.documents.len()
+            }
+            BufferManagerEvent::Clone => self.active = self.clone_active(),
+            BufferManagerEvent::WriteAll { quit } => {
+                let modified = self.modified_names().len();
+                let failures = self.write_all();
+
+                for failure in &failures {
+                    tracing::error!("Failed to write {}: {}", failure.name, failure.reason);
+                }
+                if failures.is_empty() && !quit {
+                    match modified {
+                        0 => tracing::info!("No buffers with unsaved changes"),
+                        count => tracing::info!("Wrote {count} buffer(s)"),
+                    }
+                }
+
+                if quit && failures.is_empty() {
+                    events.push(AppEvent::Quit);
+                }
+            }
+            BufferManagerEvent::PublishDiagnostics { path, diagnostics } => {
+                if let Some(index) = self.find(&path) {
+                    self.documents[index].set_diagnostics(diagnostics);
+                }
+            }
+            BufferManagerEvent::ShowAnsiColors(index) => {
+                if let Some(document) = self.documents.get_mut(index) {
+                    document.show_ansi_colors();
+                }
+            }
+            BufferManagerEvent::StripAnsi(index) => {
+                if let Some(document) = self.documents.get_mut(index) {
+                    document.strip_ansi();
+                }
+            }
+            BufferManagerEvent::ToggleFollow => self.toggle_follow(),
+            BufferManagerEvent::ToggleGuard => self.toggle_guard(),
+            BufferManagerEvent::ShowPretty(index) => {
+                if let Some(document) = self.documents.get_mut(index) {
+                    document.pretty = true;
+                }
+            }
+            BufferManagerEvent::SetDirectory(directory) => {
+                self.active_mut().directory = directory;
+            }
+            BufferManagerEvent::TogglePretty => {
+                let document = self.active_mut();
+                document.pretty = !document.pretty;
+            }
+            BufferManagerEvent::TailAppend { path, text } => {
+                if let Some(index) = self.find(&path) {
+                    self.documents[index].append_tail(&text);
+                }
+            }
+            BufferManagerEvent::NextHunk => events.extend(self.jump_to_hunk(git::next_hunk)),
+            BufferManagerEvent::PreviousHunk => {
+                events.extend(self.jump_to_hunk(git::previous_hunk))
+            }
+            BufferManagerEvent::BlameLoaded {
+                path,
+                line,
+                revision,
+                text,
+            } => {
+                if let Some(index) = self.find(&path) {
+                    let document = &mut self.documents[index];
+                    document.blame_pending = false;
+
+                    if let Some(blame) = document
+                        .blame
+                        .as_mut()
+                        .filter(|blame| blame.line == line && blame.revision == revision)
+                    {
+                        blame.text = text;
+                    }
+                }
+            }
+            BufferManagerEvent::Reload => {
+                let document = self.active_mut();
+                if let Err(err) = document.reload() {
+                    tracing::error!("Failed to reload {}: {err}", document.file.display_name());
+                }
+            }
+            BufferManagerEvent::TailReload(path) => {
+                let reloaded = self
+                    .find(&path)
+                    .map(|index| self.documents[index].reload_tail());
+
+                if let Some(Err(err)) = reloaded {
+                    tracing::error!("Failed to reload {}: {err}", path.display());
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Loads `path` as a new document without making it active.
+    ///
+    /// Returns the index of the document, reusing an already open one for the same path.
+    /// A path that does not exist yet opens as an empty buffer, to be created on save.
+    pub fn load(&mut self, path: PathBuf) -> io::Result<usize> {
+        if let Some(index) = self.find(&path) {
+            return Ok(index);
+        }
+
+        let document = match editorconfig::read(&path) {
+            Ok(text) => Document::new(text, Some(path)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let mut document = Document::new(String::new(), Some(path));
+                document.apply_template();
+                document
+            }
+            Err(err) => return Err(err),
+        };
+
+        if document.file.read_only {
+            tracing::warn!("{} is read-only", document.file.display_name());
+        }
+
+        Ok(self.push(document))
+    }
+
+    /// Writes every modified document, returning the ones that failed.
+    pub fn write_all(&mut self) -> Vec<WriteFailure> {
+        self.documents
+            .iter_mut()
+            .filter(|document| document.is_modified())
+            .filter_map(|document| {
+                document
+                    .file
+                    .write(&document.buffer)
+                    .err()
+                    .map(|err| WriteFailure {
+                        name: document.file.display_name(),
+                        reason: err.to_string(),
+                    })
+            })
+            .collect()
+    }
+
+    /// Opens a scratch copy of the active document and returns its index.
+    fn clone_active(&mut self) -> usize {
+        let document = self.active();
+        let mut copy = Document::new(document.buffer.as_rope().to_string(), None);
+        copy.cursor.position = document.cursor.position;
+        copy.scroll = document.scroll;
+        copy.pretty = document.pretty;
+
+        self.push(copy)
+    }
+
+    /// Overrides the guard of the active document, or stops overriding it.
+    fn toggle_guard(&mut self) {
+        let document = self.active_mut();
+
+        let Some(guard) = document.guard else {
+            tracing::info!("Buffer is within the size limits");
+            return;
+        };
+
+        document.guard_override = !document.guard_override;
+        if document.guard_override {
+            tracing::info!("Per-edit work back on despite {guard}");
+        } else {
+            tracing::info!("Per-edit work off again: {guard}");
+        }
+    }
+
+    /// Starts following the active document's file from its current end, read-only,
+    /// or stops following and restores its previous read-only state.
+    fn toggle_follow(&mut self) {
+        let sender = self.sender.clone();
+        let document = self.active_mut();
+
+        if let Some(tail) = document.follow.take() {
+            document.read_only = tail.previous_read_only;
+            return;
+        }
+
+        let Some(path) = document.file.path.clone() else {
+            tracing::warn!("Cannot follow a buffer without a file");
+            return;
+        };
+        if document.is_modified() {
+            tracing::warn!("Cannot follow a buffer with unsaved changes");
+            return;
+        }
+
+        match std::fs::metadata(&path) {
+            Ok(metadata) => {
+                document.follow = Some(Tail::start(
+                    path,
+                    metadata.len(),
+                    document.read_only,
+                    sender,
+                ));
+                document.read_only = true;
+                document.cursor.position =
+                    Position::new(document.buffer.len_lines().saturating_sub(1), 0);
+            }
+            Err(err) => tracing::error!("Failed to follow {}: {err}", path.display()),
+        }
+    }
+
+    /// Requests the blame of the active document's cursor line in the background, unless
+    /// it is already known or another request is running.
+    pub fn update_blame(&mut self) {
+        let sender = self.sender.clone();
+        let document = self.active_mut();
+
+        if document.is_guarded() {
+            return;
+        }
+
+        let Some(path) = document.file.path.clone() else {
+            return;
+        };
+        let line = document.cursor.position.line;
+        let revision = document.buffer.revision();
+
+        let known = document
+            .blame
+            .as_ref()
+            .is_some_and(|blame| blame.line == line && blame.revision == revision);
+        if known || document.blame_pending {
+            return;
+        }
+
+        document.blame = Some(LineBlame {
+            line,
+            revision,
+            text: None,
+        });
+        document.blame_pending = true;
+
+        let contents = document.buffer.as_rope().to_string();
+        std::thread::spawn(move || {
+            let text = git::blame_line(&path, line, &contents).map(|blame| blame.describe());
+            let _ = sender.send(Event::App(AppEvent::BufferManager(
+                BufferManagerEvent::BlameLoaded {
+                    path,
+                    line,
+                    revision,
+                    text,
+                },
+            )));
+        });
+    }
+
+    /// Moves the cursor of the active document to the hunk chosen by `target`.
+    fn jump_to_hunk(&mut self, target: fn(&[Hunk], usize) -> Option<usize>) -> Vec<AppEvent> {
+        let document = self.active_mut();
+        document.update_hunks();
+
+        match target(&document.hunks, document.cursor.position.line) {
+            Some(line) => vec![AppEvent::Cursor(CursorEvent::SetPosition { line, col: 0 })],
+            None => {
+                tracing::warn!("No changes since HEAD");
+                vec![]
+            }
+        }
+    }
+
+    fn find(&self, path: &Path) -> Option<usize> {
+        let path = &file::absolute(path.to_path_buf());
+        let canonical = path.canonicalize().ok();
+
+        self.documents.iter().position(|document| {
+            document.file.path.as_ref().is_some_and(|open| {
+                open == path || (canonical.is_some() && open.canonicalize().ok() == canonical)
+            })
+        })
+    }
+
+    /// Adds a document, starting its language server, and returns its index.
+    ///
+    /// Asks how to show the document if it contains ANSI escape codes, or whether to
+    /// use the pretty view if it looks minified.
+    fn push(&mut self, mut document: Document) -> usize {
+        let text = document.buffer.as_rope().to_string();
+        let index = self.documents.len();
+
+        document.file.fixeol = self.fixeol;
+        document.lsp = self.start_lsp(&document);
+        document.refresh_git_base();
+
+        if ansi::contains_escapes(&text) {
+            let _ = self.sender.send(Event::App(AppEvent::Confirm {
+                message: format!(
+                    "{} contains ANSI escape codes. Show colors read-only (y) or strip them (n)?",
+                    document.file.display_name()
+                ),
+                on_confirm: vec![AppEvent::BufferManager(BufferManagerEvent::ShowAnsiColors(
+                    index,
+                ))],
+                on_deny: vec![AppEvent::BufferManager(BufferManagerEvent::StripAnsi(
+                    index,
+                ))],
+            }));
+        } else if let Some(len) = pretty::minified_line_len(&document.buffer) {
+            let _ = self.sender.send(Event::App(AppEvent::Confirm {
+                message: format!(
+                    "{} looks minified, with a line of {len} chars. Show it in the pretty view?",
+                    document.file.display_name()
+                ),
+                on_confirm: vec![AppEvent::BufferManager(BufferManagerEvent::ShowPretty(
+                    index,
+                ))],
+                on_deny: vec![],
+            }));
+        }
+
+        self.documents.push(document);
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::BufferEvent;
+
+    fn manager_with(text: &str, path: Option<&str>) -> BufferManager {
+        let document = Document::new(text.to_string(), path.map(PathBuf::from));
+        BufferManager::new(document, mpsc::channel().0)
+    }
+
+    fn edit(document: &mut Document) {
+        document.buffer.handle_event(BufferEvent::InsertChar {
+            char: 'x',
+            position: Position::new(0, 0),
+        });
+    }
+
+    #[test]
+    fn should_compare_two_documents_with_each_other() {
+        let mut manager = manager_with("a\nb\n", None);
+        let second = manager.push(Document::new(String::from("a\nc\n"), None));
+
+        manager.compare(0, second);
+        assert_eq!(manager.documents()[0].hunks.len(), 1);
+        assert_eq!(manager.documents()[second].hunks.len(), 1);
+
+        manager
+            .document_mut(second)
+            .unwrap()
+            .buffer
+            .handle_event(BufferEvent::ReplaceLines {
+                start: 1,
+                end: 1,
+                text: String::from("b"),
+            });
+        manager.update_comparison();
+
+        assert!(manager.documents()[0].hunks.is_empty());
+        assert!(manager.documents()[second].hunks.is_empty());
+    }
+
+    #[test]
+    fn should_tell_buffer_kinds_apart() {
+        let mut document = Document::new(String::new(), None);
+        assert_eq!(document.kind(), BufferKind::Scratch);
+
+        document.file = File::new(Some(PathBuf::from("test_kind.txt")));
+        assert_eq!(document.kind(), BufferKind::File);
+
+        document.read_only = true;
+        assert_eq!(document.kind(), BufferKind::ReadOnly);
+    }
+
+    #[test]
+    fn should_open_missing_file_as_empty_buffer_and_switch_to_it() {
+        let mut manager = manager_with("first", None);
+
+        manager.handle_event(BufferManagerEvent::Open(PathBuf::from(
+            "test_manager_missing.txt",
+        )));
+
+        assert_eq!(manager.documents().len(), 2);
+        assert_eq!(manager.active().buffer.as_rope().to_string(), "");
+        assert_eq!(
+            manager.active().file.path,
+            Some(file::absolute(PathBuf::from("test_manager_missing.txt")))
+        );
+    }
+
+    #[test]
+    fn should_reuse_document_when_opening_same_path() {
+        let mut manager = manager_with("", Some("test_manager_same.txt"));
+
+        manager.handle_event(BufferManagerEvent::Open(PathBuf::from(
+            "test_manager_other.txt",
+        )));
+        manager.handle_event(BufferManagerEvent::Open(PathBuf::from(
+            "test_manager_same.txt",
+        )));
+
+        assert_eq!(manager.documents().len(), 2);
+        assert_eq!(
+            manager.active().file.path,
+            Some(file::absolute(PathBuf::from("test_manager_same.txt")))
+        );
+    }
+
+    #[test]
+    fn should_cycle_through_documents() {
+        let mut manager = manager_with("", None);
+        manager.load(PathBuf::from("test_manager_b.txt")).unwrap();
+        manager.load(PathBuf::from("test_manager_c.txt")).unwrap();
+
+        manager.handle_event(BufferManagerEvent::Previous);
+        assert_eq!(
+            manager.active().file.path,
+            Some(file::absolute(PathBuf::from("test_manager_c.txt")))
+        );
+
+        manager.handle_event(BufferManagerEvent::Next);
+        assert_eq!(manager.active().file.path, None);
+    }
+
+    #[test]
+    fn should_keep_writing_after_a_failure_and_report_it() {
+        let path = PathBuf::from("test_manager_write_all.txt");
+        let mut manager = manager_with("", None);
+        let index = manager.load(path.clone()).unwrap();
+
+        edit(manager.active_mut());
+        edit(&mut manager.documents[index]);
+
+        let failures = manager.write_all();
+
+        assert_eq!(
+            failures,
+            vec![WriteFailure {
+                name: String::from("[No Name]"),
+                reason: String::from("No file name"),
+            }]
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x\n");
+        assert_eq!(manager.modified_names(), vec![String::from("[No Name]")]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn should_read_and_write_files_as_their_editorconfig_and_modelines_say() {
+        let directory = PathBuf::from("test_manager_editorconfig");
+        let path = directory.join("notes.txt");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join(".editorconfig"),
+            "root = true\n[*.txt]\ncharset = latin1\nindent_style = space\n",
+        )
+        .unwrap();
+        std::fs::write(&path, b"caf\xE9\n# vim: set ts=2 noeol:\n").unwrap();
+        let mut manager = manager_with("", None);
+
+        let index = manager.load(path.clone()).unwrap();
+        let document = &mut manager.documents[index];
+        let settings = document.file.settings.clone();
+        document.file.write(&document.buffer).unwrap();
+        let written = std::fs::read(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(document.buffer.as_rope().line(0).to_string(), "café\n");
+        assert_eq!(
+            settings,
+            FileSettings {
+                expandtab: Some(true),
+                tabstop: Some(2),
+                charset: Some(editorconfig::Charset::Latin1),
+                final_newline: Some(false),
+            }
+        );
+        assert_eq!(written, b"caf\xE9\n# vim: set ts=2 noeol:".to_vec());
+    }
+
+    #[test]
+    fn should_store_published_diagnostics_with_char_columns() {
+        let mut manager = manager_with("é = 1", Some("test_manager_diagnostics.rs"));
+
+        manager.handle_event(BufferManagerEvent::PublishDiagnostics {
+            path: PathBuf::from("test_manager_diagnostics.rs"),
+            diagnostics: vec![Diagnostic {
+                start: Position::new(0, 4),
+                end: Position::new(0, 5),
+                severity: crate::app::diagnostics::Severity::Error,
+                message: String::from("expected type"),
+            }],
+        });
+
+        let diagnostic = &manager.active().diagnostics[0];
+
+        assert_eq!(diagnostic.start.col, 4);
+        assert_eq!(manager.active().signs().len(), 1);
+    }
+
+    #[test]
+    fn should_scroll_only_when_cursor_leaves_the_view() {
+        let mut document = Document::new(String::from("0\n1\n2\n3\n4\n5"), None);
+
+        document.cursor.position = Position::new(4, 0);
+        document.scroll_to_cursor(3);
+        assert_eq!(document.scroll, 2);
+
+        document.cursor.position = Position::new(3, 0);
+        document.scroll_to_cursor(3);
+        assert_eq!(document.scroll, 2);
+
+        document.cursor.position = Position::new(1, 0);
+        document.scroll_to_cursor(3);
+        assert_eq!(document.scroll, 1);
+    }
+
+    #[test]
+    fn should_keep_pinned_cursor_at_the_bottom_when_tail_appends() {
+        let mut document = Document::new(String::from("one\ntwo"), None);
+        document.cursor.position = Position::new(1, 0);
+
+        document.append_tail(" more\nthree\n");
+
+        assert_eq!(
+            document.buffer.as_rope().to_string(),
+            "one\ntwo more\nthree\n"
+        );
+        assert_eq!(document.cursor.position, Position::new(3, 0));
+        assert!(!document.is_modified());
+
+        document.cursor.position = Position::new(0, 0);
+        document.append_tail("four\n");
+
+        assert_eq!(document.cursor.position, Position::new(0, 0));
+    }
+
+    #[test]
+    fn should_shift_colors_of_appended_text() {
+        let mut document = Document::new(String::from("\x1b[31mred\x1b[0m"), None);
+        document.show_ansi_colors();
+
+        document.append_tail("\x1b[32mgreen\x1b[0m\n");
+
+        assert_eq!(document.buffer.as_rope().to_string(), "redgreen\n");
+        assert_eq!(document.line_colors(0)[1].start, 3);
+        assert_eq!(document.line_colors(0)[1].end, 8);
+    }
+
+    #[test]
+    fn should_restore_read_only_state_when_unfollowing() {
+        std::fs::write("test_manager_follow.txt", "log\n").unwrap();
+        let mut manager = manager_with("log\n", Some("test_manager_follow.txt"));
+
+        manager.handle_event(BufferManagerEvent::ToggleFollow);
+        let following = manager.active().follow.is_some() && manager.active().read_only;
+        manager.handle_event(BufferManagerEvent::ToggleFollow);
+        std::fs::remove_file("test_manager_follow.txt").unwrap();
+
+        assert!(following);
+        assert!(manager.active().follow.is_none());
+        assert!(!manager.active().read_only);
+    }
+
+    #[test]
+    fn should_skip_hunks_while_guarded_unless_overridden() {
+        let mut manager = manager_with("one\nfour", None);
+        let limits = Limits {
+            max_size: 0,
+            max_line: 3,
+        };
+        let document = manager.active_mut();
+        document.git_base = Some(String::from("one\ntwo"));
+
+        document.update_guard(limits);
+        document.update_hunks();
+        assert_eq!(document.guard, Some(Guard::LongLine { line: 1, len: 4 }));
+        assert!(document.hunks.is_empty());
+
+        manager.handle_event(BufferManagerEvent::ToggleGuard);
+        let document = manager.active_mut();
+        document.update_hunks();
+        assert!(!document.hunks.is_empty());
+    }
+
+    #[test]
+    fn should_keep_only_blame_of_the_requested_line() {
+        let mut manager = manager_with("a\nb", Some("test_manager_blame.txt"));
+        let path = PathBuf::from("test_manager_blame.txt");
+        manager.active_mut().blame = Some(LineBlame {
+            line: 1,
+            revision: 0,
+            text: None,
+        });
+
+        manager.handle_event(BufferManagerEvent::BlameLoaded {
+            path: path.clone(),
+            line: 0,
+            revision: 0,
+            text: Some(String::from("stale")),
+        });
+        assert_eq!(manager.active().blame.as_ref().unwrap().text, None);
+
+        manager.handle_event(BufferManagerEvent::BlameLoaded {
+            path,
+            line: 1,
+            revision: 0,
+            text: Some(String::from("Jane, just now • Init")),
+        });
+        assert_eq!(
+            manager.active().blame.as_ref().unwrap().text.as_deref(),
+            Some("Jane, just now • Init")
+        );
+    }
+
+    #[test]
+    fn should_ask_how_to_show_ansi_escape_codes() {
+        let (sender, receiver) = mpsc::channel();
+        let document = Document::new(String::from("\x1b[31mred\x1b[0m"), None);
+        let mut manager = BufferManager::new(document, sender);
+
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(Event::App(AppEvent::Confirm { .. }))
+        ));
+
+        manager.handle_event(BufferManagerEvent::ShowAnsiColors(0));
+
+        assert_eq!(manager.active().buffer.as_rope().to_string(), "red");
+        assert_eq!(manager.active().line_colors(0).len(), 1);
+        assert!(manager.active().read_only);
+        assert!(!manager.active().is_modified());
+    }
+
+    #[test]
+    fn should_clone_the_active_document_into_a_scratch_copy() {
+        let mut manager = manager_with("one\ntwo\n", Some("test_clone.txt"));
+        manager.active_mut().cursor.position = Position::new(1, 2);
+
+        manager.handle_event(BufferManagerEvent::Clone);
+
+        assert_eq!(manager.active_index(), 1);
+        assert_eq!(manager.active().buffer.as_rope().to_string(), "one\ntwo\n");
+        assert_eq!(manager.active().cursor.position, Position::new(1, 2));
+        assert_eq!(manager.active().file.path, None);
+        assert_eq!(
+            manager.documents()[0].file.path,
+            Some(file::absolute(PathBuf::from("test_clone.txt")))
+        );
+    }
+
+    #[test]
+    fn should_offer_the_pretty_view_for_minified_files() {
+        let (sender, receiver) = mpsc::channel();
+        let text = "x;".repeat(pretty::MINIFIED_LINE_LEN);
+        let mut manager = BufferManager::new(Document::new(text, None), sender);
+
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(Event::App(AppEvent::Confirm { .. }))
+        ));
+
+        manager.handle_event(BufferManagerEvent::ShowPretty(0));
+        assert!(manager.active().pretty);
+
+        manager.handle_event(BufferManagerEvent::TogglePretty);
+        assert!(!manager.active().pretty);
+    }
+
+    #[test]
+    fn should_strip_ansi_escape_codes_as_an_edit() {
+        let mut manager = manager_with("\x1b[1mbold\x1b[0m\n", None);
+
+        manager.handle_event(BufferManagerEvent::StripAnsi(0));
+
+        assert_eq!(manager.active().buffer.as_rope().to_string(), "bold\n");
+        assert!(!manager.active().read_only);
+        assert!(manager.active().is_modified());
+    }
+
+    #[test]
+    fn should_quit_after_write_all_only_when_everything_was_written() {
+        let mut manager = manager_with("", None);
+        edit(manager.active_mut());
+
+        let events = manager.handle_event(BufferManagerEvent::WriteAll { quit: true });
+
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn should_reload_a_file_changed_on_disk_as_an_edit() {
+        let path = PathBuf::from("test_manager_reload.txt");
+        std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        let mut manager = manager_with("one\ntwo\nthree\n", Some("test_manager_reload.txt"));
+        manager.active_mut().cursor.position = Position::new(2, 4);
+        std::fs::write(&path, "new\n").unwrap();
+
+        manager.handle_event(BufferManagerEvent::Reload);
+        std::fs::remove_file(&path).unwrap();
+
+        let document = manager.active_mut();
+        assert_eq!(document.buffer.as_rope().to_string(), "new\n");
+        assert_eq!(document.cursor.position, Position::new(1, 0));
+        assert!(!document.is_modified());
+
+        document.buffer.handle_event(BufferEvent::Undo {
+            position: Position::new(0, 0),
+        });
+        assert_eq!(document.buffer.as_rope().to_string(), "one\ntwo\nthree\n");
+    }
+}
@@ -0,0 +1,244 @@
+//! Change log module for the Zack text editor.
+//!
+//! This module provides [`ChangeLog`], where a [`Buffer`](crate::app::buffer::Buffer)
+//! reports each of its edits as a [`Change`]: the range it replaced, with the old and the
+//! new text. Undoing and redoing are reported as the smallest range that differs between
+//! the two states. Consumers that keep something derived from the text, like highlights,
+//! hunks or a language server copy, remember the [`ChangeLog::count`] they are up to date
+//! with and catch up with [`ChangeLog::since`], only looking at what changed. The log
+//! keeps the last [`MAX_CHANGES`] changes, holding at most [`MAX_BYTES`] of text; a
+//! consumer further behind rescans the text.
+
+use crate::types::position::Position;
+use ropey::Rope;
+use std::collections::VecDeque;
+
+/// Most changes kept in the log.
+pub const MAX_CHANGES: usize = 1024;
+
+/// Most bytes of old and new text kept in the log. A bigger change, like pasting a huge
+/// text, empties it.
+pub const MAX_BYTES: usize = 1 << 20;
+
+/// An edit of the text: `old_text` at `start` was replaced with `new_text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Where the change starts, in chars from the start of the text.
+    pub start_char: usize,
+    /// Where the change starts, as a line and column.
+    pub start: Position,
+    /// The column where the change starts, in UTF-16 code units as language servers
+    /// count them.
+    pub start_utf16: usize,
+    /// The text that was replaced.
+    pub old_text: String,
+    /// The text it was replaced with.
+    pub new_text: String,
+}
+
+/// The last changes of a buffer, counted since it was created.
+#[derive(Debug, Default)]
+pub struct ChangeLog {
+    changes: VecDeque<Change>,
+    /// Number of changes ever made, including those dropped from the log.
+    count: u64,
+    /// Bytes of old and new text held by `changes`.
+    bytes: usize,
+}
+
+impl Change {
+    /// Returns where the replaced text ended, before the change.
+    pub fn old_end(&self) -> Position {
+        end_of(self.start, &self.old_text)
+    }
+
+    /// Returns where the new text ends, after the change.
+    pub fn new_end(&self) -> Position {
+        end_of(self.start, &self.new_text)
+    }
+
+    /// Returns the bytes of text the change holds.
+    fn size(&self) -> usize {
+        self.old_text.len() + self.new_text.len()
+    }
+}
+
+/// Returns the position right after `text` written at `start`.
+fn end_of(start: Position, text: &str) -> Position {
+    match text.rsplit_once('\n') {
+        Some((before, last)) => Position::new(
+            start.line + before.matches('\n').count() + 1,
+            last.chars().count(),
+        ),
+        None => Position::new(start.line, start.col + text.chars().count()),
+    }
+}
+
+impl ChangeLog {
+    /// Creates an empty change log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of changes made so far, to pass to [`ChangeLog::since`] later.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Adds `change`, dropping the oldest ones when the log is full. Changes that replace
+    /// text with the same text are left out, and a change bigger than [`MAX_BYTES`] is only
+    /// counted, leaving the log empty.
+    pub fn push(&mut self, change: Change) {
+        if change.old_text == change.new_text {
+            return;
+        }
+
+        self.count += 1;
+        if change.size() > MAX_BYTES {
+            self.changes.clear();
+            self.bytes = 0;
+            return;
+        }
+
+        self.bytes += change.size();
+        self.changes.push_back(change);
+        while self.changes.len() > MAX_CHANGES || self.bytes > MAX_BYTES {
+            let oldest = self
+                .changes
+                .pop_front()
+                .expect("the log holds the new change");
+            self.bytes -= oldest.size();
+        }
+    }
+
+    /// Returns the changes made after the first `count`, oldest first, or `None` when some
+    /// of them were dropped from the log.
+    pub fn since(&self, count: u64) -> Option<impl Iterator<Item = &Change>> {
+        let oldest = self.count - self.changes.len() as u64;
+        if count < oldest || count > self.count {
+            return None;
+        }
+
+        Some(self.changes.iter().skip((count - oldest) as usize))
+    }
+}
+
+/// Returns the number of chars `old` and `new` have in common at their start and at their
+/// end, not overlapping. Chunks the two share, as an undo snapshot and the text edited from
+/// it do, are skipped without comparing their chars.
+pub fn common_ends(old: &Rope, new: &Rope) -> (usize, usize) {
+    let same = |(a, b): &(&str, &str)| a.as_ptr() == b.as_ptr() && a.len() == b.len();
+
+    let shared: usize = old
+        .chunks()
+        .zip(new.chunks())
+        .take_while(same)
+        .map(|(chunk, _)| chunk.len())
+        .sum();
+    let skipped = old.byte_to_char(shared);
+    let prefix = skipped
+        + old
+            .chars_at(skipped)
+            .zip(new.chars_at(skipped))
+            .take_while(|(a, b)| a == b)
+            .count();
+
+    let room = old.len_chars().min(new.len_chars()) - prefix;
+    let shared: usize = old
+        .chunks_at_byte(old.len_bytes())
+        .0
+        .reversed()
+        .zip(new.chunks_at_byte(new.len_bytes()).0.reversed())
+        .take_while(same)
+        .map(|(chunk, _)| chunk.len())
+        .sum();
+    let skipped = (old.len_chars() - old.byte_to_char(old.len_bytes() - shared)).min(room);
+    let suffix = skipped
+        + old
+            .chars_at(old.len_chars() - skipped)
+            .reversed()
+            .zip(new.chars_at(new.len_chars() - skipped).reversed())
+            .take(room - skipped)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+    (prefix, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(text: &str) -> Change {
+        Change {
+            start_char: 0,
+            start: Position::new(1, 2),
+            start_utf16: 2,
+            old_text: String::new(),
+            new_text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn should_return_the_changes_after_a_count() {
+        let mut log = ChangeLog::new();
+        log.push(change("a"));
+        let seen = log.count();
+        log.push(change("b"));
+        log.push(change(""));
+
+        let texts: Vec<_> = log
+            .since(seen)
+            .unwrap()
+            .map(|change| change.new_text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["b"]);
+        assert_eq!(log.since(log.count()).unwrap().count(), 0);
+
+        for _ in 0..MAX_CHANGES {
+            log.push(change("c"));
+        }
+        assert!(log.since(seen).is_none());
+    }
+
+    #[test]
+    fn should_keep_at_most_max_bytes_of_text() {
+        let mut log = ChangeLog::new();
+        let seen = log.count();
+        log.push(change("a"));
+        let half = "x".repeat(MAX_BYTES / 2);
+        log.push(change(&half));
+        log.push(change(&half));
+
+        assert!(log.since(seen).is_none());
+        assert_eq!(log.since(log.count() - 2).unwrap().count(), 2);
+
+        log.push(change(&"y".repeat(MAX_BYTES + 1)));
+        assert!(log.since(log.count() - 1).is_none());
+        assert_eq!(log.since(log.count()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn should_find_the_common_ends_of_an_edited_copy() {
+        let old = Rope::from_str(&"abcdefgh\n".repeat(10_000));
+        let mut new = old.clone();
+        new.remove(40_003..40_005);
+        new.insert(40_003, "XYZ");
+
+        assert_eq!(common_ends(&old, &new), (40_003, old.len_chars() - 40_005));
+        assert_eq!(common_ends(&old, &old.clone()), (old.len_chars(), 0));
+        assert_eq!(
+            common_ends(&Rope::from_str("aba"), &Rope::from_str("aa")),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn should_tell_where_the_old_and_new_text_end() {
+        let mut replaced = change("xy\nz");
+        replaced.old_text = String::from("abc");
+
+        assert_eq!(replaced.old_end(), Position::new(1, 5));
+        assert_eq!(replaced.new_end(), Position::new(2, 1));
+    }
+}
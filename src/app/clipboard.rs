@@ -0,0 +1,139 @@
+//! System clipboard access for the Zack text editor.
+//!
+//! The terminal gives no way to read the clipboard, so this module asks the tools of the
+//! platform for it: `wl-paste` on Wayland, `xclip` or `xsel` on X11 and `pbpaste` on
+//! macOS, and their counterparts `wl-copy` and `pbcopy` to write it. The first one that
+//! runs and succeeds wins, so the editor works wherever any of them is installed.
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// Commands printing the clipboard, tried in order.
+const READERS: &[(&str, &[&str])] = &[
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-o", "-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("pbpaste", &[]),
+];
+
+/// Commands replacing the clipboard with their input, tried in order.
+const WRITERS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-i", "-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("pbcopy", &[]),
+];
+
+/// Returns the text in the system clipboard.
+pub fn read() -> io::Result<String> {
+    read_with(READERS)
+}
+
+/// Returns the output of the first of `readers` that exits successfully, or the error of
+/// the last one when none does.
+fn read_with(readers: &[(&str, &[&str])]) -> io::Result<String> {
+    let mut error = io::Error::new(io::ErrorKind::NotFound, "no clipboard tool found");
+
+    for (program, args) in readers {
+        let output = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                error = err;
+                continue;
+            }
+        };
+
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+
+        error = io::Error::other(format!("{program} exited with {}", output.status));
+    }
+
+    Err(error)
+}
+
+/// Replaces the text in the system clipboard with `text`.
+pub fn write(text: &str) -> io::Result<()> {
+    write_with(WRITERS, text)
+}
+
+/// Feeds `text` to the first of `writers` that exits successfully, returning the error of
+/// the last one when none does.
+fn write_with(writers: &[(&str, &[&str])], text: &str) -> io::Result<()> {
+    let mut error = io::Error::new(io::ErrorKind::NotFound, "no clipboard tool found");
+
+    for (program, args) in writers {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                error = err;
+                continue;
+            }
+        };
+
+        // Dropping stdin closes it, so the tool sees the end of the text.
+        let written = child
+            .stdin
+            .take()
+            .map_or(Ok(()), |mut stdin| stdin.write_all(text.as_bytes()));
+        let status = child.wait()?;
+
+        match written {
+            Ok(()) if status.success() => return Ok(()),
+            Ok(()) => error = io::Error::other(format!("{program} exited with {status}")),
+            Err(err) => error = err,
+        }
+    }
+
+    Err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_from_the_first_tool_that_succeeds() {
+        let readers: &[(&str, &[&str])] = &[
+            ("zack-missing-clipboard-tool", &[]),
+            ("false", &[]),
+            ("printf", &["copied"]),
+        ];
+
+        assert_eq!(read_with(readers).unwrap(), "copied");
+        assert!(read_with(&readers[..2]).is_err());
+    }
+
+    #[test]
+    fn should_write_with_the_first_tool_that_succeeds() {
+        let path = std::env::temp_dir().join(format!("zack-clipboard-{}", std::process::id()));
+        let command = format!("cat > {}", path.display());
+        let writers: &[(&str, &[&str])] = &[
+            ("zack-missing-clipboard-tool", &[]),
+            ("false", &[]),
+            ("sh", &["-c", &command]),
+        ];
+
+        write_with(writers, "copied").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "copied");
+        assert!(write_with(&writers[..2], "copied").is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
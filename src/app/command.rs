@@ -0,0 +1,1915 @@
+//! Command-line (`:`) parsing and execution for the Zack text editor.
+//!
+//! This module turns the text typed after `:` into a [`Command`], made of an optional
+//! [`RangeSpec`] (`%`, `5`, `.`, `$`, `'<,'>`, ...) and a [`CommandKind`]. Executing a
+//! command resolves its range against the current [`App`] state and produces the
+//! [`AppEvent`]s that carry it out.
+//!
+//! # Supported commands
+//!
+//! - `:{range}` jumps to the last line of the range.
+//! - `:w` saves the buffer; `:w file` writes a copy to another file, refusing to replace
+//!   an existing one unless forced with `:w!`, and `:{range}w file` writes only the
+//!   lines of the range. A buffer without a file takes the name of the first one.
+//! - `:{range}export file` writes the buffer, or the lines of the range, with its colors
+//!   as HTML (`.html` files) or ANSI text (any other file); `:export!` replaces a file.
+//! - `:{range}hardcopy` prints the buffer, or the lines of the range, through the print
+//!   command, with a header on each page.
+//! - `:saveas file` saves the buffer to a new path, which becomes its file.
+//! - `:wa` saves every modified buffer, reporting each failure without stopping, or how
+//!   many were written.
+//! - `:q` / `:qa` quits, asking whether to save buffers with unsaved changes first, listing
+//!   them; `:q!` / `:qa!` discards them.
+//! - `:wq` / `:x` saves and quits.
+//! - `:xa` / `:wqa` saves every modified buffer and quits if all of them were written.
+//! - `:e file` opens a file in a new buffer; `:bn` / `:bp` switch between buffers.
+//! - `:oldfiles` picks a recently opened file to open again, the newest first; typing
+//!   filters them and `Tab` goes through them.
+//! - `:cd [dir]` changes the working directory of the editor, to the home directory
+//!   without one; `:lcd [dir]` changes only that of the buffer, which relative paths given
+//!   to `:e`, `:saveas`, `:w`, `:r` and the file prompts are taken from; `:pwd` shows it.
+//! - `:clone` opens a scratch copy of the buffer, to try changes on it.
+//! - `:set option` changes an editor option.
+//! - `:!cmd` runs a shell command and shows its output; `:r !cmd` inserts it below the cursor.
+//! - `:r file` inserts the contents of a file below the cursor line, as one edit.
+//! - `:{range}!cmd` pipes the lines of the range through a shell command.
+//! - `:help` shows the keys of each mode; `F1` does the same.
+//! - `:messages` lists the notifications of the session, with their age and level.
+//! - `:log [count]` lists the last log records, at the level set with `--log-level`.
+//! - `:registers` shows what each register holds: the text `p` puts (`"`), the last search
+//!   (`/`), the last command line (`:`) and the clipboard (`+`); `:marks` lists the marks set, with their line.
+//! - `:jobs` lists the running background jobs; `:cancel [id]` stops one or all of them.
+//! - `:tail` toggles following the file, showing what gets appended to it read-only.
+//! - `:pretty` toggles the pretty view, splitting long lines at synthetic breaks.
+//! - `:guard` turns per-edit work back on for a buffer over the size limits, or off again.
+//! - `:diff [file [file]]` shows how the buffer differs from its file on disk, from
+//!   another file, or how two files differ; `:sdiff` shows the same side by side.
+//! - `:{range}diffclip` shows how the buffer, or the lines of the range, differ from the
+//!   clipboard; `:sdiffclip` shows the same side by side.
+//! - `:license` adds a license header at the top of the buffer, commented out for its
+//!   file type, or updates the years of the copyright line already there.
+//! - `:{range}count` shows the line, word and char counts of the buffer, or of the lines
+//!   of the range, as `:'<,'>count` does for a visual selection.
+//! - `:make [args]` (or `:compile`) runs the build command of `:set makeprg` in the
+//!   background and goes to the first `file:line:col` location of its output; `:cnext`,
+//!   `:cprevious` and `:clist` go through and list the others.
+//! - `:{range}format` pipes the buffer, or the lines of the range, through the formatter
+//!   set for its file type in the configuration file.
+//! - `:delete-file [file]` moves the file of the buffer, or the given file, to the trash;
+//!   `:delete-file --force [file]` deletes it permanently, after confirmation.
+//! - `:accept ours|theirs|both` resolves the merge conflict under the cursor, or the next
+//!   one, keeping the local side, the remote side or both.
+//! - `:earlier [count]` / `:later [count]` go back or forward by a number of undo states,
+//!   or by time with a count of `s`, `m` or `h`, as in `:earlier 10m`.
+//! - `:terminal` (or `:term`) opens a shell in a split at the bottom of the editor, or
+//!   focuses it when already open; `Ctrl-W w` goes back to the windows.
+//! - `:autocmd` (or `:au`) lists the autocommands; `:autocmd Hook pattern command` adds
+//!   one for the session, running `command` when `Hook` fires in a buffer whose path
+//!   matches `pattern`, as in `:au BufWritePost *.rs make`.
+//! - `:Name [argument]`, starting with an uppercase letter, runs a user command
+//!   registered by a script of the configuration directory.
+//! - `:undotree` lists the undo states in a side panel, to preview and jump to any of them.
+//! - `:map mode keys action` binds keys to an action in a mode for the session, shadowing
+//!   the bindings of the keymap file and the defaults; `:map --buffer` binds them in the
+//!   active buffer only.
+//! - `:layout` lists the saved layouts; `:layout save name` saves the open files, cursors
+//!   and view options, `:layout name` restores them and `:layout delete name` forgets them.
+
+use crate::{
+    app::{
+        App,
+        buffer_manager::Document,
+        clipboard,
+        diff::{self, DiffKind},
+        export::{self, ExportFormat},
+        file,
+        filetype::FiletypeSettings,
+        hardcopy,
+        hooks::Autocmd,
+        jobs::JobId,
+        keymap::{self, Action, KeymapMode},
+        layout::{Layout, LayoutStore},
+        license::{self, CommentStyle},
+        logging,
+        marks::Marks,
+        merge::{self, Resolution},
+        state, template,
+        undo::UndoSpan,
+    },
+    event::{
+        AppEvent, BufferEvent, BufferManagerEvent, CursorEvent, FileEvent, JobEvent, QuickfixEvent,
+        ShellEvent, TerminalEvent,
+    },
+    types::position::Position,
+    ui::components::prompt::PromptKind,
+};
+use crossterm::event::KeyEvent;
+use std::{borrow::Cow, fmt, path::PathBuf, time::Instant};
+
+/// Most unsaved buffers named when asking before quitting.
+const MAX_LISTED: usize = 5;
+
+/// A single line address, as typed by the user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Address {
+    /// A one-based line number.
+    Number(usize),
+    /// The cursor line (`.`).
+    Current,
+    /// The last line of the buffer (`$`).
+    Last,
+    /// The line of a mark (`'a`, `'<`, ...).
+    Mark(char),
+}
+
+/// The range prefix of a command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RangeSpec {
+    /// Every line in the buffer (`%`).
+    Whole,
+    /// A single line.
+    Single(Address),
+    /// All lines from the first address to the second, inclusive.
+    Span(Address, Address),
+}
+
+/// A resolved, zero-based and inclusive line range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// What a command does, independent of its range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommandKind {
+    /// Moves the cursor to the last line of the range.
+    Goto,
+    /// Saves the buffer, or writes it (or the lines of the range) to another path if one
+    /// is given, replacing an existing file only if `force` is set.
+    Write { path: Option<PathBuf>, force: bool },
+    /// Saves the buffer to a new path, which becomes its file.
+    SaveAs(PathBuf),
+    /// Writes the buffer (or the lines of the range) with its colors to a path, as HTML
+    /// or ANSI text, replacing an existing file only if `force` is set.
+    Export { path: PathBuf, force: bool },
+    /// Prints the buffer (or the lines of the range) through the print command.
+    Hardcopy,
+    /// Saves every modified buffer.
+    WriteAll,
+    /// Saves the buffer and quits.
+    WriteQuit,
+    /// Saves every modified buffer and quits.
+    WriteQuitAll,
+    /// Quits the editor, discarding unsaved changes if `force` is set.
+    Quit { force: bool },
+    /// Quits the editor with all its buffers, discarding unsaved changes if `force` is set.
+    QuitAll { force: bool },
+    /// Opens a file in a new buffer.
+    Edit(PathBuf),
+    /// Switches to the next buffer.
+    BufferNext,
+    /// Switches to the previous buffer.
+    BufferPrevious,
+    /// Opens a scratch copy of the buffer.
+    Clone,
+    /// Changes an editor option, like `mouse` or `nomouse`.
+    Set(String),
+    /// Pipes the lines of the range through a shell command, replacing them with its output.
+    Filter(String),
+    /// Runs a shell command and shows its output.
+    Shell(String),
+    /// Inserts the output of a shell command below the cursor line.
+    ReadCommand(String),
+    /// Inserts the contents of a file below the cursor line.
+    ReadFile(PathBuf),
+    /// Shows the help screen listing the keys of each mode.
+    Help,
+    /// Lists the notifications of the session.
+    Messages,
+    /// Lists the last log records, all those kept without a count.
+    Log(Option<usize>),
+    /// Shows the contents of the registers.
+    Registers,
+    /// Lists the marks set, with their position and line.
+    Marks,
+    /// Lists the running background jobs.
+    Jobs,
+    /// Cancels the job with the given id, or every job.
+    Cancel(Option<JobId>),
+    /// Starts or stops following the file of the active buffer.
+    Tail,
+    /// Overrides the size guard of the active buffer, or stops overriding it.
+    Guard,
+    /// Turns the pretty view of the active buffer on or off.
+    Pretty,
+    /// Shows the differences between the buffer and its file (no paths), another file
+    /// (one path) or two files (two paths).
+    Diff {
+        paths: Vec<PathBuf>,
+        side_by_side: bool,
+    },
+    /// Shows the differences between the clipboard and the buffer, or the lines of the
+    /// range.
+    DiffClipboard { side_by_side: bool },
+    /// Lists, saves, restores or deletes named layouts.
+    Layout(LayoutAction),
+    /// Adds a license header to the buffer, or updates its copyright years.
+    License,
+    /// Pipes the buffer, or the lines of the range, through the formatter of its file
+    /// type.
+    Format,
+    /// Shows the line, word and char counts of the buffer, or of the lines of the range.
+    Count,
+    /// Runs the build command, with the given arguments, and goes to the first error of
+    /// its output.
+    Make(Option<String>),
+    /// Goes to the next entry of the quickfix list.
+    QuickfixNext,
+    /// Goes to the previous entry of the quickfix list.
+    QuickfixPrevious,
+    /// Lists the entries of the quickfix list.
+    QuickfixList,
+    /// Moves the file of the buffer, or the given one, to the trash, or deletes it
+    /// permanently if `force` is set.
+    DeleteFile { path: Option<PathBuf>, force: bool },
+    /// Resolves the merge conflict under the cursor, or the next one below it.
+    Accept(Resolution),
+    /// Goes back in the undo history, or forward when `later` is set.
+    Earlier { span: UndoSpan, later: bool },
+    /// Lists the undo states in a side panel.
+    UndoTree,
+    /// Opens or focuses the terminal pane.
+    Terminal,
+    /// Picks a recently opened file to open again.
+    OldFiles,
+    /// Changes the working directory of the editor, or only that of the active buffer if
+    /// `local` is set, to `path`, or to the home directory when there is none.
+    ChangeDirectory { path: Option<PathBuf>, local: bool },
+    /// Shows the directory relative paths are taken from in the active buffer.
+    PrintDirectory,
+    /// Lists the autocommands, or adds one.
+    Autocmd(Option<Autocmd>),
+    /// Runs a user command registered by a script.
+    User { name: String, argument: String },
+    /// Binds keys to an action in a mode, in the active buffer only if `buffer` is set.
+    Map {
+        mode: KeymapMode,
+        keys: Vec<KeyEvent>,
+        action: Action,
+        buffer: bool,
+    },
+}
+
+/// What `:layout` does with the saved layouts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayoutAction {
+    List,
+    Save(String),
+    Load(String),
+    Delete(String),
+}
+
+/// A parsed command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Command {
+    pub range: Option<RangeSpec>,
+    pub kind: CommandKind,
+}
+
+/// Errors produced while parsing or executing a command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommandError {
+    /// The command name is not recognized.
+    UnknownCommand(String),
+    /// The range prefix could not be parsed.
+    InvalidRange(String),
+    /// The range refers to a mark that has not been set.
+    UnknownMark(char),
+    /// A range was given to a command that does not accept one.
+    RangeNotAllowed,
+    /// The command needs a range that was not given.
+    RangeRequired,
+    /// The command needs a file name but the buffer has none.
+    NoFileName,
+    /// The command needs an argument that was not given.
+    ArgumentRequired,
+    /// The argument of the command is not supported.
+    InvalidArgument(String),
+    /// A file needed by the command could not be read.
+    ReadFailed { path: PathBuf, reason: String },
+    /// The system clipboard could not be read.
+    ClipboardFailed(String),
+    /// Writing would replace an existing file without being forced.
+    FileExists(PathBuf),
+    /// The path cannot be written to.
+    CannotWrite { path: PathBuf, reason: String },
+    /// No layout was saved with the given name.
+    UnknownLayout(String),
+    /// The layouts file could not be read or written.
+    LayoutFailed(String),
+    /// The comment syntax of the named file is not known.
+    UnknownCommentSyntax(String),
+    /// No formatter is configured for the file type of the named file.
+    NoFormatter(String),
+    /// The file to delete does not exist.
+    NoSuchFile(PathBuf),
+    /// The directory to change to does not exist.
+    NoSuchDirectory(PathBuf),
+    /// There is no merge conflict at or below the cursor.
+    NoConflict,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::UnknownCommand(name) => write!(f, "Not an editor command: {name}"),
+            CommandError::InvalidRange(range) => write!(f, "Invalid range: {range}"),
+            CommandError::UnknownMark(mark) => write!(f, "Mark not set: '{mark}"),
+            CommandError::RangeNotAllowed => write!(f, "No range allowed"),
+            CommandError::RangeRequired => write!(f, "Range required"),
+            CommandError::NoFileName => write!(f, "No file name"),
+            CommandError::ArgumentRequired => write!(f, "Argument required"),
+            CommandError::InvalidArgument(argument) => write!(f, "Invalid argument: {argument}"),
+            CommandError::ReadFailed { path, reason } => {
+                write!(f, "Cannot read {}: {reason}", path.display())
+            }
+            CommandError::ClipboardFailed(reason) => {
+                write!(f, "Cannot read the clipboard: {reason}")
+            }
+            CommandError::FileExists(path) => {
+                write!(f, "File exists: {} (add ! to override)", path.display())
+            }
+            CommandError::CannotWrite { path, reason } => {
+                write!(f, "Cannot write {}: {reason}", path.display())
+            }
+            CommandError::UnknownLayout(name) => write!(f, "No layout named {name}"),
+            CommandError::LayoutFailed(reason) => write!(f, "Cannot access layouts: {reason}"),
+            CommandError::UnknownCommentSyntax(name) => {
+                write!(f, "Unknown comment syntax for {name}")
+            }
+            CommandError::NoFormatter(name) => write!(f, "No formatter set for {name}"),
+            CommandError::NoSuchFile(path) => write!(f, "No such file: {}", path.display()),
+            CommandError::NoSuchDirectory(path) => {
+                write!(f, "No such directory: {}", path.display())
+            }
+            CommandError::NoConflict => write!(f, "No merge conflict at or below the cursor"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl Address {
+    /// Resolves the address to a zero-based line index, clamped to the buffer.
+    fn resolve(
+        &self,
+        current_line: usize,
+        last_line: usize,
+        marks: &Marks,
+    ) -> Result<usize, CommandError> {
+        let line = match self {
+            Address::Number(number) => number.saturating_sub(1),
+            Address::Current => current_line,
+            Address::Last => last_line,
+            Address::Mark(mark) => {
+                marks
+                    .get(*mark)
+                    .ok_or(CommandError::UnknownMark(*mark))?
+                    .line
+            }
+        };
+
+        Ok(line.min(last_line))
+    }
+}
+
+impl RangeSpec {
+    /// Resolves the range to zero-based line indices, swapping backwards ranges.
+    pub fn resolve(
+        &self,
+        current_line: usize,
+        last_line: usize,
+        marks: &Marks,
+    ) -> Result<LineRange, CommandError> {
+        let (start, end) = match self {
+            RangeSpec::Whole => (0, last_line),
+            RangeSpec::Single(address) => {
+                let line = address.resolve(current_line, last_line, marks)?;
+                (line, line)
+            }
+            RangeSpec::Span(from, to) => (
+                from.resolve(current_line, last_line, marks)?,
+                to.resolve(current_line, last_line, marks)?,
+            ),
+        };
+
+        Ok(LineRange {
+            start: start.min(end),
+            end: start.max(end),
+        })
+    }
+}
+
+/// Parses the text typed after `:` into a [`Command`].
+pub fn parse(input: &str) -> Result<Command, CommandError> {
+    let input = input.trim();
+    let (range, rest) = parse_range(input)?;
+
+    let rest = rest.trim();
+
+    if let Some(shell_command) = rest.strip_prefix('!') {
+        let shell_command = shell_command.trim();
+
+        if shell_command.is_empty() {
+            return Err(CommandError::ArgumentRequired);
+        }
+
+        let kind = match range {
+            Some(_) => CommandKind::Filter(shell_command.to_string()),
+            None => CommandKind::Shell(shell_command.to_string()),
+        };
+
+        return Ok(Command { range, kind });
+    }
+
+    let (name, argument) = match rest.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, Some(argument.trim())),
+        None => (rest, None),
+    };
+
+    let (bare_name, force) = match name.strip_suffix('!') {
+        Some(bare_name) => (bare_name, true),
+        None => (name, false),
+    };
+
+    let kind = match (bare_name, force) {
+        ("", false) => CommandKind::Goto,
+        ("w" | "write", force) => CommandKind::Write {
+            path: argument.map(PathBuf::from),
+            force,
+        },
+        ("sav" | "saveas", false) => CommandKind::SaveAs(
+            argument
+                .map(PathBuf::from)
+                .ok_or(CommandError::ArgumentRequired)?,
+        ),
+        ("export", force) => CommandKind::Export {
+            path: argument
+                .map(PathBuf::from)
+                .ok_or(CommandError::ArgumentRequired)?,
+            force,
+        },
+        ("ha" | "hardcopy", false) => CommandKind::Hardcopy,
+        ("wa" | "wall", false) => CommandKind::WriteAll,
+        ("wq" | "x" | "xit", false) => CommandKind::WriteQuit,
+        ("xa" | "xall" | "wqa" | "wqall", false) => CommandKind::WriteQuitAll,
+        ("q" | "quit", force) => CommandKind::Quit { force },
+        ("qa" | "qall", force) => CommandKind::QuitAll { force },
+        ("e" | "edit", false) => CommandKind::Edit(
+            argument
+                .map(PathBuf::from)
+                .ok_or(CommandError::ArgumentRequired)?,
+        ),
+        ("bn" | "bnext", false) => CommandKind::BufferNext,
+        ("bp" | "bprevious", false) => CommandKind::BufferPrevious,
+        ("clone", false) => CommandKind::Clone,
+        ("r" | "read", false) => {
+            let argument = argument.ok_or(CommandError::ArgumentRequired)?;
+
+            match argument.strip_prefix('!').map(str::trim) {
+                Some(shell_command) if !shell_command.is_empty() => {
+                    CommandKind::ReadCommand(shell_command.to_string())
+                }
+                Some(_) => return Err(CommandError::ArgumentRequired),
+                None => CommandKind::ReadFile(PathBuf::from(argument)),
+            }
+        }
+        ("h" | "help", false) => CommandKind::Help,
+        ("mes" | "messages", false) => CommandKind::Messages,
+        ("log", false) => CommandKind::Log(
+            argument
+                .map(|count| {
+                    count
+                        .parse()
+                        .map_err(|_| CommandError::InvalidArgument(count.to_string()))
+                })
+                .transpose()?,
+        ),
+        ("reg" | "registers" | "di" | "display", false) => CommandKind::Registers,
+        ("marks", false) => CommandKind::Marks,
+        ("jobs", false) => CommandKind::Jobs,
+        ("tail", false) => CommandKind::Tail,
+        ("guard", false) => CommandKind::Guard,
+        ("pretty", false) => CommandKind::Pretty,
+        ("diff" | "sdiff", false) => {
+            let paths: Vec<PathBuf> = argument
+                .map(|argument| argument.split_whitespace().map(PathBuf::from).collect())
+                .unwrap_or_default();
+
+            if paths.len() > 2 {
+                return Err(CommandError::InvalidArgument(
+                    argument.unwrap_or_default().to_string(),
+                ));
+            }
+
+            CommandKind::Diff {
+                paths,
+                side_by_side: bare_name == "sdiff",
+            }
+        }
+        ("diffclip" | "sdiffclip", false) => CommandKind::DiffClipboard {
+            side_by_side: bare_name == "sdiffclip",
+        },
+        ("layout", false) => CommandKind::Layout(parse_layout_action(argument)?),
+        ("license", false) => CommandKind::License,
+        ("format", false) => CommandKind::Format,
+        ("count", false) => CommandKind::Count,
+        ("mak" | "make" | "compile", false) => CommandKind::Make(argument.map(String::from)),
+        ("cn" | "cnext", false) => CommandKind::QuickfixNext,
+        ("cp" | "cprevious" | "cN" | "cNext", false) => CommandKind::QuickfixPrevious,
+        ("cl" | "clist", false) => CommandKind::QuickfixList,
+        ("delete-file", false) => parse_delete_file(argument),
+        ("accept", false) => {
+            let argument = argument.ok_or(CommandError::ArgumentRequired)?;
+
+            CommandKind::Accept(
+                Resolution::from_name(argument)
+                    .ok_or_else(|| CommandError::InvalidArgument(argument.to_string()))?,
+            )
+        }
+        ("earlier" | "later", false) => CommandKind::Earlier {
+            span: match argument {
+                Some(argument) => UndoSpan::parse(argument)
+                    .ok_or_else(|| CommandError::InvalidArgument(argument.to_string()))?,
+                None => UndoSpan::Steps(1),
+            },
+            later: bare_name == "later",
+        },
+        ("undotree" | "undolist", false) => CommandKind::UndoTree,
+        ("terminal" | "term", false) => CommandKind::Terminal,
+        ("cd" | "chdir", false) => CommandKind::ChangeDirectory {
+            path: argument.map(PathBuf::from),
+            local: false,
+        },
+        ("lcd" | "lchdir", false) => CommandKind::ChangeDirectory {
+            path: argument.map(PathBuf::from),
+            local: true,
+        },
+        ("pwd", false) => CommandKind::PrintDirectory,
+        ("ol" | "oldfiles", false) => CommandKind::OldFiles,
+        ("au" | "autocmd", false) => CommandKind::Autocmd(
+            argument
+                .map(|argument| Autocmd::parse(argument).map_err(CommandError::InvalidArgument))
+                .transpose()?,
+        ),
+        ("map", false) => parse_map(argument.ok_or(CommandError::ArgumentRequired)?)?,
+        ("cancel", false) => CommandKind::Cancel(
+            argument
+                .map(|id| {
+                    id.parse()
+                        .map_err(|_| CommandError::InvalidArgument(id.to_string()))
+                })
+                .transpose()?,
+        ),
+        ("se" | "set", false) => {
+            CommandKind::Set(argument.ok_or(CommandError::ArgumentRequired)?.to_string())
+        }
+        (name, false) if name.starts_with(|c: char| c.is_ascii_uppercase()) => CommandKind::User {
+            name: name.to_string(),
+            argument: argument.unwrap_or_default().to_string(),
+        },
+        _ => return Err(CommandError::UnknownCommand(name.to_string())),
+    };
+
+    if range.is_some()
+        && !matches!(
+            kind,
+            CommandKind::Goto
+                | CommandKind::Write { .. }
+                | CommandKind::Export { .. }
+                | CommandKind::Hardcopy
+                | CommandKind::DiffClipboard { .. }
+                | CommandKind::Format
+                | CommandKind::Count
+        )
+    {
+        return Err(CommandError::RangeNotAllowed);
+    }
+
+    Ok(Command { range, kind })
+}
+
+/// Parses and executes a command line against the current application state.
+///
+/// Returns the events that carry out the command, or the reason it cannot run.
+pub fn execute(input: &str, app: &App) -> Result<Vec<AppEvent>, CommandError> {
+    let command = parse(input)?;
+
+    let document = app.buffers.active();
+    let current_line = document.cursor.position.line;
+    let last_line = document.buffer.len_lines().saturating_sub(1);
+    let range = command
+        .range
+        .as_ref()
+        .map(|range| range.resolve(current_line, last_line, &app.marks))
+        .transpose()?;
+
+    let events = match command.kind {
+        CommandKind::Goto => match range {
+            Some(range) => vec![AppEvent::Cursor(CursorEvent::SetLinePosition(range.end))],
+            None => vec![],
+        },
+        CommandKind::Write { path: None, .. } if range.is_some() => {
+            return Err(CommandError::ArgumentRequired);
+        }
+        CommandKind::Write { path: None, .. } => vec![AppEvent::File(FileEvent::Save)],
+        CommandKind::Write {
+            path: Some(path),
+            force,
+        } => vec![write_to(document, document.resolve(path), range, force)?],
+        CommandKind::SaveAs(path) => vec![AppEvent::File(FileEvent::SaveAs(path))],
+        CommandKind::Export { path, force } => {
+            export_to(app, path, range, force)?;
+            vec![]
+        }
+        CommandKind::Hardcopy => vec![print(app, range)],
+        CommandKind::WriteAll => vec![AppEvent::BufferManager(BufferManagerEvent::WriteAll {
+            quit: false,
+        })],
+        CommandKind::WriteQuit => {
+            if document.file.path.is_none() {
+                return Err(CommandError::NoFileName);
+            }
+
+            vec![AppEvent::File(FileEvent::Save), AppEvent::Quit]
+        }
+        CommandKind::WriteQuitAll => {
+            vec![AppEvent::BufferManager(BufferManagerEvent::WriteAll {
+                quit: true,
+            })]
+        }
+        CommandKind::Quit { force } | CommandKind::QuitAll { force } => {
+            let unsaved = app.buffers.modified_names();
+
+            if !force && !unsaved.is_empty() {
+                return Ok(vec![AppEvent::Dialog {
+                    message: unsaved_message(&unsaved),
+                    on_yes: vec![AppEvent::BufferManager(BufferManagerEvent::WriteAll {
+                        quit: true,
+                    })],
+                    on_no: vec![AppEvent::Quit],
+                }]);
+            }
+
+            vec![AppEvent::Quit]
+        }
+        CommandKind::Edit(path) => vec![AppEvent::BufferManager(BufferManagerEvent::Open(path))],
+        CommandKind::BufferNext => vec![AppEvent::BufferManager(BufferManagerEvent::Next)],
+        CommandKind::BufferPrevious => vec![AppEvent::BufferManager(BufferManagerEvent::Previous)],
+        CommandKind::Clone => vec![AppEvent::BufferManager(BufferManagerEvent::Clone)],
+        CommandKind::Set(argument) => vec![AppEvent::SetOption(argument)],
+        CommandKind::Filter(shell_command) => {
+            let range = range.ok_or(CommandError::RangeRequired)?;
+
+            vec![AppEvent::Shell(ShellEvent::Filter {
+                command: shell_command,
+                start: range.start,
+                end: range.end,
+            })]
+        }
+        CommandKind::Shell(shell_command) => vec![AppEvent::Shell(ShellEvent::Run {
+            command: shell_command,
+        })],
+        CommandKind::ReadCommand(shell_command) => vec![AppEvent::Shell(ShellEvent::Read {
+            command: shell_command,
+            line: current_line,
+        })],
+        CommandKind::ReadFile(path) => read_file(document, &document.resolve(path), current_line)?
+            .into_iter()
+            .collect(),
+        CommandKind::Help => vec![keymap::help_event()],
+        CommandKind::Messages => vec![AppEvent::ShowMessage {
+            title: String::from(":messages"),
+            text: app.notifications.describe(Instant::now()),
+        }],
+        CommandKind::Log(count) => vec![AppEvent::ShowMessage {
+            title: String::from(":log"),
+            text: logging::describe(count.unwrap_or(logging::MAX_RECENT)),
+        }],
+        CommandKind::Registers => vec![AppEvent::ShowMessage {
+            title: String::from(":registers"),
+            text: describe_registers(app, clipboard::read().ok()),
+        }],
+        CommandKind::Marks => vec![AppEvent::ShowMessage {
+            title: String::from(":marks"),
+            text: describe_marks(app),
+        }],
+        CommandKind::Jobs => vec![AppEvent::ShowMessage {
+            title: String::from(":jobs"),
+            text: describe_jobs(app),
+        }],
+        CommandKind::Cancel(Some(id)) => vec![AppEvent::Job(JobEvent::Cancel(id))],
+        CommandKind::Cancel(None) => vec![AppEvent::Job(JobEvent::CancelAll)],
+        CommandKind::Tail => vec![AppEvent::BufferManager(BufferManagerEvent::ToggleFollow)],
+        CommandKind::Guard => vec![AppEvent::BufferManager(BufferManagerEvent::ToggleGuard)],
+        CommandKind::Pretty => vec![AppEvent::BufferManager(BufferManagerEvent::TogglePretty)],
+        CommandKind::Diff {
+            paths,
+            side_by_side,
+        } => vec![describe_diff(app, &paths, side_by_side)?],
+        CommandKind::DiffClipboard { side_by_side } => {
+            let clipboard =
+                clipboard::read().map_err(|err| CommandError::ClipboardFailed(err.to_string()))?;
+
+            vec![describe_clipboard_diff(
+                document,
+                clipboard,
+                range,
+                side_by_side,
+            )]
+        }
+        CommandKind::Layout(action) => run_layout(app, action)?,
+        CommandKind::License => license_header(document, app.buffers.filetype_settings(document))?
+            .into_iter()
+            .collect(),
+        CommandKind::Make(arguments) => vec![AppEvent::Shell(ShellEvent::Make {
+            command: match arguments {
+                Some(arguments) => format!("{} {arguments}", app.options.makeprg),
+                None => app.options.makeprg.clone(),
+            },
+        })],
+        CommandKind::QuickfixNext => vec![AppEvent::Quickfix(QuickfixEvent::Next)],
+        CommandKind::QuickfixPrevious => vec![AppEvent::Quickfix(QuickfixEvent::Previous)],
+        CommandKind::QuickfixList => vec![AppEvent::Quickfix(QuickfixEvent::List)],
+        CommandKind::Count => {
+            let counts = match range {
+                Some(range) => document.buffer.counts(Some((
+                    Position::new(range.start, 0),
+                    Position::new(range.end, usize::MAX),
+                ))),
+                None => document.counts(),
+            };
+            tracing::info!("{counts}");
+
+            vec![]
+        }
+        CommandKind::Format => {
+            let formatter = app
+                .buffers
+                .filetype_settings(document)
+                .and_then(|settings| settings.formatter.clone())
+                .ok_or_else(|| CommandError::NoFormatter(document.file.display_name()))?;
+            let range = range.unwrap_or_else(|| whole_text(document));
+
+            vec![AppEvent::Shell(ShellEvent::Filter {
+                command: formatter,
+                start: range.start,
+                end: range.end,
+            })]
+        }
+        CommandKind::DeleteFile { path, force } => vec![delete_file(document, path, force)?],
+        CommandKind::Accept(resolution) => vec![accept(document, resolution)?],
+        CommandKind::Earlier { span, later } => {
+            let history = document.buffer.history();
+            let state = match (span, later) {
+                (UndoSpan::Steps(count), false) => history.current().saturating_sub(count),
+                (UndoSpan::Steps(count), true) => history.current().saturating_add(count),
+                (UndoSpan::Time(by), forward) => history.state_at(by, forward),
+            };
+
+            vec![AppEvent::Buffer(BufferEvent::UndoTo {
+                state,
+                position: document.cursor.position,
+            })]
+        }
+        CommandKind::UndoTree => vec![AppEvent::ShowUndoTree],
+        CommandKind::Terminal => vec![AppEvent::Terminal(TerminalEvent::Open)],
+        CommandKind::ChangeDirectory { path, local } => {
+            vec![change_directory(document, path, local)?]
+        }
+        CommandKind::OldFiles => vec![AppEvent::OpenPrompt(PromptKind::RecentFile)],
+        CommandKind::PrintDirectory => {
+            tracing::info!("{}", document.working_directory().display());
+
+            vec![]
+        }
+        CommandKind::Autocmd(Some(autocmd)) => vec![AppEvent::AddAutocmd(autocmd)],
+        CommandKind::Autocmd(None) => vec![AppEvent::ShowMessage {
+            title: String::from(":autocmd"),
+            text: app.hooks.list(),
+        }],
+        CommandKind::User { name, argument } => vec![AppEvent::RunUserCommand { name, argument }],
+        CommandKind::Map {
+            mode,
+            keys,
+            action,
+            buffer,
+        } => vec![AppEvent::Map {
+            mode,
+            keys,
+            action,
+            buffer,
+        }],
+    };
+
+    Ok(events)
+}
+
+/// Returns the question asked before quitting with the `unsaved` buffers: their names,
+/// one per line past the first, the first [`MAX_LISTED`] of them.
+fn unsaved_message(unsaved: &[String]) -> String {
+    let [name] = unsaved else {
+        let mut message = format!("{} buffers have unsaved changes:\n", unsaved.len());
+        for name in unsaved.iter().take(MAX_LISTED) {
+            message.push_str(&format!("  {name}\n"));
+        }
+        if unsaved.len() > MAX_LISTED {
+            message.push_str(&format!("  and {} more\n", unsaved.len() - MAX_LISTED));
+        }
+        message.push_str("Save them before quitting?");
+        return message;
+    };
+
+    format!("Save changes to {name} before quitting?")
+}
+
+/// Returns the edit adding a license header to `document` or updating its years, or
+/// `None` when they are up to date. The header comes from the project, then the user
+/// configuration, then [`license::DEFAULT_HEADER`]. The comment marker of the file type
+/// in the configuration file, if set, is used over the known syntax of the file.
+fn license_header(
+    document: &Document,
+    filetype: Option<&FiletypeSettings>,
+) -> Result<Option<AppEvent>, CommandError> {
+    let path = document
+        .file
+        .path
+        .as_ref()
+        .ok_or(CommandError::NoFileName)?;
+    let style = filetype
+        .and_then(|settings| settings.comment.clone())
+        .map(|marker| CommentStyle::Line(Cow::Owned(marker)))
+        .or_else(|| CommentStyle::of(path))
+        .ok_or_else(|| CommandError::UnknownCommentSyntax(document.file.display_name()))?;
+
+    let header = std::fs::read_to_string(license::PROJECT_PATH)
+        .ok()
+        .or_else(|| std::fs::read_to_string(state::config_directory()?.join("license")).ok())
+        .unwrap_or_else(|| license::DEFAULT_HEADER.to_string());
+    let year = template::today()[..4].parse().unwrap_or_default();
+    let header = license::expand(&header, year, &template::author());
+
+    let lines = buffer_lines(document);
+
+    match license::edit(&lines, &style, &header, year) {
+        Some(edit) => Ok(Some(AppEvent::Buffer(edit))),
+        None => {
+            tracing::info!("The copyright years are up to date");
+            Ok(None)
+        }
+    }
+}
+
+/// Builds the edit resolving the conflict of `document` under the cursor, or the next
+/// one below it, as `resolution` says.
+fn accept(document: &Document, resolution: Resolution) -> Result<AppEvent, CommandError> {
+    let lines = buffer_lines(document);
+    let conflict = merge::at_or_after(&merge::conflicts(&lines), document.cursor.position.line)
+        .ok_or(CommandError::NoConflict)?;
+
+    Ok(AppEvent::Buffer(conflict.resolve(&lines, resolution)))
+}
+
+/// Returns the lines of `document`, without their line endings.
+fn buffer_lines(document: &Document) -> Vec<String> {
+    document
+        .buffer
+        .as_rope()
+        .lines()
+        .map(|line| line.to_string().trim_end_matches(['\n', '\r']).to_string())
+        .collect()
+}
+
+/// Parses the argument of `:delete-file`: an optional `--force` and an optional path.
+fn parse_delete_file(argument: Option<&str>) -> CommandKind {
+    let argument = argument.unwrap_or_default();
+    let (force, path) = match argument.strip_prefix("--force") {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+            (true, rest.trim())
+        }
+        _ => (false, argument),
+    };
+
+    CommandKind::DeleteFile {
+        path: Some(path)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from),
+        force,
+    }
+}
+
+/// Parses the argument of `:map`: an optional `--buffer`, then `mode keys action`.
+fn parse_map(argument: &str) -> Result<CommandKind, CommandError> {
+    let (buffer, binding) = match argument.strip_prefix("--buffer") {
+        Some(rest) if rest.starts_with(char::is_whitespace) => (true, rest.trim()),
+        _ => (false, argument),
+    };
+    let (mode, keys, action) =
+        keymap::parse_binding(binding).map_err(CommandError::InvalidArgument)?;
+
+    Ok(CommandKind::Map {
+        mode,
+        keys,
+        action,
+        buffer,
+    })
+}
+
+/// Builds the event deleting `path`, or the file of `document`: moving it to the trash
+/// right away, or asking first when it is deleted permanently.
+fn delete_file(
+    document: &Document,
+    path: Option<PathBuf>,
+    force: bool,
+) -> Result<AppEvent, CommandError> {
+    let name = match &path {
+        Some(path) => path.display().to_string(),
+        None => document.file.display_name(),
+    };
+    let path = path
+        .map(|path| document.resolve(path))
+        .or_else(|| document.file.path.clone())
+        .ok_or(CommandError::NoFileName)?;
+
+    if std::fs::symlink_metadata(&path).is_err() {
+        return Err(CommandError::NoSuchFile(path));
+    }
+
+    let event = AppEvent::File(FileEvent::Delete {
+        path: path.clone(),
+        permanently: force,
+    });
+
+    Ok(match force {
+        true => AppEvent::Confirm {
+            message: format!("Delete {name} permanently?"),
+            on_confirm: vec![event],
+            on_deny: vec![],
+        },
+        false => event,
+    })
+}
+
+/// Parses the argument of `:layout`: nothing, a name, or `save` / `delete` and a name.
+fn parse_layout_action(argument: Option<&str>) -> Result<LayoutAction, CommandError> {
+    let Some(argument) = argument else {
+        return Ok(LayoutAction::List);
+    };
+
+    let words: Vec<&str> = argument.split_whitespace().collect();
+
+    match words.as_slice() {
+        ["save"] | ["delete"] => Err(CommandError::ArgumentRequired),
+        ["save", name] => Ok(LayoutAction::Save(name.to_string())),
+        ["delete", name] => Ok(LayoutAction::Delete(name.to_string())),
+        [name] => Ok(LayoutAction::Load(name.to_string())),
+        _ => Err(CommandError::InvalidArgument(argument.to_string())),
+    }
+}
+
+/// Carries out a `:layout` action against the layouts of the project.
+fn run_layout(app: &App, action: LayoutAction) -> Result<Vec<AppEvent>, CommandError> {
+    let store = LayoutStore::new();
+    let failed = |err: std::io::Error| CommandError::LayoutFailed(err.to_string());
+
+    let message = |text: String| {
+        vec![AppEvent::ShowMessage {
+            title: String::from(":layout"),
+            text,
+        }]
+    };
+
+    match action {
+        LayoutAction::List => {
+            let names = store.names().map_err(failed)?;
+
+            Ok(message(if names.is_empty() {
+                String::from("No layouts saved")
+            } else {
+                names.join("\n")
+            }))
+        }
+        LayoutAction::Save(name) => {
+            let layout =
+                Layout::capture(&app.buffers, &app.options).ok_or(CommandError::NoFileName)?;
+            store.save(&name, &layout).map_err(failed)?;
+
+            Ok(message(format!(
+                "Saved layout {name} with {} file(s)",
+                layout.files.len()
+            )))
+        }
+        LayoutAction::Load(name) => store
+            .get(&name)
+            .map_err(failed)?
+            .map(|layout| layout.restore_events())
+            .ok_or(CommandError::UnknownLayout(name)),
+        LayoutAction::Delete(name) => {
+            if !store.delete(&name).map_err(failed)? {
+                return Err(CommandError::UnknownLayout(name));
+            }
+
+            Ok(message(format!("Deleted layout {name}")))
+        }
+    }
+}
+
+/// Builds the edit inserting the contents of the file at `path` below `line`, or nothing
+/// when the file is empty.
+fn read_file(
+    document: &Document,
+    path: &PathBuf,
+    line: usize,
+) -> Result<Option<AppEvent>, CommandError> {
+    let text = std::fs::read_to_string(path).map_err(|err| CommandError::ReadFailed {
+        path: path.clone(),
+        reason: err.to_string(),
+    })?;
+
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    let line_end = Position::new(line, 0);
+    let position = Position::new(line, document.buffer.max_visible_col(&line_end));
+
+    Ok(Some(AppEvent::Buffer(BufferEvent::InsertText {
+        text: format!("\n{}", text.trim_end_matches('\n')),
+        position,
+    })))
+}
+
+/// Builds the event showing the diff asked for by `:diff` or `:sdiff`.
+///
+/// The buffer is compared with its file on disk, or with the file in `paths`; two paths
+/// compare two files.
+fn describe_diff(
+    app: &App,
+    paths: &[PathBuf],
+    side_by_side: bool,
+) -> Result<AppEvent, CommandError> {
+    let read = |path: &PathBuf| {
+        std::fs::read_to_string(path).map_err(|err| CommandError::ReadFailed {
+            path: path.clone(),
+            reason: err.to_string(),
+        })
+    };
+    let document = app.buffers.active();
+    let buffer_text = || document.buffer.as_rope().to_string();
+
+    let (old_name, old, new_name, new) = match paths {
+        [] => {
+            let path = document
+                .file
+                .path
+                .as_ref()
+                .ok_or(CommandError::NoFileName)?;
+            let name = path.display().to_string();
+            (
+                format!("{name} (disk)"),
+                read(path)?,
+                format!("{name} (buffer)"),
+                buffer_text(),
+            )
+        }
+        [path] => (
+            path.display().to_string(),
+            read(path)?,
+            format!("{} (buffer)", document.file.display_name()),
+            buffer_text(),
+        ),
+        [old_path, new_path, ..] => (
+            old_path.display().to_string(),
+            read(old_path)?,
+            new_path.display().to_string(),
+            read(new_path)?,
+        ),
+    };
+
+    Ok(show_diff(&old_name, &old, &new_name, &new, side_by_side))
+}
+
+/// Builds the event showing the diff asked for by `:diffclip` or `:sdiffclip`.
+///
+/// The clipboard is the old side, so the diff reads as the changes the buffer, or the
+/// lines of `range`, make to it.
+fn describe_clipboard_diff(
+    document: &Document,
+    clipboard: String,
+    range: Option<LineRange>,
+    side_by_side: bool,
+) -> AppEvent {
+    let name = document.file.display_name();
+    let (new_name, new) = match range {
+        Some(range) => (
+            format!("{name} (lines {}-{})", range.start + 1, range.end + 1),
+            document.buffer.lines_text(range.start, range.end),
+        ),
+        None => (
+            format!("{name} (buffer)"),
+            document.buffer.as_rope().to_string(),
+        ),
+    };
+
+    show_diff("clipboard", &clipboard, &new_name, &new, side_by_side)
+}
+
+/// Returns the event showing how `new` differs from `old`, unified or side by side, or
+/// a message when they are the same.
+fn show_diff(old_name: &str, old: &str, new_name: &str, new: &str, side_by_side: bool) -> AppEvent {
+    let title = format!("{old_name} → {new_name}");
+    let lines = if side_by_side {
+        diff::side_by_side(old, new)
+    } else {
+        diff::unified(old, new, old_name, new_name)
+    };
+
+    if lines.is_empty() || lines.iter().all(|line| line.kind == DiffKind::Context) {
+        return AppEvent::ShowMessage {
+            title,
+            text: String::from("No differences"),
+        };
+    }
+
+    AppEvent::ShowDiff { title, lines }
+}
+
+/// Lists the running jobs, one per line, as `id  name  progress  elapsed`.
+/// Lists the registers holding something, one per line, with line breaks shown as `^J`.
+/// `clipboard` is the text of the system clipboard, when it can be read.
+fn describe_registers(app: &App, clipboard: Option<String>) -> String {
+    let registers = [
+        (
+            '"',
+            app.register.as_ref().map(|register| register.text.clone()),
+        ),
+        ('/', app.search.pattern.clone()),
+        (
+            ':',
+            app.command_line.command_history.entries().last().cloned(),
+        ),
+        ('+', clipboard),
+    ];
+
+    let lines: Vec<String> = registers
+        .into_iter()
+        .filter_map(|(name, text)| Some((name, text.filter(|text| !text.is_empty())?)))
+        .map(|(name, text)| format!("\"{name}  {}", text.replace('\n', "^J")))
+        .collect();
+
+    match lines.is_empty() {
+        true => String::from("No registers set"),
+        false => lines.join("\n"),
+    }
+}
+
+/// Lists the marks set, one per line, with their one-based line and column and the text
+/// of their line.
+fn describe_marks(app: &App) -> String {
+    let marks = app.marks.list();
+    if marks.is_empty() {
+        return String::from("No marks set");
+    }
+
+    let buffer = &app.buffers.active().buffer;
+    marks
+        .iter()
+        .map(|(mark, position)| {
+            let text = match position.line < buffer.len_lines() {
+                true => buffer.lines_text(position.line, position.line),
+                false => String::new(),
+            };
+            let text = text.trim_end();
+
+            format!(
+                "{mark}  {:>5}  {:>4}  {text}",
+                position.line + 1,
+                position.col + 1
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn describe_jobs(app: &App) -> String {
+    if app.jobs.running().is_empty() {
+        return String::from("No jobs running");
+    }
+
+    app.jobs
+        .running()
+        .iter()
+        .map(|job| {
+            let mut line = format!("{:>3}  {}", job.id, job.name);
+
+            if let Some(percent) = job.percent {
+                line.push_str(&format!("  {percent}%"));
+            }
+            if let Some(message) = &job.message {
+                line.push_str(&format!("  {message}"));
+            }
+
+            line.push_str(&format!("  {}s", job.started.elapsed().as_secs()));
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits a leading range off `input`, returning it with the remaining text.
+fn parse_range(input: &str) -> Result<(Option<RangeSpec>, &str), CommandError> {
+    if let Some(rest) = input.strip_prefix('%') {
+        return Ok((Some(RangeSpec::Whole), rest));
+    }
+
+    let Some((from, rest)) = parse_address(input)? else {
+        return Ok((None, input));
+    };
+
+    let Some(after_comma) = rest.strip_prefix(',') else {
+        return Ok((Some(RangeSpec::Single(from)), rest));
+    };
+
+    match parse_address(after_comma)? {
+        Some((to, rest)) => Ok((Some(RangeSpec::Span(from, to)), rest)),
+        None => Err(CommandError::InvalidRange(input.to_string())),
+    }
+}
+
+/// Returns the event changing to the directory asked for by `:cd` or `:lcd`, taking a
+/// relative `path` from the directory of the active buffer and `~` as the home directory.
+fn change_directory(
+    document: &Document,
+    path: Option<PathBuf>,
+    local: bool,
+) -> Result<AppEvent, CommandError> {
+    let home = std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let directory = match path {
+        None => home,
+        Some(path) => match path.strip_prefix("~") {
+            Ok(rest) => home.join(rest),
+            Err(_) => document.working_directory().join(path),
+        },
+    };
+
+    let directory = match directory.canonicalize() {
+        Ok(canonical) if canonical.is_dir() => canonical,
+        _ => return Err(CommandError::NoSuchDirectory(directory)),
+    };
+
+    Ok(AppEvent::ChangeDirectory { directory, local })
+}
+
+/// Returns the event writing the buffer, or the lines of `range`, to `path`.
+///
+/// An existing file other than the buffer's own is only replaced when `force` is set.
+/// A buffer without a file takes `path` as its file, unless only a range is written.
+fn write_to(
+    document: &Document,
+    path: PathBuf,
+    range: Option<LineRange>,
+    force: bool,
+) -> Result<AppEvent, CommandError> {
+    let own = document.file.path.as_ref() == Some(&path);
+
+    if !force && !own && path.exists() {
+        return Err(CommandError::FileExists(path));
+    }
+    if let Err(reason) = file::check_save_path(&path) {
+        return Err(CommandError::CannotWrite { path, reason });
+    }
+
+    Ok(AppEvent::File(FileEvent::WriteTo {
+        path,
+        range,
+        set_path: document.file.path.is_none() && range.is_none(),
+    }))
+}
+
+/// Writes the lines of `range`, or the whole buffer, with their colors to `path`.
+fn export_to(
+    app: &App,
+    path: PathBuf,
+    range: Option<LineRange>,
+    force: bool,
+) -> Result<(), CommandError> {
+    let document = app.buffers.active();
+
+    if !force && path.exists() {
+        return Err(CommandError::FileExists(path));
+    }
+    if let Err(reason) = file::check_save_path(&path) {
+        return Err(CommandError::CannotWrite { path, reason });
+    }
+
+    let range = range.unwrap_or_else(|| whole_text(document));
+    let text = export::render(
+        document,
+        range,
+        ExportFormat::from_path(&path),
+        app.theme.background(document.kind()),
+    );
+
+    std::fs::write(&path, text).map_err(|err| CommandError::CannotWrite {
+        path: path.clone(),
+        reason: err.to_string(),
+    })?;
+    tracing::info!(
+        "Exported {} line(s) to {}",
+        range.end - range.start + 1,
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Builds the event printing the lines of `range`, or the whole buffer, in pages.
+fn print(app: &App, range: Option<LineRange>) -> AppEvent {
+    let document = app.buffers.active();
+    let range = range.unwrap_or_else(|| whole_text(document));
+
+    let text = if app.options.printcolor {
+        export::render(
+            document,
+            range,
+            ExportFormat::Ansi,
+            app.theme.background(document.kind()),
+        )
+    } else {
+        document.buffer.lines_text(range.start, range.end)
+    };
+    let (text, pages) =
+        hardcopy::paginate(&document.file.display_name(), &text, app.options.printlines);
+
+    AppEvent::Shell(ShellEvent::Print {
+        command: app.options.printcmd.clone(),
+        text,
+        pages,
+    })
+}
+
+/// Returns the lines of the text of `document`, leaving out the empty line after a
+/// final line break.
+fn whole_text(document: &Document) -> LineRange {
+    let rope = document.buffer.as_rope();
+    let trailing_break = rope.len_chars() > 0 && rope.char(rope.len_chars() - 1) == '\n';
+
+    LineRange {
+        start: 0,
+        end: rope
+            .len_lines()
+            .saturating_sub(1 + usize::from(trailing_break)),
+    }
+}
+
+/// Parses a single address at the start of `input`, if there is one.
+fn parse_address(input: &str) -> Result<Option<(Address, &str)>, CommandError> {
+    let mut chars = input.chars();
+
+    match chars.next() {
+        Some('.') => Ok(Some((Address::Current, chars.as_str()))),
+        Some('$') => Ok(Some((Address::Last, chars.as_str()))),
+        Some('\'') => match chars.next() {
+            Some(mark) => Ok(Some((Address::Mark(mark), chars.as_str()))),
+            None => Err(CommandError::InvalidRange(input.to_string())),
+        },
+        Some(c) if c.is_ascii_digit() => {
+            let digits = input.len() - input.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let number = input[..digits]
+                .parse()
+                .map_err(|_| CommandError::InvalidRange(input.to_string()))?;
+
+            Ok(Some((Address::Number(number), &input[digits..])))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{hooks::Hook, marks::MarkEvent},
+        types::position::Position,
+    };
+
+    fn marks_with(mark: char, line: usize) -> Marks {
+        let mut marks = Marks::new();
+        marks.handle_event(MarkEvent::Set {
+            mark,
+            position: Position::new(line, 0),
+        });
+        marks
+    }
+
+    #[test]
+    fn should_parse_simple_commands() {
+        assert_eq!(
+            parse("w").unwrap().kind,
+            CommandKind::Write {
+                path: None,
+                force: false
+            }
+        );
+        assert_eq!(parse("q").unwrap().kind, CommandKind::Quit { force: false });
+        assert_eq!(parse("wq").unwrap().kind, CommandKind::WriteQuit);
+        assert_eq!(parse("x").unwrap().kind, CommandKind::WriteQuit);
+    }
+
+    #[test]
+    fn should_parse_multi_buffer_commands() {
+        assert_eq!(parse("wa").unwrap().kind, CommandKind::WriteAll);
+        assert_eq!(parse("xa").unwrap().kind, CommandKind::WriteQuitAll);
+        assert_eq!(parse("wqa").unwrap().kind, CommandKind::WriteQuitAll);
+        assert_eq!(
+            parse("qa").unwrap().kind,
+            CommandKind::QuitAll { force: false }
+        );
+        assert_eq!(
+            parse("qa!").unwrap().kind,
+            CommandKind::QuitAll { force: true }
+        );
+        assert_eq!(
+            parse("e other.txt").unwrap().kind,
+            CommandKind::Edit(PathBuf::from("other.txt"))
+        );
+        assert_eq!(parse("e"), Err(CommandError::ArgumentRequired));
+        assert_eq!(
+            parse("lcd src").unwrap().kind,
+            CommandKind::ChangeDirectory {
+                path: Some(PathBuf::from("src")),
+                local: true
+            }
+        );
+        assert_eq!(
+            parse("cd").unwrap().kind,
+            CommandKind::ChangeDirectory {
+                path: None,
+                local: false
+            }
+        );
+        assert_eq!(parse("pwd").unwrap().kind, CommandKind::PrintDirectory);
+        assert_eq!(parse("oldfiles").unwrap().kind, CommandKind::OldFiles);
+        assert_eq!(parse("clone").unwrap().kind, CommandKind::Clone);
+        assert_eq!(
+            parse("%export! out.html").unwrap().kind,
+            CommandKind::Export {
+                path: PathBuf::from("out.html"),
+                force: true
+            }
+        );
+        assert_eq!(parse("export"), Err(CommandError::ArgumentRequired));
+        assert_eq!(parse("'<,'>ha").unwrap().kind, CommandKind::Hardcopy);
+        assert_eq!(
+            parse("wa!"),
+            Err(CommandError::UnknownCommand(String::from("wa!")))
+        );
+    }
+
+    #[test]
+    fn should_leave_changing_the_working_directory_to_the_app() {
+        let document = Document::new(String::new(), None);
+        let before = std::env::current_dir().unwrap();
+
+        let event = change_directory(&document, Some(PathBuf::from("src")), false).unwrap();
+
+        assert_eq!(
+            event,
+            AppEvent::ChangeDirectory {
+                directory: before.join("src").canonicalize().unwrap(),
+                local: false
+            }
+        );
+        assert_eq!(std::env::current_dir().unwrap(), before);
+        assert!(matches!(
+            change_directory(&document, Some(PathBuf::from("missing_directory")), true),
+            Err(CommandError::NoSuchDirectory(_))
+        ));
+    }
+
+    #[test]
+    fn should_list_the_unsaved_buffers_before_quitting() {
+        let names: Vec<String> = (1..=7).map(|n| format!("{n}.txt")).collect();
+
+        assert_eq!(
+            unsaved_message(&names[..1]),
+            "Save changes to 1.txt before quitting?"
+        );
+        assert_eq!(
+            unsaved_message(&names[..2]),
+            "2 buffers have unsaved changes:\n  1.txt\n  2.txt\nSave them before quitting?"
+        );
+        assert!(
+            unsaved_message(&names).ends_with("  5.txt\n  and 2 more\nSave them before quitting?")
+        );
+    }
+
+    #[test]
+    fn should_parse_filter_with_range() {
+        let command = parse("%!sort -u").unwrap();
+
+        assert_eq!(command.range, Some(RangeSpec::Whole));
+        assert_eq!(command.kind, CommandKind::Filter(String::from("sort -u")));
+        assert_eq!(parse("%!"), Err(CommandError::ArgumentRequired));
+    }
+
+    #[test]
+    fn should_parse_shell_and_read_commands() {
+        assert_eq!(
+            parse("!ls -la").unwrap().kind,
+            CommandKind::Shell(String::from("ls -la"))
+        );
+        assert_eq!(
+            parse("r !date").unwrap().kind,
+            CommandKind::ReadCommand(String::from("date"))
+        );
+        assert_eq!(
+            parse("r notes.txt").unwrap().kind,
+            CommandKind::ReadFile(PathBuf::from("notes.txt"))
+        );
+        assert_eq!(parse("r"), Err(CommandError::ArgumentRequired));
+    }
+
+    #[test]
+    fn should_read_files_below_the_line() {
+        let path = PathBuf::from("test_read_file.txt");
+        std::fs::write(&path, "a\nb\n").unwrap();
+        let document = Document::new(String::from("first\nsecond"), None);
+
+        let event = read_file(&document, &path, 0);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            event,
+            Ok(Some(AppEvent::Buffer(BufferEvent::InsertText {
+                text: String::from("\na\nb"),
+                position: Position::new(0, 5),
+            })))
+        );
+        assert!(matches!(
+            read_file(&document, &path, 0),
+            Err(CommandError::ReadFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn should_update_license_years_for_known_file_types() {
+        let year = &template::today()[..4];
+        let document = Document::new(
+            String::from("// Copyright 2020 Ana\nfn main() {}\n"),
+            Some(PathBuf::from("test_license.rs")),
+        );
+
+        assert_eq!(
+            license_header(&document, None),
+            Ok(Some(AppEvent::Buffer(BufferEvent::ReplaceLines {
+                start: 0,
+                end: 0,
+                text: format!("// Copyright 2020-{year} Ana"),
+            })))
+        );
+        assert_eq!(
+            license_header(
+                &Document::new(String::new(), Some(PathBuf::from("test_license.unknown"))),
+                None
+            ),
+            Err(CommandError::UnknownCommentSyntax(String::from(
+                "test_license.unknown"
+            )))
+        );
+        assert_eq!(
+            license_header(&Document::new(String::new(), None), None),
+            Err(CommandError::NoFileName)
+        );
+    }
+
+    #[test]
+    fn should_confirm_permanent_deletes_only() {
+        let path = PathBuf::from("test_delete_file.txt");
+        std::fs::write(&path, "").unwrap();
+        let document = Document::new(String::new(), Some(path.clone()));
+        let delete = |permanently| {
+            AppEvent::File(FileEvent::Delete {
+                path: file::absolute(path.clone()),
+                permanently,
+            })
+        };
+
+        let trashed = delete_file(&document, None, false);
+        let forced = delete_file(&document, None, true);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(trashed, Ok(delete(false)));
+        assert_eq!(
+            forced,
+            Ok(AppEvent::Confirm {
+                message: String::from("Delete test_delete_file.txt permanently?"),
+                on_confirm: vec![delete(true)],
+                on_deny: vec![],
+            })
+        );
+        assert_eq!(
+            delete_file(&document, None, false),
+            Err(CommandError::NoSuchFile(file::absolute(path)))
+        );
+    }
+
+    #[test]
+    fn should_parse_job_commands() {
+        assert_eq!(parse("jobs").unwrap().kind, CommandKind::Jobs);
+        assert_eq!(parse("reg").unwrap().kind, CommandKind::Registers);
+        assert_eq!(parse("marks").unwrap().kind, CommandKind::Marks);
+        assert_eq!(parse("messages").unwrap().kind, CommandKind::Messages);
+        assert_eq!(parse("log").unwrap().kind, CommandKind::Log(None));
+        assert_eq!(parse("log 20").unwrap().kind, CommandKind::Log(Some(20)));
+        assert_eq!(
+            parse("log all"),
+            Err(CommandError::InvalidArgument(String::from("all")))
+        );
+        assert_eq!(parse("help").unwrap().kind, CommandKind::Help);
+        assert_eq!(parse("tail").unwrap().kind, CommandKind::Tail);
+        assert_eq!(parse("guard").unwrap().kind, CommandKind::Guard);
+        assert_eq!(parse("pretty").unwrap().kind, CommandKind::Pretty);
+        assert_eq!(parse("license").unwrap().kind, CommandKind::License);
+        assert_eq!(parse("%format").unwrap().kind, CommandKind::Format);
+        assert_eq!(parse("'<,'>count").unwrap().kind, CommandKind::Count);
+        assert_eq!(
+            parse("make test").unwrap().kind,
+            CommandKind::Make(Some(String::from("test")))
+        );
+        assert_eq!(parse("compile").unwrap().kind, CommandKind::Make(None));
+        assert_eq!(parse("cn").unwrap().kind, CommandKind::QuickfixNext);
+        assert_eq!(
+            parse("cprevious").unwrap().kind,
+            CommandKind::QuickfixPrevious
+        );
+        assert_eq!(
+            parse("earlier").unwrap().kind,
+            CommandKind::Earlier {
+                span: UndoSpan::Steps(1),
+                later: false
+            }
+        );
+        assert_eq!(
+            parse("later 10s").unwrap().kind,
+            CommandKind::Earlier {
+                span: UndoSpan::Time(std::time::Duration::from_secs(10)),
+                later: true
+            }
+        );
+        assert_eq!(
+            parse("earlier 1y"),
+            Err(CommandError::InvalidArgument(String::from("1y")))
+        );
+        assert_eq!(parse("undotree").unwrap().kind, CommandKind::UndoTree);
+        assert_eq!(parse("term").unwrap().kind, CommandKind::Terminal);
+        assert_eq!(parse("au").unwrap().kind, CommandKind::Autocmd(None));
+        assert_eq!(
+            parse("autocmd BufWritePost *.rs make").unwrap().kind,
+            CommandKind::Autocmd(Some(Autocmd {
+                hook: Hook::BufWritePost,
+                pattern: String::from("*.rs"),
+                command: String::from("make"),
+            }))
+        );
+        assert_eq!(
+            parse("Upper all lines").unwrap().kind,
+            CommandKind::User {
+                name: String::from("Upper"),
+                argument: String::from("all lines"),
+            }
+        );
+        assert_eq!(
+            parse("map --buffer n <C-s> :w").unwrap().kind,
+            CommandKind::Map {
+                mode: KeymapMode::Normal,
+                keys: keymap::parse_keys("<C-s>"),
+                action: Action::Command(String::from("w")),
+                buffer: true,
+            }
+        );
+        assert_eq!(
+            parse("map normal Q"),
+            Err(CommandError::InvalidArgument(String::from(
+                "Expected `mode keys action`: normal Q"
+            )))
+        );
+        assert_eq!(parse("map"), Err(CommandError::ArgumentRequired));
+        assert_eq!(
+            parse("accept theirs").unwrap().kind,
+            CommandKind::Accept(Resolution::Theirs)
+        );
+        assert_eq!(
+            parse("accept mine"),
+            Err(CommandError::InvalidArgument(String::from("mine")))
+        );
+        assert_eq!(
+            parse("delete-file").unwrap().kind,
+            CommandKind::DeleteFile {
+                path: None,
+                force: false,
+            }
+        );
+        assert_eq!(
+            parse("delete-file --force notes.txt").unwrap().kind,
+            CommandKind::DeleteFile {
+                path: Some(PathBuf::from("notes.txt")),
+                force: true,
+            }
+        );
+        assert_eq!(
+            parse("delete-file --forced").unwrap().kind,
+            CommandKind::DeleteFile {
+                path: Some(PathBuf::from("--forced")),
+                force: false,
+            }
+        );
+        assert_eq!(
+            parse("sdiff a.txt b.txt").unwrap().kind,
+            CommandKind::Diff {
+                paths: vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")],
+                side_by_side: true,
+            }
+        );
+        assert_eq!(
+            parse("diff a b c"),
+            Err(CommandError::InvalidArgument(String::from("a b c")))
+        );
+        assert_eq!(parse("cancel").unwrap().kind, CommandKind::Cancel(None));
+        assert_eq!(
+            parse("cancel 3").unwrap().kind,
+            CommandKind::Cancel(Some(3))
+        );
+        assert_eq!(
+            parse("cancel x"),
+            Err(CommandError::InvalidArgument(String::from("x")))
+        );
+    }
+
+    #[test]
+    fn should_diff_the_clipboard_against_the_buffer_or_a_range() {
+        let command = parse("'<,'>sdiffclip").unwrap();
+        let document = Document::new(String::from("one\ntwo\nthree\n"), None);
+        let range = LineRange { start: 1, end: 2 };
+
+        assert_eq!(
+            command.kind,
+            CommandKind::DiffClipboard { side_by_side: true }
+        );
+        assert_eq!(parse("2clone"), Err(CommandError::RangeNotAllowed));
+        assert!(matches!(
+            describe_clipboard_diff(&document, String::from("two\nthree\n"), Some(range), false),
+            AppEvent::ShowMessage { .. }
+        ));
+        assert!(matches!(
+            describe_clipboard_diff(&document, String::from("two\n"), None, false),
+            AppEvent::ShowDiff { title, .. } if title == "clipboard → [No Name] (buffer)"
+        ));
+    }
+
+    #[test]
+    fn should_parse_layout_actions() {
+        assert_eq!(
+            parse("layout").unwrap().kind,
+            CommandKind::Layout(LayoutAction::List)
+        );
+        assert_eq!(
+            parse("layout review").unwrap().kind,
+            CommandKind::Layout(LayoutAction::Load(String::from("review")))
+        );
+        assert_eq!(
+            parse("layout save review").unwrap().kind,
+            CommandKind::Layout(LayoutAction::Save(String::from("review")))
+        );
+        assert_eq!(
+            parse("layout delete review").unwrap().kind,
+            CommandKind::Layout(LayoutAction::Delete(String::from("review")))
+        );
+        assert_eq!(parse("layout save"), Err(CommandError::ArgumentRequired));
+        assert_eq!(
+            parse("layout a b c"),
+            Err(CommandError::InvalidArgument(String::from("a b c")))
+        );
+    }
+
+    #[test]
+    fn should_parse_set_with_argument() {
+        assert_eq!(
+            parse("set nomouse").unwrap().kind,
+            CommandKind::Set(String::from("nomouse"))
+        );
+        assert_eq!(parse("set"), Err(CommandError::ArgumentRequired));
+    }
+
+    #[test]
+    fn should_parse_write_with_path() {
+        let command = parse("w  notes.txt ").unwrap();
+
+        assert_eq!(
+            command.kind,
+            CommandKind::Write {
+                path: Some(PathBuf::from("notes.txt")),
+                force: false
+            }
+        );
+        assert_eq!(
+            parse("10,20w! part.txt").unwrap(),
+            Command {
+                range: Some(RangeSpec::Span(Address::Number(10), Address::Number(20))),
+                kind: CommandKind::Write {
+                    path: Some(PathBuf::from("part.txt")),
+                    force: true
+                },
+            }
+        );
+        assert_eq!(
+            parse("saveas new.txt").unwrap().kind,
+            CommandKind::SaveAs(PathBuf::from("new.txt"))
+        );
+    }
+
+    #[test]
+    fn should_refuse_to_replace_other_files_unless_forced() {
+        let path = PathBuf::from("test_command_write_existing.txt");
+        std::fs::write(&path, "").unwrap();
+        let document = Document::new(String::new(), Some(PathBuf::from("own.txt")));
+
+        let refused = write_to(&document, path.clone(), None, false);
+        let forced = write_to(&document, path.clone(), None, true);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(refused, Err(CommandError::FileExists(path.clone())));
+        assert_eq!(
+            forced,
+            Ok(AppEvent::File(FileEvent::WriteTo {
+                path,
+                range: None,
+                set_path: false
+            }))
+        );
+    }
+
+    #[test]
+    fn should_parse_ranges() {
+        assert_eq!(parse("%").unwrap().range, Some(RangeSpec::Whole));
+        assert_eq!(
+            parse("12").unwrap().range,
+            Some(RangeSpec::Single(Address::Number(12)))
+        );
+        assert_eq!(
+            parse(".,$").unwrap().range,
+            Some(RangeSpec::Span(Address::Current, Address::Last))
+        );
+        assert_eq!(
+            parse("'<,'>").unwrap().range,
+            Some(RangeSpec::Span(Address::Mark('<'), Address::Mark('>')))
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_commands_and_bad_ranges() {
+        assert_eq!(
+            parse("frobnicate"),
+            Err(CommandError::UnknownCommand(String::from("frobnicate")))
+        );
+        assert!(matches!(parse("1,"), Err(CommandError::InvalidRange(_))));
+        assert_eq!(parse("%q"), Err(CommandError::RangeNotAllowed));
+    }
+
+    #[test]
+    fn should_resolve_ranges_against_buffer_and_marks() {
+        let marks = marks_with('<', 7);
+
+        let whole = RangeSpec::Whole.resolve(3, 9, &marks).unwrap();
+        let backwards = RangeSpec::Span(Address::Mark('<'), Address::Number(2))
+            .resolve(3, 9, &marks)
+            .unwrap();
+        let clamped = RangeSpec::Single(Address::Number(99))
+            .resolve(3, 9, &marks)
+            .unwrap();
+
+        assert_eq!(whole, LineRange { start: 0, end: 9 });
+        assert_eq!(backwards, LineRange { start: 1, end: 7 });
+        assert_eq!(clamped, LineRange { start: 9, end: 9 });
+    }
+
+    #[test]
+    fn should_fail_to_resolve_unset_mark() {
+        let result = RangeSpec::Single(Address::Mark('a')).resolve(0, 0, &Marks::new());
+
+        assert_eq!(result, Err(CommandError::UnknownMark('a')));
+    }
+}
@@ -0,0 +1,501 @@
+//! Insert-mode completion for the Zack text editor.
+//!
+//! This module provides the [`Completion`] struct, which holds the candidates shown in
+//! the completion popup, and [`CompletionEvent`], which drives it. Candidates come from
+//! several sources, enabled and ordered by the `complete` option:
+//!
+//! - **Synchronous providers**, like buffer words, file paths or a dictionary, asked when
+//!   completion is requested, so the popup opens immediately (see [`sources`]).
+//! - **Language server items**, requested through an [`LspEvent`] and merged in when the
+//!   response arrives on the event queue. Responses for outdated requests are dropped.
+//!
+//! Every time candidates arrive, they are ranked together (see [`rank`]).
+
+use crate::{
+    app::{
+        buffer::Buffer,
+        completion::sources::{CompletionContext, CompletionProvider},
+    },
+    event::{AppEvent, BufferEvent, LspEvent},
+    types::position::Position,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+pub mod rank;
+pub mod sources;
+
+/// Where a completion candidate came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompletionSource {
+    /// A word found in the current buffer.
+    Buffer,
+    /// An item returned by the language server.
+    Lsp,
+    /// A word of the system word list.
+    Dictionary,
+    /// A file or directory, after a path typed in the buffer.
+    Path,
+}
+
+impl CompletionSource {
+    /// Every source.
+    pub const ALL: [CompletionSource; 4] = [
+        CompletionSource::Path,
+        CompletionSource::Lsp,
+        CompletionSource::Buffer,
+        CompletionSource::Dictionary,
+    ];
+
+    /// Sources enabled by default, in order of priority.
+    pub const DEFAULT: [CompletionSource; 3] = [
+        CompletionSource::Path,
+        CompletionSource::Lsp,
+        CompletionSource::Buffer,
+    ];
+
+    /// Returns the name of the source in the `complete` option.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompletionSource::Buffer => "buffer",
+            CompletionSource::Lsp => "lsp",
+            CompletionSource::Dictionary => "dictionary",
+            CompletionSource::Path => "path",
+        }
+    }
+
+    /// Returns the source called `name` in the `complete` option.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.name() == name)
+    }
+
+    /// Returns the short tag shown next to candidates in the popup.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            CompletionSource::Buffer => "buf",
+            CompletionSource::Lsp => "lsp",
+            CompletionSource::Dictionary => "dict",
+            CompletionSource::Path => "path",
+        }
+    }
+}
+
+/// A single completion candidate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompletionItem {
+    /// Full text of the candidate, including the already typed prefix.
+    pub text: String,
+    pub source: CompletionSource,
+}
+
+/// State of the completion popup.
+#[derive(Debug)]
+pub struct Completion {
+    /// Whether the popup is currently shown.
+    pub active: bool,
+    /// Position of the first character of the word being completed.
+    pub start: Position,
+    /// Text typed between `start` and the cursor.
+    pub prefix: String,
+    /// Candidates matching `prefix`, best first.
+    pub items: Vec<CompletionItem>,
+    /// Index of the highlighted candidate.
+    pub selected: usize,
+    /// Identifier of the latest language server request.
+    request_id: u64,
+    /// Sources enabled for the latest request, from the most to the least important.
+    sources: Vec<CompletionSource>,
+    /// Sources answering synchronously.
+    providers: Vec<Box<dyn CompletionProvider>>,
+}
+
+/// Events that open, update or close the completion popup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompletionEvent {
+    /// Opens the popup for the word ending at `position`.
+    Request { position: Position },
+
+    /// Merges the items of a language server response into the popup.
+    LspItems { request_id: u64, items: Vec<String> },
+}
+
+impl Default for Completion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Completion {
+    /// Creates an inactive `Completion` with every provider.
+    pub fn new() -> Self {
+        Self::with_providers(sources::providers())
+    }
+
+    /// Creates an inactive `Completion` asking the given providers.
+    pub fn with_providers(providers: Vec<Box<dyn CompletionProvider>>) -> Self {
+        Self {
+            active: false,
+            start: Position::default(),
+            prefix: String::new(),
+            items: vec![],
+            selected: 0,
+            request_id: 0,
+            sources: CompletionSource::DEFAULT.to_vec(),
+            providers,
+        }
+    }
+
+    /// Handles a `CompletionEvent` and returns any follow-up `AppEvent`s.
+    ///
+    /// `sources` lists the enabled sources, from the most to the least important.
+    pub fn handle_event(
+        &mut self,
+        event: CompletionEvent,
+        buffer: &Buffer,
+        sources: &[CompletionSource],
+    ) -> Vec<AppEvent> {
+        let mut events = vec![];
+
+        match event {
+            CompletionEvent::Request { position } => {
+                self.sources = sources.to_vec();
+                events.extend(self.request(position, buffer));
+            }
+            CompletionEvent::LspItems { request_id, items } => {
+                if self.active && request_id == self.request_id {
+                    self.merge_lsp_items(items);
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Handles a key while the popup is open.
+    ///
+    /// Returns `None` when the key does not belong to the popup; in that case the popup
+    /// closes and the key should be handled by the current mode as usual.
+    /// - `Ctrl-N` / `Down`: Highlights the next candidate.
+    /// - `Ctrl-P` / `Up`: Highlights the previous candidate.
+    /// - `Enter` / `Tab`: Inserts the rest of the highlighted candidate.
+    /// - `Esc`: Closes the popup without inserting anything.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Vec<AppEvent>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Char('n') if ctrl => self.select_next(),
+            KeyCode::Down => self.select_next(),
+            KeyCode::Char('p') if ctrl => self.select_previous(),
+            KeyCode::Up => self.select_previous(),
+            KeyCode::Enter | KeyCode::Tab => return Some(self.accept()),
+            KeyCode::Esc => self.close(),
+            _ => {
+                self.close();
+                return None;
+            }
+        }
+
+        Some(vec![])
+    }
+
+    /// Closes the popup and discards its candidates.
+    pub fn close(&mut self) {
+        self.active = false;
+        self.items.clear();
+        self.selected = 0;
+    }
+
+    fn request(&mut self, position: Position, buffer: &Buffer) -> Vec<AppEvent> {
+        let line = buffer.as_rope().line(position.line).to_string();
+        let before_cursor: String = line.chars().take(position.col).collect();
+
+        // After a path, the name being typed is completed, whatever chars it has.
+        let typed_path = sources::typed_path(&before_cursor)
+            .filter(|_| self.sources.contains(&CompletionSource::Path));
+        let prefix_len = match &typed_path {
+            Some((_, name)) => name.chars().count(),
+            None => before_cursor
+                .chars()
+                .rev()
+                .take_while(|c| sources::is_word_char(*c))
+                .count(),
+        };
+
+        self.prefix = before_cursor
+            .chars()
+            .skip(position.col - prefix_len)
+            .collect();
+        self.start = Position::new(position.line, position.col - prefix_len);
+
+        let context = CompletionContext {
+            buffer,
+            position,
+            prefix: &self.prefix,
+            line_before: &before_cursor,
+        };
+        let items = self
+            .providers
+            .iter()
+            .filter(|provider| self.sources.contains(&provider.source()))
+            .flat_map(|provider| {
+                provider
+                    .complete(&context)
+                    .into_iter()
+                    .map(|text| CompletionItem {
+                        text,
+                        source: provider.source(),
+                    })
+            })
+            .collect();
+
+        self.items = rank::merge(items, &self.prefix, &self.sources);
+        self.selected = 0;
+        self.active = true;
+        self.request_id += 1;
+
+        if !self.sources.contains(&CompletionSource::Lsp) {
+            return vec![];
+        }
+
+        vec![AppEvent::Lsp(LspEvent::Completion {
+            request_id: self.request_id,
+            position,
+        })]
+    }
+
+    /// Ranks the language server items together with the candidates already shown.
+    fn merge_lsp_items(&mut self, items: Vec<String>) {
+        let lsp_items = items.into_iter().map(|text| CompletionItem {
+            text,
+            source: CompletionSource::Lsp,
+        });
+        let items = lsp_items.chain(self.items.drain(..)).collect();
+
+        self.items = rank::merge(items, &self.prefix, &self.sources);
+        self.selected = 0;
+    }
+
+    fn select_next(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + 1) % self.items.len();
+        }
+    }
+
+    fn select_previous(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+        }
+    }
+
+    fn accept(&mut self) -> Vec<AppEvent> {
+        let prefix_len = self.prefix.chars().count();
+        let end = Position::new(self.start.line, self.start.col + prefix_len);
+
+        let events = match self.items.get(self.selected) {
+            Some(item) if item.text.starts_with(&self.prefix) => {
+                vec![AppEvent::Buffer(BufferEvent::InsertText {
+                    text: item.text.chars().skip(prefix_len).collect(),
+                    position: end,
+                })]
+            }
+            // A fuzzy match replaces the typed prefix, deleting it backwards.
+            Some(item) => (1..=prefix_len)
+                .rev()
+                .map(|offset| {
+                    AppEvent::Buffer(BufferEvent::DeleteChar {
+                        position: Position::new(self.start.line, self.start.col + offset),
+                    })
+                })
+                .chain([AppEvent::Buffer(BufferEvent::InsertText {
+                    text: item.text.clone(),
+                    position: self.start,
+                })])
+                .collect(),
+            None => vec![],
+        };
+
+        self.close();
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn texts(completion: &Completion) -> Vec<&str> {
+        completion
+            .items
+            .iter()
+            .map(|item| item.text.as_str())
+            .collect()
+    }
+
+    fn requested(text: &str, position: Position) -> (Completion, Buffer) {
+        let buffer = Buffer::new(String::from(text));
+        let mut completion = Completion::new();
+        completion.handle_event(
+            CompletionEvent::Request { position },
+            &buffer,
+            &CompletionSource::DEFAULT,
+        );
+
+        (completion, buffer)
+    }
+
+    #[test]
+    fn should_collect_buffer_words_matching_prefix() {
+        let (completion, _) = requested("alpha beta alphabet al", Position::new(0, 22));
+
+        assert!(completion.active);
+        assert_eq!(completion.prefix, "al");
+        assert_eq!(completion.start, Position::new(0, 20));
+        assert_eq!(texts(&completion), vec!["alpha", "alphabet"]);
+    }
+
+    #[test]
+    fn should_request_lsp_items_with_increasing_ids() {
+        let buffer = Buffer::new(String::from("x"));
+        let mut completion = Completion::new();
+        let position = Position::new(0, 1);
+
+        completion.handle_event(CompletionEvent::Request { position }, &buffer, &[]);
+        let events = completion.handle_event(
+            CompletionEvent::Request { position },
+            &buffer,
+            &CompletionSource::DEFAULT,
+        );
+
+        assert_eq!(
+            events,
+            vec![AppEvent::Lsp(LspEvent::Completion {
+                request_id: 2,
+                position
+            })]
+        );
+    }
+
+    #[test]
+    fn should_merge_lsp_items_first_without_duplicates() {
+        let (mut completion, buffer) = requested("print prin", Position::new(0, 10));
+
+        completion.handle_event(
+            CompletionEvent::LspItems {
+                request_id: 1,
+                items: vec![
+                    String::from("println"),
+                    String::from("print"),
+                    String::from("other"),
+                ],
+            },
+            &buffer,
+            &CompletionSource::DEFAULT,
+        );
+
+        assert_eq!(texts(&completion), vec!["println", "print"]);
+        assert_eq!(completion.items[0].source, CompletionSource::Lsp);
+    }
+
+    #[test]
+    fn should_ignore_outdated_lsp_responses() {
+        let (mut completion, buffer) = requested("foo fo", Position::new(0, 6));
+
+        completion.handle_event(
+            CompletionEvent::LspItems {
+                request_id: 7,
+                items: vec![String::from("format")],
+            },
+            &buffer,
+            &CompletionSource::DEFAULT,
+        );
+
+        assert_eq!(texts(&completion), vec!["foo"]);
+    }
+
+    #[test]
+    fn should_cycle_selection_and_insert_rest_of_candidate() {
+        let (mut completion, _) = requested("abc abd ab", Position::new(0, 10));
+
+        completion.handle_key(key(KeyCode::Down));
+        let events = completion.handle_key(key(KeyCode::Enter));
+
+        assert!(!completion.active);
+        assert_eq!(
+            events,
+            Some(vec![AppEvent::Buffer(BufferEvent::InsertText {
+                text: String::from("d"),
+                position: Position::new(0, 10),
+            })])
+        );
+    }
+
+    #[test]
+    fn should_skip_disabled_sources() {
+        let buffer = Buffer::new(String::from("alpha al"));
+        let mut completion = Completion::new();
+
+        let events = completion.handle_event(
+            CompletionEvent::Request {
+                position: Position::new(0, 8),
+            },
+            &buffer,
+            &[CompletionSource::Lsp],
+        );
+
+        assert!(completion.items.is_empty());
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn should_replace_prefix_with_fuzzy_match() {
+        let (mut completion, _) = requested("file_name fn", Position::new(0, 12));
+
+        let events = completion.handle_key(key(KeyCode::Enter));
+
+        assert_eq!(
+            events,
+            Some(vec![
+                AppEvent::Buffer(BufferEvent::DeleteChar {
+                    position: Position::new(0, 12),
+                }),
+                AppEvent::Buffer(BufferEvent::DeleteChar {
+                    position: Position::new(0, 11),
+                }),
+                AppEvent::Buffer(BufferEvent::InsertText {
+                    text: String::from("file_name"),
+                    position: Position::new(0, 10),
+                }),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_complete_names_after_a_typed_path() {
+        let directory = PathBuf::from("test_completion_dir");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("my-notes.md"), "").unwrap();
+        let text = "see ./test_completion_dir/my-";
+
+        let (completion, _) = requested(text, Position::new(0, text.len()));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(completion.prefix, "my-");
+        assert_eq!(completion.start, Position::new(0, text.len() - 3));
+        assert_eq!(texts(&completion), vec!["my-notes.md"]);
+        assert_eq!(completion.items[0].source, CompletionSource::Path);
+    }
+
+    #[test]
+    fn should_close_and_pass_through_unrelated_keys() {
+        let (mut completion, _) = requested("abc ab", Position::new(0, 6));
+
+        let events = completion.handle_key(key(KeyCode::Char('x')));
+
+        assert_eq!(events, None);
+        assert!(!completion.active);
+    }
+}
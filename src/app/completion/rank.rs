@@ -0,0 +1,178 @@
+//! Ranking of completion candidates.
+//!
+//! Candidates match the typed prefix fuzzily: its chars must appear in the candidate in
+//! order, not necessarily next to each other, ignoring case unless the prefix has an
+//! uppercase letter. [`merge`] sorts the candidates of every source together: those
+//! starting with the prefix first, then by the priority of their source, then by how
+//! well they match. Within a source, the order it gave is kept.
+
+use crate::app::completion::{CompletionItem, CompletionSource};
+use std::collections::HashSet;
+
+/// Most candidates kept after ranking.
+pub const MAX_ITEMS: usize = 100;
+
+/// Score of each matched char.
+const MATCH_SCORE: i32 = 1;
+/// Bonus for a char matched right after the previous one.
+const CONSECUTIVE_BONUS: i32 = 4;
+/// Bonus for a char matched at the start of a word, like `b` in `foo_bar` or `fooBar`.
+const WORD_START_BONUS: i32 = 3;
+/// Penalty for each skipped char between two matched ones.
+const GAP_PENALTY: i32 = 1;
+
+/// Scores how well `candidate` matches `pattern`, or returns `None` if it does not.
+///
+/// Higher is better; the score only compares candidates for the same pattern.
+pub fn fuzzy_score(candidate: &str, pattern: &str) -> Option<i32> {
+    let ignore_case = !pattern.chars().any(char::is_uppercase);
+    let same = |a: char, b: char| {
+        if ignore_case {
+            a.to_lowercase().eq(b.to_lowercase())
+        } else {
+            a == b
+        }
+    };
+
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in pattern.chars() {
+        let index = (next..chars.len()).find(|&index| same(chars[index], wanted))?;
+
+        score += MATCH_SCORE;
+        match previous {
+            Some(previous) if previous + 1 == index => score += CONSECUTIVE_BONUS,
+            Some(previous) => score -= GAP_PENALTY * (index - previous - 1) as i32,
+            None => score -= GAP_PENALTY * index as i32,
+        }
+        if is_word_start(&chars, index) {
+            score += WORD_START_BONUS;
+        }
+
+        previous = Some(index);
+        next = index + 1;
+    }
+
+    Some(score)
+}
+
+/// Returns `true` if `candidate` fuzzily matches `pattern`.
+pub fn matches(candidate: &str, pattern: &str) -> bool {
+    fuzzy_score(candidate, pattern).is_some()
+}
+
+/// Ranks `items` for `prefix`, dropping those that do not match it, duplicates and
+/// the prefix itself. `priorities` lists sources from the most to the least important.
+pub fn merge(
+    items: Vec<CompletionItem>,
+    prefix: &str,
+    priorities: &[CompletionSource],
+) -> Vec<CompletionItem> {
+    let priority = |source: CompletionSource| {
+        priorities
+            .iter()
+            .position(|candidate| *candidate == source)
+            .unwrap_or(priorities.len())
+    };
+
+    let mut ranked: Vec<(bool, usize, i32, CompletionItem)> = items
+        .into_iter()
+        .filter(|item| item.text != prefix)
+        .filter_map(|item| {
+            let score = fuzzy_score(&item.text, prefix)?;
+            let starts_with = item.text.starts_with(prefix);
+
+            // Prefix matches keep the order of their source.
+            Some((
+                !starts_with,
+                priority(item.source),
+                if starts_with { 0 } else { -score },
+                item,
+            ))
+        })
+        .collect();
+
+    ranked.sort_by_key(|(fuzzy, priority, score, _)| (*fuzzy, *priority, *score));
+
+    let mut seen = HashSet::new();
+    ranked
+        .into_iter()
+        .map(|(_, _, _, item)| item)
+        .filter(|item| seen.insert(item.text.clone()))
+        .take(MAX_ITEMS)
+        .collect()
+}
+
+/// Returns `true` if the char at `index` starts a word of `chars`.
+fn is_word_start(chars: &[char], index: usize) -> bool {
+    let Some(&before) = index.checked_sub(1).and_then(|before| chars.get(before)) else {
+        return true;
+    };
+
+    !before.is_alphanumeric() || (before.is_lowercase() && chars[index].is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, source: CompletionSource) -> CompletionItem {
+        CompletionItem {
+            text: String::from(text),
+            source,
+        }
+    }
+
+    #[test]
+    fn should_match_chars_in_order_with_smart_case() {
+        assert!(matches("file_name", "fn"));
+        assert!(matches("FileName", "fn"));
+        assert!(!matches("file_name", "nf"));
+        assert!(!matches("filename", "fN"));
+    }
+
+    #[test]
+    fn should_prefer_contiguous_and_word_start_matches() {
+        let contiguous = fuzzy_score("format", "for").unwrap();
+        let scattered = fuzzy_score("fxoxr", "for").unwrap();
+        let word_start = fuzzy_score("get_name", "gn").unwrap();
+        let inner = fuzzy_score("getting", "gn").unwrap();
+
+        assert!(contiguous > scattered);
+        assert!(word_start > inner);
+    }
+
+    #[test]
+    fn should_rank_prefix_matches_then_priority_then_score() {
+        let items = vec![
+            item("xprint", CompletionSource::Buffer),
+            item("print_line", CompletionSource::Buffer),
+            item("printf", CompletionSource::Lsp),
+            item("pr_int", CompletionSource::Lsp),
+            item("print_line", CompletionSource::Lsp),
+        ];
+
+        let ranked = merge(
+            items,
+            "print",
+            &[CompletionSource::Lsp, CompletionSource::Buffer],
+        );
+
+        let texts: Vec<(&str, CompletionSource)> = ranked
+            .iter()
+            .map(|item| (item.text.as_str(), item.source))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("printf", CompletionSource::Lsp),
+                ("print_line", CompletionSource::Lsp),
+                ("pr_int", CompletionSource::Lsp),
+                ("xprint", CompletionSource::Buffer),
+            ]
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Synchronous completion sources.
+//!
+//! A [`CompletionProvider`] returns candidates for the word being completed as soon as
+//! completion is requested. Adding a source means implementing the trait and listing it
+//! in [`providers`]; ranking and the popup work the same for every source. The language
+//! server is the exception: it answers asynchronously, so [`Completion`] requests its
+//! items separately and merges them in when they arrive.
+//!
+//! [`Completion`]: crate::app::completion::Completion
+
+use crate::{
+    app::{
+        buffer::Buffer,
+        completion::{CompletionSource, rank},
+    },
+    types::position::Position,
+};
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Word list used by the dictionary source.
+const DICTIONARY_PATH: &str = "/usr/share/dict/words";
+
+/// What a provider knows about the word being completed.
+#[derive(Debug)]
+pub struct CompletionContext<'a> {
+    pub buffer: &'a Buffer,
+    /// Position of the cursor.
+    pub position: Position,
+    /// Text typed before the cursor, from the start of the word.
+    pub prefix: &'a str,
+    /// Text of the cursor line before the cursor.
+    pub line_before: &'a str,
+}
+
+/// A source of completion candidates.
+pub trait CompletionProvider: fmt::Debug {
+    /// Returns which source the candidates come from.
+    fn source(&self) -> CompletionSource;
+
+    /// Returns candidates matching `context.prefix`; they are ranked afterwards.
+    fn complete(&self, context: &CompletionContext) -> Vec<String>;
+}
+
+/// Completes words found in the current buffer.
+#[derive(Debug)]
+pub struct BufferWords;
+
+/// Completes the names of files and directories after a typed path.
+#[derive(Debug)]
+pub struct Paths;
+
+/// Completes words of a word list, one per line, read once on first use.
+#[derive(Debug)]
+pub struct Dictionary {
+    path: PathBuf,
+    words: OnceLock<Vec<String>>,
+}
+
+/// Returns every synchronous provider.
+pub fn providers() -> Vec<Box<dyn CompletionProvider>> {
+    vec![
+        Box::new(Paths),
+        Box::new(BufferWords),
+        Box::new(Dictionary::new(Path::new(DICTIONARY_PATH))),
+    ]
+}
+
+impl CompletionProvider for BufferWords {
+    fn source(&self) -> CompletionSource {
+        CompletionSource::Buffer
+    }
+
+    /// Collects the distinct words of the buffer matching the prefix, sorted
+    /// alphabetically.
+    fn complete(&self, context: &CompletionContext) -> Vec<String> {
+        let text = context.buffer.as_rope().to_string();
+        let mut words: Vec<String> = text
+            .split(|c: char| !is_word_char(c))
+            .filter(|word| !word.is_empty() && rank::matches(word, context.prefix))
+            .map(String::from)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        words.sort();
+        words
+    }
+}
+
+impl CompletionProvider for Paths {
+    fn source(&self) -> CompletionSource {
+        CompletionSource::Path
+    }
+
+    /// Lists the directory of the typed path, sorted, with a `/` after directories.
+    /// Hidden entries are only listed once their leading `.` is typed.
+    fn complete(&self, context: &CompletionContext) -> Vec<String> {
+        match typed_path(context.line_before) {
+            Some((directory, name)) => directory_entries(&directory, name.starts_with('.')),
+            None => vec![],
+        }
+    }
+}
+
+impl Dictionary {
+    /// Creates a dictionary reading its words from `path`.
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            words: OnceLock::new(),
+        }
+    }
+}
+
+impl CompletionProvider for Dictionary {
+    fn source(&self) -> CompletionSource {
+        CompletionSource::Dictionary
+    }
+
+    /// Returns the words starting with the prefix; matching a whole word list fuzzily
+    /// would mostly bring noise. A missing word list completes nothing.
+    fn complete(&self, context: &CompletionContext) -> Vec<String> {
+        if context.prefix.is_empty() {
+            return vec![];
+        }
+
+        let words = self.words.get_or_init(|| {
+            std::fs::read_to_string(&self.path)
+                .map(|text| text.lines().map(String::from).collect())
+                .unwrap_or_default()
+        });
+
+        words
+            .iter()
+            .filter(|word| word.starts_with(context.prefix))
+            .take(rank::MAX_ITEMS)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Returns the directory to list and the start of the name being typed, when the text
+/// before the cursor ends with something that looks like a path: starting with `/`,
+/// `./`, `../` or `~/`, after a space, a quote or a bracket.
+pub fn typed_path(line_before: &str) -> Option<(PathBuf, &str)> {
+    let start = line_before
+        .rfind(|c: char| c.is_whitespace() || "\"'`()[]{}<>=,".contains(c))
+        .map_or(0, |index| index + 1);
+    let token = &line_before[start..];
+
+    if !["/", "./", "../", "~/"]
+        .iter()
+        .any(|marker| token.starts_with(marker))
+    {
+        return None;
+    }
+
+    let slash = token.rfind('/')?;
+    let (directory, name) = (&token[..=slash], &token[slash + 1..]);
+    let directory = match directory.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var_os("HOME")?).join(rest),
+        None => PathBuf::from(directory),
+    };
+
+    Some((directory, name))
+}
+
+/// Returns the names in `directory`, sorted, with a `/` after directories. Hidden
+/// entries are left out unless `hidden` is set; an unreadable directory has none.
+pub fn directory_entries(directory: &Path, hidden: bool) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return vec![];
+    };
+
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let mut name = entry.file_name().into_string().ok()?;
+            if name.starts_with('.') && !hidden {
+                return None;
+            }
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                name.push('/');
+            }
+
+            Some(name)
+        })
+        .collect();
+
+    names.sort();
+    names
+}
+
+/// Returns `true` for characters that can be part of a completed word.
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn context<'a>(buffer: &'a Buffer, prefix: &'a str) -> CompletionContext<'a> {
+        CompletionContext {
+            buffer,
+            position: Position::new(0, 0),
+            prefix,
+            line_before: prefix,
+        }
+    }
+
+    #[test]
+    fn should_complete_buffer_words_fuzzily() {
+        let buffer = Buffer::new(String::from("file_name fine other"));
+
+        let words = BufferWords.complete(&context(&buffer, "fn"));
+
+        assert_eq!(words, vec!["file_name", "fine"]);
+    }
+
+    #[test]
+    fn should_recognize_typed_paths() {
+        assert_eq!(
+            typed_path("cat ./src/ma"),
+            Some((PathBuf::from("./src/"), "ma"))
+        );
+        assert_eq!(typed_path("x=\"/etc/"), Some((PathBuf::from("/etc/"), "")));
+        assert_eq!(typed_path("src/main"), None);
+        assert_eq!(
+            typed_path("open(../lib/a"),
+            Some((PathBuf::from("../lib/"), "a"))
+        );
+    }
+
+    #[test]
+    fn should_list_directory_entries_hiding_dotfiles() {
+        let directory = PathBuf::from("test_paths_dir");
+        fs::create_dir_all(directory.join("sub")).unwrap();
+        fs::write(directory.join("file.txt"), "").unwrap();
+        fs::write(directory.join(".hidden"), "").unwrap();
+        let buffer = Buffer::new(String::new());
+        let line = "./test_paths_dir/";
+        let hidden_line = "./test_paths_dir/.";
+
+        let names = Paths.complete(&CompletionContext {
+            line_before: line,
+            ..context(&buffer, "")
+        });
+        let hidden = Paths.complete(&CompletionContext {
+            line_before: hidden_line,
+            ..context(&buffer, ".")
+        });
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(names, vec!["file.txt", "sub/"]);
+        assert_eq!(hidden, vec![".hidden", "file.txt", "sub/"]);
+    }
+
+    #[test]
+    fn should_complete_dictionary_words_by_prefix() {
+        let path = PathBuf::from("test_dictionary.txt");
+        fs::write(&path, "apple\napricot\nbanana\n").unwrap();
+        let buffer = Buffer::new(String::new());
+        let dictionary = Dictionary::new(&path);
+
+        let words = dictionary.complete(&context(&buffer, "ap"));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(words, vec!["apple", "apricot"]);
+    }
+}
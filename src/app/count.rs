@@ -0,0 +1,87 @@
+//! Line, word and character counts for the Zack text editor.
+//!
+//! This module provides [`Counts`], shown by `:count` and, with `:set wordcount`, in the
+//! status line. Lines and chars come from the statistics the rope keeps; words are
+//! counted by walking its chunks, only when the counts are asked for.
+
+use ropey::RopeSlice;
+use std::fmt;
+
+/// Lines, words and chars of a text. A word is a run of chars other than whitespace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+}
+
+impl Counts {
+    /// Counts the lines, words and chars of `text`; a final line break does not start
+    /// another line.
+    pub fn of(text: RopeSlice) -> Self {
+        let chars = text.len_chars();
+        let trailing_break = chars > 0 && text.char(chars - 1) == '\n';
+        let lines = match chars {
+            0 => 0,
+            _ => text.len_lines() - usize::from(trailing_break),
+        };
+
+        let mut words = 0;
+        let mut in_word = false;
+        for chunk in text.chunks() {
+            for c in chunk.chars() {
+                let blank = c.is_whitespace();
+                words += usize::from(!blank && !in_word);
+                in_word = !blank;
+            }
+        }
+
+        Self {
+            lines,
+            words,
+            chars,
+        }
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+
+        write!(
+            f,
+            "{} line{}, {} word{}, {} char{}",
+            self.lines,
+            plural(self.lines),
+            self.words,
+            plural(self.words),
+            self.chars,
+            plural(self.chars)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ropey::Rope;
+
+    #[test]
+    fn should_count_lines_words_and_chars() {
+        let rope = Rope::from_str("one two\n  three\n\nfour\n");
+
+        assert_eq!(
+            Counts::of(rope.slice(..)),
+            Counts {
+                lines: 4,
+                words: 4,
+                chars: 22,
+            }
+        );
+        assert_eq!(
+            Counts::of(rope.slice(4..10)).to_string(),
+            "2 lines, 1 word, 6 chars"
+        );
+        assert_eq!(Counts::of(rope.slice(0..0)), Counts::default());
+    }
+}
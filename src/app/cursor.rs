@@ -4,6 +4,9 @@
 //! within the text buffer and methods to move or set the cursor position based
 //! on various user actions. It also defines [`CursorEvent`], which represents
 //! all possible cursor-related actions.
+//!
+//! As in Vim, the cursor rests on the last character of a line in normal and visual
+//! mode, and may only go right after it while inserting, to type there.
 
 use crate::app::{buffer::Buffer, modes::EditorMode};
 use crate::event::AppEvent;
@@ -12,7 +15,7 @@ use crossterm::{
     QueueableCommand,
     cursor::{self, SetCursorStyle},
 };
-use ratatui::{Frame, layout::Rect};
+use ratatui::Frame;
 use std::io::{Write, stdout};
 
 /// Represents the text cursor, tracking its current line and column position.
@@ -20,6 +23,9 @@ use std::io::{Write, stdout};
 pub struct Cursor {
     /// Current position of the cursor in the buffer.
     pub position: Position,
+    /// Whether the cursor may stand right after the last character of a line, as while
+    /// inserting; otherwise it stays on the last character.
+    pub past_end: bool,
 }
 
 /// Events that can trigger cursor movement or repositioning.
@@ -47,9 +53,24 @@ impl Cursor {
     pub fn new() -> Self {
         Self {
             position: Position::new(0, 0),
+            past_end: false,
+        }
+    }
+
+    /// Returns the last column the cursor may be on in its line.
+    pub fn max_col(&self, buffer: &Buffer) -> usize {
+        let max_col = buffer.max_visible_col(&self.position);
+        match self.past_end {
+            true => max_col,
+            false => max_col.saturating_sub(1),
         }
     }
 
+    /// Moves the cursor back to the last column it may be on, when it is past it.
+    pub fn clamp(&mut self, buffer: &Buffer) {
+        self.position.col = self.position.col.min(self.max_col(buffer));
+    }
+
     /// Handles a `CursorEvent`, possibly adjusting the cursor's position.
     /// Returns any follow-up `AppEvent`s.
     pub fn handle_event(&mut self, event: CursorEvent, buffer: &Buffer) -> Vec<AppEvent> {
@@ -74,14 +95,18 @@ impl Cursor {
         events
     }
 
-    /// Renders the cursor at the correct screen position with appropriate style.
-    pub fn render_cursor(&self, frame: &mut Frame, current_mode: EditorMode) {
-        let cursor_position = self.calculate_cursor_position(frame.area());
+    /// Renders the cursor at `screen_position`.
+    pub fn render_cursor(&self, frame: &mut Frame, screen_position: ratatui::layout::Position) {
+        frame.set_cursor_position(screen_position);
+    }
 
+    /// Gives the terminal cursor the style of the current mode: a bar while inserting,
+    /// a block otherwise. It is a setting of the terminal rather than part of a frame, so
+    /// it is written once the frame is drawn.
+    pub fn apply_style(current_mode: EditorMode) -> std::io::Result<()> {
         let mut stdout = stdout();
-        frame.set_cursor_position(cursor_position);
-        stdout.queue(self.set_cursor_style(current_mode)).unwrap();
-        stdout.flush().unwrap();
+        stdout.queue(Self::style(current_mode))?;
+        stdout.flush()
     }
 
     fn move_left(&mut self) -> Vec<AppEvent> {
@@ -93,7 +118,7 @@ impl Cursor {
     }
 
     fn move_right(&mut self, buffer: &Buffer) -> Vec<AppEvent> {
-        let max_col = buffer.max_visible_col(&self.position);
+        let max_col = self.max_col(buffer);
 
         if self.position.col < max_col {
             self.position.col += 1;
@@ -105,7 +130,7 @@ impl Cursor {
     fn move_up(&mut self, buffer: &Buffer) -> Vec<AppEvent> {
         if self.position.line > 0 {
             self.position.line -= 1;
-            self.position.col = buffer.clamp_col_position(&self.position);
+            self.clamp(buffer);
         }
 
         vec![]
@@ -116,7 +141,7 @@ impl Cursor {
 
         if self.position.line + 1 < total_lines {
             self.position.line += 1;
-            self.position.col = buffer.clamp_col_position(&self.position);
+            self.clamp(buffer);
         }
 
         vec![]
@@ -129,7 +154,7 @@ impl Cursor {
     }
 
     fn move_to_line_end(&mut self, buffer: &Buffer) -> Vec<AppEvent> {
-        self.position.col = buffer.max_visible_col(&self.position);
+        self.position.col = self.max_col(buffer);
 
         vec![]
     }
@@ -145,48 +170,24 @@ impl Cursor {
         let total_lines = buffer.len_lines().saturating_sub(1);
 
         self.position.line = line.min(total_lines);
-        self.position.col = buffer.clamp_col_position(&self.position);
+        self.clamp(buffer);
 
         vec![]
     }
 
     fn set_col_position(&mut self, col: usize, buffer: &Buffer) -> Vec<AppEvent> {
-        let max_col = buffer.max_visible_col(&self.position);
-        self.position.col = col.min(max_col);
+        self.position.col = col.min(self.max_col(buffer));
 
         vec![]
     }
 
-    fn set_cursor_style(&self, current_mode: EditorMode) -> SetCursorStyle {
+    fn style(current_mode: EditorMode) -> SetCursorStyle {
         match current_mode {
             EditorMode::Insert { .. } => cursor::SetCursorStyle::SteadyBar,
+            EditorMode::OperatorPending { .. } => cursor::SetCursorStyle::SteadyUnderScore,
             _ => cursor::SetCursorStyle::SteadyBlock,
         }
     }
-
-    /// Calculates the actual terminal coordinates where the cursor should appear.
-    fn calculate_cursor_position(&self, area: Rect) -> ratatui::layout::Position {
-        let text_area = Rect {
-            x: area.x + 1,
-            y: area.y + 1,
-            width: area.width.saturating_sub(2),
-            height: area.height.saturating_sub(2),
-        };
-
-        let clamped_line = self
-            .position
-            .line
-            .min(text_area.height.saturating_sub(1) as usize);
-        let clamped_col = self
-            .position
-            .col
-            .min(text_area.width.saturating_sub(1) as usize);
-
-        ratatui::layout::Position {
-            x: text_area.x + clamped_col as u16,
-            y: text_area.y + clamped_line as u16,
-        }
-    }
 }
 
 #[cfg(test)]
@@ -210,6 +211,10 @@ mod tests {
         cursor.handle_event(CursorEvent::MoveRight, &buffer);
         assert_eq!(cursor.position.col, 2);
 
+        cursor.handle_event(CursorEvent::MoveRight, &buffer);
+        assert_eq!(cursor.position.col, 2, "Should stay on the last character");
+
+        cursor.past_end = true;
         cursor.handle_event(CursorEvent::MoveRight, &buffer);
         assert_eq!(cursor.position.col, 3);
 
@@ -237,6 +242,7 @@ mod tests {
     fn should_move_down_and_up_across_lines_and_clamp_column() {
         let buffer = buffer_with_lines(&["abc", "d", "efg"]);
         let mut cursor = Cursor::new();
+        cursor.past_end = true;
         cursor.position.col = 2;
 
         cursor.handle_event(CursorEvent::MoveDown, &buffer);
@@ -265,6 +271,10 @@ mod tests {
         cursor.handle_event(CursorEvent::MoveToLineStart, &buffer);
         assert_eq!(cursor.position.col, 0);
 
+        cursor.handle_event(CursorEvent::MoveToLineEnd, &buffer);
+        assert_eq!(cursor.position.col, 3);
+
+        cursor.past_end = true;
         cursor.handle_event(CursorEvent::MoveToLineEnd, &buffer);
         assert_eq!(cursor.position.col, 4);
     }
@@ -276,6 +286,19 @@ mod tests {
 
         cursor.handle_event(CursorEvent::SetPosition { line: 5, col: 99 }, &buffer);
         assert_eq!(cursor.position.line, 1);
-        assert_eq!(cursor.position.col, 4);
+        assert_eq!(cursor.position.col, 3);
+    }
+
+    #[test]
+    fn should_stay_on_empty_lines_in_normal_mode() {
+        let buffer = buffer_with_lines(&["", "ab"]);
+        let mut cursor = Cursor::new();
+
+        cursor.handle_event(CursorEvent::MoveToLineEnd, &buffer);
+        assert_eq!(cursor.position.col, 0);
+
+        cursor.handle_event(CursorEvent::SetPosition { line: 1, col: 2 }, &buffer);
+        cursor.handle_event(CursorEvent::MoveUp, &buffer);
+        assert_eq!(cursor.position, Position::new(0, 0));
     }
 }
@@ -0,0 +1,190 @@
+//! Diagnostics and gutter signs for the Zack text editor.
+//!
+//! This module defines [`Diagnostic`], a message attached to a range of the buffer
+//! (usually published by the language server), and [`Sign`], a marker drawn in the
+//! editor gutter. Each sign carries the text shown when hovering it and the
+//! [`SignAction`] run when it is clicked.
+
+use crate::{app::git::LineChange, types::position::Position};
+
+/// How serious a diagnostic is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// A message attached to a range of the buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// First character covered by the diagnostic.
+    pub start: Position,
+    /// Position just after the last character covered by the diagnostic.
+    pub end: Position,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// The kind of a gutter sign, which decides how it is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignKind {
+    Diagnostic(Severity),
+    /// A line changed since `HEAD`.
+    Git(LineChange),
+}
+
+/// What happens when a sign is clicked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignAction {
+    /// Shows every diagnostic of the line in a details popup.
+    ShowDiagnostics { line: usize },
+    /// Shows the `HEAD` lines replaced by the hunk starting at `line`.
+    ShowHunk { line: usize },
+}
+
+/// A marker drawn in the gutter next to a line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sign {
+    pub line: usize,
+    pub kind: SignKind,
+    /// Text shown when hovering the sign.
+    pub tooltip: String,
+    /// Action run when the sign is clicked.
+    pub action: SignAction,
+}
+
+impl Severity {
+    /// Converts an LSP `DiagnosticSeverity` number, defaulting to `Error` when unknown.
+    pub fn from_lsp(severity: u64) -> Self {
+        match severity {
+            2 => Severity::Warning,
+            3 => Severity::Information,
+            4 => Severity::Hint,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Returns the symbol drawn in the gutter.
+    pub fn symbol(&self) -> char {
+        match self {
+            Severity::Error => 'E',
+            Severity::Warning => 'W',
+            Severity::Information => 'I',
+            Severity::Hint => 'H',
+        }
+    }
+
+    /// Returns a lowercase label used in messages.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Information => "info",
+            Severity::Hint => "hint",
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Returns `true` if the diagnostic covers `position`.
+    ///
+    /// Empty ranges cover the single character they start at.
+    pub fn contains(&self, position: Position) -> bool {
+        if self.start == self.end {
+            return position == self.start;
+        }
+
+        self.start <= position && position < self.end
+    }
+
+    /// Formats the diagnostic as `severity: message`.
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.severity.label(), self.message)
+    }
+}
+
+impl SignKind {
+    /// Returns the symbol drawn in the gutter.
+    pub fn symbol(&self) -> char {
+        match self {
+            SignKind::Diagnostic(severity) => severity.symbol(),
+            SignKind::Git(change) => change.symbol(),
+        }
+    }
+}
+
+/// Builds one sign per line with diagnostics, using the most severe one for its symbol.
+pub fn diagnostic_signs(diagnostics: &[Diagnostic]) -> Vec<Sign> {
+    let mut lines: Vec<usize> = diagnostics.iter().map(|d| d.start.line).collect();
+    lines.sort_unstable();
+    lines.dedup();
+
+    lines
+        .into_iter()
+        .map(|line| {
+            let on_line: Vec<&Diagnostic> = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.start.line == line)
+                .collect();
+            let severity = on_line
+                .iter()
+                .map(|diagnostic| diagnostic.severity)
+                .min()
+                .unwrap_or(Severity::Error);
+
+            Sign {
+                line,
+                kind: SignKind::Diagnostic(severity),
+                tooltip: on_line
+                    .iter()
+                    .map(|diagnostic| diagnostic.describe())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                action: SignAction::ShowDiagnostics { line },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(line: usize, severity: Severity, message: &str) -> Diagnostic {
+        Diagnostic {
+            start: Position::new(line, 2),
+            end: Position::new(line, 5),
+            severity,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn should_contain_positions_inside_range_only() {
+        let diagnostic = diagnostic(1, Severity::Error, "x");
+
+        assert!(diagnostic.contains(Position::new(1, 2)));
+        assert!(diagnostic.contains(Position::new(1, 4)));
+        assert!(!diagnostic.contains(Position::new(1, 5)));
+        assert!(!diagnostic.contains(Position::new(0, 3)));
+    }
+
+    #[test]
+    fn should_build_one_sign_per_line_with_most_severe_symbol() {
+        let diagnostics = vec![
+            diagnostic(3, Severity::Warning, "unused"),
+            diagnostic(3, Severity::Error, "mismatched types"),
+            diagnostic(0, Severity::Hint, "consider"),
+        ];
+
+        let signs = diagnostic_signs(&diagnostics);
+
+        assert_eq!(signs.len(), 2);
+        assert_eq!(signs[0].line, 0);
+        assert_eq!(signs[1].kind, SignKind::Diagnostic(Severity::Error));
+        assert_eq!(signs[1].tooltip, "warning: unused\nerror: mismatched types");
+        assert_eq!(signs[1].action, SignAction::ShowDiagnostics { line: 3 });
+    }
+}
@@ -0,0 +1,314 @@
+//! Line diff module for the Zack text editor.
+//!
+//! This module compares two texts line by line, using a longest common subsequence of
+//! their lines, and formats the result for reading: as a unified diff with a few lines
+//! of context ([`unified`]), or as two columns ([`side_by_side`]). Each output line is a
+//! [`DiffLine`] tagged with its [`DiffKind`], so the UI can color it.
+
+/// Largest number of line pairs compared exactly; bigger changes become a single block.
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+/// Unchanged lines kept around each change in a unified diff.
+const CONTEXT_LINES: usize = 3;
+
+/// Widest column of a side-by-side diff.
+const MAX_COLUMN_WIDTH: usize = 60;
+
+/// How two texts relate at one line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffOp {
+    /// `old[0]` and `new[1]` are the same line.
+    Equal(usize, usize),
+    /// `old[0]` was removed.
+    Delete(usize),
+    /// `new[0]` was added.
+    Insert(usize),
+}
+
+/// The role of a line of formatted diff output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+    /// File names and hunk headers.
+    Header,
+    Context,
+    Added,
+    Removed,
+    /// A side-by-side row whose two sides differ.
+    Changed,
+}
+
+/// A line of formatted diff output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+impl DiffLine {
+    fn new(kind: DiffKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+}
+
+/// Compares `old` with `new` and returns the operations turning one into the other, in
+/// order.
+pub fn ops(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let pairs = if old_middle.len() * new_middle.len() <= MAX_DIFF_CELLS {
+        common_lines(old_middle, new_middle)
+    } else {
+        vec![]
+    };
+
+    let mut ops: Vec<DiffOp> = (0..prefix).map(|i| DiffOp::Equal(i, i)).collect();
+    let (mut old_index, mut new_index) = (0, 0);
+
+    for (old_match, new_match) in pairs
+        .into_iter()
+        .chain([(old_middle.len(), new_middle.len())])
+    {
+        ops.extend((old_index..old_match).map(|i| DiffOp::Delete(prefix + i)));
+        ops.extend((new_index..new_match).map(|j| DiffOp::Insert(prefix + j)));
+
+        if old_match < old_middle.len() {
+            ops.push(DiffOp::Equal(prefix + old_match, prefix + new_match));
+        }
+
+        old_index = old_match + 1;
+        new_index = new_match + 1;
+    }
+
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    ops.extend((0..suffix).map(|i| DiffOp::Equal(old_end + i, new_end + i)));
+
+    ops
+}
+
+/// Formats the differences between `old` and `new` as a unified diff, or returns an
+/// empty vector when they have the same lines.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = ops(&old_lines, &new_lines);
+
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Equal(..)))
+        .map(|(index, _)| index)
+        .collect();
+    if changed.is_empty() {
+        return vec![];
+    }
+
+    let mut lines = vec![
+        DiffLine::new(DiffKind::Header, format!("--- {old_name}")),
+        DiffLine::new(DiffKind::Header, format!("+++ {new_name}")),
+    ];
+
+    // Group changes whose context would overlap into the same hunk.
+    let mut groups: Vec<(usize, usize)> = vec![];
+    for index in changed {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(ops.len());
+
+        match groups.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => groups.push((start, end)),
+        }
+    }
+
+    for (start, end) in groups {
+        let hunk = &ops[start..end];
+        let old_start = hunk.iter().find_map(|op| match op {
+            DiffOp::Equal(i, _) | DiffOp::Delete(i) => Some(*i),
+            DiffOp::Insert(_) => None,
+        });
+        let new_start = hunk.iter().find_map(|op| match op {
+            DiffOp::Equal(_, j) | DiffOp::Insert(j) => Some(*j),
+            DiffOp::Delete(_) => None,
+        });
+        let old_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Insert(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Delete(_)))
+            .count();
+
+        lines.push(DiffLine::new(
+            DiffKind::Header,
+            format!(
+                "@@ -{},{old_count} +{},{new_count} @@",
+                old_start.map_or(0, |i| i + 1),
+                new_start.map_or(0, |j| j + 1)
+            ),
+        ));
+
+        lines.extend(hunk.iter().map(|op| match *op {
+            DiffOp::Equal(i, _) => DiffLine::new(DiffKind::Context, format!(" {}", old_lines[i])),
+            DiffOp::Delete(i) => DiffLine::new(DiffKind::Removed, format!("-{}", old_lines[i])),
+            DiffOp::Insert(j) => DiffLine::new(DiffKind::Added, format!("+{}", new_lines[j])),
+        }));
+    }
+
+    lines
+}
+
+/// Formats `old` and `new` as two columns, marking rows that differ with `|`, lines
+/// only in `old` with `<` and lines only in `new` with `>`.
+pub fn side_by_side(old: &str, new: &str) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let width = old_lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_COLUMN_WIDTH);
+
+    let row = |left: &str, marker: char, right: &str| {
+        let left: String = left.chars().take(width).collect();
+        format!("{left:<width$} {marker} {right}")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![];
+    let mut removed: Vec<&str> = vec![];
+    let mut added: Vec<&str> = vec![];
+
+    // Pairs the pending removed and added lines of a change block, one row each.
+    let flush = |removed: &mut Vec<&str>, added: &mut Vec<&str>, lines: &mut Vec<DiffLine>| {
+        for index in 0..removed.len().max(added.len()) {
+            lines.push(match (removed.get(index), added.get(index)) {
+                (Some(old), Some(new)) => DiffLine::new(DiffKind::Changed, row(old, '|', new)),
+                (Some(old), None) => DiffLine::new(DiffKind::Removed, row(old, '<', "")),
+                (None, Some(new)) => DiffLine::new(DiffKind::Added, row("", '>', new)),
+                (None, None) => unreachable!("index is below one of the lengths"),
+            });
+        }
+        removed.clear();
+        added.clear();
+    };
+
+    for op in ops(&old_lines, &new_lines) {
+        match op {
+            DiffOp::Delete(i) => removed.push(old_lines[i]),
+            DiffOp::Insert(j) => added.push(new_lines[j]),
+            DiffOp::Equal(i, _) => {
+                flush(&mut removed, &mut added, &mut lines);
+                lines.push(DiffLine::new(
+                    DiffKind::Context,
+                    row(old_lines[i], ' ', old_lines[i]),
+                ));
+            }
+        }
+    }
+    flush(&mut removed, &mut added, &mut lines);
+
+    lines
+}
+
+/// Returns the index pairs of a longest common subsequence of `old` and `new`.
+fn common_lines(old: &[&str], new: &[&str]) -> Vec<(usize, usize)> {
+    let width = new.len() + 1;
+    // lengths[i * width + j]: length of the LCS of old[i..] and new[j..].
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[DiffLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn should_format_unified_diff_with_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+
+        let lines = unified(old, new, "a.txt", "b.txt");
+
+        assert_eq!(
+            texts(&lines),
+            vec![
+                "--- a.txt",
+                "+++ b.txt",
+                "@@ -2,7 +2,7 @@",
+                " 2",
+                " 3",
+                " 4",
+                "-5",
+                "+five",
+                " 6",
+                " 7",
+                " 8",
+            ]
+        );
+        assert_eq!(lines[6].kind, DiffKind::Removed);
+        assert_eq!(lines[7].kind, DiffKind::Added);
+    }
+
+    #[test]
+    fn should_return_nothing_for_same_lines() {
+        assert!(unified("a\nb\n", "a\nb\n", "a", "b").is_empty());
+    }
+
+    #[test]
+    fn should_pair_changed_lines_side_by_side() {
+        let lines = side_by_side("a\nb\nc\n", "a\nB\nc\nd\n");
+
+        assert_eq!(texts(&lines), vec!["a   a", "b | B", "c   c", "  > d"]);
+        assert_eq!(lines[1].kind, DiffKind::Changed);
+        assert_eq!(lines[3].kind, DiffKind::Added);
+    }
+}
@@ -0,0 +1,223 @@
+//! Headless driver for end-to-end tests of the Zack text editor.
+//!
+//! [`Driver`] runs an [`App`] without a terminal: its event handler has no background
+//! thread, frames are drawn on a [`TestBackend`], and every key or event fed to it is
+//! handled synchronously, along with the events it queues, before the call returns.
+//! Tests can then check the text, the cursor and the screen, as in:
+//!
+//! ```
+//! use zack::app::driver::Driver;
+//!
+//! let mut driver = Driver::new("world");
+//! driver.keys("ihello <Esc>");
+//! assert_eq!(driver.text(), "hello world");
+//! ```
+
+use crate::{
+    app::{App, keymap::parse_keys},
+    event::{AppEvent, Event, EventHandler, recording::Recorded},
+    types::position::Position,
+};
+use crossterm::event::{Event as CrosstermEvent, KeyEvent};
+use ratatui::{Terminal, backend::TestBackend, layout::Rect};
+
+/// Width of the screen of a driver made with [`Driver::new`].
+const DEFAULT_WIDTH: u16 = 80;
+
+/// Height of the screen of a driver made with [`Driver::new`].
+const DEFAULT_HEIGHT: u16 = 24;
+
+/// Most events handled by one [`Driver::settle`], in case events keep queueing others.
+const MAX_SETTLE_EVENTS: usize = 10_000;
+
+/// An application driven synchronously, drawn on an in-memory screen.
+pub struct Driver {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Driver {
+    /// Creates a driver editing `text`, without a file, on an 80x24 screen.
+    pub fn new(text: &str) -> Self {
+        Self::with_size(text, DEFAULT_WIDTH, DEFAULT_HEIGHT)
+    }
+
+    /// Creates a driver editing `text`, without a file, on a `width` x `height` screen.
+    pub fn with_size(text: &str, width: u16, height: u16) -> Self {
+        let mut app = App::with_event_handler(text.to_string(), None, EventHandler::headless());
+        app.area = Some(Rect::new(0, 0, width, height));
+        let terminal =
+            Terminal::new(TestBackend::new(width, height)).expect("test backend never fails");
+
+        let mut driver = Self { app, terminal };
+        driver.settle();
+        driver
+    }
+
+    /// Presses `key` and handles everything it leads to.
+    pub fn key(&mut self, key: KeyEvent) -> &mut Self {
+        self.app
+            .process_event(Event::Crossterm(CrosstermEvent::Key(key)));
+        self.settle()
+    }
+
+    /// Types `keys`, where `<Name>` stands for a special key: `<Esc>`, `<Enter>`,
+    /// `<Tab>`, `<BS>`, `<Del>`, `<Up>`, `<Down>`, `<Left>`, `<Right>`, `<F1>` to
+    /// `<F12>`, `<lt>` for `<` itself, and `<C-x>` for `x` with `Ctrl`.
+    pub fn keys(&mut self, keys: &str) -> &mut Self {
+        for key in parse_keys(keys) {
+            self.key(key);
+        }
+
+        self
+    }
+
+    /// Sends `event` and handles everything it leads to.
+    pub fn send(&mut self, event: AppEvent) -> &mut Self {
+        self.app.process_event(Event::App(event));
+        self.settle()
+    }
+
+    /// Feeds the events of a recording, handling everything each one leads to before
+    /// the next, without waiting between them.
+    pub fn replay(&mut self, recording: &[Recorded]) -> &mut Self {
+        for recorded in recording {
+            self.app
+                .process_event(Event::Replayed(recorded.event.clone()));
+            self.settle();
+        }
+
+        self
+    }
+
+    /// Resizes the screen to `width` x `height`, as the terminal would, and handles
+    /// everything it leads to.
+    pub fn resize(&mut self, width: u16, height: u16) -> &mut Self {
+        self.terminal.backend_mut().resize(width, height);
+        self.app
+            .process_event(Event::Crossterm(CrosstermEvent::Resize(width, height)));
+        self.settle()
+    }
+
+    /// Runs the periodic work of a tick.
+    pub fn tick(&mut self) -> &mut Self {
+        self.app.process_event(Event::Tick);
+        self.settle()
+    }
+
+    /// Handles the queued events until there are none left.
+    pub fn settle(&mut self) -> &mut Self {
+        for _ in 0..MAX_SETTLE_EVENTS {
+            match self.app.event_handler.try_next() {
+                Some(event) => self.app.process_event(event),
+                None => break,
+            }
+        }
+
+        self
+    }
+
+    /// Returns the text of the active buffer.
+    pub fn text(&self) -> String {
+        self.app.buffers.active().buffer.as_rope().to_string()
+    }
+
+    /// Returns the cursor position in the active buffer.
+    pub fn cursor(&self) -> Position {
+        self.app.buffers.active().cursor.position
+    }
+
+    /// Draws a frame and returns the screen, one line per row, without trailing spaces.
+    pub fn screen(&mut self) -> String {
+        let frame = self
+            .terminal
+            .draw(|frame| {
+                self.app.update_viewport(frame.area());
+                self.app.render(frame);
+            })
+            .expect("test backend never fails");
+        let buffer = frame.buffer;
+
+        (0..buffer.area.height)
+            .map(|row| {
+                let line: String = (0..buffer.area.width)
+                    .map(|col| buffer[(col, row)].symbol())
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns where the last frame drawn put the terminal cursor, as `(column, row)`.
+    pub fn screen_cursor(&mut self) -> (u16, u16) {
+        let position = self
+            .terminal
+            .get_cursor_position()
+            .expect("test backend never fails");
+        (position.x, position.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::recording::{self, Recorder},
+        ui::components::FocusableComponent,
+    };
+
+    #[test]
+    fn should_type_undo_and_redo_end_to_end() {
+        let mut driver = Driver::new("hello");
+
+        driver.keys("ihey <Esc>");
+        assert_eq!(driver.text(), "hey hello");
+        assert_eq!(driver.cursor(), Position::new(0, 3));
+
+        driver.keys("u");
+        assert_eq!(driver.text(), "hello");
+
+        driver.keys("<C-r>");
+        assert_eq!(driver.text(), "hey hello");
+    }
+
+    #[test]
+    fn should_replay_a_recorded_session() {
+        let path = std::env::temp_dir().join("test_driver_recording.json");
+        let mut recorded = Driver::new("one\ntwo");
+        recorded.app.recorder = Some(Recorder::create(&path).unwrap());
+        recorded.keys("jinew <Esc>:1<Enter>");
+
+        let recording = recording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut replayed = Driver::new("one\ntwo");
+        replayed.replay(&recording);
+
+        assert_eq!(replayed.text(), "one\nnew two");
+        assert_eq!(replayed.text(), recorded.text());
+        assert_eq!(replayed.cursor(), recorded.cursor());
+    }
+
+    #[test]
+    fn should_run_commands_and_draw_the_screen() {
+        let mut driver = Driver::with_size("one\ntwo\nthree", 60, 10);
+
+        driver.keys(":3<Enter>");
+        assert_eq!(driver.cursor(), Position::new(2, 0));
+
+        let screen = driver.screen();
+        assert!(screen.contains("three"));
+        assert!(screen.contains("normal"));
+
+        driver.keys("<F1>");
+        assert_eq!(driver.app.focus, FocusableComponent::MessagePane);
+        assert!(driver.screen().contains("Everywhere"));
+
+        driver.keys("q:set maxline=3<Enter>").tick();
+        assert!(driver.screen().contains("large file mode"));
+
+        driver.send(AppEvent::Quit);
+        assert!(!driver.app.running);
+    }
+}
@@ -0,0 +1,436 @@
+//! Per-file settings for the Zack text editor, from EditorConfig files and modelines.
+//!
+//! This module provides [`FileSettings`], the settings of one file that override the
+//! global options and those of its file type for its buffer: the indentation `Tab` inserts, the charset the file is
+//! read and written in, and whether it ends with a line break.
+//!
+//! # Overview
+//!
+//! - `.editorconfig` files are looked for in the directory of the file and each one
+//!   above it, up to the first marked `root = true`. Sections whose glob matches the
+//!   file apply, the closer file and the later section winning. Supported keys are
+//!   `indent_style`, `indent_size`, `tab_width`, `charset` (`utf-8`, `utf-8-bom` or
+//!   `latin1`) and `insert_final_newline`.
+//! - Vim-style modelines in the first or last [`MODELINES`] lines, like
+//!   `# vim: set et ts=2:`, are applied over them. They set `et` / `noet`, `ts`, `sw`
+//!   and `eol` / `noeol`; the charset is only taken from EditorConfig, since the file
+//!   has to be decoded before its modelines can be read.
+
+use crate::app::{filetype::FiletypeSettings, options::Options};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Name of the EditorConfig files.
+const EDITORCONFIG_FILE: &str = ".editorconfig";
+
+/// Lines read for modelines at the start and at the end of a file.
+pub const MODELINES: usize = 5;
+
+/// Charset a file is read and written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charset {
+    Utf8,
+    /// UTF-8 starting with a byte order mark.
+    Utf8Bom,
+    Latin1,
+}
+
+/// Settings of a file overriding the global options; `None` keeps the global one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileSettings {
+    /// Insert spaces, rather than a tab, on `Tab`.
+    pub expandtab: Option<bool>,
+    /// Columns of one level of indentation.
+    pub tabstop: Option<usize>,
+    pub charset: Option<Charset>,
+    /// End the file with a line break when saving, or remove the one it ends with.
+    pub final_newline: Option<bool>,
+}
+
+impl Charset {
+    /// Returns the charset called `name` in EditorConfig files.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "utf-8" => Some(Charset::Utf8),
+            "utf-8-bom" => Some(Charset::Utf8Bom),
+            "latin1" => Some(Charset::Latin1),
+            _ => None,
+        }
+    }
+
+    /// Decodes the content of a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `std::io::Error` if the bytes are not valid UTF-8 for UTF-8 charsets.
+    pub fn decode(self, bytes: Vec<u8>) -> io::Result<String> {
+        let bytes = match self {
+            Charset::Latin1 => return Ok(bytes.into_iter().map(char::from).collect()),
+            Charset::Utf8Bom if bytes.starts_with(b"\xEF\xBB\xBF") => bytes[3..].to_vec(),
+            Charset::Utf8 | Charset::Utf8Bom => bytes,
+        };
+
+        String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Encodes text to be written to a file; chars Latin-1 cannot hold become `?`.
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            Charset::Utf8 => text.as_bytes().to_vec(),
+            Charset::Utf8Bom => [b"\xEF\xBB\xBF", text.as_bytes()].concat(),
+            Charset::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(c).unwrap_or(b'?'))
+                .collect(),
+        }
+    }
+}
+
+impl FileSettings {
+    /// Returns the text `Tab` inserts: a tab, or `tabstop` spaces with `expandtab`, each
+    /// setting falling back on the one of the file type, then on the global `options`.
+    pub fn tab(&self, filetype: Option<&FiletypeSettings>, options: &Options) -> String {
+        let expandtab = self
+            .expandtab
+            .or_else(|| filetype?.expandtab)
+            .unwrap_or(options.expandtab);
+
+        match expandtab {
+            true => " ".repeat(self.tabstop(filetype, options)),
+            false => String::from("\t"),
+        }
+    }
+
+    /// Returns the columns between tab stops, which tabs are drawn up to, falling back on
+    /// the setting of the file type, then on the global `options`.
+    pub fn tabstop(&self, filetype: Option<&FiletypeSettings>, options: &Options) -> usize {
+        self.tabstop
+            .or_else(|| filetype?.tabstop)
+            .unwrap_or(options.tabstop)
+    }
+
+    /// Returns the settings of the file at `path` whose text is `text`: those of its
+    /// EditorConfig files, overridden by its modelines.
+    pub fn load(path: &Path, text: &str) -> Self {
+        let mut settings = Self::from_editorconfig(path);
+        settings.apply_modelines(text);
+
+        settings
+    }
+
+    /// Returns the settings the EditorConfig files above `path` give it.
+    pub fn from_editorconfig(path: &Path) -> Self {
+        let mut settings = Self::default();
+        let Ok(path) = std::path::absolute(path) else {
+            return settings;
+        };
+
+        for (directory, content) in editorconfig_files(&path) {
+            let Ok(relative) = path.strip_prefix(&directory) else {
+                continue;
+            };
+            settings.apply_editorconfig(&content, &relative.to_string_lossy());
+        }
+
+        settings
+    }
+
+    /// Applies the sections of an EditorConfig file matching `relative`, the path of the
+    /// file from the directory of the EditorConfig file.
+    fn apply_editorconfig(&mut self, content: &str, relative: &str) {
+        let mut matches = false;
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                matches = section_matches(section, relative);
+            } else if matches && let Some((key, value)) = line.split_once('=') {
+                self.apply_property(&key.trim().to_lowercase(), &value.trim().to_lowercase());
+            }
+        }
+    }
+
+    /// Applies one EditorConfig property; `unset` goes back to the global option.
+    fn apply_property(&mut self, key: &str, value: &str) {
+        let unset = value == "unset";
+
+        match key {
+            "indent_style" => {
+                self.expandtab = match value {
+                    "space" => Some(true),
+                    "tab" => Some(false),
+                    _ => None,
+                }
+            }
+            "indent_size" | "tab_width" if unset => self.tabstop = None,
+            "indent_size" | "tab_width" => {
+                if let Ok(width @ 1..) = value.parse() {
+                    self.tabstop = Some(width);
+                }
+            }
+            "charset" => {
+                self.charset = Charset::from_name(value);
+                if self.charset.is_none() && !unset {
+                    tracing::warn!("Unsupported charset in {EDITORCONFIG_FILE}: {value}");
+                }
+            }
+            "insert_final_newline" => {
+                self.final_newline = match value {
+                    "true" => Some(true),
+                    "false" => Some(false),
+                    _ => None,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies the modelines in the first and last [`MODELINES`] lines of `text`.
+    pub fn apply_modelines(&mut self, text: &str) {
+        let lines: Vec<&str> = text.lines().collect();
+        let tail = lines.len().saturating_sub(MODELINES).max(MODELINES);
+
+        for line in lines.iter().take(MODELINES).chain(lines.iter().skip(tail)) {
+            if let Some(options) = modeline_options(line) {
+                options
+                    .iter()
+                    .for_each(|option| self.apply_vim_option(option));
+            }
+        }
+    }
+
+    /// Applies one option of a modeline, like `et` or `ts=4`; others are ignored.
+    fn apply_vim_option(&mut self, option: &str) {
+        if let Some((name, value)) = option.split_once('=') {
+            if let ("ts" | "tabstop" | "sw" | "shiftwidth", Ok(width @ 1..)) = (name, value.parse())
+            {
+                self.tabstop = Some(width);
+            }
+            return;
+        }
+
+        match option {
+            "et" | "expandtab" => self.expandtab = Some(true),
+            "noet" | "noexpandtab" => self.expandtab = Some(false),
+            "eol" | "endofline" => self.final_newline = Some(true),
+            "noeol" | "noendofline" => self.final_newline = Some(false),
+            _ => {}
+        }
+    }
+}
+
+/// Reads the file at `path` in the charset its EditorConfig files give it, UTF-8 by
+/// default.
+///
+/// # Errors
+///
+/// Returns an `std::io::Error` if the file cannot be read or is not valid in its charset.
+pub fn read(path: &Path) -> io::Result<String> {
+    let charset = FileSettings::from_editorconfig(path).charset;
+
+    charset
+        .unwrap_or(Charset::Utf8)
+        .decode(std::fs::read(path)?)
+}
+
+/// Returns the directory and content of the EditorConfig files applying to `path`, from
+/// the root one down to the closest.
+fn editorconfig_files(path: &Path) -> Vec<(PathBuf, String)> {
+    let mut files = vec![];
+
+    for directory in path.ancestors().skip(1) {
+        let Ok(content) = std::fs::read_to_string(directory.join(EDITORCONFIG_FILE)) else {
+            continue;
+        };
+        let root = content.lines().any(|line| {
+            line.split_once('=').is_some_and(|(key, value)| {
+                key.trim().eq_ignore_ascii_case("root") && value.trim().eq_ignore_ascii_case("true")
+            })
+        });
+
+        files.push((directory.to_path_buf(), content));
+        if root {
+            break;
+        }
+    }
+
+    files.reverse();
+    files
+}
+
+/// Returns whether the glob of a section matches `relative`. A glob without `/` matches
+/// the file name in any directory.
+pub fn section_matches(section: &str, relative: &str) -> bool {
+    let relative = relative.replace('\\', "/");
+
+    match section.strip_prefix('/') {
+        Some(glob) => glob_matches(glob, &relative),
+        None if section.contains('/') => glob_matches(section, &relative),
+        None => {
+            glob_matches(&format!("**/{section}"), &relative) || glob_matches(section, &relative)
+        }
+    }
+}
+
+/// Matches `text` against an EditorConfig glob: `*` within a directory, `**` across
+/// them, `?`, `[chars]` and `{alternatives,...}`.
+fn glob_matches(glob: &str, text: &str) -> bool {
+    if let Some(open) = glob.find('{')
+        && let Some(length) = glob[open..].find('}')
+    {
+        let (before, after) = (&glob[..open], &glob[open + length + 1..]);
+
+        return glob[open + 1..open + length]
+            .split(',')
+            .any(|alternative| glob_matches(&format!("{before}{alternative}{after}"), text));
+    }
+
+    matches_from(glob.as_bytes(), text.as_bytes())
+}
+
+/// Matches `text` against a glob without alternatives, byte by byte.
+fn matches_from(glob: &[u8], text: &[u8]) -> bool {
+    match glob {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            matches_from(rest, text)
+                || (0..text.len()).any(|i| text[i] == b'/' && matches_from(rest, &text[i + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| matches_from(rest, &text[i..])),
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| matches_from(rest, &text[i..])),
+        [b'?', rest @ ..] => {
+            text.first().is_some_and(|&c| c != b'/') && matches_from(rest, &text[1..])
+        }
+        [b'[', rest @ ..] if rest.contains(&b']') => {
+            let end = rest.iter().position(|&c| c == b']').unwrap_or_default();
+            let (negated, class) = match &rest[..end] {
+                [b'!', class @ ..] => (true, class),
+                class => (false, class),
+            };
+
+            text.first()
+                .is_some_and(|c| class.contains(c) != negated && *c != b'/')
+                && matches_from(&rest[end + 1..], &text[1..])
+        }
+        [c, rest @ ..] => text.first() == Some(c) && matches_from(rest, &text[1..]),
+    }
+}
+
+/// Returns the options of a vim modeline, `vim: set et ts=4:` or `vi: noet ts=8`,
+/// when `line` holds one.
+fn modeline_options(line: &str) -> Option<Vec<&str>> {
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| {
+        line.match_indices(marker)
+            .find(|(at, _)| {
+                line[..*at]
+                    .chars()
+                    .next_back()
+                    .is_none_or(char::is_whitespace)
+            })
+            .map(|(at, marker)| at + marker.len())
+    })?;
+    let rest = line[start..].trim_start();
+
+    let options = match rest
+        .strip_prefix("set ")
+        .or_else(|| rest.strip_prefix("se "))
+    {
+        Some(set) => set
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect(),
+        None => rest
+            .split([' ', '\t', ':'])
+            .filter(|o| !o.is_empty())
+            .collect(),
+    };
+
+    Some(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_match_editorconfig_globs() {
+        assert!(section_matches("*", "src/main.rs"));
+        assert!(section_matches("*.{rs,toml}", "Cargo.toml"));
+        assert!(section_matches("*.md", "docs/guide.md"));
+        assert!(section_matches("/src/*.rs", "src/main.rs"));
+        assert!(!section_matches("/src/*.rs", "src/app/mod.rs"));
+        assert!(section_matches("src/**.rs", "src/app/mod.rs"));
+        assert!(section_matches("Makefile", "sub/Makefile"));
+        assert!(section_matches("[Mm]akefile", "makefile"));
+        assert!(!section_matches("*.py", "main.rs"));
+    }
+
+    #[test]
+    fn should_apply_matching_sections_in_order() {
+        let mut settings = FileSettings::default();
+
+        settings.apply_editorconfig(
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\n\
+             insert_final_newline = true\n\n[Makefile]\nindent_style = tab\n\n\
+             [*.rs]\ncharset = utf-8-bom\nindent_size = 2\n",
+            "src/main.rs",
+        );
+
+        assert_eq!(
+            settings,
+            FileSettings {
+                expandtab: Some(true),
+                tabstop: Some(2),
+                charset: Some(Charset::Utf8Bom),
+                final_newline: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn should_read_vim_modelines() {
+        assert_eq!(
+            modeline_options("# vim: set et ts=2 sw=2: trailing"),
+            Some(vec!["et", "ts=2", "sw=2"])
+        );
+        assert_eq!(
+            modeline_options("/* vi:noet:ts=8 */"),
+            Some(vec!["noet", "ts=8", "*/"])
+        );
+        assert_eq!(modeline_options("let x = navim: et"), None);
+
+        let mut settings = FileSettings {
+            expandtab: Some(false),
+            ..FileSettings::default()
+        };
+        settings.apply_modelines("fn main() {}\n\n// vim: set et ts=3 noeol:\n");
+
+        assert_eq!(settings.expandtab, Some(true));
+        assert_eq!(settings.tabstop, Some(3));
+        assert_eq!(settings.final_newline, Some(false));
+    }
+
+    #[test]
+    fn should_decode_and_encode_charsets() {
+        assert_eq!(
+            Charset::Latin1.decode(vec![b'c', 0xE9]).unwrap(),
+            String::from("cé")
+        );
+        assert_eq!(Charset::Latin1.encode("cé→"), vec![b'c', 0xE9, b'?']);
+        assert_eq!(
+            Charset::Utf8Bom.decode(b"\xEF\xBB\xBFhi".to_vec()).unwrap(),
+            String::from("hi")
+        );
+        assert_eq!(Charset::Utf8Bom.encode("hi"), b"\xEF\xBB\xBFhi".to_vec());
+        assert!(Charset::Utf8.decode(vec![0xE9]).is_err());
+    }
+}
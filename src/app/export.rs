@@ -0,0 +1,242 @@
+//! Styled export for the Zack text editor.
+//!
+//! `:export file` renders the buffer, or the lines of a range, with the colors it is
+//! shown with: the colors of a file viewed with its ANSI escape codes, over the theme's
+//! background for the buffer. The output is HTML for `.html` / `.htm` files, ready to
+//! paste into an email or a blog post, and text with ANSI escape codes for any other
+//! file, to `cat` in a terminal.
+
+use crate::app::{ansi, buffer_manager::Document, command::LineRange};
+use ratatui::style::{Color, Modifier, Style};
+use std::path::Path;
+
+/// What an export is written as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A `<pre>` block with a `<span>` for each styled run.
+    Html,
+    /// Text with SGR escape codes.
+    Ansi,
+}
+
+impl ExportFormat {
+    /// Returns the format for the extension of `path`: HTML for `.html` and `.htm`,
+    /// ANSI otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension)
+                if extension.eq_ignore_ascii_case("html")
+                    || extension.eq_ignore_ascii_case("htm") =>
+            {
+                ExportFormat::Html
+            }
+            _ => ExportFormat::Ansi,
+        }
+    }
+}
+
+/// Renders the lines of `range` of `document` in `format`, over `background`.
+pub fn render(
+    document: &Document,
+    range: LineRange,
+    format: ExportFormat,
+    background: Color,
+) -> String {
+    let lines = (range.start..=range.end).map(|line| styled_runs(document, line));
+
+    match format {
+        ExportFormat::Html => {
+            let mut html = match css_color(background) {
+                Some(color) => format!("<pre style=\"background-color:{color};padding:8px\">"),
+                None => String::from("<pre>"),
+            };
+
+            for runs in lines {
+                for (style, text) in runs {
+                    let text = escape_html(&text);
+                    match css_style(style) {
+                        Some(css) => html.push_str(&format!("<span style=\"{css}\">{text}</span>")),
+                        None => html.push_str(&text),
+                    }
+                }
+                html.push('\n');
+            }
+
+            html.push_str("</pre>\n");
+            html
+        }
+        ExportFormat::Ansi => {
+            let mut text = String::new();
+
+            for runs in lines {
+                for (style, run) in runs {
+                    match ansi::sgr(style).as_str() {
+                        "" => text.push_str(&run),
+                        sgr => text.push_str(&format!("{sgr}{run}\x1b[0m")),
+                    }
+                }
+                text.push('\n');
+            }
+
+            text
+        }
+    }
+}
+
+/// Splits `line` into runs of chars sharing a style, without its line break.
+fn styled_runs(document: &Document, line: usize) -> Vec<(Style, String)> {
+    let text = document.buffer.lines_text(line, line);
+    let colors = document.line_colors(line);
+    let mut runs: Vec<(Style, String)> = vec![];
+
+    for (col, c) in text.trim_end_matches(['\n', '\r']).chars().enumerate() {
+        let style = colors
+            .iter()
+            .find(|span| span.start <= col && col < span.end)
+            .map_or(Style::default(), |span| span.style);
+
+        match runs.last_mut() {
+            Some((last, run)) if *last == style => run.push(c),
+            _ => runs.push((style, c.to_string())),
+        }
+    }
+
+    runs
+}
+
+/// Returns the inline CSS of `style`, or `None` for the default style. Reversed colors
+/// are left out, since the colors they swap with are unknown.
+fn css_style(style: Style) -> Option<String> {
+    let mut properties = vec![];
+
+    if let Some(color) = style.fg.and_then(css_color) {
+        properties.push(format!("color:{color}"));
+    }
+    if let Some(color) = style.bg.and_then(css_color) {
+        properties.push(format!("background-color:{color}"));
+    }
+    for (modifier, property) in [
+        (Modifier::BOLD, "font-weight:bold"),
+        (Modifier::DIM, "opacity:0.6"),
+        (Modifier::ITALIC, "font-style:italic"),
+        (Modifier::UNDERLINED, "text-decoration:underline"),
+    ] {
+        if style.add_modifier.contains(modifier) {
+            properties.push(property.to_string());
+        }
+    }
+
+    (!properties.is_empty()).then(|| properties.join(";"))
+}
+
+/// Returns `color` as a CSS hex color, using the xterm palette for indexed colors, or
+/// `None` for the default color.
+fn css_color(color: Color) -> Option<String> {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+    let level = |value: u8| if value == 0 { 0 } else { 55 + 40 * value };
+
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        color => match ansi::named_index(color)? {
+            index @ 0..=15 => BASIC[index as usize],
+            index @ 16..=231 => {
+                let cube = index - 16;
+                (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+            }
+            index => {
+                let gray = 8 + 10 * (index - 232);
+                (gray, gray, gray)
+            }
+        },
+    };
+
+    Some(format!("#{r:02x}{g:02x}{b:02x}"))
+}
+
+/// Escapes the characters HTML gives a meaning to.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn colored_document() -> Document {
+        let mut document = Document::new(String::from("a <b>\nplain\n"), None);
+        document.colors = vec![ansi::ColorSpan {
+            line: 0,
+            start: 2,
+            end: 5,
+            style: Style::default().fg(Color::Indexed(1)),
+        }];
+        document
+    }
+
+    #[test]
+    fn should_pick_the_format_from_the_extension() {
+        assert_eq!(
+            ExportFormat::from_path(&PathBuf::from("out.HTML")),
+            ExportFormat::Html
+        );
+        assert_eq!(
+            ExportFormat::from_path(&PathBuf::from("out.ans")),
+            ExportFormat::Ansi
+        );
+    }
+
+    #[test]
+    fn should_render_colored_runs_as_html_spans() {
+        let html = render(
+            &colored_document(),
+            LineRange { start: 0, end: 1 },
+            ExportFormat::Html,
+            Color::Rgb(28, 28, 40),
+        );
+
+        assert_eq!(
+            html,
+            "<pre style=\"background-color:#1c1c28;padding:8px\">\
+             a <span style=\"color:#cd0000\">&lt;b&gt;</span>\nplain\n</pre>\n"
+        );
+    }
+
+    #[test]
+    fn should_render_colored_runs_as_ansi_text() {
+        let text = render(
+            &colored_document(),
+            LineRange { start: 0, end: 0 },
+            ExportFormat::Ansi,
+            Color::Reset,
+        );
+
+        assert_eq!(text, "a \x1b[38;5;1m<b>\x1b[0m\n");
+    }
+
+    #[test]
+    fn should_convert_palette_colors_to_css() {
+        assert_eq!(css_color(Color::Indexed(196)).unwrap(), "#ff0000");
+        assert_eq!(css_color(Color::Indexed(244)).unwrap(), "#808080");
+        assert_eq!(css_color(Color::Reset), None);
+    }
+}
@@ -7,23 +7,85 @@
 //!
 //! # Overview
 //!
-//! - `File` holds an optional path to the current file being edited.
+//! - `File` holds an optional path to the current file being edited, made absolute so it
+//!   names the same file after `:cd`, and shown relative to the working directory.
 //! - Handles events to save the buffer content to disk.
+//! - Remembers the buffer revision last written, to tell whether there are unsaved changes.
+//! - Remembers the size and modification time of the file as last read or written, to
+//!   show them in the status line and notice when something else modifies the file.
 //! - If no path is set, requests focus change to the filename prompt UI.
+//! - Notes when the file cannot be written, to show it as read-only; when saving is
+//!   refused for lack of permission, the filename prompt opens to save it elsewhere.
+//! - Saving to a new path asks before replacing an existing file or creating missing
+//!   directories, and refuses paths that cannot be files.
+//! - Writes in the charset of the file, ending it with a line break or not, as its
+//!   EditorConfig files and modelines ask.
+//! - Deleting a file moves it to the trash of the operating system, so it can be
+//!   restored, unless it is deleted permanently.
 //!
 //! # Usage
 //!
 //! Create a `File` instance with an optional path, call `handle_event`
 //! with save events to persist buffer content.
 
-use crate::{app::buffer::Buffer, event::AppEvent, ui::components::FocusableComponent};
-use std::path::PathBuf;
+use crate::{
+    app::{
+        buffer::Buffer,
+        command::LineRange,
+        editorconfig::{Charset, FileSettings},
+    },
+    event::AppEvent,
+    ui::components::prompt::PromptKind,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often the file on disk is compared with what was last read or written.
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Size and modification time of a file on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiskInfo {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl DiskInfo {
+    /// Reads the size and modification time of the file at `path`, if it exists.
+    pub fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+
+        Some(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
 
 #[derive(Debug)]
 /// Represents the currently loaded file in the editor.
 pub struct File {
     /// Optional path to the file on disk.
     pub path: Option<PathBuf>,
+    /// Buffer revision that was last written to (or read from) disk.
+    saved_revision: u64,
+    /// The file on disk as last read or written, when it exists.
+    pub disk: Option<DiskInfo>,
+    /// Set when the file on disk no longer matches `disk`, i.e. it was modified elsewhere.
+    pub changed_on_disk: bool,
+    /// When the file on disk was last compared with `disk`.
+    last_check: Option<Instant>,
+    /// Settings from its EditorConfig files and modelines, used when writing it.
+    pub settings: FileSettings,
+    /// Add a missing final line break when writing, unless `settings` say otherwise.
+    pub fixeol: bool,
+    /// Set when the file on disk, as last read or written, does not end with a line
+    /// break.
+    pub noeol: bool,
+    /// Set when the file exists but cannot be written, as last read or written.
+    pub read_only: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -31,8 +93,20 @@ pub struct File {
 pub enum FileEvent {
     /// Save the file to the existing path.
     Save,
-    /// Save the file to a new specified path.
+    /// Save the file to a new specified path, asking before replacing another file or
+    /// creating missing directories.
     SaveAs(PathBuf),
+    /// Write the buffer, or the lines of `range`, to `path` without asking, creating
+    /// missing directories. With `set_path`, `path` becomes the file of the buffer;
+    /// otherwise it only gets a copy.
+    WriteTo {
+        path: PathBuf,
+        range: Option<LineRange>,
+        set_path: bool,
+    },
+    /// Delete the file or directory at `path`, moving it to the trash unless
+    /// `permanently` is set.
+    Delete { path: PathBuf, permanently: bool },
 }
 
 impl Default for File {
@@ -45,7 +119,90 @@ impl Default for File {
 impl File {
     /// Creates a new `File` instance with an optional path.
     pub fn new(path: Option<PathBuf>) -> Self {
-        Self { path }
+        let mut file = Self {
+            path: path.map(absolute),
+            saved_revision: 0,
+            disk: None,
+            changed_on_disk: false,
+            last_check: None,
+            settings: FileSettings::default(),
+            fixeol: true,
+            noeol: false,
+            read_only: false,
+        };
+        file.refresh_disk_info();
+
+        file
+    }
+
+    /// Returns `true` when `buffer` has been edited since it was last saved.
+    pub fn is_modified(&self, buffer: &Buffer) -> bool {
+        buffer.revision() != self.saved_revision
+    }
+
+    /// Records the current content of `buffer` as matching the file on disk.
+    pub fn mark_saved(&mut self, buffer: &Buffer) {
+        self.saved_revision = buffer.revision();
+        self.noeol = self.path.is_some() && lacks_final_newline(&buffer.as_rope().to_string());
+    }
+
+    /// Records the file on disk as the version the buffer is based on, e.g. after
+    /// reading or writing it.
+    pub fn refresh_disk_info(&mut self) {
+        self.disk = self.read_disk_info();
+        self.changed_on_disk = false;
+        self.read_only = self.disk.is_some() && self.path.as_deref().is_some_and(is_read_only);
+    }
+
+    /// Compares the file on disk with the recorded version, at most once per
+    /// [`DISK_CHECK_INTERVAL`], and flags it as changed when it differs.
+    pub fn check_disk(&mut self) {
+        if self
+            .last_check
+            .is_some_and(|checked| checked.elapsed() < DISK_CHECK_INTERVAL)
+        {
+            return;
+        }
+
+        self.last_check = Some(Instant::now());
+
+        let current = self.read_disk_info();
+        if self.disk.is_some() && current != self.disk {
+            self.changed_on_disk = true;
+        }
+    }
+
+    fn read_disk_info(&self) -> Option<DiskInfo> {
+        DiskInfo::read(self.path.as_ref()?)
+    }
+
+    /// Returns the path for display, relative to the working directory when it is inside
+    /// it, or `[No Name]` when there is none.
+    pub fn display_name(&self) -> String {
+        match &self.path {
+            Some(path) => relative_name(path),
+            None => String::from("[No Name]"),
+        }
+    }
+
+    /// Writes the buffer to the current path and records it as saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `std::io::Error` if there is no path or the write operation fails.
+    pub fn write(&mut self, buffer: &Buffer) -> std::io::Result<()> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No file name"))?;
+
+        let content = self.content(buffer);
+        self.write_to_file(path, &content)?;
+        self.saved_revision = buffer.revision();
+        self.noeol = lacks_final_newline(&content);
+        self.refresh_disk_info();
+
+        Ok(())
     }
 
     /// Handles a file-related event and returns resulting app events.
@@ -54,44 +211,284 @@ impl File {
 
         match event {
             FileEvent::Save => events.extend(self.save_file(buffer)),
-            FileEvent::SaveAs(path) => {
-                self.path = Some(path);
+            FileEvent::SaveAs(path) => events.extend(self.save_as(path, buffer)),
+            FileEvent::WriteTo {
+                path,
+                range,
+                set_path,
+            } => self.write_to(path, range, set_path, buffer),
+            FileEvent::Delete { path, permanently } => self.delete(path, permanently),
+        }
+
+        events
+    }
+
+    /// Saves the buffer to `path` once it is known to be safe: a file other than the
+    /// current one is only replaced, and missing directories only created, after
+    /// confirmation.
+    fn save_as(&mut self, path: PathBuf, buffer: &Buffer) -> Vec<AppEvent> {
+        let path = absolute(path);
+        if let Err(reason) = check_save_path(&path) {
+            tracing::error!("Cannot save to {}: {reason}", path.display());
+            return vec![];
+        }
 
-                events.extend(self.save_file(buffer))
+        let write = AppEvent::File(FileEvent::WriteTo {
+            path: path.clone(),
+            range: None,
+            set_path: true,
+        });
+
+        if path.exists() && self.path.as_ref() != Some(&path) {
+            vec![AppEvent::Dialog {
+                message: format!("Overwrite {}?", relative_name(&path)),
+                on_yes: vec![write],
+                on_no: vec![],
+            }]
+        } else if let Some(directory) = missing_directory(&path) {
+            vec![AppEvent::Confirm {
+                message: format!("Create directory {}?", directory.display()),
+                on_confirm: vec![write],
+                on_deny: vec![],
+            }]
+        } else {
+            self.write_to(path, None, true, buffer);
+            vec![]
+        }
+    }
+
+    /// Writes the buffer, or the lines of `range`, to `path`, creating its directory if
+    /// needed. The whole buffer written to the current path, or with `set_path`, is
+    /// saved: `path` becomes the current path. On failure, the current path stays as
+    /// it was.
+    fn write_to(
+        &mut self,
+        path: PathBuf,
+        range: Option<LineRange>,
+        set_path: bool,
+        buffer: &Buffer,
+    ) {
+        let path = absolute(path);
+        if let Some(directory) = missing_directory(&path)
+            && let Err(err) = std::fs::create_dir_all(directory)
+        {
+            tracing::error!("Failed to create {}: {err}", directory.display());
+            return;
+        }
+
+        let Some(range) = range else {
+            if set_path || self.path.as_ref() == Some(&path) {
+                let previous = self.path.replace(path);
+
+                if let Err(err) = self.write(buffer) {
+                    tracing::error!("Failed to save file: {err}");
+                    self.path = previous;
+                }
+            } else if let Err(err) = self.write_to_file(&path, &self.content(buffer)) {
+                tracing::error!("Failed to write {}: {err}", path.display());
             }
+
+            return;
+        };
+
+        let lines = buffer.lines_text(range.start, range.end);
+        match std::fs::write(&path, lines) {
+            Ok(()) => tracing::info!(
+                "Wrote lines {}-{} to {}",
+                range.start + 1,
+                range.end + 1,
+                path.display()
+            ),
+            Err(err) => tracing::error!("Failed to write {}: {err}", path.display()),
         }
+    }
 
-        events
+    /// Deletes `path`, forgetting the version on disk when it is the current file so it
+    /// is not reported as changed elsewhere; saving the buffer creates it again.
+    fn delete(&mut self, path: PathBuf, permanently: bool) {
+        if let Err(err) = delete_path(&path, permanently) {
+            tracing::error!("Failed to delete {}: {err}", path.display());
+            return;
+        }
+
+        match permanently {
+            true => tracing::info!("Deleted {}", path.display()),
+            false => tracing::info!("Moved {} to the trash", path.display()),
+        }
+
+        if self.path.as_ref() == Some(&absolute(path)) {
+            self.refresh_disk_info();
+        }
     }
 
     /// Saves the buffer content to the current file path if set.
     ///
-    /// If no path is set, requests focus change to the filename prompt.
-    fn save_file(&self, buffer: &Buffer) -> Vec<AppEvent> {
+    /// If no path is set, or the file cannot be written for lack of permission, opens
+    /// the save-as prompt, so the changes can go elsewhere.
+    fn save_file(&mut self, buffer: &Buffer) -> Vec<AppEvent> {
         match &self.path {
-            Some(path) => match self.write_to_file(path, buffer) {
+            Some(_) => match self.write(buffer) {
                 Ok(_) => vec![],
-                Err(err) => {
-                    eprintln!("Failed to save file: {}", err);
-                    vec![]
-                }
+                Err(err) => save_failed(&err),
             },
 
-            None => vec![AppEvent::ChangeFocus(FocusableComponent::FilenamePrompt)],
+            None => vec![AppEvent::OpenPrompt(PromptKind::SaveAs)],
+        }
+    }
+
+    /// Returns the text written for `buffer`, adding or removing its final line break as
+    /// the settings of the file ask, or adding a missing one with `fixeol`.
+    fn content(&self, buffer: &Buffer) -> String {
+        let mut content = buffer.as_rope().to_string();
+
+        // Without a setting of its own, the file keeps its final line break, or its lack
+        // of one with `nofixeol`.
+        let add = self.settings.final_newline.unwrap_or(self.fixeol);
+        match self.settings.final_newline {
+            _ if add && lacks_final_newline(&content) => content.push('\n'),
+            Some(false) if content.ends_with('\n') => {
+                content.pop();
+                if content.ends_with('\r') {
+                    content.pop();
+                }
+            }
+            _ => {}
         }
+
+        content
     }
 
-    /// Writes the buffer content to disk at the specified path.
+    /// Writes `content` to disk at the specified path, in the charset of the file.
+    ///
     /// # Errors
     ///
     /// Returns an `std::io::Error` if the write operation fails.
-    fn write_to_file(&self, path: &PathBuf, buffer: &Buffer) -> std::io::Result<()> {
-        let mut content = String::new();
-        for line in buffer.lines() {
-            content.push_str(&line.to_string());
+    fn write_to_file(&self, path: &PathBuf, content: &str) -> std::io::Result<()> {
+        let charset = self.settings.charset.unwrap_or(Charset::Utf8);
+        std::fs::write(path, charset.encode(content))
+    }
+}
+
+/// Reports a failed save, opening the save-as prompt when it was refused for lack of
+/// permission.
+fn save_failed(err: &std::io::Error) -> Vec<AppEvent> {
+    if err.kind() != std::io::ErrorKind::PermissionDenied {
+        tracing::error!("Failed to save file: {err}");
+        return vec![];
+    }
+
+    tracing::error!("Failed to save file: {err}; save it elsewhere");
+    vec![AppEvent::OpenPrompt(PromptKind::SaveAs)]
+}
+
+/// Returns `true` when the user may not write to the file at `path`, as `access(2)`
+/// tells. The file is not opened, so watchers see no event and FIFOs do not block.
+#[cfg(unix)]
+fn is_read_only(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+
+    // SAFETY: `path` is a valid NUL-terminated string that outlives the call.
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) != 0 }
+}
+
+/// Returns `true` when the file at `path` is marked read-only.
+#[cfg(not(unix))]
+fn is_read_only(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().readonly())
+}
+
+/// Returns `path` made absolute against the working directory, without resolving links,
+/// or as it is when that fails.
+pub fn absolute(path: PathBuf) -> PathBuf {
+    std::path::absolute(&path).unwrap_or(path)
+}
+
+/// Returns the name of `path` relative to the working directory when it is inside it, or
+/// the whole path otherwise.
+pub fn relative_name(path: &Path) -> String {
+    let working_directory = std::env::current_dir().unwrap_or_default();
+
+    match path.strip_prefix(&working_directory) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.display().to_string(),
+        _ => path.display().to_string(),
+    }
+}
+
+/// Returns `true` when `text` has some content but does not end with a line break.
+fn lacks_final_newline(text: &str) -> bool {
+    !text.is_empty() && !text.ends_with('\n')
+}
+
+/// Returns why `path` cannot be saved to: it is a directory, or one of its parents is
+/// a file.
+pub fn check_save_path(path: &Path) -> Result<(), String> {
+    if path.is_dir() {
+        return Err(String::from("Is a directory"));
+    }
+
+    match path.ancestors().skip(1).find(|ancestor| ancestor.exists()) {
+        Some(ancestor) if !ancestor.as_os_str().is_empty() && !ancestor.is_dir() => {
+            Err(format!("{} is not a directory", ancestor.display()))
         }
+        _ => Ok(()),
+    }
+}
 
-        std::fs::write(path, content)
+/// Moves the file or directory at `path` to the trash, or removes it for good when
+/// `permanently` is set.
+///
+/// # Errors
+///
+/// Returns an `std::io::Error` if `path` does not exist, or the trash or the file system
+/// refuses to remove it.
+pub fn delete_path(path: &Path, permanently: bool) -> std::io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+
+    match (permanently, metadata.is_dir()) {
+        (true, true) => std::fs::remove_dir_all(path),
+        (true, false) => std::fs::remove_file(path),
+        (false, _) => trash::delete(path).map_err(std::io::Error::other),
+    }
+}
+
+/// Returns the directory of `path` when it does not exist yet.
+fn missing_directory(path: &Path) -> Option<&Path> {
+    path.parent()
+        .filter(|directory| !directory.as_os_str().is_empty() && !directory.exists())
+}
+
+/// Formats a size in bytes for display, like `512 B` or `1.5 KiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Formats how long ago something happened, like `just now`, `5m ago` or `2d ago`.
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+
+    match seconds {
+        0..10 => String::from("just now"),
+        10..60 => format!("{seconds}s ago"),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86400 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
     }
 }
 
@@ -117,7 +514,7 @@ mod tests {
         let path = PathBuf::from("test.txt");
         let file = File::new(Some(path.clone()));
 
-        assert_eq!(file.path, Some(path));
+        assert_eq!(file.path, Some(absolute(path)));
     }
 
     #[test]
@@ -134,11 +531,34 @@ mod tests {
 
         let saved_content = std::fs::read_to_string(&path).expect("File should exist");
 
-        assert_eq!(saved_content, "Hello, Zack!");
+        assert_eq!(saved_content, "Hello, Zack!\n");
 
         let _ = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn should_forget_the_disk_version_of_a_deleted_file() {
+        let path = PathBuf::from("test_delete.txt");
+        std::fs::write(&path, "gone").unwrap();
+        let mut file = File::new(Some(path.clone()));
+        let buffer = create_buffer_with_text("gone");
+
+        let events = file.handle_event(
+            FileEvent::Delete {
+                path: path.clone(),
+                permanently: true,
+            },
+            &buffer,
+        );
+
+        assert!(events.is_empty());
+        assert!(!path.exists());
+        assert_eq!(file.disk, None);
+
+        file.check_disk();
+        assert!(!file.changed_on_disk);
+    }
+
     #[test]
     fn should_update_path_and_save_on_save_as() {
         let path = PathBuf::from("test_save_as.txt");
@@ -150,25 +570,256 @@ mod tests {
         let events = file.handle_event(FileEvent::SaveAs(path.clone()), &buffer);
 
         assert!(events.is_empty());
-        assert_eq!(file.path, Some(path.clone()));
+        assert_eq!(file.path, Some(absolute(path.clone())));
 
         let saved_content = std::fs::read_to_string(&path).expect("File should exist");
 
-        assert_eq!(saved_content, "New content");
+        assert_eq!(saved_content, "New content\n");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn should_save_as_its_own_file_through_a_relative_path_without_asking() {
+        let path = PathBuf::from("test_save_as_own.txt");
+        std::fs::write(&path, "old\n").unwrap();
+        let mut file = File::new(Some(path.clone()));
+        let buffer = create_buffer_with_text("new");
+
+        let events = file.handle_event(FileEvent::SaveAs(path.clone()), &buffer);
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(events.is_empty());
+        assert_eq!(content, "new\n");
+    }
+
+    #[test]
+    fn should_ask_before_overwriting_another_file() {
+        let path = PathBuf::from("test_save_as_existing.txt");
+        std::fs::write(&path, "old").unwrap();
+        let mut file = File::default();
+        let buffer = create_buffer_with_text("new");
+
+        let events = file.handle_event(FileEvent::SaveAs(path.clone()), &buffer);
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            events,
+            vec![AppEvent::Dialog {
+                message: String::from("Overwrite test_save_as_existing.txt?"),
+                on_yes: vec![AppEvent::File(FileEvent::WriteTo {
+                    path: absolute(path),
+                    range: None,
+                    set_path: true,
+                })],
+                on_no: vec![],
+            }]
+        );
+        assert_eq!(content, "old");
+        assert!(file.path.is_none());
+    }
+
+    #[test]
+    fn should_create_missing_directories_once_confirmed() {
+        let directory = PathBuf::from("test_save_as_dir");
+        let path = directory.join("nested").join("file.txt");
+        let mut file = File::default();
+        let buffer = create_buffer_with_text("text");
+
+        let events = file.handle_event(FileEvent::SaveAs(path.clone()), &buffer);
+        file.handle_event(
+            FileEvent::WriteTo {
+                path: path.clone(),
+                range: None,
+                set_path: true,
+            },
+            &buffer,
+        );
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(matches!(
+            &events[..],
+            [AppEvent::Confirm { message, .. }] if message.starts_with("Create directory")
+        ));
+        assert_eq!(content, "text\n");
+        assert_eq!(file.path, Some(absolute(path)));
+    }
+
+    #[test]
+    fn should_write_copies_and_ranges_without_changing_the_path() {
+        let original = PathBuf::from("test_write_to_original.txt");
+        let copy = PathBuf::from("test_write_to_copy.txt");
+        let part = PathBuf::from("test_write_to_part.txt");
+        let mut file = File::new(Some(original.clone()));
+        let buffer = create_buffer_with_text("one\ntwo\nthree\n");
+
+        file.handle_event(
+            FileEvent::WriteTo {
+                path: copy.clone(),
+                range: None,
+                set_path: false,
+            },
+            &buffer,
+        );
+        file.handle_event(
+            FileEvent::WriteTo {
+                path: part.clone(),
+                range: Some(LineRange { start: 1, end: 2 }),
+                set_path: false,
+            },
+            &buffer,
+        );
+        let copied = std::fs::read_to_string(&copy).unwrap();
+        let written_part = std::fs::read_to_string(&part).unwrap();
+        std::fs::remove_file(&copy).unwrap();
+        std::fs::remove_file(&part).unwrap();
+
+        assert_eq!(copied, "one\ntwo\nthree\n");
+        assert_eq!(written_part, "two\nthree\n");
+        assert_eq!(file.path, Some(absolute(original.clone())));
+        assert!(!original.exists());
+    }
+
+    #[test]
+    fn should_refuse_to_save_to_a_directory_or_below_a_file() {
+        let path = PathBuf::from("test_save_as_parent.txt");
+        std::fs::write(&path, "").unwrap();
+
+        let directory = check_save_path(Path::new("src"));
+        let below_file = check_save_path(&path.join("child.txt"));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(directory, Err(String::from("Is a directory")));
+        assert_eq!(
+            below_file,
+            Err(String::from("test_save_as_parent.txt is not a directory"))
+        );
+        assert_eq!(check_save_path(Path::new("new_file.txt")), Ok(()));
+    }
+
+    #[test]
+    fn should_track_modifications_until_saved() {
+        let path = PathBuf::from("test_modified.txt");
+        let mut file = File::new(Some(path.clone()));
+        let mut buffer = create_buffer_with_text("abc");
+
+        assert!(!file.is_modified(&buffer));
+
+        buffer.handle_event(crate::app::buffer::BufferEvent::InsertChar {
+            char: 'x',
+            position: crate::types::position::Position::new(0, 0),
+        });
+        assert!(file.is_modified(&buffer));
+
+        file.handle_event(FileEvent::Save, &buffer);
+        assert!(!file.is_modified(&buffer));
 
         let _ = std::fs::remove_file(&path);
     }
 
     #[test]
-    fn should_request_focus_change_when_saving_without_path() {
+    fn should_add_a_missing_final_newline_unless_told_not_to() {
+        let path = PathBuf::from("test_fixeol.txt");
+        let mut file = File::new(Some(path.clone()));
+        let buffer = create_buffer_with_text("text");
+
+        file.mark_saved(&buffer);
+        assert!(file.noeol);
+        file.write(&buffer).unwrap();
+        let fixed = std::fs::read_to_string(&path).unwrap();
+        assert!(!file.noeol);
+
+        file.fixeol = false;
+        file.write(&buffer).unwrap();
+        let kept = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(fixed, "text\n");
+        assert_eq!(kept, "text");
+        assert!(file.noeol);
+    }
+
+    #[test]
+    fn should_fail_to_write_without_path() {
+        let mut file = File::default();
+        let buffer = create_buffer_with_text("text");
+
+        assert!(file.write(&buffer).is_err());
+    }
+
+    #[test]
+    fn should_open_save_as_prompt_when_saving_without_path() {
         let mut file = File::default();
         let buffer = create_buffer_with_text("Some text");
 
         let events = file.handle_event(FileEvent::Save, &buffer);
 
+        assert_eq!(events, vec![AppEvent::OpenPrompt(PromptKind::SaveAs)]);
+    }
+
+    #[test]
+    fn should_format_sizes_and_ages() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+
+        assert_eq!(format_age(Duration::from_secs(3)), "just now");
+        assert_eq!(format_age(Duration::from_secs(42)), "42s ago");
+        assert_eq!(format_age(Duration::from_secs(300)), "5m ago");
+        assert_eq!(format_age(Duration::from_secs(2 * 86400)), "2d ago");
+    }
+
+    #[test]
+    fn should_flag_files_changed_on_disk() {
+        let path = PathBuf::from("test_file_changed.txt");
+        std::fs::write(&path, "one").unwrap();
+        let mut file = File::new(Some(path.clone()));
+
+        file.check_disk();
+        let unchanged = !file.changed_on_disk;
+
+        std::fs::write(&path, "longer").unwrap();
+        file.last_check = None;
+        file.check_disk();
+        let changed = file.changed_on_disk;
+
+        file.write(&create_buffer_with_text("saved")).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(unchanged);
+        assert!(changed);
+        assert!(!file.changed_on_disk);
+        assert_eq!(file.disk.map(|disk| disk.size), Some(6));
+    }
+
+    #[test]
+    fn should_offer_to_save_elsewhere_when_permission_is_denied() {
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let full = std::io::Error::other("No space left on device");
+
         assert_eq!(
-            events,
-            vec![AppEvent::ChangeFocus(FocusableComponent::FilenamePrompt)]
+            save_failed(&denied),
+            vec![AppEvent::OpenPrompt(PromptKind::SaveAs)]
         );
+        assert_eq!(save_failed(&full), vec![]);
+        assert!(!is_read_only(Path::new("Cargo.toml")));
+        assert!(!File::new(Some(PathBuf::from("missing_file.txt"))).read_only);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_check_permissions_without_opening_the_file() {
+        let path = std::ffi::CString::new("test_file_fifo").unwrap();
+        // SAFETY: `path` is a valid NUL-terminated string that outlives the call.
+        assert_eq!(unsafe { libc::mkfifo(path.as_ptr(), 0o644) }, 0);
+
+        // Opening a FIFO without a reader for writing would block here.
+        let read_only = File::new(Some(PathBuf::from("test_file_fifo"))).read_only;
+        std::fs::remove_file("test_file_fifo").unwrap();
+
+        assert!(!read_only);
     }
 }
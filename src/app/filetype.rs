@@ -0,0 +1,256 @@
+//! File type detection and per-language settings for the Zack text editor.
+//!
+//! This module provides [`FileType`], detected for each buffer from the extension or
+//! name of its file, or else from the `#!` line of scripts, and [`FiletypeSettings`],
+//! read from the `[filetype.<name>]` sections of the configuration file:
+//!
+//! ```text
+//! [filetype.rust]
+//! tabstop = 4
+//! expandtab = true
+//! comment = //
+//! formatter = rustfmt --edition 2024
+//! lsp = rust-analyzer
+//! ```
+//!
+//! `tabstop` and `expandtab` override the global options for buffers of that type, and
+//! are overridden in turn by EditorConfig files and modelines. `comment` is the line
+//! comment marker `:license` uses, `formatter` the command `:format` pipes the buffer
+//! through, and `lsp` the language server started for it. The `[hooks]` section is read
+//! by the [`hooks`](crate::app::hooks) module, and the `[statusline]` one by the
+//! [`statusline`](crate::app::statusline) module.
+
+use crate::app::{hooks, statusline};
+use std::{collections::HashMap, path::Path};
+
+/// Name of the configuration file, in the configuration directory.
+pub const CONFIG_FILE: &str = "config";
+
+/// Prefix of the sections of the configuration file setting a file type.
+const SECTION_PREFIX: &str = "filetype.";
+
+/// Known file types: `(name, extensions or file names, interpreters)`.
+const FILETYPES: &[(&str, &[&str], &[&str])] = &[
+    ("rust", &["rs"], &[]),
+    ("python", &["py", "pyw", "pyi"], &["python"]),
+    ("go", &["go"], &[]),
+    ("c", &["c", "h"], &[]),
+    ("cpp", &["cc", "cpp", "cxx", "hpp", "hh"], &[]),
+    (
+        "javascript",
+        &["js", "mjs", "cjs", "jsx"],
+        &["node", "deno"],
+    ),
+    ("typescript", &["ts", "mts", "tsx"], &[]),
+    (
+        "sh",
+        &["sh", "bash", "zsh"],
+        &["sh", "bash", "zsh", "dash", "ksh"],
+    ),
+    ("fish", &["fish"], &["fish"]),
+    ("ruby", &["rb", "Gemfile", "Rakefile"], &["ruby"]),
+    ("perl", &["pl", "pm"], &["perl"]),
+    ("lua", &["lua"], &["lua", "luajit"]),
+    (
+        "make",
+        &["mk", "Makefile", "makefile", "GNUmakefile"],
+        &["make"],
+    ),
+    ("dockerfile", &["Dockerfile"], &[]),
+    ("toml", &["toml"], &[]),
+    ("yaml", &["yaml", "yml"], &[]),
+    ("json", &["json"], &[]),
+    ("markdown", &["md", "markdown"], &[]),
+    ("html", &["html", "htm"], &[]),
+    ("css", &["css"], &[]),
+];
+
+/// The language of a buffer, like `rust` or `python`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FileType(&'static str);
+
+/// Settings of a file type from the configuration file; `None` keeps the default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FiletypeSettings {
+    pub tabstop: Option<usize>,
+    pub expandtab: Option<bool>,
+    /// Line comment marker, like `//` or `#`.
+    pub comment: Option<String>,
+    /// Shell command reading the buffer on its standard input and printing it formatted.
+    pub formatter: Option<String>,
+    /// Language server command and its arguments.
+    pub lsp: Option<Vec<String>>,
+}
+
+impl FileType {
+    /// Returns the file type called `name`, if it is known.
+    pub fn from_name(name: &str) -> Option<Self> {
+        FILETYPES
+            .iter()
+            .find(|(known, ..)| *known == name)
+            .map(|(name, ..)| FileType(name))
+    }
+
+    /// Detects the type of the file at `path` whose text is `text`: from its extension,
+    /// or its name for files like `Makefile`, then from the interpreter of its `#!` line.
+    pub fn detect(path: Option<&Path>, text: &str) -> Option<Self> {
+        path.and_then(Self::from_path)
+            .or_else(|| Self::from_shebang(text.lines().next()?))
+    }
+
+    /// Returns the name of the file type.
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let extension = path.extension().and_then(|extension| extension.to_str());
+
+        FILETYPES
+            .iter()
+            .find(|(_, keys, _)| {
+                keys.contains(&name) || extension.is_some_and(|e| keys.contains(&e))
+            })
+            .map(|(name, ..)| FileType(name))
+    }
+
+    /// Detects the type from a `#!` line, like `#!/bin/sh` or `#!/usr/bin/env python3`.
+    fn from_shebang(line: &str) -> Option<Self> {
+        let mut words = line.strip_prefix("#!")?.split_whitespace();
+        let mut program = words.next()?.rsplit('/').next()?;
+        if program == "env" {
+            program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+        }
+        // `python3.12` runs `python`.
+        let interpreter = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+        FILETYPES
+            .iter()
+            .find(|(_, _, interpreters)| interpreters.contains(&interpreter))
+            .map(|(name, ..)| FileType(name))
+    }
+}
+
+/// Reads the `[filetype.<name>]` sections of the configuration file `text`, skipping
+/// blank lines and `#` comments. Returns the settings by file type name with the errors
+/// of the lines that could not be read.
+pub fn parse_config(text: &str) -> (HashMap<String, FiletypeSettings>, Vec<String>) {
+    let mut filetypes: HashMap<String, FiletypeSettings> = HashMap::new();
+    let mut errors = vec![];
+    let mut section = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |err: String| format!("{CONFIG_FILE}:{}: {err}", index + 1);
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().strip_prefix(SECTION_PREFIX).map(String::from);
+            if section.is_none() && ![hooks::SECTION, statusline::SECTION].contains(&name.trim()) {
+                errors.push(error(format!("Unknown section: {name}")));
+            }
+            continue;
+        }
+
+        let Some(name) = &section else {
+            continue;
+        };
+        let settings = filetypes.entry(name.clone()).or_default();
+
+        if let Err(err) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Expected key = value: {line}"))
+            .and_then(|(key, value)| settings.set(key.trim(), value.trim()))
+        {
+            errors.push(error(err));
+        }
+    }
+
+    (filetypes, errors)
+}
+
+impl FiletypeSettings {
+    /// Sets the setting `key` from the configuration file to `value`.
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("Invalid value for {key}: {value}");
+
+        match key {
+            "tabstop" => match value.parse() {
+                Ok(width @ 1..) => self.tabstop = Some(width),
+                _ => return Err(invalid()),
+            },
+            "expandtab" => self.expandtab = Some(value.parse().map_err(|_| invalid())?),
+            "comment" if !value.is_empty() => self.comment = Some(value.to_string()),
+            "formatter" if !value.is_empty() => self.formatter = Some(value.to_string()),
+            "lsp" if !value.is_empty() => {
+                self.lsp = Some(value.split_whitespace().map(String::from).collect());
+            }
+            "comment" | "formatter" | "lsp" => return Err(invalid()),
+            _ => return Err(format!("Unknown setting: {key}")),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn detect(path: &str, text: &str) -> Option<&'static str> {
+        let path = PathBuf::from(path);
+        FileType::detect(
+            Some(path.as_path()).filter(|path| !path.as_os_str().is_empty()),
+            text,
+        )
+        .map(|filetype| filetype.name())
+    }
+
+    #[test]
+    fn should_detect_file_types_from_names_and_shebangs() {
+        assert_eq!(detect("src/main.rs", ""), Some("rust"));
+        assert_eq!(detect("Makefile", ""), Some("make"));
+        assert_eq!(
+            detect("build", "#!/usr/bin/env -S python3.12 -u\n"),
+            Some("python")
+        );
+        assert_eq!(detect("", "#!/bin/bash\necho hi"), Some("sh"));
+        assert_eq!(detect("notes.txt", "plain"), None);
+        assert_eq!(
+            FileType::from_name("go").map(|filetype| filetype.name()),
+            Some("go")
+        );
+    }
+
+    #[test]
+    fn should_read_filetype_sections_of_the_config_file() {
+        let (filetypes, errors) = parse_config(
+            "# languages\n[filetype.rust]\ntabstop = 2\nexpandtab = true\ncomment = //\n\
+             lsp = rust-analyzer --log\n\n[filetype.python]\nformatter = black -q -\n\
+             tabstop = wide\n[editor]\nmouse = true\n[hooks]\nBufEnter * set wrap\n",
+        );
+
+        assert_eq!(
+            filetypes["rust"],
+            FiletypeSettings {
+                tabstop: Some(2),
+                expandtab: Some(true),
+                comment: Some(String::from("//")),
+                formatter: None,
+                lsp: Some(vec![String::from("rust-analyzer"), String::from("--log")]),
+            }
+        );
+        assert_eq!(filetypes["python"].formatter.as_deref(), Some("black -q -"));
+        assert_eq!(
+            errors,
+            vec![
+                String::from("config:10: Invalid value for tabstop: wide"),
+                String::from("config:11: Unknown section: editor"),
+            ]
+        );
+    }
+}
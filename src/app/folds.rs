@@ -0,0 +1,272 @@
+//! Folding module for the Zack text editor.
+//!
+//! This module provides the [`Folds`] struct, which keeps the folds closed in a document,
+//! and [`FoldEvent`], used to open and close them. A fold is an indented block with the
+//! line introducing it: a line and the lines below it that are indented more, blank lines
+//! included. A closed fold shows as its first line followed by the number of lines it
+//! hides, and `j` / `k` step over it as over a single line.
+//!
+//! Closed folds are kept as line ranges, shifted as lines are added or removed above
+//! them, so they stay closed while the text around them is edited.
+
+use crate::{
+    app::buffer::Buffer,
+    event::{AppEvent, CursorEvent},
+};
+
+/// The closed folds of a document, as inclusive ranges of lines sorted by first line.
+/// Folds may nest; the outermost closed one decides what is shown.
+#[derive(Debug, Default)]
+pub struct Folds {
+    closed: Vec<(usize, usize)>,
+}
+
+/// Events that open or close folds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FoldEvent {
+    /// Opens the closed fold on `line`, or closes the fold around it (`za`).
+    Toggle { line: usize },
+    /// Opens the closed fold on `line` (`zo`).
+    Open { line: usize },
+    /// Closes the innermost fold around `line`, or the one around it when that one is
+    /// already closed (`zc`).
+    Close { line: usize },
+    /// Opens every fold (`zR`).
+    OpenAll,
+}
+
+impl Folds {
+    /// Creates an empty set of folds, with every line shown.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a `FoldEvent` on the text of `buffer` and returns any follow-up
+    /// `AppEvent`s: closing a fold moves the cursor to its first line.
+    pub fn handle_event(&mut self, event: FoldEvent, buffer: &Buffer) -> Vec<AppEvent> {
+        match event {
+            FoldEvent::Toggle { line } if self.closed_on(line).is_some() => self.open(line),
+            FoldEvent::Toggle { line } | FoldEvent::Close { line } => {
+                return self.close(line, buffer);
+            }
+            FoldEvent::Open { line } => self.open(line),
+            FoldEvent::OpenAll => self.closed.clear(),
+        }
+
+        vec![]
+    }
+
+    /// Returns `true` when no fold is closed.
+    pub fn is_empty(&self) -> bool {
+        self.closed.is_empty()
+    }
+
+    /// Returns the outermost closed fold hiding `line`, which is not its first line.
+    pub fn hiding(&self, line: usize) -> Option<(usize, usize)> {
+        self.closed
+            .iter()
+            .find(|(start, end)| *start < line && line <= *end)
+            .copied()
+    }
+
+    /// Returns the last line of the closed fold shown as `line`, when `line` starts one
+    /// and is not hidden itself.
+    pub fn closed_at(&self, line: usize) -> Option<usize> {
+        if self.hiding(line).is_some() {
+            return None;
+        }
+
+        self.closed
+            .iter()
+            .filter(|(start, _)| *start == line)
+            .map(|(_, end)| *end)
+            .max()
+    }
+
+    /// Returns the line shown after `line`, which must be shown itself: the one past the
+    /// fold it starts when closed.
+    pub fn next_visible(&self, line: usize) -> usize {
+        self.closed_at(line).unwrap_or(line) + 1
+    }
+
+    /// Returns the line shown before `line`, the first line of the fold hiding the line
+    /// above it if any, or `None` at the top.
+    pub fn previous_visible(&self, line: usize) -> Option<usize> {
+        let above = line.checked_sub(1)?;
+
+        Some(self.hiding(above).map_or(above, |(start, _)| start))
+    }
+
+    /// Opens the closed folds hiding `line`, as when the cursor jumps into one.
+    pub fn reveal(&mut self, line: usize) {
+        self.closed
+            .retain(|(start, end)| !(*start < line && line <= *end));
+    }
+
+    /// Keeps the folds on the same text after an edit at `line` turned `before` lines
+    /// into `after`. Folds below the edit move with it; a fold around it grows or
+    /// shrinks. Edits of unknown place, like undoing, open every fold.
+    pub fn edited(&mut self, line: Option<usize>, before: usize, after: usize) {
+        if before == after {
+            return;
+        }
+        let Some(line) = line else {
+            self.closed.clear();
+            return;
+        };
+
+        let shift = |at: usize| (at + after).saturating_sub(before);
+        self.closed = self
+            .closed
+            .iter()
+            .filter_map(|&(start, end)| match (start > line, end < line) {
+                (_, true) => Some((start, end)),
+                (true, _) => Some((shift(start), shift(end))),
+                (false, false) => Some((start, shift(end))).filter(|(start, end)| start < end),
+            })
+            .collect();
+    }
+
+    /// Returns the outermost closed fold on `line`: hiding it or starting on it.
+    fn closed_on(&self, line: usize) -> Option<(usize, usize)> {
+        self.closed
+            .iter()
+            .find(|(start, end)| *start <= line && line <= *end)
+            .copied()
+    }
+
+    /// Opens the outermost closed fold on `line`, leaving those nested in it closed.
+    fn open(&mut self, line: usize) {
+        if let Some(fold) = self.closed_on(line) {
+            self.closed.retain(|closed| *closed != fold);
+        }
+    }
+
+    /// Closes the innermost fold around `line` that is still open, moving the cursor
+    /// to its first line.
+    fn close(&mut self, line: usize, buffer: &Buffer) -> Vec<AppEvent> {
+        let mut fold = region_starting_at(buffer, line).or_else(|| region_around(buffer, line));
+
+        while let Some(closed) = fold.filter(|fold| self.closed.contains(fold)) {
+            fold = region_around(buffer, closed.0);
+        }
+
+        let Some(fold) = fold else {
+            return vec![];
+        };
+
+        let index = self.closed.partition_point(|closed| *closed < fold);
+        self.closed.insert(index, fold);
+
+        vec![AppEvent::Cursor(CursorEvent::SetLinePosition(fold.0))]
+    }
+}
+
+/// Returns the indentation of `line`, or `None` when it is blank.
+fn indent(buffer: &Buffer, line: usize) -> Option<usize> {
+    let content = buffer.as_rope().line(line);
+    let mut indent = 0;
+
+    for c in content.chars() {
+        match c {
+            ' ' | '\t' => indent += 1,
+            '\n' | '\r' => return None,
+            _ => return Some(indent),
+        }
+    }
+
+    None
+}
+
+/// Returns the fold starting on `line`: it and the lines below indented more, up to the
+/// last one of them that is not blank.
+pub fn region_starting_at(buffer: &Buffer, line: usize) -> Option<(usize, usize)> {
+    let header = indent(buffer, line)?;
+    let mut end = line;
+
+    for below in line + 1..buffer.len_lines() {
+        match indent(buffer, below) {
+            Some(indent) if indent > header => end = below,
+            Some(_) => break,
+            None => {}
+        }
+    }
+
+    (end > line).then_some((line, end))
+}
+
+/// Returns the innermost fold holding `line` below its first line: the one starting on
+/// the closest line above that is indented less.
+pub fn region_around(buffer: &Buffer, line: usize) -> Option<(usize, usize)> {
+    // A blank line belongs to the block of the next line that is not blank.
+    let indent_of_line = (line..buffer.len_lines()).find_map(|below| indent(buffer, below))?;
+
+    (0..line)
+        .rev()
+        .find(|&above| indent(buffer, above).is_some_and(|indent| indent < indent_of_line))
+        .and_then(|header| region_starting_at(buffer, header))
+        .filter(|(_, end)| line <= *end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer() -> Buffer {
+        Buffer::new(String::from(
+            "fn main() {\n    if x {\n        y();\n\n        z();\n    }\n}\n",
+        ))
+    }
+
+    #[test]
+    fn should_find_folds_from_indentation() {
+        let buffer = buffer();
+
+        assert_eq!(region_starting_at(&buffer, 0), Some((0, 5)));
+        assert_eq!(region_starting_at(&buffer, 1), Some((1, 4)));
+        assert_eq!(region_starting_at(&buffer, 2), None);
+        assert_eq!(region_around(&buffer, 3), Some((1, 4)));
+        assert_eq!(region_around(&buffer, 5), Some((0, 5)));
+        assert_eq!(region_around(&buffer, 0), None);
+    }
+
+    #[test]
+    fn should_close_enclosing_folds_and_open_one_level() {
+        let buffer = buffer();
+        let mut folds = Folds::new();
+
+        assert_eq!(
+            folds.handle_event(FoldEvent::Close { line: 2 }, &buffer),
+            vec![AppEvent::Cursor(CursorEvent::SetLinePosition(1))]
+        );
+        folds.handle_event(FoldEvent::Close { line: 1 }, &buffer);
+        assert_eq!(folds.closed, vec![(0, 5), (1, 4)]);
+        assert_eq!(folds.closed_at(0), Some(5));
+        assert_eq!(folds.closed_at(1), None);
+        assert_eq!(folds.next_visible(0), 6);
+        assert_eq!(folds.previous_visible(6), Some(0));
+
+        folds.handle_event(FoldEvent::Toggle { line: 0 }, &buffer);
+        assert_eq!(folds.closed, vec![(1, 4)]);
+        assert_eq!(folds.hiding(3), Some((1, 4)));
+
+        folds.reveal(3);
+        assert!(folds.is_empty());
+    }
+
+    #[test]
+    fn should_move_folds_with_the_lines_edited_above_them() {
+        let mut folds = Folds {
+            closed: vec![(2, 4), (6, 8)],
+        };
+
+        folds.edited(Some(3), 10, 12);
+        assert_eq!(folds.closed, vec![(2, 6), (8, 10)]);
+
+        folds.edited(Some(0), 12, 11);
+        assert_eq!(folds.closed, vec![(1, 5), (7, 9)]);
+
+        folds.edited(None, 11, 10);
+        assert!(folds.is_empty());
+    }
+}
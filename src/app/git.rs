@@ -0,0 +1,389 @@
+//! Git integration module for the Zack text editor.
+//!
+//! This module compares a buffer with the version of its file committed at `HEAD`,
+//! read by shelling out to `git show`. The differences are grouped into [`Hunk`]s of
+//! added, modified or deleted lines, which the editor draws as gutter signs and jumps
+//! between with `]c` and `[c`. It also asks `git blame` who last changed a line, for
+//! the annotation shown next to the cursor line.
+
+use crate::app::{
+    diagnostics::{Sign, SignAction, SignKind},
+    diff::{self, DiffOp},
+    file,
+};
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// How a run of lines differs from `HEAD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A run of changed lines of the buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    /// First buffer line of the hunk; for deletions, the line following the removed ones.
+    pub start: usize,
+    /// Number of buffer lines in the hunk, zero for deletions.
+    pub lines: usize,
+    pub change: LineChange,
+    /// Lines of `HEAD` replaced or removed by the hunk.
+    pub removed: Vec<String>,
+}
+
+impl LineChange {
+    /// Returns the symbol drawn in the gutter.
+    pub fn symbol(&self) -> char {
+        match self {
+            LineChange::Added => '+',
+            LineChange::Modified => '~',
+            LineChange::Deleted => '-',
+        }
+    }
+}
+
+impl Hunk {
+    /// Describes the hunk for tooltips: what was added, or the `HEAD` lines it replaced.
+    pub fn describe(&self) -> String {
+        match self.change {
+            LineChange::Added => format!("{} line(s) added", self.lines),
+            LineChange::Modified | LineChange::Deleted => self
+                .removed
+                .iter()
+                .map(|line| format!("-{line}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// The last commit that changed a line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlameInfo {
+    pub author: String,
+    /// Author time, in seconds since the Unix epoch.
+    pub time: u64,
+    pub summary: String,
+    /// `true` for lines not committed yet.
+    pub uncommitted: bool,
+}
+
+impl BlameInfo {
+    /// Formats the blame like `Jane Doe, 3d ago • Fix the parser`.
+    pub fn describe(&self) -> String {
+        if self.uncommitted {
+            return String::from("Not committed yet");
+        }
+
+        let age = SystemTime::now()
+            .duration_since(UNIX_EPOCH + Duration::from_secs(self.time))
+            .unwrap_or_default();
+
+        format!(
+            "{}, {} • {}",
+            self.author,
+            file::format_age(age),
+            self.summary
+        )
+    }
+}
+
+/// Returns the content of `path` as committed at `HEAD`, or `None` when the file is not
+/// tracked, is outside a repository, or `git` is unavailable.
+pub fn head_text(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .arg("show")
+        .arg(format!("HEAD:./{name}"))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Returns the branch checked out in the repository of `path`, or `None` when the file
+/// is outside a repository, `HEAD` is detached, or `git` is unavailable.
+pub fn branch(path: &Path) -> Option<String> {
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(["symbolic-ref", "--short", "HEAD"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !branch.is_empty()).then_some(branch)
+}
+
+/// Returns the `user.name` set in the git configuration, if `git` knows one.
+pub fn user_name() -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "user.name"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    let name = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+/// Blames `line` (zero-based) of `path` as it reads in `contents`, so unsaved edits
+/// are accounted for. Returns `None` when the file is not tracked or `git` fails.
+pub fn blame_line(path: &Path, line: usize, contents: &str) -> Option<BlameInfo> {
+    let name = path.file_name()?.to_str()?;
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(["blame", "--porcelain", "--contents", "-", "-L"])
+        .arg(format!("{},{}", line + 1, line + 1))
+        .arg("--")
+        .arg(name)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // Dropping stdin closes it, so git sees the end of the contents.
+    let _ = child.stdin.take()?.write_all(contents.as_bytes());
+    let output = child.wait_with_output().ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_blame(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parses the `git blame --porcelain` output of a single line.
+fn parse_blame(output: &str) -> Option<BlameInfo> {
+    let mut lines = output.lines();
+    let hash = lines.next()?.split_whitespace().next()?;
+
+    let mut blame = BlameInfo {
+        author: String::new(),
+        time: 0,
+        summary: String::new(),
+        uncommitted: hash.chars().all(|c| c == '0'),
+    };
+
+    for line in lines {
+        if let Some(author) = line.strip_prefix("author ") {
+            blame.author = author.to_string();
+        } else if let Some(time) = line.strip_prefix("author-time ") {
+            blame.time = time.parse().unwrap_or_default();
+        } else if let Some(summary) = line.strip_prefix("summary ") {
+            blame.summary = summary.to_string();
+        }
+    }
+
+    Some(blame)
+}
+
+/// Compares `base` with `current` line by line and returns the hunks of `current`.
+pub fn diff(base: &str, current: &str) -> Vec<Hunk> {
+    let old: Vec<&str> = base.lines().collect();
+    let new: Vec<&str> = current.lines().collect();
+
+    let mut hunks = vec![];
+    let mut removed: Vec<String> = vec![];
+    let mut added = 0;
+    // Lines of `current` seen so far.
+    let mut seen = 0;
+
+    let mut flush = |removed: &mut Vec<String>, added: &mut usize, seen: usize| {
+        let change = match (removed.is_empty(), *added == 0) {
+            (true, true) => return,
+            (true, false) => LineChange::Added,
+            (false, true) => LineChange::Deleted,
+            (false, false) => LineChange::Modified,
+        };
+
+        hunks.push(Hunk {
+            start: seen - *added,
+            lines: *added,
+            change,
+            removed: std::mem::take(removed),
+        });
+        *added = 0;
+    };
+
+    for op in diff::ops(&old, &new) {
+        match op {
+            DiffOp::Delete(i) => removed.push(old[i].to_string()),
+            DiffOp::Insert(_) => {
+                added += 1;
+                seen += 1;
+            }
+            DiffOp::Equal(..) => {
+                flush(&mut removed, &mut added, seen);
+                seen += 1;
+            }
+        }
+    }
+    flush(&mut removed, &mut added, seen);
+
+    hunks
+}
+
+/// Builds a gutter sign for every line of every hunk.
+///
+/// Deletions are marked on the line that followed them, or on the last line when they
+/// were at the end of the file.
+pub fn hunk_signs(hunks: &[Hunk], line_count: usize) -> Vec<Sign> {
+    let last_line = line_count.saturating_sub(1);
+
+    hunks
+        .iter()
+        .flat_map(|hunk| {
+            (hunk.start..hunk.start + hunk.lines.max(1)).map(move |line| Sign {
+                line: line.min(last_line),
+                kind: SignKind::Git(hunk.change),
+                tooltip: hunk.describe(),
+                action: SignAction::ShowHunk { line: hunk.start },
+            })
+        })
+        .collect()
+}
+
+/// Returns the start of the first hunk after `line`, wrapping around to the first one.
+pub fn next_hunk(hunks: &[Hunk], line: usize) -> Option<usize> {
+    hunks
+        .iter()
+        .map(|hunk| hunk.start)
+        .find(|start| *start > line)
+        .or_else(|| hunks.first().map(|hunk| hunk.start))
+}
+
+/// Returns the start of the last hunk before `line`, wrapping around to the last one.
+pub fn previous_hunk(hunks: &[Hunk], line: usize) -> Option<usize> {
+    hunks
+        .iter()
+        .rev()
+        .map(|hunk| hunk.start)
+        .find(|start| *start < line)
+        .or_else(|| hunks.last().map(|hunk| hunk.start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_find_added_modified_and_deleted_lines() {
+        let base = "a\nb\nc\nd\ne\n";
+        let current = "a\nB\nc\nnew\nd\n";
+
+        let hunks = diff(base, current);
+
+        assert_eq!(
+            hunks,
+            vec![
+                Hunk {
+                    start: 1,
+                    lines: 1,
+                    change: LineChange::Modified,
+                    removed: vec![String::from("b")],
+                },
+                Hunk {
+                    start: 3,
+                    lines: 1,
+                    change: LineChange::Added,
+                    removed: vec![],
+                },
+                Hunk {
+                    start: 5,
+                    lines: 0,
+                    change: LineChange::Deleted,
+                    removed: vec![String::from("e")],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_nothing_for_identical_text() {
+        assert!(diff("a\nb\n", "a\nb\n").is_empty());
+    }
+
+    #[test]
+    fn should_mark_deletions_at_the_end_on_the_last_line() {
+        let hunks = diff("a\nb\n", "a\n");
+
+        let signs = hunk_signs(&hunks, 2);
+
+        assert_eq!(signs.len(), 1);
+        assert_eq!(signs[0].line, 1);
+        assert_eq!(signs[0].kind, SignKind::Git(LineChange::Deleted));
+        assert_eq!(signs[0].tooltip, "-b");
+    }
+
+    #[test]
+    fn should_parse_porcelain_blame() {
+        let output = "1b2c3d4e 3 3 1\n\
+                      author Jane Doe\n\
+                      author-mail <jane@example.com>\n\
+                      author-time 1700000000\n\
+                      summary Fix the parser\n\
+                      filename src/main.rs\n\
+                      \tlet x = 1;\n";
+
+        let blame = parse_blame(output).unwrap();
+
+        assert_eq!(blame.author, "Jane Doe");
+        assert_eq!(blame.time, 1_700_000_000);
+        assert_eq!(blame.summary, "Fix the parser");
+        assert!(!blame.uncommitted);
+        assert!(blame.describe().starts_with("Jane Doe, "));
+    }
+
+    #[test]
+    fn should_describe_uncommitted_lines() {
+        let output = "0000000000000000000000000000000000000000 1 1 1\nauthor Not Committed Yet\n";
+
+        assert_eq!(parse_blame(output).unwrap().describe(), "Not committed yet");
+    }
+
+    #[test]
+    fn should_jump_between_hunks_wrapping_around() {
+        let hunks = diff("a\nb\nc\nd\n", "A\nb\nc\nD\n");
+
+        assert_eq!(next_hunk(&hunks, 0), Some(3));
+        assert_eq!(next_hunk(&hunks, 3), Some(0));
+        assert_eq!(previous_hunk(&hunks, 3), Some(0));
+        assert_eq!(previous_hunk(&hunks, 0), Some(3));
+    }
+}
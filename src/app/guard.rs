@@ -0,0 +1,113 @@
+//! Size guards for the Zack text editor.
+//!
+//! Some work is redone after every edit or every frame: diffing against git, blaming
+//! the cursor line, styling each char with its colors and diagnostics, wrapping long
+//! lines, drawing the minimap and the search marks of the scrollbar. That is fine for
+//! source files, but makes minified scripts or database dumps unusable. This module
+//! provides the [`Limits`] above which a document is too big for that work, set with
+//! `:set maxsize=<bytes>` and `:set maxline=<chars>`, and the [`Guard`] a document gets
+//! when it goes over them. Guarded documents skip the work and say so in the status
+//! line, as large file mode, until `:guard` turns it back on for them.
+
+use crate::app::{buffer::Buffer, file, options::Options};
+use std::fmt;
+
+/// Sizes above which a document is guarded; `0` means no limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Most bytes in the whole document.
+    pub max_size: usize,
+    /// Most chars in a single line.
+    pub max_line: usize,
+}
+
+/// Why a document is guarded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Guard {
+    /// The document has this many bytes.
+    Size(usize),
+    /// A line, zero-based, has this many chars.
+    LongLine { line: usize, len: usize },
+}
+
+impl Limits {
+    /// Returns the limits set in `options`.
+    pub fn from_options(options: &Options) -> Self {
+        Self {
+            max_size: options.maxsize,
+            max_line: options.maxline,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::from_options(&Options::default())
+    }
+}
+
+impl Guard {
+    /// Returns why `buffer` goes over `limits`, if it does.
+    ///
+    /// The size is checked first, since it is known without walking the lines.
+    pub fn check(buffer: &Buffer, limits: Limits) -> Option<Guard> {
+        let size = buffer.as_rope().len_bytes();
+        if limits.max_size > 0 && size > limits.max_size {
+            return Some(Guard::Size(size));
+        }
+
+        if limits.max_line == 0 {
+            return None;
+        }
+
+        buffer
+            .lines()
+            .map(|line| {
+                let len = line.len_chars();
+                len - usize::from(len > 0 && line.char(len - 1) == '\n')
+            })
+            .enumerate()
+            .find(|(_, len)| *len > limits.max_line)
+            .map(|(line, len)| Guard::LongLine { line, len })
+    }
+}
+
+impl fmt::Display for Guard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Guard::Size(size) => write!(f, "large file ({})", file::format_size(*size as u64)),
+            Guard::LongLine { line, len } => write!(f, "line {} has {len} chars", line + 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_size: usize, max_line: usize) -> Limits {
+        Limits { max_size, max_line }
+    }
+
+    #[test]
+    fn should_guard_large_documents_and_long_lines() {
+        let buffer = Buffer::new(String::from("short\nmuch longer line\n"));
+
+        assert_eq!(Guard::check(&buffer, limits(10, 0)), Some(Guard::Size(23)));
+        assert_eq!(
+            Guard::check(&buffer, limits(100, 10)),
+            Some(Guard::LongLine { line: 1, len: 16 })
+        );
+        assert_eq!(Guard::check(&buffer, limits(100, 20)), None);
+        assert_eq!(Guard::check(&buffer, limits(0, 0)), None);
+    }
+
+    #[test]
+    fn should_describe_why_a_document_is_guarded() {
+        assert_eq!(
+            Guard::LongLine { line: 0, len: 90 }.to_string(),
+            "line 1 has 90 chars"
+        );
+        assert_eq!(Guard::Size(2048).to_string(), "large file (2.0 KiB)");
+    }
+}
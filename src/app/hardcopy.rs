@@ -0,0 +1,60 @@
+//! Printing for the Zack text editor.
+//!
+//! `:hardcopy` lays the buffer, or the lines of a range, out in pages and pipes them to
+//! the print command set with `:set printcmd=<command>` (`lpr` by default), as a
+//! background job. Each page starts with a header naming the file and the page, and
+//! ends with a form feed; `:set printlines=<n>` sets how many lines a page holds, header
+//! included. With `:set printcolor`, the text keeps its colors as ANSI escape codes, for
+//! commands that understand them.
+
+/// Lines taken by the header of a page: the header itself and a blank line.
+const HEADER_LINES: usize = 2;
+
+/// Form feed, which makes printers start a new page.
+const FORM_FEED: char = '\x0c';
+
+/// Lays `text` out in pages of `page_lines` lines with a header naming `name`, and
+/// returns them with the number of pages.
+pub fn paginate(name: &str, text: &str, page_lines: usize) -> (String, usize) {
+    let lines: Vec<&str> = text.lines().collect();
+    let body_lines = page_lines.saturating_sub(HEADER_LINES).max(1);
+    let pages = lines.len().div_ceil(body_lines).max(1);
+    let mut output = String::new();
+
+    for page in 0..pages {
+        output.push_str(&format!("{name}    Page {} of {pages}\n\n", page + 1));
+
+        for line in lines.iter().skip(page * body_lines).take(body_lines) {
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        output.push(FORM_FEED);
+    }
+
+    (output, pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_text_in_pages_with_headers() {
+        let (output, pages) = paginate("notes.txt", "a\nb\nc\n", 4);
+
+        assert_eq!(pages, 2);
+        assert_eq!(
+            output,
+            "notes.txt    Page 1 of 2\n\na\nb\n\x0cnotes.txt    Page 2 of 2\n\nc\n\x0c"
+        );
+    }
+
+    #[test]
+    fn should_print_one_page_for_empty_text() {
+        assert_eq!(
+            paginate("[No Name]", "", 66),
+            (String::from("[No Name]    Page 1 of 1\n\n\x0c"), 1)
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Input history module for the Zack text editor.
+//!
+//! This module provides [`History`], the list of past entries of a prompt, walked with
+//! `Up` and `Down` like a shell history. The command line keeps one for commands and
+//! one for searches, and the prompt one for file names, one for line numbers and one of
+//! the files opened, which `:oldfiles` picks from and `--continue` reopens the newest of.
+//! They are saved to the state file on exit and loaded back on start, keyed by
+//! [`HistoryKind`].
+
+use crate::app::state;
+use serde_json::{Value, json};
+use std::{io, path::Path};
+
+/// Most entries kept per history; older ones are dropped.
+const MAX_ENTRIES: usize = 100;
+
+/// Key of the histories in the state file.
+const STATE_KEY: &str = "history";
+
+/// Which prompt a history belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HistoryKind {
+    Command,
+    Search,
+    Filename,
+    Line,
+    /// Files opened, as absolute paths.
+    Recent,
+}
+
+/// Past entries of a prompt, oldest first, and the position while walking them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History {
+    entries: Vec<String>,
+    /// Entry shown while walking the history, or `None` while editing a new one.
+    index: Option<usize>,
+    /// What was typed before walking the history, restored past the newest entry.
+    draft: String,
+}
+
+impl HistoryKind {
+    /// Returns the name the history is stored under.
+    pub fn key(&self) -> &'static str {
+        match self {
+            HistoryKind::Command => "command",
+            HistoryKind::Search => "search",
+            HistoryKind::Filename => "filename",
+            HistoryKind::Line => "line",
+            HistoryKind::Recent => "recent",
+        }
+    }
+}
+
+impl History {
+    /// Creates an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the entries, oldest first.
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Records a submitted entry as the newest one and stops walking the history.
+    ///
+    /// Empty entries are ignored, and an entry submitted again moves to the end instead
+    /// of appearing twice.
+    pub fn push(&mut self, entry: &str) {
+        self.reset();
+
+        if entry.trim().is_empty() {
+            return;
+        }
+
+        self.entries.retain(|existing| existing != entry);
+        self.entries.push(entry.to_string());
+
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+    }
+
+    /// Steps back to the previous entry, remembering `current` as the draft when
+    /// starting to walk. Returns `None` when there is no older entry.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let index = match self.index {
+            Some(0) => return None,
+            Some(index) => index - 1,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+        };
+
+        self.index = Some(index);
+        Some(&self.entries[index])
+    }
+
+    /// Steps forward to the next entry, or back to the draft past the newest one.
+    /// Returns `None` when not walking the history.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&str> {
+        let index = self.index?;
+
+        if index + 1 < self.entries.len() {
+            self.index = Some(index + 1);
+            Some(&self.entries[index + 1])
+        } else {
+            self.index = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Stops walking the history, e.g. when the prompt closes.
+    pub fn reset(&mut self) {
+        self.index = None;
+        self.draft.clear();
+    }
+
+    /// Loads the history of `kind` from the state file at `path`, empty when it was
+    /// never saved.
+    pub fn load(path: &Path, kind: HistoryKind) -> Self {
+        let entries = state::read(path, STATE_KEY)
+            .and_then(|histories| histories.get(kind.key()).cloned())
+            .and_then(|entries| match entries {
+                Value::Array(entries) => Some(entries),
+                _ => None,
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|entry| entry.as_str().map(String::from))
+            .collect();
+
+        Self {
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// Saves the given histories to the state file at `path`, replacing the stored ones.
+    pub fn save_all(path: &Path, histories: &[(HistoryKind, &History)]) -> io::Result<()> {
+        let mut stored = match state::read(path, STATE_KEY) {
+            Some(Value::Object(stored)) => stored,
+            _ => serde_json::Map::new(),
+        };
+
+        for (kind, history) in histories {
+            stored.insert(kind.key().to_string(), json!(history.entries));
+        }
+
+        state::write(path, STATE_KEY, Value::Object(stored))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    fn history_of(entries: &[&str]) -> History {
+        let mut history = History::new();
+        entries.iter().for_each(|entry| history.push(entry));
+
+        history
+    }
+
+    #[test]
+    fn should_walk_back_and_forth_restoring_the_draft() {
+        let mut history = history_of(&["w", "q"]);
+
+        assert_eq!(history.previous("typed"), Some("q"));
+        assert_eq!(history.previous("q"), Some("w"));
+        assert_eq!(history.previous("w"), None);
+        assert_eq!(history.next(), Some("q"));
+        assert_eq!(history.next(), Some("typed"));
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn should_move_repeated_entries_to_the_end_and_skip_empty_ones() {
+        let history = history_of(&["a", "b", "a", " "]);
+
+        assert_eq!(history.entries(), ["b", "a"]);
+    }
+
+    #[test]
+    fn should_save_and_load_histories_by_kind() {
+        let path = PathBuf::from("test_history_state.json");
+        let commands = history_of(&["w", "q"]);
+        let searches = history_of(&["foo"]);
+
+        History::save_all(
+            &path,
+            &[
+                (HistoryKind::Command, &commands),
+                (HistoryKind::Search, &searches),
+            ],
+        )
+        .unwrap();
+        let loaded = History::load(&path, HistoryKind::Command);
+        let missing = History::load(&path, HistoryKind::Filename);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.entries(), ["w", "q"]);
+        assert!(missing.entries().is_empty());
+    }
+}
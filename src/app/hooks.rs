@@ -0,0 +1,258 @@
+//! Hooks for the Zack text editor.
+//!
+//! This module provides [`Hook`], the moments of the editor others can subscribe to, and
+//! [`Hooks`], the autocommands run at them, as in Vim. The editor fires a hook when it
+//! handles the events leading to it: `BufReadPost` after opening a file, `BufEnter` when
+//! another buffer becomes active, `BufWritePre` and `BufWritePost` around each write,
+//! `ModeChanged`, `InsertEnter` and `InsertLeave` when the mode changes, and `CursorHold`
+//! once the keyboard has been left alone for `:set updatetime` milliseconds.
+//!
+//! Autocommands are read from the `[hooks]` section of the configuration file, one per
+//! line, or added for the session with `:autocmd`:
+//!
+//! ```text
+//! [hooks]
+//! BufWritePost *.rs make
+//! BufReadPost *.md set wrap
+//! ```
+//!
+//! The pattern is matched against the path of the active buffer as in EditorConfig files,
+//! so `*.rs` matches Rust files in any directory and `*` matches every buffer. Scripts
+//! subscribe to the same hooks with `on`.
+
+use crate::app::{editorconfig, filetype::CONFIG_FILE};
+use std::{fmt, path::Path};
+
+/// Section of the configuration file listing autocommands.
+pub const SECTION: &str = "hooks";
+
+/// The hooks, by name.
+const HOOKS: [(&str, Hook); 8] = [
+    ("BufReadPost", Hook::BufReadPost),
+    ("BufEnter", Hook::BufEnter),
+    ("BufWritePre", Hook::BufWritePre),
+    ("BufWritePost", Hook::BufWritePost),
+    ("ModeChanged", Hook::ModeChanged),
+    ("InsertEnter", Hook::InsertEnter),
+    ("InsertLeave", Hook::InsertLeave),
+    ("CursorHold", Hook::CursorHold),
+];
+
+/// A moment of the editor autocommands and scripts can run at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Hook {
+    /// A file was read into a buffer.
+    BufReadPost,
+    /// Another buffer became active.
+    BufEnter,
+    /// The buffer is about to be written; changes made now are written with it.
+    BufWritePre,
+    /// The buffer was written.
+    BufWritePost,
+    /// The editor entered another mode.
+    ModeChanged,
+    /// The editor entered insert mode.
+    InsertEnter,
+    /// The editor left insert mode.
+    InsertLeave,
+    /// No key was typed for `updatetime` milliseconds.
+    CursorHold,
+}
+
+/// A command run when `hook` fires in a buffer whose path matches `pattern`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Autocmd {
+    pub hook: Hook,
+    pub pattern: String,
+    pub command: String,
+}
+
+/// The autocommands of the session.
+#[derive(Debug, Default)]
+pub struct Hooks {
+    autocmds: Vec<Autocmd>,
+    /// Set while hooks run, so the ones they would fire in turn are skipped.
+    pub running: bool,
+}
+
+impl Hook {
+    /// Returns the hook called `name`, ignoring case as Vim does.
+    pub fn from_name(name: &str) -> Option<Self> {
+        HOOKS
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|(_, hook)| *hook)
+    }
+
+    /// Returns the name of the hook.
+    pub fn name(self) -> &'static str {
+        HOOKS
+            .iter()
+            .find(|(_, hook)| *hook == self)
+            .map_or("", |(name, _)| name)
+    }
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Autocmd {
+    /// Parses `Hook pattern command`, as in the configuration file and `:autocmd`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut words = text.trim().splitn(3, char::is_whitespace);
+        let (Some(hook), Some(pattern), Some(command)) = (words.next(), words.next(), words.next())
+        else {
+            return Err(format!("Expected `hook pattern command`: {text}"));
+        };
+        let hook = Hook::from_name(hook).ok_or_else(|| format!("Unknown hook: {hook}"))?;
+        let command = command.trim().trim_start_matches(':');
+        if command.is_empty() {
+            return Err(format!("Expected `hook pattern command`: {text}"));
+        }
+
+        Ok(Self {
+            hook,
+            pattern: pattern.to_string(),
+            command: command.to_string(),
+        })
+    }
+
+    /// Returns `true` if the autocommand runs for `hook` in a buffer of file `path`. Only
+    /// `*` matches buffers without a file.
+    fn matches(&self, hook: Hook, path: Option<&Path>) -> bool {
+        self.hook == hook
+            && match path {
+                _ if self.pattern == "*" => true,
+                Some(path) => editorconfig::section_matches(&self.pattern, &path.to_string_lossy()),
+                None => false,
+            }
+    }
+}
+
+impl Hooks {
+    /// Creates an empty set of autocommands.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the autocommands read from the configuration file with `autocmds`.
+    pub fn set(&mut self, autocmds: Vec<Autocmd>) {
+        self.autocmds = autocmds;
+    }
+
+    /// Adds `autocmd`, run after the ones already there.
+    pub fn add(&mut self, autocmd: Autocmd) {
+        self.autocmds.push(autocmd);
+    }
+
+    /// Returns the commands to run for `hook` in a buffer of file `path`, in order.
+    pub fn commands(&self, hook: Hook, path: Option<&Path>) -> Vec<String> {
+        self.autocmds
+            .iter()
+            .filter(|autocmd| autocmd.matches(hook, path))
+            .map(|autocmd| autocmd.command.clone())
+            .collect()
+    }
+
+    /// Lists the autocommands, one per line, as `:autocmd` shows them.
+    pub fn list(&self) -> String {
+        match self.autocmds.is_empty() {
+            true => String::from("No autocommands"),
+            false => self
+                .autocmds
+                .iter()
+                .map(|autocmd| format!("{} {} {}", autocmd.hook, autocmd.pattern, autocmd.command))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// Reads the `[hooks]` section of the configuration file `text`. Returns its
+/// autocommands with the errors of the lines that could not be read.
+pub fn parse_config(text: &str) -> (Vec<Autocmd>, Vec<String>) {
+    let mut autocmds = vec![];
+    let mut errors = vec![];
+    let mut in_section = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == SECTION;
+            continue;
+        }
+
+        if in_section {
+            match Autocmd::parse(line) {
+                Ok(autocmd) => autocmds.push(autocmd),
+                Err(err) => errors.push(format!("{CONFIG_FILE}:{}: {err}", index + 1)),
+            }
+        }
+    }
+
+    (autocmds, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_autocommands_from_the_config_file() {
+        let (autocmds, errors) = parse_config(
+            "[filetype.rust]\ntabstop = 4\n[hooks]\n# build on save\n\
+             bufwritepost *.rs :make\nBufEnter * set wrap\nBufFoo * w\nCursorHold *\n",
+        );
+
+        assert_eq!(
+            autocmds,
+            vec![
+                Autocmd {
+                    hook: Hook::BufWritePost,
+                    pattern: String::from("*.rs"),
+                    command: String::from("make"),
+                },
+                Autocmd {
+                    hook: Hook::BufEnter,
+                    pattern: String::from("*"),
+                    command: String::from("set wrap"),
+                },
+            ]
+        );
+        assert_eq!(
+            errors,
+            vec![
+                String::from("config:7: Unknown hook: BufFoo"),
+                String::from("config:8: Expected `hook pattern command`: CursorHold *"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_run_the_commands_matching_the_hook_and_path() {
+        let mut hooks = Hooks::new();
+        hooks.set(parse_config("[hooks]\nBufWritePost *.rs make\nBufWritePost * echo\n").0);
+        hooks.add(Autocmd::parse("BufWritePre src/*.rs count").unwrap());
+
+        assert_eq!(
+            hooks.commands(Hook::BufWritePost, Some(Path::new("/tmp/main.rs"))),
+            vec![String::from("make"), String::from("echo")]
+        );
+        assert_eq!(
+            hooks.commands(Hook::BufWritePost, None),
+            vec![String::from("echo")]
+        );
+        assert_eq!(
+            hooks.commands(Hook::BufWritePre, Some(Path::new("src/lib.rs"))),
+            vec![String::from("count")]
+        );
+        assert!(hooks.commands(Hook::BufEnter, None).is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+//! Background job module for the Zack text editor.
+//!
+//! This module provides [`Jobs`], which runs long tasks (shell commands, searches,
+//! formatters, ...) on background threads so they never block the UI. Every job gets a
+//! [`JobId`], can report progress through its [`JobHandle`] and can be cancelled.
+//!
+//! Workers talk to the editor only through [`JobEvent`]s sent on the event queue: the
+//! progress they report, and the events they return when they finish. Results of a
+//! cancelled job are dropped.
+
+use crate::event::{AppEvent, Event};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Instant,
+};
+
+/// Frames of the spinner shown while jobs run.
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Identifies a job for progress reports and cancellation.
+pub type JobId = u64;
+
+/// Events reported by running jobs, or sent to control them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JobEvent {
+    /// A job reports what it is doing, and how far along it is when known.
+    Progress {
+        id: JobId,
+        message: String,
+        percent: Option<u8>,
+    },
+    /// A job finished, producing `events` for the editor.
+    Finished { id: JobId, events: Vec<AppEvent> },
+    /// Asks a job to stop.
+    Cancel(JobId),
+    /// Asks every running job to stop.
+    CancelAll,
+}
+
+/// A running job, as seen by the editor.
+#[derive(Debug)]
+pub struct Job {
+    pub id: JobId,
+    /// Short description, like the command being run.
+    pub name: String,
+    /// Last progress message reported.
+    pub message: Option<String>,
+    /// Last progress percentage reported.
+    pub percent: Option<u8>,
+    pub started: Instant,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// The worker side of a job, used to report progress and check for cancellation.
+#[derive(Clone, Debug)]
+pub struct JobHandle {
+    id: JobId,
+    cancelled: Arc<AtomicBool>,
+    sender: mpsc::Sender<Event>,
+}
+
+/// Starts jobs and tracks the running ones.
+#[derive(Debug)]
+pub struct Jobs {
+    next_id: JobId,
+    running: Vec<Job>,
+    /// Used by the workers to deliver progress and results.
+    sender: mpsc::Sender<Event>,
+}
+
+impl JobHandle {
+    /// Returns the id of the job.
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// Returns `true` once the job was asked to stop; workers should return early.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Reports what the job is doing, with a percentage when it is known.
+    pub fn progress(&self, message: impl Into<String>, percent: Option<u8>) {
+        let _ = self
+            .sender
+            .send(Event::App(AppEvent::Job(JobEvent::Progress {
+                id: self.id,
+                message: message.into(),
+                percent,
+            })));
+    }
+}
+
+impl Jobs {
+    /// Creates an empty job list that delivers job events through `sender`.
+    pub fn new(sender: mpsc::Sender<Event>) -> Self {
+        Self {
+            next_id: 1,
+            running: vec![],
+            sender,
+        }
+    }
+
+    /// Runs `work` on a background thread and returns the id of the new job.
+    ///
+    /// The events returned by `work` are dispatched when it finishes, unless the job
+    /// was cancelled in the meantime.
+    pub fn spawn<F>(&mut self, name: impl Into<String>, work: F) -> JobId
+    where
+        F: FnOnce(&JobHandle) -> Vec<AppEvent> + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let handle = JobHandle {
+            id,
+            cancelled: Arc::clone(&cancelled),
+            sender: self.sender.clone(),
+        };
+
+        self.running.push(Job {
+            id,
+            name: name.into(),
+            message: None,
+            percent: None,
+            started: Instant::now(),
+            cancelled,
+        });
+
+        thread::spawn(move || {
+            let events = work(&handle);
+            let _ = handle
+                .sender
+                .send(Event::App(AppEvent::Job(JobEvent::Finished { id, events })));
+        });
+
+        id
+    }
+
+    /// Returns the running jobs, oldest first.
+    pub fn running(&self) -> &[Job] {
+        &self.running
+    }
+
+    /// Returns the spinner frame to show while jobs run, or `None` when idle.
+    pub fn spinner(&self) -> Option<char> {
+        let oldest = self.running.first()?;
+        let frame = oldest.started.elapsed().as_millis() / 100;
+
+        Some(SPINNER[frame as usize % SPINNER.len()])
+    }
+
+    /// Describes the oldest running job for the status line, like `sort 40% reading`.
+    pub fn status(&self) -> Option<String> {
+        let job = self.running.first()?;
+        let mut status = job.name.clone();
+
+        if let Some(percent) = job.percent {
+            status.push_str(&format!(" {percent}%"));
+        }
+        if let Some(message) = &job.message {
+            status.push_str(&format!(" {message}"));
+        }
+        if self.running.len() > 1 {
+            status.push_str(&format!(" (+{})", self.running.len() - 1));
+        }
+
+        Some(status)
+    }
+
+    /// Handles a `JobEvent` and returns any follow-up `AppEvent`s.
+    pub fn handle_event(&mut self, event: JobEvent) -> Vec<AppEvent> {
+        let mut events = vec![];
+
+        match event {
+            JobEvent::Progress {
+                id,
+                message,
+                percent,
+            } => {
+                if let Some(job) = self.running.iter_mut().find(|job| job.id == id) {
+                    job.message = Some(message);
+                    job.percent = percent;
+                }
+            }
+            JobEvent::Finished {
+                id,
+                events: results,
+            } => {
+                if let Some(index) = self.running.iter().position(|job| job.id == id) {
+                    let job = self.running.remove(index);
+
+                    if !job.cancelled.load(Ordering::Relaxed) {
+                        events.extend(results);
+                    }
+                }
+            }
+            JobEvent::Cancel(id) => match self.running.iter().find(|job| job.id == id) {
+                Some(job) => job.cancelled.store(true, Ordering::Relaxed),
+                None => tracing::warn!("No job with id {id}"),
+            },
+            JobEvent::CancelAll => {
+                for job in &self.running {
+                    job.cancelled.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Receives job events until the given job finishes, applying all of them.
+    fn run_until_finished(jobs: &mut Jobs, receiver: &mpsc::Receiver<Event>) -> Vec<AppEvent> {
+        loop {
+            let Event::App(AppEvent::Job(event)) = receiver.recv().unwrap() else {
+                continue;
+            };
+            let finished = matches!(event, JobEvent::Finished { .. });
+            let events = jobs.handle_event(event);
+
+            if finished {
+                return events;
+            }
+        }
+    }
+
+    #[test]
+    fn should_deliver_results_and_forget_finished_jobs() {
+        let (sender, receiver) = mpsc::channel();
+        let mut jobs = Jobs::new(sender);
+
+        let id = jobs.spawn("work", |_| vec![AppEvent::Quit]);
+
+        assert_eq!(id, 1);
+        assert!(jobs.spinner().is_some());
+        assert_eq!(
+            run_until_finished(&mut jobs, &receiver),
+            vec![AppEvent::Quit]
+        );
+        assert!(jobs.running().is_empty());
+        assert_eq!(jobs.spinner(), None);
+    }
+
+    #[test]
+    fn should_track_progress_of_running_jobs() {
+        let (sender, _receiver) = mpsc::channel();
+        let mut jobs = Jobs::new(sender);
+        let (release, wait) = mpsc::channel::<()>();
+
+        let id = jobs.spawn("grep", move |_| {
+            let _ = wait.recv();
+            vec![]
+        });
+        jobs.handle_event(JobEvent::Progress {
+            id,
+            message: String::from("src/"),
+            percent: Some(40),
+        });
+
+        assert_eq!(jobs.status(), Some(String::from("grep 40% src/")));
+        let _ = release.send(());
+    }
+
+    #[test]
+    fn should_drop_results_of_cancelled_jobs() {
+        let (sender, receiver) = mpsc::channel();
+        let mut jobs = Jobs::new(sender);
+        let (release, wait) = mpsc::channel::<()>();
+
+        let id = jobs.spawn("slow", move |handle| {
+            let _ = wait.recv();
+            assert!(handle.is_cancelled());
+            vec![AppEvent::Quit]
+        });
+
+        jobs.handle_event(JobEvent::Cancel(id));
+        release.send(()).unwrap();
+
+        assert!(run_until_finished(&mut jobs, &receiver).is_empty());
+    }
+}
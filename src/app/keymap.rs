@@ -0,0 +1,722 @@
+//! Keymap registry for the Zack text editor.
+//!
+//! Every component documents the keys it handles as a list of [`Binding`]s kept next to
+//! the code handling them, like [`normal::BINDINGS`]. This module gathers those lists in
+//! [`sections`], grouped by mode and component, and renders them as the help screen
+//! opened with `F1` or `:help`.
+//!
+//! The modes do not match keys themselves: they look the keys typed up in a [`Keymap`],
+//! which binds sequences of keys to [`Action`]s, one table per [`KeymapMode`]. A keymap
+//! is made of layers, looked up from the top down so each one can add or shadow
+//! bindings of those below it: the bindings local to the buffer, then those of the user,
+//! then the defaults. The user's bindings are read from the `keymap` file of the
+//! configuration directory, one per line, as in:
+//!
+//! ```text
+//! # mode  keys   action
+//! normal  <C-s>  :w
+//! normal  Q      quit
+//! ```
+//!
+//! `:map mode keys action` adds a binding for the session, and `:map --buffer` one for
+//! the buffer only. Actions are named as in [`Action::from_name`], or are a command line
+//! starting with `:`. Keys use the notation of [`parse_keys`]. An exact match wins over
+//! longer sequences starting the same way; insert mode only looks single keys up, and
+//! types the characters bound to nothing.
+
+use crate::{
+    app::{
+        modes::{EditorMode, insert, normal, operator_pending, visual},
+        operator::Operator,
+    },
+    event::AppEvent,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+/// Name of the file of the configuration directory holding the user's bindings.
+pub const KEYMAP_FILE: &str = "keymap";
+
+/// Title of the message pane showing the help screen.
+pub const HELP_TITLE: &str = ":help";
+
+/// A key, or a sequence of keys, and what it does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub keys: &'static str,
+    pub description: &'static str,
+}
+
+/// Keys handled whatever the mode is.
+pub const GLOBAL_BINDINGS: &[Binding] = &[Binding {
+    keys: "F1",
+    description: "Show or hide this help",
+}];
+
+/// Keys handled while the completion popup is open.
+pub const COMPLETION_BINDINGS: &[Binding] = &[
+    Binding {
+        keys: "Ctrl-N / Down",
+        description: "Highlight the next candidate",
+    },
+    Binding {
+        keys: "Ctrl-P / Up",
+        description: "Highlight the previous candidate",
+    },
+    Binding {
+        keys: "Enter / Tab",
+        description: "Insert the highlighted candidate",
+    },
+    Binding {
+        keys: "Esc",
+        description: "Close the popup",
+    },
+];
+
+/// Keys handled while typing in the command line or a prompt.
+pub const PROMPT_BINDINGS: &[Binding] = &[
+    Binding {
+        keys: "Enter",
+        description: "Run the command or accept the input",
+    },
+    Binding {
+        keys: "Esc",
+        description: "Cancel",
+    },
+    Binding {
+        keys: "Up / Down",
+        description: "Recall older or newer entries",
+    },
+    Binding {
+        keys: "Tab",
+        description: "Complete the path in file name prompts",
+    },
+    Binding {
+        keys: "Home / Ctrl-A, End / Ctrl-E",
+        description: "Move to the start or the end",
+    },
+    Binding {
+        keys: "Ctrl-U / Ctrl-W",
+        description: "Delete before the cursor, or the word before it",
+    },
+];
+
+/// Keys handled by the message pane, where this help is shown.
+pub const MESSAGE_PANE_BINDINGS: &[Binding] = &[
+    Binding {
+        keys: "j / k, Down / Up",
+        description: "Scroll by one line",
+    },
+    Binding {
+        keys: "Ctrl-D / Ctrl-U, PageDown / PageUp",
+        description: "Scroll by half a page",
+    },
+    Binding {
+        keys: "g / G",
+        description: "Jump to the top or the bottom",
+    },
+    Binding {
+        keys: "q, Esc, Enter, F1",
+        description: "Close the pane",
+    },
+];
+
+/// Returns every group of bindings with its title, in the order of the help screen.
+pub fn sections() -> [(&'static str, &'static [Binding]); 8] {
+    [
+        ("Everywhere", GLOBAL_BINDINGS),
+        ("Normal mode", normal::BINDINGS),
+        ("Insert mode", insert::BINDINGS),
+        ("Visual mode", visual::BINDINGS),
+        ("Operator-pending mode", operator_pending::BINDINGS),
+        ("Completion popup", COMPLETION_BINDINGS),
+        ("Command line and prompts", PROMPT_BINDINGS),
+        ("Message pane", MESSAGE_PANE_BINDINGS),
+    ]
+}
+
+/// Returns the event opening the help screen in the message pane.
+pub fn help_event() -> AppEvent {
+    AppEvent::ShowMessage {
+        title: String::from(HELP_TITLE),
+        text: help_text(),
+    }
+}
+
+/// Renders the help screen: a titled block of aligned bindings for each section.
+pub fn help_text() -> String {
+    let width = sections()
+        .iter()
+        .flat_map(|(_, bindings)| bindings.iter())
+        .map(|binding| binding.keys.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let blocks: Vec<String> = sections()
+        .iter()
+        .map(|(title, bindings)| {
+            let lines: Vec<String> = bindings
+                .iter()
+                .map(|binding| format!("  {:<width$}  {}", binding.keys, binding.description))
+                .collect();
+
+            format!("{title}\n{}", lines.join("\n"))
+        })
+        .collect();
+
+    blocks.join("\n\n")
+}
+
+/// A mode with its own table of bindings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeymapMode {
+    Normal,
+    Insert,
+    Visual,
+    OperatorPending,
+}
+
+impl KeymapMode {
+    /// Returns the mode called `name` in the keymap file and `:map`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" | "n" => Some(KeymapMode::Normal),
+            "insert" | "i" => Some(KeymapMode::Insert),
+            "visual" | "v" => Some(KeymapMode::Visual),
+            "operator" | "o" => Some(KeymapMode::OperatorPending),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the mode, as written in the keymap file.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeymapMode::Normal => "normal",
+            KeymapMode::Insert => "insert",
+            KeymapMode::Visual => "visual",
+            KeymapMode::OperatorPending => "operator",
+        }
+    }
+
+    /// Returns the table used in `mode`.
+    pub fn of(mode: EditorMode) -> Self {
+        match mode {
+            EditorMode::Normal => KeymapMode::Normal,
+            EditorMode::Insert { .. } => KeymapMode::Insert,
+            EditorMode::Visual { .. } => KeymapMode::Visual,
+            EditorMode::OperatorPending { .. } => KeymapMode::OperatorPending,
+        }
+    }
+}
+
+/// What a sequence of keys does; each mode turns the actions it knows into events and
+/// ignores the others.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    /// Up to the start of the line, as the target of an operator.
+    LineStart,
+    /// Up to the end of the line, as the target of an operator.
+    LineEnd,
+    /// The word under the cursor, as the target of an operator.
+    InnerWord,
+    /// The word under the cursor with its blanks, as the target of an operator.
+    AWord,
+    NextHunk,
+    PreviousHunk,
+    NextWindow,
+    PreviousWindow,
+    OnlyWindow,
+    ToggleFold,
+    OpenFold,
+    CloseFold,
+    OpenAllFolds,
+    Insert,
+    Append,
+    Visual,
+    VisualLine,
+    /// Goes back to normal mode, cancelling what was pending.
+    Normal,
+    /// Waits for the target of the operator.
+    Operator(Operator),
+    Put,
+    /// Puts whole lines indented like the cursor line, as `]p`.
+    PutIndented,
+    CommandLine,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    /// Searches for the visual selection.
+    SearchSelection,
+    /// Copies the visual selection to the system clipboard.
+    Copy,
+    Undo,
+    Redo,
+    Save,
+    OpenFile,
+    GotoLine,
+    Suspend,
+    Quit,
+    Complete,
+    DeleteBackward,
+    Newline,
+    /// Inserts a tab, or spaces with `expandtab`.
+    InsertTab,
+    /// Inserts the next key literally, or the code point typed after `u`.
+    InsertLiteral,
+    /// Runs a command line, without its `:`.
+    Command(String),
+}
+
+/// Names of the actions in the keymap file and `:map`.
+const ACTION_NAMES: &[(&str, Action)] = &[
+    ("move-left", Action::MoveLeft),
+    ("move-right", Action::MoveRight),
+    ("move-up", Action::MoveUp),
+    ("move-down", Action::MoveDown),
+    ("line-start", Action::LineStart),
+    ("line-end", Action::LineEnd),
+    ("inner-word", Action::InnerWord),
+    ("a-word", Action::AWord),
+    ("next-hunk", Action::NextHunk),
+    ("previous-hunk", Action::PreviousHunk),
+    ("next-window", Action::NextWindow),
+    ("previous-window", Action::PreviousWindow),
+    ("only-window", Action::OnlyWindow),
+    ("toggle-fold", Action::ToggleFold),
+    ("open-fold", Action::OpenFold),
+    ("close-fold", Action::CloseFold),
+    ("open-all-folds", Action::OpenAllFolds),
+    ("insert", Action::Insert),
+    ("append", Action::Append),
+    ("visual", Action::Visual),
+    ("visual-line", Action::VisualLine),
+    ("normal", Action::Normal),
+    ("delete", Action::Operator(Operator::Delete)),
+    ("change", Action::Operator(Operator::Change)),
+    ("yank", Action::Operator(Operator::Yank)),
+    ("lowercase", Action::Operator(Operator::Lowercase)),
+    ("uppercase", Action::Operator(Operator::Uppercase)),
+    ("toggle-case", Action::Operator(Operator::ToggleCase)),
+    ("format", Action::Operator(Operator::Format)),
+    ("put", Action::Put),
+    ("put-indented", Action::PutIndented),
+    ("command-line", Action::CommandLine),
+    ("search", Action::Search),
+    ("next-match", Action::NextMatch),
+    ("previous-match", Action::PreviousMatch),
+    ("search-selection", Action::SearchSelection),
+    ("copy", Action::Copy),
+    ("undo", Action::Undo),
+    ("redo", Action::Redo),
+    ("save", Action::Save),
+    ("open-file", Action::OpenFile),
+    ("goto-line", Action::GotoLine),
+    ("suspend", Action::Suspend),
+    ("quit", Action::Quit),
+    ("complete", Action::Complete),
+    ("delete-backward", Action::DeleteBackward),
+    ("newline", Action::Newline),
+    ("insert-tab", Action::InsertTab),
+    ("insert-literal", Action::InsertLiteral),
+];
+
+impl Action {
+    /// Returns the action called `name`, like `undo` or `next-window`, or the command
+    /// line after `:`, as in `:w`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(command) = name.strip_prefix(':') {
+            return (!command.is_empty()).then(|| Action::Command(command.to_string()));
+        }
+
+        ACTION_NAMES
+            .iter()
+            .find(|(action_name, _)| *action_name == name)
+            .map(|(_, action)| action.clone())
+    }
+}
+
+/// The bindings of one layer, by sequence of normalized keys.
+pub type Layer = HashMap<Vec<KeyEvent>, Action>;
+
+/// What the keys typed so far lead to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Lookup {
+    /// They are bound to the action.
+    Action(Action),
+    /// They start longer sequences; more keys are needed.
+    Prefix,
+    /// Nothing is bound to them.
+    Unbound,
+}
+
+/// The bindings of a mode, as layers looked up from the top down.
+#[derive(Clone, Debug)]
+pub struct Keymap<'a> {
+    /// The layers, the top one first.
+    layers: Vec<&'a Layer>,
+}
+
+impl Keymap<'_> {
+    /// Looks `keys` up, from the top layer down.
+    pub fn lookup(&self, keys: &[KeyEvent]) -> Lookup {
+        let keys = normalize_keys(keys);
+
+        if let Some(action) = self.layers.iter().find_map(|layer| layer.get(&keys)) {
+            return Lookup::Action(action.clone());
+        }
+
+        let prefix = self
+            .layers
+            .iter()
+            .flat_map(|layer| layer.keys())
+            .any(|bound| bound.len() > keys.len() && bound.starts_with(&keys));
+
+        match prefix {
+            true => Lookup::Prefix,
+            false => Lookup::Unbound,
+        }
+    }
+}
+
+/// The bindings of every mode, from the defaults and the user.
+#[derive(Debug)]
+pub struct Keymaps {
+    defaults: HashMap<KeymapMode, Layer>,
+    /// Bindings of the keymap file and `:map`.
+    pub user: HashMap<KeymapMode, Layer>,
+}
+
+impl Default for Keymaps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keymaps {
+    /// Creates the `Keymaps` with the default bindings only.
+    pub fn new() -> Self {
+        let modes = [
+            KeymapMode::Normal,
+            KeymapMode::Insert,
+            KeymapMode::Visual,
+            KeymapMode::OperatorPending,
+        ];
+
+        Self {
+            defaults: modes.map(|mode| (mode, defaults(mode))).into(),
+            user: HashMap::new(),
+        }
+    }
+
+    /// Returns the bindings of `mode`, with those of `buffer`, local to the active
+    /// buffer, on top.
+    pub fn get<'a>(
+        &'a self,
+        mode: KeymapMode,
+        buffer: &'a HashMap<KeymapMode, Layer>,
+    ) -> Keymap<'a> {
+        let layers = [
+            buffer.get(&mode),
+            self.user.get(&mode),
+            self.defaults.get(&mode),
+        ];
+
+        Keymap {
+            layers: layers.into_iter().flatten().collect(),
+        }
+    }
+}
+
+/// Returns the default bindings of `mode`.
+fn defaults(mode: KeymapMode) -> Layer {
+    let bindings: &[(&str, Action)] = match mode {
+        KeymapMode::Normal => &[
+            ("h", Action::MoveLeft),
+            ("l", Action::MoveRight),
+            ("j", Action::MoveDown),
+            ("k", Action::MoveUp),
+            ("]c", Action::NextHunk),
+            ("[c", Action::PreviousHunk),
+            ("<C-w>w", Action::NextWindow),
+            ("<C-w><C-w>", Action::NextWindow),
+            ("<C-w>W", Action::PreviousWindow),
+            ("<C-w>o", Action::OnlyWindow),
+            ("<C-w><C-o>", Action::OnlyWindow),
+            ("za", Action::ToggleFold),
+            ("zo", Action::OpenFold),
+            ("zc", Action::CloseFold),
+            ("zR", Action::OpenAllFolds),
+            ("i", Action::Insert),
+            ("a", Action::Append),
+            ("v", Action::Visual),
+            ("V", Action::VisualLine),
+            ("d", Action::Operator(Operator::Delete)),
+            ("c", Action::Operator(Operator::Change)),
+            ("y", Action::Operator(Operator::Yank)),
+            ("gu", Action::Operator(Operator::Lowercase)),
+            ("gU", Action::Operator(Operator::Uppercase)),
+            ("g~", Action::Operator(Operator::ToggleCase)),
+            ("gq", Action::Operator(Operator::Format)),
+            ("p", Action::Put),
+            ("]p", Action::PutIndented),
+            (":", Action::CommandLine),
+            ("/", Action::Search),
+            ("n", Action::NextMatch),
+            ("N", Action::PreviousMatch),
+            ("u", Action::Undo),
+            ("<C-r>", Action::Redo),
+            ("<C-s>", Action::Save),
+            ("<C-p>", Action::OpenFile),
+            ("<C-g>", Action::GotoLine),
+            ("<C-z>", Action::Suspend),
+            ("q", Action::Quit),
+            ("<Esc>", Action::Quit),
+            ("<C-c>", Action::Quit),
+            ("<C-C>", Action::Quit),
+        ],
+        KeymapMode::Insert => &[
+            ("<Esc>", Action::Normal),
+            ("<Left>", Action::MoveLeft),
+            ("<Right>", Action::MoveRight),
+            ("<Up>", Action::MoveUp),
+            ("<Down>", Action::MoveDown),
+            ("<C-n>", Action::Complete),
+            ("<BS>", Action::DeleteBackward),
+            ("<Enter>", Action::Newline),
+            ("<Tab>", Action::InsertTab),
+            ("<C-v>", Action::InsertLiteral),
+            ("<C-s>", Action::Save),
+        ],
+        KeymapMode::Visual => &[
+            ("h", Action::MoveLeft),
+            ("<Left>", Action::MoveLeft),
+            ("l", Action::MoveRight),
+            ("<Right>", Action::MoveRight),
+            ("j", Action::MoveDown),
+            ("<Down>", Action::MoveDown),
+            ("k", Action::MoveUp),
+            ("<Up>", Action::MoveUp),
+            ("*", Action::SearchSelection),
+            ("Y", Action::Copy),
+            (":", Action::CommandLine),
+            ("v", Action::Visual),
+            ("V", Action::VisualLine),
+            ("<Esc>", Action::Normal),
+        ],
+        KeymapMode::OperatorPending => &[
+            ("h", Action::MoveLeft),
+            ("<Left>", Action::MoveLeft),
+            ("l", Action::MoveRight),
+            ("<Right>", Action::MoveRight),
+            ("j", Action::MoveDown),
+            ("<Down>", Action::MoveDown),
+            ("k", Action::MoveUp),
+            ("<Up>", Action::MoveUp),
+            ("0", Action::LineStart),
+            ("$", Action::LineEnd),
+            ("iw", Action::InnerWord),
+            ("aw", Action::AWord),
+            ("<Esc>", Action::Normal),
+        ],
+    };
+
+    bindings
+        .iter()
+        .map(|(keys, action)| (normalize_keys(&parse_keys(keys)), action.clone()))
+        .collect()
+}
+
+/// Drops what tells keys apart without changing what they type: `Shift` on characters,
+/// which already are upper case, and the kind and state of the event.
+fn normalize_keys(keys: &[KeyEvent]) -> Vec<KeyEvent> {
+    keys.iter()
+        .map(|key| {
+            let modifiers = match key.code {
+                KeyCode::Char(_) => key.modifiers - KeyModifiers::SHIFT,
+                _ => key.modifiers,
+            };
+            KeyEvent::new(key.code, modifiers)
+        })
+        .collect()
+}
+
+/// Adds a binding of `keys` to `action` in `mode` to `layers`.
+pub fn bind(
+    layers: &mut HashMap<KeymapMode, Layer>,
+    mode: KeymapMode,
+    keys: &[KeyEvent],
+    action: Action,
+) {
+    layers
+        .entry(mode)
+        .or_default()
+        .insert(normalize_keys(keys), action);
+}
+
+/// Parses a binding written `mode keys action`, as in the keymap file and `:map`.
+pub fn parse_binding(text: &str) -> Result<(KeymapMode, Vec<KeyEvent>, Action), String> {
+    let mut words = text.split_whitespace();
+    let (Some(mode), Some(keys)) = (words.next(), words.next()) else {
+        return Err(format!("Expected `mode keys action`: {text}"));
+    };
+    let action = words.collect::<Vec<_>>().join(" ");
+    if action.is_empty() {
+        return Err(format!("Expected `mode keys action`: {text}"));
+    }
+
+    let mode = KeymapMode::from_name(mode).ok_or_else(|| format!("Unknown mode: {mode}"))?;
+    let action = Action::from_name(&action).ok_or_else(|| format!("Unknown action: {action}"))?;
+
+    Ok((mode, parse_keys(keys), action))
+}
+
+/// Reads the bindings of the keymap file `text`, skipping blank lines and `#` comments.
+/// Returns them with the errors of the lines that could not be read.
+pub fn parse_file(text: &str) -> (HashMap<KeymapMode, Layer>, Vec<String>) {
+    let mut layers = HashMap::new();
+    let mut errors = vec![];
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_binding(line) {
+            Ok((mode, keys, action)) => bind(&mut layers, mode, &keys, action),
+            Err(err) => errors.push(format!("{KEYMAP_FILE}:{}: {err}", index + 1)),
+        }
+    }
+
+    (layers, errors)
+}
+
+/// Turns key notation into key events: characters stand for themselves, and `<Name>`
+/// for a special key: `<Esc>`, `<Enter>`, `<Tab>`, `<BS>`, `<Del>`, `<Up>`, `<Down>`,
+/// `<Left>`, `<Right>`, `<F1>` to `<F12>`, `<lt>` for `<` itself, and `<C-x>` for `x`
+/// with `Ctrl`.
+pub fn parse_keys(keys: &str) -> Vec<KeyEvent> {
+    let mut events = vec![];
+    let mut rest = keys;
+
+    while let Some(c) = rest.chars().next() {
+        let special = rest
+            .strip_prefix('<')
+            .and_then(|inner| inner.split_once('>'))
+            .and_then(|(name, after)| Some((special_key(name)?, after)));
+
+        match special {
+            Some((key, after)) => {
+                events.push(key);
+                rest = after;
+            }
+            None => {
+                events.push(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    events
+}
+
+/// Returns the key called `name` in the `<Name>` notation.
+fn special_key(name: &str) -> Option<KeyEvent> {
+    if let Some(key) = name.strip_prefix("C-") {
+        let mut chars = key.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+        return Some(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+
+    let code = match name {
+        "Esc" => KeyCode::Esc,
+        "Enter" | "CR" => KeyCode::Enter,
+        "Tab" => KeyCode::Tab,
+        "BS" => KeyCode::Backspace,
+        "Del" => KeyCode::Delete,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "lt" => KeyCode::Char('<'),
+        _ => KeyCode::F(
+            name.strip_prefix('F')?
+                .parse()
+                .ok()
+                .filter(|n| (1..=12).contains(n))?,
+        ),
+    };
+
+    Some(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_list_every_section_with_aligned_keys() {
+        let help = help_text();
+
+        for (title, bindings) in sections() {
+            assert!(help.contains(&format!("{title}\n")));
+            assert!(!bindings.is_empty());
+        }
+        assert!(help.contains("  F1  "));
+    }
+
+    #[test]
+    fn should_parse_key_notation() {
+        assert_eq!(
+            parse_keys("a<Esc><C-r><lt><x>"),
+            vec![
+                KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+                KeyEvent::new(KeyCode::Char('<'), KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('<'), KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('>'), KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_look_keys_up_through_the_layers() {
+        let (user, errors) = parse_file("# mine\nnormal Q quit\nnormal u :w\nnormal x dance\n");
+        let mut buffer = HashMap::new();
+        bind(
+            &mut buffer,
+            KeymapMode::Normal,
+            &parse_keys("Q"),
+            Action::Undo,
+        );
+        let keymaps = Keymaps {
+            user,
+            ..Keymaps::new()
+        };
+        let keymap = keymaps.get(KeymapMode::Normal, &buffer);
+
+        assert_eq!(errors, ["keymap:4: Unknown action: dance"]);
+        assert_eq!(
+            keymap.lookup(&parse_keys("Q")),
+            Lookup::Action(Action::Undo)
+        );
+        assert_eq!(
+            keymap.lookup(&parse_keys("u")),
+            Lookup::Action(Action::Command(String::from("w")))
+        );
+        assert_eq!(keymap.lookup(&parse_keys("<C-w>")), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&parse_keys("x")), Lookup::Unbound);
+        assert_eq!(
+            keymap.lookup(&[KeyEvent::new(KeyCode::Char('V'), KeyModifiers::SHIFT)]),
+            Lookup::Action(Action::VisualLine)
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Latency diagnostics for the Zack text editor.
+//!
+//! The editor times each stage of its loop: handling events, the periodic work of ticks
+//! (guards, git hunks, blame), laying the view out and drawing it. [`Latency`] keeps those
+//! timings for the last frames, along with the time from each key press to the next frame
+//! drawn. `F12` toggles an overlay graphing them, to see which feature slows typing down.
+//!
+//! The overlay is a debug HUD: below the timings, it shows the frame rate, the last
+//! event handled, how many events each frame handled and how much input waits in the
+//! queue, along with the size of the active rope and the viewport, to diagnose slowness
+//! in large files.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Frames kept for the graph and the summary.
+const MAX_FRAMES: usize = 120;
+
+/// Most characters kept of the description of the last event.
+const MAX_EVENT_LEN: usize = 60;
+
+/// A stage of the editor loop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Handling keys, mouse and application events.
+    Events,
+    /// Periodic work between events.
+    Tick,
+    /// Scrolling the view to the cursor.
+    Layout,
+    /// Drawing the frame.
+    Draw,
+}
+
+/// Time spent in each stage before a frame, and the input latency it ended.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameTimings {
+    /// Time since the first key press received before the frame and after the previous
+    /// one.
+    pub input: Option<Duration>,
+    /// Time spent in each [`Stage`], in the order of [`Stage::ALL`].
+    pub stages: [Duration; 4],
+    /// Events handled before the frame.
+    pub events: usize,
+    /// When the frame was drawn.
+    pub drawn: Option<Instant>,
+}
+
+/// Timings of the last frames.
+#[derive(Debug, Default)]
+pub struct Latency {
+    /// Set while the overlay is shown.
+    pub visible: bool,
+    frames: VecDeque<FrameTimings>,
+    /// Timings of the frame being prepared.
+    current: FrameTimings,
+    /// When the oldest key press not drawn yet was received.
+    pending_input: Option<Instant>,
+    /// Description of the last event handled while the overlay is shown.
+    last_event: Option<String>,
+}
+
+impl Stage {
+    /// Every stage, in the order they run.
+    pub const ALL: [Stage; 4] = [Stage::Events, Stage::Tick, Stage::Layout, Stage::Draw];
+
+    /// Returns the name shown in the overlay.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Events => "events",
+            Stage::Tick => "tick",
+            Stage::Layout => "layout",
+            Stage::Draw => "draw",
+        }
+    }
+}
+
+impl Latency {
+    /// Creates an empty, hidden record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Notes a key press received at `now`, unless an earlier one is still undrawn.
+    pub fn input(&mut self, now: Instant) {
+        self.pending_input.get_or_insert(now);
+    }
+
+    /// Adds `spent` in `stage` to the current frame.
+    pub fn record(&mut self, stage: Stage, spent: Duration) {
+        let index = Stage::ALL.iter().position(|s| *s == stage).unwrap_or(0);
+        self.current.stages[index] += spent;
+    }
+
+    /// Counts an event handled in the current frame, described by `describe` while the
+    /// overlay is shown.
+    pub fn event(&mut self, describe: impl FnOnce() -> String) {
+        self.current.events += 1;
+
+        if self.visible {
+            self.last_event = Some(describe().chars().take(MAX_EVENT_LEN).collect());
+        }
+    }
+
+    /// Returns the description of the last event handled while the overlay is shown.
+    pub fn last_event(&self) -> Option<&str> {
+        self.last_event.as_deref()
+    }
+
+    /// Returns the events handled before the last frame.
+    pub fn events_per_frame(&self) -> usize {
+        self.frames.back().map_or(0, |frame| frame.events)
+    }
+
+    /// Returns the frames drawn per second over the kept frames.
+    pub fn fps(&self) -> f64 {
+        let mut drawn = self.frames.iter().filter_map(|frame| frame.drawn);
+        match (drawn.next(), drawn.next_back()) {
+            (Some(first), Some(last)) if last > first => {
+                (self.frames.len() - 1) as f64 / last.duration_since(first).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Closes the current frame, drawn at `now`.
+    pub fn frame_drawn(&mut self, now: Instant) {
+        let mut frame = std::mem::take(&mut self.current);
+        frame.drawn = Some(now);
+        frame.input = self
+            .pending_input
+            .take()
+            .map(|input| now.duration_since(input));
+
+        self.frames.push_back(frame);
+        if self.frames.len() > MAX_FRAMES {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Returns the input latencies of the frames that ended one, oldest first, in
+    /// microseconds.
+    pub fn input_micros(&self) -> Vec<u64> {
+        self.frames
+            .iter()
+            .filter_map(|frame| frame.input)
+            .map(|input| input.as_micros() as u64)
+            .collect()
+    }
+
+    /// Summarizes the kept frames: the last and the worst input latency, then the
+    /// average and the worst time of each stage.
+    pub fn summary(&self) -> Vec<String> {
+        let inputs: Vec<Duration> = self.frames.iter().filter_map(|frame| frame.input).collect();
+        let mut lines = vec![match (inputs.last(), inputs.iter().max()) {
+            (Some(last), Some(max)) => format!(
+                "key→frame {:>7} max {:>7}",
+                format_duration(*last),
+                format_duration(*max)
+            ),
+            _ => String::from("key→frame      no keys yet"),
+        }];
+
+        for (index, stage) in Stage::ALL.iter().enumerate() {
+            let times: Vec<Duration> = self
+                .frames
+                .iter()
+                .map(|frame| frame.stages[index])
+                .collect();
+            let total: Duration = times.iter().sum();
+            let average = total / times.len().max(1) as u32;
+            let max = times.iter().max().copied().unwrap_or_default();
+
+            lines.push(format!(
+                "{:<9} {:>7} max {:>7}",
+                stage.name(),
+                format_duration(average),
+                format_duration(max)
+            ));
+        }
+
+        lines
+    }
+}
+
+/// Formats a duration in milliseconds with a precision of a microsecond.
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_measure_from_the_first_undrawn_key_to_the_frame() {
+        let mut latency = Latency::new();
+        let now = Instant::now();
+
+        latency.input(now);
+        latency.input(now + Duration::from_millis(2));
+        latency.frame_drawn(now + Duration::from_millis(5));
+        latency.frame_drawn(now + Duration::from_millis(9));
+
+        assert_eq!(latency.input_micros(), vec![5000]);
+        assert_eq!(latency.frames.len(), 2);
+        assert_eq!(latency.fps(), 250.0);
+    }
+
+    #[test]
+    fn should_describe_events_only_while_visible() {
+        let mut latency = Latency::new();
+
+        latency.event(|| String::from("hidden"));
+        latency.visible = true;
+        latency.event(|| "x".repeat(100));
+        latency.frame_drawn(Instant::now());
+
+        assert_eq!(latency.events_per_frame(), 2);
+        assert_eq!(latency.last_event().map(str::len), Some(MAX_EVENT_LEN));
+    }
+
+    #[test]
+    fn should_summarize_each_stage() {
+        let mut latency = Latency::new();
+
+        latency.record(Stage::Draw, Duration::from_millis(1));
+        latency.frame_drawn(Instant::now());
+        let summary = latency.summary();
+
+        assert_eq!(summary.len(), 1 + Stage::ALL.len());
+        assert!(summary[0].contains("no keys yet"));
+        assert!(summary[4].starts_with("draw"));
+    }
+}
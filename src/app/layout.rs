@@ -0,0 +1,323 @@
+//! Named layout module for the Zack text editor.
+//!
+//! This module provides [`Layout`], a snapshot of the workspace: the open files with
+//! their cursor positions, which one is active, and the view options. Layouts are
+//! saved under a name with `:layout save name` and recreated with `:layout name`.
+//!
+//! [`LayoutStore`] persists them per project, as JSON in `.zack/layouts.json` under the
+//! directory the editor was started from.
+
+use crate::{
+    app::{buffer_manager::BufferManager, options::Options},
+    event::{AppEvent, BufferManagerEvent, CursorEvent},
+    types::position::Position,
+};
+use serde_json::{Map, Value, json};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Where layouts are stored, relative to the project directory.
+const LAYOUTS_PATH: &str = ".zack/layouts.json";
+
+/// A file of a layout and where its cursor was.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutFile {
+    pub path: PathBuf,
+    pub cursor: Position,
+}
+
+/// A saved workspace arrangement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub files: Vec<LayoutFile>,
+    /// Index in `files` of the active file.
+    pub active: usize,
+    /// `:set` arguments recreating the view options, like `wrap` or `nonumber`.
+    pub options: Vec<String>,
+}
+
+/// Reads and writes the layouts of a project.
+#[derive(Debug)]
+pub struct LayoutStore {
+    path: PathBuf,
+}
+
+impl Layout {
+    /// Captures the documents with a file and the view options.
+    ///
+    /// Returns `None` when no open document has a file.
+    pub fn capture(buffers: &BufferManager, options: &Options) -> Option<Self> {
+        let mut files = vec![];
+        let mut active = 0;
+
+        for (index, document) in buffers.documents().iter().enumerate() {
+            let Some(path) = &document.file.path else {
+                continue;
+            };
+
+            if index == buffers.active_index() {
+                active = files.len();
+            }
+
+            files.push(LayoutFile {
+                path: path.clone(),
+                cursor: document.cursor.position,
+            });
+        }
+
+        if files.is_empty() {
+            return None;
+        }
+
+        let flag = |name: &str, enabled: bool| {
+            if enabled {
+                name.to_string()
+            } else {
+                format!("no{name}")
+            }
+        };
+
+        Some(Self {
+            files,
+            active,
+            options: vec![
+                flag("wrap", options.wrap),
+                flag("number", options.number),
+                flag("ruler", options.ruler),
+            ],
+        })
+    }
+
+    /// Returns the events recreating the layout: opening every file at its cursor, then
+    /// switching back to the active one and applying the options.
+    pub fn restore_events(&self) -> Vec<AppEvent> {
+        let mut events = vec![];
+
+        for file in &self.files {
+            events.push(AppEvent::BufferManager(BufferManagerEvent::Open(
+                file.path.clone(),
+            )));
+            events.push(AppEvent::Cursor(CursorEvent::SetPosition {
+                line: file.cursor.line,
+                col: file.cursor.col,
+            }));
+        }
+
+        if let Some(active) = self.files.get(self.active) {
+            events.push(AppEvent::BufferManager(BufferManagerEvent::Open(
+                active.path.clone(),
+            )));
+        }
+
+        events.extend(self.options.iter().cloned().map(AppEvent::SetOption));
+
+        events
+    }
+
+    fn to_json(&self) -> Value {
+        let files: Vec<Value> = self
+            .files
+            .iter()
+            .map(|file| {
+                json!({
+                    "path": file.path.display().to_string(),
+                    "line": file.cursor.line,
+                    "col": file.cursor.col,
+                })
+            })
+            .collect();
+
+        json!({ "files": files, "active": self.active, "options": self.options })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let files = value
+            .get("files")?
+            .as_array()?
+            .iter()
+            .map(|file| {
+                Some(LayoutFile {
+                    path: PathBuf::from(file.get("path")?.as_str()?),
+                    cursor: Position::new(
+                        file.get("line")?.as_u64()? as usize,
+                        file.get("col")?.as_u64()? as usize,
+                    ),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let options = value
+            .get("options")
+            .and_then(Value::as_array)
+            .map(|options| {
+                options
+                    .iter()
+                    .filter_map(|option| option.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            files,
+            active: value.get("active").and_then(Value::as_u64).unwrap_or(0) as usize,
+            options,
+        })
+    }
+}
+
+impl LayoutStore {
+    /// Creates a store for the project in the current directory.
+    pub fn new() -> Self {
+        Self::in_directory(Path::new("."))
+    }
+
+    /// Creates a store for the project in `directory`.
+    pub fn in_directory(directory: &Path) -> Self {
+        Self {
+            path: directory.join(LAYOUTS_PATH),
+        }
+    }
+
+    /// Returns the names of the saved layouts, sorted.
+    pub fn names(&self) -> io::Result<Vec<String>> {
+        Ok(self.read()?.keys().cloned().collect())
+    }
+
+    /// Returns the layout saved as `name`, if any.
+    pub fn get(&self, name: &str) -> io::Result<Option<Layout>> {
+        Ok(self.read()?.get(name).and_then(Layout::from_json))
+    }
+
+    /// Saves `layout` as `name`, replacing any layout with that name.
+    pub fn save(&self, name: &str, layout: &Layout) -> io::Result<()> {
+        let mut layouts = self.read()?;
+        layouts.insert(name.to_string(), layout.to_json());
+
+        self.write(layouts)
+    }
+
+    /// Deletes the layout saved as `name`, returning `false` if there was none.
+    pub fn delete(&self, name: &str) -> io::Result<bool> {
+        let mut layouts = self.read()?;
+        let removed = layouts.remove(name).is_some();
+
+        if removed {
+            self.write(layouts)?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Reads every saved layout; a missing file means there are none.
+    fn read(&self) -> io::Result<Map<String, Value>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+            Err(err) => return Err(err),
+        };
+
+        match serde_json::from_str(&text) {
+            Ok(Value::Object(layouts)) => Ok(layouts),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "layouts file is not a JSON object",
+            )),
+            Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+    }
+
+    fn write(&self, layouts: Map<String, Value>) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        let text = serde_json::to_string_pretty(&Value::Object(layouts))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        fs::write(&self.path, text)
+    }
+}
+
+impl Default for LayoutStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{buffer_manager::Document, file};
+    use std::sync::mpsc;
+
+    fn layout() -> Layout {
+        Layout {
+            files: vec![
+                LayoutFile {
+                    path: PathBuf::from("src/main.rs"),
+                    cursor: Position::new(3, 4),
+                },
+                LayoutFile {
+                    path: PathBuf::from("README.md"),
+                    cursor: Position::new(0, 0),
+                },
+            ],
+            active: 1,
+            options: vec![String::from("wrap"), String::from("nonumber")],
+        }
+    }
+
+    #[test]
+    fn should_capture_documents_with_files_only() {
+        let mut buffers = BufferManager::new(Document::new(String::new(), None), mpsc::channel().0);
+        buffers.load(PathBuf::from("test_layout_a.txt")).unwrap();
+        let mut options = Options::new();
+        options.wrap = true;
+
+        let layout = Layout::capture(&buffers, &options).unwrap();
+
+        assert_eq!(layout.files.len(), 1);
+        assert_eq!(
+            layout.files[0].path,
+            file::absolute(PathBuf::from("test_layout_a.txt"))
+        );
+        assert_eq!(layout.options, vec!["wrap", "nonumber", "ruler"]);
+    }
+
+    #[test]
+    fn should_restore_files_then_active_file_and_options() {
+        let events = layout().restore_events();
+
+        assert_eq!(
+            events,
+            vec![
+                AppEvent::BufferManager(BufferManagerEvent::Open(PathBuf::from("src/main.rs"))),
+                AppEvent::Cursor(CursorEvent::SetPosition { line: 3, col: 4 }),
+                AppEvent::BufferManager(BufferManagerEvent::Open(PathBuf::from("README.md"))),
+                AppEvent::Cursor(CursorEvent::SetPosition { line: 0, col: 0 }),
+                AppEvent::BufferManager(BufferManagerEvent::Open(PathBuf::from("README.md"))),
+                AppEvent::SetOption(String::from("wrap")),
+                AppEvent::SetOption(String::from("nonumber")),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_save_load_and_delete_layouts() {
+        let directory = PathBuf::from("test_layout_store");
+        let store = LayoutStore::in_directory(&directory);
+
+        store.save("review", &layout()).unwrap();
+        let names = store.names().unwrap();
+        let loaded = store.get("review").unwrap();
+        let deleted = store.delete("review").unwrap();
+        let missing = store.get("review").unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(names, vec!["review"]);
+        assert_eq!(loaded, Some(layout()));
+        assert!(deleted);
+        assert_eq!(missing, None);
+    }
+}
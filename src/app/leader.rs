@@ -0,0 +1,157 @@
+//! Line leader module for the Zack text editor.
+//!
+//! A leader is what starts a line before its text: the indentation, a line comment marker
+//! like `//` or `#`, and a list bullet like `-`, `*`, `+` or `1.`, each followed by a
+//! blank. Pressing `Enter` in insert mode continues the leader of the line on the new
+//! one, with [`continuation`], the number of a numbered item counted up, unless
+//! `:set noautocontinue` is set. On a line holding nothing but its leader, like an empty
+//! bullet, `Enter` clears the leader instead, ending the list or comment. Wrapping with
+//! `gq` keeps leaders on the lines it fills.
+
+/// Bullets of list items.
+const BULLETS: [char; 3] = ['-', '*', '+'];
+
+/// Returns the leader of `line`, given the line comment marker of its file type, or
+/// `None` when it has no marker nor bullet after its indentation.
+pub fn leader<'a>(line: &'a str, comment: Option<&str>) -> Option<&'a str> {
+    let (_, end) = split(line, comment)?;
+    Some(&line[..end])
+}
+
+/// Returns the leader of the lines continuing `line` in the same paragraph or list item:
+/// its own leader with the list bullet turned into blanks, or its indentation when it
+/// has none.
+pub fn hanging(line: &str, comment: Option<&str>) -> String {
+    match split(line, comment) {
+        Some((bullet, end)) => format!("{}{}", &line[..bullet], " ".repeat(end - bullet)),
+        None => indentation(line).to_string(),
+    }
+}
+
+/// Returns the blanks starting `line`.
+pub fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Returns where the list bullet of the leader of `line` starts, or where the leader ends
+/// when it has none, and where the leader ends.
+fn split(line: &str, comment: Option<&str>) -> Option<(usize, usize)> {
+    let indent = indentation(line).len();
+    let mut end = indent;
+
+    if let Some(marker) = comment.filter(|marker| !marker.is_empty())
+        && line[end..].starts_with(marker)
+    {
+        // Doubled markers and doc comment markers, like `///`, `//!` or `##`.
+        end += marker.len();
+        end += line[end..].len()
+            - line[end..]
+                .trim_start_matches(|c: char| c.is_ascii_punctuation())
+                .len();
+        end += blanks(&line[end..])?;
+    }
+
+    let bullet = end;
+    if let Some(len) = self::bullet(&line[end..]) {
+        end += len;
+    }
+
+    (end > indent).then_some((bullet, end))
+}
+
+/// Returns the leader of `line` when the line holds nothing else, as an empty bullet or
+/// comment does.
+pub fn bare<'a>(line: &'a str, comment: Option<&str>) -> Option<&'a str> {
+    let leader = leader(line.trim_end_matches(['\r', '\n']), comment)?;
+    line[leader.len()..].trim().is_empty().then_some(leader)
+}
+
+/// Returns the leader `line` continues on the line after it, with the number of a
+/// numbered item counted up, or `None` when it has none, when the line holds nothing
+/// but its leader or when `col`, where the line is split, falls inside it.
+pub fn continuation(line: &str, comment: Option<&str>, col: usize) -> Option<String> {
+    let leader = leader(line, comment)?;
+    if col < leader.chars().count() || bare(line, comment).is_some() {
+        return None;
+    }
+
+    let bare = leader.trim_end_matches([' ', '\t']);
+    if let Some(number) = bare.strip_suffix(['.', ')']) {
+        let start = number.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        if let Ok(value) = number[start..].parse::<u64>() {
+            return Some(format!(
+                "{}{}{}",
+                &leader[..start],
+                value + 1,
+                &leader[number.len()..]
+            ));
+        }
+    }
+
+    Some(leader.to_string())
+}
+
+/// Returns the length of the list bullet starting `text` with the blanks after it.
+fn bullet(text: &str) -> Option<usize> {
+    let marker = match text.chars().next()? {
+        c if BULLETS.contains(&c) => 1,
+        _ if numbered(text) => text.find(['.', ')'])? + 1,
+        _ => return None,
+    };
+
+    Some(marker + blanks(&text[marker..])?)
+}
+
+/// Returns `true` when `text` starts with the number of a numbered item, like `1.` or
+/// `2)`.
+fn numbered(text: &str) -> bool {
+    let rest = text.trim_start_matches(|c: char| c.is_ascii_digit());
+    rest.len() < text.len() && rest.starts_with(['.', ')'])
+}
+
+/// Returns the length of the blanks starting `text`, or `None` when there are none.
+fn blanks(text: &str) -> Option<usize> {
+    let len = text.len() - text.trim_start_matches([' ', '\t']).len();
+    (len > 0).then_some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_find_comment_markers_and_bullets() {
+        assert_eq!(leader("    // text", Some("//")), Some("    // "));
+        assert_eq!(leader("/// docs", Some("//")), Some("/// "));
+        assert_eq!(leader("# - item", Some("#")), Some("# - "));
+        assert_eq!(leader("  * item", None), Some("  * "));
+        assert_eq!(leader("12. item", None), Some("12. "));
+
+        assert_eq!(leader("# Title", None), None);
+        assert_eq!(leader("//text", Some("//")), None);
+        assert_eq!(leader("-1", None), None);
+        assert_eq!(leader("    plain", Some("//")), None);
+    }
+
+    #[test]
+    fn should_hang_the_following_lines_under_the_bullet() {
+        assert_eq!(hanging("  // 1. item", Some("//")), "  //    ");
+        assert_eq!(hanging("\ttext", None), "\t");
+    }
+
+    #[test]
+    fn should_continue_the_leader_after_it() {
+        assert_eq!(
+            continuation("// note", Some("//"), 7),
+            Some(String::from("// "))
+        );
+        assert_eq!(
+            continuation("  9) item", None, 9),
+            Some(String::from("  10) "))
+        );
+        assert_eq!(continuation("- item", None, 1), None);
+        assert_eq!(continuation("  - \n", None, 4), None);
+        assert_eq!(bare("  // \n", Some("//")), Some("  // "));
+        assert_eq!(bare("1. one", None), None);
+    }
+}
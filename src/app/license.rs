@@ -0,0 +1,325 @@
+//! License headers for the Zack text editor.
+//!
+//! `:license` puts a license or copyright header at the top of the buffer, commented out
+//! with the syntax of the file type, below the `#!` line of scripts. When the top of the
+//! buffer already has a copyright line, its years are brought up to date instead:
+//! `2019` or `2019-2023` becomes `2019-2026`.
+//!
+//! The header is read from `.zack/license` in the working directory, for a project
+//! policy, or else from `~/.config/zack/license`; without either, it is a one-line
+//! copyright notice. `{{year}}` and `{{author}}` are replaced, as in file templates.
+
+use crate::event::BufferEvent;
+use std::{borrow::Cow, path::Path};
+
+/// The header used when no license file is found.
+pub const DEFAULT_HEADER: &str = "Copyright (c) {{year}} {{author}}";
+
+/// Where a project keeps its header, relative to the working directory.
+pub const PROJECT_PATH: &str = ".zack/license";
+
+/// Lines searched for an existing copyright line.
+const SCAN_LINES: usize = 20;
+
+/// How a file type writes comments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// Every line starts with a marker, like `//`.
+    Line(Cow<'static, str>),
+    /// The comment opens and closes on lines of their own, each line between them
+    /// starting with `prefix`.
+    Block {
+        open: &'static str,
+        prefix: &'static str,
+        close: &'static str,
+    },
+}
+
+/// Comment styles by file extension, or by name for files without one.
+const STYLES: &[(&[&str], CommentStyle)] = &[
+    (
+        &[
+            "rs", "c", "h", "cc", "cpp", "hpp", "cs", "go", "java", "js", "jsx", "ts", "tsx", "kt",
+            "swift", "scala", "dart", "zig", "proto",
+        ],
+        CommentStyle::Line(Cow::Borrowed("//")),
+    ),
+    (
+        &[
+            "py",
+            "sh",
+            "bash",
+            "zsh",
+            "fish",
+            "rb",
+            "pl",
+            "toml",
+            "yaml",
+            "yml",
+            "r",
+            "nix",
+            "cmake",
+            "mk",
+            "conf",
+            "ex",
+            "exs",
+            "jl",
+            "Makefile",
+            "Dockerfile",
+        ],
+        CommentStyle::Line(Cow::Borrowed("#")),
+    ),
+    (
+        &["lua", "sql", "hs", "elm"],
+        CommentStyle::Line(Cow::Borrowed("--")),
+    ),
+    (
+        &["el", "lisp", "clj", "scm", "ini", "asm"],
+        CommentStyle::Line(Cow::Borrowed(";")),
+    ),
+    (&["tex", "erl"], CommentStyle::Line(Cow::Borrowed("%"))),
+    (&["vim"], CommentStyle::Line(Cow::Borrowed("\""))),
+    (
+        &["css", "scss", "less"],
+        CommentStyle::Block {
+            open: "/*",
+            prefix: " * ",
+            close: " */",
+        },
+    ),
+    (
+        &["html", "xml", "svg", "md"],
+        CommentStyle::Block {
+            open: "<!--",
+            prefix: "  ",
+            close: "-->",
+        },
+    ),
+];
+
+impl CommentStyle {
+    /// Returns the comment style of the file at `path`, if its type is known.
+    pub fn of(path: &Path) -> Option<Self> {
+        let key = path.extension().or_else(|| path.file_name())?.to_str()?;
+
+        STYLES
+            .iter()
+            .find(|(keys, _)| keys.contains(&key))
+            .map(|(_, style)| style.clone())
+    }
+
+    /// Comments `text` out, one comment line per line of text.
+    pub fn comment(&self, text: &str) -> Vec<String> {
+        match self {
+            CommentStyle::Line(marker) => text
+                .lines()
+                .map(|line| match line.is_empty() {
+                    true => marker.to_string(),
+                    false => format!("{marker} {line}"),
+                })
+                .collect(),
+            CommentStyle::Block {
+                open,
+                prefix,
+                close,
+            } => {
+                let mut lines = vec![open.to_string()];
+                lines.extend(
+                    text.lines()
+                        .map(|line| format!("{prefix}{line}").trim_end().to_string()),
+                );
+                lines.push(close.to_string());
+                lines
+            }
+        }
+    }
+}
+
+/// Replaces the placeholders of a header.
+pub fn expand(header: &str, year: u32, author: &str) -> String {
+    header
+        .replace("{{year}}", &year.to_string())
+        .replace("{{author}}", author)
+}
+
+/// Returns the edit putting `header` at the top of `lines`, the lines of the buffer, or
+/// bringing the years of its copyright line up to `year`. Returns `None` when that line
+/// is already up to date.
+pub fn edit(
+    lines: &[String],
+    style: &CommentStyle,
+    header: &str,
+    year: u32,
+) -> Option<BufferEvent> {
+    let existing = lines
+        .iter()
+        .take(SCAN_LINES)
+        .position(|line| line.to_ascii_lowercase().contains("copyright"));
+
+    if let Some(index) = existing {
+        return update_years(&lines[index], year).map(|text| BufferEvent::ReplaceLines {
+            start: index,
+            end: index,
+            text,
+        });
+    }
+
+    // The first line is replaced by the header and itself, or by itself and the header
+    // for a `#!` line, with a blank line between the header and the code.
+    let first = lines.first().map(String::as_str).unwrap_or_default();
+    let header = style.comment(header);
+    let replacement = if first.starts_with("#!") {
+        [
+            vec![first.to_string()],
+            header,
+            vec![String::new(), String::new()],
+        ]
+        .concat()
+    } else {
+        [header, vec![String::new(), first.to_string()]].concat()
+    };
+
+    Some(BufferEvent::ReplaceLines {
+        start: 0,
+        end: 0,
+        text: replacement.join("\n"),
+    })
+}
+
+/// Returns `line`, a copyright line, with its years ending at `year`, or `None` when
+/// they already do or it has none.
+pub fn update_years(line: &str, year: u32) -> Option<String> {
+    let from = line.to_ascii_lowercase().find("copyright")?;
+    let (start, first) = find_year(line, from)?;
+    let mut end = start + 4;
+    let mut last = first;
+
+    let rest = &line[end..];
+    let after_dash = rest
+        .trim_start()
+        .strip_prefix(['-', '–'])
+        .map(str::trim_start);
+    if let Some(after_dash) = after_dash
+        && let Some((0, range_end)) = find_year(after_dash, 0)
+    {
+        end = line.len() - after_dash.len() + 4;
+        last = range_end;
+    }
+
+    if last >= year {
+        return None;
+    }
+
+    Some(format!("{}{first}-{year}{}", &line[..start], &line[end..]))
+}
+
+/// Finds the first four-digit year in `text` from byte `from`, returning its position
+/// and value.
+fn find_year(text: &str, from: usize) -> Option<(usize, u32)> {
+    let bytes = text.as_bytes();
+    let is_digit = |index: usize| bytes.get(index).is_some_and(u8::is_ascii_digit);
+
+    let start = (from..bytes.len().saturating_sub(3)).find(|&start| {
+        (start..start + 4).all(is_digit)
+            && (start == 0 || !is_digit(start - 1))
+            && !is_digit(start + 4)
+    })?;
+
+    Some((start, text[start..start + 4].parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.split('\n').map(String::from).collect()
+    }
+
+    #[test]
+    fn should_comment_headers_in_the_syntax_of_the_file() {
+        let header = "Copyright 2026 Ana\n\nMIT";
+
+        assert_eq!(
+            CommentStyle::of(Path::new("src/main.rs"))
+                .unwrap()
+                .comment(header),
+            vec!["// Copyright 2026 Ana", "//", "// MIT"]
+        );
+        assert_eq!(
+            CommentStyle::of(Path::new("Makefile"))
+                .unwrap()
+                .comment("MIT"),
+            vec!["# MIT"]
+        );
+        assert_eq!(
+            CommentStyle::of(Path::new("style.css"))
+                .unwrap()
+                .comment(header),
+            vec!["/*", " * Copyright 2026 Ana", " *", " * MIT", " */"]
+        );
+        assert_eq!(CommentStyle::of(Path::new("notes.unknown")), None);
+    }
+
+    #[test]
+    fn should_update_copyright_years() {
+        assert_eq!(
+            update_years("// Copyright (c) 2019 Ana", 2026).as_deref(),
+            Some("// Copyright (c) 2019-2026 Ana")
+        );
+        assert_eq!(
+            update_years("# Copyright 2019 - 2023, Ana", 2026).as_deref(),
+            Some("# Copyright 2019-2026, Ana")
+        );
+        assert_eq!(update_years("// Copyright 2019-2026 Ana", 2026), None);
+        assert_eq!(update_years("// Copyright 2026 Ana", 2026), None);
+        assert_eq!(update_years("// Copyright Ana", 2026), None);
+        assert_eq!(update_years("// 2019 Copyright Ana", 2026), None);
+    }
+
+    #[test]
+    fn should_insert_headers_below_the_shebang() {
+        let style = &CommentStyle::Line(Cow::Borrowed("#"));
+
+        assert_eq!(
+            edit(&lines("#!/bin/sh\necho hi"), style, "MIT", 2026),
+            Some(BufferEvent::ReplaceLines {
+                start: 0,
+                end: 0,
+                text: String::from("#!/bin/sh\n# MIT\n\n"),
+            })
+        );
+        assert_eq!(
+            edit(&lines(""), style, "MIT", 2026),
+            Some(BufferEvent::ReplaceLines {
+                start: 0,
+                end: 0,
+                text: String::from("# MIT\n\n"),
+            })
+        );
+        assert_eq!(
+            edit(&lines("print(1)"), style, "MIT", 2026),
+            Some(BufferEvent::ReplaceLines {
+                start: 0,
+                end: 0,
+                text: String::from("# MIT\n\nprint(1)"),
+            })
+        );
+    }
+
+    #[test]
+    fn should_update_an_existing_header_instead_of_adding_one() {
+        let style = &CommentStyle::Line(Cow::Borrowed("//"));
+        let text = lines("//! Docs\n// Copyright 2020 Ana\nfn main() {}");
+
+        assert_eq!(
+            edit(&text, style, "MIT", 2026),
+            Some(BufferEvent::ReplaceLines {
+                start: 1,
+                end: 1,
+                text: String::from("// Copyright 2020-2026 Ana"),
+            })
+        );
+        assert_eq!(edit(&text, style, "MIT", 2020), None);
+    }
+}
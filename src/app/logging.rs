@@ -0,0 +1,161 @@
+//! Logging for the Zack text editor.
+//!
+//! The editor and its subsystems log with the `tracing` macros, events carrying their
+//! fields along with the message. How much is kept is set when starting the editor, with
+//! `--log-level off|error|warn|info|debug|trace` (`info` by default), and `--log-file
+//! path` also writes the records to a file, at the `debug` level unless `--log-level`
+//! says otherwise. Whatever the level, info records and above still become notifications
+//! (see [`notifications`](super::notifications)).
+//!
+//! The last [`MAX_RECENT`] records kept are held in memory as structured [`Entry`]s, and
+//! `:log` lists them inside the editor, like `tail` on the log file.
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, RwLock},
+    time::SystemTime,
+};
+use tracing::{Level, level_filters::LevelFilter};
+
+/// Most records kept for `:log`.
+pub const MAX_RECENT: usize = 500;
+
+/// Level used when `--log-level` is not given.
+pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+
+/// Level used with `--log-file` when `--log-level` is not given.
+pub const FILE_LEVEL: LevelFilter = LevelFilter::DEBUG;
+
+/// The records kept for `:log`, oldest first.
+static RECENT: Mutex<VecDeque<Entry>> = Mutex::new(VecDeque::new());
+
+/// The level set at startup; records above it are dropped.
+static LEVEL: RwLock<LevelFilter> = RwLock::new(DEFAULT_LEVEL);
+
+/// A record kept for `:log`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// When it was logged.
+    pub time: SystemTime,
+    pub level: Level,
+    /// The module it was logged from, like `zack::app::file`.
+    pub target: String,
+    /// The message followed by the other fields of the event, with its secrets masked.
+    pub text: String,
+}
+
+impl Entry {
+    /// Formats the entry as `hh:mm:ss level target: text`, in UTC.
+    pub fn describe(&self) -> String {
+        let seconds = self
+            .time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+
+        format!(
+            "{:02}:{:02}:{:02} {:<5} {}: {}",
+            seconds / 3600 % 24,
+            seconds / 60 % 60,
+            seconds % 60,
+            self.level.as_str().to_ascii_lowercase(),
+            self.target,
+            self.text
+        )
+    }
+}
+
+/// Returns the level called `name` in `--log-level`.
+pub fn parse_level(name: &str) -> Option<LevelFilter> {
+    name.parse().ok()
+}
+
+/// Returns the level set at startup.
+pub fn level() -> LevelFilter {
+    LEVEL.read().map_or(DEFAULT_LEVEL, |level| *level)
+}
+
+/// Sets the level of the records kept.
+pub fn set_level(level: LevelFilter) {
+    if let Ok(mut current) = LEVEL.write() {
+        *current = level;
+    }
+}
+
+/// Keeps the record of `level` logged from `target`, whose text is `text` once redacted,
+/// when its level is enabled. Returns the entry kept.
+pub fn keep(level: Level, target: &str, text: &str) -> Option<Entry> {
+    if level > self::level() {
+        return None;
+    }
+
+    let entry = Entry {
+        time: SystemTime::now(),
+        level,
+        target: target.to_string(),
+        text: text.to_string(),
+    };
+    if let Ok(mut recent) = RECENT.lock() {
+        recent.push_back(entry.clone());
+
+        if recent.len() > MAX_RECENT {
+            recent.pop_front();
+        }
+    }
+
+    Some(entry)
+}
+
+/// Returns the last `count` records kept, oldest first.
+pub fn recent(count: usize) -> Vec<Entry> {
+    match RECENT.lock() {
+        Ok(recent) => recent
+            .iter()
+            .skip(recent.len().saturating_sub(count))
+            .cloned()
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Lists the last `count` records kept for `:log`, one per line.
+pub fn describe(count: usize) -> String {
+    let entries = recent(count);
+
+    if entries.is_empty() {
+        return match level() == LevelFilter::OFF {
+            true => String::from("Logging is off, see --log-level"),
+            false => String::from("No log records"),
+        };
+    }
+
+    entries
+        .iter()
+        .map(Entry::describe)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn should_parse_level_names() {
+        assert_eq!(parse_level("debug"), Some(LevelFilter::DEBUG));
+        assert_eq!(parse_level("OFF"), Some(LevelFilter::OFF));
+        assert_eq!(parse_level("loud"), None);
+    }
+
+    #[test]
+    fn should_describe_an_entry_with_its_time_level_and_target() {
+        let entry = Entry {
+            time: SystemTime::UNIX_EPOCH + Duration::from_secs(3600 + 2 * 60 + 3),
+            level: Level::WARN,
+            target: String::from("zack::app::file"),
+            text: String::from("careful"),
+        };
+
+        assert_eq!(entry.describe(), "01:02:03 warn  zack::app::file: careful");
+    }
+}
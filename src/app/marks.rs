@@ -0,0 +1,93 @@
+//! Marks module for the Zack text editor.
+//!
+//! This module provides the [`Marks`] struct, which maps single-character mark names
+//! to buffer positions, and [`MarkEvent`], used to set them. Visual mode records the
+//! bounds of the last selection in the `<` and `>` marks, which command ranges such as
+//! `:'<,'>` refer to.
+
+use crate::{event::AppEvent, types::position::Position};
+use std::collections::HashMap;
+
+/// Name of the mark holding the start of the last visual selection.
+pub const VISUAL_START_MARK: char = '<';
+
+/// Name of the mark holding the end of the last visual selection.
+pub const VISUAL_END_MARK: char = '>';
+
+/// Stores named positions in the buffer.
+#[derive(Debug, Default)]
+pub struct Marks {
+    marks: HashMap<char, Position>,
+}
+
+/// Events that modify marks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkEvent {
+    /// Sets `mark` to `position`, replacing any previous value.
+    Set { mark: char, position: Position },
+}
+
+impl Marks {
+    /// Creates an empty set of marks.
+    pub fn new() -> Self {
+        Self {
+            marks: HashMap::new(),
+        }
+    }
+
+    /// Handles a `MarkEvent` and returns any follow-up `AppEvent`s.
+    pub fn handle_event(&mut self, event: MarkEvent) -> Vec<AppEvent> {
+        match event {
+            MarkEvent::Set { mark, position } => {
+                self.marks.insert(mark, position);
+            }
+        }
+
+        vec![]
+    }
+
+    /// Returns the position stored in `mark`, if it has been set.
+    pub fn get(&self, mark: char) -> Option<Position> {
+        self.marks.get(&mark).copied()
+    }
+
+    /// Returns every mark set with its position, sorted by name.
+    pub fn list(&self) -> Vec<(char, Position)> {
+        let mut marks: Vec<(char, Position)> = self
+            .marks
+            .iter()
+            .map(|(mark, position)| (*mark, *position))
+            .collect();
+        marks.sort_unstable();
+        marks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_return_none_for_unset_mark() {
+        let marks = Marks::new();
+
+        assert_eq!(marks.get('a'), None);
+    }
+
+    #[test]
+    fn should_set_and_overwrite_mark() {
+        let mut marks = Marks::new();
+
+        marks.handle_event(MarkEvent::Set {
+            mark: 'a',
+            position: Position::new(1, 2),
+        });
+        marks.handle_event(MarkEvent::Set {
+            mark: 'a',
+            position: Position::new(3, 4),
+        });
+
+        assert_eq!(marks.get('a'), Some(Position::new(3, 4)));
+        assert_eq!(marks.list(), vec![('a', Position::new(3, 4))]);
+    }
+}
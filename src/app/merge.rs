@@ -0,0 +1,197 @@
+//! Merge conflicts for the Zack text editor.
+//!
+//! `zack --merge LOCAL BASE REMOTE MERGED` makes zack usable as `git mergetool`: the
+//! three versions are shown read-only above `MERGED`, which holds the conflicts git left
+//! in it and is written back with `:w`. `:accept ours`, `:accept theirs` and `:accept
+//! both` resolve the conflict under the cursor, or else the next one below it, keeping
+//! the local side, the remote side, or both one after the other.
+//!
+//! Conflicts are found by their markers, with or without the base section written by
+//! `merge.conflictStyle=diff3`:
+//!
+//! ```text
+//! <<<<<<< ours
+//! local lines
+//! ||||||| base
+//! base lines
+//! =======
+//! remote lines
+//! >>>>>>> theirs
+//! ```
+
+use crate::event::BufferEvent;
+
+/// Starts the local side of a conflict.
+const OURS_MARKER: &str = "<<<<<<<";
+
+/// Starts the base section of a conflict, in the diff3 style.
+const BASE_MARKER: &str = "|||||||";
+
+/// Separates the local and remote sides of a conflict.
+const SEPARATOR: &str = "=======";
+
+/// Ends the remote side of a conflict.
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// Which side of a conflict to keep.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// The local side.
+    Ours,
+    /// The remote side.
+    Theirs,
+    /// The local side, then the remote side.
+    Both,
+}
+
+impl Resolution {
+    /// Returns the resolution called `name` in `:accept`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ours" | "local" => Some(Resolution::Ours),
+            "theirs" | "remote" => Some(Resolution::Theirs),
+            "both" => Some(Resolution::Both),
+            _ => None,
+        }
+    }
+}
+
+/// The lines of the markers of a conflict in the buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The `<<<<<<<` line.
+    pub start: usize,
+    /// The `|||||||` line, in the diff3 style.
+    pub base: Option<usize>,
+    /// The `=======` line.
+    pub separator: usize,
+    /// The `>>>>>>>` line.
+    pub end: usize,
+}
+
+impl Conflict {
+    /// Returns the lines of the local side.
+    pub fn ours<'a>(&self, lines: &'a [String]) -> &'a [String] {
+        &lines[self.start + 1..self.base.unwrap_or(self.separator)]
+    }
+
+    /// Returns the lines of the remote side.
+    pub fn theirs<'a>(&self, lines: &'a [String]) -> &'a [String] {
+        &lines[self.separator + 1..self.end]
+    }
+
+    /// Returns the edit replacing the conflict, markers included, with the side or sides
+    /// chosen by `resolution`.
+    pub fn resolve(&self, lines: &[String], resolution: Resolution) -> BufferEvent {
+        let kept = match resolution {
+            Resolution::Ours => self.ours(lines).to_vec(),
+            Resolution::Theirs => self.theirs(lines).to_vec(),
+            Resolution::Both => [self.ours(lines), self.theirs(lines)].concat(),
+        };
+
+        BufferEvent::ReplaceLines {
+            start: self.start,
+            end: self.end,
+            text: kept.iter().map(|line| format!("{line}\n")).collect(),
+        }
+    }
+}
+
+/// Finds the conflicts of `lines`, in order. Markers that do not form a whole conflict
+/// are ignored.
+pub fn conflicts(lines: &[String]) -> Vec<Conflict> {
+    let mut found = vec![];
+    let mut open: Option<(usize, Option<usize>, Option<usize>)> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let marker = |marker: &str| {
+            line.strip_prefix(marker)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+        };
+
+        open = match open {
+            _ if marker(OURS_MARKER) => Some((index, None, None)),
+            Some((start, None, None)) if marker(BASE_MARKER) => Some((start, Some(index), None)),
+            Some((start, base, None)) if line == SEPARATOR => Some((start, base, Some(index))),
+            Some((start, base, Some(separator))) if marker(THEIRS_MARKER) => {
+                found.push(Conflict {
+                    start,
+                    base,
+                    separator,
+                    end: index,
+                });
+                None
+            }
+            open => open,
+        };
+    }
+
+    found
+}
+
+/// Returns the conflict holding `line`, or else the first one below it.
+pub fn at_or_after(conflicts: &[Conflict], line: usize) -> Option<Conflict> {
+    conflicts
+        .iter()
+        .find(|conflict| conflict.end >= line)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn should_find_conflicts_with_and_without_a_base() {
+        let text = lines(
+            "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\nb\n<<<<<<< ours\nx\n||||||| base\nw\n=======\n>>>>>>> theirs\n=======",
+        );
+
+        assert_eq!(
+            conflicts(&text),
+            vec![
+                Conflict {
+                    start: 1,
+                    base: None,
+                    separator: 3,
+                    end: 5,
+                },
+                Conflict {
+                    start: 7,
+                    base: Some(9),
+                    separator: 11,
+                    end: 12,
+                },
+            ]
+        );
+        assert_eq!(at_or_after(&conflicts(&text), 6).map(|c| c.start), Some(7));
+        assert_eq!(at_or_after(&conflicts(&text), 13), None);
+    }
+
+    #[test]
+    fn should_keep_the_chosen_sides() {
+        let text = lines("<<<<<<< HEAD\nours\n||||||| base\nbase\n=======\ntheirs\n>>>>>>> topic");
+        let conflict = conflicts(&text)[0];
+        let replacement = |resolution| match conflict.resolve(&text, resolution) {
+            BufferEvent::ReplaceLines { start, end, text } => (start, end, text),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            replacement(Resolution::Ours),
+            (0, 6, String::from("ours\n"))
+        );
+        assert_eq!(
+            replacement(Resolution::Theirs),
+            (0, 6, String::from("theirs\n"))
+        );
+        assert_eq!(
+            replacement(Resolution::Both),
+            (0, 6, String::from("ours\ntheirs\n"))
+        );
+    }
+}
@@ -1,30 +1,167 @@
-use crate::app::buffer::Buffer;
+use crate::app::buffer_manager::{BufferManager, Document};
+use crate::app::completion::Completion;
 use crate::app::cursor::Cursor;
-use crate::app::file::File;
-use crate::app::modes::normal::NormalMode;
+use crate::app::guard::Limits;
+use crate::app::history::{History, HistoryKind};
+use crate::app::hooks::{Hook, Hooks};
+use crate::app::jobs::Jobs;
+use crate::app::keymap::{KeymapMode, Keymaps};
+use crate::app::marks::Marks;
+use crate::app::modes::EditorMode;
+use crate::app::modes::normal::{MAX_COUNT, NormalMode};
 use crate::app::modes::{Mode, change_mode};
-use crate::event::{AppEvent, Event, EventHandler};
+use crate::app::mouse::Mouse;
+use crate::app::notifications::Notifications;
+use crate::app::operator::{Register, Wrap};
+use crate::app::options::Options;
+use crate::app::quickfix::Quickfix;
+use crate::app::scripting::Scripts;
+use crate::app::search::Search;
+use crate::app::shell::Shell;
+use crate::app::watch::ConfigWatch;
+use crate::app::windows::Windows;
+use crate::event::recording::Recorder;
+use crate::event::{
+    AppEvent, BufferEvent, BufferManagerEvent, CursorEvent, Event, EventHandler, FileEvent,
+    SearchEvent, TerminalEvent,
+};
 use crate::ui::components::FocusableComponent;
-use crate::ui::components::filename_prompt::FilenamePrompt;
+use crate::ui::components::command_line::{CommandLine, CommandLineKind};
+use crate::ui::components::confirm_prompt::ConfirmPrompt;
+use crate::ui::components::dialog::Dialog;
+use crate::ui::components::editor::Editor;
+use crate::ui::components::message_pane::MessagePane;
+use crate::ui::components::prompt::Prompt;
+use crate::ui::components::tab_bar::TabBar;
+use crate::ui::components::terminal_pane::TerminalPane;
+use crate::ui::components::undo_panel::UndoPanel;
+use crate::ui::terminal;
+use crate::ui::theme::{THEME_FILE, Theme};
+use color_eyre::eyre::eyre;
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent, MouseEventKind,
+};
 use ratatui::DefaultTerminal;
 use ratatui::Frame;
+use ratatui::layout::{Position, Rect};
+use std::io::stdout;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
+pub mod ansi;
 pub mod buffer;
+pub mod buffer_manager;
+pub mod changes;
+pub mod clipboard;
+pub mod command;
+pub mod completion;
+pub mod count;
 pub mod cursor;
+pub mod diagnostics;
+pub mod diff;
+pub mod driver;
+pub mod editorconfig;
+pub mod export;
 pub mod file;
+pub mod filetype;
+pub mod folds;
+pub mod git;
+pub mod guard;
+pub mod hardcopy;
+pub mod history;
+pub mod hooks;
+pub mod jobs;
+pub mod keymap;
+pub mod latency;
+pub mod layout;
+pub mod leader;
+pub mod license;
+pub mod logging;
+pub mod marks;
+pub mod merge;
 pub mod modes;
+pub mod mouse;
+pub mod notifications;
+pub mod operator;
+pub mod options;
+pub mod paste;
+pub mod pretty;
+#[cfg(test)]
+mod properties;
+pub mod pty;
+pub mod quickfix;
+pub mod redact;
+pub mod scripting;
+pub mod search;
+pub mod shell;
+pub mod state;
+pub mod statusline;
+pub mod tail;
+pub mod template;
+pub mod undo;
+pub mod watch;
+pub mod windows;
+pub mod wrap;
+
+/// Longest time spent handling queued events before drawing a frame again.
+const MAX_FRAME_DELAY: Duration = Duration::from_millis(50);
+
+/// Longest wait for an event before the periodic work of a tick is done anyway, so
+/// watching files and holding the cursor go on while ticks are off.
+const MAX_EVENT_WAIT: Duration = Duration::from_secs(1);
+
+/// Time without input after which the editor is idle, unless something is still going on.
+const IDLE_AFTER: Duration = Duration::from_secs(2);
 
 #[derive(Debug)]
 pub struct App {
     pub running: bool,
     pub focus: FocusableComponent,
     pub mode: Box<dyn Mode>,
-    pub cursor: Cursor,
-    pub buffer: Buffer,
-    pub file: File,
+    /// The default and user bindings of the modes; those of a buffer are kept with it.
+    pub keymaps: Keymaps,
+    pub buffers: BufferManager,
+    pub windows: Windows,
+    pub marks: Marks,
+    /// Places in files the output of the last `:make` points at.
+    pub quickfix: Quickfix,
+    pub hooks: Hooks,
+    pub scripts: Scripts,
+    /// The unnamed register, holding the text last deleted, changed or yanked.
+    pub register: Option<Register>,
+    pub search: Search,
+    pub completion: Completion,
+    pub options: Options,
+    pub theme: Theme,
+    pub mouse: Mouse,
+    pub notifications: Notifications,
+    pub jobs: Jobs,
     pub event_handler: EventHandler,
-    pub filename_prompt: FilenamePrompt, // TODO: as component list grows, make a ComponentRegistry
+    /// Area of the last frame, which the editor fills; `None` before the first one.
+    pub area: Option<Rect>,
+    pub prompt: Prompt, // TODO: as component list grows, make a ComponentRegistry
+    pub command_line: CommandLine,
+    pub confirm_prompt: ConfirmPrompt,
+    pub dialog: Dialog,
+    pub message_pane: MessagePane,
+    pub undo_panel: UndoPanel,
+    pub terminal_pane: TerminalPane,
+    /// Writes the handled events to a file when started with `--record`.
+    pub recorder: Option<Recorder>,
+    /// When the last terminal input arrived, to tell when the editor is idle.
+    last_input: Instant,
+    /// Set once the `CursorHold` hooks ran, until the next input.
+    cursor_held: bool,
+    /// Set while the last input was played back from a recording, whose edits join runs
+    /// rather than stopping at the implicit undo break points.
+    replaying: bool,
+    /// Title last given to the terminal window; `None` until set.
+    title: Option<String>,
+    /// Notices changes to the configuration files, to read them again.
+    config_watch: ConfigWatch,
+    /// Set when reading the terminal failed, until it is set up again.
+    terminal_lost: bool,
+    pub latency: latency::Latency,
 }
 
 impl Default for App {
@@ -35,60 +172,749 @@ impl Default for App {
 
 impl App {
     pub fn new(initial_text: String, maybe_path: Option<PathBuf>) -> Self {
+        Self::with_event_handler(initial_text, maybe_path, EventHandler::new())
+    }
+
+    /// Creates the application around `event_handler`, which may not read the terminal,
+    /// as when driving it from tests.
+    pub fn with_event_handler(
+        initial_text: String,
+        maybe_path: Option<PathBuf>,
+        event_handler: EventHandler,
+    ) -> Self {
+        let mut document = Document::new(initial_text, maybe_path);
+        document.apply_template();
+
         Self {
             running: true,
-            buffer: Buffer::new(initial_text),
-            file: File::new(maybe_path),
-            mode: Box::new(NormalMode),
-            cursor: Cursor::new(),
-            event_handler: EventHandler::new(),
+            buffers: BufferManager::new(document, event_handler.sender()),
+            windows: Windows::new(),
+            marks: Marks::new(),
+            quickfix: Quickfix::new(),
+            hooks: Hooks::new(),
+            scripts: Scripts::new(),
+            register: None,
+            search: Search::new(),
+            completion: Completion::new(),
+            options: Options::new(),
+            theme: Theme::new(),
+            mouse: Mouse::new(),
+            notifications: Notifications::new(),
+            jobs: Jobs::new(event_handler.sender()),
+            mode: Box::new(NormalMode::default()),
+            keymaps: Keymaps::new(),
+            event_handler,
             focus: FocusableComponent::Editor,
-            filename_prompt: FilenamePrompt::new(),
+            area: None,
+            prompt: Prompt::new(),
+            command_line: CommandLine::new(),
+            confirm_prompt: ConfirmPrompt::new(),
+            dialog: Dialog::new(),
+            message_pane: MessagePane::new(),
+            undo_panel: UndoPanel::new(),
+            terminal_pane: TerminalPane::new(),
+            recorder: None,
+            last_input: Instant::now(),
+            cursor_held: false,
+            replaying: false,
+            title: None,
+            config_watch: ConfigWatch::default(),
+            terminal_lost: false,
+            latency: latency::Latency::new(),
         }
     }
 
     pub fn run(mut self, mut terminal: DefaultTerminal) -> color_eyre::Result<()> {
+        self.load_history();
+        let active = self.buffers.active_index();
+        for index in (0..self.buffers.documents().len()).filter(|&index| index != active) {
+            self.remember_file(index);
+        }
+        self.remember_file(active);
+        self.load_keymap();
+        self.load_config();
+        self.load_theme();
+        self.load_scripts();
+        if let Some(directory) = state::config_directory() {
+            self.config_watch = ConfigWatch::new(
+                directory,
+                &[filetype::CONFIG_FILE, keymap::KEYMAP_FILE, THEME_FILE],
+            );
+        }
+        if self.buffers.active().file.path.is_some() {
+            self.run_buffer_hooks(Hook::BufReadPost);
+        }
+
         while self.running {
+            let size = terminal.size()?;
+            self.area = Some(Rect::new(0, 0, size.width, size.height));
+            let layout_started = Instant::now();
+            self.update_viewport(Rect::new(0, 0, size.width, size.height));
+            let draw_started = Instant::now();
             terminal.draw(|frame| self.render(frame))?;
+            Cursor::apply_style(self.mode.get_current_mode())?;
+            self.update_title();
+
+            {
+                let now = Instant::now();
+                self.latency
+                    .record(latency::Stage::Layout, draw_started - layout_started);
+                self.latency
+                    .record(latency::Stage::Draw, now - draw_started);
+                self.latency.frame_drawn(now);
+            }
+
             self.handle_event()?;
+
+            if std::mem::take(&mut self.terminal_lost) {
+                terminal::restore();
+                if let Err(err) = terminal::reinit(&mut terminal, self.options.mouse) {
+                    tracing::error!("Cannot set the terminal up again: {err}");
+                }
+                self.title = None;
+            }
+
+            if terminal::take_suspend_request() {
+                terminal::suspend(&mut terminal, self.options.mouse)?;
+                self.title = None;
+            }
         }
 
+        self.save_history();
+
         Ok(())
     }
 
-    pub fn tick(&self) {}
+    /// Records the file of a document as the most recently opened one, for `:oldfiles`
+    /// and `--continue`.
+    fn remember_file(&mut self, index: usize) {
+        let Some(path) = self
+            .buffers
+            .documents()
+            .get(index)
+            .and_then(|document| document.file.path.as_deref())
+            .and_then(|path| std::path::absolute(path).ok())
+        else {
+            return;
+        };
+
+        self.prompt
+            .histories
+            .entry(HistoryKind::Recent)
+            .or_default()
+            .push(&path.to_string_lossy());
+    }
+
+    /// Loads the prompt histories of previous sessions from the state file.
+    fn load_history(&mut self) {
+        let Some(path) = state::path() else {
+            return;
+        };
+
+        self.command_line.command_history = History::load(&path, HistoryKind::Command);
+        self.command_line.search_history = History::load(&path, HistoryKind::Search);
+        for kind in [
+            HistoryKind::Filename,
+            HistoryKind::Line,
+            HistoryKind::Recent,
+        ] {
+            self.prompt
+                .histories
+                .insert(kind, History::load(&path, kind));
+        }
+    }
+
+    /// Reads the user's bindings from the keymap file of the configuration directory,
+    /// logging the lines that cannot be read.
+    fn load_keymap(&mut self) {
+        let Some(directory) = state::config_directory() else {
+            return;
+        };
+        // A missing file binds nothing, so removing it drops the bindings it had.
+        let text = std::fs::read_to_string(directory.join(keymap::KEYMAP_FILE)).unwrap_or_default();
+
+        let (layers, errors) = keymap::parse_file(&text);
+        for err in errors {
+            tracing::warn!("{err}");
+        }
+        self.keymaps.user = layers;
+    }
+
+    /// Reads the settings of the file types, the autocommands and the status line from the
+    /// configuration file of the configuration directory, logging the lines that cannot be read.
+    fn load_config(&mut self) {
+        let Some(directory) = state::config_directory() else {
+            return;
+        };
+        let text =
+            std::fs::read_to_string(directory.join(filetype::CONFIG_FILE)).unwrap_or_default();
+
+        let (filetypes, errors) = filetype::parse_config(&text);
+        let (autocmds, hook_errors) = hooks::parse_config(&text);
+        let (statusline, statusline_errors) = statusline::parse_config(&text);
+        for err in errors
+            .into_iter()
+            .chain(hook_errors)
+            .chain(statusline_errors)
+        {
+            tracing::warn!("{err}");
+        }
+        self.buffers.set_filetypes(filetypes);
+        self.hooks.set(autocmds);
+        self.options.statusline = statusline;
+    }
+
+    /// Reads the colors of the theme file of the configuration directory, logging the
+    /// lines that cannot be read.
+    fn load_theme(&mut self) {
+        let Some(directory) = state::config_directory() else {
+            return;
+        };
+        let text = std::fs::read_to_string(directory.join(THEME_FILE)).unwrap_or_default();
+
+        let (theme, errors) = Theme::parse(&text);
+        for err in errors {
+            tracing::warn!("{err}");
+        }
+        self.theme = theme;
+    }
+
+    /// Reads again the configuration files changed since they were last read, so their
+    /// settings, bindings and colors apply without restarting.
+    fn reload_config(&mut self) {
+        for name in self.config_watch.changed() {
+            match name {
+                filetype::CONFIG_FILE => self.load_config(),
+                keymap::KEYMAP_FILE => self.load_keymap(),
+                THEME_FILE => self.load_theme(),
+                _ => continue,
+            }
+            tracing::info!("Reloaded {name}");
+        }
+    }
+
+    /// Runs the scripts of the configuration directory, logging the ones that fail.
+    fn load_scripts(&mut self) {
+        let Some(directory) = state::config_directory() else {
+            return;
+        };
+
+        let (events, errors) = self
+            .scripts
+            .load_directory(&directory.join(scripting::SCRIPTS_DIRECTORY));
+        for err in errors {
+            tracing::warn!("{err}");
+        }
+        self.dispatch_multiple_events(events);
+    }
+
+    /// Runs the autocommands of `hook` matching the active buffer, then the script
+    /// functions subscribed to it, passing them `argument`. Their events are handled right
+    /// away, so the changes of `BufWritePre` are written; hooks they fire are skipped.
+    fn run_hooks(&mut self, hook: Hook, argument: &str) {
+        if self.hooks.running {
+            return;
+        }
+        self.hooks.running = true;
+
+        let path = self.buffers.active().file.path.clone();
+        for input in self.hooks.commands(hook, path.as_deref()) {
+            match command::execute(&input, self) {
+                Ok(events) => events
+                    .into_iter()
+                    .for_each(|event| self.handle_app_event(event)),
+                Err(err) => tracing::warn!("{hook}: {err}"),
+            }
+        }
+
+        let events = self.scripts.trigger(hook, argument, self.buffers.active());
+        events
+            .into_iter()
+            .for_each(|event| self.handle_app_event(event));
+
+        self.hooks.running = false;
+    }
+
+    /// Runs the hooks of the active buffer, passing them its name.
+    fn run_buffer_hooks(&mut self, hook: Hook) {
+        let name = self.buffers.active().file.display_name();
+        self.run_hooks(hook, &name);
+    }
+
+    /// Saves the prompt histories to the state file, for the next sessions.
+    fn save_history(&self) {
+        let Some(path) = state::path() else {
+            return;
+        };
+
+        let mut histories = vec![
+            (HistoryKind::Command, &self.command_line.command_history),
+            (HistoryKind::Search, &self.command_line.search_history),
+        ];
+        histories.extend(
+            self.prompt
+                .histories
+                .iter()
+                .map(|(kind, history)| (*kind, history)),
+        );
+
+        let result = History::save_all(&path, &histories);
+
+        if let Err(err) = result {
+            tracing::error!("Failed to save history: {err}");
+        }
+    }
+
+    /// Runs periodic work between events, like refreshing the git hunks after edits,
+    /// noticing changes made to the file by other programs, offering to reload it, and
+    /// blaming the cursor line. Documents over the size limits skip the work redone after
+    /// each edit.
+    pub fn tick(&mut self) {
+        self.notifications.collect();
+        self.reload_config();
+
+        self.buffers.update_comparison();
+        let document = self.buffers.active_mut();
+
+        document.update_guard(Limits::from_options(&self.options));
+        document.update_hunks();
+
+        let changed = document.file.changed_on_disk;
+        document.file.check_disk();
+        if !changed && document.file.changed_on_disk && self.focus == FocusableComponent::Editor {
+            let name = document.file.display_name();
+            let message = match document.is_modified() {
+                true => format!("{name} changed on disk. Reload it and lose your changes?"),
+                false => format!("{name} changed on disk. Reload it?"),
+            };
+            self.event_handler.send(AppEvent::Dialog {
+                message,
+                on_yes: vec![AppEvent::BufferManager(BufferManagerEvent::Reload)],
+                on_no: vec![],
+            });
+        }
+
+        if self.options.blame {
+            self.buffers.update_blame();
+        }
+
+        let hold = Duration::from_millis(self.options.updatetime);
+        if !self.cursor_held && self.last_input.elapsed() >= hold {
+            self.cursor_held = true;
+            self.run_buffer_hooks(Hook::CursorHold);
+        }
+    }
 
     pub fn quit(&mut self) {
         self.running = false;
     }
 
-    fn render(&self, frame: &mut Frame) {
+    pub fn render(&self, frame: &mut Frame) {
         frame.render_widget(self, frame.area());
 
-        self.cursor
-            .render_cursor(frame, self.mode.get_current_mode())
+        if self.focus == FocusableComponent::CommandLine {
+            frame.set_cursor_position(self.command_line.cursor_position(frame.area()));
+        } else if self.focus == FocusableComponent::Prompt {
+            frame.set_cursor_position(self.prompt.cursor_position(frame.area()));
+        } else if self.focus == FocusableComponent::TerminalPane
+            && let Some(area) = self.windows.panel_area(frame.area())
+        {
+            frame.set_cursor_position(self.terminal_pane.cursor_position(area));
+        } else {
+            let document = self.buffers.active();
+            let screen_position = Editor::screen_position(
+                document,
+                &self.options,
+                self.buffers.tabstop(document, &self.options),
+                self.windows.focused_area(frame.area()),
+                document.cursor.position,
+            );
+
+            document.cursor.render_cursor(frame, screen_position)
+        }
+    }
+
+    /// Lays the editor out again for a terminal resized to `area`, right away rather
+    /// than when the next frame is drawn: the documents on screen keep their scroll
+    /// within their text, and the active one keeps its cursor in view, with long lines
+    /// wrapped to the new width. A tooltip placed for the old size is dismissed.
+    pub fn resize(&mut self, area: Rect) {
+        self.area = Some(area);
+        self.mouse.dismiss();
+
+        for &index in self.windows.documents() {
+            if let Some(document) = self.buffers.document_mut(index) {
+                document.scroll = document
+                    .scroll
+                    .min(document.buffer.len_lines().saturating_sub(1));
+            }
+        }
+
+        if let Some(panel) = self.windows.panel_area(area) {
+            self.terminal_pane.resize(panel);
+        }
+        self.update_viewport(area);
+    }
+
+    /// Opens the terminal pane, or closes it when its shell exits, handing the rows it
+    /// takes from the windows to them again.
+    fn handle_terminal_event(&mut self, event: TerminalEvent) {
+        let area = self.area.unwrap_or(Rect::new(0, 0, 80, 24));
+
+        match event {
+            TerminalEvent::Open => {
+                if !self.terminal_pane.is_open() {
+                    self.windows.panel = true;
+                    let panel = self.windows.panel_area(area).unwrap_or(area);
+
+                    if let Err(err) = self.terminal_pane.open(panel, self.event_handler.sender()) {
+                        tracing::error!("Failed to start the terminal: {err}");
+                        self.windows.panel = false;
+                        return;
+                    }
+                }
+                self.focus = FocusableComponent::TerminalPane;
+            }
+            TerminalEvent::Output { pid, bytes } => {
+                if self.terminal_pane.runs(pid) {
+                    self.terminal_pane.feed(&bytes);
+                }
+                return;
+            }
+            TerminalEvent::Exited { pid } if !self.terminal_pane.runs(pid) => return,
+            TerminalEvent::Close | TerminalEvent::Exited { .. } => {
+                self.terminal_pane.close();
+                self.windows.panel = false;
+                if self.focus == FocusableComponent::TerminalPane {
+                    self.focus = FocusableComponent::Editor;
+                }
+            }
+        }
+
+        self.update_viewport(area);
+    }
+
+    /// Makes `document` active, as when its window gets the focus.
+    fn focus_document(&mut self, document: usize) {
+        if document != self.buffers.active_index() {
+            self.buffers.set_active(document);
+            self.completion.close();
+            self.mouse.dismiss();
+            self.run_buffer_hooks(Hook::BufEnter);
+        }
+    }
+
+    /// Returns the area of the focused window in the last frame, `None` before the first one.
+    pub fn editor_area(&self) -> Option<Rect> {
+        self.area.map(|area| self.windows.focused_area(area))
+    }
+
+    /// Scrolls the active document so its cursor is visible in its window of the editor
+    /// `area`.
+    ///
+    /// While wrapping, the lines above the cursor may take more rows than the view has,
+    /// so the view keeps scrolling down until the cursor row fits. With the scrolling of
+    /// the windows locked, the other windows scroll to the same line.
+    pub fn update_viewport(&mut self, area: Rect) {
+        self.windows.sync(self.buffers.active_index());
+        self.windows.buffer_bar = self.options.bufferline && self.buffers.documents().len() > 1;
+        let area = self.windows.focused_area(area);
+        let tabstop = self.buffers.tabstop(self.buffers.active(), &self.options);
+        let document = self.buffers.active_mut();
+        let height = Editor::text_area(document, &self.options, area).height as usize;
+
+        document.scroll_to_cursor(height);
+
+        while document.scroll < document.cursor.position.line
+            && Editor::rows_to(
+                document,
+                &self.options,
+                tabstop,
+                area,
+                document.cursor.position,
+            )
+            .is_some_and(|rows| rows > height)
+        {
+            document.scroll = document.folds.next_visible(document.scroll);
+        }
+
+        if self.windows.scroll_bind {
+            let scroll = document.scroll;
+
+            for &index in self.windows.documents() {
+                if let Some(other) = self.buffers.document_mut(index) {
+                    other.scroll = scroll.min(other.buffer.len_lines().saturating_sub(1));
+                }
+            }
+        }
     }
 
+    /// Waits for the next event and handles it, along with the events queued behind it,
+    /// so a backlog like a replayed macro is not redrawn after each of its events. The
+    /// frame is still drawn once [`MAX_FRAME_DELAY`] has passed. When the event thread
+    /// stops, it is restarted and the terminal set up again; fails once it stopped too
+    /// often, as no input can come anymore. Ticks once when no event comes for
+    /// [`MAX_EVENT_WAIT`].
     fn handle_event(&mut self) -> color_eyre::Result<()> {
-        match self.event_handler.next()? {
+        let mut next = Some(
+            self.event_handler
+                .next_timeout(MAX_EVENT_WAIT)?
+                .unwrap_or(Event::Tick),
+        );
+        let started = Instant::now();
+
+        while let Some(event) = next {
+            if let Event::Error(message) = event {
+                if !self.event_handler.restart() {
+                    return Err(eyre!("Stopped reading the terminal: {message}"));
+                }
+                tracing::error!("Lost the terminal input ({message}), reading it again");
+                self.terminal_lost = true;
+                break;
+            }
+            self.process_event(event);
+
+            next = (self.running && started.elapsed() < MAX_FRAME_DELAY)
+                .then(|| self.event_handler.try_next())
+                .flatten();
+        }
+
+        self.event_handler
+            .set_tick_rate(match self.is_idle(Instant::now()) {
+                true => self.options.idletickrate,
+                false => self.options.tickrate,
+            });
+
+        Ok(())
+    }
+
+    /// Returns the terminal window title: the name of the active file, marked while it
+    /// has unsaved changes.
+    pub fn window_title(&self) -> String {
+        let document = self.buffers.active();
+        let name = document
+            .file
+            .path
+            .as_deref()
+            .and_then(|path| path.file_name())
+            .map_or_else(
+                || document.file.display_name(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+
+        match document.is_modified() {
+            true => format!("{name} ● — zack"),
+            false => format!("{name} — zack"),
+        }
+    }
+
+    /// Sets the terminal window title when the active file or its modified state changed.
+    fn update_title(&mut self) {
+        let title = self.window_title();
+
+        if self.title.as_ref() != Some(&title) {
+            terminal::set_title(&title);
+            self.title = Some(title);
+        }
+    }
+
+    /// Returns `true` when nothing needs ticks at full rate: there was no input for
+    /// [`IDLE_AFTER`], no job is running and no notification is shown.
+    pub fn is_idle(&self, now: Instant) -> bool {
+        if self.latency.visible {
+            return false;
+        }
+
+        now.duration_since(self.last_input) >= IDLE_AFTER
+            && self.jobs.running().is_empty()
+            && self.notifications.current(now).is_none()
+    }
+
+    /// Handles a single event taken from the queue.
+    pub fn process_event(&mut self, event: Event) {
+        let (started, stage) = (
+            Instant::now(),
+            match event {
+                Event::Tick => latency::Stage::Tick,
+                _ => latency::Stage::Events,
+            },
+        );
+        if let Event::Crossterm(crossterm::event::Event::Key(_))
+        | Event::Replayed(crossterm::event::Event::Key(_)) = event
+        {
+            self.latency.input(started);
+        }
+        self.latency.event(|| match &event {
+            Event::Tick => String::from("Tick"),
+            Event::Crossterm(event) | Event::Replayed(event) => format!("{event:?}"),
+            Event::App(event) => format!("{event:?}"),
+            Event::Error(_) => String::from("Error"),
+        });
+
+        if let Some(recorder) = &mut self.recorder
+            && let Err(err) = recorder.record(&event)
+        {
+            tracing::error!("Failed to record events: {err}");
+            self.recorder = None;
+        }
+
+        match event {
             Event::Tick => self.tick(),
-            Event::Crossterm(event) => self.handle_crossterm_event(event),
+            Event::Crossterm(event) => {
+                self.replaying = false;
+                self.handle_crossterm_event(event);
+            }
+            Event::Replayed(event) => {
+                self.replaying = true;
+                self.handle_crossterm_event(event);
+            }
             Event::App(event) => self.handle_app_event(event),
+            Event::Error(message) => {
+                tracing::error!("Stopped reading the terminal: {message}");
+                self.quit();
+            }
         }
 
-        Ok(())
+        self.latency.record(stage, started.elapsed());
     }
 
     fn handle_crossterm_event(&mut self, event: crossterm::event::Event) {
+        self.last_input = Instant::now();
+        self.cursor_held = false;
+
+        if let crossterm::event::Event::Resize(width, height) = event {
+            self.resize(Rect::new(0, 0, width, height));
+            return;
+        }
+
+        if let crossterm::event::Event::Mouse(mouse_event) = event {
+            if self.options.mouse && self.focus == FocusableComponent::Editor {
+                let Some(area) = self.area else {
+                    return;
+                };
+
+                // A click on the bar switches to the document named there.
+                if let MouseEventKind::Down(_) = mouse_event.kind
+                    && let Some(bar) = self.windows.tab_bar(area)
+                    && bar.contains(Position::new(mouse_event.column, mouse_event.row))
+                {
+                    if let Some(document) = TabBar::document_at(self, bar, mouse_event.column) {
+                        self.windows.sync(document);
+                        self.focus_document(document);
+                    }
+                    return;
+                }
+                // A click on another window only focuses it.
+                if let MouseEventKind::Down(_) = mouse_event.kind
+                    && let Some(document) =
+                        self.windows
+                            .document_at(area, mouse_event.column, mouse_event.row)
+                    && document != self.buffers.active_index()
+                {
+                    self.windows.sync(document);
+                    self.focus_document(document);
+                    return;
+                }
+                if let MouseEventKind::Down(_) = mouse_event.kind
+                    && let Some(panel) = self.windows.panel_area(area)
+                    && panel.contains(Position::new(mouse_event.column, mouse_event.row))
+                {
+                    self.focus = FocusableComponent::TerminalPane;
+                    return;
+                }
+                let area = self.windows.focused_area(area);
+
+                let document = self.buffers.active();
+                let next_events = self.mouse.handle_event(
+                    mouse_event,
+                    document,
+                    &self.options,
+                    self.buffers.tabstop(document, &self.options),
+                    area,
+                );
+                self.dispatch_multiple_events(next_events);
+            }
+
+            return;
+        }
+
+        if let crossterm::event::Event::Paste(text) = event {
+            match self.focus {
+                FocusableComponent::Editor => self.handle_paste(text),
+                FocusableComponent::TerminalPane => self.terminal_pane.write(text.as_bytes()),
+                _ => {}
+            }
+
+            return;
+        }
+
         if let crossterm::event::Event::Key(key_event) = event {
+            self.mouse.dismiss();
+
             match self.focus {
-                FocusableComponent::FilenamePrompt => {
-                    for event in self.filename_prompt.handle_key(key_event) {
+                FocusableComponent::Prompt => {
+                    for event in self.prompt.handle_key(key_event) {
+                        self.event_handler.send(event);
+                    }
+                }
+                FocusableComponent::CommandLine => {
+                    for event in self.command_line.handle_key(key_event) {
+                        self.event_handler.send(event);
+                    }
+                }
+                FocusableComponent::ConfirmPrompt => {
+                    for event in self.confirm_prompt.handle_key(key_event) {
+                        self.event_handler.send(event);
+                    }
+                }
+                FocusableComponent::Dialog => {
+                    for event in self.dialog.handle_key(key_event) {
+                        self.event_handler.send(event);
+                    }
+                }
+                FocusableComponent::MessagePane => {
+                    for event in self.message_pane.handle_key(key_event) {
+                        self.event_handler.send(event);
+                    }
+                }
+                FocusableComponent::TerminalPane => {
+                    for event in self.terminal_pane.handle_key(key_event) {
+                        self.event_handler.send(event);
+                    }
+                }
+                FocusableComponent::UndoPanel => {
+                    let position = self.buffers.active().cursor.position;
+                    for event in self.undo_panel.handle_key(key_event, position) {
                         self.event_handler.send(event);
                     }
                 }
                 FocusableComponent::Editor => {
-                    for event in self.mode.handle_key(key_event, self.cursor.position) {
+                    if key_event.code == KeyCode::F(1) {
+                        self.event_handler.send(keymap::help_event());
+                        return;
+                    }
+                    if key_event.code == KeyCode::F(12) {
+                        self.latency.visible = !self.latency.visible;
+                        return;
+                    }
+
+                    if self.completion.active
+                        && let Some(events) = self.completion.handle_key(key_event)
+                    {
+                        self.dispatch_multiple_events(events);
+                        return;
+                    }
+
+                    let document = self.buffers.active();
+                    let keymap = self.keymaps.get(
+                        KeymapMode::of(self.mode.get_current_mode()),
+                        &document.keymaps,
+                    );
+
+                    let events = self
+                        .mode
+                        .handle_key(key_event, &keymap, document.cursor.position);
+                    for event in self.coalesce_moves(key_event, events) {
                         self.event_handler.send(event);
                     }
                 }
@@ -96,31 +922,559 @@ impl App {
         }
     }
 
+    /// Joins the presses of `key` waiting in the queue into the single cursor move
+    /// `events` it made, if so, so holding a movement key moves in one go instead of one
+    /// event at a time. The joined presses are still recorded.
+    fn coalesce_moves(&mut self, key: KeyEvent, events: Vec<AppEvent>) -> Vec<AppEvent> {
+        let [
+            AppEvent::Cursor(
+                CursorEvent::MoveLeft
+                | CursorEvent::MoveRight
+                | CursorEvent::MoveUp
+                | CursorEvent::MoveDown,
+            ),
+        ] = events.as_slice()
+        else {
+            return events;
+        };
+
+        let input = crossterm::event::Event::Key(key);
+        let repeats = self.event_handler.take_repeats(&input);
+        if repeats == 0 {
+            return events;
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            let event = Event::Crossterm(input);
+            if let Err(err) = (0..repeats).try_for_each(|_| recorder.record(&event)) {
+                tracing::error!("Failed to record events: {err}");
+                self.recorder = None;
+            }
+        }
+
+        vec![AppEvent::Repeat {
+            count: repeats + 1,
+            event: Box::new(events[0].clone()),
+        }]
+    }
+
     fn handle_app_event(&mut self, event: AppEvent) {
         match event {
+            AppEvent::Cursor(cursor_event @ (CursorEvent::MoveDown | CursorEvent::MoveUp))
+                if (self.options.wrap && self.options.displaymoves)
+                    || self.buffers.active().pretty =>
+            {
+                let Some(area) = self.editor_area() else {
+                    return;
+                };
+                let down = cursor_event == CursorEvent::MoveDown;
+                let document = self.buffers.active();
+                let position = Editor::display_move(
+                    document,
+                    &self.options,
+                    self.buffers.tabstop(document, &self.options),
+                    area,
+                    down,
+                );
+
+                self.handle_app_event(AppEvent::Cursor(CursorEvent::SetPosition {
+                    line: position.line,
+                    col: position.col,
+                }));
+            }
+
             AppEvent::Cursor(cursor_event) => {
-                let next_events = self.cursor.handle_event(cursor_event, &self.buffer);
+                let next_events = self.buffers.active_mut().move_cursor(cursor_event);
                 self.dispatch_multiple_events(next_events);
             }
 
             AppEvent::Buffer(buffer_event) => {
-                let next_events = self.buffer.handle_event(buffer_event);
+                if self.buffers.active().read_only {
+                    tracing::warn!("Cannot make changes, buffer is read-only");
+                    return;
+                }
+
+                self.break_undo_before(&buffer_event);
+                let line = buffer_event.edit_position().map(|position| position.line);
+                let document = self.buffers.active_mut();
+                let before = document.buffer.len_lines();
+                let next_events = document.buffer.handle_event(buffer_event);
+                document
+                    .folds
+                    .edited(line, before, document.buffer.len_lines());
                 self.dispatch_multiple_events(next_events);
             }
 
             AppEvent::File(file_event) => {
-                let next_events = self.file.handle_event(file_event, &self.buffer);
+                let file_event = match file_event {
+                    FileEvent::SaveAs(path) => {
+                        FileEvent::SaveAs(self.buffers.active().resolve(path))
+                    }
+                    file_event => file_event,
+                };
+                let saving = matches!(file_event, FileEvent::Save | FileEvent::SaveAs(_));
+                if saving {
+                    self.run_buffer_hooks(Hook::BufWritePre);
+                }
+                let document = self.buffers.active_mut();
+                let next_events = document.file.handle_event(file_event, &document.buffer);
+
+                if !document.is_modified() {
+                    document.refresh_git_base();
+                }
+
                 self.dispatch_multiple_events(next_events);
+                if saving && !self.buffers.active().is_modified() {
+                    self.run_buffer_hooks(Hook::BufWritePost);
+                }
+            }
+
+            AppEvent::BufferManager(manager_event) => {
+                let active = self.buffers.active_index();
+                let count = self.buffers.documents().len();
+                let opening = matches!(manager_event, BufferManagerEvent::Open(_));
+                let manager_event = match manager_event {
+                    BufferManagerEvent::Open(path) => {
+                        BufferManagerEvent::Open(self.buffers.active().resolve(path))
+                    }
+                    manager_event => manager_event,
+                };
+                let next_events = self.buffers.handle_event(manager_event);
+
+                if self.buffers.active_index() != active {
+                    self.completion.close();
+                    self.mouse.dismiss();
+                    self.windows.sync(self.buffers.active_index());
+                }
+
+                self.dispatch_multiple_events(next_events);
+                if opening && self.buffers.documents().len() > count {
+                    self.run_buffer_hooks(Hook::BufReadPost);
+                }
+                if opening {
+                    self.remember_file(self.buffers.active_index());
+                }
+                if self.buffers.active_index() != active {
+                    self.run_buffer_hooks(Hook::BufEnter);
+                }
+            }
+
+            AppEvent::Window(window_event) => {
+                if self.terminal_pane.is_open() && self.windows.wraps(window_event) {
+                    self.focus = FocusableComponent::TerminalPane;
+                    return;
+                }
+                let document = self.windows.handle_event(window_event);
+                self.focus_document(document);
+            }
+
+            AppEvent::Fold(fold_event) => {
+                let document = self.buffers.active_mut();
+                let next_events = document.folds.handle_event(fold_event, &document.buffer);
+                self.dispatch_multiple_events(next_events);
+            }
+
+            AppEvent::Mark(mark_event) => {
+                let next_events = self.marks.handle_event(mark_event);
+                self.dispatch_multiple_events(next_events);
+            }
+
+            AppEvent::Quickfix(quickfix_event) => {
+                let next_events = self.quickfix.handle_event(quickfix_event);
+                self.dispatch_multiple_events(next_events);
+            }
+
+            AppEvent::Search(search_event) => {
+                let buffer = &self.buffers.active().buffer;
+                let next_events = self.search.handle_event(search_event, buffer);
+                self.dispatch_multiple_events(next_events);
+            }
+
+            AppEvent::Completion(completion_event) => {
+                let buffer = &self.buffers.active().buffer;
+                let next_events =
+                    self.completion
+                        .handle_event(completion_event, buffer, &self.options.complete);
+                self.dispatch_multiple_events(next_events);
+            }
+
+            AppEvent::Shell(shell_event) => {
+                let next_events = Shell::handle_event(shell_event, &self.buffers, &mut self.jobs);
+                self.dispatch_multiple_events(next_events);
+            }
+
+            AppEvent::Job(job_event) => {
+                let next_events = self.jobs.handle_event(job_event);
+                self.dispatch_multiple_events(next_events);
+            }
+
+            AppEvent::Terminal(terminal_event) => self.handle_terminal_event(terminal_event),
+
+            AppEvent::Lsp(lsp_event) => {
+                let document = self.buffers.active_mut();
+
+                if let Some(lsp) = &mut document.lsp {
+                    let next_events = lsp.handle_event(lsp_event, &document.buffer);
+                    self.dispatch_multiple_events(next_events);
+                }
             }
 
             AppEvent::ChangeFocus(component) => self.focus = component,
 
-            AppEvent::ChangeToMode(new_mode) => change_mode(new_mode, self),
+            AppEvent::ChangeToMode(new_mode) => {
+                let insert = |mode| matches!(mode, EditorMode::Insert { .. });
+                let was_insert = insert(self.mode.get_current_mode());
+
+                self.buffers.active_mut().buffer.break_undo();
+                change_mode(new_mode, self);
+
+                self.run_hooks(Hook::ModeChanged, KeymapMode::of(new_mode).name());
+                match (was_insert, insert(new_mode)) {
+                    (false, true) => self.run_buffer_hooks(Hook::InsertEnter),
+                    (true, false) => self.run_buffer_hooks(Hook::InsertLeave),
+                    _ => {}
+                }
+            }
+
+            AppEvent::AddAutocmd(autocmd) => self.hooks.add(autocmd),
+
+            AppEvent::RunUserCommand { name, argument } => {
+                match self
+                    .scripts
+                    .run_command(&name, &argument, self.buffers.active())
+                {
+                    Ok(events) => self.dispatch_multiple_events(events),
+                    Err(err) => tracing::warn!("{err}"),
+                }
+            }
+
+            AppEvent::OpenCommandLine { kind, prefill } => {
+                self.command_line.open(kind, prefill);
+                self.focus = FocusableComponent::CommandLine;
+            }
+
+            AppEvent::SubmitCommandLine { kind, input } => self.submit_command_line(kind, input),
+
+            AppEvent::OpenPrompt(kind) => {
+                self.prompt.open(kind);
+                self.focus = FocusableComponent::Prompt;
+            }
+
+            AppEvent::Confirm {
+                message,
+                on_confirm,
+                on_deny,
+            } => {
+                self.confirm_prompt.open(message, on_confirm, on_deny);
+                self.focus = FocusableComponent::ConfirmPrompt;
+            }
+
+            AppEvent::Dialog {
+                message,
+                on_yes,
+                on_no,
+            } => {
+                self.dialog.open(message, on_yes, on_no);
+                self.focus = FocusableComponent::Dialog;
+            }
+
+            // `:cd` moves the whole editor, so shell commands and path completion follow
+            // it, and drops the local directory of the active buffer, as Vim does for its
+            // window.
+            AppEvent::ChangeDirectory { directory, local } => {
+                if !local && let Err(err) = std::env::set_current_dir(&directory) {
+                    tracing::error!("Cannot change to {}: {err}", directory.display());
+                    return;
+                }
+                tracing::info!("{}", directory.display());
+
+                self.handle_app_event(AppEvent::BufferManager(BufferManagerEvent::SetDirectory(
+                    local.then_some(directory),
+                )));
+            }
+
+            AppEvent::ShowMessage { title, text } => {
+                self.message_pane.open(title, &text);
+                self.focus = FocusableComponent::MessagePane;
+            }
+
+            AppEvent::ShowDiff { title, lines } => {
+                self.message_pane.open_diff(title, lines);
+                self.focus = FocusableComponent::MessagePane;
+            }
+
+            AppEvent::ShowUndoTree => {
+                self.undo_panel
+                    .open(self.buffers.active().buffer.undo_states());
+                self.focus = FocusableComponent::UndoPanel;
+            }
+
+            AppEvent::RunSignAction(action) => {
+                self.mouse.run_sign_action(action, self.buffers.active())
+            }
+
+            AppEvent::Repeat { count, event } => {
+                for _ in 0..count.min(self.repeat_limit(&event)) {
+                    self.handle_app_event((*event).clone());
+                }
+            }
+
+            AppEvent::Operate {
+                operator,
+                target,
+                count,
+                position,
+            } => {
+                let document = self.buffers.active();
+                let buffer = &document.buffer;
+                let Some(span) = operator::span(buffer, target, count, position) else {
+                    return;
+                };
+
+                if operator.keeps_text() {
+                    self.register = Some(span.register(buffer));
+                }
+                let comment = self.buffers.comment_marker(document);
+                let wrap = Wrap {
+                    width: self.options.textwidth,
+                    comment: comment.as_deref(),
+                };
+                let events = operator.apply(span, buffer, wrap);
+                self.apply_edits(events);
+            }
+
+            AppEvent::Copy { start, end } => {
+                let text = self.buffers.active().buffer.text_between(start, end);
+
+                match clipboard::write(&text) {
+                    Ok(()) => tracing::info!(
+                        "Copied {} character(s) to the clipboard",
+                        text.chars().count()
+                    ),
+                    Err(err) => tracing::warn!("Cannot copy to the clipboard: {err}"),
+                }
+            }
+
+            AppEvent::InsertTab { position } => {
+                let document = self.buffers.active();
+                let filetype = self.buffers.filetype_settings(document);
+                let text = document.file.settings.tab(filetype, &self.options);
+                self.handle_app_event(AppEvent::Buffer(BufferEvent::InsertText { text, position }));
+            }
+
+            AppEvent::InsertNewline { position } => {
+                let document = self.buffers.active();
+                let comment = self.buffers.comment_marker(document);
+                let line = document.buffer.lines_text(position.line, position.line);
+                let (continued, bare) = match self.options.autocontinue {
+                    true => (
+                        leader::continuation(&line, comment.as_deref(), position.col),
+                        leader::bare(&line, comment.as_deref()),
+                    ),
+                    false => (None, None),
+                };
+
+                let event = match (continued, bare) {
+                    (Some(leader), _) => BufferEvent::InsertText {
+                        text: format!("\n{leader}"),
+                        position,
+                    },
+                    // Enter on an empty item or comment ends it, keeping the indentation.
+                    (None, Some(bare)) if position.col >= bare.chars().count() => {
+                        let (mut start, mut end) = (position, position);
+                        (start.col, end.col) = (0, bare.chars().count());
+                        let indentation = leader::indentation(bare);
+
+                        self.handle_app_event(AppEvent::Buffer(BufferEvent::ReplaceRange {
+                            start,
+                            end,
+                            text: indentation.to_string(),
+                        }));
+                        // Queued after the move to the start of the replaced range.
+                        self.dispatch_multiple_events(vec![AppEvent::Cursor(
+                            CursorEvent::SetPosition {
+                                line: position.line,
+                                col: indentation.chars().count(),
+                            },
+                        )]);
+                        return;
+                    }
+                    (None, _) => BufferEvent::InsertNewline { position },
+                };
+                self.handle_app_event(AppEvent::Buffer(event));
+            }
+
+            AppEvent::Put { position, reindent } => {
+                if let Some(register) = &self.register {
+                    let buffer = &self.buffers.active().buffer;
+                    let events = match reindent {
+                        true => register
+                            .reindented(buffer, position.line)
+                            .put(buffer, position),
+                        false => register.put(buffer, position),
+                    };
+                    self.apply_edits(events);
+                }
+            }
+
+            AppEvent::Surround {
+                open,
+                close,
+                start,
+                end,
+            } => {
+                if self.options.autosurround {
+                    self.handle_app_event(AppEvent::ChangeToMode(EditorMode::Normal));
+                    self.handle_app_event(AppEvent::Buffer(BufferEvent::Surround {
+                        open,
+                        close,
+                        start,
+                        end,
+                    }));
+                }
+            }
+
+            AppEvent::Map {
+                mode,
+                keys,
+                action,
+                buffer,
+            } => {
+                let layers = match buffer {
+                    true => &mut self.buffers.active_mut().keymaps,
+                    false => &mut self.keymaps.user,
+                };
+                keymap::bind(layers, mode, &keys, action);
+            }
+
+            AppEvent::SetOption(argument) => self.set_option(&argument),
+
+            AppEvent::Suspend => terminal::request_suspend(),
 
             AppEvent::Quit => self.quit(),
         }
     }
 
+    /// Returns how many times `event` can be repeated before moving the cursor out of the
+    /// buffer, or [`MAX_COUNT`] for events not bound to it.
+    fn repeat_limit(&self, event: &AppEvent) -> usize {
+        let document = self.buffers.active();
+        let position = document.cursor.position;
+        let moves_by_rows = (self.options.wrap && self.options.displaymoves) || document.pretty;
+
+        match event {
+            AppEvent::Cursor(CursorEvent::MoveLeft) => position.col,
+            AppEvent::Cursor(CursorEvent::MoveRight) => document
+                .buffer
+                .max_visible_col(&position)
+                .saturating_sub(position.col),
+            AppEvent::Cursor(CursorEvent::MoveUp) if !moves_by_rows => position.line,
+            AppEvent::Cursor(CursorEvent::MoveDown) if !moves_by_rows => document
+                .buffer
+                .len_lines()
+                .saturating_sub(position.line + 1),
+            _ => MAX_COUNT,
+        }
+    }
+
+    /// Starts a new undo step before `event` when it is made outside insert mode, or at
+    /// the implicit break points of insert mode: the end of a sentence and after a pause.
+    /// Replayed edits that continue the run of the previous one never break; typed ones
+    /// do, however fast they come.
+    fn break_undo_before(&mut self, event: &BufferEvent) {
+        let insert = matches!(self.mode.get_current_mode(), EditorMode::Insert { .. });
+        let buffer = &mut self.buffers.active_mut().buffer;
+        let now = Instant::now();
+
+        let sentence = self.options.undobreak && undo::ends_sentence(event, buffer);
+        let pause = self.options.undopause > 0
+            && buffer
+                .history()
+                .is_paused(now, Duration::from_millis(self.options.undopause));
+
+        if (!insert || sentence || pause) && !(self.replaying && buffer.history().coalesces(event))
+        {
+            buffer.break_undo();
+        }
+    }
+
+    /// Handles `events` at once, in order, but queues the cursor moves among them, so they
+    /// come after the cursor moves the edits lead to and the cursor ends where they leave it.
+    fn apply_edits(&mut self, events: Vec<AppEvent>) {
+        let (moves, edits): (Vec<AppEvent>, Vec<AppEvent>) = events
+            .into_iter()
+            .partition(|event| matches!(event, AppEvent::Cursor(_)));
+
+        for edit in edits {
+            self.handle_app_event(edit);
+        }
+        self.dispatch_multiple_events(moves);
+    }
+
+    /// Inserts pasted text in insert mode; in normal mode, offers to open a dropped file.
+    fn handle_paste(&mut self, text: String) {
+        match self.mode.get_current_mode() {
+            EditorMode::Insert { .. } => {
+                self.event_handler
+                    .send(AppEvent::Buffer(BufferEvent::InsertText {
+                        text,
+                        position: self.buffers.active().cursor.position,
+                    }))
+            }
+            EditorMode::Normal => {
+                if let Some(path) = paste::dropped_path(&text) {
+                    self.event_handler.send(AppEvent::Confirm {
+                        message: format!("Open {}?", path.display()),
+                        on_confirm: vec![AppEvent::BufferManager(BufferManagerEvent::Open(path))],
+                        on_deny: vec![],
+                    });
+                }
+            }
+            EditorMode::Visual { .. } | EditorMode::OperatorPending { .. } => {}
+        }
+    }
+
+    fn set_option(&mut self, argument: &str) {
+        let mouse = self.options.mouse;
+
+        if let Err(err) = self.options.set(argument) {
+            tracing::warn!("{err}");
+            return;
+        }
+
+        redact::configure(&self.options.secrets);
+        self.buffers.set_fixeol(self.options.fixeol);
+
+        if self.options.mouse != mouse {
+            let result = if self.options.mouse {
+                crossterm::execute!(stdout(), EnableMouseCapture)
+            } else {
+                self.mouse.dismiss();
+                crossterm::execute!(stdout(), DisableMouseCapture)
+            };
+
+            if let Err(err) = result {
+                tracing::error!("Failed to toggle mouse capture: {err}");
+            }
+        }
+    }
+
+    fn submit_command_line(&mut self, kind: CommandLineKind, input: String) {
+        match kind {
+            CommandLineKind::Command => match command::execute(&input, self) {
+                Ok(events) => self.dispatch_multiple_events(events),
+                Err(err) => tracing::warn!("{err}"),
+            },
+            CommandLineKind::Search => {
+                self.event_handler
+                    .send(AppEvent::Search(SearchEvent::Start {
+                        pattern: input,
+                        position: self.buffers.active().cursor.position,
+                    }));
+            }
+        }
+    }
+
     fn dispatch_multiple_events(&mut self, events: Vec<AppEvent>) {
         for event in events {
             self.event_handler.send(event);
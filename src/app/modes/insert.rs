@@ -1,46 +1,214 @@
 use super::Mode;
-use crate::event::{AppEvent, BufferEvent};
+use crate::app::file::FileEvent;
+use crate::app::keymap::{Action, Binding, Keymap, Lookup};
+use crate::event::{AppEvent, BufferEvent, CompletionEvent};
 use crate::types::position::Position;
+use crate::ui::components::command_line::CommandLineKind;
 use crate::{app::modes::EditorMode, event::CursorEvent};
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::cell::RefCell;
 
-#[derive(Debug)]
-pub struct InsertMode;
+/// Keys handled in insert mode, listed in the help screen; other keys type their char.
+pub const BINDINGS: &[Binding] = &[
+    Binding {
+        keys: "Esc",
+        description: "Go back to normal mode",
+    },
+    Binding {
+        keys: "Arrows",
+        description: "Move the cursor",
+    },
+    Binding {
+        keys: "Enter / Backspace",
+        description: "Break the line or delete the char before the cursor",
+    },
+    Binding {
+        keys: "Tab",
+        description: "Indent with a tab, or spaces with :set expandtab",
+    },
+    Binding {
+        keys: "Ctrl-N",
+        description: "Complete the word before the cursor",
+    },
+    Binding {
+        keys: "Ctrl-S",
+        description: "Save the file, staying in insert mode",
+    },
+    Binding {
+        keys: "Ctrl-V {key}",
+        description: "Insert the key literally, control keys as control chars",
+    },
+    Binding {
+        keys: "Ctrl-V uXXXX / UXXXXXXXX / xXX",
+        description: "Insert the char of a hex code point",
+    },
+];
+
+#[derive(Debug, Default)]
+pub struct InsertMode {
+    /// Set when entered after the cursor, with `a`.
+    pub append: bool,
+    /// What was typed after `Ctrl-V`, while a literal is pending: nothing yet, or the
+    /// `u`, `U` or `x` of a code point and its hex digits so far.
+    literal: RefCell<Option<String>>,
+}
+
+impl InsertMode {
+    /// Creates insert mode, entered after the cursor if `append` is set.
+    pub fn new(append: bool) -> Self {
+        Self {
+            append,
+            ..Self::default()
+        }
+    }
+
+    /// Handles `key` typed after `Ctrl-V`: a code point prefix starts collecting hex
+    /// digits, and any other key is inserted as the char it stands for.
+    fn handle_literal(&self, key: KeyEvent, keymap: &Keymap, position: Position) -> Vec<AppEvent> {
+        let insert = |char| vec![AppEvent::Buffer(BufferEvent::InsertChar { char, position })];
+        let Some(typed) = self.literal.take() else {
+            return vec![];
+        };
+
+        let Some(prefix) = typed.chars().next() else {
+            return match key.code {
+                KeyCode::Char(prefix @ ('u' | 'U' | 'x')) if key.modifiers.is_empty() => {
+                    self.literal.replace(Some(prefix.to_string()));
+                    vec![]
+                }
+                _ => literal_char(key).map_or_else(Vec::new, insert),
+            };
+        };
+
+        let digits = &typed[1..];
+        let max_digits = match prefix {
+            'u' => 4,
+            'U' => 8,
+            _ => 2,
+        };
+
+        match key.code {
+            KeyCode::Char(digit) if digit.is_ascii_hexdigit() => {
+                let digits = format!("{digits}{digit}");
+                if digits.len() < max_digits {
+                    self.literal.replace(Some(format!("{prefix}{digits}")));
+                    return vec![];
+                }
+
+                code_point(&digits).map_or_else(Vec::new, insert)
+            }
+            // Another key ends the code point early, then does what it does; without
+            // digits, the prefix was meant literally.
+            _ => {
+                let char = match digits.is_empty() {
+                    true => Some(prefix),
+                    false => code_point(digits),
+                };
+                let mut events = char.map_or_else(Vec::new, insert);
+                let after = Position::new(position.line, position.col + events.len());
+                events.extend(self.handle_key(key, keymap, after));
+                events
+            }
+        }
+    }
+}
+
+/// Returns the char `key` stands for when typed after `Ctrl-V`: `Ctrl` with a letter
+/// gives its control char, as `Ctrl-A` gives U+0001.
+fn literal_char(key: KeyEvent) -> Option<char> {
+    match key.code {
+        KeyCode::Char(char) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            let code = char.to_ascii_uppercase() as u32;
+            (0x40..0x60)
+                .contains(&code)
+                .then(|| char::from((code - 0x40) as u8))
+        }
+        KeyCode::Char(char) => Some(char),
+        KeyCode::Enter => Some('\r'),
+        KeyCode::Tab => Some('\t'),
+        KeyCode::Esc => Some('\x1b'),
+        KeyCode::Backspace => Some('\x08'),
+        KeyCode::Delete => Some('\x7f'),
+        _ => None,
+    }
+}
+
+/// Returns the char of the code point written in hex `digits`, if valid.
+fn code_point(digits: &str) -> Option<char> {
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+}
 
 impl Mode for InsertMode {
     fn get_mode_label(&self) -> &'static str {
-        "insert"
+        match self.literal.borrow().as_deref() {
+            Some("") => "insert ^V",
+            Some(_) => "insert ^V hex",
+            None => "insert",
+        }
     }
 
     fn get_current_mode(&self) -> EditorMode {
-        EditorMode::Insert
+        EditorMode::Insert {
+            append: self.append,
+        }
     }
 
-    fn handle_key(&self, key: KeyEvent, current_cursor_position: Position) -> Vec<AppEvent> {
-        let mut events = vec![];
+    fn handle_key(
+        &self,
+        key: KeyEvent,
+        keymap: &Keymap,
+        current_cursor_position: Position,
+    ) -> Vec<AppEvent> {
+        if self.literal.borrow().is_some() {
+            return self.handle_literal(key, keymap, current_cursor_position);
+        }
 
-        match key.code {
-            KeyCode::Esc => {
-                events.push(AppEvent::Cursor(CursorEvent::MoveLeft));
-                events.push(AppEvent::ChangeToMode(EditorMode::Normal));
-            }
-            KeyCode::Left => events.push(AppEvent::Cursor(CursorEvent::MoveLeft)),
-            KeyCode::Right => events.push(AppEvent::Cursor(CursorEvent::MoveRight)),
-            KeyCode::Up => events.push(AppEvent::Cursor(CursorEvent::MoveUp)),
-            KeyCode::Down => events.push(AppEvent::Cursor(CursorEvent::MoveDown)),
-            KeyCode::Backspace => events.push(AppEvent::Buffer(BufferEvent::DeleteChar {
+        let action = match keymap.lookup(&[key]) {
+            Lookup::Action(action) => action,
+            _ => match key.code {
+                KeyCode::Char(char) => {
+                    return vec![AppEvent::Buffer(BufferEvent::InsertChar {
+                        char,
+                        position: current_cursor_position,
+                    })];
+                }
+                _ => return vec![],
+            },
+        };
+
+        match action {
+            Action::Normal => vec![
+                AppEvent::Cursor(CursorEvent::MoveLeft),
+                AppEvent::ChangeToMode(EditorMode::Normal),
+            ],
+            Action::MoveLeft => vec![AppEvent::Cursor(CursorEvent::MoveLeft)],
+            Action::MoveRight => vec![AppEvent::Cursor(CursorEvent::MoveRight)],
+            Action::MoveUp => vec![AppEvent::Cursor(CursorEvent::MoveUp)],
+            Action::MoveDown => vec![AppEvent::Cursor(CursorEvent::MoveDown)],
+            Action::Complete => vec![AppEvent::Completion(CompletionEvent::Request {
                 position: current_cursor_position,
-            })),
-            KeyCode::Char(char) => events.push(AppEvent::Buffer(BufferEvent::InsertChar {
-                char,
+            })],
+            Action::DeleteBackward => vec![AppEvent::Buffer(BufferEvent::DeleteChar {
                 position: current_cursor_position,
-            })),
-            KeyCode::Enter => events.push(AppEvent::Buffer(BufferEvent::InsertNewline {
+            })],
+            Action::Newline => vec![AppEvent::InsertNewline {
                 position: current_cursor_position,
-            })),
-            _ => {}
+            }],
+            Action::InsertTab => vec![AppEvent::InsertTab {
+                position: current_cursor_position,
+            }],
+            Action::InsertLiteral => {
+                self.literal.replace(Some(String::new()));
+                vec![]
+            }
+            Action::Save => vec![AppEvent::File(FileEvent::Save)],
+            Action::Command(input) => vec![AppEvent::SubmitCommandLine {
+                kind: CommandLineKind::Command,
+                input,
+            }],
+            _ => vec![],
         }
-
-        events
     }
 }
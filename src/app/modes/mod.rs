@@ -1,35 +1,131 @@
 use crate::{
     app::{
         App,
-        modes::{insert::InsertMode, normal::NormalMode, visual::VisualMode},
+        keymap::Keymap,
+        modes::{
+            insert::InsertMode,
+            normal::{MAX_COUNT, NormalMode},
+            operator_pending::OperatorPendingMode,
+            visual::VisualMode,
+        },
+        operator::Operator,
     },
-    event::AppEvent,
-    types::position::Position,
+    event::{AppEvent, CursorEvent},
+    types::{position::Position, selection::SelectionKind},
 };
-use crossterm::event::KeyEvent;
-use std::fmt::Debug;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{cell::Cell, fmt::Debug};
 
 pub mod insert;
 pub mod normal;
+pub mod operator_pending;
 pub mod visual;
 
+/// The mode the editor is in, with what it needs to enter it.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EditorMode {
-    Insert,
+    /// Typing text, before the cursor, or after it when `append` is set, as with `a`.
+    Insert {
+        append: bool,
+    },
     Normal,
-    Visual,
+    /// Selecting text by characters (`v`) or whole lines (`V`).
+    Visual {
+        kind: VisualKind,
+    },
+    /// Waiting for the motion or text object of `operator`, typed after `count`.
+    OperatorPending {
+        operator: Operator,
+        count: Option<usize>,
+    },
 }
 
 pub trait Mode: Debug {
     fn get_current_mode(&self) -> EditorMode;
     fn get_mode_label(&self) -> &'static str;
-    fn handle_key(&self, key: KeyEvent, current_cursor_position: Position) -> Vec<AppEvent>;
+    /// Handles `key`, looking the keys typed up in `keymap`, the bindings of this mode.
+    fn handle_key(
+        &self,
+        key: KeyEvent,
+        keymap: &Keymap,
+        current_cursor_position: Position,
+    ) -> Vec<AppEvent>;
+
+    /// Returns the ordered `(start, end)` bounds of the active selection, both inclusive.
+    fn get_selection(&self, _current_cursor_position: Position) -> Option<(Position, Position)> {
+        None
+    }
+
+    /// Returns the count typed before a command, while the command is still to come.
+    fn pending_count(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// How visual mode selects text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VisualKind {
+    /// From the anchor to the cursor (`v`).
+    #[default]
+    Char,
+    /// The whole lines from the anchor to the cursor (`V`).
+    Line,
+}
+
+impl From<VisualKind> for SelectionKind {
+    fn from(kind: VisualKind) -> Self {
+        match kind {
+            VisualKind::Char => SelectionKind::CharWise,
+            VisualKind::Line => SelectionKind::LineWise,
+        }
+    }
 }
 
 pub fn change_mode(new_mode: EditorMode, app: &mut App) {
+    // Only insert mode lets the cursor go right after the last character of a line.
+    let document = app.buffers.active_mut();
+    document.cursor.past_end = matches!(new_mode, EditorMode::Insert { .. });
+    document.cursor.clamp(&document.buffer);
+
     match new_mode {
-        EditorMode::Insert => app.mode = Box::new(InsertMode),
-        EditorMode::Normal => app.mode = Box::new(NormalMode),
-        EditorMode::Visual => app.mode = Box::new(VisualMode),
+        EditorMode::Insert { append } => {
+            if append {
+                let document = app.buffers.active_mut();
+                document
+                    .cursor
+                    .handle_event(CursorEvent::MoveRight, &document.buffer);
+            }
+            app.mode = Box::new(InsertMode::new(append))
+        }
+        EditorMode::Normal => app.mode = Box::new(NormalMode::default()),
+        EditorMode::Visual { kind } => {
+            app.mode = Box::new(VisualMode::new(app.buffers.active().cursor.position, kind))
+        }
+        EditorMode::OperatorPending { operator, count } => {
+            app.mode = Box::new(OperatorPendingMode::new(operator, count))
+        }
     }
 }
+
+/// Adds the digit typed with `key` to `count`, returning `false` when it is not part of
+/// one: a count cannot start with `0`.
+fn push_count_digit(count: &Cell<Option<usize>>, key: KeyEvent) -> bool {
+    let KeyCode::Char(digit) = key.code else {
+        return false;
+    };
+    let Some(digit) = digit.to_digit(10) else {
+        return false;
+    };
+    if key.modifiers != KeyModifiers::NONE || (digit == 0 && count.get().is_none()) {
+        return false;
+    }
+
+    let pending = count.get().unwrap_or(0);
+    count.set(Some(
+        pending
+            .saturating_mul(10)
+            .saturating_add(digit as usize)
+            .min(MAX_COUNT),
+    ));
+    true
+}
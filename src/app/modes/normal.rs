@@ -1,11 +1,229 @@
-use super::Mode;
-use crate::event::{AppEvent, FileEvent};
+use super::{Mode, push_count_digit};
+use crate::app::keymap::{Action, Binding, Keymap, Lookup};
+use crate::event::{
+    AppEvent, BufferEvent, BufferManagerEvent, FileEvent, FoldEvent, SearchEvent, WindowEvent,
+};
 use crate::types::position::Position;
-use crate::{app::modes::EditorMode, event::CursorEvent};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::ui::components::command_line::CommandLineKind;
+use crate::ui::components::prompt::PromptKind;
+use crate::{
+    app::modes::{EditorMode, VisualKind},
+    event::CursorEvent,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::cell::{Cell, RefCell};
 
-#[derive(Debug)]
-pub struct NormalMode;
+/// Keys handled in normal mode, listed in the help screen.
+pub const BINDINGS: &[Binding] = &[
+    Binding {
+        keys: "h / j / k / l",
+        description: "Move left, down, up or right",
+    },
+    Binding {
+        keys: "{count} h / j / k / l, ]c / [c",
+        description: "Repeat the move count times",
+    },
+    Binding {
+        keys: "i / a",
+        description: "Insert before or after the cursor",
+    },
+    Binding {
+        keys: "v / V",
+        description: "Select characters or whole lines in visual mode",
+    },
+    Binding {
+        keys: "d / c / y {motion}",
+        description: "Delete, change or yank the text of a motion",
+    },
+    Binding {
+        keys: "gu / gU / g~ {motion}",
+        description: "Make lowercase, uppercase or switch the case",
+    },
+    Binding {
+        keys: "gq {motion}",
+        description: "Wrap the lines to the text width (:set textwidth)",
+    },
+    Binding {
+        keys: "p",
+        description: "Put the yanked or deleted text after the cursor",
+    },
+    Binding {
+        keys: "]p",
+        description: "Put whole lines indented like the cursor line",
+    },
+    Binding {
+        keys: ":",
+        description: "Open the command line",
+    },
+    Binding {
+        keys: "/",
+        description: "Search forward",
+    },
+    Binding {
+        keys: "n / N",
+        description: "Go to the next or previous match",
+    },
+    Binding {
+        keys: "]c / [c",
+        description: "Go to the next or previous changed hunk",
+    },
+    Binding {
+        keys: "za / zo / zc",
+        description: "Toggle, open or close the fold of the indented block",
+    },
+    Binding {
+        keys: "zR",
+        description: "Open every fold",
+    },
+    Binding {
+        keys: "Ctrl-W w / Ctrl-W W",
+        description: "Focus the next or previous window",
+    },
+    Binding {
+        keys: "Ctrl-W o",
+        description: "Close the other windows",
+    },
+    Binding {
+        keys: "u / Ctrl-R",
+        description: "Undo or redo",
+    },
+    Binding {
+        keys: "Ctrl-S",
+        description: "Save",
+    },
+    Binding {
+        keys: "Ctrl-P",
+        description: "Open a file",
+    },
+    Binding {
+        keys: "Ctrl-G",
+        description: "Go to a line",
+    },
+    Binding {
+        keys: "Ctrl-Z",
+        description: "Suspend to the shell",
+    },
+    Binding {
+        keys: "q, Esc, Ctrl-C",
+        description: "Quit",
+    },
+];
+
+/// Largest count typed before a command; more digits leave it at this.
+pub const MAX_COUNT: usize = 99_999;
+
+#[derive(Debug, Default)]
+pub struct NormalMode {
+    /// Keys typed so far of a command bound to several keys, like `]c` or `Ctrl-W w`.
+    keys: RefCell<Vec<KeyEvent>>,
+    /// Count typed before the command, as in `5j`.
+    count: Cell<Option<usize>>,
+}
+
+/// Wraps a single move in `events` repeated `count` times; the app clamps the count to
+/// the buffer, so a huge count does not flood the event queue.
+fn repeat(events: Vec<AppEvent>, count: Option<usize>) -> Vec<AppEvent> {
+    match (count, events.as_slice()) {
+        (
+            Some(count),
+            [
+                event @ (AppEvent::Cursor(
+                    CursorEvent::MoveLeft
+                    | CursorEvent::MoveRight
+                    | CursorEvent::MoveUp
+                    | CursorEvent::MoveDown,
+                )
+                | AppEvent::BufferManager(
+                    BufferManagerEvent::NextHunk | BufferManagerEvent::PreviousHunk,
+                )),
+            ],
+        ) if count > 1 => vec![AppEvent::Repeat {
+            count,
+            event: Box::new(event.clone()),
+        }],
+        _ => events,
+    }
+}
+
+/// Returns the events of `action` typed after `count`.
+fn action_events(
+    action: Action,
+    count: Option<usize>,
+    current_cursor_position: Position,
+) -> Vec<AppEvent> {
+    let event = match action {
+        Action::MoveLeft => AppEvent::Cursor(CursorEvent::MoveLeft),
+        Action::MoveRight => AppEvent::Cursor(CursorEvent::MoveRight),
+        Action::MoveDown => AppEvent::Cursor(CursorEvent::MoveDown),
+        Action::MoveUp => AppEvent::Cursor(CursorEvent::MoveUp),
+        Action::NextHunk => AppEvent::BufferManager(BufferManagerEvent::NextHunk),
+        Action::PreviousHunk => AppEvent::BufferManager(BufferManagerEvent::PreviousHunk),
+        Action::NextWindow => AppEvent::Window(WindowEvent::Next),
+        Action::PreviousWindow => AppEvent::Window(WindowEvent::Previous),
+        Action::OnlyWindow => AppEvent::Window(WindowEvent::Only),
+        Action::ToggleFold => AppEvent::Fold(FoldEvent::Toggle {
+            line: current_cursor_position.line,
+        }),
+        Action::OpenFold => AppEvent::Fold(FoldEvent::Open {
+            line: current_cursor_position.line,
+        }),
+        Action::CloseFold => AppEvent::Fold(FoldEvent::Close {
+            line: current_cursor_position.line,
+        }),
+        Action::OpenAllFolds => AppEvent::Fold(FoldEvent::OpenAll),
+        Action::Insert => AppEvent::ChangeToMode(EditorMode::Insert { append: false }),
+        Action::Append => AppEvent::ChangeToMode(EditorMode::Insert { append: true }),
+        Action::Visual => AppEvent::ChangeToMode(EditorMode::Visual {
+            kind: VisualKind::Char,
+        }),
+        Action::VisualLine => AppEvent::ChangeToMode(EditorMode::Visual {
+            kind: VisualKind::Line,
+        }),
+        Action::Operator(operator) => {
+            AppEvent::ChangeToMode(EditorMode::OperatorPending { operator, count })
+        }
+        Action::Put => AppEvent::Put {
+            position: current_cursor_position,
+            reindent: false,
+        },
+        Action::PutIndented => AppEvent::Put {
+            position: current_cursor_position,
+            reindent: true,
+        },
+        Action::CommandLine => AppEvent::OpenCommandLine {
+            kind: CommandLineKind::Command,
+            prefill: String::new(),
+        },
+        Action::Search => AppEvent::OpenCommandLine {
+            kind: CommandLineKind::Search,
+            prefill: String::new(),
+        },
+        Action::NextMatch => AppEvent::Search(SearchEvent::Next {
+            position: current_cursor_position,
+        }),
+        Action::PreviousMatch => AppEvent::Search(SearchEvent::Previous {
+            position: current_cursor_position,
+        }),
+        Action::Undo => AppEvent::Buffer(BufferEvent::Undo {
+            position: current_cursor_position,
+        }),
+        Action::Redo => AppEvent::Buffer(BufferEvent::Redo {
+            position: current_cursor_position,
+        }),
+        Action::Save => AppEvent::File(FileEvent::Save),
+        Action::OpenFile => AppEvent::OpenPrompt(PromptKind::OpenFile),
+        Action::GotoLine => AppEvent::OpenPrompt(PromptKind::GotoLine),
+        Action::Suspend => AppEvent::Suspend,
+        Action::Quit => AppEvent::Quit,
+        Action::Command(input) => AppEvent::SubmitCommandLine {
+            kind: CommandLineKind::Command,
+            input,
+        },
+        _ => return vec![],
+    };
+
+    vec![event]
+}
 
 impl Mode for NormalMode {
     fn get_mode_label(&self) -> &'static str {
@@ -16,33 +234,203 @@ impl Mode for NormalMode {
         EditorMode::Normal
     }
 
-    fn handle_key(&self, key: KeyEvent, _: Position) -> Vec<AppEvent> {
-        let mut events = vec![];
+    fn pending_count(&self) -> Option<usize> {
+        self.count.get()
+    }
 
-        match key.code {
-            KeyCode::Char('v') => events.push(AppEvent::ChangeToMode(EditorMode::Visual)),
-            KeyCode::Char('i') => events.push(AppEvent::ChangeToMode(EditorMode::Insert)),
-            KeyCode::Char('a') => {
-                events.push(AppEvent::Cursor(CursorEvent::MoveRight));
-                events.push(AppEvent::ChangeToMode(EditorMode::Insert));
-            }
-            KeyCode::Char('h') => events.push(AppEvent::Cursor(CursorEvent::MoveLeft)),
-            KeyCode::Char('l') => events.push(AppEvent::Cursor(CursorEvent::MoveRight)),
-            KeyCode::Char('j') => events.push(AppEvent::Cursor(CursorEvent::MoveDown)),
-            KeyCode::Char('k') => events.push(AppEvent::Cursor(CursorEvent::MoveUp)),
+    fn handle_key(
+        &self,
+        key: KeyEvent,
+        keymap: &Keymap,
+        current_cursor_position: Position,
+    ) -> Vec<AppEvent> {
+        if self.keys.borrow().is_empty() && push_count_digit(&self.count, key) {
+            return vec![];
+        }
 
-            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                events.push(AppEvent::File(FileEvent::Save));
-            }
+        // `Esc` drops a count or keys typed by mistake instead of quitting.
+        if key.code == KeyCode::Esc
+            && (self.count.get().is_some() || !self.keys.borrow().is_empty())
+        {
+            self.keys.take();
+            self.count.take();
+            return vec![];
+        }
+
+        self.keys.borrow_mut().push(key);
+        let lookup = keymap.lookup(&self.keys.borrow());
+        if lookup == Lookup::Prefix {
+            return vec![];
+        }
 
-            KeyCode::Char('q') | KeyCode::Esc => events.push(AppEvent::Quit),
-            KeyCode::Char('c' | 'C') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                events.push(AppEvent::Quit);
+        self.keys.take();
+        let count = self.count.take();
+        match lookup {
+            Lookup::Action(action) => {
+                repeat(action_events(action, count, current_cursor_position), count)
             }
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::keymap::{KeymapMode, Keymaps, bind, parse_keys};
+    use crossterm::event::KeyModifiers;
+    use std::collections::HashMap;
 
-            _ => {}
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn should_jump_between_hunks_with_bracket_c() {
+        let mode = NormalMode::default();
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Normal, &buffer);
+        let position = Position::new(0, 0);
+
+        assert!(
+            mode.handle_key(key(KeyCode::Char(']')), &keymap, position)
+                .is_empty()
+        );
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('c')), &keymap, position),
+            vec![AppEvent::BufferManager(BufferManagerEvent::NextHunk)]
+        );
+
+        mode.handle_key(key(KeyCode::Char('[')), &keymap, position);
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('c')), &keymap, position),
+            vec![AppEvent::BufferManager(BufferManagerEvent::PreviousHunk)]
+        );
+    }
+
+    #[test]
+    fn should_repeat_moves_by_the_typed_count() {
+        let mode = NormalMode::default();
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Normal, &buffer);
+        let position = Position::new(0, 0);
+
+        mode.handle_key(key(KeyCode::Char('1')), &keymap, position);
+        mode.handle_key(key(KeyCode::Char('0')), &keymap, position);
+        assert_eq!(mode.pending_count(), Some(10));
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('j')), &keymap, position),
+            vec![AppEvent::Repeat {
+                count: 10,
+                event: Box::new(AppEvent::Cursor(CursorEvent::MoveDown)),
+            }]
+        );
+        assert_eq!(mode.pending_count(), None);
+
+        for _ in 0..12 {
+            mode.handle_key(key(KeyCode::Char('9')), &keymap, position);
         }
+        assert_eq!(mode.pending_count(), Some(MAX_COUNT));
+        assert!(
+            mode.handle_key(key(KeyCode::Esc), &keymap, position)
+                .is_empty()
+        );
+
+        mode.handle_key(key(KeyCode::Char('2')), &keymap, position);
+        mode.handle_key(key(KeyCode::Char(']')), &keymap, position);
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('c')), &keymap, position),
+            vec![AppEvent::Repeat {
+                count: 2,
+                event: Box::new(AppEvent::BufferManager(BufferManagerEvent::NextHunk)),
+            }]
+        );
+        assert!(
+            mode.handle_key(key(KeyCode::Char('0')), &keymap, position)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn should_switch_windows_with_ctrl_w() {
+        let mode = NormalMode::default();
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Normal, &buffer);
+        let position = Position::new(0, 0);
+        let ctrl_w = KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL);
+
+        assert!(mode.handle_key(ctrl_w, &keymap, position).is_empty());
+        assert_eq!(
+            mode.handle_key(ctrl_w, &keymap, position),
+            vec![AppEvent::Window(WindowEvent::Next)]
+        );
+
+        mode.handle_key(ctrl_w, &keymap, position);
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('o')), &keymap, position),
+            vec![AppEvent::Window(WindowEvent::Only)]
+        );
+    }
+
+    #[test]
+    fn should_drop_unknown_two_key_commands() {
+        let mode = NormalMode::default();
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Normal, &buffer);
+        let position = Position::new(0, 0);
+
+        mode.handle_key(key(KeyCode::Char(']')), &keymap, position);
+
+        assert!(
+            mode.handle_key(key(KeyCode::Char('q')), &keymap, position)
+                .is_empty()
+        );
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('q')), &keymap, position),
+            vec![AppEvent::Quit]
+        );
+    }
+
+    #[test]
+    fn should_run_the_bindings_shadowing_the_defaults() {
+        let mode = NormalMode::default();
+        let keymaps = Keymaps::new();
+        let mut buffer = HashMap::new();
+        bind(
+            &mut buffer,
+            KeymapMode::Normal,
+            &parse_keys("q"),
+            Action::Command(String::from("w")),
+        );
+        bind(
+            &mut buffer,
+            KeymapMode::Normal,
+            &parse_keys("gj"),
+            Action::MoveDown,
+        );
+        let keymap = keymaps.get(KeymapMode::Normal, &buffer);
+        let position = Position::new(0, 0);
+
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('q')), &keymap, position),
+            vec![AppEvent::SubmitCommandLine {
+                kind: CommandLineKind::Command,
+                input: String::from("w"),
+            }]
+        );
 
-        events
+        mode.handle_key(key(KeyCode::Char('3')), &keymap, position);
+        mode.handle_key(key(KeyCode::Char('g')), &keymap, position);
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('j')), &keymap, position),
+            vec![AppEvent::Repeat {
+                count: 3,
+                event: Box::new(AppEvent::Cursor(CursorEvent::MoveDown)),
+            }]
+        );
     }
 }
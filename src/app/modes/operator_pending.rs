@@ -0,0 +1,251 @@
+use super::{Mode, push_count_digit};
+use crate::app::keymap::{Action, Binding, Keymap, Lookup};
+use crate::app::modes::{EditorMode, normal::MAX_COUNT};
+use crate::app::operator::{Operator, Target};
+use crate::event::AppEvent;
+use crate::types::position::Position;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::cell::{Cell, RefCell};
+
+/// Keys handled in operator-pending mode, listed in the help screen.
+pub const BINDINGS: &[Binding] = &[
+    Binding {
+        keys: "h / l, Left / Right",
+        description: "Act on the characters before or from the cursor",
+    },
+    Binding {
+        keys: "j / k, Down / Up",
+        description: "Act on the cursor line and the lines below or above",
+    },
+    Binding {
+        keys: "0 / $",
+        description: "Act up to the start or the end of the line",
+    },
+    Binding {
+        keys: "iw / aw",
+        description: "Act on the word, without or with its blanks",
+    },
+    Binding {
+        keys: "dd / cc / yy / guu / gUU / g~~",
+        description: "Act on whole lines",
+    },
+    Binding {
+        keys: "{count}",
+        description: "Repeat the motion, times the count before the operator",
+    },
+    Binding {
+        keys: "Esc",
+        description: "Cancel the operator",
+    },
+];
+
+/// Waits for the motion or text object of an operator, then applies it.
+#[derive(Debug)]
+pub struct OperatorPendingMode {
+    pub operator: Operator,
+    /// Count typed before the operator, as in `3dl`.
+    count: Option<usize>,
+    /// Count typed after the operator, before the motion, as in `d3l`.
+    motion_count: Cell<Option<usize>>,
+    /// Keys typed so far of a motion bound to several keys, like `iw`, or `g` for `gugu`
+    /// and the like.
+    keys: RefCell<Vec<KeyEvent>>,
+}
+
+impl OperatorPendingMode {
+    /// Creates an `OperatorPendingMode` for `operator`, typed after `count`.
+    pub fn new(operator: Operator, count: Option<usize>) -> Self {
+        Self {
+            operator,
+            count,
+            motion_count: Cell::new(None),
+            keys: RefCell::new(vec![]),
+        }
+    }
+
+    /// Returns the target typed with `keys`. `None` cancels the operator; `Some(None)`
+    /// waits for another key.
+    fn target(&self, keys: &[KeyEvent], keymap: &Keymap) -> Option<Option<Target>> {
+        let doubled = self.operator.doubled();
+        let codes: Vec<_> = keys.iter().map(|key| key.code).collect();
+
+        // The operator typed again acts on whole lines, as in `dd`, `gUU` and `gUgU`.
+        match codes.as_slice() {
+            [KeyCode::Char(key)] | [KeyCode::Char('g'), KeyCode::Char(key)] if *key == doubled => {
+                return Some(Some(Target::Lines));
+            }
+            [KeyCode::Char('g')] if Operator::from_g_key(doubled).is_some() => return Some(None),
+            _ => {}
+        }
+
+        let target = match keymap.lookup(keys) {
+            Lookup::Action(Action::MoveLeft) => Target::Left,
+            Lookup::Action(Action::MoveRight) => Target::Right,
+            Lookup::Action(Action::MoveDown) => Target::Down,
+            Lookup::Action(Action::MoveUp) => Target::Up,
+            Lookup::Action(Action::LineStart) => Target::LineStart,
+            Lookup::Action(Action::LineEnd) => Target::LineEnd,
+            Lookup::Action(Action::InnerWord) => Target::InnerWord,
+            Lookup::Action(Action::AWord) => Target::AWord,
+            Lookup::Prefix => return Some(None),
+            _ => return None,
+        };
+
+        Some(Some(target))
+    }
+
+    /// Returns the count the target is repeated by: both counts multiplied.
+    fn total_count(&self) -> Option<usize> {
+        match (self.count, self.motion_count.get()) {
+            (None, None) => None,
+            (before, after) => Some(
+                before
+                    .unwrap_or(1)
+                    .saturating_mul(after.unwrap_or(1))
+                    .min(MAX_COUNT),
+            ),
+        }
+    }
+}
+
+impl Mode for OperatorPendingMode {
+    fn get_mode_label(&self) -> &'static str {
+        self.operator.label()
+    }
+
+    fn get_current_mode(&self) -> EditorMode {
+        EditorMode::OperatorPending {
+            operator: self.operator,
+            count: self.count,
+        }
+    }
+
+    fn pending_count(&self) -> Option<usize> {
+        self.total_count()
+    }
+
+    fn handle_key(
+        &self,
+        key: KeyEvent,
+        keymap: &Keymap,
+        current_cursor_position: Position,
+    ) -> Vec<AppEvent> {
+        if self.keys.borrow().is_empty() && push_count_digit(&self.motion_count, key) {
+            return vec![];
+        }
+
+        self.keys.borrow_mut().push(key);
+        let target = match self.target(&self.keys.borrow(), keymap) {
+            Some(None) => return vec![],
+            Some(Some(target)) => Some(target),
+            // `Esc`, or any key that is not a motion, cancels the operator.
+            None => None,
+        };
+        self.keys.take();
+
+        let mut events = vec![AppEvent::ChangeToMode(EditorMode::Normal)];
+        if let Some(target) = target {
+            events.push(AppEvent::Operate {
+                operator: self.operator,
+                target,
+                count: self.total_count().unwrap_or(1),
+                position: current_cursor_position,
+            });
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::keymap::{KeymapMode, Keymaps};
+    use crossterm::event::KeyModifiers;
+    use std::collections::HashMap;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn operate(operator: Operator, target: Target, count: usize) -> Vec<AppEvent> {
+        vec![
+            AppEvent::ChangeToMode(EditorMode::Normal),
+            AppEvent::Operate {
+                operator,
+                target,
+                count,
+                position: Position::new(0, 0),
+            },
+        ]
+    }
+
+    #[test]
+    fn should_multiply_counts_and_compose_text_objects() {
+        let mode = OperatorPendingMode::new(Operator::Delete, Some(2));
+        let position = Position::new(0, 0);
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::OperatorPending, &buffer);
+
+        mode.handle_key(key(KeyCode::Char('3')), &keymap, position);
+        assert_eq!(mode.pending_count(), Some(6));
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('j')), &keymap, position),
+            operate(Operator::Delete, Target::Down, 6)
+        );
+
+        let mode = OperatorPendingMode::new(Operator::Yank, None);
+        assert!(
+            mode.handle_key(key(KeyCode::Char('a')), &keymap, position)
+                .is_empty()
+        );
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('w')), &keymap, position),
+            operate(Operator::Yank, Target::AWord, 1)
+        );
+    }
+
+    #[test]
+    fn should_act_on_lines_when_the_operator_is_typed_again() {
+        let position = Position::new(0, 0);
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::OperatorPending, &buffer);
+
+        let mode = OperatorPendingMode::new(Operator::Change, None);
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('c')), &keymap, position),
+            operate(Operator::Change, Target::Lines, 1)
+        );
+
+        let mode = OperatorPendingMode::new(Operator::Uppercase, None);
+        mode.handle_key(key(KeyCode::Char('g')), &keymap, position);
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('U')), &keymap, position),
+            operate(Operator::Uppercase, Target::Lines, 1)
+        );
+    }
+
+    #[test]
+    fn should_cancel_on_esc_and_unknown_keys() {
+        let position = Position::new(0, 0);
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::OperatorPending, &buffer);
+        let cancel = vec![AppEvent::ChangeToMode(EditorMode::Normal)];
+
+        let mode = OperatorPendingMode::new(Operator::Delete, None);
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Esc), &keymap, position),
+            cancel
+        );
+
+        let mode = OperatorPendingMode::new(Operator::Delete, None);
+        mode.handle_key(key(KeyCode::Char('i')), &keymap, position);
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('x')), &keymap, position),
+            cancel
+        );
+    }
+}
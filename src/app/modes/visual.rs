@@ -1,27 +1,351 @@
 use super::Mode;
-use crate::event::AppEvent;
-use crate::{app::modes::EditorMode, types::position::Position};
+use crate::app::keymap::{Action, Binding, Keymap, Lookup};
+use crate::app::marks::{VISUAL_END_MARK, VISUAL_START_MARK};
+use crate::event::{AppEvent, CursorEvent, MarkEvent, SearchEvent};
+use crate::ui::components::command_line::CommandLineKind;
+use crate::{
+    app::modes::{EditorMode, VisualKind},
+    types::{position::Position, selection::Selection},
+};
 use crossterm::event::{KeyCode, KeyEvent};
+use std::cell::Cell;
+
+/// Range prefilled in the command line when it is opened from visual mode.
+const VISUAL_RANGE: &str = "'<,'>";
+
+/// Quotes and brackets typed over a selection to wrap it, with their closing pair.
+const SURROUND_PAIRS: &[(char, char)] = &[
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+];
+
+/// Keys handled in visual mode, listed in the help screen.
+pub const BINDINGS: &[Binding] = &[
+    Binding {
+        keys: "h / j / k / l, arrows",
+        description: "Extend the selection",
+    },
+    Binding {
+        keys: "*",
+        description: "Search for the selected text",
+    },
+    Binding {
+        keys: "Y",
+        description: "Copy the selection to the system clipboard",
+    },
+    Binding {
+        keys: "\" / ' / ` / ( / [ / {",
+        description: "Wrap the selection in the pair (:set autosurround)",
+    },
+    Binding {
+        keys: ":",
+        description: "Run a command on the selected lines",
+    },
+    Binding {
+        keys: "v / V",
+        description: "Select characters or whole lines, or go back to normal mode",
+    },
+    Binding {
+        keys: "Esc",
+        description: "Go back to normal mode",
+    },
+];
 
 #[derive(Debug)]
-pub struct VisualMode;
+pub struct VisualMode {
+    /// Position where the selection started; the cursor is its other end.
+    pub anchor: Position,
+    /// Whether characters or whole lines are selected, switched by `v` and `V`.
+    kind: Cell<VisualKind>,
+}
+
+impl VisualMode {
+    /// Creates a `VisualMode` whose selection of `kind` is anchored at `anchor`.
+    pub fn new(anchor: Position, kind: VisualKind) -> Self {
+        Self {
+            anchor,
+            kind: Cell::new(kind),
+        }
+    }
+
+    /// Returns the ordered selection bounds between the anchor and the cursor, from the
+    /// start of the first line to past the end of the last one for whole lines.
+    fn selection(&self, current_cursor_position: Position) -> (Position, Position) {
+        let selection =
+            Selection::new(self.anchor, current_cursor_position, self.kind.get().into());
+        let (start, end) = (selection.start(), selection.end());
+
+        match self.kind.get() {
+            VisualKind::Char => (start, end),
+            VisualKind::Line => (
+                Position::new(start.line, 0),
+                Position::new(end.line, usize::MAX),
+            ),
+        }
+    }
+
+    /// Selects by `kind`, or leaves visual mode when it already does, as `v` and `V` do.
+    fn switch(&self, kind: VisualKind, current_cursor_position: Position) -> Vec<AppEvent> {
+        if self.kind.get() == kind {
+            return self.leave(current_cursor_position);
+        }
+
+        self.kind.set(kind);
+        vec![]
+    }
+
+    /// Records the selection bounds in the `'<` and `'>` marks and leaves visual mode.
+    fn leave(&self, current_cursor_position: Position) -> Vec<AppEvent> {
+        let (start, end) = self.selection(current_cursor_position);
+
+        vec![
+            AppEvent::Mark(MarkEvent::Set {
+                mark: VISUAL_START_MARK,
+                position: start,
+            }),
+            AppEvent::Mark(MarkEvent::Set {
+                mark: VISUAL_END_MARK,
+                position: end,
+            }),
+            AppEvent::ChangeToMode(EditorMode::Normal),
+        ]
+    }
+}
 
 impl Mode for VisualMode {
     fn get_mode_label(&self) -> &'static str {
-        "visual"
+        match self.kind.get() {
+            VisualKind::Char => "visual",
+            VisualKind::Line => "visual line",
+        }
     }
 
     fn get_current_mode(&self) -> EditorMode {
-        EditorMode::Visual
+        EditorMode::Visual {
+            kind: self.kind.get(),
+        }
     }
 
-    fn handle_key(&self, key: KeyEvent, _: Position) -> Vec<AppEvent> {
+    fn handle_key(
+        &self,
+        key: KeyEvent,
+        keymap: &Keymap,
+        current_cursor_position: Position,
+    ) -> Vec<AppEvent> {
         let mut events = vec![];
 
-        if key.code == KeyCode::Esc {
-            events.push(AppEvent::ChangeToMode(EditorMode::Normal))
+        let action = match keymap.lookup(&[key]) {
+            Lookup::Action(action) => action,
+            _ => {
+                if let KeyCode::Char(open) = key.code
+                    && let Some((open, close)) = SURROUND_PAIRS
+                        .iter()
+                        .find(|(pair, _)| *pair == open)
+                        .copied()
+                {
+                    let (start, end) = self.selection(current_cursor_position);
+                    events.push(AppEvent::Surround {
+                        open,
+                        close,
+                        start,
+                        end,
+                    });
+                }
+                return events;
+            }
+        };
+
+        match action {
+            Action::MoveLeft => events.push(AppEvent::Cursor(CursorEvent::MoveLeft)),
+            Action::MoveRight => events.push(AppEvent::Cursor(CursorEvent::MoveRight)),
+            Action::MoveDown => events.push(AppEvent::Cursor(CursorEvent::MoveDown)),
+            Action::MoveUp => events.push(AppEvent::Cursor(CursorEvent::MoveUp)),
+
+            Action::SearchSelection => {
+                let (start, end) = self.selection(current_cursor_position);
+
+                events.extend(self.leave(current_cursor_position));
+                events.push(AppEvent::Search(SearchEvent::Selection { start, end }));
+            }
+            Action::Copy => {
+                let (start, end) = self.selection(current_cursor_position);
+
+                events.extend(self.leave(current_cursor_position));
+                events.push(AppEvent::Copy { start, end });
+            }
+            Action::CommandLine => {
+                events.extend(self.leave(current_cursor_position));
+                events.push(AppEvent::OpenCommandLine {
+                    kind: CommandLineKind::Command,
+                    prefill: String::from(VISUAL_RANGE),
+                });
+            }
+            Action::Command(input) => {
+                events.extend(self.leave(current_cursor_position));
+                events.push(AppEvent::SubmitCommandLine {
+                    kind: CommandLineKind::Command,
+                    input,
+                });
+            }
+            Action::Normal => events.extend(self.leave(current_cursor_position)),
+            Action::Visual => events.extend(self.switch(VisualKind::Char, current_cursor_position)),
+            Action::VisualLine => {
+                events.extend(self.switch(VisualKind::Line, current_cursor_position))
+            }
+
+            _ => {}
         }
 
         events
     }
+
+    fn get_selection(&self, current_cursor_position: Position) -> Option<(Position, Position)> {
+        Some(self.selection(current_cursor_position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::keymap::{KeymapMode, Keymaps};
+    use crossterm::event::KeyModifiers;
+    use std::collections::HashMap;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn should_order_selection_regardless_of_direction() {
+        let mode = VisualMode::new(Position::new(2, 3), VisualKind::Char);
+
+        assert_eq!(
+            mode.get_selection(Position::new(0, 1)),
+            Some((Position::new(0, 1), Position::new(2, 3)))
+        );
+    }
+
+    #[test]
+    fn should_search_for_selection_on_star() {
+        let mode = VisualMode::new(Position::new(0, 4), VisualKind::Char);
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Visual, &buffer);
+
+        let events = mode.handle_key(key(KeyCode::Char('*')), &keymap, Position::new(0, 1));
+
+        assert!(events.contains(&AppEvent::ChangeToMode(EditorMode::Normal)));
+        assert_eq!(
+            events.last(),
+            Some(&AppEvent::Search(SearchEvent::Selection {
+                start: Position::new(0, 1),
+                end: Position::new(0, 4),
+            }))
+        );
+    }
+
+    #[test]
+    fn should_copy_selection_on_capital_y() {
+        let mode = VisualMode::new(Position::new(2, 3), VisualKind::Line);
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Visual, &buffer);
+
+        let events = mode.handle_key(key(KeyCode::Char('Y')), &keymap, Position::new(1, 1));
+
+        assert!(events.contains(&AppEvent::ChangeToMode(EditorMode::Normal)));
+        assert_eq!(
+            events.last(),
+            Some(&AppEvent::Copy {
+                start: Position::new(1, 0),
+                end: Position::new(2, usize::MAX),
+            })
+        );
+    }
+
+    #[test]
+    fn should_surround_selection_on_opening_pair() {
+        let mode = VisualMode::new(Position::new(0, 4), VisualKind::Char);
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Visual, &buffer);
+
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('[')), &keymap, Position::new(0, 1)),
+            vec![AppEvent::Surround {
+                open: '[',
+                close: ']',
+                start: Position::new(0, 1),
+                end: Position::new(0, 4),
+            }]
+        );
+        assert!(
+            mode.handle_key(key(KeyCode::Char(']')), &keymap, Position::new(0, 1))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn should_set_marks_and_prefill_range_on_colon() {
+        let mode = VisualMode::new(Position::new(1, 0), VisualKind::Char);
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Visual, &buffer);
+
+        let events = mode.handle_key(key(KeyCode::Char(':')), &keymap, Position::new(3, 2));
+
+        assert_eq!(
+            events,
+            vec![
+                AppEvent::Mark(MarkEvent::Set {
+                    mark: '<',
+                    position: Position::new(1, 0),
+                }),
+                AppEvent::Mark(MarkEvent::Set {
+                    mark: '>',
+                    position: Position::new(3, 2),
+                }),
+                AppEvent::ChangeToMode(EditorMode::Normal),
+                AppEvent::OpenCommandLine {
+                    kind: CommandLineKind::Command,
+                    prefill: String::from("'<,'>"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_select_whole_lines_and_switch_kinds() {
+        let mode = VisualMode::new(Position::new(2, 3), VisualKind::Line);
+        let keymaps = Keymaps::new();
+        let buffer = HashMap::new();
+        let keymap = keymaps.get(KeymapMode::Visual, &buffer);
+        let cursor = Position::new(1, 1);
+
+        assert_eq!(mode.get_mode_label(), "visual line");
+        assert_eq!(
+            mode.get_selection(cursor),
+            Some((Position::new(1, 0), Position::new(2, usize::MAX)))
+        );
+
+        assert!(
+            mode.handle_key(key(KeyCode::Char('v')), &keymap, cursor)
+                .is_empty()
+        );
+        assert_eq!(
+            mode.get_current_mode(),
+            EditorMode::Visual {
+                kind: VisualKind::Char
+            }
+        );
+        assert_eq!(
+            mode.handle_key(key(KeyCode::Char('v')), &keymap, cursor)
+                .last(),
+            Some(&AppEvent::ChangeToMode(EditorMode::Normal))
+        );
+    }
 }
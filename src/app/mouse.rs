@@ -0,0 +1,426 @@
+//! Mouse handling module for the Zack text editor.
+//!
+//! This module provides the [`Mouse`] struct, which turns terminal mouse events into
+//! editor actions while mouse support is enabled (`:set mouse`). Clicking the text moves
+//! the cursor, clicking a gutter sign runs its [`SignAction`], and resting the pointer
+//! over a sign or an underlined diagnostic for [`HOVER_DELAY`] shows its message in a
+//! floating [`Tooltip`]. Dragging over the text selects it in visual mode, from where
+//! the button was pressed, so it can be copied without the gutter and borders.
+
+use crate::{
+    app::{
+        buffer_manager::Document,
+        diagnostics::SignAction,
+        modes::{EditorMode, VisualKind},
+        options::Options,
+    },
+    event::{AppEvent, CursorEvent},
+    ui::components::editor::{Editor, EditorHit},
+};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use std::time::{Duration, Instant};
+
+/// How long the pointer must rest on something before its tooltip appears.
+pub const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+/// Text shown in a floating box next to a screen cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tooltip {
+    pub column: u16,
+    pub row: u16,
+    pub text: String,
+}
+
+/// Where the pointer is resting, and since when.
+#[derive(Clone, Copy, Debug)]
+struct Hover {
+    column: u16,
+    row: u16,
+    since: Instant,
+}
+
+/// State of the left button held after being pressed on the text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Drag {
+    /// Pressed, not moved yet.
+    Pressed,
+    /// Moved, selecting in visual mode.
+    Selecting,
+}
+
+/// Tracks the pointer and the tooltip pinned by the last sign click.
+#[derive(Debug, Default)]
+pub struct Mouse {
+    hover: Option<Hover>,
+    last_click: Option<(u16, u16)>,
+    pinned: Option<Tooltip>,
+    drag: Option<Drag>,
+}
+
+impl Mouse {
+    /// Creates a `Mouse` with no pointer position recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a terminal mouse event over the editor `area` showing `document`.
+    /// Returns any follow-up `AppEvent`s.
+    pub fn handle_event(
+        &mut self,
+        event: MouseEvent,
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+    ) -> Vec<AppEvent> {
+        let mut events = vec![];
+
+        match event.kind {
+            MouseEventKind::Moved => {
+                let moved = self
+                    .hover
+                    .is_none_or(|hover| (hover.column, hover.row) != (event.column, event.row));
+
+                if moved {
+                    self.hover = Some(Hover {
+                        column: event.column,
+                        row: event.row,
+                        since: Instant::now(),
+                    });
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.hover = None;
+                self.pinned = None;
+                self.last_click = Some((event.column, event.row));
+                self.drag = None;
+
+                match Editor::hit_test(document, options, tabstop, area, event.column, event.row) {
+                    Some(EditorHit::Gutter { line }) => {
+                        if let Some(sign) = document.signs().into_iter().find(|s| s.line == line) {
+                            events.push(AppEvent::RunSignAction(sign.action));
+                        }
+                    }
+                    Some(EditorHit::Minimap { line }) => {
+                        events.push(AppEvent::Cursor(CursorEvent::SetPosition { line, col: 0 }));
+                    }
+                    Some(EditorHit::Text(position)) => {
+                        self.drag = Some(Drag::Pressed);
+                        events.push(AppEvent::Cursor(CursorEvent::SetPosition {
+                            line: position.line,
+                            col: position.col,
+                        }));
+                    }
+                    None => {}
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.drag.is_some() => {
+                let Some(EditorHit::Text(position)) =
+                    Editor::hit_test(document, options, tabstop, area, event.column, event.row)
+                else {
+                    return events;
+                };
+
+                // The cursor is still where the button was pressed: it anchors the selection.
+                if self.drag == Some(Drag::Pressed) {
+                    self.drag = Some(Drag::Selecting);
+                    events.push(AppEvent::ChangeToMode(EditorMode::Visual {
+                        kind: VisualKind::Char,
+                    }));
+                }
+                events.push(AppEvent::Cursor(CursorEvent::SetPosition {
+                    line: position.line,
+                    col: position.col,
+                }));
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.drag = None,
+            _ => {}
+        }
+
+        events
+    }
+
+    /// Returns the tooltip to draw: the pinned one, or the hovered one once the pointer
+    /// has rested for [`HOVER_DELAY`].
+    pub fn tooltip(
+        &self,
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+    ) -> Option<Tooltip> {
+        if let Some(pinned) = &self.pinned {
+            return Some(pinned.clone());
+        }
+
+        let hover = self.hover?;
+
+        if hover.since.elapsed() < HOVER_DELAY {
+            return None;
+        }
+
+        Self::hover_text(document, options, tabstop, area, hover.column, hover.row).map(|text| {
+            Tooltip {
+                column: hover.column,
+                row: hover.row,
+                text,
+            }
+        })
+    }
+
+    /// Returns the text describing what lies under the screen cell at `column`, `row`:
+    /// the tooltip of a gutter sign, or the messages of the diagnostics covering the text.
+    pub fn hover_text(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+        column: u16,
+        row: u16,
+    ) -> Option<String> {
+        match Editor::hit_test(document, options, tabstop, area, column, row)? {
+            EditorHit::Gutter { line } => document
+                .signs()
+                .into_iter()
+                .find(|sign| sign.line == line)
+                .map(|sign| sign.tooltip),
+            EditorHit::Text(position) => {
+                let messages: Vec<String> = document
+                    .diagnostics
+                    .iter()
+                    .filter(|diagnostic| diagnostic.contains(position))
+                    .map(|diagnostic| diagnostic.describe())
+                    .collect();
+
+                (!messages.is_empty()).then(|| messages.join("\n"))
+            }
+            EditorHit::Minimap { .. } => None,
+        }
+    }
+
+    /// Runs a sign's action, pinning its output at the last clicked cell.
+    pub fn run_sign_action(&mut self, action: SignAction, document: &Document) {
+        let (column, row) = self.last_click.unwrap_or_default();
+
+        let text = match action {
+            SignAction::ShowDiagnostics { line } => document
+                .diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.start.line == line)
+                .map(|diagnostic| {
+                    format!(
+                        "{}:{}: {}",
+                        diagnostic.start.line + 1,
+                        diagnostic.start.col + 1,
+                        diagnostic.describe()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            SignAction::ShowHunk { line } => document
+                .hunks
+                .iter()
+                .find(|hunk| hunk.start == line)
+                .map(|hunk| hunk.describe())
+                .unwrap_or_default(),
+        };
+
+        if !text.is_empty() {
+            self.pinned = Some(Tooltip { column, row, text });
+        }
+    }
+
+    /// Hides the pinned tooltip and forgets the hover, e.g. after a key press.
+    pub fn dismiss(&mut self) {
+        self.hover = None;
+        self.pinned = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::diagnostics::{Diagnostic, Severity},
+        types::position::Position,
+    };
+    use crossterm::event::KeyModifiers;
+
+    fn area() -> Rect {
+        Rect::new(0, 0, 40, 10)
+    }
+
+    fn document() -> Document {
+        let mut document = Document::new(String::from("fn main() {\n    let x;\n}"), None);
+        document.diagnostics = vec![Diagnostic {
+            start: Position::new(1, 8),
+            end: Position::new(1, 9),
+            severity: Severity::Warning,
+            message: String::from("unused variable: `x`"),
+        }];
+
+        document
+    }
+
+    fn mouse_event(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn should_describe_diagnostic_under_pointer() {
+        let document = document();
+
+        // Border (1) + gutter (2) + col 8 = column 11, line 1 = row 2.
+        assert_eq!(
+            Mouse::hover_text(&document, &Options::new(), 4, area(), 11, 2),
+            Some(String::from("warning: unused variable: `x`"))
+        );
+        assert_eq!(
+            Mouse::hover_text(&document, &Options::new(), 4, area(), 10, 2),
+            None
+        );
+    }
+
+    #[test]
+    fn should_describe_sign_under_pointer() {
+        let document = document();
+
+        assert_eq!(
+            Mouse::hover_text(&document, &Options::new(), 4, area(), 1, 2),
+            Some(String::from("warning: unused variable: `x`"))
+        );
+        assert_eq!(
+            Mouse::hover_text(&document, &Options::new(), 4, area(), 1, 1),
+            None
+        );
+    }
+
+    #[test]
+    fn should_wait_for_hover_delay_before_showing_tooltip() {
+        let document = document();
+        let mut mouse = Mouse::new();
+
+        mouse.handle_event(
+            mouse_event(MouseEventKind::Moved, 11, 2),
+            &document,
+            &Options::new(),
+            4,
+            area(),
+        );
+        assert_eq!(mouse.tooltip(&document, &Options::new(), 4, area()), None);
+
+        mouse.hover = mouse.hover.map(|hover| Hover {
+            since: Instant::now() - HOVER_DELAY,
+            ..hover
+        });
+        assert!(
+            mouse
+                .tooltip(&document, &Options::new(), 4, area())
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn should_run_sign_action_when_clicking_sign() {
+        let document = document();
+        let mut mouse = Mouse::new();
+
+        let events = mouse.handle_event(
+            mouse_event(MouseEventKind::Down(MouseButton::Left), 1, 2),
+            &document,
+            &Options::new(),
+            4,
+            area(),
+        );
+
+        assert_eq!(
+            events,
+            vec![AppEvent::RunSignAction(SignAction::ShowDiagnostics {
+                line: 1
+            })]
+        );
+    }
+
+    #[test]
+    fn should_move_cursor_when_clicking_text() {
+        let document = document();
+        let mut mouse = Mouse::new();
+
+        let events = mouse.handle_event(
+            mouse_event(MouseEventKind::Down(MouseButton::Left), 5, 1),
+            &document,
+            &Options::new(),
+            4,
+            area(),
+        );
+
+        assert_eq!(
+            events,
+            vec![AppEvent::Cursor(CursorEvent::SetPosition {
+                line: 0,
+                col: 2
+            })]
+        );
+    }
+
+    #[test]
+    fn should_select_in_visual_mode_when_dragging_over_text() {
+        let document = document();
+        let mut mouse = Mouse::new();
+        let mut drag = |kind, column| {
+            mouse.handle_event(
+                mouse_event(kind, column, 2),
+                &document,
+                &Options::new(),
+                4,
+                area(),
+            )
+        };
+        let set_position = |col| AppEvent::Cursor(CursorEvent::SetPosition { line: 1, col });
+
+        drag(MouseEventKind::Down(MouseButton::Left), 5);
+        assert_eq!(
+            drag(MouseEventKind::Drag(MouseButton::Left), 9),
+            vec![
+                AppEvent::ChangeToMode(EditorMode::Visual {
+                    kind: VisualKind::Char
+                }),
+                set_position(6),
+            ]
+        );
+        assert_eq!(
+            drag(MouseEventKind::Drag(MouseButton::Left), 11),
+            vec![set_position(8)]
+        );
+
+        drag(MouseEventKind::Up(MouseButton::Left), 11);
+        assert!(drag(MouseEventKind::Drag(MouseButton::Left), 5).is_empty());
+    }
+
+    #[test]
+    fn should_pin_sign_action_output_until_dismissed() {
+        let document = document();
+        let mut mouse = Mouse::new();
+        mouse.last_click = Some((1, 2));
+
+        mouse.run_sign_action(SignAction::ShowDiagnostics { line: 1 }, &document);
+
+        assert_eq!(
+            mouse.tooltip(&document, &Options::new(), 4, area()),
+            Some(Tooltip {
+                column: 1,
+                row: 2,
+                text: String::from("2:9: warning: unused variable: `x`"),
+            })
+        );
+
+        mouse.dismiss();
+        assert_eq!(mouse.tooltip(&document, &Options::new(), 4, area()), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! Notifications module for the Zack text editor.
+//!
+//! Everything the editor has to tell about, from a failed save to a finished export, is
+//! logged with the `tracing` macros from wherever it happens, including background
+//! threads. The [`Layer`] installed at startup turns the info, warning and error events
+//! of the editor into [`Notification`]s, and the application collects them into its
+//! [`Notifications`] on every tick. The latest one is shown in a bar at the bottom of the
+//! editor until its level's timeout passes, dimmed shortly before it goes away, and
+//! `:messages` lists the whole history.
+//!
+//! Records at the level set with `--log-level` are also kept for `:log`, and written to
+//! the file given with `--log-file` (see [`logging`]). Secrets are masked everywhere (see
+//! [`redact`]).
+
+use crate::app::{logging, redact};
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write as _},
+    fs::File,
+    io::Write as _,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::{
+    Event, Level as TracingLevel, Metadata, Subscriber,
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+};
+use tracing_subscriber::{layer::Context, prelude::*};
+
+/// Most notifications kept for `:messages`.
+const MAX_HISTORY: usize = 200;
+
+/// How long before its timeout a notification is dimmed.
+const FADE: Duration = Duration::from_millis(800);
+
+/// Notifications logged since the last time they were collected.
+static PENDING: Mutex<Vec<Notification>> = Mutex::new(vec![]);
+
+/// How important a notification is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+/// A message for the user, with when it was logged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+    pub level: Level,
+    pub text: String,
+    pub time: Instant,
+}
+
+/// The notifications of the session, newest last.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    history: VecDeque<Notification>,
+}
+
+/// Forwards the events of the editor to the notifications, and the events at the
+/// logging level to `:log` and to the log file when there is one.
+pub struct Layer {
+    file: Option<Mutex<File>>,
+}
+
+/// Collects the message of an event followed by its other fields, as `message key=value`.
+#[derive(Default)]
+struct Message {
+    text: String,
+    fields: String,
+}
+
+impl Level {
+    /// Returns the name shown in `:messages`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+
+    /// Returns how long a notification of this level stays in the bar; errors stay
+    /// longer, since they matter more.
+    pub fn timeout(&self) -> Duration {
+        match self {
+            Level::Info => Duration::from_secs(3),
+            Level::Warn => Duration::from_secs(5),
+            Level::Error => Duration::from_secs(8),
+        }
+    }
+}
+
+impl Notification {
+    /// Creates a notification logged at `time`.
+    pub fn new(level: Level, text: impl Into<String>, time: Instant) -> Self {
+        Self {
+            level,
+            text: text.into(),
+            time,
+        }
+    }
+
+    /// Returns `true` if the notification is shown in the bar at `now`.
+    pub fn is_visible(&self, now: Instant) -> bool {
+        now.duration_since(self.time) < self.level.timeout()
+    }
+
+    /// Returns `true` if the notification is about to leave the bar at `now`.
+    pub fn is_fading(&self, now: Instant) -> bool {
+        now.duration_since(self.time) + FADE >= self.level.timeout()
+    }
+}
+
+impl Notifications {
+    /// Creates an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `notification`, forgetting the oldest one past [`MAX_HISTORY`].
+    pub fn push(&mut self, notification: Notification) {
+        self.history.push_back(notification);
+
+        if self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    /// Moves the notifications logged since the last call into the history.
+    pub fn collect(&mut self) {
+        let pending = match PENDING.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return,
+        };
+
+        for notification in pending {
+            self.push(notification);
+        }
+    }
+
+    /// Returns the notification shown in the bar at `now`: the latest one, until it
+    /// times out.
+    pub fn current(&self, now: Instant) -> Option<&Notification> {
+        self.history
+            .back()
+            .filter(|notification| notification.is_visible(now))
+    }
+
+    /// Lists the notifications, oldest first, as `age  level  text`.
+    pub fn describe(&self, now: Instant) -> String {
+        if self.history.is_empty() {
+            return String::from("No messages");
+        }
+
+        self.history
+            .iter()
+            .map(|notification| {
+                format!(
+                    "{:>8}  {:<5}  {}",
+                    format_age(now.duration_since(notification.time)),
+                    notification.level.name(),
+                    notification.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.text.push_str(value),
+            name => {
+                let _ = write!(self.fields, " {name}={value}");
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.text, "{value:?}");
+            }
+            name => {
+                let _ = write!(self.fields, " {name}={value:?}");
+            }
+        }
+    }
+}
+
+impl<S: Subscriber> tracing_subscriber::Layer<S> for Layer {
+    fn enabled(&self, metadata: &Metadata<'_>, _: Context<'_, S>) -> bool {
+        *metadata.level() <= TracingLevel::INFO || *metadata.level() <= logging::level()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(logging::level().max(LevelFilter::INFO))
+    }
+
+    /// Only events of the editor itself become notifications, not those of its
+    /// dependencies.
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let mut message = Message::default();
+        event.record(&mut message);
+        let text = redact::redact(&format!("{}{}", message.text, message.fields));
+        let metadata = event.metadata();
+
+        if let (Some(entry), Some(file)) = (
+            logging::keep(*metadata.level(), metadata.target(), &text),
+            &self.file,
+        ) && let Ok(mut file) = file.lock()
+        {
+            let _ = writeln!(file, "{}", entry.describe());
+        }
+
+        let level = match *metadata.level() {
+            TracingLevel::ERROR => Level::Error,
+            TracingLevel::WARN => Level::Warn,
+            TracingLevel::INFO => Level::Info,
+            _ => return,
+        };
+        if !metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            return;
+        }
+
+        if let Ok(mut pending) = PENDING.lock() {
+            pending.push(Notification::new(level, text, Instant::now()));
+        }
+    }
+}
+
+/// Installs the layer, keeping the events up to `level` and writing them to `file` as well
+/// when given.
+pub fn init(level: LevelFilter, file: Option<File>) {
+    logging::set_level(level);
+
+    let layer = Layer {
+        file: file.map(Mutex::new),
+    };
+    let _ = tracing_subscriber::registry().with(layer).try_init();
+}
+
+/// Formats an age as `12s ago`, `3m ago` or `2h ago`.
+fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        seconds @ 0..60 => format!("{seconds}s ago"),
+        seconds @ 60..3600 => format!("{}m ago", seconds / 60),
+        seconds => format!("{}h ago", seconds / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_show_the_latest_notification_until_it_times_out() {
+        let mut notifications = Notifications::new();
+        let now = Instant::now();
+
+        assert_eq!(notifications.current(now), None);
+
+        notifications.push(Notification::new(Level::Error, "failed", now));
+        notifications.push(Notification::new(Level::Info, "saved", now));
+        let latest = notifications.current(now).unwrap();
+
+        assert_eq!(latest.text, "saved");
+        assert!(!latest.is_fading(now));
+        assert!(latest.is_fading(now + Duration::from_millis(2500)));
+        assert_eq!(notifications.current(now + Duration::from_secs(4)), None);
+    }
+
+    #[test]
+    fn should_keep_events_with_their_fields_for_log_and_the_log_file() {
+        let path = std::env::temp_dir().join("test_notifications_layer.log");
+        let layer = Layer {
+            file: Some(Mutex::new(File::create(&path).unwrap())),
+        };
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::warn!(path = "notes.txt", bytes = 12, "Cannot save");
+        });
+
+        let entry = logging::recent(logging::MAX_RECENT)
+            .into_iter()
+            .find(|entry| entry.text.starts_with("Cannot save"))
+            .unwrap();
+        assert_eq!(entry.text, "Cannot save path=notes.txt bytes=12");
+        assert_eq!(entry.level, TracingLevel::WARN);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", entry.describe())
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_keep_a_bounded_history() {
+        let mut notifications = Notifications::new();
+        let now = Instant::now();
+
+        for index in 0..=MAX_HISTORY {
+            notifications.push(Notification::new(Level::Info, index.to_string(), now));
+        }
+
+        assert_eq!(notifications.history.len(), MAX_HISTORY);
+        assert_eq!(notifications.history[0].text, "1");
+    }
+
+    #[test]
+    fn should_describe_the_history_with_ages_and_levels() {
+        let mut notifications = Notifications::new();
+        let now = Instant::now();
+
+        assert_eq!(notifications.describe(now), "No messages");
+
+        notifications.push(Notification::new(Level::Warn, "careful", now));
+
+        assert_eq!(
+            notifications.describe(now + Duration::from_secs(90)),
+            "  1m ago  warn   careful"
+        );
+    }
+}
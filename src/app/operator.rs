@@ -0,0 +1,577 @@
+//! Operators for the Zack text editor.
+//!
+//! An operator typed in normal mode (`d`, `c`, `y`, `gu`, `gU`, `g~`, `gq`) waits in
+//! operator-pending mode for a motion or a text object, then acts on the text it covers:
+//! `dl` deletes a character, `c$` changes up to the end of the line, `yiw` yanks the word
+//! under the cursor. Typing the operator again acts on whole lines, as in `dd` or `3yy`,
+//! and counts before the operator and before the motion multiply, as in `2d3j`.
+//!
+//! Deleted, changed and yanked text goes to the unnamed register, which `p` puts after
+//! the cursor, or below the cursor line for whole lines.
+
+use crate::{
+    app::{buffer::Buffer, leader, modes::EditorMode, wrap},
+    event::{AppEvent, BufferEvent, CursorEvent},
+    types::position::Position,
+};
+
+/// What an operator does to the text it covers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    /// `d`: deletes it.
+    Delete,
+    /// `c`: deletes it and enters insert mode.
+    Change,
+    /// `y`: copies it to the unnamed register.
+    Yank,
+    /// `gu`: makes it lowercase.
+    Lowercase,
+    /// `gU`: makes it uppercase.
+    Uppercase,
+    /// `g~`: switches the case of each letter.
+    ToggleCase,
+    /// `gq`: wraps the lines it touches to the text width.
+    Format,
+}
+
+/// How `gq` wraps lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wrap<'a> {
+    /// Columns the lines are wrapped at.
+    pub width: usize,
+    /// Line comment marker of the file type, kept at the start of wrapped comments.
+    pub comment: Option<&'a str>,
+}
+
+/// The text an operator acts on, given by a motion or a text object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// `h`: the characters before the cursor.
+    Left,
+    /// `l`: the characters from the cursor.
+    Right,
+    /// `0`: up to the start of the line.
+    LineStart,
+    /// `$`: up to the end of the line.
+    LineEnd,
+    /// `k`: the cursor line and the lines above.
+    Up,
+    /// `j`: the cursor line and the lines below.
+    Down,
+    /// The operator typed again: the cursor line and the lines below, as many as the count.
+    Lines,
+    /// `iw`: the word, or the run of blanks, under the cursor.
+    InnerWord,
+    /// `aw`: the word under the cursor with the blanks after it, or before it at the end
+    /// of the line.
+    AWord,
+}
+
+/// A stretch of the buffer covered by a target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Span {
+    /// Columns `start..end` of `line`.
+    Chars {
+        line: usize,
+        start: usize,
+        end: usize,
+    },
+    /// The whole lines `first..=last`.
+    Lines { first: usize, last: usize },
+}
+
+/// Text kept by `d`, `c` and `y`, put back by `p` and `]p`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Register {
+    pub text: String,
+    /// Set when the text is made of whole lines, ending with a line break.
+    pub linewise: bool,
+}
+
+impl Operator {
+    /// Returns the key typing the operator again to act on whole lines, as in `dd` or
+    /// `guu`.
+    pub fn doubled(self) -> char {
+        match self {
+            Operator::Delete => 'd',
+            Operator::Change => 'c',
+            Operator::Yank => 'y',
+            Operator::Lowercase => 'u',
+            Operator::Uppercase => 'U',
+            Operator::ToggleCase => '~',
+            Operator::Format => 'q',
+        }
+    }
+
+    /// Returns the operator typed as `g` then `key`.
+    pub fn from_g_key(key: char) -> Option<Self> {
+        match key {
+            'u' => Some(Operator::Lowercase),
+            'U' => Some(Operator::Uppercase),
+            '~' => Some(Operator::ToggleCase),
+            'q' => Some(Operator::Format),
+            _ => None,
+        }
+    }
+
+    /// Returns the label shown while the operator waits for its target.
+    pub fn label(self) -> &'static str {
+        match self {
+            Operator::Delete => "pending d",
+            Operator::Change => "pending c",
+            Operator::Yank => "pending y",
+            Operator::Lowercase => "pending gu",
+            Operator::Uppercase => "pending gU",
+            Operator::ToggleCase => "pending g~",
+            Operator::Format => "pending gq",
+        }
+    }
+
+    /// Returns `true` for the operators keeping the text in the unnamed register.
+    pub fn keeps_text(self) -> bool {
+        matches!(self, Operator::Delete | Operator::Change | Operator::Yank)
+    }
+
+    /// Returns the events applying the operator to `span`, wrapping lines as `wrap` says.
+    pub fn apply(self, span: Span, buffer: &Buffer, wrap: Wrap) -> Vec<AppEvent> {
+        let span = match (self, span) {
+            // `gq` always acts on whole lines.
+            (Operator::Format, Span::Chars { line, .. }) => Span::Lines {
+                first: line,
+                last: line,
+            },
+            _ => span,
+        };
+        let start = span.start();
+        let mut events = match (self, span) {
+            (Operator::Yank, _) => vec![],
+            (Operator::Format, Span::Lines { first, last }) => {
+                vec![AppEvent::Buffer(BufferEvent::ReplaceLines {
+                    start: first,
+                    end: last,
+                    text: wrap::hard_wrap(&span.text(buffer), wrap.width, wrap.comment) + "\n",
+                })]
+            }
+            (Operator::Delete, Span::Lines { first, last }) => delete_lines(buffer, first, last),
+            (Operator::Change, Span::Lines { first, last }) => {
+                vec![AppEvent::Buffer(BufferEvent::ReplaceLines {
+                    start: first,
+                    end: last,
+                    text: String::from("\n"),
+                })]
+            }
+            (Operator::Delete | Operator::Change, Span::Chars { line, start, end }) => {
+                vec![replace_chars(line, start, end, String::new())]
+            }
+            (case, Span::Chars { line, start, end }) => {
+                let text = case.convert(&span.text(buffer));
+                vec![replace_chars(line, start, end, text)]
+            }
+            (case, Span::Lines { first, last }) => {
+                vec![AppEvent::Buffer(BufferEvent::ReplaceLines {
+                    start: first,
+                    end: last,
+                    text: case.convert(&span.text(buffer)),
+                })]
+            }
+        };
+
+        match self {
+            // Entering insert mode first, so the text typed joins the same undo step.
+            Operator::Change => events.insert(
+                0,
+                AppEvent::ChangeToMode(EditorMode::Insert { append: false }),
+            ),
+            Operator::Delete if matches!(span, Span::Lines { .. }) => {}
+            _ => events.push(AppEvent::Cursor(CursorEvent::SetPosition {
+                line: start.line,
+                col: start.col,
+            })),
+        }
+
+        events
+    }
+
+    /// Changes the case of `text` as `gu`, `gU` and `g~` do; other operators leave it.
+    fn convert(self, text: &str) -> String {
+        match self {
+            Operator::Lowercase => text.to_lowercase(),
+            Operator::Uppercase => text.to_uppercase(),
+            Operator::ToggleCase => text
+                .chars()
+                .flat_map(|char| match char.is_uppercase() {
+                    true => char.to_lowercase().collect::<Vec<_>>(),
+                    false => char.to_uppercase().collect(),
+                })
+                .collect(),
+            _ => text.to_string(),
+        }
+    }
+}
+
+impl Span {
+    /// Returns where the span starts, where the cursor goes after most operators.
+    pub fn start(&self) -> Position {
+        match *self {
+            Span::Chars { line, start, .. } => Position::new(line, start),
+            Span::Lines { first, .. } => Position::new(first, 0),
+        }
+    }
+
+    /// Returns the text covered, ending with a line break for whole lines.
+    pub fn text(&self, buffer: &Buffer) -> String {
+        match *self {
+            Span::Chars { line, start, end } => {
+                buffer.as_rope().line(line).slice(start..end).to_string()
+            }
+            Span::Lines { first, last } => {
+                let mut text = buffer.lines_text(first, last);
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                text
+            }
+        }
+    }
+
+    /// Returns the text covered as a register.
+    pub fn register(&self, buffer: &Buffer) -> Register {
+        Register {
+            text: self.text(buffer),
+            linewise: matches!(self, Span::Lines { .. }),
+        }
+    }
+}
+
+impl Register {
+    /// Returns the register with its whole lines indented like the line `line`: the first
+    /// one takes its indentation, and the others keep theirs relative to the first. Text
+    /// that is not made of whole lines is kept as is.
+    pub fn reindented(&self, buffer: &Buffer, line: usize) -> Register {
+        if !self.linewise {
+            return self.clone();
+        }
+
+        let line = buffer.lines_text(line, line);
+        let target = leader::indentation(&line);
+        let first = self
+            .text
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .map(leader::indentation)
+            .unwrap_or_default();
+
+        let text = self
+            .text
+            .lines()
+            .map(|line| match line.strip_prefix(first) {
+                _ if line.trim().is_empty() => String::new(),
+                Some(rest) => format!("{target}{rest}"),
+                // Lines indented less than the first one are brought up to it.
+                None => format!("{target}{}", line.trim_start()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Register {
+            text: text + "\n",
+            linewise: true,
+        }
+    }
+
+    /// Returns the events putting the text after the cursor at `position`, or below its
+    /// line for whole lines, leaving the cursor at the start of what was put.
+    pub fn put(&self, buffer: &Buffer, position: Position) -> Vec<AppEvent> {
+        let end = buffer.max_visible_col(&position);
+
+        match self.linewise {
+            true => vec![
+                AppEvent::Buffer(BufferEvent::InsertText {
+                    text: format!("\n{}", self.text.strip_suffix('\n').unwrap_or(&self.text)),
+                    position: Position::new(position.line, end),
+                }),
+                AppEvent::Cursor(CursorEvent::SetPosition {
+                    line: position.line + 1,
+                    col: 0,
+                }),
+            ],
+            false => {
+                let col = (position.col + 1).min(end);
+                vec![
+                    AppEvent::Buffer(BufferEvent::InsertText {
+                        text: self.text.clone(),
+                        position: Position::new(position.line, col),
+                    }),
+                    AppEvent::Cursor(CursorEvent::SetPosition {
+                        line: position.line,
+                        col,
+                    }),
+                ]
+            }
+        }
+    }
+}
+
+/// Returns the stretch of `buffer` covered by `target` from the cursor at `position`,
+/// repeated `count` times, or `None` when it covers nothing.
+pub fn span(buffer: &Buffer, target: Target, count: usize, position: Position) -> Option<Span> {
+    let count = count.max(1);
+    let line = position.line;
+    let last_line = buffer.len_lines().saturating_sub(1);
+    let chars: Vec<char> = buffer
+        .as_rope()
+        .line(line)
+        .chars()
+        .take(buffer.max_visible_col(&position))
+        .collect();
+    let col = position.col.min(chars.len());
+    let columns =
+        |start: usize, end: usize| (start < end).then_some(Span::Chars { line, start, end });
+
+    match target {
+        Target::Left => columns(col.saturating_sub(count), col),
+        Target::Right => columns(col, (col + count).min(chars.len())),
+        Target::LineStart => columns(0, col),
+        Target::LineEnd => columns(col, chars.len()),
+        Target::Up => (line > 0).then(|| Span::Lines {
+            first: line.saturating_sub(count),
+            last: line,
+        }),
+        Target::Down => (line < last_line).then(|| Span::Lines {
+            first: line,
+            last: (line + count).min(last_line),
+        }),
+        Target::Lines => Some(Span::Lines {
+            first: line,
+            last: (line + count - 1).min(last_line),
+        }),
+        Target::InnerWord | Target::AWord => {
+            if col >= chars.len() {
+                return None;
+            }
+
+            let mut start = run_start(&chars, col);
+            let mut end = col;
+            for _ in 0..count {
+                end = run_end(&chars, end);
+                let blank = class(chars[end - 1]) == Class::Blank;
+                let blank_before = start > 0 && class(chars[start - 1]) == Class::Blank;
+                if target == Target::AWord {
+                    match end < chars.len() && (blank || class(chars[end]) == Class::Blank) {
+                        // A word and the blanks after it, or blanks and the word after them.
+                        true => end = run_end(&chars, end),
+                        // No blanks after the last word: take those before it.
+                        false if !blank && blank_before => start = run_start(&chars, start - 1),
+                        false => {}
+                    }
+                }
+                if end >= chars.len() {
+                    break;
+                }
+            }
+
+            columns(start, end)
+        }
+    }
+}
+
+/// Kinds of characters whose runs make words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+fn class(char: char) -> Class {
+    match char {
+        _ if char.is_whitespace() => Class::Blank,
+        _ if char.is_alphanumeric() || char == '_' => Class::Word,
+        _ => Class::Punctuation,
+    }
+}
+
+/// Returns where the run of characters of the same class as `chars[col]` starts.
+fn run_start(chars: &[char], col: usize) -> usize {
+    let kind = class(chars[col]);
+    chars[..col]
+        .iter()
+        .rposition(|char| class(*char) != kind)
+        .map_or(0, |before| before + 1)
+}
+
+/// Returns where the run of characters of the same class as `chars[col]` ends, exclusive.
+fn run_end(chars: &[char], col: usize) -> usize {
+    let kind = class(chars[col]);
+    chars[col..]
+        .iter()
+        .position(|char| class(*char) != kind)
+        .map_or(chars.len(), |after| col + after)
+}
+
+/// Returns the edit replacing columns `start..end` of `line` with `text`.
+fn replace_chars(line: usize, start: usize, end: usize, text: String) -> AppEvent {
+    AppEvent::Buffer(BufferEvent::ReplaceRange {
+        start: Position::new(line, start),
+        end: Position::new(line, end),
+        text,
+    })
+}
+
+/// Returns the events deleting the lines `first..=last` and leaving the cursor on the
+/// line after them, or on the line before when they were the last ones.
+fn delete_lines(buffer: &Buffer, first: usize, last: usize) -> Vec<AppEvent> {
+    let at_end = last + 1 >= buffer.len_lines();
+
+    match (at_end, first.checked_sub(1)) {
+        // Removing the line break before them too, so no empty line is left at the end.
+        (true, Some(before)) => vec![
+            AppEvent::Buffer(BufferEvent::ReplaceRange {
+                start: Position::new(before, buffer.max_visible_col(&Position::new(before, 0))),
+                end: Position::new(last, buffer.max_visible_col(&Position::new(last, 0))),
+                text: String::new(),
+            }),
+            AppEvent::Cursor(CursorEvent::SetPosition {
+                line: before,
+                col: 0,
+            }),
+        ],
+        _ => vec![AppEvent::Buffer(BufferEvent::ReplaceLines {
+            start: first,
+            end: last,
+            text: String::new(),
+        })],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(text: &str) -> Buffer {
+        Buffer::new(text.to_string())
+    }
+
+    #[test]
+    fn should_find_the_span_of_each_target() {
+        let buffer = buffer("let answer = 42;\nsecond\nthird");
+        let at = Position::new(0, 5);
+        let span = |target, count| span(&buffer, target, count, at);
+
+        assert_eq!(
+            span(Target::Right, 3),
+            Some(Span::Chars {
+                line: 0,
+                start: 5,
+                end: 8
+            })
+        );
+        assert_eq!(
+            span(Target::Left, 9),
+            Some(Span::Chars {
+                line: 0,
+                start: 0,
+                end: 5
+            })
+        );
+        assert_eq!(
+            span(Target::LineEnd, 1),
+            Some(Span::Chars {
+                line: 0,
+                start: 5,
+                end: 16
+            })
+        );
+        assert_eq!(span(Target::Up, 1), None);
+        assert_eq!(
+            span(Target::Down, 5),
+            Some(Span::Lines { first: 0, last: 2 })
+        );
+        assert_eq!(
+            span(Target::Lines, 2),
+            Some(Span::Lines { first: 0, last: 1 })
+        );
+        assert_eq!(
+            span(Target::InnerWord, 1),
+            Some(Span::Chars {
+                line: 0,
+                start: 4,
+                end: 10
+            })
+        );
+        assert_eq!(
+            span(Target::AWord, 1),
+            Some(Span::Chars {
+                line: 0,
+                start: 4,
+                end: 11
+            })
+        );
+        assert_eq!(
+            span(Target::InnerWord, 3),
+            Some(Span::Chars {
+                line: 0,
+                start: 4,
+                end: 12
+            })
+        );
+    }
+
+    #[test]
+    fn should_take_the_blanks_before_the_last_word_of_a_line() {
+        let buffer = buffer("one two");
+
+        assert_eq!(
+            span(&buffer, Target::AWord, 1, Position::new(0, 5)),
+            Some(Span::Chars {
+                line: 0,
+                start: 3,
+                end: 7
+            })
+        );
+    }
+
+    #[test]
+    fn should_switch_the_case_of_each_letter() {
+        assert_eq!(Operator::ToggleCase.convert("Hello, World"), "hELLO, wORLD");
+        assert_eq!(Operator::Uppercase.convert("ß"), "SS");
+    }
+
+    #[test]
+    fn should_put_lines_below_the_cursor_line() {
+        let buffer = buffer("one\ntwo");
+        let register = Register {
+            text: String::from("new\n"),
+            linewise: true,
+        };
+
+        assert_eq!(
+            register.put(&buffer, Position::new(1, 1))[0],
+            AppEvent::Buffer(BufferEvent::InsertText {
+                text: String::from("\nnew"),
+                position: Position::new(1, 3),
+            })
+        );
+    }
+
+    #[test]
+    fn should_indent_put_lines_like_the_cursor_line() {
+        let buffer = buffer("fn main() {\n    body();\n}");
+        let register = Register {
+            text: String::from("if ok {\n\tdone();\n\n}\n"),
+            linewise: true,
+        };
+
+        assert_eq!(
+            register.reindented(&buffer, 1).text,
+            "    if ok {\n    \tdone();\n\n    }\n"
+        );
+        assert_eq!(register.reindented(&buffer, 0).text, register.text);
+
+        let charwise = Register {
+            text: String::from("  word"),
+            linewise: false,
+        };
+        assert_eq!(charwise.reindented(&buffer, 1), charwise);
+    }
+}
@@ -0,0 +1,367 @@
+//! Editor options for the Zack text editor.
+//!
+//! This module provides the [`Options`] struct, holding the settings users can change
+//! at runtime with `:set`, and the parser for `:set` arguments. Boolean options follow
+//! the usual modal-editor conventions: `:set name` enables, `:set noname` disables and
+//! `:set invname` (or `:set name!`) toggles. Other options take a value, as in
+//! `:set complete=lsp,buffer`.
+
+use crate::app::{completion::CompletionSource, redact, statusline::StatusLine};
+use std::fmt;
+
+/// Runtime settings of the editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Capture mouse events for hovering, clicking signs and placing the cursor.
+    pub mouse: bool,
+    /// Continue long lines on the following screen rows instead of cutting them.
+    pub wrap: bool,
+    /// Show line numbers in the gutter.
+    pub number: bool,
+    /// Make `j` and `k` move by screen rows instead of buffer lines while wrapping.
+    pub displaymoves: bool,
+    /// Show the size of the file on disk in the status line.
+    pub filesize: bool,
+    /// Show when the file on disk was last modified in the status line.
+    pub filetime: bool,
+    /// Show who last changed the cursor line, at its end.
+    pub blame: bool,
+    /// Show the cursor line, the line count and the column in the status line.
+    pub ruler: bool,
+    /// Tint the editor background by buffer kind, as defined by the theme.
+    pub tint: bool,
+    /// Dim the windows without the focus: all of them while another component, like the
+    /// command line, has the focus.
+    pub dim: bool,
+    /// Completion sources to ask, from the most to the least important.
+    pub complete: Vec<CompletionSource>,
+    /// Start a new undo step at the end of each sentence typed in insert mode.
+    pub undobreak: bool,
+    /// Start a new undo step when typing resumes after this many milliseconds in insert
+    /// mode; `0` never does.
+    pub undopause: u64,
+    /// Bytes above which a document skips per-edit work like git diffing; `0` for no
+    /// limit.
+    pub maxsize: usize,
+    /// Chars in a line above which a document skips per-edit work; `0` for no limit.
+    pub maxline: usize,
+    /// Command `:hardcopy` pipes the pages to.
+    pub printcmd: String,
+    /// Lines on a printed page, header included.
+    pub printlines: usize,
+    /// Keep the colors of the text as ANSI escape codes when printing.
+    pub printcolor: bool,
+    /// Patterns of the secrets masked in the message pane, notifications and logs.
+    pub secrets: Vec<String>,
+    /// Wrap a visual selection in the pair of a quote or bracket typed over it.
+    pub autosurround: bool,
+    /// Continue the comment marker or list bullet of a line on the one `Enter` opens after
+    /// it in insert mode.
+    pub autocontinue: bool,
+    /// Ticks per second, running periodic work like disk checks and animations.
+    pub tickrate: u32,
+    /// Ticks per second while the editor is idle; `0` only wakes it up for input.
+    pub idletickrate: u32,
+    /// Insert spaces instead of a tab character on `Tab` in insert mode.
+    pub expandtab: bool,
+    /// Columns of the indentation `Tab` inserts when `expandtab` is set.
+    pub tabstop: usize,
+    /// Columns `gq` wraps lines at.
+    pub textwidth: usize,
+    /// Add a missing final line break to files when writing them.
+    pub fixeol: bool,
+    /// Build command `:make` runs, its output read into the quickfix list.
+    pub makeprg: String,
+    /// Show the line, word and char counts of the buffer, or of the selection, in the
+    /// status line.
+    pub wordcount: bool,
+    /// Show a scrollbar on the right of windows whose text does not fit, marking the
+    /// lines with search matches and diagnostics.
+    pub scrollbar: bool,
+    /// Layout of the status line, or `None` for the mode label alone.
+    pub statusline: Option<StatusLine>,
+    /// List the buffers on the top row while several are open.
+    pub bufferline: bool,
+    /// Show a braille overview of the buffer on the right of each window.
+    pub minimap: bool,
+    /// Milliseconds without typing before the `CursorHold` hooks run.
+    pub updatetime: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            mouse: false,
+            wrap: false,
+            number: false,
+            displaymoves: false,
+            filesize: false,
+            filetime: false,
+            blame: false,
+            ruler: true,
+            tint: true,
+            dim: true,
+            complete: CompletionSource::DEFAULT.to_vec(),
+            undobreak: true,
+            undopause: 2000,
+            maxsize: 10 * 1024 * 1024,
+            maxline: 20_000,
+            printcmd: String::from("lpr"),
+            printlines: 66,
+            printcolor: false,
+            secrets: redact::default_patterns(),
+            autosurround: true,
+            autocontinue: true,
+            tickrate: 30,
+            idletickrate: 1,
+            expandtab: false,
+            tabstop: 4,
+            textwidth: 79,
+            fixeol: true,
+            makeprg: String::from("make"),
+            wordcount: false,
+            scrollbar: true,
+            statusline: None,
+            bufferline: true,
+            minimap: false,
+            updatetime: 4000,
+        }
+    }
+}
+
+/// Errors produced by `:set`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionError {
+    /// No option has this name.
+    Unknown(String),
+    /// The value given to an option is not one it accepts.
+    InvalidValue(String),
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionError::Unknown(name) => write!(f, "Unknown option: {name}"),
+            OptionError::InvalidValue(argument) => write!(f, "Invalid argument: {argument}"),
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
+
+impl Options {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a single `:set` argument, such as `mouse`, `nomouse` or `invmouse`.
+    pub fn set(&mut self, argument: &str) -> Result<(), OptionError> {
+        let argument = argument.trim();
+        let unknown = || OptionError::Unknown(argument.to_string());
+
+        if let Some((name, value)) = argument.split_once('=') {
+            return self.set_value(name, value, argument);
+        }
+
+        if let Some(name) = argument
+            .strip_prefix("inv")
+            .or_else(|| argument.strip_suffix('!'))
+        {
+            let flag = self.boolean(name).ok_or_else(unknown)?;
+            *flag = !*flag;
+        } else if let Some(flag) = self.boolean(argument) {
+            *flag = true;
+        } else if let Some(name) = argument.strip_prefix("no") {
+            *self.boolean(name).ok_or_else(unknown)? = false;
+        } else {
+            return Err(unknown());
+        }
+
+        Ok(())
+    }
+
+    /// Applies `name=value` for options taking a value; `argument` is the whole text,
+    /// for errors.
+    fn set_value(&mut self, name: &str, value: &str, argument: &str) -> Result<(), OptionError> {
+        match name {
+            "cpt" | "complete" => {
+                self.complete = value
+                    .split(',')
+                    .filter(|source| !source.is_empty())
+                    .map(|source| {
+                        CompletionSource::from_name(source)
+                            .ok_or_else(|| OptionError::InvalidValue(argument.to_string()))
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "undopause" => self.undopause = parse_number(value, argument)?,
+            "maxsize" => self.maxsize = parse_number(value, argument)?,
+            "maxline" => self.maxline = parse_number(value, argument)?,
+            "printcmd" if !value.trim().is_empty() => self.printcmd = value.trim().to_string(),
+            "printcmd" => return Err(OptionError::InvalidValue(argument.to_string())),
+            "mp" | "makeprg" if !value.trim().is_empty() => self.makeprg = value.trim().to_string(),
+            "mp" | "makeprg" => return Err(OptionError::InvalidValue(argument.to_string())),
+            "stl" | "statusline" if value.trim().is_empty() => self.statusline = None,
+            "stl" | "statusline" => {
+                self.statusline = Some(
+                    StatusLine::parse(value)
+                        .map_err(|_| OptionError::InvalidValue(argument.to_string()))?,
+                );
+            }
+            "ut" | "updatetime" => self.updatetime = parse_number(value, argument)?,
+            "printlines" => self.printlines = parse_number(value, argument)?,
+            "tickrate" => match parse_number(value, argument)? {
+                0 => return Err(OptionError::InvalidValue(argument.to_string())),
+                rate => self.tickrate = rate,
+            },
+            "idletickrate" => self.idletickrate = parse_number(value, argument)?,
+            "ts" | "tabstop" => match parse_number(value, argument)? {
+                0 => return Err(OptionError::InvalidValue(argument.to_string())),
+                width => self.tabstop = width,
+            },
+            "tw" | "textwidth" => match parse_number(value, argument)? {
+                0 => return Err(OptionError::InvalidValue(argument.to_string())),
+                width => self.textwidth = width,
+            },
+            "secrets" => {
+                self.secrets = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            _ => return Err(OptionError::Unknown(argument.to_string())),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the boolean option called `name`.
+    fn boolean(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "mouse" => Some(&mut self.mouse),
+            "scrollbar" => Some(&mut self.scrollbar),
+            "minimap" => Some(&mut self.minimap),
+            "bufferline" => Some(&mut self.bufferline),
+            "wrap" => Some(&mut self.wrap),
+            "nu" | "number" => Some(&mut self.number),
+            "displaymoves" => Some(&mut self.displaymoves),
+            "filesize" => Some(&mut self.filesize),
+            "filetime" => Some(&mut self.filetime),
+            "blame" => Some(&mut self.blame),
+            "ru" | "ruler" => Some(&mut self.ruler),
+            "tint" => Some(&mut self.tint),
+            "dim" => Some(&mut self.dim),
+            "undobreak" => Some(&mut self.undobreak),
+            "printcolor" => Some(&mut self.printcolor),
+            "autosurround" => Some(&mut self.autosurround),
+            "autocontinue" => Some(&mut self.autocontinue),
+            "fixeol" | "fixendofline" => Some(&mut self.fixeol),
+            "et" | "expandtab" => Some(&mut self.expandtab),
+            "wc" | "wordcount" => Some(&mut self.wordcount),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the value of a numeric option; `argument` is the whole text, for errors.
+fn parse_number<T: std::str::FromStr>(value: &str, argument: &str) -> Result<T, OptionError> {
+    value
+        .parse()
+        .map_err(|_| OptionError::InvalidValue(argument.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_enable_disable_and_toggle_boolean_options() {
+        let mut options = Options::new();
+
+        options.set("mouse").unwrap();
+        assert!(options.mouse);
+
+        options.set("nomouse").unwrap();
+        assert!(!options.mouse);
+
+        options.set("invmouse").unwrap();
+        assert!(options.mouse);
+
+        options.set("mouse!").unwrap();
+        assert!(!options.mouse);
+    }
+
+    #[test]
+    fn should_set_completion_sources_in_order() {
+        let mut options = Options::new();
+
+        options.set("complete=dictionary,buffer").unwrap();
+
+        assert_eq!(
+            options.complete,
+            vec![CompletionSource::Dictionary, CompletionSource::Buffer]
+        );
+        assert_eq!(
+            options.set("cpt=lsp,nope"),
+            Err(OptionError::InvalidValue(String::from("cpt=lsp,nope")))
+        );
+    }
+
+    #[test]
+    fn should_set_numeric_options() {
+        let mut options = Options::new();
+
+        options.set("undopause=500").unwrap();
+        options.set("maxline=0").unwrap();
+
+        assert_eq!(options.undopause, 500);
+        assert_eq!(options.maxline, 0);
+
+        options.set("secrets=ghp_, token=").unwrap();
+        assert_eq!(options.secrets, vec!["ghp_", "token="]);
+        options.set("secrets=").unwrap();
+        assert!(options.secrets.is_empty());
+
+        options.set("makeprg=cargo build").unwrap();
+        assert_eq!(options.makeprg, "cargo build");
+
+        options.set("printcmd=lpr -P office").unwrap();
+        assert_eq!(options.printcmd, "lpr -P office");
+        assert_eq!(
+            options.set("printcmd="),
+            Err(OptionError::InvalidValue(String::from("printcmd=")))
+        );
+        assert_eq!(
+            options.set("undopause=soon"),
+            Err(OptionError::InvalidValue(String::from("undopause=soon")))
+        );
+
+        options.set("tickrate=60").unwrap();
+        options.set("idletickrate=0").unwrap();
+        assert_eq!((options.tickrate, options.idletickrate), (60, 0));
+
+        options.set("ts=8").unwrap();
+        assert_eq!(options.tabstop, 8);
+        assert_eq!(
+            options.set("tabstop=0"),
+            Err(OptionError::InvalidValue(String::from("tabstop=0")))
+        );
+        assert_eq!(
+            options.set("tickrate=0"),
+            Err(OptionError::InvalidValue(String::from("tickrate=0")))
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_options() {
+        let mut options = Options::new();
+
+        assert_eq!(
+            options.set("nosuchthing"),
+            Err(OptionError::Unknown(String::from("nosuchthing")))
+        );
+    }
+}
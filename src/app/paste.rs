@@ -0,0 +1,121 @@
+//! Paste handling module for the Zack text editor.
+//!
+//! Terminals deliver files dragged onto their window as pasted text: a plain path,
+//! often quoted or with escaped spaces, or a `file://` URI. This module recognizes such
+//! text with [`dropped_path`], so the editor can offer to open the file instead of
+//! ignoring the paste.
+
+use std::path::PathBuf;
+
+/// Returns the path of an existing file if `text` is a single, path-looking string.
+///
+/// Accepts absolute paths, paths starting with `~/`, `./` or `../`, and `file://` URIs,
+/// optionally wrapped in single or double quotes or with backslash-escaped characters.
+pub fn dropped_path(text: &str) -> Option<PathBuf> {
+    let text = text.trim();
+
+    if text.is_empty() || text.contains('\n') {
+        return None;
+    }
+
+    let text = unquote(text);
+    let path = match text.strip_prefix("file://") {
+        Some(uri) => percent_decode(uri.strip_prefix("localhost").unwrap_or(uri))?,
+        None => unescape(text),
+    };
+
+    let path = if let Some(rest) = path.strip_prefix("~/") {
+        PathBuf::from(std::env::var_os("HOME")?).join(rest)
+    } else if ["/", "./", "../"]
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+    {
+        PathBuf::from(path)
+    } else {
+        return None;
+    };
+
+    path.is_file().then_some(path)
+}
+
+/// Removes one pair of matching surrounding quotes.
+fn unquote(text: &str) -> &str {
+    ['\'', '"']
+        .iter()
+        .find_map(|quote| text.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(text)
+}
+
+/// Resolves shell-style backslash escapes, like `\ ` for a space.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Decodes `%XX` sequences of a URI path, failing on malformed ones.
+pub fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = text.get(index + 1..index + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_recognize_existing_paths_in_dropped_forms() {
+        let path = std::env::current_dir().unwrap().join("test_paste drop.txt");
+        std::fs::write(&path, "").unwrap();
+
+        let plain = path.to_string_lossy().to_string();
+        let escaped = plain.replace(' ', "\\ ");
+        let quoted = format!("'{plain}'");
+        let uri = format!("file://{}", plain.replace(' ', "%20"));
+
+        for text in [&plain, &escaped, &quoted, &uri] {
+            assert_eq!(dropped_path(&format!("{text}\n")), Some(path.clone()));
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn should_ignore_text_that_is_not_an_existing_path() {
+        assert_eq!(dropped_path("hello world"), None);
+        assert_eq!(dropped_path("/definitely/not/here.txt"), None);
+        assert_eq!(dropped_path("./src\n./Cargo.toml"), None);
+        assert_eq!(dropped_path("file:///bad%zz"), None);
+    }
+
+    #[test]
+    fn should_accept_relative_paths_with_explicit_prefix() {
+        assert_eq!(
+            dropped_path("./Cargo.toml"),
+            Some(PathBuf::from("./Cargo.toml"))
+        );
+        assert_eq!(dropped_path("Cargo.toml"), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! Pretty view of minified files for the Zack text editor.
+//!
+//! A minified script or a JSON dump is often a single enormous line, which is neither
+//! readable cut at the edge of the screen nor wrapped at arbitrary columns. The pretty
+//! view shows such lines as segments ending at synthetic breaks: after each `;`, `{`
+//! and `}`, and otherwise at the last `,` or space that fits. The breaks only exist on
+//! screen: each segment is a range of chars of the real line, so the cursor and edits
+//! keep working on the real offsets. This module finds the segments, and tells when an
+//! opened file looks minified, to offer the view.
+
+use crate::app::buffer::Buffer;
+
+/// Chars in a line above which a file of a few lines looks minified.
+pub const MINIFIED_LINE_LEN: usize = 1000;
+
+/// Most lines a minified file has, counting an empty last line after its line break.
+const MINIFIED_MAX_LINES: usize = 3;
+
+/// Returns the length of the longest line of `buffer` when it looks minified: a few
+/// lines, one of them over [`MINIFIED_LINE_LEN`] chars.
+pub fn minified_line_len(buffer: &Buffer) -> Option<usize> {
+    if buffer.len_lines() > MINIFIED_MAX_LINES {
+        return None;
+    }
+
+    buffer
+        .lines()
+        .map(|line| line.len_chars())
+        .max()
+        .filter(|len| *len > MINIFIED_LINE_LEN)
+}
+
+/// Splits `chars` into segments of at most `width` chars, as `(start, end)` ranges.
+///
+/// A segment always ends after `;`, `{` or `}`. A longer one ends after the last `,`
+/// or space that fits, or at `width` chars when there is none.
+pub fn segments(chars: &[char], width: usize) -> Vec<(usize, usize)> {
+    let width = width.max(1);
+    let mut segments = vec![];
+    let mut start = 0;
+
+    while start < chars.len() {
+        let limit = (start + width).min(chars.len());
+        let within = &chars[start..limit];
+
+        let end = if let Some(index) = within.iter().position(|c| matches!(c, ';' | '{' | '}')) {
+            start + index + 1
+        } else if limit == chars.len() {
+            limit
+        } else if let Some(index) = within.iter().rposition(|c| matches!(c, ',' | ' ')) {
+            start + index + 1
+        } else {
+            limit
+        };
+
+        segments.push((start, end));
+        start = end;
+    }
+
+    if segments.is_empty() {
+        segments.push((0, 0));
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(text: &str, width: usize) -> Vec<String> {
+        let chars: Vec<char> = text.chars().collect();
+
+        segments(&chars, width)
+            .into_iter()
+            .map(|(start, end)| chars[start..end].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn should_break_after_statements_and_blocks() {
+        assert_eq!(
+            split("a=1;if(a){b()}c", 80),
+            vec!["a=1;", "if(a){", "b()}", "c"]
+        );
+    }
+
+    #[test]
+    fn should_break_long_segments_at_commas_then_at_the_width() {
+        assert_eq!(split("f(aa,bb,cc)", 7), vec!["f(aa,", "bb,cc)"]);
+        assert_eq!(split("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(split("", 3), vec![""]);
+    }
+
+    #[test]
+    fn should_recognize_minified_files() {
+        let minified = Buffer::new(format!("{}\n", "x;".repeat(MINIFIED_LINE_LEN)));
+        let source = Buffer::new(format!("{}\n", "x\n".repeat(MINIFIED_LINE_LEN)));
+
+        assert_eq!(
+            minified_line_len(&minified),
+            Some(2 * MINIFIED_LINE_LEN + 1)
+        );
+        assert_eq!(minified_line_len(&source), None);
+    }
+}
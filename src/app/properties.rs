@@ -0,0 +1,214 @@
+//! Property tests of the editing engine of the Zack text editor.
+//!
+//! These tests feed random sequences of [`BufferEvent`]s and [`CursorEvent`]s to a
+//! [`Buffer`] and a [`Cursor`], the edits made at the cursor as the modes make them, and
+//! check after each step that the cursor stays inside the text, that the change log
+//! replays to the same text and that undoing and redoing bring back the exact content.
+
+use crate::{
+    app::{buffer::Buffer, cursor::Cursor},
+    event::{AppEvent, BufferEvent, CursorEvent},
+    types::position::Position,
+};
+use proptest::prelude::*;
+use ropey::Rope;
+
+/// A step of a random editing session.
+#[derive(Clone, Debug)]
+enum Step {
+    /// An edit, made at the cursor or, for ranges, from it to `to`.
+    Edit(Edit),
+    /// A cursor move.
+    Move(CursorEvent),
+    Undo,
+    Redo,
+    /// Closes the current undo step, as leaving insert mode does.
+    BreakUndo,
+}
+
+/// The edits a step makes, turned into a [`BufferEvent`] at the cursor.
+#[derive(Clone, Debug)]
+enum Edit {
+    Char(char),
+    Delete,
+    Newline,
+    Text(String),
+    Lines { count: usize, text: String },
+    Range { to: Position, text: String },
+    Surround(char, char),
+}
+
+fn text() -> impl Strategy<Value = String> {
+    "[ab é\t\n]{0,6}"
+}
+
+fn position() -> impl Strategy<Value = Position> {
+    (0..8usize, 0..10usize).prop_map(|(line, col)| Position::new(line, col))
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    let edit = prop_oneof![
+        prop::sample::select(vec!['a', 'b', ' ', 'é', '\t']).prop_map(Edit::Char),
+        Just(Edit::Delete),
+        Just(Edit::Newline),
+        text().prop_map(Edit::Text),
+        (0..3usize, text()).prop_map(|(count, text)| Edit::Lines { count, text }),
+        (position(), text()).prop_map(|(to, text)| Edit::Range { to, text }),
+        Just(Edit::Surround('(', ')')),
+    ];
+    let movement = prop_oneof![
+        Just(CursorEvent::MoveLeft),
+        Just(CursorEvent::MoveRight),
+        Just(CursorEvent::MoveUp),
+        Just(CursorEvent::MoveDown),
+        Just(CursorEvent::MoveToLineStart),
+        Just(CursorEvent::MoveToLineEnd),
+        position().prop_map(|at| CursorEvent::SetPosition {
+            line: at.line,
+            col: at.col
+        }),
+        (0..8usize).prop_map(CursorEvent::SetLinePosition),
+        (0..10usize).prop_map(CursorEvent::SetColPosition),
+    ];
+
+    prop_oneof![
+        4 => edit.prop_map(Step::Edit),
+        3 => movement.prop_map(Step::Move),
+        1 => Just(Step::Undo),
+        1 => Just(Step::Redo),
+        1 => Just(Step::BreakUndo),
+    ]
+}
+
+/// Returns the event making `edit` with the cursor at `at`.
+fn buffer_event(edit: Edit, at: Position, buffer: &Buffer) -> BufferEvent {
+    match edit {
+        Edit::Char(char) => BufferEvent::InsertChar { char, position: at },
+        Edit::Delete => BufferEvent::DeleteChar { position: at },
+        Edit::Newline => BufferEvent::InsertNewline { position: at },
+        Edit::Text(text) => BufferEvent::InsertText { text, position: at },
+        Edit::Lines { count, text } => BufferEvent::ReplaceLines {
+            start: at.line,
+            end: (at.line + count).min(buffer.len_lines() - 1),
+            text,
+        },
+        Edit::Range { to, text } => BufferEvent::ReplaceRange {
+            start: at.min(to),
+            end: at.max(to),
+            text,
+        },
+        Edit::Surround(open, close) => BufferEvent::Surround {
+            open,
+            close,
+            start: at,
+            end: at,
+        },
+    }
+}
+
+/// Handles `event` as the editor does, moving the cursor as the buffer asks.
+fn handle(buffer: &mut Buffer, cursor: &mut Cursor, event: BufferEvent) {
+    for next in buffer.handle_event(event) {
+        if let AppEvent::Cursor(event) = next {
+            cursor.handle_event(event, buffer);
+        }
+    }
+}
+
+/// Undoes the last step, or redoes the last undone one, with the cursor where it is.
+fn undo(buffer: &mut Buffer, cursor: &mut Cursor, redo: bool) {
+    let position = cursor.position;
+    let event = match redo {
+        true => BufferEvent::Redo { position },
+        false => BufferEvent::Undo { position },
+    };
+    handle(buffer, cursor, event);
+}
+
+/// Checks that the cursor is on a line and a column of the buffer.
+fn assert_in_bounds(buffer: &Buffer, cursor: &Cursor) -> Result<(), TestCaseError> {
+    let at = cursor.position;
+    prop_assert!(at.line < buffer.len_lines(), "{at:?} past the last line");
+    prop_assert!(at.col <= cursor.max_col(buffer), "{at:?} past the line end");
+    prop_assert_eq!(buffer.char_to_position(buffer.position_to_char(at)), at);
+    Ok(())
+}
+
+/// Replays the changes logged since `seen` onto `model`, checking the text they replace.
+fn replay(buffer: &Buffer, model: &mut Rope, seen: u64) -> Result<(), TestCaseError> {
+    let changes = buffer.changes().since(seen);
+    prop_assert!(changes.is_some(), "changes dropped from the log");
+
+    for change in changes.into_iter().flatten() {
+        let old_end = change.start_char + change.old_text.chars().count();
+        prop_assert_eq!(
+            model.slice(change.start_char..old_end).to_string(),
+            change.old_text.clone()
+        );
+        model.remove(change.start_char..old_end);
+        model.insert(change.start_char, &change.new_text);
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn should_keep_the_engine_consistent_through_random_edits(
+        initial in "[ab \n]{0,12}",
+        past_end in any::<bool>(),
+        steps in prop::collection::vec(step(), 1..40),
+    ) {
+        let mut buffer = Buffer::new(initial.clone());
+        let mut cursor = Cursor::new();
+        cursor.past_end = past_end;
+        let mut model = Rope::from_str(&initial);
+
+        for step in steps {
+            let seen = buffer.changes().count();
+            let at = cursor.position;
+
+            match step {
+                Step::Edit(edit) => {
+                    let event = buffer_event(edit, at, &buffer);
+                    handle(&mut buffer, &mut cursor, event);
+                }
+                Step::Move(event) => {
+                    cursor.handle_event(event, &buffer);
+                }
+                Step::Undo => {
+                    let before = buffer.as_rope().to_string();
+                    let undone = !buffer.history().is_empty();
+                    undo(&mut buffer, &mut cursor, false);
+
+                    // Redoing right away comes back to the same text.
+                    if undone {
+                        undo(&mut buffer, &mut cursor, true);
+                        prop_assert_eq!(buffer.as_rope().to_string(), before);
+                        undo(&mut buffer, &mut cursor, false);
+                    }
+                }
+                Step::Redo => undo(&mut buffer, &mut cursor, true),
+                Step::BreakUndo => buffer.break_undo(),
+            }
+
+            assert_in_bounds(&buffer, &cursor)?;
+            replay(&buffer, &mut model, seen)?;
+            prop_assert_eq!(model.to_string(), buffer.as_rope().to_string());
+        }
+
+        // Undoing every step brings back the initial text, and redoing them the last one.
+        let last = buffer.as_rope().to_string();
+        let steps = buffer.history().len();
+        for _ in 0..steps {
+            undo(&mut buffer, &mut cursor, false);
+            assert_in_bounds(&buffer, &cursor)?;
+        }
+        prop_assert_eq!(buffer.as_rope().to_string(), initial);
+
+        for _ in 0..steps {
+            undo(&mut buffer, &mut cursor, true);
+            assert_in_bounds(&buffer, &cursor)?;
+        }
+        prop_assert_eq!(buffer.as_rope().to_string(), last);
+    }
+}
@@ -0,0 +1,172 @@
+//! Pseudo-terminals for the Zack text editor.
+//!
+//! This module provides [`Pty`], the shell of the terminal pane opened with `:terminal`,
+//! running on a pseudo-terminal so it behaves as it would in a terminal emulator. A
+//! thread reads what the shell prints and sends it on the event queue as
+//! [`TerminalEvent::Output`]; the keys typed in the pane are written back to it.
+
+use crate::event::{AppEvent, Event};
+use std::{fs::File, io, process::Child, sync::mpsc};
+
+/// Events that open the terminal pane or deliver what its shell prints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TerminalEvent {
+    /// Opens the terminal pane, starting its shell, or focuses it when already open.
+    Open,
+    /// Kills the shell and closes the pane.
+    Close,
+    /// Output of the shell with process id `pid`.
+    Output { pid: u32, bytes: Vec<u8> },
+    /// The shell with process id `pid` exited, closing the pane.
+    Exited { pid: u32 },
+}
+
+/// A shell running on a pseudo-terminal. Dropping it kills the shell.
+#[derive(Debug)]
+pub struct Pty {
+    master: File,
+    child: Child,
+}
+
+impl Pty {
+    /// Starts `$SHELL`, or `sh`, on a pseudo-terminal of `rows` by `cols` cells. What it
+    /// prints is sent on `sender` until it exits.
+    #[cfg(unix)]
+    pub fn spawn(rows: u16, cols: u16, sender: mpsc::Sender<Event>) -> io::Result<Self> {
+        use std::{
+            ffi::CStr,
+            io::Read,
+            os::{
+                fd::FromRawFd,
+                unix::{fs::OpenOptionsExt, process::CommandExt},
+            },
+            process::{Command, Stdio},
+            thread,
+        };
+
+        // SAFETY: `posix_openpt` takes no pointers; the descriptor is checked below.
+        let fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a new descriptor owned by nothing else.
+        let master = unsafe { File::from_raw_fd(fd) };
+
+        // SAFETY: `fd` is an open pseudo-terminal master. `ptsname` returns a pointer to
+        // a static buffer, copied right away; the pane is only opened from the event loop.
+        let slave_path = unsafe {
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let name = libc::ptsname(fd);
+            if name.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            CStr::from_ptr(name).to_string_lossy().into_owned()
+        };
+        let slave = File::options()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(slave_path)?;
+
+        resize(&master, rows, cols)?;
+
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| String::from("sh"));
+        let mut command = Command::new(shell);
+        command
+            .env("TERM", "dumb")
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+        // SAFETY: only async-signal-safe calls run between `fork` and `exec`. The shell
+        // gets a session of its own with the pseudo-terminal as its controlling terminal.
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn()?;
+        drop(command);
+
+        let pid = child.id();
+        let mut reader = master.try_clone()?;
+        thread::spawn(move || {
+            let mut chunk = [0; 4096];
+            // Reading fails once the shell exits and the slave side is closed.
+            while let Ok(count @ 1..) = reader.read(&mut chunk) {
+                let bytes = chunk[..count].to_vec();
+                let event = Event::App(AppEvent::Terminal(TerminalEvent::Output { pid, bytes }));
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(Event::App(AppEvent::Terminal(TerminalEvent::Exited {
+                pid,
+            })));
+        });
+
+        Ok(Self { master, child })
+    }
+
+    /// Terminals need a pseudo-terminal, which only unix systems have here.
+    #[cfg(not(unix))]
+    pub fn spawn(_rows: u16, _cols: u16, _sender: mpsc::Sender<Event>) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "terminals are only supported on unix",
+        ))
+    }
+
+    /// Returns the process id of the shell.
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Writes `bytes` to the shell, as if typed.
+    pub fn write(&mut self, bytes: &[u8]) {
+        use std::io::Write;
+
+        if let Err(err) = self.master.write_all(bytes) {
+            tracing::error!("Failed to write to the terminal: {err}");
+        }
+    }
+
+    /// Tells the shell its terminal is now `rows` by `cols` cells.
+    pub fn resize(&self, rows: u16, cols: u16) {
+        #[cfg(unix)]
+        if let Err(err) = resize(&self.master, rows, cols) {
+            tracing::error!("Failed to resize the terminal: {err}");
+        }
+        #[cfg(not(unix))]
+        let _ = (rows, cols);
+    }
+}
+
+impl Drop for Pty {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Sets the size of the pseudo-terminal `master`, signalling the shell with `SIGWINCH`.
+#[cfg(unix)]
+fn resize(master: &File, rows: u16, cols: u16) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let size = libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: `size` outlives the call, which only reads it.
+    match unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &size) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
@@ -0,0 +1,252 @@
+//! Quickfix list for the Zack text editor.
+//!
+//! This module provides [`Quickfix`], the list of places compiler output points at, and
+//! [`QuickfixEvent`], used to fill it and move through it. `:make` runs the build command
+//! set with `:set makeprg` as a background job, then [`parse`] reads the
+//! `file:line:col: message` locations of its output into the list and the first one is
+//! opened. `:cnext` and `:cprevious` go to the other ones, and `:clist` lists them.
+
+use crate::event::{AppEvent, BufferManagerEvent, CursorEvent};
+use std::path::PathBuf;
+
+/// A place in a file, with what the compiler said about it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuickfixEntry {
+    pub path: PathBuf,
+    /// Line, counted from zero.
+    pub line: usize,
+    /// Column, counted from zero.
+    pub col: usize,
+    pub message: String,
+}
+
+/// The quickfix list, with the entry last gone to.
+#[derive(Debug, Default)]
+pub struct Quickfix {
+    entries: Vec<QuickfixEntry>,
+    current: usize,
+}
+
+/// Events that fill the quickfix list or move through it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuickfixEvent {
+    /// Replaces the list with `entries` and goes to the first one.
+    Set(Vec<QuickfixEntry>),
+    /// Goes to the next entry (`:cnext`).
+    Next,
+    /// Goes to the previous entry (`:cprevious`).
+    Previous,
+    /// Shows the entries in the message pane (`:clist`).
+    List,
+}
+
+impl Quickfix {
+    /// Creates an empty quickfix list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a `QuickfixEvent` and returns the `AppEvent`s going to an entry or
+    /// showing the list.
+    pub fn handle_event(&mut self, event: QuickfixEvent) -> Vec<AppEvent> {
+        match event {
+            QuickfixEvent::Set(entries) => {
+                self.entries = entries;
+                self.current = 0;
+            }
+            QuickfixEvent::Next if self.current + 1 < self.entries.len() => self.current += 1,
+            QuickfixEvent::Previous if self.current > 0 => self.current -= 1,
+            QuickfixEvent::Next | QuickfixEvent::Previous if !self.entries.is_empty() => {
+                tracing::warn!("No more items");
+                return vec![];
+            }
+            QuickfixEvent::List => return self.list(),
+            QuickfixEvent::Next | QuickfixEvent::Previous => {}
+        }
+
+        self.go_to_current()
+    }
+
+    /// Opens the file of the current entry with the cursor on it.
+    fn go_to_current(&self) -> Vec<AppEvent> {
+        let Some(entry) = self.entries.get(self.current) else {
+            tracing::warn!("No errors");
+            return vec![];
+        };
+
+        tracing::info!(
+            "({} of {}) {}",
+            self.current + 1,
+            self.entries.len(),
+            entry.message
+        );
+
+        vec![
+            AppEvent::BufferManager(BufferManagerEvent::Open(entry.path.clone())),
+            AppEvent::Cursor(CursorEvent::SetPosition {
+                line: entry.line,
+                col: entry.col,
+            }),
+        ]
+    }
+
+    /// Lists the entries in the message pane, marking the current one.
+    fn list(&self) -> Vec<AppEvent> {
+        let text = match self.entries.is_empty() {
+            true => String::from("No errors"),
+            false => self
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| {
+                    format!(
+                        "{} {}:{}:{}: {}",
+                        if index == self.current { '>' } else { ' ' },
+                        entry.path.display(),
+                        entry.line + 1,
+                        entry.col + 1,
+                        entry.message
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+
+        vec![AppEvent::ShowMessage {
+            title: String::from(":clist"),
+            text,
+        }]
+    }
+}
+
+/// Reads the locations of compiler `output`: lines like `file:line:col: message` or
+/// `file:line: message`, and `--> file:line:col` lines of rustc taking their message
+/// from the `error: ...` or `warning: ...` line above. Locations given twice are kept
+/// once.
+pub fn parse(output: &str) -> Vec<QuickfixEntry> {
+    let mut entries: Vec<QuickfixEntry> = vec![];
+    let mut last_message = String::new();
+
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+        if ["error", "warning"]
+            .iter()
+            .any(|level| trimmed.starts_with(level))
+            && trimmed.contains(": ")
+        {
+            last_message = trimmed.to_string();
+        }
+
+        let location = trimmed.strip_prefix("--> ").unwrap_or(trimmed);
+        let Some(mut entry) = parse_location(location) else {
+            continue;
+        };
+        if entry.message.is_empty() {
+            entry.message = last_message.clone();
+        }
+
+        if !entries.iter().any(|known| {
+            (&known.path, known.line, known.col) == (&entry.path, entry.line, entry.col)
+        }) {
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
+
+/// Reads `file:line[:col][: message]`, with one-based numbers.
+fn parse_location(text: &str) -> Option<QuickfixEntry> {
+    let mut parts = text.splitn(4, ':');
+    let path = parts.next().filter(|path| {
+        !path.is_empty() && !path.contains(char::is_whitespace) && !path.contains("//")
+    })?;
+    let line: usize = parts.next()?.trim().parse().ok()?;
+
+    let rest: Vec<&str> = parts.collect();
+    let (col, message) = match rest.as_slice() {
+        [col, message] if col.trim().parse::<usize>().is_ok() => {
+            (col.trim().parse().ok(), message.to_string())
+        }
+        [col] if col.trim().parse::<usize>().is_ok() => (col.trim().parse().ok(), String::new()),
+        [] => (None, String::new()),
+        rest => (None, rest.join(":")),
+    };
+
+    Some(QuickfixEntry {
+        path: PathBuf::from(path),
+        line: line.checked_sub(1)?,
+        col: col.map_or(0, |col: usize| col.saturating_sub(1)),
+        message: message.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, line: usize, col: usize, message: &str) -> QuickfixEntry {
+        QuickfixEntry {
+            path: PathBuf::from(path),
+            line,
+            col,
+            message: String::from(message),
+        }
+    }
+
+    #[test]
+    fn should_parse_locations_of_compiler_output() {
+        let output = "\
+main.c:3:5: error: expected ';'
+util.c:10: warning: unused variable
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:13
+  |
+2 |     let y = x;
+warning: unused import
+ --> src/lib.rs:1:5
+make: *** [all] Error 1
+see https://example.com:443/docs
+main.c:3:5: error: expected ';'
+";
+
+        assert_eq!(
+            parse(output),
+            vec![
+                entry("main.c", 2, 4, "error: expected ';'"),
+                entry("util.c", 9, 0, "warning: unused variable"),
+                entry(
+                    "src/main.rs",
+                    1,
+                    12,
+                    "error[E0425]: cannot find value `x` in this scope"
+                ),
+                entry("src/lib.rs", 0, 4, "warning: unused import"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_step_through_entries_and_stop_at_the_ends() {
+        let mut quickfix = Quickfix::new();
+
+        let events = quickfix.handle_event(QuickfixEvent::Set(vec![
+            entry("a.rs", 1, 2, "first"),
+            entry("b.rs", 3, 0, "second"),
+        ]));
+        assert_eq!(
+            events,
+            vec![
+                AppEvent::BufferManager(BufferManagerEvent::Open(PathBuf::from("a.rs"))),
+                AppEvent::Cursor(CursorEvent::SetPosition { line: 1, col: 2 }),
+            ]
+        );
+
+        assert_eq!(quickfix.handle_event(QuickfixEvent::Next).len(), 2);
+        assert!(quickfix.handle_event(QuickfixEvent::Next).is_empty());
+        assert_eq!(quickfix.current, 1);
+
+        quickfix.handle_event(QuickfixEvent::Previous);
+        assert_eq!(quickfix.current, 0);
+    }
+}
@@ -0,0 +1,445 @@
+//! Scripting for the Zack text editor.
+//!
+//! This module provides [`Scripts`], which runs the [Rhai](https://rhai.rs) scripts of the
+//! `scripts` folder of the configuration directory, in name order, when the editor
+//! starts. Their top level registers what they add to the editor:
+//!
+//! ```text
+//! register_command("Upper", "upper");   // `:Upper` calls `fn upper(argument)`
+//! map("normal", "<C-u>", ":Upper");     // binds keys to an action, as `:map` does
+//! on("BufWritePost", "saved");          // calls `fn saved(path)` after each write
+//!
+//! fn upper(argument) {
+//!     let n = cursor_line();
+//!     set_line(n, line(n).to_upper());
+//! }
+//! ```
+//!
+//! User commands start with an uppercase letter, as in Vim. `on` subscribes to the
+//! [`Hook`]s autocommands run at; the functions are given the path of the buffer, or the
+//! name of the new mode for `ModeChanged`.
+//!
+//! Functions see the active buffer as it was when they were called: `text()`,
+//! `line(n)`, `line_count()`, `cursor_line()`, `cursor_col()` and `path()`, with lines
+//! and columns counted from zero. The changes they make with `insert(text)`,
+//! `set_line(n, text)`, `set_cursor(line, col)` and `command(":cmd")` are applied once
+//! they return; `message(text)` and `print` show a message. Scripts stop after too many
+//! operations, so a loop that never ends cannot freeze the editor.
+
+use crate::{
+    app::{buffer_manager::Document, hooks::Hook, keymap},
+    event::{AppEvent, BufferEvent, CursorEvent},
+    types::position::Position,
+    ui::components::command_line::CommandLineKind,
+};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, INT, Scope};
+use ropey::Rope;
+use std::{cell::RefCell, collections::HashMap, fmt, path::Path, rc::Rc};
+
+/// Folder of the configuration directory holding the scripts.
+pub const SCRIPTS_DIRECTORY: &str = "scripts";
+
+/// Extension of script files.
+const EXTENSION: &str = "rhai";
+
+/// Operations a script may run in one call before it is stopped.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A function of a loaded script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Handler {
+    script: usize,
+    function: String,
+}
+
+/// What the functions given to scripts read and write.
+#[derive(Debug, Default)]
+struct Context {
+    rope: Rope,
+    cursor: Position,
+    path: String,
+    /// Changes asked for by the running function.
+    events: Vec<AppEvent>,
+    /// The script being loaded, which `register_command` and `on` refer to.
+    loading: usize,
+    commands: HashMap<String, Handler>,
+    hooks: Vec<(Hook, Handler)>,
+}
+
+/// The loaded scripts, with the commands and hook handlers they registered.
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<AST>,
+    context: Rc<RefCell<Context>>,
+}
+
+impl fmt::Debug for Scripts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scripts")
+            .field("scripts", &self.scripts.len())
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scripts {
+    /// Creates the scripting engine, with no script loaded.
+    pub fn new() -> Self {
+        let context = Rc::new(RefCell::new(Context::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| tracing::info!("{text}"));
+        register_api(&mut engine, &context);
+
+        Self {
+            engine,
+            scripts: vec![],
+            context,
+        }
+    }
+
+    /// Loads the scripts of `directory` in name order. Returns the events of the key
+    /// bindings they made, with the errors of the scripts that could not be run.
+    pub fn load_directory(&mut self, directory: &Path) -> (Vec<AppEvent>, Vec<String>) {
+        let mut paths: Vec<_> = std::fs::read_dir(directory)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == EXTENSION))
+            .collect();
+        paths.sort();
+
+        let mut events = vec![];
+        let mut errors = vec![];
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let result = std::fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|source| self.load(&source));
+
+            match result {
+                Ok(bindings) => events.extend(bindings),
+                Err(err) => errors.push(format!("{SCRIPTS_DIRECTORY}/{name}: {err}")),
+            }
+        }
+
+        (events, errors)
+    }
+
+    /// Compiles the script `source` and runs its top level. Returns the events of the key
+    /// bindings it made.
+    pub fn load(&mut self, source: &str) -> Result<Vec<AppEvent>, String> {
+        let ast = self.engine.compile(source).map_err(|err| err.to_string())?;
+
+        let script = self.scripts.len();
+        self.context.borrow_mut().loading = script;
+        let result = self.engine.run_ast_with_scope(&mut Scope::new(), &ast);
+
+        let mut context = self.context.borrow_mut();
+        let events = std::mem::take(&mut context.events);
+        if let Err(err) = result {
+            // A script failing halfway registers nothing.
+            context
+                .commands
+                .retain(|_, handler| handler.script != script);
+            context
+                .hooks
+                .retain(|(_, handler)| handler.script != script);
+            return Err(err.to_string());
+        }
+        drop(context);
+
+        self.scripts.push(ast);
+        Ok(events)
+    }
+
+    /// Runs the user command `name` with `argument` on `document`, returning the changes
+    /// it made.
+    pub fn run_command(
+        &mut self,
+        name: &str,
+        argument: &str,
+        document: &Document,
+    ) -> Result<Vec<AppEvent>, String> {
+        let handler = self
+            .context
+            .borrow()
+            .commands
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Not an editor command: {name}"))?;
+
+        self.call(&handler, argument, document)
+    }
+
+    /// Calls the functions subscribed to `hook` with `argument` on `document`, returning
+    /// the changes they made. Errors are logged.
+    pub fn trigger(&mut self, hook: Hook, argument: &str, document: &Document) -> Vec<AppEvent> {
+        let handlers: Vec<Handler> = self
+            .context
+            .borrow()
+            .hooks
+            .iter()
+            .filter(|(subscribed, _)| *subscribed == hook)
+            .map(|(_, handler)| handler.clone())
+            .collect();
+
+        let mut events = vec![];
+        for handler in handlers {
+            match self.call(&handler, argument, document) {
+                Ok(changes) => events.extend(changes),
+                Err(err) => tracing::error!("{err}"),
+            }
+        }
+        events
+    }
+
+    /// Calls the function of `handler` with `argument`, letting it see `document`.
+    fn call(
+        &mut self,
+        handler: &Handler,
+        argument: &str,
+        document: &Document,
+    ) -> Result<Vec<AppEvent>, String> {
+        {
+            let mut context = self.context.borrow_mut();
+            context.rope = document.buffer.as_rope().clone();
+            context.cursor = document.cursor.position;
+            context.path = document
+                .file
+                .path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+        }
+
+        let ast = &self.scripts[handler.script];
+        // The top level already ran when the script was loaded.
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            CallFnOptions::new().eval_ast(false),
+            &mut Scope::new(),
+            ast,
+            &handler.function,
+            (argument.to_string(),),
+        );
+        let events = std::mem::take(&mut self.context.borrow_mut().events);
+
+        result
+            .map(|_| events)
+            .map_err(|err| format!("{}: {err}", handler.function))
+    }
+}
+
+/// Gives scripts the functions reading and changing the editor, all working on `context`.
+fn register_api(engine: &mut Engine, context: &Rc<RefCell<Context>>) {
+    let ctx = context.clone();
+    engine.register_fn("text", move || ctx.borrow().rope.to_string());
+
+    let ctx = context.clone();
+    engine.register_fn("line_count", move || ctx.borrow().rope.len_lines() as INT);
+
+    let ctx = context.clone();
+    engine.register_fn("line", move |n: INT| {
+        let context = ctx.borrow();
+        usize::try_from(n)
+            .ok()
+            .and_then(|n| context.rope.get_line(n))
+            .map(|line| line.to_string().trim_end_matches('\n').to_string())
+            .unwrap_or_default()
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("cursor_line", move || ctx.borrow().cursor.line as INT);
+
+    let ctx = context.clone();
+    engine.register_fn("cursor_col", move || ctx.borrow().cursor.col as INT);
+
+    let ctx = context.clone();
+    engine.register_fn("path", move || ctx.borrow().path.clone());
+
+    engine.register_fn("message", |text: &str| tracing::info!("{text}"));
+
+    let ctx = context.clone();
+    engine.register_fn("insert", move |text: &str| {
+        let mut context = ctx.borrow_mut();
+        let position = context.cursor;
+        context
+            .events
+            .push(AppEvent::Buffer(BufferEvent::InsertText {
+                text: text.to_string(),
+                position,
+            }));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn(
+        "set_line",
+        move |n: INT, text: &str| -> Result<(), Box<EvalAltResult>> {
+            let mut context = ctx.borrow_mut();
+            let line = usize::try_from(n)
+                .ok()
+                .filter(|n| *n < context.rope.len_lines())
+                .ok_or_else(|| format!("No line {n}"))?;
+
+            context
+                .events
+                .push(AppEvent::Buffer(BufferEvent::ReplaceLines {
+                    start: line,
+                    end: line,
+                    text: text.to_string(),
+                }));
+            Ok(())
+        },
+    );
+
+    let ctx = context.clone();
+    engine.register_fn("set_cursor", move |line: INT, col: INT| {
+        ctx.borrow_mut()
+            .events
+            .push(AppEvent::Cursor(CursorEvent::SetPosition {
+                line: line.max(0) as usize,
+                col: col.max(0) as usize,
+            }));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("command", move |input: &str| {
+        ctx.borrow_mut().events.push(AppEvent::SubmitCommandLine {
+            kind: CommandLineKind::Command,
+            input: input.trim_start_matches(':').to_string(),
+        });
+    });
+
+    let ctx = context.clone();
+    engine.register_fn(
+        "register_command",
+        move |name: &str, function: &str| -> Result<(), Box<EvalAltResult>> {
+            if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                return Err(
+                    format!("User commands must start with an uppercase letter: {name}").into(),
+                );
+            }
+
+            let mut context = ctx.borrow_mut();
+            let handler = Handler {
+                script: context.loading,
+                function: function.to_string(),
+            };
+            context.commands.insert(name.to_string(), handler);
+            Ok(())
+        },
+    );
+
+    let ctx = context.clone();
+    engine.register_fn(
+        "map",
+        move |mode: &str, keys: &str, action: &str| -> Result<(), Box<EvalAltResult>> {
+            let (mode, keys, action) = keymap::parse_binding(&format!("{mode} {keys} {action}"))?;
+
+            ctx.borrow_mut().events.push(AppEvent::Map {
+                mode,
+                keys,
+                action,
+                buffer: false,
+            });
+            Ok(())
+        },
+    );
+
+    let ctx = context.clone();
+    engine.register_fn(
+        "on",
+        move |hook: &str, function: &str| -> Result<(), Box<EvalAltResult>> {
+            let hook = Hook::from_name(hook).ok_or_else(|| format!("Unknown hook: {hook}"))?;
+
+            let mut context = ctx.borrow_mut();
+            let handler = Handler {
+                script: context.loading,
+                function: function.to_string(),
+            };
+            context.hooks.push((hook, handler));
+            Ok(())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::keymap::{Action, KeymapMode};
+
+    #[test]
+    fn should_run_user_commands_registered_by_scripts() {
+        let mut scripts = Scripts::new();
+        let events = scripts
+            .load(
+                r#"
+                register_command("Shout", "shout");
+                map("n", "<C-u>", ":Shout");
+
+                fn shout(argument) {
+                    let n = cursor_line();
+                    set_line(n, line(n).to_upper() + argument);
+                    set_cursor(n, 0);
+                }
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![AppEvent::Map {
+                mode: KeymapMode::Normal,
+                keys: keymap::parse_keys("<C-u>"),
+                action: Action::Command(String::from("Shout")),
+                buffer: false,
+            }]
+        );
+
+        let mut document = Document::new(String::from("one\ntwo\n"), None);
+        document.cursor.position = Position::new(1, 2);
+        assert_eq!(
+            scripts.run_command("Shout", "!", &document),
+            Ok(vec![
+                AppEvent::Buffer(BufferEvent::ReplaceLines {
+                    start: 1,
+                    end: 1,
+                    text: String::from("TWO!"),
+                }),
+                AppEvent::Cursor(CursorEvent::SetPosition { line: 1, col: 0 }),
+            ])
+        );
+        assert!(scripts.run_command("Whisper", "", &document).is_err());
+    }
+
+    #[test]
+    fn should_call_the_functions_subscribed_to_a_hook() {
+        let mut scripts = Scripts::new();
+        scripts
+            .load(r#"on("BufWritePost", "saved"); fn saved(path) { command("echo " + path); }"#)
+            .unwrap();
+        let document = Document::new(String::new(), None);
+
+        assert_eq!(
+            scripts.trigger(Hook::BufWritePost, "a.txt", &document),
+            vec![AppEvent::SubmitCommandLine {
+                kind: CommandLineKind::Command,
+                input: String::from("echo a.txt"),
+            }]
+        );
+        assert!(
+            scripts
+                .trigger(Hook::BufReadPost, "a.txt", &document)
+                .is_empty()
+        );
+
+        assert!(scripts.load(r#"on("Resize", "f");"#).is_err());
+        assert!(scripts.load("loop {}").is_err());
+    }
+}
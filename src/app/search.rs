@@ -0,0 +1,175 @@
+//! Search module for the Zack text editor.
+//!
+//! This module provides the [`Search`] struct, which remembers the last searched
+//! pattern so it can be repeated with `n`/`N`, and [`SearchEvent`], which describes
+//! every way a search can be started or repeated.
+//!
+//! Patterns are matched literally: the text typed in the search prompt, or the text
+//! selected in visual mode, is looked up exactly as-is, so special characters never
+//! need escaping.
+
+use crate::{
+    app::buffer::Buffer,
+    event::{AppEvent, CursorEvent},
+    types::position::Position,
+};
+
+/// Holds the state of the most recent search.
+#[derive(Debug, Default)]
+pub struct Search {
+    /// The last searched pattern, if any.
+    pub pattern: Option<String>,
+}
+
+/// Events that start or repeat a search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchEvent {
+    /// Searches forward for `pattern` starting after `position`.
+    Start { pattern: String, position: Position },
+
+    /// Searches forward for the text between `start` and `end` (inclusive).
+    Selection { start: Position, end: Position },
+
+    /// Repeats the last search forward from `position`.
+    Next { position: Position },
+
+    /// Repeats the last search backward from `position`.
+    Previous { position: Position },
+}
+
+impl Search {
+    /// Creates a new `Search` with no pattern.
+    pub fn new() -> Self {
+        Self { pattern: None }
+    }
+
+    /// Handles a `SearchEvent` and returns the cursor events needed to jump to the match.
+    pub fn handle_event(&mut self, event: SearchEvent, buffer: &Buffer) -> Vec<AppEvent> {
+        let mut events = vec![];
+
+        match event {
+            SearchEvent::Start { pattern, position } => {
+                self.pattern = Some(pattern);
+                events.extend(self.jump_forward(position, buffer));
+            }
+            SearchEvent::Selection { start, end } => {
+                self.pattern = Some(buffer.text_between(start, end));
+                events.extend(self.jump_forward(start, buffer));
+            }
+            SearchEvent::Next { position } => events.extend(self.jump_forward(position, buffer)),
+            SearchEvent::Previous { position } => {
+                events.extend(self.jump_backward(position, buffer))
+            }
+        }
+
+        events
+    }
+
+    fn jump_forward(&self, position: Position, buffer: &Buffer) -> Vec<AppEvent> {
+        self.pattern
+            .as_deref()
+            .and_then(|pattern| buffer.find_next(pattern, position))
+            .map(Self::jump_to)
+            .unwrap_or_default()
+    }
+
+    fn jump_backward(&self, position: Position, buffer: &Buffer) -> Vec<AppEvent> {
+        self.pattern
+            .as_deref()
+            .and_then(|pattern| buffer.find_previous(pattern, position))
+            .map(Self::jump_to)
+            .unwrap_or_default()
+    }
+
+    fn jump_to(found: Position) -> Vec<AppEvent> {
+        vec![AppEvent::Cursor(CursorEvent::SetPosition {
+            line: found.line,
+            col: found.col,
+        })]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(line: usize, col: usize) -> Position {
+        Position::new(line, col)
+    }
+
+    fn jump(line: usize, col: usize) -> Vec<AppEvent> {
+        vec![AppEvent::Cursor(CursorEvent::SetPosition { line, col })]
+    }
+
+    #[test]
+    fn should_store_pattern_and_jump_to_next_match_on_start() {
+        let buffer = Buffer::new(String::from("one two\ntwo one"));
+        let mut search = Search::new();
+
+        let events = search.handle_event(
+            SearchEvent::Start {
+                pattern: String::from("two"),
+                position: pos(0, 0),
+            },
+            &buffer,
+        );
+
+        assert_eq!(search.pattern.as_deref(), Some("two"));
+        assert_eq!(events, jump(0, 4));
+    }
+
+    #[test]
+    fn should_search_for_selected_text_literally() {
+        let buffer = Buffer::new(String::from("a.*b\na.*b"));
+        let mut search = Search::new();
+
+        let events = search.handle_event(
+            SearchEvent::Selection {
+                start: pos(0, 0),
+                end: pos(0, 3),
+            },
+            &buffer,
+        );
+
+        assert_eq!(search.pattern.as_deref(), Some("a.*b"));
+        assert_eq!(events, jump(1, 0));
+    }
+
+    #[test]
+    fn should_repeat_last_search_in_both_directions() {
+        let buffer = Buffer::new(String::from("x x x"));
+        let mut search = Search::new();
+        search.pattern = Some(String::from("x"));
+
+        let next = search.handle_event(
+            SearchEvent::Next {
+                position: pos(0, 2),
+            },
+            &buffer,
+        );
+        let previous = search.handle_event(
+            SearchEvent::Previous {
+                position: pos(0, 2),
+            },
+            &buffer,
+        );
+
+        assert_eq!(next, jump(0, 4));
+        assert_eq!(previous, jump(0, 0));
+    }
+
+    #[test]
+    fn should_do_nothing_without_pattern() {
+        let buffer = Buffer::new(String::from("text"));
+        let mut search = Search::new();
+
+        let events = search.handle_event(
+            SearchEvent::Next {
+                position: pos(0, 0),
+            },
+            &buffer,
+        );
+
+        assert!(events.is_empty());
+    }
+}
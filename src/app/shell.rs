@@ -0,0 +1,571 @@
+//! Shell command module for the Zack text editor.
+//!
+//! This module runs external commands through `sh -c` as background [`Jobs`], so slow
+//! commands never freeze the UI and can be cancelled. The outcome of each command comes
+//! back as an [`AppEvent`] through the event queue.
+//!
+//! It also defines [`ShellEvent`], which drives the shell commands of the command line:
+//!
+//! - `:!cmd` runs a command and shows its output in the message pane.
+//! - `:r !cmd` inserts the output of a command below a line.
+//! - `:{range}!cmd` filters lines through a command: the lines are sent to its stdin
+//!   and replaced with its stdout once it exits successfully.
+//! - `:hardcopy` sends the printed pages to the print command on its stdin.
+//! - `:make` runs the build command and fills the quickfix list from its output.
+
+use crate::{
+    app::{
+        buffer_manager::BufferManager,
+        jobs::{JobHandle, JobId, Jobs},
+        quickfix::{self, QuickfixEvent},
+    },
+    event::{AppEvent, BufferEvent},
+    types::position::Position,
+};
+use std::{
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// How often a running command is checked for exit or cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What a finished command printed, and how it exited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShellOutput {
+    /// Exit code, or `None` when the command was killed by a signal.
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Events that run commands on the buffer or deliver their results.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShellEvent {
+    /// Runs `command` and shows its output.
+    Run { command: String },
+    /// A command started by `Run` finished.
+    RunDone {
+        command: String,
+        result: Result<ShellOutput, String>,
+    },
+    /// Inserts the output of `command` below `line` of the active document.
+    Read { command: String, line: usize },
+    /// A command started by `Read` finished; its output is inserted if the document is unchanged.
+    ReadDone {
+        command: String,
+        document: usize,
+        revision: u64,
+        line: usize,
+        result: Result<ShellOutput, String>,
+    },
+    /// Pipes the lines `start..=end` of the active document through `command`.
+    Filter {
+        command: String,
+        start: usize,
+        end: usize,
+    },
+    /// Pipes `pages` pages of `text` to the print `command`.
+    Print {
+        command: String,
+        text: String,
+        pages: usize,
+    },
+    /// A print command finished.
+    PrintDone {
+        command: String,
+        pages: usize,
+        result: Result<ShellOutput, String>,
+    },
+    /// Runs the build `command`, reading the locations of its output into the quickfix
+    /// list.
+    Make { command: String },
+    /// A build command started by `Make` finished.
+    MakeDone {
+        command: String,
+        result: Result<ShellOutput, String>,
+    },
+    /// A filter finished; its output replaces the lines if the document is unchanged.
+    FilterDone {
+        command: String,
+        document: usize,
+        revision: u64,
+        start: usize,
+        end: usize,
+        result: Result<ShellOutput, String>,
+    },
+}
+
+/// Starts shell commands and applies their results.
+pub struct Shell;
+
+impl ShellOutput {
+    /// Returns `true` when the command exited with status 0.
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// Returns stdout followed by stderr, with a note about the exit status if it failed.
+    pub fn combined(&self) -> String {
+        let mut text = self.stdout.clone();
+
+        if !self.stderr.is_empty() {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&self.stderr);
+        }
+
+        if !self.success() {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            match self.code {
+                Some(code) => text.push_str(&format!("[shell returned {code}]")),
+                None => text.push_str("[shell was killed]"),
+            }
+        }
+
+        text
+    }
+
+    /// Describes a failed exit, with the first line of stderr when there is one.
+    pub fn describe_failure(&self) -> String {
+        let status = match self.code {
+            Some(code) => format!("shell returned {code}"),
+            None => String::from("shell was killed"),
+        };
+
+        match self.stderr.lines().next() {
+            Some(line) => format!("{status}: {line}"),
+            None => status,
+        }
+    }
+}
+
+impl Shell {
+    /// Handles a `ShellEvent`, starting commands as `jobs`, and returns any follow-up `AppEvent`s.
+    pub fn handle_event(
+        event: ShellEvent,
+        buffers: &BufferManager,
+        jobs: &mut Jobs,
+    ) -> Vec<AppEvent> {
+        let mut events = vec![];
+
+        match event {
+            ShellEvent::Run { command } => {
+                spawn(jobs, &command.clone(), None, move |result| {
+                    AppEvent::Shell(ShellEvent::RunDone { command, result })
+                });
+            }
+            ShellEvent::RunDone { command, result } => match result {
+                Err(err) => tracing::error!("Failed to run {command}: {err}"),
+                Ok(output) => events.push(AppEvent::ShowMessage {
+                    title: format!(":!{command}"),
+                    text: output.combined(),
+                }),
+            },
+            ShellEvent::Make { command } => {
+                spawn(jobs, &command.clone(), None, move |result| {
+                    AppEvent::Shell(ShellEvent::MakeDone { command, result })
+                });
+            }
+            ShellEvent::MakeDone { command, result } => match result {
+                Err(err) => tracing::error!("Failed to run {command}: {err}"),
+                Ok(output) => {
+                    let entries = quickfix::parse(&format!("{}\n{}", output.stdout, output.stderr));
+
+                    match (entries.is_empty(), output.success()) {
+                        (true, true) => tracing::info!("{command}: done"),
+                        (true, false) => {
+                            tracing::error!("{command}: {}", output.describe_failure())
+                        }
+                        (false, _) => events.push(AppEvent::Quickfix(QuickfixEvent::Set(entries))),
+                    }
+                }
+            },
+            ShellEvent::Read { command, line } => {
+                let document = buffers.active_index();
+                let revision = buffers.active().buffer.revision();
+
+                spawn(jobs, &command.clone(), None, move |result| {
+                    AppEvent::Shell(ShellEvent::ReadDone {
+                        command,
+                        document,
+                        revision,
+                        line,
+                        result,
+                    })
+                });
+            }
+            ShellEvent::ReadDone {
+                command,
+                document,
+                revision,
+                line,
+                result,
+            } => {
+                let unchanged = buffers.active_index() == document
+                    && buffers.active().buffer.revision() == revision;
+
+                match result {
+                    Err(err) => tracing::error!("Failed to run {command}: {err}"),
+                    Ok(output) if !output.success() => {
+                        tracing::error!("{command}: {}", output.describe_failure())
+                    }
+                    Ok(_) if !unchanged => {
+                        tracing::warn!(
+                            "Buffer changed while {command} was running, output discarded"
+                        )
+                    }
+                    Ok(output) if output.stdout.is_empty() => {}
+                    Ok(output) => {
+                        let buffer = &buffers.active().buffer;
+                        let line_end = Position::new(line, 0);
+                        let position = Position::new(line, buffer.max_visible_col(&line_end));
+
+                        events.push(AppEvent::Buffer(BufferEvent::InsertText {
+                            text: format!("\n{}", output.stdout.trim_end_matches('\n')),
+                            position,
+                        }));
+                    }
+                }
+            }
+            ShellEvent::Print {
+                command,
+                text,
+                pages,
+            } => {
+                spawn(jobs, &command.clone(), Some(text), move |result| {
+                    AppEvent::Shell(ShellEvent::PrintDone {
+                        command,
+                        pages,
+                        result,
+                    })
+                });
+            }
+            ShellEvent::PrintDone {
+                command,
+                pages,
+                result,
+            } => match result {
+                Err(err) => tracing::error!("Failed to run {command}: {err}"),
+                Ok(output) if !output.success() => {
+                    tracing::error!("{command}: {}", output.describe_failure())
+                }
+                Ok(_) => tracing::info!("Sent {pages} page(s) to {command}"),
+            },
+            ShellEvent::Filter {
+                command,
+                start,
+                end,
+            } => {
+                let document = buffers.active_index();
+                let buffer = &buffers.active().buffer;
+                let revision = buffer.revision();
+                let input = buffer.lines_text(start, end);
+
+                spawn(jobs, &command.clone(), Some(input), move |result| {
+                    AppEvent::Shell(ShellEvent::FilterDone {
+                        command,
+                        document,
+                        revision,
+                        start,
+                        end,
+                        result,
+                    })
+                });
+            }
+            ShellEvent::FilterDone {
+                command,
+                document,
+                revision,
+                start,
+                end,
+                result,
+            } => {
+                let unchanged = buffers.active_index() == document
+                    && buffers.active().buffer.revision() == revision;
+
+                match result {
+                    Err(err) => tracing::error!("Failed to run {command}: {err}"),
+                    Ok(output) if !output.success() => {
+                        tracing::error!("{command}: {}", output.describe_failure())
+                    }
+                    Ok(_) if !unchanged => {
+                        tracing::warn!(
+                            "Buffer changed while {command} was running, output discarded"
+                        )
+                    }
+                    Ok(output) => events.push(AppEvent::Buffer(BufferEvent::ReplaceLines {
+                        start,
+                        end,
+                        text: output.stdout,
+                    })),
+                }
+            }
+        }
+
+        events
+    }
+}
+
+/// Runs `command` as a background job, feeding it `input` on stdin.
+///
+/// `on_done` turns the outcome into the event dispatched when the job finishes.
+pub fn spawn<F>(jobs: &mut Jobs, command: &str, input: Option<String>, on_done: F) -> JobId
+where
+    F: FnOnce(Result<ShellOutput, String>) -> AppEvent + Send + 'static,
+{
+    let command = command.to_string();
+
+    jobs.spawn(command.clone(), move |handle| {
+        let result = run(&command, input, Some(handle)).map_err(|err| err.to_string());
+        vec![on_done(result)]
+    })
+}
+
+/// Runs `command` to completion with `sh -c`, feeding it `input` on stdin.
+///
+/// When running as a job, the command is killed as soon as the job is cancelled.
+pub fn run(
+    command: &str,
+    input: Option<String>,
+    job: Option<&JobHandle>,
+) -> io::Result<ShellOutput> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Written and read from other threads so a command that fills one pipe before
+    // draining another cannot deadlock against us.
+    let writer = match (child.stdin.take(), input) {
+        (Some(mut stdin), Some(input)) => {
+            Some(thread::spawn(move || stdin.write_all(input.as_bytes())))
+        }
+        _ => None,
+    };
+    let stdout = child.stdout.take().map(read_to_end);
+    let stderr = child.stderr.take().map(read_to_end);
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if job.is_some_and(JobHandle::is_cancelled) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "Cancelled"));
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    if let Some(writer) = writer {
+        // The command may exit without reading its input, which is not an error.
+        let _ = writer.join();
+    }
+
+    let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        let bytes = reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+
+    Ok(ShellOutput {
+        code: status.code(),
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
+
+/// Reads a pipe to its end on a new thread.
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = vec![];
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::buffer_manager::Document, event::Event};
+    use std::sync::mpsc;
+
+    fn manager_with(text: &str, sender: mpsc::Sender<Event>) -> BufferManager {
+        BufferManager::new(Document::new(text.to_string(), None), sender)
+    }
+
+    /// Waits for the command job to finish and returns the `ShellEvent` it produced.
+    fn finished_event(jobs: &mut Jobs, receiver: &mpsc::Receiver<Event>) -> ShellEvent {
+        loop {
+            if let Event::App(AppEvent::Job(event)) = receiver.recv().unwrap()
+                && let [AppEvent::Shell(done)] = jobs.handle_event(event).as_slice()
+            {
+                return done.clone();
+            }
+        }
+    }
+
+    #[test]
+    fn should_kill_command_when_job_is_cancelled() {
+        let (sender, receiver) = mpsc::channel();
+        let mut jobs = Jobs::new(sender);
+
+        let id = spawn(&mut jobs, "sleep 5", None, |result| {
+            AppEvent::Shell(ShellEvent::RunDone {
+                command: String::from("sleep 5"),
+                result,
+            })
+        });
+        jobs.handle_event(crate::app::jobs::JobEvent::Cancel(id));
+
+        let started = std::time::Instant::now();
+        let Event::App(AppEvent::Job(finished)) = receiver.recv().unwrap() else {
+            panic!("expected the job to finish");
+        };
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(jobs.handle_event(finished).is_empty());
+    }
+
+    #[test]
+    fn should_run_command_with_input() {
+        let output = run("sort", Some(String::from("b\na\n")), None).unwrap();
+
+        assert!(output.success());
+        assert_eq!(output.stdout, "a\nb\n");
+    }
+
+    #[test]
+    fn should_report_nonzero_exit_status() {
+        let output = run("echo oops >&2; exit 3", None, None).unwrap();
+
+        assert!(!output.success());
+        assert_eq!(output.describe_failure(), "shell returned 3: oops");
+    }
+
+    #[test]
+    fn should_combine_output_with_failure_status() {
+        let output = run("echo out; echo err >&2; exit 2", None, None).unwrap();
+
+        assert_eq!(output.combined(), "out\nerr\n[shell returned 2]");
+    }
+
+    #[test]
+    fn should_read_command_output_below_line() {
+        let (sender, receiver) = mpsc::channel();
+        let manager = manager_with("first\nsecond", sender.clone());
+        let mut jobs = Jobs::new(sender);
+
+        Shell::handle_event(
+            ShellEvent::Read {
+                command: String::from("printf 'a\\nb\\n'"),
+                line: 0,
+            },
+            &manager,
+            &mut jobs,
+        );
+
+        let done = finished_event(&mut jobs, &receiver);
+        let events = Shell::handle_event(done, &manager, &mut jobs);
+
+        assert_eq!(
+            events,
+            vec![AppEvent::Buffer(BufferEvent::InsertText {
+                text: String::from("\na\nb"),
+                position: Position::new(0, 5),
+            })]
+        );
+    }
+
+    #[test]
+    fn should_pipe_printed_pages_to_the_print_command() {
+        let (sender, receiver) = mpsc::channel();
+        let manager = manager_with("", sender.clone());
+        let mut jobs = Jobs::new(sender);
+
+        Shell::handle_event(
+            ShellEvent::Print {
+                command: String::from("wc -c"),
+                text: String::from("page\x0c"),
+                pages: 1,
+            },
+            &manager,
+            &mut jobs,
+        );
+
+        let done = finished_event(&mut jobs, &receiver);
+
+        assert!(matches!(
+            done,
+            ShellEvent::PrintDone { pages: 1, result: Ok(ref output), .. }
+                if output.stdout.trim() == "5"
+        ));
+        assert!(Shell::handle_event(done, &manager, &mut jobs).is_empty());
+    }
+
+    #[test]
+    fn should_filter_lines_in_background_and_replace_them() {
+        let (sender, receiver) = mpsc::channel();
+        let manager = manager_with("c\nb\na\nend", sender.clone());
+        let mut jobs = Jobs::new(sender);
+
+        Shell::handle_event(
+            ShellEvent::Filter {
+                command: String::from("sort"),
+                start: 0,
+                end: 2,
+            },
+            &manager,
+            &mut jobs,
+        );
+
+        let done = finished_event(&mut jobs, &receiver);
+        let events = Shell::handle_event(done, &manager, &mut jobs);
+
+        assert_eq!(
+            events,
+            vec![AppEvent::Buffer(BufferEvent::ReplaceLines {
+                start: 0,
+                end: 2,
+                text: String::from("a\nb\nc\n"),
+            })]
+        );
+    }
+
+    #[test]
+    fn should_discard_output_when_buffer_changed_or_command_failed() {
+        let manager = manager_with("a", mpsc::channel().0);
+        let mut jobs = Jobs::new(mpsc::channel().0);
+        let done = |revision, code| ShellEvent::FilterDone {
+            command: String::from("cmd"),
+            document: 0,
+            revision,
+            start: 0,
+            end: 0,
+            result: Ok(ShellOutput {
+                code: Some(code),
+                stdout: String::from("x"),
+                stderr: String::new(),
+            }),
+        };
+
+        assert!(Shell::handle_event(done(7, 0), &manager, &mut jobs).is_empty());
+        assert!(Shell::handle_event(done(0, 1), &manager, &mut jobs).is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Persistent state module for the Zack text editor.
+//!
+//! Some things the editor remembers between sessions, like the history of the command
+//! line. They are kept in a single JSON object, the state file, at
+//! `$XDG_STATE_HOME/zack/state.json` (or `~/.local/state/zack/state.json`), with one
+//! top-level key per kind of state so each can be read and replaced on its own.
+
+use serde_json::{Map, Value};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Returns the location of the state file, or `None` when no home directory is known.
+pub fn path() -> Option<PathBuf> {
+    let directory = match env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(directory) => PathBuf::from(directory),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+
+    Some(directory.join("zack").join("state.json"))
+}
+
+/// Returns the directory of the user's configuration files, `$XDG_CONFIG_HOME/zack` (or
+/// `~/.config/zack`), or `None` when no home directory is known.
+pub fn config_directory() -> Option<PathBuf> {
+    let directory = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(directory) => PathBuf::from(directory),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(directory.join("zack"))
+}
+
+/// Reads the state stored under `key`, or `None` when it was never saved or the file
+/// cannot be read.
+pub fn read(path: &Path, key: &str) -> Option<Value> {
+    read_all(path).ok()?.remove(key)
+}
+
+/// Replaces the state stored under `key`, keeping the other keys.
+pub fn write(path: &Path, key: &str, value: Value) -> io::Result<()> {
+    // A corrupt file is replaced rather than blocking every later save.
+    let mut state = read_all(path).unwrap_or_default();
+    state.insert(key.to_string(), value);
+
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let text = serde_json::to_string_pretty(&Value::Object(state))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    fs::write(path, text)
+}
+
+/// Reads the whole state file; a missing file holds no state.
+fn read_all(path: &Path) -> io::Result<Map<String, Value>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+        Err(err) => return Err(err),
+    };
+
+    match serde_json::from_str(&text) {
+        Ok(Value::Object(state)) => Ok(state),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "state file is not a JSON object",
+        )),
+        Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_replace_one_key_and_keep_the_others() {
+        let path = PathBuf::from("test_state.json");
+
+        write(&path, "a", json!([1])).unwrap();
+        write(&path, "b", json!("x")).unwrap();
+        write(&path, "a", json!([2])).unwrap();
+        let a = read(&path, "a");
+        let b = read(&path, "b");
+        let missing = read(&path, "c");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(a, Some(json!([2])));
+        assert_eq!(b, Some(json!("x")));
+        assert_eq!(missing, None);
+    }
+}
@@ -0,0 +1,265 @@
+//! Status line format for the Zack text editor.
+//!
+//! This module provides [`StatusLine`], the layout of the bar at the bottom of each
+//! window when users design their own, parsed from a format string set with
+//! `:set statusline=...` or in the `[statusline]` section of the configuration file:
+//!
+//! ```text
+//! [statusline]
+//! format = %mode% %file%%modified% %= %filetype% %branch% %line%:%col%
+//! ```
+//!
+//! Segments between `%` are replaced by what they name: `%mode%`, `%file%`,
+//! `%modified%` (`[+]` for unsaved changes), `%line%`, `%col%`, `%lines%`, `%percent%`,
+//! `%filetype%` and `%branch%`, the git branch of the file. `%=` ends the left part;
+//! the rest is drawn on the right. `%%` is a literal `%`. Without a format, the mode
+//! label is shown as before.
+
+use crate::app::{buffer_manager::Document, filetype::CONFIG_FILE};
+
+/// Section of the configuration file setting the status line.
+pub const SECTION: &str = "statusline";
+
+/// The segments, by name.
+const SEGMENTS: [(&str, Segment); 9] = [
+    ("mode", Segment::Mode),
+    ("file", Segment::File),
+    ("modified", Segment::Modified),
+    ("line", Segment::Line),
+    ("col", Segment::Col),
+    ("lines", Segment::Lines),
+    ("percent", Segment::Percent),
+    ("filetype", Segment::Filetype),
+    ("branch", Segment::Branch),
+];
+
+/// A part of the status line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// Text shown as is.
+    Text(String),
+    /// The mode label, with the count being typed; empty in windows that are not
+    /// focused.
+    Mode,
+    /// The name of the file.
+    File,
+    /// `[+]` when the buffer has unsaved changes.
+    Modified,
+    /// The cursor line, one-based.
+    Line,
+    /// The cursor column, one-based.
+    Col,
+    /// The number of lines of the buffer.
+    Lines,
+    /// How far down the buffer the cursor is, like `42%`.
+    Percent,
+    /// The file type of the buffer.
+    Filetype,
+    /// The git branch of the file.
+    Branch,
+    /// The end of the left part; what follows is drawn on the right.
+    Separator,
+}
+
+/// A status line layout, parsed from a format string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusLine {
+    segments: Vec<Segment>,
+}
+
+impl StatusLine {
+    /// Parses `format`, failing on unknown or unclosed segments.
+    pub fn parse(format: &str) -> Result<Self, String> {
+        let mut segments = vec![];
+        let mut text = String::new();
+        let mut rest = format;
+
+        while let Some(start) = rest.find('%') {
+            text.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+
+            if let Some(after) = rest.strip_prefix('%') {
+                text.push('%');
+                rest = after;
+                continue;
+            }
+
+            let segment = match rest.strip_prefix('=') {
+                Some(after) => {
+                    rest = after;
+                    Segment::Separator
+                }
+                None => {
+                    let end = rest
+                        .find('%')
+                        .ok_or_else(|| format!("Unclosed status line segment: %{rest}"))?;
+                    let name = &rest[..end];
+                    rest = &rest[end + 1..];
+
+                    SEGMENTS
+                        .iter()
+                        .find(|(known, _)| *known == name)
+                        .map(|(_, segment)| segment.clone())
+                        .ok_or_else(|| format!("Unknown status line segment: %{name}%"))?
+                }
+            };
+
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+            segments.push(segment);
+        }
+
+        text.push_str(rest);
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Returns the left and right parts of the status line of `document`, with `mode`
+    /// standing for `%mode%`.
+    pub fn render(&self, document: &Document, mode: &str) -> (String, String) {
+        let position = document.cursor.position;
+        let total = document.buffer.len_lines().max(1);
+        let mut parts = (String::new(), String::new());
+        let mut right = false;
+
+        for segment in &self.segments {
+            let text = match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Mode => mode.to_string(),
+                Segment::File => document.file.display_name(),
+                Segment::Modified if document.is_modified() => String::from("[+]"),
+                Segment::Modified => String::new(),
+                Segment::Line => (position.line + 1).to_string(),
+                Segment::Col => (position.col + 1).to_string(),
+                Segment::Lines => total.to_string(),
+                Segment::Percent => format!("{}%", (position.line + 1) * 100 / total),
+                Segment::Filetype => document
+                    .filetype
+                    .map(|filetype| filetype.name().to_string())
+                    .unwrap_or_default(),
+                Segment::Branch => document.branch.clone().unwrap_or_default(),
+                Segment::Separator => {
+                    right = true;
+                    continue;
+                }
+            };
+
+            match right {
+                true => parts.1.push_str(&text),
+                false => parts.0.push_str(&text),
+            }
+        }
+
+        parts
+    }
+}
+
+/// Reads the `format` of the `[statusline]` section of the configuration file `text`.
+/// Returns the status line it sets, if any, with the errors of the lines that could not
+/// be read.
+pub fn parse_config(text: &str) -> (Option<StatusLine>, Vec<String>) {
+    let mut statusline = None;
+    let mut errors = vec![];
+    let mut in_section = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == SECTION;
+            continue;
+        }
+
+        if in_section {
+            let result = match line.split_once('=') {
+                Some((key, value)) if key.trim() == "format" => StatusLine::parse(value.trim()),
+                Some((key, _)) => Err(format!("Unknown setting: {}", key.trim())),
+                None => Err(format!("Expected key = value: {line}")),
+            };
+
+            match result {
+                Ok(parsed) => statusline = Some(parsed),
+                Err(err) => errors.push(format!("{CONFIG_FILE}:{}: {err}", index + 1)),
+            }
+        }
+    }
+
+    (statusline, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_parse_segments_and_literal_text() {
+        let statusline = StatusLine::parse("%mode% | %file%%modified% 100%% %=%line%:%col%");
+
+        assert_eq!(
+            statusline,
+            Ok(StatusLine {
+                segments: vec![
+                    Segment::Mode,
+                    Segment::Text(String::from(" | ")),
+                    Segment::File,
+                    Segment::Modified,
+                    Segment::Text(String::from(" 100% ")),
+                    Segment::Separator,
+                    Segment::Line,
+                    Segment::Text(String::from(":")),
+                    Segment::Col,
+                ],
+            })
+        );
+        assert_eq!(
+            StatusLine::parse("%mode% %foo%"),
+            Err(String::from("Unknown status line segment: %foo%"))
+        );
+        assert_eq!(
+            StatusLine::parse("%line"),
+            Err(String::from("Unclosed status line segment: %line"))
+        );
+    }
+
+    #[test]
+    fn should_render_the_segments_of_a_document() {
+        let mut document = Document::new(
+            String::from("fn main() {}\n\n"),
+            Some(PathBuf::from("main.rs")),
+        );
+        document.cursor.position.line = 1;
+        document.branch = Some(String::from("topic"));
+        let statusline =
+            StatusLine::parse("%mode% %file% %filetype%%=%branch% %line%/%lines% %percent%")
+                .unwrap();
+
+        assert_eq!(
+            statusline.render(&document, "normal"),
+            (
+                String::from("normal main.rs rust"),
+                String::from("topic 2/3 66%")
+            )
+        );
+    }
+
+    #[test]
+    fn should_read_the_format_from_the_config_file() {
+        let (statusline, errors) = parse_config(
+            "[hooks]\nBufEnter * set wrap\n[statusline]\nformat = %file% %= %line%\nwidth = 3\n",
+        );
+
+        assert_eq!(statusline, StatusLine::parse("%file% %= %line%").ok());
+        assert_eq!(
+            errors,
+            vec![String::from("config:5: Unknown setting: width")]
+        );
+    }
+}
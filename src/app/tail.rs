@@ -0,0 +1,191 @@
+//! Follow mode module for the Zack text editor.
+//!
+//! This module provides [`Tail`], which watches a file that keeps growing, like a log,
+//! and delivers what gets appended to it as [`BufferManagerEvent::TailAppend`] events.
+//! A file that shrinks (truncated or rotated) is delivered whole again as
+//! [`BufferManagerEvent::TailReload`].
+//!
+//! The file is polled from a background thread, which stops when the `Tail` is dropped.
+
+use crate::{
+    app::buffer_manager::BufferManagerEvent,
+    event::{AppEvent, Event},
+};
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often the file is checked for new content.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Watches a file and reports its growth until dropped.
+#[derive(Debug)]
+pub struct Tail {
+    stop: Arc<AtomicBool>,
+    /// Whether the document was read-only before following started.
+    pub previous_read_only: bool,
+}
+
+impl Tail {
+    /// Starts watching `path` from byte `offset`, usually its current size.
+    pub fn start(
+        path: PathBuf,
+        offset: u64,
+        previous_read_only: bool,
+        sender: mpsc::Sender<Event>,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let watching = Arc::clone(&stop);
+
+        thread::spawn(move || {
+            let mut offset = offset;
+            // Bytes of a char split across two reads.
+            let mut pending: Vec<u8> = vec![];
+
+            while !watching.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+
+                let Ok(len) = fs::metadata(&path).map(|metadata| metadata.len()) else {
+                    continue;
+                };
+
+                let event = if len < offset {
+                    offset = len;
+                    pending.clear();
+                    BufferManagerEvent::TailReload(path.clone())
+                } else if len > offset {
+                    match read_from(&path, offset) {
+                        Ok(bytes) => {
+                            offset += bytes.len() as u64;
+                            pending.extend(bytes);
+
+                            let text = take_complete_text(&mut pending);
+                            if text.is_empty() {
+                                continue;
+                            }
+
+                            BufferManagerEvent::TailAppend {
+                                path: path.clone(),
+                                text,
+                            }
+                        }
+                        Err(err) => {
+                            tracing::warn!("Failed to read {}: {err}", path.display());
+                            continue;
+                        }
+                    }
+                } else {
+                    continue;
+                };
+
+                if sender
+                    .send(Event::App(AppEvent::BufferManager(event)))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            stop,
+            previous_read_only,
+        }
+    }
+}
+
+impl Drop for Tail {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Reads `path` from byte `offset` to its end.
+fn read_from(path: &Path, offset: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    let mut bytes = vec![];
+
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_to_end(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Decodes the bytes of `pending`, leaving a trailing incomplete char for the next read.
+///
+/// Invalid sequences are replaced rather than kept, so they cannot stall the output.
+fn take_complete_text(pending: &mut Vec<u8>) -> String {
+    let complete = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(err) if err.error_len().is_none() => err.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let bytes: Vec<u8> = pending.drain(..complete).collect();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn next_event(receiver: &mpsc::Receiver<Event>) -> BufferManagerEvent {
+        match receiver.recv_timeout(Duration::from_secs(5)).unwrap() {
+            Event::App(AppEvent::BufferManager(event)) => event,
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+
+    #[test]
+    fn should_keep_incomplete_chars_for_the_next_read() {
+        let mut pending = "aé".as_bytes().to_vec();
+        pending.pop();
+
+        assert_eq!(take_complete_text(&mut pending), "a");
+        assert_eq!(pending.len(), 1);
+
+        pending.push("é".as_bytes()[1]);
+        assert_eq!(take_complete_text(&mut pending), "é");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn should_report_appended_and_truncated_content() {
+        let path = PathBuf::from("test_tail_follow.txt");
+        fs::write(&path, "first\n").unwrap();
+        let (sender, receiver) = mpsc::channel();
+
+        let tail = Tail::start(path.clone(), 6, false, sender);
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"second\n")
+            .unwrap();
+
+        let appended = next_event(&receiver);
+        fs::write(&path, "").unwrap();
+        let reloaded = next_event(&receiver);
+        drop(tail);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            appended,
+            BufferManagerEvent::TailAppend {
+                path: path.clone(),
+                text: String::from("second\n"),
+            }
+        );
+        assert_eq!(reloaded, BufferManagerEvent::TailReload(path));
+    }
+}
@@ -0,0 +1,159 @@
+//! File templates for the Zack text editor.
+//!
+//! Opening a file that does not exist yet can start it from a template, also called a
+//! skeleton. Templates are opt-in: they live in `$XDG_CONFIG_HOME/zack/templates` (or
+//! `~/.config/zack/templates`), and nothing happens while that directory does not exist.
+//! A new file uses the template named like it, e.g. `main.rs`, or else the one for its
+//! extension, e.g. `skeleton.py` for any `.py` file.
+//!
+//! Templates may hold placeholders, replaced when the file is created:
+//!
+//! - `{{filename}}`: the name of the file, like `main.rs`.
+//! - `{{name}}`: the name without its extension, like `main`.
+//! - `{{date}}`: today's date, as `YYYY-MM-DD`.
+//! - `{{author}}`: the git `user.name`, or else the `USER` environment variable.
+
+use crate::app::{git, state};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Prefix of the templates matching files by extension.
+const SKELETON: &str = "skeleton";
+
+/// Returns the template directory, or `None` when no home directory is known.
+pub fn directory() -> Option<PathBuf> {
+    Some(state::config_directory()?.join("templates"))
+}
+
+/// Returns the content `path` starts with when it does not exist yet and a template
+/// matches it, placeholders replaced.
+pub fn for_new_file(path: &Path) -> Option<String> {
+    if path.exists() {
+        return None;
+    }
+
+    let template = find(&directory()?, path)?;
+    let text = fs::read_to_string(&template).ok()?;
+
+    tracing::info!("New file from template {}", template.display());
+
+    Some(expand(&text, path, &today(), &author()))
+}
+
+/// Returns the name filling `{{author}}`: the git `user.name`, or else `$USER`.
+pub fn author() -> String {
+    git::user_name()
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_default()
+}
+
+/// Finds the template for `path` in `directory`: the one with the same file name, or
+/// else the skeleton for its extension.
+pub fn find(directory: &Path, path: &Path) -> Option<PathBuf> {
+    let by_name = directory.join(path.file_name()?);
+    if by_name.is_file() {
+        return Some(by_name);
+    }
+
+    let extension = path.extension()?.to_str()?;
+    Some(directory.join(format!("{SKELETON}.{extension}"))).filter(|skeleton| skeleton.is_file())
+}
+
+/// Replaces the placeholders of `template` for the file at `path`.
+pub fn expand(template: &str, path: &Path, date: &str, author: &str) -> String {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+
+    template
+        .replace("{{filename}}", &file_name)
+        .replace("{{name}}", &name)
+        .replace("{{date}}", date)
+        .replace("{{author}}", author)
+}
+
+/// Returns today's date, in UTC, as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    format_date(seconds / 86_400)
+}
+
+/// Formats the date `days` after 1970-01-01 as `YYYY-MM-DD`, in the proleptic Gregorian
+/// calendar.
+fn format_date(days: u64) -> String {
+    // Counts from 0000-03-01, so leap days fall at the end of each year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_replace_placeholders() {
+        let template = "// {{filename}} ({{name}}), {{author}}, {{date}}\n";
+
+        assert_eq!(
+            expand(template, Path::new("src/main.rs"), "2024-03-01", "Ana"),
+            "// main.rs (main), Ana, 2024-03-01\n"
+        );
+    }
+
+    #[test]
+    fn should_prefer_templates_named_like_the_file() {
+        let directory = env::temp_dir().join("test_templates");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(directory.join("skeleton.rs"), "").unwrap();
+
+        let found = [
+            find(&directory, Path::new("main.rs")),
+            find(&directory, Path::new("src/lib.rs")),
+            find(&directory, Path::new("run.sh")),
+        ];
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            found,
+            [
+                Some(directory.join("main.rs")),
+                Some(directory.join("skeleton.rs")),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn should_format_dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(11_016), "2000-02-29");
+        assert_eq!(format_date(19_783), "2024-03-01");
+    }
+}
@@ -0,0 +1,534 @@
+//! Undo history module for the Zack text editor.
+//!
+//! This module provides [`UndoHistory`], the states a buffer can go back to with `u`
+//! and forward to again with `Ctrl-R`. Edits are grouped in steps: every edit made
+//! while a step is open belongs to it, and undoing brings back the state from before
+//! the whole step. A step is closed when leaving insert mode and before each edit made
+//! outside of it, so one insert session usually is one step.
+//!
+//! Typing a long paragraph in one go would then be undone all at once, so insert mode
+//! also closes the step at implicit break points: when a sentence ends (see
+//! [`ends_sentence`]) and when typing resumes after a pause. Both are configurable
+//! with `:set undobreak` and `:set undopause=<ms>`.
+//!
+//! Replaying a recorded session types far faster than a person, and may add millions of
+//! tiny edits. While the input comes from a replay, consecutive single-character
+//! insertions or deletions join one run (see [`UndoHistory::coalesces`]), which skips
+//! the implicit break points, up to a soft limit of [`MAX_RUN`] characters per run, so a
+//! replay leaves few steps. Typed input, however fast, always keeps them.
+//!
+//! The states of the history, from the oldest one kept to the last one undone, are
+//! numbered from `0`, and [`UndoHistory::jump`] goes straight to any of them: `:earlier`
+//! and `:later` go back and forward by a number of states or by time, as in `:earlier
+//! 10m`, and `:undotree` lists the states in a panel to preview and pick one.
+
+use crate::{app::buffer::Buffer, event::BufferEvent, types::position::Position};
+use ropey::Rope;
+use std::time::{Duration, Instant};
+
+/// Most steps kept; older ones can no longer be undone.
+const MAX_STEPS: usize = 1000;
+
+/// Most characters a run of coalesced edits holds before a break point applies again.
+const MAX_RUN: usize = 4096;
+
+/// A state of the buffer to go back to.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub rope: Rope,
+    /// Revision of the buffer in this state, so undoing to the saved state is not a
+    /// change.
+    pub revision: u64,
+    /// Where the cursor goes when coming back to this state.
+    pub cursor: Position,
+    /// When the step between this state and the current one was made: the step leaving
+    /// it while it can be undone to, the step reaching it while it can be redone.
+    pub time: Instant,
+}
+
+/// A state of the history, as listed by `:undotree`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UndoState {
+    /// When the step reaching this state was made; `None` for the oldest state.
+    pub time: Option<Instant>,
+    pub lines: usize,
+    pub chars: usize,
+    /// Set for the state the buffer is in.
+    pub current: bool,
+}
+
+/// How far `:earlier` and `:later` go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UndoSpan {
+    /// A number of states, as in `:earlier 3`.
+    Steps(usize),
+    /// A length of time, as in `:earlier 10s`, `10m` or `10h`.
+    Time(Duration),
+}
+
+impl UndoSpan {
+    /// Parses the argument of `:earlier` and `:later`: a count, optionally followed by
+    /// `s`, `m` or `h`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let digits = text.trim_end_matches(|char: char| char.is_ascii_alphabetic());
+        let count: u64 = digits.parse().ok()?;
+        let seconds = match &text[digits.len()..] {
+            "" => return Some(UndoSpan::Steps(count as usize)),
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            _ => return None,
+        };
+
+        Some(UndoSpan::Time(Duration::from_secs(
+            count.saturating_mul(seconds),
+        )))
+    }
+}
+
+/// Whether a single-character edit adds or removes its character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharEditKind {
+    Insert,
+    Delete,
+}
+
+/// An edit of a single character within a line, which can join a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CharEdit {
+    pub kind: CharEditKind,
+    pub position: Position,
+}
+
+/// Consecutive single-character edits of the same kind.
+#[derive(Clone, Copy, Debug)]
+struct Run {
+    kind: CharEditKind,
+    /// Where the next edit of the run happens.
+    next: Position,
+    /// Characters inserted or deleted so far.
+    len: usize,
+}
+
+/// Undo and redo stacks of a buffer.
+#[derive(Debug, Default)]
+pub struct UndoHistory {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// Set while edits are added to the newest step.
+    open: bool,
+    /// When the last edit was recorded, to notice pauses.
+    last_edit: Option<Instant>,
+    /// The run the last edit belongs to, if it was a single-character edit.
+    run: Option<Run>,
+}
+
+impl CharEdit {
+    /// Returns the single-character edit `event` makes, if it makes one: typing a
+    /// character, or deleting the one before a column other than the first.
+    pub fn of(event: &BufferEvent) -> Option<Self> {
+        match event {
+            BufferEvent::InsertChar { position, .. } => Some(Self {
+                kind: CharEditKind::Insert,
+                position: *position,
+            }),
+            BufferEvent::DeleteChar { position } if position.col > 0 => Some(Self {
+                kind: CharEditKind::Delete,
+                position: *position,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns where the next edit continuing this one happens.
+    fn next(&self) -> Position {
+        match self.kind {
+            CharEditKind::Insert => Position::new(self.position.line, self.position.col + 1),
+            CharEditKind::Delete => Position::new(self.position.line, self.position.col - 1),
+        }
+    }
+}
+
+impl UndoHistory {
+    /// Creates an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an edit made to `state`, the buffer before it: the edit starts a new step
+    /// saving `state`, unless a step is open. Any undone step can no longer be redone.
+    /// `edit` is the single-character edit made, if any, which may extend the run.
+    pub fn record(&mut self, state: Snapshot, edit: Option<CharEdit>, now: Instant) {
+        if !self.open {
+            self.undo.push(Snapshot { time: now, ..state });
+            self.open = true;
+
+            let excess = self.undo.len().saturating_sub(MAX_STEPS);
+            self.undo.drain(..excess);
+        }
+
+        self.run = edit.map(|edit| Run {
+            kind: edit.kind,
+            next: edit.next(),
+            len: match self.run {
+                Some(run) if run.kind == edit.kind && run.next == edit.position => run.len + 1,
+                _ => 1,
+            },
+        });
+        self.redo.clear();
+        self.last_edit = Some(now);
+    }
+
+    /// Closes the current step, so the next edit starts a new one.
+    pub fn break_point(&mut self) {
+        self.open = false;
+        self.run = None;
+    }
+
+    /// Returns `true` when `event` continues the run of the last edit: when replaying, it
+    /// then joins the open step instead of stopping at implicit break points, until the
+    /// run reaches [`MAX_RUN`] characters.
+    pub fn coalesces(&self, event: &BufferEvent) -> bool {
+        let (Some(run), Some(edit)) = (self.run, CharEdit::of(event)) else {
+            return false;
+        };
+
+        self.open && run.kind == edit.kind && run.next == edit.position && run.len < MAX_RUN
+    }
+
+    /// Returns `true` if more than `pause` passed between the last edit and `now`.
+    pub fn is_paused(&self, now: Instant, pause: Duration) -> bool {
+        self.last_edit
+            .is_some_and(|last_edit| now.duration_since(last_edit) > pause)
+    }
+
+    /// Returns the number of steps that can be undone.
+    pub fn len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Returns `true` when there is nothing to undo.
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty()
+    }
+
+    /// Takes the state from before the newest step, keeping `current` to redo it.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        self.break_point();
+        let previous = self.undo.pop()?;
+        self.redo.push(Snapshot {
+            time: previous.time,
+            ..current
+        });
+
+        Some(previous)
+    }
+
+    /// Takes the state from after the last undone step, keeping `current` to undo it
+    /// again.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        self.break_point();
+        let next = self.redo.pop()?;
+        self.undo.push(Snapshot {
+            time: next.time,
+            ..current
+        });
+
+        Some(next)
+    }
+
+    /// Returns the number of the current state, which is also how many steps can be
+    /// undone.
+    pub fn current(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Lists every state, oldest first, `current` being the state the buffer is in.
+    pub fn states(&self, current: &Snapshot) -> Vec<UndoState> {
+        let snapshots = self
+            .undo
+            .iter()
+            .chain([current])
+            .chain(self.redo.iter().rev());
+        // A state is reached by the step leaving the state before it, or by its own
+        // step once undone.
+        let times = [None]
+            .into_iter()
+            .chain(self.undo.iter().map(|snapshot| Some(snapshot.time)))
+            .chain(self.redo.iter().rev().map(|snapshot| Some(snapshot.time)));
+
+        snapshots
+            .zip(times)
+            .enumerate()
+            .map(|(index, (snapshot, time))| UndoState {
+                time,
+                lines: snapshot.rope.len_lines(),
+                chars: snapshot.rope.len_chars(),
+                current: index == self.undo.len(),
+            })
+            .collect()
+    }
+
+    /// Undoes or redoes steps until state `target` is reached, or the closest one that
+    /// exists, keeping `current` in the history. Returns the state to bring back, or
+    /// `None` when already there.
+    pub fn jump(&mut self, target: usize, current: Snapshot) -> Option<Snapshot> {
+        let mut state = current;
+        let start = self.undo.len();
+
+        while self.undo.len() > target
+            && let Some(previous) = self.undo(state.clone())
+        {
+            state = previous;
+        }
+        while self.undo.len() < target
+            && let Some(next) = self.redo(state.clone())
+        {
+            state = next;
+        }
+
+        (self.undo.len() != start).then_some(state)
+    }
+
+    /// Returns the state reached by going `by` back in time from the current one, or
+    /// forward when `forward` is set: the last state whose step was made by then.
+    pub fn state_at(&self, by: Duration, forward: bool) -> usize {
+        let times: Vec<Instant> = self
+            .undo
+            .iter()
+            .chain(self.redo.iter().rev())
+            .map(|snapshot| snapshot.time)
+            .collect();
+        let current = self.undo.len();
+        // The current state was reached by the step before it, or is the oldest one.
+        let Some(now) = current
+            .checked_sub(1)
+            .map(|step| times[step])
+            .or_else(|| times.first().copied())
+        else {
+            return current;
+        };
+
+        match forward {
+            true => {
+                let until = now + by;
+                current
+                    + times[current..]
+                        .iter()
+                        .take_while(|time| **time <= until)
+                        .count()
+            }
+            false => {
+                let since = now.checked_sub(by);
+                times[..current.saturating_sub(1)]
+                    .iter()
+                    .rposition(|time| since.is_some_and(|since| *time <= since))
+                    .map_or(0, |step| step + 1)
+            }
+        }
+    }
+}
+
+/// Returns `true` when `event` types a space or a line break right after the end of a
+/// sentence in `buffer`, which starts a new undo step with the next sentence.
+pub fn ends_sentence(event: &BufferEvent, buffer: &Buffer) -> bool {
+    let position = match event {
+        BufferEvent::InsertChar { char, position } if char.is_whitespace() => *position,
+        BufferEvent::InsertNewline { position } => *position,
+        _ => return false,
+    };
+
+    buffer
+        .char_before(position)
+        .is_some_and(|before| matches!(before, '.' | '!' | '?'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(text: &str) -> Snapshot {
+        Snapshot {
+            rope: Rope::from_str(text),
+            revision: text.len() as u64,
+            cursor: Position::new(0, text.len()),
+            time: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn should_group_edits_until_a_break_point() {
+        let mut history = UndoHistory::new();
+        let now = Instant::now();
+
+        history.record(state(""), None, now);
+        history.record(state("a"), None, now);
+        history.break_point();
+        history.record(state("ab"), None, now);
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.undo(state("abc")).unwrap().rope, "ab");
+        assert_eq!(history.undo(state("ab")).unwrap().rope, "");
+        assert!(history.undo(state("")).is_none());
+    }
+
+    #[test]
+    fn should_redo_until_a_new_edit() {
+        let mut history = UndoHistory::new();
+        let now = Instant::now();
+
+        history.record(state(""), None, now);
+        history.undo(state("a"));
+
+        assert_eq!(history.redo(state("")).unwrap().rope, "a");
+        assert!(history.redo(state("a")).is_none());
+
+        history.undo(state("a"));
+        history.record(state(""), None, now);
+        assert!(history.redo(state("b")).is_none());
+    }
+
+    #[test]
+    fn should_jump_to_any_state_and_list_them() {
+        let mut history = UndoHistory::new();
+        let now = Instant::now();
+
+        history.record(state(""), None, now);
+        history.break_point();
+        history.record(state("a"), None, now + Duration::from_secs(60));
+        history.break_point();
+        history.record(state("ab"), None, now + Duration::from_secs(120));
+
+        assert_eq!(history.jump(0, state("abc")).unwrap().rope, "");
+        assert_eq!(history.current(), 0);
+        assert!(history.jump(0, state("")).is_none());
+        assert_eq!(history.jump(9, state("")).unwrap().rope, "abc");
+
+        history.jump(2, state("abc"));
+        let states = history.states(&state("ab"));
+        assert_eq!(states.len(), 4);
+        assert_eq!(states[0].time, None);
+        assert_eq!(states[1].time, Some(now));
+        assert_eq!(states[3].time, Some(now + Duration::from_secs(120)));
+        assert!(states[2].current);
+        assert_eq!(states[3].chars, 3);
+    }
+
+    #[test]
+    fn should_find_states_by_time() {
+        let mut history = UndoHistory::new();
+        let now = Instant::now();
+
+        for (step, text) in ["", "a", "ab"].iter().enumerate() {
+            history.record(
+                state(text),
+                None,
+                now + Duration::from_secs(60 * step as u64),
+            );
+            history.break_point();
+        }
+
+        assert_eq!(history.state_at(Duration::from_secs(60), false), 2);
+        assert_eq!(history.state_at(Duration::from_secs(90), false), 1);
+        assert_eq!(history.state_at(Duration::from_secs(600), false), 0);
+
+        history.jump(0, state("abc"));
+        assert_eq!(history.state_at(Duration::from_secs(60), true), 2);
+        assert_eq!(history.state_at(Duration::from_secs(600), true), 3);
+    }
+
+    #[test]
+    fn should_parse_undo_spans() {
+        assert_eq!(UndoSpan::parse("3"), Some(UndoSpan::Steps(3)));
+        assert_eq!(
+            UndoSpan::parse("10m"),
+            Some(UndoSpan::Time(Duration::from_secs(600)))
+        );
+        assert_eq!(UndoSpan::parse("2d"), None);
+        assert_eq!(UndoSpan::parse("m"), None);
+    }
+
+    #[test]
+    fn should_notice_pauses_between_edits() {
+        let mut history = UndoHistory::new();
+        let now = Instant::now();
+        let pause = Duration::from_secs(2);
+
+        assert!(!history.is_paused(now, pause));
+
+        history.record(state(""), None, now);
+
+        assert!(!history.is_paused(now + Duration::from_secs(1), pause));
+        assert!(history.is_paused(now + Duration::from_secs(3), pause));
+    }
+
+    #[test]
+    fn should_coalesce_runs_of_adjacent_single_character_edits() {
+        let mut history = UndoHistory::new();
+        let now = Instant::now();
+        let insert_at = |col| BufferEvent::InsertChar {
+            char: 'a',
+            position: Position::new(0, col),
+        };
+        let delete_at = |col| BufferEvent::DeleteChar {
+            position: Position::new(0, col),
+        };
+
+        history.record(state(""), CharEdit::of(&insert_at(0)), now);
+
+        assert!(history.coalesces(&insert_at(1)));
+        assert!(!history.coalesces(&insert_at(3)));
+        assert!(!history.coalesces(&delete_at(1)));
+
+        history.record(state("ab"), CharEdit::of(&delete_at(2)), now);
+        assert!(history.coalesces(&delete_at(1)));
+
+        history.record(state("a"), CharEdit::of(&delete_at(1)), now);
+        assert!(!history.coalesces(&delete_at(0)));
+
+        history.break_point();
+        assert!(!history.coalesces(&delete_at(1)));
+    }
+
+    #[test]
+    fn should_stop_coalescing_at_the_soft_limit() {
+        let mut history = UndoHistory::new();
+        let now = Instant::now();
+        let insert_at = |col| BufferEvent::InsertChar {
+            char: 'a',
+            position: Position::new(0, col),
+        };
+
+        for col in 0..MAX_RUN {
+            history.record(state(""), CharEdit::of(&insert_at(col)), now);
+        }
+
+        assert_eq!(history.len(), 1);
+        assert!(!history.coalesces(&insert_at(MAX_RUN)));
+    }
+
+    #[test]
+    fn should_end_sentences_on_space_or_newline_after_punctuation() {
+        let buffer = Buffer::new(String::from("Done. Next"));
+        let space_at = |col| BufferEvent::InsertChar {
+            char: ' ',
+            position: Position::new(0, col),
+        };
+
+        assert!(ends_sentence(&space_at(5), &buffer));
+        assert!(!ends_sentence(&space_at(4), &buffer));
+        assert!(ends_sentence(
+            &BufferEvent::InsertNewline {
+                position: Position::new(0, 5)
+            },
+            &buffer
+        ));
+        assert!(!ends_sentence(
+            &BufferEvent::InsertChar {
+                char: 'x',
+                position: Position::new(0, 5)
+            },
+            &buffer
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+//! Configuration watching module for the Zack text editor.
+//!
+//! This module provides [`ConfigWatch`], which notices when the files of the
+//! configuration directory, like the configuration, keymap and theme files, are created,
+//! modified or removed while the editor runs, so they are read again without restarting.
+//! Like open files, they are compared with what was last seen by size and modification
+//! time, at most once per [`CHECK_INTERVAL`].
+
+use crate::app::file::DiskInfo;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// How often the watched files are compared with what was last seen.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches some files of the configuration directory for changes.
+#[derive(Debug, Default)]
+pub struct ConfigWatch {
+    /// The directory the files are in; nothing is watched without one.
+    directory: Option<PathBuf>,
+    /// The watched files, by name, with how they were last seen.
+    files: Vec<(&'static str, Option<DiskInfo>)>,
+    /// When the files were last compared.
+    last_check: Option<Instant>,
+}
+
+impl ConfigWatch {
+    /// Starts watching the files `names` of `directory`, as they are now.
+    pub fn new(directory: PathBuf, names: &[&'static str]) -> Self {
+        let files = names
+            .iter()
+            .map(|name| (*name, DiskInfo::read(&directory.join(name))))
+            .collect();
+
+        Self {
+            directory: Some(directory),
+            files,
+            last_check: Some(Instant::now()),
+        }
+    }
+
+    /// Returns the names of the files that changed since they were last seen, comparing
+    /// them at most once per [`CHECK_INTERVAL`].
+    pub fn changed(&mut self) -> Vec<&'static str> {
+        let Some(directory) = &self.directory else {
+            return vec![];
+        };
+        if self
+            .last_check
+            .is_some_and(|checked| checked.elapsed() < CHECK_INTERVAL)
+        {
+            return vec![];
+        }
+        self.last_check = Some(Instant::now());
+
+        let mut changed = vec![];
+        for (name, seen) in &mut self.files {
+            let current = DiskInfo::read(&directory.join(*name));
+            if current != *seen {
+                *seen = current;
+                changed.push(*name);
+            }
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn should_report_the_files_that_changed() {
+        let directory = std::env::temp_dir().join(format!("zack-watch-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("config"), "a").unwrap();
+
+        let mut watch = ConfigWatch::new(directory.clone(), &["config", "theme"]);
+        watch.last_check = None;
+        assert!(watch.changed().is_empty());
+
+        fs::write(directory.join("config"), "ab").unwrap();
+        fs::write(directory.join("theme"), "").unwrap();
+        watch.last_check = None;
+        assert_eq!(watch.changed(), vec!["config", "theme"]);
+
+        // Checks are spaced out.
+        fs::remove_file(directory.join("theme")).unwrap();
+        assert!(watch.changed().is_empty());
+        watch.last_check = None;
+        assert_eq!(watch.changed(), vec!["theme"]);
+
+        fs::remove_dir_all(&directory).unwrap();
+        assert!(ConfigWatch::default().changed().is_empty());
+    }
+}
@@ -0,0 +1,337 @@
+//! Windows for the Zack text editor.
+//!
+//! The editor area shows the active document, or several documents in windows: stacked
+//! one above the other, side by side, or as tab pages showing one at a time under a bar
+//! naming them. `zack -o`, `-O` and `-p` open their files this way, like Vim.
+//!
+//! The window showing the active document has the focus, so switching buffers moves the
+//! focus to the window already showing the new one, or else shows it in the focused
+//! window. `Ctrl-W w` and `Ctrl-W W` focus the next and previous windows, `Ctrl-W o`
+//! closes all but the focused one, and clicking a window focuses it.
+//!
+//! `zack -d` shows two files side by side with their scrolling locked together, so the
+//! same lines of both stay in view, and `zack --merge` shows the three versions of a
+//! merge side by side above the merged file.
+//!
+//! With several buffers open, the top row lists them all, as tab pages list their
+//! windows, unless `:set nobufferline` hides it.
+//!
+//! While the terminal pane is open, it takes the bottom third of the editor area and the
+//! windows share the rest.
+
+use ratatui::layout::{Constraint, Layout, Rect};
+
+/// How the windows share the editor area.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Arrangement {
+    /// One above the other (`-o`).
+    #[default]
+    Stacked,
+    /// Side by side (`-O`).
+    SideBySide,
+    /// One at a time, under a bar naming all of them (`-p`).
+    Tabs,
+    /// Side by side on the top third, above the last one (`--merge`).
+    Merge,
+}
+
+/// Events that change which windows are shown or focused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowEvent {
+    /// Focuses the next window, wrapping around.
+    Next,
+    /// Focuses the previous window, wrapping around.
+    Previous,
+    /// Closes every window but the focused one.
+    Only,
+}
+
+/// The documents on screen, by index in the buffer manager, and the focused one.
+#[derive(Debug)]
+pub struct Windows {
+    pub arrangement: Arrangement,
+    /// Scrolls every window along with the focused one.
+    pub scroll_bind: bool,
+    /// Keeps the bottom of the editor area for the terminal pane.
+    pub panel: bool,
+    /// Keeps the top row for the bar listing the buffers.
+    pub buffer_bar: bool,
+    documents: Vec<usize>,
+    focused: usize,
+}
+
+impl Default for Windows {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Windows {
+    /// Creates a single window showing the first document.
+    pub fn new() -> Self {
+        Self {
+            arrangement: Arrangement::default(),
+            scroll_bind: false,
+            panel: false,
+            buffer_bar: false,
+            documents: vec![0],
+            focused: 0,
+        }
+    }
+
+    /// Shows `documents` in windows arranged as `arrangement`, focusing the first one.
+    /// A document listed twice gets a single window.
+    pub fn open(&mut self, arrangement: Arrangement, documents: &[usize]) {
+        let mut shown: Vec<usize> = vec![];
+        for document in documents {
+            if !shown.contains(document) {
+                shown.push(*document);
+            }
+        }
+
+        if shown.is_empty() {
+            return;
+        }
+
+        self.arrangement = arrangement;
+        self.documents = shown;
+        self.focused = 0;
+    }
+
+    /// Returns the documents on screen, in window order.
+    pub fn documents(&self) -> &[usize] {
+        &self.documents
+    }
+
+    /// Returns the document of the focused window.
+    pub fn focused_document(&self) -> usize {
+        self.documents[self.focused]
+    }
+
+    /// Focuses the window showing `active`, the active document, or shows it in the
+    /// focused window when no window does.
+    pub fn sync(&mut self, active: usize) {
+        match self
+            .documents
+            .iter()
+            .position(|document| *document == active)
+        {
+            Some(index) => self.focused = index,
+            None => self.documents[self.focused] = active,
+        }
+    }
+
+    /// Applies `event` and returns the document to make active.
+    pub fn handle_event(&mut self, event: WindowEvent) -> usize {
+        let count = self.documents.len();
+
+        match event {
+            WindowEvent::Next => self.focused = (self.focused + 1) % count,
+            WindowEvent::Previous => self.focused = (self.focused + count - 1) % count,
+            WindowEvent::Only => {
+                self.documents = vec![self.focused_document()];
+                self.focused = 0;
+                self.scroll_bind = false;
+            }
+        }
+
+        self.focused_document()
+    }
+
+    /// Returns the area of the terminal pane at the bottom of the editor `area`, when it
+    /// is open.
+    pub fn panel_area(&self, area: Rect) -> Option<Rect> {
+        self.panel.then(|| {
+            let [_, panel] = Self::split(area);
+            panel
+        })
+    }
+
+    /// Returns the part of the editor `area` shared by the windows.
+    fn windows_area(&self, area: Rect) -> Rect {
+        let area = self.above_panel(area);
+        match self.has_bar() {
+            true => Rect {
+                y: area.y + 1,
+                height: area.height.saturating_sub(1),
+                ..area
+            },
+            false => area,
+        }
+    }
+
+    /// Returns the part of the editor `area` above the terminal pane.
+    fn above_panel(&self, area: Rect) -> Rect {
+        match self.panel {
+            true => Self::split(area)[0],
+            false => area,
+        }
+    }
+
+    /// Returns `true` if the top row is kept for the bar naming the tab pages or the
+    /// buffers.
+    fn has_bar(&self) -> bool {
+        self.buffer_bar || (self.arrangement == Arrangement::Tabs && self.documents.len() > 1)
+    }
+
+    /// Splits `area` between the windows and the terminal pane below them.
+    fn split(area: Rect) -> [Rect; 2] {
+        Layout::vertical([Constraint::Ratio(2, 3), Constraint::Ratio(1, 3)]).areas(area)
+    }
+
+    /// Returns `true` if `event` would wrap around the windows, which focuses the
+    /// terminal pane instead when it is open.
+    pub fn wraps(&self, event: WindowEvent) -> bool {
+        match event {
+            WindowEvent::Next => self.focused + 1 == self.documents.len(),
+            WindowEvent::Previous => self.focused == 0,
+            WindowEvent::Only => false,
+        }
+    }
+
+    /// Returns the row naming the tab pages at the top of `area`, when there are several.
+    pub fn tab_bar(&self, area: Rect) -> Option<Rect> {
+        let area = self.above_panel(area);
+        self.has_bar().then_some(Rect { height: 1, ..area })
+    }
+
+    /// Returns the documents drawn in the editor `area` with the area of their window,
+    /// flagging the focused one.
+    pub fn areas(&self, area: Rect) -> Vec<(usize, Rect, bool)> {
+        let area = self.windows_area(area);
+        if self.documents.len() == 1 {
+            return vec![(self.documents[0], area, true)];
+        }
+
+        let ratios = |count: usize| vec![Constraint::Ratio(1, count as u32); count];
+        let constraints = ratios(self.documents.len());
+        let areas = match self.arrangement {
+            Arrangement::Stacked => Layout::vertical(constraints).split(area),
+            Arrangement::SideBySide => Layout::horizontal(constraints).split(area),
+            Arrangement::Merge => {
+                let [top, bottom] =
+                    Layout::vertical([Constraint::Ratio(1, 3), Constraint::Ratio(2, 3)])
+                        .areas(area);
+                let mut areas = Layout::horizontal(ratios(self.documents.len() - 1))
+                    .split(top)
+                    .to_vec();
+                areas.push(bottom);
+                areas.into()
+            }
+            Arrangement::Tabs => return vec![(self.focused_document(), area, true)],
+        };
+
+        self.documents
+            .iter()
+            .zip(areas.iter())
+            .enumerate()
+            .map(|(index, (document, area))| (*document, *area, index == self.focused))
+            .collect()
+    }
+
+    /// Returns the area of the focused window in the editor `area`.
+    pub fn focused_area(&self, area: Rect) -> Rect {
+        self.areas(area)
+            .into_iter()
+            .find(|(_, _, focused)| *focused)
+            .map_or(area, |(_, area, _)| area)
+    }
+
+    /// Returns the document of the window under the screen cell at `column`, `row`.
+    pub fn document_at(&self, area: Rect, column: u16, row: u16) -> Option<usize> {
+        self.areas(area)
+            .into_iter()
+            .find(|(_, area, _)| {
+                (area.left()..area.right()).contains(&column)
+                    && (area.top()..area.bottom()).contains(&row)
+            })
+            .map(|(document, _, _)| document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_the_area_between_windows() {
+        let mut windows = Windows::new();
+        let area = Rect::new(0, 0, 80, 20);
+
+        windows.open(Arrangement::Stacked, &[0, 1]);
+        assert_eq!(
+            windows.areas(area),
+            vec![
+                (0, Rect::new(0, 0, 80, 10), true),
+                (1, Rect::new(0, 10, 80, 10), false)
+            ]
+        );
+
+        windows.open(Arrangement::SideBySide, &[0, 1]);
+        assert_eq!(windows.focused_area(area), Rect::new(0, 0, 40, 20));
+        assert_eq!(windows.document_at(area, 50, 3), Some(1));
+
+        windows.open(Arrangement::Merge, &[1, 2, 3, 0]);
+        assert_eq!(
+            windows.areas(area)[2..],
+            [
+                (3, Rect::new(53, 0, 27, 7), false),
+                (0, Rect::new(0, 7, 80, 13), false)
+            ]
+        );
+
+        windows.open(Arrangement::Tabs, &[0, 1, 1]);
+        assert_eq!(windows.documents(), &[0, 1]);
+        assert_eq!(
+            windows.areas(area),
+            vec![(0, Rect::new(0, 1, 80, 19), true)]
+        );
+        assert_eq!(windows.tab_bar(area), Some(Rect::new(0, 0, 80, 1)));
+    }
+
+    #[test]
+    fn should_keep_the_bottom_of_the_area_for_the_terminal_pane() {
+        let mut windows = Windows::new();
+        let area = Rect::new(0, 0, 80, 30);
+        assert_eq!(windows.panel_area(area), None);
+
+        windows.panel = true;
+        windows.open(Arrangement::Stacked, &[0, 1]);
+        assert_eq!(windows.panel_area(area), Some(Rect::new(0, 20, 80, 10)));
+        assert_eq!(windows.areas(area)[1], (1, Rect::new(0, 10, 80, 10), false));
+        assert!(windows.wraps(WindowEvent::Previous));
+        assert!(!windows.wraps(WindowEvent::Next));
+    }
+
+    #[test]
+    fn should_keep_the_top_row_for_the_buffer_bar() {
+        let mut windows = Windows::new();
+        let area = Rect::new(0, 0, 80, 30);
+
+        windows.buffer_bar = true;
+        windows.open(Arrangement::Stacked, &[0, 1]);
+        assert_eq!(windows.tab_bar(area), Some(Rect::new(0, 0, 80, 1)));
+        assert_eq!(windows.areas(area)[0], (0, Rect::new(0, 1, 80, 15), true));
+
+        windows.panel = true;
+        assert_eq!(windows.tab_bar(area), Some(Rect::new(0, 0, 80, 1)));
+        assert_eq!(windows.panel_area(area), Some(Rect::new(0, 20, 80, 10)));
+    }
+
+    #[test]
+    fn should_follow_the_active_document() {
+        let mut windows = Windows::new();
+        windows.open(Arrangement::Stacked, &[0, 1]);
+
+        windows.sync(1);
+        assert_eq!(windows.focused_document(), 1);
+
+        windows.sync(2);
+        assert_eq!(windows.documents(), &[0, 2]);
+
+        assert_eq!(windows.handle_event(WindowEvent::Next), 0);
+        assert_eq!(windows.handle_event(WindowEvent::Previous), 2);
+        assert_eq!(windows.handle_event(WindowEvent::Only), 2);
+        assert_eq!(windows.documents(), &[2]);
+    }
+}
@@ -0,0 +1,86 @@
+//! Hard wrapping module for the Zack text editor.
+//!
+//! This module provides [`hard_wrap`], which `gq` uses to fill whole lines of prose,
+//! commit messages or comments up to `:set textwidth`. Lines are joined into paragraphs,
+//! split at blank lines and where a new list item starts, and each paragraph is wrapped
+//! at word boundaries again, keeping its indentation and comment marker on every line and
+//! hanging the lines of a list item under its text.
+
+use crate::app::leader;
+
+/// Re-wraps the lines of `text` so they fit in `width` columns where their words allow,
+/// given the line comment marker of its file type. Blank lines are kept as they are.
+pub fn hard_wrap(text: &str, width: usize, comment: Option<&str>) -> String {
+    let mut wrapped = vec![];
+    let mut lines = text.lines().peekable();
+
+    while let Some(first) = lines.next() {
+        let leader = leader::leader(first, comment).unwrap_or(leader::indentation(first));
+        if first[leader.len()..].trim().is_empty() {
+            wrapped.push(first.to_string());
+            continue;
+        }
+
+        let hanging = leader::hanging(first, comment);
+        let mut words: Vec<&str> = first[leader.len()..].split_whitespace().collect();
+        while let Some(line) = lines.next_if(|line| {
+            let own = leader::leader(line, comment).unwrap_or(leader::indentation(line));
+            own == hanging && !line[own.len()..].trim().is_empty()
+        }) {
+            words.extend(line[hanging.len()..].split_whitespace());
+        }
+
+        fill(&mut wrapped, &words, leader, &hanging, width);
+    }
+
+    wrapped.join("\n")
+}
+
+/// Adds `words` as lines of at most `width` columns, the first starting with `leader` and
+/// the others with `hanging`. Words longer than a line get one of their own.
+fn fill(wrapped: &mut Vec<String>, words: &[&str], leader: &str, hanging: &str, width: usize) {
+    let mut line = leader.to_string();
+    let mut empty = true;
+
+    for word in words {
+        let len = line.chars().count() + word.chars().count() + usize::from(!empty);
+        if !empty && len > width {
+            wrapped.push(std::mem::replace(&mut line, hanging.to_string()));
+            empty = true;
+        }
+
+        if !empty {
+            line.push(' ');
+        }
+        line.push_str(word);
+        empty = false;
+    }
+
+    wrapped.push(line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_fill_paragraphs_up_to_the_width() {
+        assert_eq!(
+            hard_wrap("one two\nthree four five\n\n  six seven eight", 10, None),
+            "one two\nthree four\nfive\n\n  six\n  seven\n  eight"
+        );
+        assert_eq!(hard_wrap("a verylongword b", 5, None), "a\nverylongword\nb");
+    }
+
+    #[test]
+    fn should_keep_comment_markers_and_hang_list_items() {
+        assert_eq!(
+            hard_wrap("// a b c d e\n// f\n//\n// g", 8, Some("//")),
+            "// a b c\n// d e f\n//\n// g"
+        );
+        assert_eq!(
+            hard_wrap("- one two three\n- four", 10, None),
+            "- one two\n  three\n- four"
+        );
+    }
+}
@@ -1,15 +1,39 @@
 //! Defines high-level application events used to coordinate editor behavior.
 //!
 //! This module exports the [`AppEvent`] enum, which wraps lower-level domain-specific
-//! events (like [`BufferEvent`], [`CursorEvent`], and [`FileEvent`]) and higher-level
-//! app signals.
+//! events (like [`BufferEvent`], [`CursorEvent`], [`FileEvent`] and [`SearchEvent`]) and
+//! higher-level app signals.
 
-use crate::{app::modes::EditorMode, ui::components::FocusableComponent};
+use crate::{
+    app::{
+        diagnostics::SignAction,
+        diff::DiffLine,
+        hooks::Autocmd,
+        keymap::{Action, KeymapMode},
+        modes::EditorMode,
+        operator::{Operator, Target},
+    },
+    types::position::Position,
+    ui::components::{FocusableComponent, command_line::CommandLineKind, prompt::PromptKind},
+};
+use crossterm::event::KeyEvent;
+use std::path::PathBuf;
 
 /// Re-exports of domain-specific event types.
 pub use crate::app::buffer::BufferEvent;
+pub use crate::app::buffer_manager::BufferManagerEvent;
+pub use crate::app::completion::CompletionEvent;
 pub use crate::app::cursor::CursorEvent;
 pub use crate::app::file::FileEvent;
+pub use crate::app::folds::FoldEvent;
+pub use crate::app::jobs::JobEvent;
+pub use crate::app::marks::MarkEvent;
+pub use crate::app::pty::TerminalEvent;
+pub use crate::app::quickfix::QuickfixEvent;
+pub use crate::app::search::SearchEvent;
+pub use crate::app::shell::ShellEvent;
+pub use crate::app::windows::WindowEvent;
+pub use crate::lsp::LspEvent;
 
 /// Represents a high-level application event.
 ///
@@ -22,10 +46,112 @@ pub enum AppEvent {
     Cursor(CursorEvent),
     /// Event for file operations like save/load.
     File(FileEvent),
+    /// Event that opens, switches or acts on all open documents.
+    BufferManager(BufferManagerEvent),
+    /// Event that focuses or closes windows.
+    Window(WindowEvent),
+    /// Event that sets a named mark.
+    Mark(MarkEvent),
+    /// Event that fills the quickfix list or goes to its entries.
+    Quickfix(QuickfixEvent),
+    /// Event that opens or closes folds.
+    Fold(FoldEvent),
+    /// Event that starts or repeats a search.
+    Search(SearchEvent),
+    /// Event that opens or updates the completion popup.
+    Completion(CompletionEvent),
+    /// Event that runs a shell command or delivers its result.
+    Shell(ShellEvent),
+    /// Event reported by a background job, or sent to control one.
+    Job(JobEvent),
+    /// Event that opens or closes the terminal pane, or delivers what its shell prints.
+    Terminal(TerminalEvent),
+    /// Request to the language server.
+    Lsp(LspEvent),
     /// Change focus to a specific UI component.
     ChangeFocus(FocusableComponent),
     /// Switch to a different editor mode (Insert, Normal, etc.).
     ChangeToMode(EditorMode),
+    /// Open the command line for the given kind of input, starting with the given text.
+    OpenCommandLine {
+        kind: CommandLineKind,
+        prefill: String,
+    },
+    /// Run the text submitted from the command line.
+    SubmitCommandLine {
+        kind: CommandLineKind,
+        input: String,
+    },
+    /// Open the prompt asking for a value of the given kind.
+    OpenPrompt(PromptKind),
+    /// Ask a yes/no question, dispatching `on_confirm` on yes and `on_deny` on no.
+    Confirm {
+        message: String,
+        on_confirm: Vec<AppEvent>,
+        on_deny: Vec<AppEvent>,
+    },
+    /// Ask a question in a dialog, dispatching `on_yes` on yes and `on_no` on no;
+    /// cancelling dispatches nothing.
+    Dialog {
+        message: String,
+        on_yes: Vec<AppEvent>,
+        on_no: Vec<AppEvent>,
+    },
+    /// Show long text, like command output, in the message pane.
+    ShowMessage { title: String, text: String },
+    /// Show a diff in the message pane, colored by kind of line.
+    ShowDiff { title: String, lines: Vec<DiffLine> },
+    /// List the undo states of the active buffer in the undo panel.
+    ShowUndoTree,
+    /// Add an autocommand for the session.
+    AddAutocmd(Autocmd),
+    /// Run the user command `name` registered by a script, with `argument`.
+    RunUserCommand { name: String, argument: String },
+    /// Run the action of a clicked gutter sign.
+    RunSignAction(SignAction),
+    /// Handle `event` `count` times, as many as fit in the buffer for cursor moves.
+    Repeat { count: usize, event: Box<AppEvent> },
+    /// Wrap the text between `start` and `end`, both inclusive, in `open` and `close`,
+    /// typed over a visual selection, when `:set autosurround` is on.
+    Surround {
+        open: char,
+        close: char,
+        start: Position,
+        end: Position,
+    },
+    /// Apply `operator` to the text covered by `target` from `position`, repeated `count`
+    /// times.
+    Operate {
+        operator: Operator,
+        target: Target,
+        count: usize,
+        position: Position,
+    },
+    /// Copy the text between `start` and `end`, both inclusive, to the system clipboard.
+    Copy { start: Position, end: Position },
+    /// Insert the indentation of one `Tab` at `position`: a tab, or spaces with
+    /// `expandtab`, as set for the active buffer.
+    InsertTab { position: Position },
+    /// Split the line at `position`, continuing its comment marker or list bullet on the
+    /// new line when `:set autocontinue` is on.
+    InsertNewline { position: Position },
+    /// Put the text of the unnamed register after `position`, whole lines indented like
+    /// its line when `reindent` is set.
+    Put { position: Position, reindent: bool },
+    /// Bind `keys` to `action` in `mode`, for the active buffer only if `buffer` is set.
+    Map {
+        mode: KeymapMode,
+        keys: Vec<KeyEvent>,
+        action: Action,
+        buffer: bool,
+    },
+    /// Apply a `:set` argument to the editor options.
+    SetOption(String),
+    /// Change to `directory`: the working directory of the whole editor, or the local
+    /// directory of the active document when `local` is set.
+    ChangeDirectory { directory: PathBuf, local: bool },
+    /// Suspend the editor, giving the terminal back to the shell until it resumes it.
+    Suspend,
     /// Signal to quit the application.
     Quit,
 }
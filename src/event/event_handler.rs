@@ -1,25 +1,70 @@
 //! Event handling module for the Zack text editor.
 //!
 //! This module provides an `EventHandler` that handles asynchronous input events
-//! from the terminal (via Crossterm) and application-specific events. It emits events
-//! at a fixed frame rate (`TICK_FPS`) and uses a separate thread to poll for input,
-//! enabling responsive and concurrent input handling.
+//! from the terminal (via Crossterm) and application-specific events. It emits ticks
+//! at a rate the application can change at any time, [`DEFAULT_TICK_RATE`] unless set
+//! otherwise, and uses a separate thread to poll for input, enabling responsive and
+//! concurrent input handling.
 //!
 //! Events handled include:
 //! - `Crossterm` input events (keyboard, mouse, resize, etc.)
+//! - `Replayed` input events, played back from a recording
 //! - Application-specific events (`AppEvent`)
-//! - Periodic `Tick` events at 30 FPS
+//! - Periodic `Tick` events, which stop while the rate is zero, as when idle
+//! - `Error` events, when the thread can no longer read the terminal
+//!
+//! After an `Error`, the application can [restart](EventHandler::restart) the thread, as
+//! when the terminal had a hiccup, up to [`MAX_RESTARTS`] times in [`RESTART_WINDOW`];
+//! each new thread waits [`RESTART_DELAY`] before reading again.
+//!
+//! Terminal input goes through an [`InputSender`], which holds it back at its source
+//! while [`QUEUE_CAPACITY`] input events are waiting, so a huge paste or a replayed
+//! recording cannot grow the queue without limit. At most one tick waits at a time, and
+//! a run of identical cursor moves is taken as a single [`AppEvent::Repeat`], so the app
+//! handles the whole run before drawing the next frame. The app can take a run of
+//! identical input events the same way, with [`EventHandler::take_repeats`].
+//!
+//! [`EventHandler::next_timeout`] stops waiting after a while even when nothing comes,
+//! as when ticks are off, so the application keeps doing its periodic work.
+//!
+//! Dropping the `EventHandler` tells the thread to stop and waits for it, so it never
+//! outlives the editor.
 
-use super::app_events::AppEvent;
+use super::app_events::{AppEvent, CursorEvent};
 use color_eyre::eyre::WrapErr;
 use ratatui::crossterm::event::{self, Event as CrosstermEvent};
 use std::{
-    sync::mpsc,
-    thread,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
-const TICK_FPS: f64 = 30.0;
+/// Ticks per second until [`EventHandler::set_tick_rate`] changes it.
+pub const DEFAULT_TICK_RATE: u32 = 30;
+
+/// Longest wait for input before the event thread checks for shutdown and for a new
+/// tick rate.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Most terminal input events waiting in the queue before their producers wait.
+pub const QUEUE_CAPACITY: usize = 1024;
+
+/// Most restarts of the event thread within [`RESTART_WINDOW`] before giving up.
+pub const MAX_RESTARTS: usize = 3;
+
+/// Time over which restarts of the event thread are counted.
+pub const RESTART_WINDOW: Duration = Duration::from_secs(10);
+
+/// Time a restarted event thread waits before reading the terminal again.
+pub const RESTART_DELAY: Duration = Duration::from_millis(200);
+
+/// Time an input producer waits before checking again for room in the queue.
+const BACKPRESSURE_WAIT: Duration = Duration::from_millis(1);
 
 /// Enum representing all types of events handled by the editor.
 #[derive(Clone, Debug)]
@@ -30,8 +75,14 @@ pub enum Event {
     /// A raw input event received from Crossterm (keyboard, mouse, etc.).
     Crossterm(CrosstermEvent),
 
+    /// An input event played back from a recording rather than read from the terminal.
+    Replayed(CrosstermEvent),
+
     /// A high-level application-specific event.
     App(AppEvent),
+
+    /// The event thread failed to read the terminal, or panicked, and stopped.
+    Error(String),
 }
 
 /// Central struct responsible for managing and dispatching events to the application.
@@ -39,6 +90,49 @@ pub enum Event {
 pub struct EventHandler {
     sender: mpsc::Sender<Event>,
     receiver: mpsc::Receiver<Event>,
+    /// Set to ask the event thread to stop.
+    shutdown: Arc<AtomicBool>,
+    /// Ticks per second, shared with the event thread.
+    tick_rate: Arc<AtomicU64>,
+    /// Terminal input events in the queue, shared with the input producers.
+    backlog: Arc<AtomicUsize>,
+    /// Set while a tick is in the queue, shared with the event thread.
+    tick_queued: Arc<AtomicBool>,
+    /// Event taken from the queue while collecting a run of cursor moves.
+    peeked: Option<Event>,
+    /// The event thread, joined on drop; `None` when headless.
+    thread: Option<JoinHandle<()>>,
+    /// When the event thread was restarted within the last [`RESTART_WINDOW`].
+    restarts: Vec<Instant>,
+}
+
+/// Sends terminal input events to the queue, waiting while [`QUEUE_CAPACITY`] of them
+/// are already there.
+#[derive(Clone, Debug)]
+pub struct InputSender {
+    sender: mpsc::Sender<Event>,
+    backlog: Arc<AtomicUsize>,
+}
+
+impl InputSender {
+    /// Queues `event` once there is room for it; returns `false` when the queue is gone.
+    pub fn send(&self, event: CrosstermEvent) -> bool {
+        self.queue(Event::Crossterm(event))
+    }
+
+    /// Queues `event`, played back from a recording, as [`InputSender::send`] does.
+    pub fn send_replayed(&self, event: CrosstermEvent) -> bool {
+        self.queue(Event::Replayed(event))
+    }
+
+    fn queue(&self, event: Event) -> bool {
+        while self.backlog.load(Ordering::Relaxed) >= QUEUE_CAPACITY {
+            thread::sleep(BACKPRESSURE_WAIT);
+        }
+
+        self.backlog.fetch_add(1, Ordering::Relaxed);
+        self.sender.send(event).is_ok()
+    }
 }
 
 impl Default for EventHandler {
@@ -50,53 +144,422 @@ impl Default for EventHandler {
 impl EventHandler {
     /// Creates a new `EventHandler` and spawns a background thread to emit events.
     pub fn new() -> Self {
+        let mut handler = Self::headless();
+        handler.spawn_thread(Duration::ZERO);
+
+        handler
+    }
+
+    /// Spawns the event thread, reading the terminal after `delay`.
+    fn spawn_thread(&mut self, delay: Duration) {
+        let actor = EventThread::new(
+            self.input_sender(),
+            Arc::clone(&self.shutdown),
+            Arc::clone(&self.tick_rate),
+            Arc::clone(&self.tick_queued),
+        );
+        self.thread = Some(thread::spawn(move || {
+            thread::sleep(delay);
+            actor.run_reporting_errors();
+        }));
+    }
+
+    /// Starts a new event thread after the last one stopped with an `Error`. Returns
+    /// `false`, starting nothing, once it was restarted [`MAX_RESTARTS`] times in the last
+    /// [`RESTART_WINDOW`], as the terminal is then unlikely to come back.
+    pub fn restart(&mut self) -> bool {
+        if !self.allow_restart(Instant::now()) {
+            return false;
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        self.spawn_thread(RESTART_DELAY);
+
+        true
+    }
+
+    /// Counts a restart at `now`, unless there were already too many recently.
+    fn allow_restart(&mut self, now: Instant) -> bool {
+        self.restarts
+            .retain(|restart| now.duration_since(*restart) < RESTART_WINDOW);
+        if self.restarts.len() >= MAX_RESTARTS {
+            return false;
+        }
+
+        self.restarts.push(now);
+        true
+    }
+
+    /// Creates an `EventHandler` without the background thread: no terminal input and
+    /// no ticks, only the events sent to it, taken with [`EventHandler::try_next`].
+    pub fn headless() -> Self {
         let (sender, receiver) = mpsc::channel();
-        let actor = EventThread::new(sender.clone());
-        thread::spawn(|| actor.run());
-        Self { sender, receiver }
+
+        Self {
+            sender,
+            receiver,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            tick_rate: Arc::new(AtomicU64::new(DEFAULT_TICK_RATE.into())),
+            backlog: Arc::new(AtomicUsize::new(0)),
+            tick_queued: Arc::new(AtomicBool::new(false)),
+            peeked: None,
+            thread: None,
+            restarts: vec![],
+        }
+    }
+
+    /// Takes the next queued event, if there is one, without waiting.
+    pub fn try_next(&mut self) -> Option<Event> {
+        let event = self.take()?;
+        Some(self.coalesce(event))
     }
 
     /// Receives the next event from the internal queue (blocking).
-    pub fn next(&self) -> color_eyre::Result<Event> {
-        Ok(self.receiver.recv()?)
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> color_eyre::Result<Event> {
+        let event = match self.peeked.take() {
+            Some(event) => event,
+            None => self.received(self.receiver.recv()?),
+        };
+
+        Ok(self.coalesce(event))
+    }
+
+    /// Receives the next event from the internal queue, waiting at most `timeout`.
+    /// Returns `None` when no event came in time, so the caller can do time-based work
+    /// while ticks are off.
+    pub fn next_timeout(&mut self, timeout: Duration) -> color_eyre::Result<Option<Event>> {
+        let event = match self.peeked.take() {
+            Some(event) => event,
+            None => match self.receiver.recv_timeout(timeout) {
+                Ok(event) => self.received(event),
+                Err(mpsc::RecvTimeoutError::Timeout) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            },
+        };
+
+        Ok(Some(self.coalesce(event)))
+    }
+
+    /// Returns a sender that background workers can use to push events into the queue.
+    pub fn sender(&self) -> mpsc::Sender<Event> {
+        self.sender.clone()
+    }
+
+    /// Returns how many terminal input events wait in the queue.
+    pub fn queued_input(&self) -> usize {
+        self.backlog.load(Ordering::Relaxed)
+    }
+
+    /// Returns a sender for terminal input, held back while the queue is full.
+    pub fn input_sender(&self) -> InputSender {
+        InputSender {
+            sender: self.sender.clone(),
+            backlog: Arc::clone(&self.backlog),
+        }
+    }
+
+    /// Makes the event thread tick `rate` times per second from the next tick on, or
+    /// stop ticking for `0`.
+    pub fn set_tick_rate(&self, rate: u32) {
+        self.tick_rate.store(rate.into(), Ordering::Relaxed);
     }
 
     /// Sends a custom `AppEvent` into the event stream.
     pub fn send(&mut self, app_event: AppEvent) {
         let _ = self.sender.send(Event::App(app_event));
     }
+
+    /// Takes the next event without waiting, before any coalescing.
+    fn take(&mut self) -> Option<Event> {
+        match self.peeked.take() {
+            Some(event) => Some(event),
+            None => {
+                let event = self.receiver.try_recv().ok()?;
+                Some(self.received(event))
+            }
+        }
+    }
+
+    /// Counts `event` out of the queue, making room for its producer.
+    fn received(&self, event: Event) -> Event {
+        match event {
+            Event::Crossterm(_) | Event::Replayed(_) => {
+                self.backlog.fetch_sub(1, Ordering::Relaxed);
+            }
+            Event::Tick => self.tick_queued.store(false, Ordering::Relaxed),
+            _ => {}
+        }
+
+        event
+    }
+
+    /// Takes the copies of `input` queued right after it, as when a key is held down, and
+    /// returns how many there were.
+    pub fn take_repeats(&mut self, input: &CrosstermEvent) -> usize {
+        let mut count = 0;
+        while let Some(next) = self.take() {
+            match next {
+                Event::Crossterm(next) if next == *input => count += 1,
+                next => {
+                    self.peeked = Some(next);
+                    break;
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Joins the cursor moves queued right after `event`, when it is the same move, into
+    /// a single repeated move.
+    fn coalesce(&mut self, event: Event) -> Event {
+        let Event::App(AppEvent::Cursor(
+            movement @ (CursorEvent::MoveLeft
+            | CursorEvent::MoveRight
+            | CursorEvent::MoveUp
+            | CursorEvent::MoveDown),
+        )) = &event
+        else {
+            return event;
+        };
+        let movement = movement.clone();
+
+        let mut count = 1;
+        while let Some(next) = self.take() {
+            match next {
+                Event::App(AppEvent::Cursor(next)) if next == movement => count += 1,
+                next => {
+                    self.peeked = Some(next);
+                    break;
+                }
+            }
+        }
+
+        match count {
+            1 => event,
+            count => Event::App(AppEvent::Repeat {
+                count,
+                event: Box::new(AppEvent::Cursor(movement)),
+            }),
+        }
+    }
+}
+
+impl Drop for EventHandler {
+    /// Stops the event thread and waits for it, which takes at most one poll interval.
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // Nothing takes events anymore: let a waiting producer through to see it stop.
+        self.backlog.store(0, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
 }
 
 /// Background actor responsible for polling terminal events and sending periodic ticks.
 struct EventThread {
-    sender: mpsc::Sender<Event>,
+    sender: InputSender,
+    shutdown: Arc<AtomicBool>,
+    tick_rate: Arc<AtomicU64>,
+    tick_queued: Arc<AtomicBool>,
 }
 
 impl EventThread {
-    /// Creates a new `EventThread` with the given sender.
-    fn new(sender: mpsc::Sender<Event>) -> Self {
-        Self { sender }
+    /// Creates a new `EventThread` with the given sender, ticking `tick_rate` times per
+    /// second unless `tick_queued` tells a tick is still waiting, and stopping once
+    /// `shutdown` is set.
+    fn new(
+        sender: InputSender,
+        shutdown: Arc<AtomicBool>,
+        tick_rate: Arc<AtomicU64>,
+        tick_queued: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            sender,
+            shutdown,
+            tick_rate,
+            tick_queued,
+        }
+    }
+
+    /// Runs the event loop until shutdown, sending an `Error` event if it fails or
+    /// panics, so the main loop does not wait forever for input that will not come.
+    fn run_reporting_errors(self) {
+        let sender = self.sender.sender.clone();
+        let message = match panic::catch_unwind(AssertUnwindSafe(|| self.run())) {
+            Ok(Ok(())) => return,
+            Ok(Err(err)) => format!("{err:#}"),
+            Err(_) => String::from("the event thread panicked"),
+        };
+
+        let _ = sender.send(Event::Error(message));
     }
 
     /// Runs the event loop, emitting `Tick` events and handling Crossterm input.
     fn run(self) -> color_eyre::Result<()> {
-        let tick_interval = Duration::from_secs_f64(1.0 / TICK_FPS);
         let mut last_tick = Instant::now();
-        loop {
-            let timeout = tick_interval.saturating_sub(last_tick.elapsed());
-            if timeout == Duration::ZERO {
-                last_tick = Instant::now();
-                self.send(Event::Tick);
-            }
-            if event::poll(timeout).wrap_err("failed to poll for crossterm events")? {
+        while !self.shutdown.load(Ordering::Relaxed) {
+            let timeout = match self.tick_interval() {
+                Some(interval) => {
+                    let timeout = interval.saturating_sub(last_tick.elapsed());
+                    if timeout == Duration::ZERO {
+                        last_tick = Instant::now();
+                        self.send_tick();
+                    }
+                    timeout
+                }
+                None => POLL_INTERVAL,
+            };
+            if event::poll(timeout.min(POLL_INTERVAL))
+                .wrap_err("failed to poll for crossterm events")?
+            {
                 let event = event::read().wrap_err("failed to read crossterm event")?;
-                self.send(Event::Crossterm(event));
+                self.sender.send(event);
             }
         }
+
+        Ok(())
+    }
+
+    /// Returns the time between ticks, or `None` while not ticking.
+    fn tick_interval(&self) -> Option<Duration> {
+        match self.tick_rate.load(Ordering::Relaxed) {
+            0 => None,
+            rate => Some(Duration::from_secs_f64(1.0 / rate as f64)),
+        }
     }
 
-    /// Sends an event through the channel.
-    fn send(&self, event: Event) {
-        let _ = self.sender.send(event);
+    /// Sends a tick through the channel, unless the last one is still waiting.
+    fn send_tick(&self) {
+        if !self.tick_queued.swap(true, Ordering::Relaxed) {
+            let _ = self.sender.sender.send(Event::Tick);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::KeyCode;
+
+    #[test]
+    fn should_stop_the_event_thread_on_shutdown() {
+        let mut handler = EventHandler::headless();
+        let actor = EventThread::new(
+            handler.input_sender(),
+            Arc::new(AtomicBool::new(true)),
+            Arc::new(AtomicU64::new(DEFAULT_TICK_RATE.into())),
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        actor.run_reporting_errors();
+
+        assert!(handler.try_next().is_none());
+    }
+
+    #[test]
+    fn should_join_a_run_of_identical_cursor_moves() {
+        let mut handler = EventHandler::headless();
+        for event in [
+            CursorEvent::MoveDown,
+            CursorEvent::MoveDown,
+            CursorEvent::MoveDown,
+            CursorEvent::MoveLeft,
+        ] {
+            handler.send(AppEvent::Cursor(event));
+        }
+
+        assert!(matches!(
+            handler.try_next(),
+            Some(Event::App(AppEvent::Repeat { count: 3, .. }))
+        ));
+        assert!(matches!(
+            handler.try_next(),
+            Some(Event::App(AppEvent::Cursor(CursorEvent::MoveLeft)))
+        ));
+        assert!(handler.try_next().is_none());
+    }
+
+    #[test]
+    fn should_take_the_repeats_of_an_input_event() {
+        let mut handler = EventHandler::headless();
+        let input = handler.input_sender();
+        let down = CrosstermEvent::Key(KeyCode::Down.into());
+        for event in [
+            down.clone(),
+            down.clone(),
+            CrosstermEvent::FocusLost,
+            down.clone(),
+        ] {
+            input.send(event);
+        }
+
+        assert_eq!(handler.take_repeats(&down), 2);
+        assert_eq!(handler.backlog.load(Ordering::Relaxed), 1);
+        assert!(matches!(
+            handler.try_next(),
+            Some(Event::Crossterm(CrosstermEvent::FocusLost))
+        ));
+    }
+
+    #[test]
+    fn should_hold_input_back_while_the_queue_is_full() {
+        let mut handler = EventHandler::headless();
+        let input = handler.input_sender();
+        let producer = thread::spawn(move || {
+            for _ in 0..=QUEUE_CAPACITY {
+                input.send(CrosstermEvent::FocusGained);
+            }
+        });
+
+        thread::sleep(Duration::from_millis(50));
+        assert!(!producer.is_finished());
+        assert_eq!(handler.backlog.load(Ordering::Relaxed), QUEUE_CAPACITY);
+
+        handler.try_next();
+        producer.join().unwrap();
+        assert_eq!(handler.backlog.load(Ordering::Relaxed), QUEUE_CAPACITY);
+    }
+
+    #[test]
+    fn should_limit_the_restarts_of_the_event_thread() {
+        let mut handler = EventHandler::headless();
+        let now = Instant::now();
+
+        for _ in 0..MAX_RESTARTS {
+            assert!(handler.allow_restart(now));
+        }
+        assert!(!handler.allow_restart(now + Duration::from_secs(1)));
+        assert!(handler.allow_restart(now + RESTART_WINDOW));
+    }
+
+    #[test]
+    fn should_stop_waiting_for_events_after_the_timeout() {
+        let mut handler = EventHandler::headless();
+        let timeout = Duration::from_millis(10);
+
+        assert!(handler.next_timeout(timeout).unwrap().is_none());
+        handler.send(AppEvent::Quit);
+        assert!(matches!(
+            handler.next_timeout(timeout).unwrap(),
+            Some(Event::App(AppEvent::Quit))
+        ));
+    }
+
+    #[test]
+    fn should_drop_a_headless_handler_without_a_thread() {
+        let mut handler = EventHandler::headless();
+        handler.send(AppEvent::Quit);
+
+        assert!(matches!(
+            handler.try_next(),
+            Some(Event::App(AppEvent::Quit))
+        ));
+        drop(handler);
     }
 }
@@ -3,3 +3,4 @@ pub use self::event_handler::*;
 
 mod app_events;
 mod event_handler;
+pub mod recording;
@@ -0,0 +1,348 @@
+//! Event recording and replay for the Zack text editor.
+//!
+//! Started with `--record events.json`, the editor writes every event it handles to a
+//! file, one JSON object per line, with the milliseconds since the start: terminal input
+//! as `{"ms": 120, "input": {"key": {...}}}`, and application events as
+//! `{"ms": 121, "app": "..."}`, their debug text, to follow what the input led to, and
+//! failures of the event thread as `{"ms": 122, "error": "..."}`. Each line is flushed as
+//! it is written, so a crash keeps the events leading to it.
+//!
+//! Started with `--replay events.json`, the editor feeds the recorded input back through
+//! the event loop, at the recorded pace, to reproduce a session. Application events and
+//! ticks are not replayed: the input makes them again. Tests can replay a recording
+//! without waiting with [`Driver::replay`](crate::app::driver::Driver::replay).
+
+use super::{Event, InputSender};
+use crossterm::event::{
+    Event as CrosstermEvent, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers,
+    MouseButton, MouseEvent, MouseEventKind,
+};
+use serde_json::{Value, json};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Keys with a name, as written in recordings; other keys are characters or `F1`..`F12`.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Backspace", KeyCode::Backspace),
+    ("Enter", KeyCode::Enter),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Esc", KeyCode::Esc),
+    ("Null", KeyCode::Null),
+];
+
+/// Mouse buttons, as written in recordings.
+const BUTTONS: &[(&str, MouseButton)] = &[
+    ("left", MouseButton::Left),
+    ("right", MouseButton::Right),
+    ("middle", MouseButton::Middle),
+];
+
+/// Writes the events handled by the editor to a recording.
+#[derive(Debug)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+/// A terminal event read from a recording, with when it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recorded {
+    /// Time since the start of the recording.
+    pub at: Duration,
+    pub event: CrosstermEvent,
+}
+
+impl Recorder {
+    /// Creates the recording at `path`, replacing any file there.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            started: Instant::now(),
+        })
+    }
+
+    /// Writes `event` to the recording. Ticks are left out.
+    pub fn record(&mut self, event: &Event) -> io::Result<()> {
+        let ms = self.started.elapsed().as_millis() as u64;
+        let line = match event {
+            Event::Tick => return Ok(()),
+            Event::Crossterm(event) | Event::Replayed(event) => match encode(event) {
+                Some(input) => json!({ "ms": ms, "input": input }),
+                None => return Ok(()),
+            },
+            Event::App(event) => json!({ "ms": ms, "app": format!("{event:?}") }),
+            Event::Error(message) => json!({ "ms": ms, "error": message }),
+        };
+
+        writeln!(self.writer, "{line}")?;
+        self.writer.flush()
+    }
+}
+
+/// Reads the terminal events of the recording at `path`, skipping application events.
+pub fn load(path: &Path) -> io::Result<Vec<Recorded>> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+/// Reads the terminal events of a recording.
+pub fn parse(text: &str) -> io::Result<Vec<Recorded>> {
+    let invalid = |number: usize| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid event on line {}", number + 1),
+        )
+    };
+    let mut events = vec![];
+
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let value: Value = serde_json::from_str(line).map_err(|_| invalid(number))?;
+        let Some(input) = value.get("input") else {
+            continue;
+        };
+        let ms = value["ms"].as_u64().ok_or_else(|| invalid(number))?;
+        let event = decode(input).ok_or_else(|| invalid(number))?;
+
+        events.push(Recorded {
+            at: Duration::from_millis(ms),
+            event,
+        });
+    }
+
+    Ok(events)
+}
+
+/// Sends `events` through `sender` from a background thread, each one when as much time
+/// has passed as when it was recorded.
+pub fn replay(events: Vec<Recorded>, sender: InputSender) {
+    thread::spawn(move || {
+        let started = Instant::now();
+
+        for recorded in events {
+            thread::sleep(recorded.at.saturating_sub(started.elapsed()));
+            if !sender.send_replayed(recorded.event) {
+                break;
+            }
+        }
+    });
+}
+
+/// Writes a terminal event as JSON, or returns `None` for keys recordings do not know.
+pub fn encode(event: &CrosstermEvent) -> Option<Value> {
+    Some(match event {
+        CrosstermEvent::Key(key) => json!({ "key": {
+            "code": key_name(key.code)?,
+            "modifiers": key.modifiers.bits(),
+            "kind": match key.kind {
+                KeyEventKind::Press => "press",
+                KeyEventKind::Repeat => "repeat",
+                KeyEventKind::Release => "release",
+            },
+            "state": key.state.bits(),
+        }}),
+        CrosstermEvent::Mouse(mouse) => {
+            let (kind, button) = match mouse.kind {
+                MouseEventKind::Down(button) => ("down", Some(button)),
+                MouseEventKind::Up(button) => ("up", Some(button)),
+                MouseEventKind::Drag(button) => ("drag", Some(button)),
+                MouseEventKind::Moved => ("moved", None),
+                MouseEventKind::ScrollDown => ("scroll_down", None),
+                MouseEventKind::ScrollUp => ("scroll_up", None),
+                MouseEventKind::ScrollLeft => ("scroll_left", None),
+                MouseEventKind::ScrollRight => ("scroll_right", None),
+            };
+            let button = button.and_then(|button| {
+                BUTTONS
+                    .iter()
+                    .find(|(_, known)| *known == button)
+                    .map(|(name, _)| *name)
+            });
+
+            json!({ "mouse": {
+                "kind": kind,
+                "button": button,
+                "column": mouse.column,
+                "row": mouse.row,
+                "modifiers": mouse.modifiers.bits(),
+            }})
+        }
+        CrosstermEvent::Paste(text) => json!({ "paste": text }),
+        CrosstermEvent::Resize(width, height) => json!({ "resize": [width, height] }),
+        CrosstermEvent::FocusGained => json!("focus_gained"),
+        CrosstermEvent::FocusLost => json!("focus_lost"),
+    })
+}
+
+/// Reads a terminal event written by [`encode`].
+pub fn decode(value: &Value) -> Option<CrosstermEvent> {
+    match value.as_str() {
+        Some("focus_gained") => return Some(CrosstermEvent::FocusGained),
+        Some("focus_lost") => return Some(CrosstermEvent::FocusLost),
+        _ => {}
+    }
+
+    if let Some(key) = value.get("key") {
+        let kind = match key["kind"].as_str()? {
+            "press" => KeyEventKind::Press,
+            "repeat" => KeyEventKind::Repeat,
+            "release" => KeyEventKind::Release,
+            _ => return None,
+        };
+
+        return Some(CrosstermEvent::Key(KeyEvent {
+            code: key_code(key["code"].as_str()?)?,
+            modifiers: KeyModifiers::from_bits(u8::try_from(key["modifiers"].as_u64()?).ok()?)?,
+            kind,
+            state: KeyEventState::from_bits(u8::try_from(key["state"].as_u64()?).ok()?)?,
+        }));
+    }
+
+    if let Some(mouse) = value.get("mouse") {
+        let button = mouse["button"].as_str().and_then(|name| {
+            BUTTONS
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, button)| *button)
+        });
+        let kind = match (mouse["kind"].as_str()?, button) {
+            ("down", Some(button)) => MouseEventKind::Down(button),
+            ("up", Some(button)) => MouseEventKind::Up(button),
+            ("drag", Some(button)) => MouseEventKind::Drag(button),
+            ("moved", _) => MouseEventKind::Moved,
+            ("scroll_down", _) => MouseEventKind::ScrollDown,
+            ("scroll_up", _) => MouseEventKind::ScrollUp,
+            ("scroll_left", _) => MouseEventKind::ScrollLeft,
+            ("scroll_right", _) => MouseEventKind::ScrollRight,
+            _ => return None,
+        };
+
+        return Some(CrosstermEvent::Mouse(MouseEvent {
+            kind,
+            column: u16::try_from(mouse["column"].as_u64()?).ok()?,
+            row: u16::try_from(mouse["row"].as_u64()?).ok()?,
+            modifiers: KeyModifiers::from_bits(u8::try_from(mouse["modifiers"].as_u64()?).ok()?)?,
+        }));
+    }
+
+    if let Some(text) = value.get("paste") {
+        return Some(CrosstermEvent::Paste(text.as_str()?.to_string()));
+    }
+
+    let size = value.get("resize")?;
+    Some(CrosstermEvent::Resize(
+        u16::try_from(size[0].as_u64()?).ok()?,
+        u16::try_from(size[1].as_u64()?).ok()?,
+    ))
+}
+
+/// Returns how recordings write `code`: its name, the character itself, or `F1`..`F12`.
+fn key_name(code: KeyCode) -> Option<String> {
+    match code {
+        KeyCode::Char(c) => Some(c.to_string()),
+        KeyCode::F(n) => Some(format!("F{n}")),
+        _ => NAMED_KEYS
+            .iter()
+            .find(|(_, known)| *known == code)
+            .map(|(name, _)| name.to_string()),
+    }
+}
+
+/// Reads a key written by [`key_name`].
+fn key_code(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    if let Some((_, code)) = NAMED_KEYS.iter().find(|(known, _)| *known == name) {
+        return Some(*code);
+    }
+
+    name.strip_prefix('F')?.parse().ok().map(KeyCode::F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_write_and_read_back_terminal_events() {
+        let events = [
+            CrosstermEvent::Key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL)),
+            CrosstermEvent::Key(KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE)),
+            CrosstermEvent::Key(KeyEvent::new_with_kind(
+                KeyCode::Esc,
+                KeyModifiers::NONE,
+                KeyEventKind::Release,
+            )),
+            CrosstermEvent::Mouse(MouseEvent {
+                kind: MouseEventKind::Drag(MouseButton::Left),
+                column: 4,
+                row: 2,
+                modifiers: KeyModifiers::SHIFT,
+            }),
+            CrosstermEvent::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollUp,
+                column: 0,
+                row: 0,
+                modifiers: KeyModifiers::NONE,
+            }),
+            CrosstermEvent::Paste(String::from("pasted\ntext")),
+            CrosstermEvent::Resize(80, 24),
+            CrosstermEvent::FocusLost,
+        ];
+
+        for event in events {
+            assert_eq!(decode(&encode(&event).unwrap()), Some(event));
+        }
+    }
+
+    #[test]
+    fn should_record_events_and_load_the_input() {
+        let path = std::env::temp_dir().join("test_recording.json");
+        let key = CrosstermEvent::Key(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE));
+
+        let mut recorder = Recorder::create(&path).unwrap();
+        recorder.record(&Event::Tick).unwrap();
+        recorder.record(&Event::Crossterm(key.clone())).unwrap();
+        recorder
+            .record(&Event::App(crate::event::AppEvent::Quit))
+            .unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let loaded = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.contains(r#""app":"Quit""#));
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].event, key);
+    }
+
+    #[test]
+    fn should_reject_malformed_lines() {
+        let err = parse("{\"ms\": 1, \"input\": \"focus_lost\"}\n{\"ms\": 2, \"input\": {}}")
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "invalid event on line 2");
+    }
+}
@@ -0,0 +1,22 @@
+//! Zack, a TUI text editor.
+//!
+//! The editor is a library with a thin binary on top: `main.rs` parses the arguments,
+//! sets the terminal up and runs an [`App`]. Everything else lives here, so the editing
+//! engine can be embedded, fuzzed and benchmarked without a terminal:
+//!
+//! - [`app`]: the editor state, with buffers, cursors, modes, commands and options.
+//! - [`event`]: the events driving it and the queue they go through.
+//! - [`lsp`]: the language server client.
+//! - [`types`]: small shared types, like positions.
+//! - [`ui`]: the components drawing the editor with ratatui.
+//!
+//! [`app::driver::Driver`] runs an [`App`] synchronously on an in-memory screen, feeding
+//! it keys and events, for tests and benchmarks.
+
+pub mod app;
+pub mod event;
+pub mod lsp;
+pub mod types;
+pub mod ui;
+
+pub use app::App;
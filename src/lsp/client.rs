@@ -0,0 +1,360 @@
+//! Language server client for the Zack text editor.
+//!
+//! This module provides the [`LspClient`] struct, which spawns a language server for the
+//! opened file and talks to it over stdio without ever blocking the UI thread:
+//!
+//! - A writer thread performs the `initialize` handshake and then forwards queued
+//!   messages to the server's stdin.
+//! - A reader thread parses the server's responses and notifications (like published
+//!   diagnostics) and sends the results back to the application as [`AppEvent`]s through
+//!   the [`EventHandler`](crate::event::EventHandler) channel.
+//!
+//! The document is synced before each request. Servers that accept changed ranges get
+//! the changes logged in the buffer's [`ChangeLog`](crate::app::changes::ChangeLog)
+//! since the last sync; others, or a client too far behind the log, get the whole text.
+//!
+//! Dropping the client asks the server to shut down and exit, and stops it if it has
+//! not after [`SHUTDOWN_GRACE`], reaping the process in the background.
+//!
+//! It also defines [`LspEvent`], the requests the rest of the editor can make.
+
+use super::protocol;
+use crate::{
+    app::{buffer::Buffer, filetype::FileType},
+    event::{AppEvent, BufferManagerEvent, CompletionEvent, Event},
+    types::position::Position,
+};
+use serde_json::Value;
+use std::{
+    io::{BufReader, BufWriter},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long a server is given to exit on its own once asked to, before it is killed.
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+/// Language servers started by default for file types: `(file type, command)`. The name
+/// of the file type is the language id sent to the server.
+const SERVERS: &[(&str, &[&str])] = &[
+    ("rust", &["rust-analyzer"]),
+    ("python", &["pylsp"]),
+    ("go", &["gopls"]),
+    ("c", &["clangd"]),
+    ("cpp", &["clangd"]),
+    ("javascript", &["typescript-language-server", "--stdio"]),
+    ("typescript", &["typescript-language-server", "--stdio"]),
+];
+
+/// Requests that can be made to the language server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LspEvent {
+    /// Requests completion items at `position`.
+    ///
+    /// The response is delivered later as a [`CompletionEvent::LspItems`].
+    Completion { request_id: u64, position: Position },
+}
+
+/// A running language server attached to the current file.
+#[derive(Debug)]
+pub struct LspClient {
+    /// URI of the document open in the server.
+    uri: String,
+    /// Version of the document last sent to the server.
+    version: i64,
+    /// Count of the buffer changes the server's copy of the document is up to date with.
+    synced: u64,
+    /// Set by the reader thread when the server takes changed ranges.
+    incremental: Arc<AtomicBool>,
+    /// Queue of messages forwarded to the server by the writer thread.
+    outgoing: mpsc::Sender<Value>,
+    /// The server process, taken when the client is dropped to reap it.
+    child: Option<Child>,
+}
+
+impl LspClient {
+    /// Starts the language server of `filetype` for the file at `path`, whose text is in
+    /// `buffer`: `command`, or else the default one of the file type.
+    ///
+    /// Returns `None` when the file type has no server or the server cannot be spawned,
+    /// in which case the editor simply runs without language features.
+    pub fn start(
+        path: &Path,
+        buffer: &Buffer,
+        filetype: FileType,
+        command: Option<&[String]>,
+        sender: mpsc::Sender<Event>,
+    ) -> Option<Self> {
+        let language_id = filetype.name();
+        let command: Vec<&str> = match command {
+            Some(command) => command.iter().map(String::as_str).collect(),
+            None => SERVERS
+                .iter()
+                .find(|(name, _)| *name == language_id)?
+                .1
+                .to_vec(),
+        };
+        let (program, arguments) = command.split_first()?;
+
+        let mut child = Command::new(program)
+            .args(arguments)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .inspect_err(|err| tracing::debug!("Language server {program} unavailable: {err}"))
+            .ok()?;
+
+        let stdin = child.stdin.take()?;
+        let stdout = child.stdout.take()?;
+
+        let root = std::env::current_dir().ok()?;
+        let uri = file_uri(&std::path::absolute(path).ok()?);
+
+        let (outgoing, outgoing_rx) = mpsc::channel();
+        let (ready, ready_rx) = mpsc::channel();
+        let incremental = Arc::new(AtomicBool::new(false));
+
+        thread::spawn({
+            let root_uri = file_uri(&root);
+            move || write_loop(stdin, &root_uri, ready_rx, outgoing_rx)
+        });
+        thread::spawn({
+            let outgoing = outgoing.clone();
+            let incremental = incremental.clone();
+            move || read_loop(stdout, ready, incremental, outgoing, sender)
+        });
+
+        let text = buffer.as_rope().to_string();
+        let _ = outgoing.send(protocol::did_open(&uri, language_id, &text));
+
+        Some(Self {
+            uri,
+            version: 0,
+            synced: buffer.changes().count(),
+            incremental,
+            outgoing,
+            child: Some(child),
+        })
+    }
+
+    /// Handles an `LspEvent` and returns any follow-up `AppEvent`s.
+    pub fn handle_event(&mut self, event: LspEvent, buffer: &Buffer) -> Vec<AppEvent> {
+        match event {
+            LspEvent::Completion {
+                request_id,
+                position,
+            } => self.request_completion(request_id, position, buffer),
+        }
+
+        vec![]
+    }
+
+    /// Syncs the document and queues a completion request.
+    fn request_completion(&mut self, request_id: u64, position: Position, buffer: &Buffer) {
+        self.sync(buffer);
+        let line = buffer.as_rope().line(position.line).to_string();
+
+        let _ = self.outgoing.send(protocol::completion(
+            request_id,
+            &self.uri,
+            position.line,
+            protocol::utf16_col(&line, position.col),
+        ));
+    }
+
+    /// Sends the server the changes made to `buffer` since the last sync, or its whole
+    /// text. Nothing is sent when it is up to date.
+    fn sync(&mut self, buffer: &Buffer) {
+        let log = buffer.changes();
+        if log.count() == self.synced {
+            return;
+        }
+
+        self.version += 1;
+        let message = match log
+            .since(self.synced)
+            .filter(|_| self.incremental.load(Ordering::Relaxed))
+        {
+            Some(changes) => protocol::did_change_ranges(&self.uri, self.version, changes),
+            None => protocol::did_change(&self.uri, self.version, &buffer.as_rope().to_string()),
+        };
+        self.synced = log.count();
+
+        let _ = self.outgoing.send(message);
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        let _ = self.outgoing.send(protocol::shutdown());
+        let _ = self.outgoing.send(protocol::exit());
+
+        if let Some(child) = self.child.take() {
+            thread::spawn(move || reap(child));
+        }
+    }
+}
+
+/// Waits up to [`SHUTDOWN_GRACE`] for the server to exit, then kills it, and waits for
+/// it so it does not linger as a zombie.
+fn reap(mut child: Child) {
+    let deadline = Instant::now() + SHUTDOWN_GRACE;
+
+    while Instant::now() < deadline {
+        match child.try_wait() {
+            Ok(Some(_)) => return,
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(_) => break,
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Performs the handshake, then forwards queued messages until the queue or pipe closes.
+fn write_loop(
+    stdin: ChildStdin,
+    root_uri: &str,
+    ready: mpsc::Receiver<()>,
+    outgoing: mpsc::Receiver<Value>,
+) {
+    let mut writer = BufWriter::new(stdin);
+
+    if protocol::write_message(&mut writer, &protocol::initialize(root_uri)).is_err()
+        || ready.recv().is_err()
+        || protocol::write_message(&mut writer, &protocol::initialized()).is_err()
+    {
+        return;
+    }
+
+    for message in outgoing {
+        if protocol::write_message(&mut writer, &message).is_err() {
+            return;
+        }
+    }
+}
+
+/// Dispatches server messages until the server exits.
+fn read_loop(
+    stdout: ChildStdout,
+    ready: mpsc::Sender<()>,
+    incremental: Arc<AtomicBool>,
+    outgoing: mpsc::Sender<Value>,
+    sender: mpsc::Sender<Event>,
+) {
+    let mut reader = BufReader::new(stdout);
+
+    while let Ok(Some(message)) = protocol::read_message(&mut reader) {
+        let id = message.get("id");
+        let method = message.get("method").and_then(Value::as_str);
+
+        if method == Some(protocol::PUBLISH_DIAGNOSTICS) {
+            let params = message.get("params").cloned().unwrap_or_default();
+            let path = params
+                .get("uri")
+                .and_then(Value::as_str)
+                .and_then(protocol::uri_to_path);
+
+            if let Some(path) = path {
+                let event = BufferManagerEvent::PublishDiagnostics {
+                    path,
+                    diagnostics: protocol::diagnostics(&params),
+                };
+
+                if sender
+                    .send(Event::App(AppEvent::BufferManager(event)))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            continue;
+        }
+
+        if method.is_some() {
+            // Requests from the server (e.g. progress tokens) only need an acknowledgement.
+            if let Some(id) = id {
+                let _ = outgoing.send(protocol::empty_response(id));
+            }
+            continue;
+        }
+
+        match id.and_then(Value::as_u64) {
+            Some(protocol::INITIALIZE_ID) => {
+                incremental.store(protocol::incremental_sync(&message), Ordering::Relaxed);
+                let _ = ready.send(());
+            }
+            Some(request_id) => {
+                let items = message
+                    .get("result")
+                    .map(protocol::completion_items)
+                    .unwrap_or_default();
+                let event = CompletionEvent::LspItems { request_id, items };
+
+                if sender
+                    .send(Event::App(AppEvent::Completion(event)))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// Builds a `file://` URI for an absolute path, percent-encoding every byte outside the
+/// unreserved characters of RFC 3986 except the `/` separating its components.
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(*byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_percent_encode_reserved_and_non_ascii_bytes_in_file_uris() {
+        let path = Path::new("/tmp/a b#1?x=100%/é-_.~.rs");
+
+        let uri = file_uri(path);
+
+        assert_eq!(uri, "file:///tmp/a%20b%231%3Fx%3D100%25/%C3%A9-_.~.rs");
+        assert_eq!(protocol::uri_to_path(&uri).as_deref(), Some(path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_kill_and_reap_servers_that_do_not_exit() {
+        let child = Command::new("sleep").arg("10").spawn().unwrap();
+        let pid = child.id() as libc::pid_t;
+        let started = Instant::now();
+
+        reap(child);
+
+        // The process was waited for, so no zombie is left with its pid.
+        // SAFETY: signal 0 only checks that the process exists.
+        let exists = unsafe { libc::kill(pid, 0) } == 0;
+        assert!(!exists);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,4 @@
+pub use self::client::*;
+
+mod client;
+pub mod protocol;
@@ -0,0 +1,408 @@
+//! Language Server Protocol message helpers.
+//!
+//! This module builds the JSON-RPC messages the editor sends to a language server and
+//! implements the `Content-Length` framing used on the server's stdio. It only covers
+//! the small subset of the protocol the editor uses: initialization and shutdown,
+//! document synchronization by whole text or changed ranges, completion and published
+//! diagnostics.
+
+use crate::{
+    app::{
+        changes::Change,
+        diagnostics::{Diagnostic, Severity},
+        paste,
+    },
+    types::position::Position,
+};
+use serde_json::{Value, json};
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+/// Method of the notification carrying a document's diagnostics.
+pub const PUBLISH_DIAGNOSTICS: &str = "textDocument/publishDiagnostics";
+
+/// Identifier of the `initialize` request; completion requests use ids starting at 1.
+pub const INITIALIZE_ID: u64 = 0;
+
+/// Identifier of the `shutdown` request, a string so its response is not taken for
+/// completion items.
+pub const SHUTDOWN_ID: &str = "shutdown";
+
+/// Writes `message` to `writer` with its `Content-Length` header.
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+/// Reads the next framed message from `reader`.
+///
+/// Returns `Ok(None)` when the server closed its output.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = content_length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Builds the `initialize` request.
+pub fn initialize(root_uri: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": INITIALIZE_ID,
+        "method": "initialize",
+        "params": {
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "capabilities": {
+                "textDocument": {
+                    "completion": { "completionItem": { "snippetSupport": false } }
+                }
+            }
+        }
+    })
+}
+
+/// Builds the `initialized` notification.
+pub fn initialized() -> Value {
+    json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} })
+}
+
+/// Builds the `shutdown` request, asking the server to get ready to exit.
+pub fn shutdown() -> Value {
+    json!({ "jsonrpc": "2.0", "id": SHUTDOWN_ID, "method": "shutdown" })
+}
+
+/// Builds the `exit` notification, asking the server to exit.
+pub fn exit() -> Value {
+    json!({ "jsonrpc": "2.0", "method": "exit" })
+}
+
+/// Builds a `textDocument/didOpen` notification.
+pub fn did_open(uri: &str, language_id: &str, text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": uri, "languageId": language_id, "version": 0, "text": text }
+        }
+    })
+}
+
+/// Builds a `textDocument/didChange` notification replacing the whole document.
+pub fn did_change(uri: &str, version: i64, text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": uri, "version": version },
+            "contentChanges": [{ "text": text }]
+        }
+    })
+}
+
+/// Builds a `textDocument/didChange` notification sending only `changes`, oldest first:
+/// the range each one replaced, with its new text.
+pub fn did_change_ranges<'a>(
+    uri: &str,
+    version: i64,
+    changes: impl Iterator<Item = &'a Change>,
+) -> Value {
+    let content_changes: Vec<Value> = changes
+        .map(|change| {
+            let utf16_len = |text: &str| text.encode_utf16().count();
+            let (end_line, end_character) = match change.old_text.rsplit_once('\n') {
+                Some((before, last)) => (
+                    change.start.line + before.matches('\n').count() + 1,
+                    utf16_len(last),
+                ),
+                None => (
+                    change.start.line,
+                    change.start_utf16 + utf16_len(&change.old_text),
+                ),
+            };
+
+            json!({
+                "range": {
+                    "start": { "line": change.start.line, "character": change.start_utf16 },
+                    "end": { "line": end_line, "character": end_character }
+                },
+                "text": change.new_text
+            })
+        })
+        .collect();
+
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": uri, "version": version },
+            "contentChanges": content_changes
+        }
+    })
+}
+
+/// Returns `true` when the `initialize` response of a server says it takes the changed
+/// ranges of a document rather than its whole text.
+pub fn incremental_sync(response: &Value) -> bool {
+    let sync = response.pointer("/result/capabilities/textDocumentSync");
+    let kind = sync.and_then(|sync| sync.get("change")).or(sync);
+
+    kind.and_then(Value::as_u64) == Some(2)
+}
+
+/// Builds a `textDocument/completion` request.
+///
+/// `character` must be expressed in UTF-16 code units, see [`utf16_col`].
+pub fn completion(id: u64, uri: &str, line: usize, character: usize) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "textDocument/completion",
+        "params": {
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character }
+        }
+    })
+}
+
+/// Builds an empty successful response to a request sent by the server.
+pub fn empty_response(id: &Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": null })
+}
+
+/// Extracts the text to insert for each item of a completion response.
+///
+/// Accepts both a bare `CompletionItem[]` and a `CompletionList`, preferring each item's
+/// `textEdit.newText`, then `insertText`, then `label`.
+pub fn completion_items(result: &Value) -> Vec<String> {
+    let items = match result {
+        Value::Array(items) => items,
+        Value::Object(list) => match list.get("items") {
+            Some(Value::Array(items)) => items,
+            _ => return vec![],
+        },
+        _ => return vec![],
+    };
+
+    items
+        .iter()
+        .filter_map(|item| {
+            item.pointer("/textEdit/newText")
+                .or_else(|| item.get("insertText"))
+                .or_else(|| item.get("label"))
+                .and_then(Value::as_str)
+                .map(String::from)
+        })
+        .collect()
+}
+
+/// Extracts the diagnostics of a `publishDiagnostics` notification.
+///
+/// Columns are left in UTF-16 code units; convert them with [`char_col`] once the
+/// line text is known.
+pub fn diagnostics(params: &Value) -> Vec<Diagnostic> {
+    let Some(Value::Array(items)) = params.get("diagnostics") else {
+        return vec![];
+    };
+
+    let position = |value: Option<&Value>| {
+        let line = value?.get("line")?.as_u64()? as usize;
+        let character = value?.get("character")?.as_u64()? as usize;
+        Some(Position::new(line, character))
+    };
+
+    items
+        .iter()
+        .filter_map(|item| {
+            Some(Diagnostic {
+                start: position(item.pointer("/range/start"))?,
+                end: position(item.pointer("/range/end"))?,
+                severity: Severity::from_lsp(
+                    item.get("severity").and_then(Value::as_u64).unwrap_or(1),
+                ),
+                message: item.get("message")?.as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Converts a `file://` URI back into a path, decoding its `%XX` sequences.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    uri.strip_prefix("file://")
+        .and_then(paste::percent_decode)
+        .map(PathBuf::from)
+}
+
+/// Converts a column counted in chars into UTF-16 code units, as LSP positions expect.
+pub fn utf16_col(line: &str, col: usize) -> usize {
+    line.chars().take(col).map(char::len_utf16).sum()
+}
+
+/// Converts a column counted in UTF-16 code units back into chars.
+pub fn char_col(line: &str, utf16: usize) -> usize {
+    let mut units = 0;
+
+    line.chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= utf16
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn should_round_trip_framed_messages() {
+        let mut bytes = vec![];
+        write_message(&mut bytes, &initialized()).unwrap();
+        write_message(&mut bytes, &empty_response(&json!(3))).unwrap();
+
+        let mut reader = Cursor::new(bytes);
+
+        assert_eq!(read_message(&mut reader).unwrap(), Some(initialized()));
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(empty_response(&json!(3)))
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn should_fail_without_content_length() {
+        let mut reader = Cursor::new(b"Content-Type: x\r\n\r\n{}".to_vec());
+
+        assert!(read_message(&mut reader).is_err());
+    }
+
+    #[test]
+    fn should_extract_completion_items_from_list_and_array() {
+        let list = json!({
+            "isIncomplete": false,
+            "items": [
+                { "label": "push(…)", "insertText": "push" },
+                { "label": "len", "textEdit": { "newText": "len()" } },
+                { "label": "capacity" }
+            ]
+        });
+        let array = json!([{ "label": "map" }]);
+
+        assert_eq!(completion_items(&list), vec!["push", "len()", "capacity"]);
+        assert_eq!(completion_items(&array), vec!["map"]);
+        assert!(completion_items(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn should_send_changed_ranges_in_utf16_code_units() {
+        let change = Change {
+            start_char: 9,
+            start: Position::new(1, 3),
+            start_utf16: 4,
+            old_text: String::from("é\nab"),
+            new_text: String::from("x"),
+        };
+
+        let message = did_change_ranges("file:///a.rs", 3, [change].iter());
+        assert_eq!(
+            message.pointer("/params/contentChanges/0"),
+            Some(&json!({
+                "range": {
+                    "start": { "line": 1, "character": 4 },
+                    "end": { "line": 2, "character": 2 }
+                },
+                "text": "x"
+            }))
+        );
+    }
+
+    #[test]
+    fn should_detect_incremental_sync() {
+        let response =
+            |sync: Value| json!({ "result": { "capabilities": { "textDocumentSync": sync } } });
+
+        assert!(incremental_sync(&response(json!(2))));
+        assert!(incremental_sync(&response(
+            json!({ "openClose": true, "change": 2 })
+        )));
+        assert!(!incremental_sync(&response(json!(1))));
+        assert!(!incremental_sync(&json!({ "result": {} })));
+    }
+
+    #[test]
+    fn should_count_utf16_code_units() {
+        assert_eq!(utf16_col("abc", 2), 2);
+        assert_eq!(utf16_col("😀x", 2), 3);
+    }
+
+    #[test]
+    fn should_convert_utf16_columns_back_to_chars() {
+        assert_eq!(char_col("abc", 2), 2);
+        assert_eq!(char_col("😀x", 3), 2);
+        assert_eq!(char_col("ab", 10), 2);
+    }
+
+    #[test]
+    fn should_extract_diagnostics_and_skip_malformed_ones() {
+        let params = json!({
+            "uri": "file:///tmp/main.rs",
+            "diagnostics": [
+                {
+                    "range": {
+                        "start": { "line": 1, "character": 4 },
+                        "end": { "line": 1, "character": 9 }
+                    },
+                    "severity": 2,
+                    "message": "unused variable"
+                },
+                { "message": "no range" }
+            ]
+        });
+
+        assert_eq!(
+            diagnostics(&params),
+            vec![Diagnostic {
+                start: Position::new(1, 4),
+                end: Position::new(1, 9),
+                severity: Severity::Warning,
+                message: String::from("unused variable"),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_convert_file_uri_to_path() {
+        assert_eq!(
+            uri_to_path("file:///tmp/my%20file.rs"),
+            Some(PathBuf::from("/tmp/my file.rs"))
+        );
+        assert_eq!(uri_to_path("untitled:1"), None);
+    }
+}
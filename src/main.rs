@@ -1,40 +1,157 @@
-use app::App;
-use std::{env, path::PathBuf};
-
-mod app;
-mod event;
-mod types;
-mod ui;
-
-#[cfg(feature = "debug-logs")]
-fn init_logging() {
-    use simplelog::*;
-    let _ = CombinedLogger::init(vec![WriteLogger::new(
-        LevelFilter::Debug,
-        Config::default(),
-        std::fs::File::create("zack.log").unwrap(),
-    )]);
+use color_eyre::eyre::{WrapErr, eyre};
+use std::{env, fs::File, path::PathBuf};
+use zack::{
+    app::{
+        self, App,
+        buffer_manager::BufferManagerEvent,
+        editorconfig,
+        history::{History, HistoryKind},
+        logging, state,
+        windows::Arrangement,
+    },
+    event::recording::{self, Recorder},
+    ui::terminal,
+};
+
+/// Installs the logger: `--log-level level` sets what is kept for `:log`, and
+/// `--log-file path` also writes it to `path`.
+fn init_logging(args: &mut Vec<String>) -> color_eyre::Result<()> {
+    let level = take_value(args, "--log-level")
+        .map(|name| logging::parse_level(&name).ok_or_else(|| eyre!("unknown log level {name}")))
+        .transpose()?;
+    let file = take_value(args, "--log-file")
+        .map(|path| File::create(&path).wrap_err(format!("cannot log to {path}")))
+        .transpose()?;
+
+    let level = match (level, &file) {
+        (Some(level), _) => level,
+        (None, Some(_)) => logging::FILE_LEVEL,
+        (None, None) => logging::DEFAULT_LEVEL,
+    };
+    app::notifications::init(level, file);
+
+    Ok(())
+}
+
+/// Removes `flag` and the value following it from `args`, returning the value.
+fn take_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    take_values(args, flag, 1)?.pop()
 }
 
-#[cfg(not(feature = "debug-logs"))]
-fn init_logging() {}
+/// Removes `flag` and the `count` values following it from `args`, returning the values,
+/// or `None` without the flag or with fewer values.
+fn take_values(args: &mut Vec<String>, flag: &str, count: usize) -> Option<Vec<String>> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.remove(index);
+
+    (index + count <= args.len()).then(|| args.drain(index..index + count).collect())
+}
 
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
-    init_logging();
 
-    let terminal = ratatui::init();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    init_logging(&mut args)?;
+
+    // `-f` follows the first file, like `tail -f`.
+    let follow = args.iter().any(|arg| arg == "-f");
+    args.retain(|arg| arg != "-f");
+
+    // `-o`, `-O` and `-p` open the files in windows stacked, side by side or as tab pages.
+    let arrangement = args.iter().rev().find_map(|arg| match arg.as_str() {
+        "-o" => Some(Arrangement::Stacked),
+        "-O" => Some(Arrangement::SideBySide),
+        "-p" => Some(Arrangement::Tabs),
+        _ => None,
+    });
+    args.retain(|arg| !matches!(arg.as_str(), "-o" | "-O" | "-p"));
 
-    let maybe_path = env::args().nth(1).map(PathBuf::from);
+    // `--record file` writes the session's events to `file`, `--replay file` plays them.
+    let recorder = take_value(&mut args, "--record")
+        .map(|path| Recorder::create(path.as_ref()).wrap_err(format!("cannot record to {path}")))
+        .transpose()?;
+    let replay = take_value(&mut args, "--replay")
+        .map(|path| recording::load(path.as_ref()).wrap_err(format!("cannot replay {path}")))
+        .transpose()?;
+
+    // `-d a b` compares two files side by side, as a difftool.
+    let diff = args.iter().any(|arg| arg == "-d");
+    args.retain(|arg| arg != "-d");
+    if diff && args.len() != 2 {
+        return Err(eyre!("-d compares exactly two files"));
+    }
+
+    // `--merge LOCAL BASE REMOTE MERGED` resolves conflicts in MERGED, as a mergetool. The
+    // merged file is opened first, so it is the active document.
+    let merge = args.iter().any(|arg| arg == "--merge");
+    if merge {
+        match take_values(&mut args, "--merge", 4) {
+            Some(files) if args.is_empty() => args = files,
+            _ => return Err(eyre!("--merge takes LOCAL, BASE, REMOTE and MERGED")),
+        }
+        args.rotate_right(1);
+    }
+
+    // `--continue` reopens the file opened last, when no file is given.
+    let resume = args.iter().any(|arg| arg == "--continue");
+    args.retain(|arg| arg != "--continue");
+    if resume && args.is_empty() {
+        let recent = state::path().map(|path| History::load(&path, HistoryKind::Recent));
+        match recent.as_ref().and_then(|recent| recent.entries().last()) {
+            Some(file) => args.push(file.clone()),
+            None => tracing::warn!("No recent file to continue"),
+        }
+    }
+
+    // Restores the terminal when dropped, after `app.run` or on an early return.
+    let (terminal, _guard) = terminal::init();
+
+    let maybe_path = args.first().map(PathBuf::from);
 
     let file_content = maybe_path
         .as_ref()
-        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|path| editorconfig::read(path).ok())
         .unwrap_or_else(|| String::from(""));
 
-    let result = App::new(file_content, maybe_path).run(terminal);
+    let mut app = App::new(file_content, maybe_path);
+
+    app.recorder = recorder;
+
+    if let Some(events) = replay {
+        recording::replay(events, app.event_handler.input_sender());
+    }
+
+    if follow {
+        app.buffers.handle_event(BufferManagerEvent::ToggleFollow);
+    }
+
+    let mut opened = vec![app.buffers.active_index()];
+    for path in args.iter().skip(1).map(PathBuf::from) {
+        match app.buffers.load(path) {
+            Ok(index) => opened.push(index),
+            Err(err) => tracing::error!("Failed to open file: {err}"),
+        }
+    }
 
-    ratatui::restore();
+    if merge {
+        // LOCAL, BASE and REMOTE are only shown, above MERGED.
+        for &index in &opened[1..] {
+            if let Some(document) = app.buffers.document_mut(index) {
+                document.read_only = true;
+            }
+        }
+        opened.rotate_left(1);
+        app.windows.open(Arrangement::Merge, &opened);
+        app.windows.sync(app.buffers.active_index());
+    } else if diff {
+        app.windows.open(Arrangement::SideBySide, &opened);
+        app.windows.scroll_bind = true;
+        if let [first, second] = opened[..] {
+            app.buffers.compare(first, second);
+        }
+    } else if let Some(arrangement) = arrangement {
+        app.windows.open(arrangement, &opened);
+    }
 
-    result
+    app.run(terminal)
 }
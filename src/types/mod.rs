@@ -1 +1,3 @@
 pub mod position;
+pub mod range;
+pub mod selection;
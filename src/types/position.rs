@@ -4,7 +4,8 @@
 //! in a text buffer using a zero-based `(line, column)` format. It's useful for cursor tracking,
 //! highlighting, editing operations, and buffer navigation.
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Positions order by line first, then by column, matching their order in the buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Position {
     /// Zero-based line index.
     pub line: usize,
@@ -57,6 +58,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::clone_on_copy)]
     fn should_be_copy_and_clone() {
         let a = Position::new(4, 4);
         let b = a;
@@ -65,4 +67,14 @@ mod tests {
         assert_eq!(a, b);
         assert_eq!(a, c);
     }
+
+    #[test]
+    fn should_order_by_line_then_col() {
+        assert!(Position::new(0, 9) < Position::new(1, 0));
+        assert!(Position::new(2, 1) < Position::new(2, 3));
+        assert_eq!(
+            Position::new(4, 2).max(Position::new(4, 1)),
+            Position::new(4, 2)
+        );
+    }
 }
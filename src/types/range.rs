@@ -0,0 +1,107 @@
+//! Range type used throughout the Zack text editor.
+//!
+//! This module defines the [`Range`] struct, a span of text between two [`Position`]s,
+//! the end excluded, as edits, search matches and language server ranges are. A range
+//! is always normalized: its start never comes after its end.
+
+use crate::types::position::Position;
+
+/// The text from `start` up to `end`, exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Range {
+    /// First position of the range.
+    pub start: Position,
+    /// Position right after the range.
+    pub end: Position,
+}
+
+impl Range {
+    /// Creates the range between `a` and `b`, in whichever order they come.
+    pub fn new(a: Position, b: Position) -> Self {
+        Self {
+            start: a.min(b),
+            end: a.max(b),
+        }
+    }
+
+    /// Creates the empty range at `position`.
+    pub fn point(position: Position) -> Self {
+        Self::new(position, position)
+    }
+
+    /// Returns `true` when the range covers no text.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns `true` when `position` is inside the range.
+    pub fn contains(&self, position: Position) -> bool {
+        self.start <= position && position < self.end
+    }
+
+    /// Returns `true` when all of `other` is inside the range.
+    pub fn contains_range(&self, other: &Range) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Returns `true` when the range and `other` share some text.
+    pub fn overlaps(&self, other: &Range) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// Returns the smallest range covering both the range and `other`.
+    pub fn union(&self, other: &Range) -> Range {
+        Range::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Returns the lines the range touches, the line of its end included only when the
+    /// range reaches into it.
+    pub fn lines(&self) -> std::ops::RangeInclusive<usize> {
+        let last = match self.end.col == 0 && self.end.line > self.start.line {
+            true => self.end.line - 1,
+            false => self.end.line,
+        };
+
+        self.start.line..=last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(line: usize, col: usize) -> Position {
+        Position::new(line, col)
+    }
+
+    #[test]
+    fn should_normalize_and_contain_positions_before_the_end() {
+        let range = Range::new(pos(2, 1), pos(0, 3));
+
+        assert_eq!(range.start, pos(0, 3));
+        assert!(range.contains(pos(1, 99)));
+        assert!(range.contains(pos(0, 3)));
+        assert!(!range.contains(pos(2, 1)));
+        assert!(Range::point(pos(1, 1)).is_empty());
+    }
+
+    #[test]
+    fn should_compare_ranges() {
+        let range = Range::new(pos(0, 2), pos(0, 6));
+
+        assert!(range.contains_range(&Range::new(pos(0, 3), pos(0, 6))));
+        assert!(range.overlaps(&Range::new(pos(0, 5), pos(1, 0))));
+        assert!(!range.overlaps(&Range::new(pos(0, 6), pos(1, 0))));
+        assert_eq!(
+            range.union(&Range::new(pos(0, 0), pos(0, 1))),
+            Range::new(pos(0, 0), pos(0, 6))
+        );
+    }
+
+    #[test]
+    fn should_list_the_lines_it_touches() {
+        assert_eq!(Range::new(pos(1, 2), pos(3, 0)).lines(), 1..=2);
+        assert_eq!(Range::new(pos(1, 2), pos(3, 1)).lines(), 1..=3);
+        assert_eq!(Range::point(pos(4, 0)).lines(), 4..=4);
+    }
+}
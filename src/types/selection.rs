@@ -0,0 +1,148 @@
+//! Selection type used throughout the Zack text editor.
+//!
+//! This module defines the [`Selection`] struct, text picked between an anchor, where
+//! selecting started, and a head, where the cursor is, by characters, whole lines or a
+//! block of columns. Both ends are included, as the cells under them are selected; the
+//! text it covers is given as [`Range`]s, one per line for a block.
+
+use crate::types::{position::Position, range::Range};
+
+/// How a selection picks the text between its ends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelectionKind {
+    /// Every character from one end to the other.
+    #[default]
+    CharWise,
+    /// The whole lines from one end to the other.
+    LineWise,
+    /// The columns between the ends, on each line between them.
+    BlockWise,
+}
+
+/// Text selected from `anchor` to `head`, both included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// Where selecting started.
+    pub anchor: Position,
+    /// Where the cursor is; the end that moves.
+    pub head: Position,
+    /// How the text between the ends is picked.
+    pub kind: SelectionKind,
+}
+
+impl Selection {
+    /// Creates a selection of `kind` from `anchor` to `head`.
+    pub fn new(anchor: Position, head: Position, kind: SelectionKind) -> Self {
+        Self { anchor, head, kind }
+    }
+
+    /// Returns the end that comes first in the buffer.
+    pub fn start(&self) -> Position {
+        self.anchor.min(self.head)
+    }
+
+    /// Returns the end that comes last in the buffer.
+    pub fn end(&self) -> Position {
+        self.anchor.max(self.head)
+    }
+
+    /// Returns the columns of a block, both included.
+    fn cols(&self) -> (usize, usize) {
+        (
+            self.anchor.col.min(self.head.col),
+            self.anchor.col.max(self.head.col),
+        )
+    }
+
+    /// Returns the range covering the whole selection: for a block, the lines between its
+    /// ends, from its first to past its last column.
+    pub fn range(&self) -> Range {
+        let (start, end) = (self.start(), self.end());
+
+        match self.kind {
+            SelectionKind::CharWise => Range::new(start, Position::new(end.line, end.col + 1)),
+            SelectionKind::LineWise => {
+                Range::new(Position::new(start.line, 0), Position::new(end.line + 1, 0))
+            }
+            SelectionKind::BlockWise => {
+                let (first, last) = self.cols();
+                Range::new(
+                    Position::new(start.line, first),
+                    Position::new(end.line, last + 1),
+                )
+            }
+        }
+    }
+
+    /// Returns the ranges of selected text, in buffer order: one for characters or lines,
+    /// one per line for a block.
+    pub fn ranges(&self) -> Vec<Range> {
+        match self.kind {
+            SelectionKind::CharWise | SelectionKind::LineWise => vec![self.range()],
+            SelectionKind::BlockWise => {
+                let (first, last) = self.cols();
+                (self.start().line..=self.end().line)
+                    .map(|line| {
+                        Range::new(Position::new(line, first), Position::new(line, last + 1))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns `true` when `position` is selected.
+    pub fn contains(&self, position: Position) -> bool {
+        match self.kind {
+            SelectionKind::BlockWise => {
+                let (first, last) = self.cols();
+                (self.start().line..=self.end().line).contains(&position.line)
+                    && (first..=last).contains(&position.col)
+            }
+            _ => self.range().contains(position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(line: usize, col: usize) -> Position {
+        Position::new(line, col)
+    }
+
+    #[test]
+    fn should_cover_both_ends_in_buffer_order() {
+        let selection = Selection::new(pos(2, 1), pos(1, 4), SelectionKind::CharWise);
+
+        assert_eq!((selection.start(), selection.end()), (pos(1, 4), pos(2, 1)));
+        assert_eq!(selection.range(), Range::new(pos(1, 4), pos(2, 2)));
+        assert!(selection.contains(pos(2, 1)));
+        assert!(!selection.contains(pos(1, 3)));
+    }
+
+    #[test]
+    fn should_select_whole_lines() {
+        let selection = Selection::new(pos(3, 5), pos(1, 2), SelectionKind::LineWise);
+
+        assert_eq!(selection.range(), Range::new(pos(1, 0), pos(4, 0)));
+        assert!(selection.contains(pos(3, 80)));
+        assert_eq!(selection.range().lines(), 1..=3);
+    }
+
+    #[test]
+    fn should_select_a_block_of_columns_on_each_line() {
+        let selection = Selection::new(pos(0, 4), pos(2, 1), SelectionKind::BlockWise);
+
+        assert_eq!(
+            selection.ranges(),
+            vec![
+                Range::new(pos(0, 1), pos(0, 5)),
+                Range::new(pos(1, 1), pos(1, 5)),
+                Range::new(pos(2, 1), pos(2, 5)),
+            ]
+        );
+        assert!(selection.contains(pos(1, 4)));
+        assert!(!selection.contains(pos(1, 5)));
+    }
+}
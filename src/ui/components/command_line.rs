@@ -0,0 +1,247 @@
+//! Command line component rendering logic.
+//!
+//! This module defines the `CommandLine` component, the single-line input shown at the
+//! bottom of the editor after pressing `:` (commands) or `/` (search). It collects the
+//! typed text, editable anywhere with the usual line-editing keys, and, on `Enter`,
+//! hands it back to the application to be executed. `Up` and `Down` walk the history of
+//! the kind of input being typed.
+
+use crate::app::history::History;
+use crate::event::AppEvent;
+use crate::ui::components::FocusableComponent;
+use crate::ui::components::text_input::TextInput;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::buffer::Buffer;
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Stylize},
+    widgets::{Paragraph, Widget},
+};
+
+/// What the text typed in the command line is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandLineKind {
+    /// An ex-style command, entered with `:`.
+    Command,
+    /// A forward search pattern, entered with `/`.
+    Search,
+}
+
+impl CommandLineKind {
+    /// Returns the character displayed before the input.
+    pub fn prefix(&self) -> char {
+        match self {
+            CommandLineKind::Command => ':',
+            CommandLineKind::Search => '/',
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CommandLine {
+    /// What the input will be used for once submitted.
+    pub kind: CommandLineKind,
+    /// The text typed so far, without the prefix.
+    pub input: TextInput,
+    /// Commands entered before.
+    pub command_history: History,
+    /// Search patterns entered before.
+    pub search_history: History,
+}
+
+impl Default for CommandLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandLine {
+    /// Creates a new, empty `CommandLine` in command kind.
+    pub fn new() -> Self {
+        Self {
+            kind: CommandLineKind::Command,
+            input: TextInput::new(),
+            command_history: History::new(),
+            search_history: History::new(),
+        }
+    }
+
+    /// Prepares the command line to receive input of the given kind, starting with `prefill`.
+    pub fn open(&mut self, kind: CommandLineKind, prefill: String) {
+        self.kind = kind;
+        self.input.set(prefill);
+        self.command_history.reset();
+        self.search_history.reset();
+    }
+
+    /// Handles a key event while the command line is active.
+    ///
+    /// Returns a vector of `AppEvent`s that may trigger further actions:
+    /// - `Esc`: Clears the input and returns focus to the editor.
+    /// - `Enter`: Emits a `SubmitCommandLine` event with the input and returns focus.
+    /// - `Backspace`: Closes the line when it is empty.
+    /// - `Up` / `Down`: Replaces the input with an older or newer history entry.
+    /// - Editing keys: Edit the input at the cursor (see [`TextInput::handle_key`]).
+    ///
+    /// Other keys are ignored.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<AppEvent> {
+        let history = match self.kind {
+            CommandLineKind::Command => &mut self.command_history,
+            CommandLineKind::Search => &mut self.search_history,
+        };
+
+        match key.code {
+            KeyCode::Esc => {
+                self.input.clear();
+                history.reset();
+                vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+            }
+            KeyCode::Enter => {
+                let input = self.input.take();
+                history.push(&input);
+                vec![
+                    AppEvent::ChangeFocus(FocusableComponent::Editor),
+                    AppEvent::SubmitCommandLine {
+                        kind: self.kind,
+                        input,
+                    },
+                ]
+            }
+            KeyCode::Backspace if self.input.is_empty() => {
+                vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+            }
+            KeyCode::Up => {
+                if let Some(entry) = history.previous(self.input.text()) {
+                    self.input.set(entry);
+                }
+                vec![]
+            }
+            KeyCode::Down => {
+                if let Some(entry) = history.next() {
+                    self.input.set(entry);
+                }
+                vec![]
+            }
+            _ => {
+                self.input.handle_key(key);
+                vec![]
+            }
+        }
+    }
+
+    /// Renders the command line on the last text row of the editor, inside its border.
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let line = Paragraph::new(format!("{}{}", self.kind.prefix(), self.input.text()))
+            .fg(Color::White)
+            .bg(Color::Reset);
+
+        line.render(Self::line_area(area), buf);
+    }
+
+    /// Returns the terminal position where the cursor should be drawn while typing.
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        let line_area = Self::line_area(area);
+        let typed = self.input.cursor() as u16 + 1;
+
+        Position {
+            x: line_area.x + typed.min(line_area.width.saturating_sub(1)),
+            y: line_area.y,
+        }
+    }
+
+    fn line_area(area: Rect) -> Rect {
+        Rect {
+            x: area.x + 1,
+            y: area.y + area.height.saturating_sub(2),
+            width: area.width.saturating_sub(2),
+            height: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn should_open_with_kind_and_prefill() {
+        let mut line = CommandLine::new();
+
+        line.open(CommandLineKind::Search, String::from("foo"));
+
+        assert_eq!(line.kind, CommandLineKind::Search);
+        assert_eq!(line.input, "foo");
+    }
+
+    #[test]
+    fn should_submit_and_clear_input_on_enter() {
+        let mut line = CommandLine::new();
+        line.open(CommandLineKind::Command, String::from("'<,'>"));
+
+        line.handle_key(key(KeyCode::Char('w')));
+        let events = line.handle_key(key(KeyCode::Enter));
+
+        assert_eq!(line.input, "");
+        assert!(events.contains(&AppEvent::SubmitCommandLine {
+            kind: CommandLineKind::Command,
+            input: String::from("'<,'>w"),
+        }));
+        assert!(events.contains(&AppEvent::ChangeFocus(FocusableComponent::Editor)));
+    }
+
+    #[test]
+    fn should_recall_history_of_the_open_kind() {
+        let mut line = CommandLine::new();
+        line.command_history.push("w");
+        line.search_history.push("foo");
+        line.open(CommandLineKind::Search, String::from("ba"));
+
+        line.handle_key(key(KeyCode::Up));
+        assert_eq!(line.input, "foo");
+
+        line.handle_key(key(KeyCode::Down));
+        assert_eq!(line.input, "ba");
+    }
+
+    #[test]
+    fn should_record_submitted_input_in_history() {
+        let mut line = CommandLine::new();
+        line.open(CommandLineKind::Command, String::from("wq"));
+
+        line.handle_key(key(KeyCode::Enter));
+
+        assert_eq!(line.command_history.entries(), ["wq"]);
+        assert!(line.search_history.entries().is_empty());
+    }
+
+    #[test]
+    fn should_close_on_backspace_when_input_empty() {
+        let mut line = CommandLine::new();
+
+        let events = line.handle_key(key(KeyCode::Backspace));
+
+        assert_eq!(
+            events,
+            vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+        );
+    }
+
+    #[test]
+    fn should_clear_input_and_change_focus_on_esc() {
+        let mut line = CommandLine::new();
+        line.input.set("wq");
+
+        let events = line.handle_key(key(KeyCode::Esc));
+
+        assert_eq!(line.input, "");
+        assert_eq!(
+            events,
+            vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+        );
+    }
+}
@@ -0,0 +1,53 @@
+//! Completion popup rendering logic.
+//!
+//! This module defines the `CompletionMenu` component, which draws the candidates of
+//! the active [`Completion`] in a [`Popup`] just below the word being completed,
+//! highlighting the selected one and tagging each with its source.
+
+use crate::{
+    app::completion::Completion,
+    ui::components::popup::{Anchor, Popup},
+};
+use ratatui::{
+    layout::Position,
+    style::{Color, Style},
+    text::Line,
+};
+
+/// Maximum number of candidates visible at once.
+const MAX_VISIBLE_ITEMS: usize = 8;
+
+/// The `CompletionMenu` component responsible for drawing the completion popup.
+pub struct CompletionMenu;
+
+impl CompletionMenu {
+    /// Returns the popup for `completion`, below the screen cell `anchor` where the
+    /// completed word starts, or `None` when there are no candidates.
+    pub fn popup(completion: &Completion, anchor: Position) -> Option<Popup<'static>> {
+        if completion.items.is_empty() {
+            return None;
+        }
+
+        let first = completion
+            .selected
+            .saturating_sub(MAX_VISIBLE_ITEMS.saturating_sub(1));
+        let lines: Vec<Line> = completion
+            .items
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(MAX_VISIBLE_ITEMS)
+            .map(|(index, item)| {
+                let line = Line::from(format!("{} [{}]", item.text, item.source.tag()));
+
+                if index == completion.selected {
+                    line.style(Style::default().bg(Color::Blue).fg(Color::White))
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        Some(Popup::new(lines, Anchor::Cell(anchor)))
+    }
+}
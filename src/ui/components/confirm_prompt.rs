@@ -0,0 +1,138 @@
+//! Confirmation prompt component rendering logic.
+//!
+//! This module defines the `ConfirmPrompt` component, a yes/no question shown on the
+//! last text row of the editor. Answering yes or no dispatches the events the prompt
+//! was opened with for that answer; any other key just dismisses it.
+
+use crate::event::AppEvent;
+use crate::ui::components::FocusableComponent;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::buffer::Buffer;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Stylize},
+    widgets::{Paragraph, Widget},
+};
+
+#[derive(Debug, Default)]
+pub struct ConfirmPrompt {
+    /// The question shown to the user.
+    pub message: String,
+    /// Events dispatched when the user confirms.
+    pub on_confirm: Vec<AppEvent>,
+    /// Events dispatched when the user explicitly answers no.
+    pub on_deny: Vec<AppEvent>,
+}
+
+impl ConfirmPrompt {
+    /// Creates a new, empty `ConfirmPrompt`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prepares the prompt to ask `message`, running `on_confirm` if the answer is yes
+    /// and `on_deny` if it is no.
+    pub fn open(&mut self, message: String, on_confirm: Vec<AppEvent>, on_deny: Vec<AppEvent>) {
+        self.message = message;
+        self.on_confirm = on_confirm;
+        self.on_deny = on_deny;
+    }
+
+    /// Handles a key event while the prompt is active.
+    ///
+    /// Returns a vector of `AppEvent`s that may trigger further actions:
+    /// - `y`, `Y` or `Enter`: Returns focus and emits the confirmed events.
+    /// - `n` or `N`: Returns focus and emits the denied events.
+    /// - Any other key: Returns focus without running anything.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<AppEvent> {
+        let confirmed = std::mem::take(&mut self.on_confirm);
+        let denied = std::mem::take(&mut self.on_deny);
+        self.message.clear();
+
+        let mut events = vec![AppEvent::ChangeFocus(FocusableComponent::Editor)];
+
+        match key.code {
+            KeyCode::Char('y' | 'Y') | KeyCode::Enter => events.extend(confirmed),
+            KeyCode::Char('n' | 'N') => events.extend(denied),
+            _ => {}
+        }
+
+        events
+    }
+
+    /// Renders the question on the last text row of the editor, inside its border.
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let line = Paragraph::new(format!("{} (y/n)", self.message))
+            .fg(Color::Yellow)
+            .bg(Color::Reset);
+
+        let area = Rect {
+            x: area.x + 1,
+            y: area.y + area.height.saturating_sub(2),
+            width: area.width.saturating_sub(2),
+            height: 1,
+        };
+
+        line.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn should_emit_confirmed_events_on_yes() {
+        let mut prompt = ConfirmPrompt::new();
+        prompt.open(String::from("Quit?"), vec![AppEvent::Quit], vec![]);
+
+        let events = prompt.handle_key(key(KeyCode::Char('y')));
+
+        assert_eq!(
+            events,
+            vec![
+                AppEvent::ChangeFocus(FocusableComponent::Editor),
+                AppEvent::Quit
+            ]
+        );
+        assert!(prompt.on_confirm.is_empty());
+    }
+
+    #[test]
+    fn should_emit_denied_events_on_no() {
+        let mut prompt = ConfirmPrompt::new();
+        prompt.open(String::from("Save?"), vec![], vec![AppEvent::Quit]);
+
+        let events = prompt.handle_key(key(KeyCode::Char('n')));
+
+        assert_eq!(
+            events,
+            vec![
+                AppEvent::ChangeFocus(FocusableComponent::Editor),
+                AppEvent::Quit
+            ]
+        );
+    }
+
+    #[test]
+    fn should_only_dismiss_on_other_keys() {
+        let mut prompt = ConfirmPrompt::new();
+        prompt.open(
+            String::from("Quit?"),
+            vec![AppEvent::Quit],
+            vec![AppEvent::Quit],
+        );
+
+        let events = prompt.handle_key(key(KeyCode::Esc));
+
+        assert_eq!(
+            events,
+            vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+        );
+    }
+}
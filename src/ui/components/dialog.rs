@@ -0,0 +1,199 @@
+//! Confirmation dialog component rendering logic.
+//!
+//! This module defines the `Dialog` component, a question answered with Yes, No or
+//! Cancel in a [`Popup`] centered over the editor. It asks before quitting with unsaved
+//! changes, before overwriting another file and before reloading a file changed on disk.
+//! Answering yes or no dispatches the events the dialog was opened with for that answer;
+//! cancelling runs nothing.
+
+use crate::event::AppEvent;
+use crate::ui::components::{
+    FocusableComponent,
+    popup::{Anchor, Layer, Popup},
+};
+use crate::ui::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+/// Widest a dialog grows, borders included.
+const MAX_WIDTH: u16 = 60;
+
+/// The answers of a dialog, in the order of their buttons.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DialogButton {
+    #[default]
+    Yes,
+    No,
+    Cancel,
+}
+
+const BUTTONS: [DialogButton; 3] = [DialogButton::Yes, DialogButton::No, DialogButton::Cancel];
+
+#[derive(Debug, Default)]
+pub struct Dialog {
+    /// The question shown to the user.
+    pub message: String,
+    /// Events dispatched when the user answers yes.
+    pub on_yes: Vec<AppEvent>,
+    /// Events dispatched when the user answers no.
+    pub on_no: Vec<AppEvent>,
+    /// The button `Enter` presses.
+    pub selected: DialogButton,
+}
+
+impl DialogButton {
+    /// Returns the label of the button.
+    fn label(self) -> &'static str {
+        match self {
+            DialogButton::Yes => "Yes",
+            DialogButton::No => "No",
+            DialogButton::Cancel => "Cancel",
+        }
+    }
+
+    /// Returns the button `offset` places away, wrapping around.
+    fn step(self, offset: isize) -> Self {
+        let index = BUTTONS
+            .iter()
+            .position(|button| *button == self)
+            .unwrap_or(0);
+        BUTTONS[(index as isize + offset).rem_euclid(BUTTONS.len() as isize) as usize]
+    }
+}
+
+impl Dialog {
+    /// Creates a new, empty `Dialog`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prepares the dialog to ask `message`, running `on_yes` or `on_no` on those
+    /// answers, with Yes selected.
+    pub fn open(&mut self, message: String, on_yes: Vec<AppEvent>, on_no: Vec<AppEvent>) {
+        self.message = message;
+        self.on_yes = on_yes;
+        self.on_no = on_no;
+        self.selected = DialogButton::Yes;
+    }
+
+    /// Handles a key event while the dialog is active.
+    ///
+    /// Returns a vector of `AppEvent`s that may trigger further actions:
+    /// - `Left`/`h`/`Shift-Tab` and `Right`/`l`/`Tab`: Select the previous or next button.
+    /// - `Enter` or `Space`: Answer with the selected button.
+    /// - `y`, `n` and `c`: Answer yes, no or cancel.
+    /// - `Esc` or `q`: Cancel.
+    ///
+    /// Answering returns focus to the editor. Other keys are ignored.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<AppEvent> {
+        let answer = match key.code {
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => {
+                self.selected = self.selected.step(-1);
+                return vec![];
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                self.selected = self.selected.step(1);
+                return vec![];
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => self.selected,
+            KeyCode::Char('y' | 'Y') => DialogButton::Yes,
+            KeyCode::Char('n' | 'N') => DialogButton::No,
+            KeyCode::Char('c' | 'C' | 'q') | KeyCode::Esc => DialogButton::Cancel,
+            _ => return vec![],
+        };
+
+        let on_yes = std::mem::take(&mut self.on_yes);
+        let on_no = std::mem::take(&mut self.on_no);
+        self.message.clear();
+
+        let mut events = vec![AppEvent::ChangeFocus(FocusableComponent::Editor)];
+        match answer {
+            DialogButton::Yes => events.extend(on_yes),
+            DialogButton::No => events.extend(on_no),
+            DialogButton::Cancel => {}
+        }
+
+        events
+    }
+
+    /// Returns the popup showing the question above the buttons, the selected one in
+    /// the colors of `theme`.
+    pub fn popup(&self, theme: &Theme) -> Popup<'static> {
+        let mut lines: Vec<Line> = self
+            .message
+            .lines()
+            .map(|line| Line::from(format!(" {line} ")))
+            .collect();
+
+        let buttons: Vec<Span> = BUTTONS
+            .iter()
+            .flat_map(|button| {
+                let style = match *button == self.selected {
+                    true => Style::default()
+                        .fg(Color::Black)
+                        .bg(theme.dialog_selected)
+                        .add_modifier(Modifier::BOLD),
+                    false => Style::default(),
+                };
+
+                [
+                    Span::raw(" "),
+                    Span::styled(format!("[ {} ]", button.label()), style),
+                ]
+            })
+            .collect();
+        lines.push(Line::default());
+        lines.push(Line::from(buttons).centered());
+
+        Popup::new(lines, Anchor::Center)
+            .layer(Layer::Dialog)
+            .title(" Confirm ")
+            .max_size(MAX_WIDTH, u16::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn should_answer_with_the_selected_button() {
+        let mut dialog = Dialog::new();
+        dialog.open(String::from("Save?"), vec![AppEvent::Quit], vec![]);
+
+        assert!(dialog.handle_key(key(KeyCode::Right)).is_empty());
+        assert_eq!(dialog.selected, DialogButton::No);
+        dialog.handle_key(key(KeyCode::BackTab));
+        dialog.handle_key(key(KeyCode::Left));
+        assert_eq!(dialog.selected, DialogButton::Cancel);
+
+        assert_eq!(
+            dialog.handle_key(key(KeyCode::Enter)),
+            vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+        );
+        assert!(dialog.on_yes.is_empty());
+    }
+
+    #[test]
+    fn should_answer_with_shortcut_keys() {
+        let mut dialog = Dialog::new();
+        dialog.open(String::from("Quit?"), vec![], vec![AppEvent::Quit]);
+
+        assert!(dialog.handle_key(key(KeyCode::Char('x'))).is_empty());
+        assert_eq!(
+            dialog.handle_key(key(KeyCode::Char('n'))),
+            vec![
+                AppEvent::ChangeFocus(FocusableComponent::Editor),
+                AppEvent::Quit
+            ]
+        );
+    }
+}
@@ -2,21 +2,112 @@
 //!
 //! This module defines the `Editor` component, responsible for rendering the main text
 //! editing area of the Zack text editor. It displays the contents of the buffer,
-//! including the current mode and styling.
+//! including the current mode, the status of the file on disk and styling, on a
+//! background tinted by the kind of buffer, and a gutter on the left while the document
+//! has signs or line numbers to show. With `wrap` set, long lines continue on the
+//! following rows, marked in the gutter; in the pretty view of a document, they are
+//! split at synthetic breaks instead (see [`pretty`]). A scrollbar over the right border
+//! shows which part of a long document is in view, marking the lines with search matches
+//! and diagnostics. Control chars are drawn as visible placeholders, in their own color.
+//! Windows without the focus are dimmed and drawn with a plain border: all of them while
+//! another component has the focus, and the other windows of a split otherwise. It also
+//! maps screen coordinates back to the gutter or buffer positions, for mouse handling.
 
-use crate::app::App;
+use crate::{
+    app::{
+        App,
+        buffer_manager::Document,
+        diagnostics::{Severity, SignKind},
+        file,
+        git::LineChange,
+        options::Options,
+        pretty,
+    },
+    types::position::Position,
+    ui::components::{
+        FocusableComponent,
+        minimap::{self, Minimap},
+        tab_bar::TabBar,
+    },
+};
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Rect},
-    style::{Color, Stylize},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span, Text},
     widgets::{Block, BorderType, Paragraph, Widget},
 };
 
+/// Space after the sign and line number columns.
+const GUTTER_PADDING: u16 = 1;
+
+/// Columns between the end of the cursor line and its blame annotation.
+const BLAME_GAP: u16 = 3;
+
+/// Drawn in the gutter next to rows continuing a wrapped line.
+const WRAP_MARKER: char = '↪';
+
+/// Drawn after the first line of a closed fold, before the number of lines it hides.
+const FOLD_MARKER: char = '⋯';
+
+/// Drawn over the right border next to the lines in view, as the scrollbar thumb.
+pub const SCROLLBAR_THUMB: char = '┃';
+
+/// Drawn on the scrollbar next to lines with a search match or a diagnostic.
+pub const SCROLLBAR_MARK: char = '■';
+
+/// Style patched onto the placeholders drawn for control chars.
+const CONTROL_STYLE: Style = Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD);
+
+/// Returns the placeholder drawn for the control char `c`, if it is one: its symbol in
+/// the Unicode Control Pictures block, one cell wide, so form feeds, escapes and NULs in
+/// the buffer are visible and never reach the terminal. Tabs are expanded to spaces.
+pub fn control_picture(c: char) -> Option<char> {
+    match c {
+        '\t' => None,
+        '\0'..='\x1f' => char::from_u32(0x2400 + c as u32),
+        '\x7f' => Some('\u{2421}'),
+        '\u{80}'..='\u{9f}' => Some(char::REPLACEMENT_CHARACTER),
+        _ => None,
+    }
+}
+
+/// Returns the number of cells the char `c` takes when drawn `col` cells into a row: a
+/// tab reaches the next multiple of `tabstop`, any other char takes one.
+pub fn cell_width(c: char, col: usize, tabstop: usize) -> usize {
+    let tabstop = tabstop.max(1);
+
+    match c {
+        '\t' => tabstop - col % tabstop,
+        _ => 1,
+    }
+}
+
 /// The `Editor` component responsible for rendering the editable text area.
 pub struct Editor;
 
+/// A screen row of the editor: the chars `start..end` of a buffer line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayRow {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// What lies under a screen cell of the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorHit {
+    /// The sign gutter next to `line`.
+    Gutter { line: usize },
+    /// A position in the buffer text.
+    Text(Position),
+    /// The row of the minimap showing `line` first.
+    Minimap { line: usize },
+}
+
 impl Editor {
-    /// Renders the editor component onto the provided area of the screen.
+    /// Renders the editor component onto the provided area of the screen: a window for
+    /// each document on screen, and the bar naming the tab pages.
     ///
     /// # Arguments
     ///
@@ -24,24 +115,1254 @@ impl Editor {
     /// - `area`: The screen region to render into.
     /// - `buf`: The terminal buffer to draw on.
     pub fn render(app: &App, area: Rect, buf: &mut Buffer) {
-        let block = Block::bordered()
-            .title("zack")
-            .title_alignment(Alignment::Center)
-            .title_bottom(app.mode.get_mode_label())
+        let windows = app.windows.areas(area);
+        let split = windows.len() > 1;
+
+        for (index, window, focused) in windows {
+            if let Some(document) = app.buffers.documents().get(index) {
+                Self::render_window(app, document, window, focused, split, buf);
+            }
+        }
+
+        if let Some(bar) = app.windows.tab_bar(area) {
+            TabBar::render(app, bar, buf);
+        }
+    }
+
+    /// Renders `document` in the window at `area`. Only the focused window shows the mode,
+    /// the jobs and the selection; windows that are not focused are dimmed. Split windows
+    /// are titled with the name of their file.
+    fn render_window(
+        app: &App,
+        document: &Document,
+        area: Rect,
+        focused: bool,
+        split: bool,
+        buf: &mut Buffer,
+    ) {
+        let options = &app.options;
+        let tabstop = app.buffers.tabstop(document, options);
+        let background = if options.tint {
+            app.theme.background(document.kind())
+        } else {
+            Color::Reset
+        };
+        // Another component, like the command line, or another window has the keyboard.
+        let dimmed = options.dim && (app.focus != FocusableComponent::Editor || !focused);
+        let title = match split {
+            true => format!(" {} ", document.file.display_name()),
+            false => String::from("zack"),
+        };
+
+        let mut block = Block::bordered()
+            .title(title)
             .title_alignment(Alignment::Left)
-            .border_type(BorderType::Rounded);
+            .border_type(BorderType::Rounded)
+            .fg(Color::Gray)
+            .bg(background);
 
-        let mut text = String::new();
-        for line in app.buffer.lines() {
-            text.push_str(&line.to_string());
+        let label = app.mode.get_mode_label();
+        let mode = match app.mode.pending_count() {
+            Some(count) => format!("{label} {count}"),
+            None => label.to_string(),
+        };
+        match &options.statusline {
+            Some(statusline) => {
+                let (left, right) = statusline.render(document, if focused { &mode } else { "" });
+                block = block
+                    .title_bottom(left)
+                    .title_bottom(Line::from(right).alignment(Alignment::Right));
+            }
+            None if focused => block = block.title_bottom(mode),
+            None => {}
         }
 
+        if dimmed {
+            block = block
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(app.theme.inactive_border));
+        }
+
+        if focused && let (Some(spinner), Some(status)) = (app.jobs.spinner(), app.jobs.status()) {
+            block = block.title_bottom(
+                Line::from(format!(" {spinner} {status} ")).alignment(Alignment::Right),
+            );
+        }
+
+        if let Some(status) = Self::file_status(document, options) {
+            block = block.title_bottom(status.alignment(Alignment::Right));
+        }
+
+        let selection = app
+            .mode
+            .get_selection(document.cursor.position)
+            .filter(|_| focused);
+
+        if options.wordcount {
+            block = block.title_bottom(
+                Line::from(Self::word_count(document, selection)).alignment(Alignment::Right),
+            );
+        }
+
+        if options.ruler {
+            block =
+                block.title_bottom(Line::from(Self::ruler(document)).alignment(Alignment::Right));
+        }
+        let text_area = Self::text_area(document, options, area);
+        let rows = Self::display_rows(document, options, tabstop, area);
+
+        block.render(area, buf);
+        Self::render_gutter(document, options, tabstop, area, buf);
+
+        let mut styled: Option<(usize, String, Vec<Style>)> = None;
+        let text: Text = rows
+            .iter()
+            .map(|row| {
+                if styled.as_ref().is_none_or(|(line, _, _)| *line != row.line) {
+                    let content = document.buffer.as_rope().line(row.line).to_string();
+                    let content = content.trim_end_matches(['\n', '\r']).to_string();
+                    let styles = Self::line_styles(document, row.line, &content, selection);
+                    styled = Some((row.line, content, styles));
+                }
+
+                let (_, content, styles) = styled.as_ref().expect("styled line was just set");
+                let content: String = content
+                    .chars()
+                    .skip(row.start)
+                    .take(row.end - row.start)
+                    .collect();
+                let end = row.end.min(styles.len());
+                let mut line =
+                    Self::styled_line(&content, &styles[row.start.min(end)..end], tabstop);
+
+                if let Some(last) = document.folds.closed_at(row.line) {
+                    line.push_span(Span::styled(
+                        format!(" {FOLD_MARKER} {} lines", last - row.line),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+
+                line
+            })
+            .collect();
+
         let paragraph = Paragraph::new(text)
-            .block(block)
             .fg(Color::Gray)
-            .bg(Color::Reset)
+            .bg(background)
             .alignment(Alignment::Left);
 
-        paragraph.render(area, buf);
+        paragraph.render(text_area, buf);
+
+        if let (Some(minimap), Some(first), Some(last)) = (
+            Self::minimap_area(document, options, area),
+            rows.first(),
+            rows.last(),
+        ) {
+            Minimap::render(document, first.line, last.line, minimap, buf);
+        }
+
+        if options.scrollbar {
+            // Finding the matches searches the whole text after each edit.
+            let pattern = app
+                .search
+                .pattern
+                .as_deref()
+                .filter(|p| !p.is_empty() && !document.is_guarded());
+            Self::render_scrollbar(document, &rows, pattern, area, buf);
+        }
+
+        if options.blame {
+            Self::render_blame(document, options, tabstop, area, buf);
+        }
+
+        if dimmed {
+            buf.set_style(area, Style::default().add_modifier(Modifier::DIM));
+        }
+    }
+
+    /// Draws the scrollbar over the right border of the window at `area`, when the
+    /// document has more lines than `rows` show: a thumb next to the part in view, and
+    /// marks next to the lines matching the search `pattern` or with diagnostics, the
+    /// worst diagnostic taking the color.
+    fn render_scrollbar(
+        document: &Document,
+        rows: &[DisplayRow],
+        pattern: Option<&str>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let inner = Block::bordered().inner(area);
+        let total = document.buffer.len_lines();
+        let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+            return;
+        };
+        if (first.line == 0 && last.line + 1 >= total) || inner.height == 0 || area.width < 2 {
+            return;
+        }
+
+        let x = area.right() - 1;
+        let height = inner.height as usize;
+        let row_of = |line: usize| (line * height / total).min(height - 1);
+
+        let thumb = row_of(first.line)..=row_of(last.line);
+        for row in thumb {
+            buf.set_string(
+                x,
+                inner.y + row as u16,
+                SCROLLBAR_THUMB.to_string(),
+                Style::default().fg(Color::White),
+            );
+        }
+
+        let mut marks: Vec<(usize, Color)> = pattern
+            .map(|pattern| document.match_lines(pattern))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|line| (line, Color::Yellow))
+            .collect();
+        let mut diagnostics = document.diagnostics.iter().collect::<Vec<_>>();
+        diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity));
+        marks.extend(diagnostics.into_iter().map(|diagnostic| {
+            let color = match diagnostic.severity {
+                Severity::Error => Color::Red,
+                Severity::Warning => Color::Yellow,
+                Severity::Information | Severity::Hint => Color::Blue,
+            };
+            (diagnostic.start.line, color)
+        }));
+
+        for (line, color) in marks {
+            buf.set_string(
+                x,
+                inner.y + row_of(line) as u16,
+                SCROLLBAR_MARK.to_string(),
+                Style::default().fg(color),
+            );
+        }
+    }
+
+    /// Draws the blame of the cursor line after its last char, when there is room.
+    fn render_blame(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let line = document.cursor.position.line;
+        let Some(text) = document
+            .blame
+            .as_ref()
+            .filter(|blame| blame.line == line && blame.revision == document.buffer.revision())
+            .and_then(|blame| blame.text.as_deref())
+        else {
+            return;
+        };
+
+        let text_area = Self::text_area(document, options, area);
+        let len = Self::line_len(document, line);
+        let position = Position::new(line, len);
+        let end = Self::screen_position(document, options, tabstop, area, position);
+        // Leave a gap after the text; a full row has no room left.
+        let x = end.x + BLAME_GAP;
+        if x >= text_area.right() {
+            return;
+        }
+
+        let width = (text_area.right() - x) as usize;
+        let text: String = text.chars().take(width).collect();
+
+        buf.set_string(
+            x,
+            end.y,
+            text,
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+        );
+    }
+
+    /// Returns the status line segments describing the file on disk: its size and age
+    /// when enabled, `[RO]` when it cannot be written, and a warning when it was modified
+    /// by another program.
+    fn file_status(document: &Document, options: &Options) -> Option<Line<'static>> {
+        let file = &document.file;
+        let mut spans = vec![];
+
+        if let Some(disk) = file.disk {
+            if options.filesize {
+                spans.push(Span::raw(format!(" {} ", file::format_size(disk.size))));
+            }
+
+            if let Some(age) = disk
+                .modified
+                .filter(|_| options.filetime)
+                .and_then(|modified| modified.elapsed().ok())
+            {
+                spans.push(Span::raw(format!(" {} ", file::format_age(age))));
+            }
+        }
+
+        if document.read_only || file.read_only {
+            spans.push(Span::styled(" [RO] ", Style::default().fg(Color::Red)));
+        }
+
+        if file.noeol {
+            spans.push(Span::styled(
+                " [noeol] ",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+
+        if let Some(guard) = document.guard.filter(|_| document.is_guarded()) {
+            spans.push(Span::styled(
+                format!(" ⚠ {guard}, large file mode "),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
+        if file.changed_on_disk {
+            spans.push(Span::styled(
+                " ⚠ changed on disk ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        (!spans.is_empty()).then(|| Line::from(spans))
+    }
+
+    /// Returns the counts segment, like ` 3 lines, 12 words, 80 chars `, of the selection
+    /// if there is one, or else of the whole buffer.
+    fn word_count(document: &Document, selection: Option<(Position, Position)>) -> String {
+        match selection {
+            Some(_) => format!(" {} selected ", document.buffer.counts(selection)),
+            None => format!(" {} ", document.counts()),
+        }
+    }
+
+    /// Returns the ruler segment, like ` 12/340 (3%) col 5 `, with one-based numbers.
+    fn ruler(document: &Document) -> String {
+        let position = document.cursor.position;
+        let total = document.buffer.len_lines().max(1);
+        let percent = (position.line + 1) * 100 / total;
+
+        format!(
+            " {}/{total} ({percent}%) col {} ",
+            position.line + 1,
+            position.col + 1
+        )
+    }
+
+    /// Returns the rows visible in the editor `area`, from the document scroll down.
+    ///
+    /// Without `wrap` every line is a single row, cut at the edge of the text area. A
+    /// closed fold is a single row showing its first line. Tabs reach the next multiple
+    /// of `tabstop` cells.
+    pub fn display_rows(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+    ) -> Vec<DisplayRow> {
+        let text_area = Self::text_area(document, options, area);
+        let width = text_area.width as usize;
+        let height = text_area.height as usize;
+        let mut rows = vec![];
+        let mut line = document.scroll;
+
+        while line < document.buffer.len_lines() && rows.len() < height {
+            rows.extend(Self::line_rows(document, options, tabstop, width, line));
+            line = document.folds.next_visible(line);
+        }
+
+        rows.truncate(height);
+        rows
+    }
+
+    /// Returns `true` when long lines take several rows: with `wrap` set, unless the
+    /// document is guarded, or in the pretty view of the document.
+    fn wraps(document: &Document, options: &Options) -> bool {
+        (options.wrap && !document.is_guarded()) || document.pretty
+    }
+
+    /// Splits `line` into the rows it takes in a text area `width` cells wide.
+    fn line_rows(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        width: usize,
+        line: usize,
+    ) -> Vec<DisplayRow> {
+        let len = Self::line_len(document, line);
+        let folded = document.folds.closed_at(line).is_some();
+
+        if folded
+            || !Self::wraps(document, options)
+            || width == 0
+            || Self::cells(document, line, 0, len, tabstop) <= width
+        {
+            return vec![DisplayRow {
+                line,
+                start: 0,
+                end: len,
+            }];
+        }
+
+        if document.pretty {
+            let chars: Vec<char> = document
+                .buffer
+                .as_rope()
+                .line(line)
+                .chars()
+                .take(len)
+                .collect();
+
+            return pretty::segments(&chars, width)
+                .into_iter()
+                .map(|(start, end)| DisplayRow { line, start, end })
+                .collect();
+        }
+
+        let mut rows = vec![];
+        let mut start = 0;
+        let mut cells = 0;
+        for (col, c) in Self::line_chars(document, line).take(len).enumerate() {
+            if col > start && cells + cell_width(c, cells, tabstop) > width {
+                rows.push(DisplayRow {
+                    line,
+                    start,
+                    end: col,
+                });
+                start = col;
+                cells = 0;
+            }
+            cells += cell_width(c, cells, tabstop);
+        }
+        rows.push(DisplayRow {
+            line,
+            start,
+            end: len,
+        });
+
+        rows
+    }
+
+    /// Returns the chars of `line`, with its line break, or none past the end of the buffer.
+    fn line_chars(document: &Document, line: usize) -> impl Iterator<Item = char> + '_ {
+        document
+            .buffer
+            .as_rope()
+            .get_line(line)
+            .into_iter()
+            .flat_map(|content| content.chars())
+    }
+
+    /// Returns the number of cells the chars `start..end` of `line` take from the start
+    /// of a row, counting one for each column past the end of the line.
+    fn cells(document: &Document, line: usize, start: usize, end: usize, tabstop: usize) -> usize {
+        let mut chars = Self::line_chars(document, line).skip(start);
+
+        (start..end).fold(0, |cells, _| {
+            cells + cell_width(chars.next().unwrap_or(' '), cells, tabstop)
+        })
+    }
+
+    /// Returns the column of the char of `row` drawn `cells` cells into it. Cells past
+    /// the end of the row count one column each.
+    fn col_at(document: &Document, row: DisplayRow, cells: usize, tabstop: usize) -> usize {
+        let mut drawn = 0;
+        for (col, c) in Self::line_chars(document, row.line)
+            .enumerate()
+            .take(row.end)
+            .skip(row.start)
+        {
+            drawn += cell_width(c, drawn, tabstop);
+            if cells < drawn {
+                return col;
+            }
+        }
+
+        row.end + (cells - drawn)
+    }
+
+    /// Returns the number of chars of `line`, without its line break.
+    fn line_len(document: &Document, line: usize) -> usize {
+        let rope = document.buffer.as_rope();
+        if line >= rope.len_lines() {
+            return 0;
+        }
+
+        let content = rope.line(line);
+        let mut len = content.len_chars();
+        while len > 0 && matches!(content.char(len - 1), '\n' | '\r') {
+            len -= 1;
+        }
+
+        len
+    }
+
+    /// Returns the number of rows from the top of the view down to the one showing
+    /// `position`, inclusive, or `None` when `position` is above the view.
+    pub fn rows_to(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+        position: Position,
+    ) -> Option<usize> {
+        if position.line < document.scroll {
+            return None;
+        }
+
+        let width = Self::text_area(document, options, area).width as usize;
+        let mut above = 0;
+        let mut line = document.scroll;
+        while line < position.line {
+            above += Self::line_rows(document, options, tabstop, width, line).len();
+            line = document.folds.next_visible(line);
+        }
+        let within = Self::line_rows(document, options, tabstop, width, position.line)
+            .iter()
+            .filter(|row| row.start <= position.col && row.start > 0)
+            .count();
+
+        Some(above + within + 1)
+    }
+
+    /// Returns where `j` (`down`) or `k` moves the cursor when moving by display rows.
+    ///
+    /// The screen column within the row is kept, clamped to the length of the target row.
+    pub fn display_move(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+        down: bool,
+    ) -> Position {
+        let width = Self::text_area(document, options, area).width as usize;
+        let position = document.cursor.position;
+        let rows = Self::line_rows(document, options, tabstop, width, position.line);
+        let index = rows
+            .iter()
+            .rposition(|row| row.start <= position.col)
+            .unwrap_or(0);
+        let offset = Self::cells(
+            document,
+            position.line,
+            rows[index].start,
+            position.col,
+            tabstop,
+        );
+
+        let target = if down {
+            let below = document.folds.next_visible(position.line);
+            match rows.get(index + 1) {
+                Some(row) => *row,
+                None if below < document.buffer.len_lines() => {
+                    Self::line_rows(document, options, tabstop, width, below)[0]
+                }
+                None => return position,
+            }
+        } else if index > 0 {
+            rows[index - 1]
+        } else if let Some(above) = document.folds.previous_visible(position.line) {
+            *Self::line_rows(document, options, tabstop, width, above)
+                .last()
+                .expect("a line has at least one row")
+        } else {
+            return position;
+        };
+
+        let col = Self::col_at(document, target, offset, tabstop)
+            .min(target.end.saturating_sub(1).max(target.start));
+
+        Position::new(target.line, col)
+    }
+
+    /// Returns the width of the gutter, which is hidden while there is nothing to show.
+    pub fn gutter_width(document: &Document, options: &Options) -> u16 {
+        Self::sign_width(document) + Self::number_width(document, options)
+    }
+
+    /// Returns the width of the sign columns.
+    ///
+    /// Git changes and diagnostics get one column each, followed by a separating space.
+    fn sign_width(document: &Document) -> u16 {
+        let columns =
+            u16::from(!document.hunks.is_empty()) + u16::from(!document.diagnostics.is_empty());
+
+        if columns == 0 {
+            0
+        } else {
+            columns + GUTTER_PADDING
+        }
+    }
+
+    /// Returns the width of the line number column, which also holds the wrap markers.
+    fn number_width(document: &Document, options: &Options) -> u16 {
+        if options.number {
+            document.buffer.len_lines().to_string().len() as u16 + GUTTER_PADDING
+        } else if Self::wraps(document, options) {
+            1 + GUTTER_PADDING
+        } else {
+            0
+        }
+    }
+
+    /// Returns the gutter column of signs of `kind`: git changes come first.
+    fn sign_column(document: &Document, kind: SignKind) -> u16 {
+        match kind {
+            SignKind::Git(_) => 0,
+            SignKind::Diagnostic(_) => u16::from(!document.hunks.is_empty()),
+        }
+    }
+
+    /// Returns the screen region where the buffer text is drawn, between the gutter and
+    /// the minimap.
+    pub fn text_area(document: &Document, options: &Options, area: Rect) -> Rect {
+        let inner = Block::bordered().inner(area);
+        let gutter = Self::gutter_width(document, options).min(inner.width);
+        let minimap =
+            Self::minimap_area(document, options, area).map_or(0, |minimap| minimap.width);
+
+        Rect {
+            x: inner.x + gutter,
+            width: inner.width - gutter - minimap.min(inner.width - gutter),
+            ..inner
+        }
+    }
+
+    /// Returns the region of the minimap on the right of the window at `area`, when it is
+    /// shown, the window is wide enough for it and the document is not guarded, as drawing
+    /// it walks every line.
+    fn minimap_area(document: &Document, options: &Options, area: Rect) -> Option<Rect> {
+        let inner = Block::bordered().inner(area);
+        let shown = options.minimap && !document.is_guarded();
+
+        (shown && inner.width >= minimap::WIDTH * 3).then(|| Rect {
+            x: inner.right() - minimap::WIDTH,
+            width: minimap::WIDTH,
+            ..inner
+        })
+    }
+
+    /// Returns what lies under the screen cell at `column`, `row`, if it is inside the editor.
+    pub fn hit_test(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+        column: u16,
+        row: u16,
+    ) -> Option<EditorHit> {
+        let text_area = Self::text_area(document, options, area);
+        let gutter_x = text_area.x - Self::gutter_width(document, options);
+
+        if row < text_area.y || row >= text_area.bottom() || column < gutter_x {
+            return None;
+        }
+
+        let rows = Self::display_rows(document, options, tabstop, area);
+        let index = (row - text_area.y) as usize;
+        let display_row = match rows.get(index) {
+            Some(display_row) => *display_row,
+            // Past the end of the buffer, where lines would be without wrapping.
+            None => DisplayRow {
+                line: document.scroll + index,
+                start: 0,
+                end: 0,
+            },
+        };
+        let line = display_row.line;
+
+        if column < text_area.x {
+            Some(EditorHit::Gutter { line })
+        } else if column < text_area.right() {
+            let cells = (column - text_area.x) as usize;
+            Some(EditorHit::Text(Position::new(
+                line,
+                Self::col_at(document, display_row, cells, tabstop),
+            )))
+        } else {
+            let minimap = Self::minimap_area(document, options, area)
+                .filter(|minimap| column >= minimap.x && column < minimap.right())?;
+            Minimap::line_at(document, minimap, row).map(|line| EditorHit::Minimap { line })
+        }
+    }
+
+    /// Returns the screen cell showing `position`, clamped to the visible text area.
+    pub fn screen_position(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+        position: Position,
+    ) -> ratatui::layout::Position {
+        let text_area = Self::text_area(document, options, area);
+        let last_row = text_area.height.saturating_sub(1) as usize;
+        let last_col = text_area.width.saturating_sub(1) as usize;
+
+        let (row, start) = if Self::wraps(document, options) || !document.folds.is_empty() {
+            let row = Self::rows_to(document, options, tabstop, area, position)
+                .map_or(0, |rows| rows - 1);
+            let width = text_area.width as usize;
+            let start = Self::line_rows(document, options, tabstop, width, position.line)
+                .iter()
+                .map(|row| row.start)
+                .filter(|start| *start <= position.col)
+                .max()
+                .unwrap_or(0);
+
+            (row, start)
+        } else {
+            (position.line.saturating_sub(document.scroll), 0)
+        };
+        let col = Self::cells(document, position.line, start, position.col, tabstop);
+
+        ratatui::layout::Position {
+            x: text_area.x + col.min(last_col) as u16,
+            y: text_area.y + row.min(last_row) as u16,
+        }
+    }
+
+    /// Returns the color of a gutter sign and of the underline of its diagnostics.
+    pub fn sign_color(kind: SignKind) -> Color {
+        match kind {
+            SignKind::Diagnostic(Severity::Error) => Color::Red,
+            SignKind::Diagnostic(Severity::Warning) => Color::Yellow,
+            SignKind::Diagnostic(Severity::Information) => Color::Blue,
+            SignKind::Diagnostic(Severity::Hint) => Color::Cyan,
+            SignKind::Git(LineChange::Added) => Color::Green,
+            SignKind::Git(LineChange::Modified) => Color::Blue,
+            SignKind::Git(LineChange::Deleted) => Color::Red,
+        }
+    }
+
+    /// Draws the signs and line numbers next to the first row of each line, and a wrap
+    /// marker next to the rows continuing a wrapped line.
+    fn render_gutter(
+        document: &Document,
+        options: &Options,
+        tabstop: usize,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let width = Self::gutter_width(document, options);
+        if width == 0 {
+            return;
+        }
+
+        let text_area = Self::text_area(document, options, area);
+        let gutter_x = text_area.x - width;
+        let number_x = gutter_x + Self::sign_width(document);
+        let number_width = Self::number_width(document, options).saturating_sub(GUTTER_PADDING);
+        let signs = document.signs();
+
+        for (index, row) in Self::display_rows(document, options, tabstop, area)
+            .iter()
+            .enumerate()
+        {
+            let y = text_area.y + index as u16;
+
+            if row.start > 0 {
+                buf.set_string(
+                    number_x + number_width.saturating_sub(1),
+                    y,
+                    WRAP_MARKER.to_string(),
+                    Style::default().fg(Color::DarkGray),
+                );
+                continue;
+            }
+
+            for sign in signs.iter().filter(|sign| sign.line == row.line) {
+                buf.set_string(
+                    gutter_x + Self::sign_column(document, sign.kind),
+                    y,
+                    sign.kind.symbol().to_string(),
+                    Style::default().fg(Self::sign_color(sign.kind)),
+                );
+            }
+
+            if options.number {
+                buf.set_string(
+                    number_x,
+                    y,
+                    format!("{:>width$}", row.line + 1, width = number_width as usize),
+                    Style::default().fg(Color::DarkGray),
+                );
+            }
+        }
+    }
+
+    /// Computes the style of every char of a line: ANSI colors first, then diagnostics
+    /// underline and the selection reverses.
+    fn line_styles(
+        document: &Document,
+        line: usize,
+        content: &str,
+        selection: Option<(Position, Position)>,
+    ) -> Vec<Style> {
+        let selected = Self::selected_cols(line, selection);
+        // Looking up colors and diagnostics for every char is too slow on huge lines.
+        let guarded = document.is_guarded();
+        let colors = if guarded {
+            &[]
+        } else {
+            document.line_colors(line)
+        };
+
+        (0..content.chars().count())
+            .map(|col| {
+                let mut style = colors
+                    .iter()
+                    .find(|span| span.start <= col && col < span.end)
+                    .map_or(Style::default(), |span| span.style);
+                let position = Position::new(line, col);
+
+                if let Some(diagnostic) = document
+                    .diagnostics
+                    .iter()
+                    .filter(|_| !guarded)
+                    .filter(|diagnostic| diagnostic.contains(position))
+                    .min_by_key(|diagnostic| diagnostic.severity)
+                {
+                    style =
+                        style
+                            .add_modifier(Modifier::UNDERLINED)
+                            .underline_color(Self::sign_color(SignKind::Diagnostic(
+                                diagnostic.severity,
+                            )));
+                }
+
+                if selected.is_some_and(|(from, to)| from <= col && col <= to) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+
+                style
+            })
+            .collect()
+    }
+
+    /// Returns the inclusive column span of `line` covered by `selection`, if any.
+    fn selected_cols(
+        line: usize,
+        selection: Option<(Position, Position)>,
+    ) -> Option<(usize, usize)> {
+        let (start, end) = selection?;
+
+        if line < start.line || line > end.line {
+            return None;
+        }
+
+        let from = if line == start.line { start.col } else { 0 };
+        let to = if line == end.line {
+            end.col
+        } else {
+            usize::MAX
+        };
+
+        Some((from, to))
+    }
+
+    /// Builds a line out of runs of chars sharing the same style, drawing control chars
+    /// as their placeholders and tabs as spaces up to the next multiple of `tabstop`.
+    fn styled_line(content: &str, styles: &[Style], tabstop: usize) -> Line<'static> {
+        let mut spans: Vec<Span> = vec![];
+        let mut run = String::new();
+        let mut run_style = Style::default();
+        let mut cells = 0;
+
+        for (c, style) in content.chars().zip(styles.iter().copied()) {
+            let width = cell_width(c, cells, tabstop);
+            cells += width;
+            let (c, style) = match control_picture(c) {
+                Some(picture) => (picture, style.patch(CONTROL_STYLE)),
+                None => (c, style),
+            };
+
+            if style != run_style && !run.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut run), run_style));
+            }
+
+            run_style = style;
+            match c {
+                '\t' => run.extend(std::iter::repeat_n(' ', width)),
+                c => run.push(c),
+            }
+        }
+
+        if !run.is_empty() {
+            spans.push(Span::styled(run, run_style));
+        }
+
+        Line::from(spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::diagnostics::Diagnostic;
+    use crate::app::guard::Limits;
+
+    fn document_with_diagnostic() -> Document {
+        let mut document = Document::new(String::from("let x = 1;\nlet y;"), None);
+        document.diagnostics = vec![Diagnostic {
+            start: Position::new(1, 4),
+            end: Position::new(1, 5),
+            severity: Severity::Warning,
+            message: String::from("unused"),
+        }];
+
+        document
+    }
+
+    #[test]
+    fn should_compute_selected_cols_for_each_line() {
+        let selection = Some((Position::new(1, 2), Position::new(3, 4)));
+
+        assert_eq!(Editor::selected_cols(0, selection), None);
+        assert_eq!(Editor::selected_cols(1, selection), Some((2, usize::MAX)));
+        assert_eq!(Editor::selected_cols(2, selection), Some((0, usize::MAX)));
+        assert_eq!(Editor::selected_cols(3, selection), Some((0, 4)));
+        assert_eq!(Editor::selected_cols(4, selection), None);
+    }
+
+    #[test]
+    fn should_split_line_around_selection() {
+        let document = Document::new(String::from("abcdef"), None);
+        let styles = Editor::line_styles(
+            &document,
+            0,
+            "abcdef",
+            Some((Position::new(0, 1), Position::new(0, 3))),
+        );
+        let line = Editor::styled_line("abcdef", &styles, 4);
+
+        let contents: Vec<_> = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+
+        assert_eq!(contents, vec!["a", "bcd", "ef"]);
+        assert!(
+            line.spans[1]
+                .style
+                .add_modifier
+                .contains(Modifier::REVERSED)
+        );
+    }
+
+    #[test]
+    fn should_draw_control_chars_as_placeholders() {
+        let line = Editor::styled_line("a\x0cb\x1b\t", &[Style::default(); 5], 4);
+
+        let contents: Vec<_> = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(contents, vec!["a", "␌", "b", "␛", "    "]);
+        assert_eq!(line.spans[1].style, CONTROL_STYLE);
+        assert_eq!(control_picture('\x7f'), Some('␡'));
+        assert_eq!(control_picture('\u{9b}'), Some(char::REPLACEMENT_CHARACTER));
+    }
+
+    #[test]
+    fn should_underline_diagnostic_ranges() {
+        let document = document_with_diagnostic();
+
+        let styles = Editor::line_styles(&document, 1, "let y;", None);
+
+        assert!(!styles[3].add_modifier.contains(Modifier::UNDERLINED));
+        assert!(styles[4].add_modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(styles[4].underline_color, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn should_apply_ansi_colors() {
+        let mut document = Document::new(String::from("ok!"), None);
+        document.colors = vec![crate::app::ansi::ColorSpan {
+            line: 0,
+            start: 0,
+            end: 2,
+            style: Style::default().fg(Color::Green),
+        }];
+
+        let styles = Editor::line_styles(&document, 0, "ok!", None);
+
+        assert_eq!(styles[1].fg, Some(Color::Green));
+        assert_eq!(styles[2].fg, None);
+    }
+
+    #[test]
+    fn should_reserve_gutter_only_when_there_are_signs() {
+        let area = Rect::new(0, 0, 20, 10);
+
+        let plain = Document::new(String::new(), None);
+        let with_signs = document_with_diagnostic();
+
+        assert_eq!(Editor::text_area(&plain, &Options::new(), area).x, 1);
+        assert_eq!(Editor::text_area(&with_signs, &Options::new(), area).x, 3);
+    }
+
+    #[test]
+    fn should_draw_git_signs_before_diagnostics() {
+        let area = Rect::new(0, 0, 20, 5);
+        let mut document = document_with_diagnostic();
+        document.git_base = Some(String::from("let x = 1;\n"));
+        document.update_hunks();
+        let mut buf = Buffer::empty(area);
+
+        Editor::render_gutter(&document, &Options::new(), 4, area, &mut buf);
+
+        assert_eq!(Editor::text_area(&document, &Options::new(), area).x, 4);
+        assert_eq!(buf[(1, 2)].symbol(), "+");
+        assert_eq!(buf[(2, 2)].symbol(), "W");
+    }
+
+    #[test]
+    fn should_map_screen_cells_to_gutter_and_text() {
+        let area = Rect::new(0, 0, 20, 10);
+        let document = document_with_diagnostic();
+
+        assert_eq!(
+            Editor::hit_test(&document, &Options::new(), 4, area, 1, 2),
+            Some(EditorHit::Gutter { line: 1 })
+        );
+        assert_eq!(
+            Editor::hit_test(&document, &Options::new(), 4, area, 7, 2),
+            Some(EditorHit::Text(Position::new(1, 4)))
+        );
+        assert_eq!(
+            Editor::hit_test(&document, &Options::new(), 4, area, 0, 2),
+            None
+        );
+        assert_eq!(
+            Editor::hit_test(&document, &Options::new(), 4, area, 5, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn should_account_for_scroll_when_mapping_positions() {
+        let area = Rect::new(0, 0, 20, 10);
+        let mut document = Document::new(String::from("a\nb\nc\nd"), None);
+        document.scroll = 2;
+
+        assert_eq!(
+            Editor::hit_test(&document, &Options::new(), 4, area, 1, 1),
+            Some(EditorHit::Text(Position::new(2, 0)))
+        );
+        assert_eq!(
+            Editor::screen_position(&document, &Options::new(), 4, area, Position::new(3, 0)),
+            ratatui::layout::Position { x: 1, y: 2 }
+        );
+    }
+
+    fn wrapping() -> Options {
+        let mut options = Options::new();
+        options.wrap = true;
+        options
+    }
+
+    #[test]
+    fn should_split_long_lines_into_rows_when_wrapping() {
+        // Text area of 5 columns: the border and the wrap marker take the rest.
+        let area = Rect::new(0, 0, 9, 5);
+        let document = Document::new(String::from("abcdefghijkl\nxy"), None);
+
+        let rows = Editor::display_rows(&document, &wrapping(), 4, area);
+        let unwrapped = Editor::display_rows(&document, &Options::new(), 4, area);
+
+        assert_eq!(
+            rows,
+            vec![
+                DisplayRow {
+                    line: 0,
+                    start: 0,
+                    end: 5
+                },
+                DisplayRow {
+                    line: 0,
+                    start: 5,
+                    end: 10
+                },
+                DisplayRow {
+                    line: 0,
+                    start: 10,
+                    end: 12
+                },
+            ]
+        );
+        assert_eq!(unwrapped.len(), 2);
+        assert_eq!(
+            Editor::screen_position(&document, &wrapping(), 4, area, Position::new(0, 7)),
+            ratatui::layout::Position { x: 5, y: 2 }
+        );
+        assert_eq!(
+            Editor::hit_test(&document, &wrapping(), 4, area, 4, 3),
+            Some(EditorHit::Text(Position::new(0, 11)))
+        );
+    }
+
+    #[test]
+    fn should_expand_tabs_to_the_next_tabstop() {
+        // Text area of 5 columns, starting at x 3.
+        let area = Rect::new(0, 0, 9, 5);
+        let document = Document::new(String::from("ab\tcdef\tg"), None);
+
+        let rows = Editor::display_rows(&document, &wrapping(), 4, area);
+
+        assert_eq!(
+            rows,
+            vec![
+                DisplayRow {
+                    line: 0,
+                    start: 0,
+                    end: 4
+                },
+                DisplayRow {
+                    line: 0,
+                    start: 4,
+                    end: 9
+                },
+            ]
+        );
+        assert_eq!(
+            Editor::screen_position(&document, &wrapping(), 4, area, Position::new(0, 3)),
+            ratatui::layout::Position { x: 7, y: 1 }
+        );
+        assert_eq!(
+            Editor::screen_position(&document, &wrapping(), 4, area, Position::new(0, 8)),
+            ratatui::layout::Position { x: 7, y: 2 }
+        );
+        assert_eq!(
+            Editor::hit_test(&document, &wrapping(), 4, area, 6, 1),
+            Some(EditorHit::Text(Position::new(0, 2)))
+        );
+        assert_eq!(
+            Editor::hit_test(&document, &wrapping(), 4, area, 7, 1),
+            Some(EditorHit::Text(Position::new(0, 3)))
+        );
+    }
+
+    #[test]
+    fn should_not_wrap_guarded_documents() {
+        let area = Rect::new(0, 0, 9, 5);
+        let mut document = Document::new(String::from("abcdefghijkl\nxy"), None);
+        document.update_guard(Limits {
+            max_size: 0,
+            max_line: 10,
+        });
+
+        assert_eq!(
+            Editor::display_rows(&document, &wrapping(), 4, area).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn should_split_lines_at_synthetic_breaks_in_pretty_view() {
+        let area = Rect::new(0, 0, 10, 6);
+        let mut document = Document::new(String::from("a=1;if(b){c}"), None);
+        document.pretty = true;
+
+        let rows = Editor::display_rows(&document, &Options::new(), 4, area);
+
+        let starts: Vec<usize> = rows.iter().map(|row| row.start).collect();
+        assert_eq!(starts, vec![0, 4, 10]);
+        assert_eq!(
+            Editor::screen_position(&document, &Options::new(), 4, area, Position::new(0, 11)),
+            ratatui::layout::Position { x: 4, y: 3 }
+        );
+    }
+
+    #[test]
+    fn should_number_logical_lines_and_mark_wrapped_rows() {
+        let area = Rect::new(0, 0, 10, 6);
+        let document = Document::new(String::from("abcdefghij\nxy"), None);
+        let mut options = wrapping();
+        options.number = true;
+        let mut buf = Buffer::empty(area);
+
+        Editor::render_gutter(&document, &options, 4, area, &mut buf);
+
+        assert_eq!(buf[(1, 1)].symbol(), "1");
+        assert_eq!(buf[(1, 2)].symbol(), "↪");
+        assert_eq!(buf[(1, 3)].symbol(), "2");
+    }
+
+    #[test]
+    fn should_draw_blame_after_the_cursor_line() {
+        let area = Rect::new(0, 0, 30, 4);
+        let mut document = Document::new(String::from("let x;"), None);
+        document.blame = Some(crate::app::buffer_manager::LineBlame {
+            line: 0,
+            revision: 0,
+            text: Some(String::from("Jane, 2d ago • Init")),
+        });
+        let mut buf = Buffer::empty(area);
+
+        Editor::render_blame(&document, &Options::new(), 4, area, &mut buf);
+
+        let row: String = (0..30).map(|x| buf[(x, 1)].symbol()).collect();
+        assert_eq!(row.trim(), "Jane, 2d ago • Init");
+        assert_eq!(buf[(10, 1)].symbol(), "J");
+    }
+
+    #[test]
+    fn should_describe_cursor_position_in_ruler() {
+        let mut document = Document::new(String::from("a\nb\nc\nd"), None);
+        document.cursor.position = Position::new(1, 0);
+
+        assert_eq!(Editor::ruler(&document), " 2/4 (50%) col 1 ");
+    }
+
+    #[test]
+    fn should_show_enabled_file_segments_and_disk_changes() {
+        let mut document = Document::new(String::new(), None);
+        document.file.disk = Some(crate::app::file::DiskInfo {
+            size: 2048,
+            modified: None,
+        });
+        let mut options = Options::new();
+
+        assert_eq!(Editor::file_status(&document, &options), None);
+
+        options.filesize = true;
+        document.file.changed_on_disk = true;
+        let status = Editor::file_status(&document, &options).unwrap();
+
+        assert_eq!(status.to_string(), " 2.0 KiB  ⚠ changed on disk ");
+
+        document.file.read_only = true;
+        let status = Editor::file_status(&document, &options).unwrap();
+        assert_eq!(status.to_string(), " 2.0 KiB  [RO]  ⚠ changed on disk ");
+    }
+
+    #[test]
+    fn should_move_by_display_rows() {
+        let area = Rect::new(0, 0, 9, 5);
+        let mut document = Document::new(String::from("abcdefghijkl\nxy"), None);
+        document.cursor.position = Position::new(0, 2);
+
+        let down = Editor::display_move(&document, &wrapping(), 4, area, true);
+        assert_eq!(down, Position::new(0, 7));
+
+        document.cursor.position = Position::new(0, 11);
+        assert_eq!(
+            Editor::display_move(&document, &wrapping(), 4, area, true),
+            Position::new(1, 1)
+        );
+
+        document.cursor.position = Position::new(1, 0);
+        assert_eq!(
+            Editor::display_move(&document, &wrapping(), 4, area, false),
+            Position::new(0, 10)
+        );
     }
 }
@@ -0,0 +1,106 @@
+//! Latency overlay rendering logic.
+//!
+//! This module defines the `LatencyOverlay` component, a box in the top right corner of
+//! the editor graphing the key-to-frame latency of the last frames, above the time spent
+//! in each stage of the editor loop and the state of the event queue, the active rope
+//! and the viewport.
+
+use crate::app::App;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, Paragraph, Sparkline, Widget},
+};
+
+/// Width of the box, borders included.
+const WIDTH: u16 = 40;
+
+/// Rows of the latency graph.
+const GRAPH_HEIGHT: u16 = 3;
+
+/// The `LatencyOverlay` component responsible for drawing latency diagnostics.
+pub struct LatencyOverlay;
+
+impl LatencyOverlay {
+    /// Renders the diagnostics of `app` in the top right corner of `area`.
+    pub fn render(app: &App, area: Rect, buf: &mut Buffer) {
+        let latency = &app.latency;
+        let summary: Vec<Line> = latency
+            .summary()
+            .into_iter()
+            .chain(Self::stats(app))
+            .map(Line::from)
+            .collect();
+        let height = (GRAPH_HEIGHT + summary.len() as u16 + 2).min(area.height);
+        let width = WIDTH.min(area.width);
+        let overlay = Rect {
+            x: area.right().saturating_sub(width),
+            y: area.y,
+            width,
+            height,
+        };
+        let block = Block::bordered()
+            .border_type(BorderType::Plain)
+            .title(" debug (F12) ");
+        let inner = block.inner(overlay);
+
+        Clear.render(overlay, buf);
+        block.fg(Color::DarkGray).render(overlay, buf);
+
+        let micros = latency.input_micros();
+        let shown = &micros[micros.len().saturating_sub(inner.width as usize)..];
+        Sparkline::default().data(shown).fg(Color::Yellow).render(
+            Rect {
+                height: GRAPH_HEIGHT.min(inner.height),
+                ..inner
+            },
+            buf,
+        );
+
+        Paragraph::new(summary).fg(Color::Gray).render(
+            Rect {
+                y: inner.y + GRAPH_HEIGHT,
+                height: inner.height.saturating_sub(GRAPH_HEIGHT),
+                ..inner
+            },
+            buf,
+        );
+    }
+
+    /// Describes the frame rate, the events and the queue, the active rope and the
+    /// viewport.
+    fn stats(app: &App) -> Vec<String> {
+        let latency = &app.latency;
+        let document = app.buffers.active();
+        let rope = document.buffer.as_rope();
+        let view = app
+            .editor_area()
+            .map(|area| app.windows.focused_area(area))
+            .unwrap_or_default();
+
+        vec![
+            format!(
+                "fps {:>6.1}  events/frame {:>5}",
+                latency.fps(),
+                latency.events_per_frame()
+            ),
+            format!("queued input {:>5}", app.event_handler.queued_input()),
+            format!("last {}", latency.last_event().unwrap_or("-")),
+            format!(
+                "rope {} lines {} chars {} B",
+                rope.len_lines(),
+                rope.len_chars(),
+                rope.len_bytes()
+            ),
+            format!(
+                "view top {} {}x{} rev {}",
+                document.scroll,
+                view.width,
+                view.height,
+                document.buffer.revision()
+            ),
+        ]
+    }
+}
@@ -0,0 +1,219 @@
+//! Message pane component rendering logic.
+//!
+//! This module defines the `MessagePane` component, a scrollable box covering the
+//! lower half of the editor. It shows long text, like the output of `:!cmd` or a diff,
+//! until it is dismissed. Secrets in the text are masked (see [`redact`]) and control
+//! chars are drawn as placeholders.
+
+use crate::app::diff::{DiffKind, DiffLine};
+use crate::app::redact;
+use crate::event::AppEvent;
+use crate::ui::components::{FocusableComponent, editor};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, Paragraph, Widget},
+};
+
+/// Lines moved by half-page scrolling.
+const HALF_PAGE: usize = 10;
+
+/// Returns `line` with its control chars, like the escapes of colored command output,
+/// replaced by their placeholders.
+fn printable(line: &str) -> String {
+    line.chars()
+        .map(|c| editor::control_picture(c).unwrap_or(c))
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct MessagePane {
+    /// Shown in the border of the pane.
+    pub title: String,
+    /// The text, split into lines.
+    pub lines: Vec<String>,
+    /// Style of each line; missing entries use the default style.
+    pub styles: Vec<Style>,
+    /// Index of the first visible line.
+    pub scroll: usize,
+}
+
+impl MessagePane {
+    /// Creates a new, empty `MessagePane`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the content of the pane, scrolled to the top.
+    pub fn open(&mut self, title: String, text: &str) {
+        self.title = title;
+        self.lines = redact::redact(text).lines().map(printable).collect();
+        self.styles.clear();
+        self.scroll = 0;
+    }
+
+    /// Replaces the content of the pane with a diff, coloring additions and removals.
+    pub fn open_diff(&mut self, title: String, lines: Vec<DiffLine>) {
+        self.title = title;
+        self.styles = lines
+            .iter()
+            .map(|line| Self::diff_style(line.kind))
+            .collect();
+        let text: Vec<String> = lines.into_iter().map(|line| line.text).collect();
+        self.lines = redact::redact(&text.join("\n"))
+            .split('\n')
+            .map(printable)
+            .collect();
+        self.scroll = 0;
+    }
+
+    fn diff_style(kind: DiffKind) -> Style {
+        match kind {
+            DiffKind::Header => Style::default().fg(Color::Cyan),
+            DiffKind::Context => Style::default(),
+            DiffKind::Added => Style::default().fg(Color::Green),
+            DiffKind::Removed => Style::default().fg(Color::Red),
+            DiffKind::Changed => Style::default().fg(Color::Yellow),
+        }
+    }
+
+    /// Handles a key event while the pane is active.
+    ///
+    /// Returns a vector of `AppEvent`s that may trigger further actions:
+    /// - `j`/`Down` and `k`/`Up`: Scroll by one line.
+    /// - `Ctrl + D`/`PageDown` and `Ctrl + U`/`PageUp`: Scroll by half a page.
+    /// - `g` and `G`: Jump to the top or the bottom.
+    /// - `q`, `Esc`, `Enter` or `F1`: Close the pane and return focus to the editor.
+    ///
+    /// Other keys are ignored.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<AppEvent> {
+        let last = self.lines.len().saturating_sub(1);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter | KeyCode::F(1) => {
+                return vec![AppEvent::ChangeFocus(FocusableComponent::Editor)];
+            }
+            KeyCode::Char('d') if ctrl => self.scroll = (self.scroll + HALF_PAGE).min(last),
+            KeyCode::PageDown => self.scroll = (self.scroll + HALF_PAGE).min(last),
+            KeyCode::Char('u') if ctrl => self.scroll = self.scroll.saturating_sub(HALF_PAGE),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(HALF_PAGE),
+            KeyCode::Char('j') | KeyCode::Down => self.scroll = (self.scroll + 1).min(last),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Char('g') => self.scroll = 0,
+            KeyCode::Char('G') => self.scroll = last,
+            _ => {}
+        }
+
+        vec![]
+    }
+
+    /// Renders the pane over the lower half of the editor.
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let height = (area.height / 2).max(3).min(area.height);
+        let pane = Rect {
+            x: area.x,
+            y: area.bottom() - height,
+            width: area.width,
+            height,
+        };
+
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .map(|(index, line)| {
+                let style = self.styles.get(index).copied().unwrap_or_default();
+                Line::styled(line.as_str(), style)
+            })
+            .collect();
+        let position = format!(" {}/{} ", self.scroll + 1, self.lines.len().max(1));
+
+        Clear.render(pane, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .title(self.title.as_str())
+                    .title_bottom(Line::from(position).alignment(Alignment::Right))
+                    .title_bottom(Line::from(" q to close ").alignment(Alignment::Left)),
+            )
+            .fg(Color::Gray)
+            .bg(Color::Reset)
+            .render(pane, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn pane_with_lines(count: usize) -> MessagePane {
+        let mut pane = MessagePane::new();
+        let text: Vec<String> = (0..count).map(|n| n.to_string()).collect();
+        pane.open(String::from("output"), &text.join("\n"));
+
+        pane
+    }
+
+    #[test]
+    fn should_scroll_within_bounds() {
+        let mut pane = pane_with_lines(3);
+
+        pane.handle_key(key(KeyCode::Char('k')));
+        assert_eq!(pane.scroll, 0);
+
+        pane.handle_key(key(KeyCode::Char('j')));
+        pane.handle_key(key(KeyCode::PageDown));
+        assert_eq!(pane.scroll, 2);
+
+        pane.handle_key(key(KeyCode::Char('g')));
+        assert_eq!(pane.scroll, 0);
+    }
+
+    #[test]
+    fn should_color_diff_lines_by_kind() {
+        let mut pane = pane_with_lines(2);
+
+        pane.open_diff(
+            String::from(":diff"),
+            vec![
+                DiffLine {
+                    kind: DiffKind::Removed,
+                    text: String::from("-old"),
+                },
+                DiffLine {
+                    kind: DiffKind::Added,
+                    text: String::from("+new"),
+                },
+            ],
+        );
+
+        assert_eq!(pane.lines, vec!["-old", "+new"]);
+        assert_eq!(pane.styles[0].fg, Some(Color::Red));
+        assert_eq!(pane.styles[1].fg, Some(Color::Green));
+
+        pane.open(String::from("output"), "plain");
+        assert!(pane.styles.is_empty());
+    }
+
+    #[test]
+    fn should_close_on_q() {
+        let mut pane = pane_with_lines(1);
+
+        let events = pane.handle_key(key(KeyCode::Char('q')));
+
+        assert_eq!(
+            events,
+            vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Minimap component rendering logic.
+//!
+//! This module defines the `Minimap` component, shown with `:set minimap`: a column on
+//! the right of each window drawing the whole buffer in braille, each cell two dots wide
+//! and four tall. A dot stands for a few columns of a line and is set when they hold
+//! anything but blanks, so the shape of the code shows at a glance; long buffers give
+//! each dot row several lines, sampling the first. The rows of the lines in view are
+//! highlighted, and clicking a row jumps to its lines.
+
+use crate::app::buffer_manager::Document;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+};
+
+/// Width of the minimap, in cells.
+pub const WIDTH: u16 = 10;
+
+/// Text columns each dot stands for.
+const COLS_PER_DOT: usize = 4;
+
+/// Dots of a braille cell, by row then column.
+const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// First braille pattern, with no dot set.
+const BRAILLE: u32 = 0x2800;
+
+/// The `Minimap` component drawing a compressed overview of a buffer.
+pub struct Minimap;
+
+impl Minimap {
+    /// Returns how many lines each row of dots stands for, to fit `total` lines in
+    /// `height` cells.
+    fn lines_per_dot(total: usize, height: u16) -> usize {
+        let dot_rows = (height as usize * DOTS.len()).max(1);
+        total.div_ceil(dot_rows).max(1)
+    }
+
+    /// Returns the lines of the cell row `row` of a minimap `height` cells tall.
+    fn lines_of_row(total: usize, height: u16, row: u16) -> std::ops::Range<usize> {
+        let lines = Self::lines_per_dot(total, height) * DOTS.len();
+        let start = row as usize * lines;
+        start.min(total)..(start + lines).min(total)
+    }
+
+    /// Returns the line a click on `row` of the minimap at `area` jumps to, if the row
+    /// shows any.
+    pub fn line_at(document: &Document, area: Rect, row: u16) -> Option<usize> {
+        let total = document.buffer.len_lines();
+        let lines = Self::lines_of_row(total, area.height, row.checked_sub(area.y)?);
+        (!lines.is_empty()).then_some(lines.start)
+    }
+
+    /// Returns the braille cells of the minimap of `document`, `height` rows of `WIDTH`.
+    fn cells(document: &Document, height: u16) -> Vec<String> {
+        let rope = document.buffer.as_rope();
+        let total = rope.len_lines();
+        let per_dot = Self::lines_per_dot(total, height);
+        let dot_cols = WIDTH as usize * 2;
+
+        (0..height as usize)
+            .map(|row| {
+                let mut cells = vec![0; WIDTH as usize];
+
+                for (dot_row, dots) in DOTS.iter().enumerate() {
+                    let line = (row * DOTS.len() + dot_row) * per_dot;
+                    let Some(text) = rope.get_line(line) else {
+                        break;
+                    };
+
+                    let chars: Vec<char> = text.chars().take(dot_cols * COLS_PER_DOT).collect();
+                    for (dot_col, span) in chars.chunks(COLS_PER_DOT).enumerate() {
+                        if span.iter().any(|c| !c.is_whitespace()) {
+                            cells[dot_col / 2] |= dots[dot_col % 2];
+                        }
+                    }
+                }
+
+                cells
+                    .into_iter()
+                    .map(|cell| char::from_u32(BRAILLE + cell).unwrap_or(' '))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Renders the minimap of `document` in `area`, highlighting the rows of the lines
+    /// `first..=last` in view.
+    pub fn render(document: &Document, first: usize, last: usize, area: Rect, buf: &mut Buffer) {
+        let total = document.buffer.len_lines();
+
+        for (row, cells) in Self::cells(document, area.height).into_iter().enumerate() {
+            let lines = Self::lines_of_row(total, area.height, row as u16);
+            let in_view = lines.start <= last && first < lines.end;
+            let style = match in_view {
+                true => Style::default().fg(Color::Gray).bg(Color::DarkGray),
+                false => Style::default().fg(Color::DarkGray),
+            };
+
+            buf.set_string(area.x, area.y + row as u16, cells, style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_draw_the_shape_of_the_text_in_braille() {
+        let document = Document::new(String::from("fn main() {\n    x\n\n}\n"), None);
+
+        let cells = Minimap::cells(&document, 2);
+        // `fn main() {` sets the top dots of its three chunks, `x`, indented by four, the
+        // second dot of the right column, and `}` the bottom dot of the left one.
+        assert_eq!(cells[0].chars().take(3).collect::<String>(), "⡙⠁⠀");
+        assert_eq!(cells[1], "⠀".repeat(WIDTH as usize));
+    }
+
+    #[test]
+    fn should_give_each_row_several_lines_in_long_buffers() {
+        let text = "x\n".repeat(100);
+        let document = Document::new(text, None);
+        let area = Rect::new(70, 1, WIDTH, 10);
+
+        // 101 lines on 40 dot rows: 3 lines per dot, 12 per cell row.
+        assert_eq!(Minimap::lines_per_dot(101, 10), 3);
+        assert_eq!(Minimap::line_at(&document, area, 3), Some(24));
+        assert_eq!(Minimap::line_at(&document, area, 10), None);
+    }
+}
@@ -1,19 +1,69 @@
 //! UI components for the Zack text editor.
 //!
-//! This module exposes individual UI components, like the main editor and the filename prompt.
+//! This module exposes individual UI components, like the main editor, the prompt and
+//! the command line.
 //! It also defines [`FocusableComponent`], an enum used by the application state to determine
 //! which component is currently focused.
 
+/// The command line component, used for `:` commands and `/` searches.
+pub mod command_line;
+
+/// The yes/no prompt used to confirm actions.
+pub mod confirm_prompt;
+
+/// The completion popup shown while completing a word in insert mode.
+pub mod completion_menu;
+
+/// The Yes/No/Cancel dialog asking before discarding or overwriting work.
+pub mod dialog;
+
 /// The editor component responsible for text editing.
 pub mod editor;
 
-/// The filename prompt component, used for saving or naming files.
-pub mod filename_prompt;
+/// The braille overview of the buffer on the right of each window, shown with
+/// `:set minimap`.
+pub mod minimap;
+
+/// The scrollable pane showing long messages, like command output.
+pub mod message_pane;
+
+/// The overlay graphing the latency of the editor loop, toggled with `F12`.
+pub mod latency_overlay;
+
+/// The bar showing the latest notification at the bottom of the editor.
+pub mod notification_bar;
+
+/// The floating boxes drawn over the editor, like menus, tooltips and dialogs.
+pub mod popup;
+
+/// The prompt component, used to ask for a file name or a line number.
+pub mod prompt;
+
+/// The bar naming the tab pages at the top of the editor.
+pub mod tab_bar;
+
+/// The split at the bottom of the editor running a shell, opened with `:terminal`.
+pub mod terminal_pane;
+
+/// The single-line input with a cursor shared by the prompts.
+pub mod text_input;
+
+/// The popup showing hover tooltips and sign details.
+pub mod tooltip;
+
+/// The side panel listing the states of the undo history, opened with `:undotree`.
+pub mod undo_panel;
 
 /// Represents which component in the UI currently has focus.
 /// Used by the main [`App`](crate::app::App) state to direct user input and rendering.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum FocusableComponent {
     Editor,
-    FilenamePrompt,
+    Prompt,
+    CommandLine,
+    ConfirmPrompt,
+    Dialog,
+    MessagePane,
+    UndoPanel,
+    TerminalPane,
 }
@@ -0,0 +1,52 @@
+//! Notification bar rendering logic.
+//!
+//! This module defines the `NotificationBar` component, which shows the latest
+//! [`Notification`] on the last text row of the editor, where the command line opens,
+//! colored by its level and dimmed while it fades out.
+
+use crate::app::notifications::{Level, Notification};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Paragraph, Widget},
+};
+use std::time::Instant;
+
+/// The `NotificationBar` component responsible for drawing notifications.
+pub struct NotificationBar;
+
+impl NotificationBar {
+    /// Renders `notification` as it looks at `now` inside `area`.
+    pub fn render(notification: &Notification, now: Instant, area: Rect, buf: &mut Buffer) {
+        let text = notification.text.lines().next().unwrap_or_default();
+
+        Paragraph::new(text)
+            .style(Self::style(notification, now))
+            .render(Self::bar_area(area), buf);
+    }
+
+    /// Returns the colors of `notification` at `now`.
+    fn style(notification: &Notification, now: Instant) -> Style {
+        let style = match notification.level {
+            Level::Info => Style::default().fg(Color::White),
+            Level::Warn => Style::default().fg(Color::Black).bg(Color::Yellow),
+            Level::Error => Style::default().fg(Color::White).bg(Color::Red),
+        };
+
+        if notification.is_fading(now) {
+            style.add_modifier(Modifier::DIM)
+        } else {
+            style
+        }
+    }
+
+    fn bar_area(area: Rect) -> Rect {
+        Rect {
+            x: area.x + 1,
+            y: area.y + area.height.saturating_sub(2),
+            width: area.width.saturating_sub(2),
+            height: 1,
+        }
+    }
+}
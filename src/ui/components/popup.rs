@@ -0,0 +1,213 @@
+//! Floating popup rendering logic.
+//!
+//! This module defines [`Popup`], a bordered box of lines floating over the editor,
+//! and [`Overlay`], the popups of a frame drawn in the order of their [`Layer`], so
+//! a tooltip covers the completion menu and a dialog covers both. Components build a
+//! popup with its [`Anchor`] and size limits instead of placing and clearing boxes
+//! themselves; the popup is moved back inside the screen when it would overflow.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    style::{Color, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, Paragraph, Widget},
+};
+
+/// Where a popup is placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    /// Just below the screen cell, or above it when there is no room below.
+    Cell(Position),
+    /// In the middle of the screen.
+    Center,
+}
+
+/// How high a popup is stacked; higher layers are drawn over lower ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    /// Menus following the cursor, like the completion popup.
+    #[default]
+    Menu,
+    /// Tooltips shown by the mouse.
+    Tooltip,
+    /// Dialogs waiting for an answer.
+    Dialog,
+}
+
+/// A bordered box of lines floating over the editor.
+#[derive(Clone, Debug)]
+pub struct Popup<'a> {
+    lines: Vec<Line<'a>>,
+    anchor: Anchor,
+    layer: Layer,
+    title: Option<String>,
+    max_width: u16,
+    max_height: u16,
+}
+
+/// The popups of a frame, drawn lowest layer first.
+#[derive(Debug, Default)]
+pub struct Overlay<'a> {
+    popups: Vec<Popup<'a>>,
+}
+
+impl<'a> Popup<'a> {
+    /// Creates a popup showing `lines` at `anchor`, on the menu layer, as large as its
+    /// lines.
+    pub fn new(lines: Vec<Line<'a>>, anchor: Anchor) -> Self {
+        Self {
+            lines,
+            anchor,
+            layer: Layer::default(),
+            title: None,
+            max_width: u16::MAX,
+            max_height: u16::MAX,
+        }
+    }
+
+    /// Stacks the popup on `layer`.
+    pub fn layer(mut self, layer: Layer) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Titles the top border with `title`.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Limits the popup, borders included, to `width` x `height` cells.
+    pub fn max_size(mut self, width: u16, height: u16) -> Self {
+        self.max_width = width;
+        self.max_height = height;
+        self
+    }
+
+    /// Returns where the popup is drawn inside `area`.
+    pub fn area(&self, area: Rect) -> Rect {
+        let title = self.title.as_ref().map_or(0, |title| title.chars().count());
+        let content = self.lines.iter().map(Line::width).max().unwrap_or(0);
+        let width = (content.max(title) as u16 + 2)
+            .min(self.max_width)
+            .min(area.width);
+        let height = (self.lines.len() as u16 + 2)
+            .min(self.max_height)
+            .min(area.height);
+
+        let (x, y) = match self.anchor {
+            Anchor::Cell(cell) => {
+                let below = cell.y + 1;
+                let y = if below + height <= area.bottom() {
+                    below
+                } else {
+                    cell.y.saturating_sub(height).max(area.y)
+                };
+
+                (cell.x.min(area.right().saturating_sub(width)), y)
+            }
+            Anchor::Center => (
+                area.x + (area.width - width) / 2,
+                area.y + (area.height - height) / 2,
+            ),
+        };
+
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Renders the popup inside `area`, clearing what it covers.
+    pub fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup = self.area(area);
+        let mut block = Block::bordered().border_type(BorderType::Plain);
+        if let Some(title) = self.title {
+            block = block.title(title);
+        }
+
+        Clear.render(popup, buf);
+        Paragraph::new(self.lines)
+            .block(block)
+            .fg(Color::Gray)
+            .bg(Color::Black)
+            .render(popup, buf);
+    }
+}
+
+impl<'a> Overlay<'a> {
+    /// Creates an overlay with no popups.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `popup`, drawn over the popups of its layer added before it.
+    pub fn push(&mut self, popup: Popup<'a>) {
+        self.popups.push(popup);
+    }
+
+    /// Renders the popups inside `area`, lowest layer first.
+    pub fn render(mut self, area: Rect, buf: &mut Buffer) {
+        self.popups.sort_by_key(|popup| popup.layer);
+
+        for popup in self.popups {
+            popup.render(area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn popup(width: usize, height: usize, anchor: Anchor) -> Popup<'static> {
+        let lines = vec![Line::from("x".repeat(width - 2)); height - 2];
+        Popup::new(lines, anchor)
+    }
+
+    #[test]
+    fn should_place_box_below_pointed_cell() {
+        let area = Rect::new(0, 0, 40, 20);
+
+        assert_eq!(
+            popup(9, 3, Anchor::Cell(Position::new(5, 3))).area(area),
+            Rect::new(5, 4, 9, 3)
+        );
+    }
+
+    #[test]
+    fn should_keep_box_inside_area() {
+        let area = Rect::new(0, 0, 40, 20);
+
+        assert_eq!(
+            popup(9, 3, Anchor::Cell(Position::new(38, 19))).area(area),
+            Rect::new(31, 16, 9, 3)
+        );
+        assert_eq!(
+            popup(60, 30, Anchor::Center).max_size(30, 10).area(area),
+            Rect::new(5, 5, 30, 10)
+        );
+    }
+
+    #[test]
+    fn should_draw_higher_layers_over_lower_ones() {
+        let area = Rect::new(0, 0, 20, 6);
+        let mut buf = Buffer::empty(area);
+        let mut overlay = Overlay::new();
+
+        overlay.push(Popup::new(vec![Line::from("dialog")], Anchor::Center).layer(Layer::Dialog));
+        overlay.push(Popup::new(
+            vec![Line::from("menu menu menu")],
+            Anchor::Center,
+        ));
+        overlay.render(area, &mut buf);
+
+        let row: String = (0..area.width)
+            .map(|x| buf[(x, 2)].symbol().to_string())
+            .collect();
+        assert_eq!(row, "  │men│dialog│enu│  ");
+    }
+}
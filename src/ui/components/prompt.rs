@@ -0,0 +1,661 @@
+//! Prompt component rendering logic.
+//!
+//! This module defines the `Prompt` component, a single-line input shown in a box at the
+//! bottom of the terminal window whenever the editor asks for a value: a file name to
+//! save as or to open, or a line to go to. What is asked for is a [`PromptKind`], which
+//! provides a [`PromptSpec`]: the title and label shown, the history the entries go to,
+//! a validator run on `Enter`, an optional completion source cycled with `Tab`, given the
+//! history of the prompt, and the events the accepted input turns into. Everything else —
+//! editing at the cursor, history, the list of completions and rendering — is shared by
+//! all prompts.
+
+use crate::app::completion::sources;
+use crate::app::file;
+use crate::app::history::{History, HistoryKind};
+use crate::event::{AppEvent, BufferManagerEvent, CursorEvent, FileEvent};
+use crate::ui::components::FocusableComponent;
+use crate::ui::components::text_input::TextInput;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::buffer::Buffer;
+use ratatui::{
+    layout::{Alignment, Position, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph, Widget},
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Most completion candidates listed above the prompt at once.
+const MAX_LISTED_COMPLETIONS: usize = 8;
+
+/// What a prompt asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    /// A file name to save the buffer to.
+    SaveAs,
+    /// A file to open in a new buffer.
+    OpenFile,
+    /// A one-based line number to move the cursor to.
+    GotoLine,
+    /// A recently opened file to open again.
+    RecentFile,
+}
+
+/// How a prompt behaves.
+#[derive(Debug, Clone, Copy)]
+pub struct PromptSpec {
+    /// Shown on the border of the prompt.
+    pub title: &'static str,
+    /// Shown before the input.
+    pub label: &'static str,
+    /// Where submitted entries are remembered.
+    pub history: HistoryKind,
+    /// Returns why the input cannot be submitted, if it cannot.
+    pub validate: fn(&str) -> Result<(), String>,
+    /// Returns the candidates `Tab` cycles through for the input and the history of the
+    /// prompt, if the prompt completes.
+    pub complete: Option<fn(&str, &History) -> Vec<String>>,
+    /// Lists the candidates as soon as the prompt opens, with the first one as input.
+    pub complete_on_open: bool,
+    /// Returns the events carrying out a valid input.
+    pub submit: fn(&str) -> Vec<AppEvent>,
+}
+
+#[derive(Debug)]
+pub struct Prompt {
+    /// How the open prompt behaves.
+    pub spec: PromptSpec,
+    /// The current input from the user.
+    pub input: TextInput,
+    /// Why the last submitted input was refused, shown until the input changes.
+    pub error: Option<String>,
+    /// Entries submitted before, by kind of prompt.
+    pub histories: HashMap<HistoryKind, History>,
+    /// Candidates of the last `Tab`, and the one shown.
+    completions: Vec<String>,
+    completion_index: usize,
+}
+
+impl PromptKind {
+    /// Returns how prompts of this kind behave.
+    pub fn spec(&self) -> PromptSpec {
+        match self {
+            PromptKind::SaveAs => PromptSpec {
+                title: "Filename",
+                label: "Save as",
+                history: HistoryKind::Filename,
+                validate: |input| {
+                    require_input(input)?;
+                    file::check_save_path(Path::new(input))
+                },
+                complete: Some(complete_path),
+                complete_on_open: false,
+                submit: |input| vec![AppEvent::File(FileEvent::SaveAs(PathBuf::from(input)))],
+            },
+            PromptKind::OpenFile => PromptSpec {
+                title: "Open",
+                label: "File",
+                history: HistoryKind::Filename,
+                validate: require_input,
+                complete: Some(complete_path),
+                complete_on_open: false,
+                submit: |input| {
+                    vec![AppEvent::BufferManager(BufferManagerEvent::Open(
+                        PathBuf::from(input),
+                    ))]
+                },
+            },
+            PromptKind::GotoLine => PromptSpec {
+                title: "Go to",
+                label: "Line",
+                history: HistoryKind::Line,
+                validate: |input| match input.trim().parse::<usize>() {
+                    Ok(line) if line > 0 => Ok(()),
+                    _ => Err(String::from("Not a line number")),
+                },
+                complete: None,
+                complete_on_open: false,
+                submit: |input| {
+                    let line: usize = input.trim().parse().unwrap_or(1);
+                    vec![AppEvent::Cursor(CursorEvent::SetLinePosition(line - 1))]
+                },
+            },
+            PromptKind::RecentFile => PromptSpec {
+                title: "Recent files",
+                label: "File",
+                history: HistoryKind::Recent,
+                validate: require_input,
+                complete: Some(complete_recent),
+                complete_on_open: true,
+                submit: |input| {
+                    vec![AppEvent::BufferManager(BufferManagerEvent::Open(
+                        PathBuf::from(input),
+                    ))]
+                },
+            },
+        }
+    }
+}
+
+/// Returns the part of a path after its last separator, keeping the `/` that ends the
+/// name of a directory.
+fn last_component(path: &str) -> &str {
+    let trimmed = path.strip_suffix('/').unwrap_or(path);
+
+    trimmed.rfind('/').map_or(path, |slash| &path[slash + 1..])
+}
+
+/// Refuses an empty input.
+fn require_input(input: &str) -> Result<(), String> {
+    if input.is_empty() {
+        Err(String::from("Nothing entered"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the paths starting with `input`: the entries of the directory it names whose
+/// name starts with what follows its last `/`. A leading `~/` stands for the home
+/// directory, and hidden entries are only offered once their `.` is typed.
+fn complete_path(input: &str, _: &History) -> Vec<String> {
+    let (directory, name) = match input.rfind('/') {
+        Some(slash) => input.split_at(slash + 1),
+        None => ("", input),
+    };
+
+    let listed = match directory.strip_prefix("~/") {
+        Some(rest) => match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(rest),
+            None => return vec![],
+        },
+        None if directory.is_empty() => PathBuf::from("."),
+        None => PathBuf::from(directory),
+    };
+
+    sources::directory_entries(&listed, name.starts_with('.'))
+        .into_iter()
+        .filter(|entry| entry.starts_with(name))
+        .map(|entry| format!("{directory}{entry}"))
+        .collect()
+}
+
+/// Returns the files of `history` whose path contains `input`, ignoring case, the most
+/// recently opened first.
+fn complete_recent(input: &str, history: &History) -> Vec<String> {
+    let input = input.to_lowercase();
+
+    history
+        .entries()
+        .iter()
+        .rev()
+        .filter(|entry| entry.to_lowercase().contains(&input))
+        .cloned()
+        .collect()
+}
+
+impl Default for Prompt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Prompt {
+    /// Creates a new save-as `Prompt` with an empty input.
+    pub fn new() -> Self {
+        Self {
+            spec: PromptKind::SaveAs.spec(),
+            input: TextInput::new(),
+            error: None,
+            histories: HashMap::new(),
+            completions: vec![],
+            completion_index: 0,
+        }
+    }
+
+    /// Prepares the prompt to ask for a value of `kind`, with an empty input.
+    pub fn open(&mut self, kind: PromptKind) {
+        self.open_with(kind.spec());
+    }
+
+    /// Prepares the prompt to behave as `spec`, with an empty input, or the first
+    /// candidate when it completes on open.
+    pub fn open_with(&mut self, spec: PromptSpec) {
+        self.spec = spec;
+        self.input.clear();
+        self.error = None;
+        self.completions.clear();
+        self.history().reset();
+        if spec.complete_on_open {
+            self.complete();
+        }
+    }
+
+    /// Returns the history of the open prompt.
+    fn history(&mut self) -> &mut History {
+        self.histories.entry(self.spec.history).or_default()
+    }
+
+    /// Handles a key event while the prompt is active.
+    ///
+    /// Returns a vector of `AppEvent`s that may trigger further actions:
+    /// - `Esc`: Clears the input and returns focus to the editor.
+    /// - `Enter`: If the input is valid, emits the events of the prompt and returns focus;
+    ///   otherwise shows why it is not.
+    /// - `Up` / `Down`: Replaces the input with an older or newer entry.
+    /// - `Tab`: Replaces the input with the next completion candidate.
+    /// - Editing keys: Edit the input at the cursor (see [`TextInput::handle_key`]).
+    ///
+    /// Other keys are ignored.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<AppEvent> {
+        if key.code != KeyCode::Tab {
+            self.completions.clear();
+        }
+
+        match key.code {
+            KeyCode::Esc => {
+                self.input.clear();
+                self.error = None;
+                self.history().reset();
+                vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+            }
+            KeyCode::Enter => {
+                if let Err(reason) = (self.spec.validate)(self.input.text()) {
+                    self.error = Some(reason);
+                    return vec![];
+                }
+
+                let input = self.input.take();
+                self.history().push(&input);
+
+                let mut events = (self.spec.submit)(&input);
+                events.push(AppEvent::ChangeFocus(FocusableComponent::Editor));
+                events
+            }
+            KeyCode::Up => {
+                let input = self.input.text().to_string();
+                if let Some(entry) = self.history().previous(&input) {
+                    let entry = entry.to_string();
+                    self.input.set(entry);
+                }
+                vec![]
+            }
+            KeyCode::Down => {
+                if let Some(entry) = self.history().next() {
+                    let entry = entry.to_string();
+                    self.input.set(entry);
+                }
+                vec![]
+            }
+            KeyCode::Tab => {
+                self.complete();
+                vec![]
+            }
+            _ => {
+                if self.input.handle_key(key) {
+                    self.error = None;
+                }
+                vec![]
+            }
+        }
+    }
+
+    /// Shows the next completion candidate, asking the completion source for them on
+    /// the first `Tab`. After a single candidate, like a directory, `Tab` asks again to
+    /// go on from it.
+    fn complete(&mut self) {
+        let Some(complete) = self.spec.complete else {
+            return;
+        };
+
+        if self.completions.len() < 2 {
+            let history = self.histories.entry(self.spec.history).or_default();
+            self.completions = complete(self.input.text(), history);
+            self.completion_index = 0;
+        } else {
+            self.completion_index = (self.completion_index + 1) % self.completions.len();
+        }
+
+        if let Some(candidate) = self.completions.get(self.completion_index) {
+            self.input.set(candidate.clone());
+        }
+    }
+
+    /// Renders the prompt at the bottom of the screen.
+    ///
+    /// This includes a block with borders and the label of the prompt followed by the
+    /// current user input, and the reason the last input was refused.
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let mut spans = vec![Span::raw(format!(
+            "{}: {}",
+            self.spec.label,
+            self.input.text()
+        ))];
+
+        if let Some(error) = &self.error {
+            spans.push(Span::raw(format!("  {error}")).fg(Color::Red));
+        }
+
+        let prompt = Paragraph::new(Line::from(spans))
+            .block(
+                Block::default()
+                    .border_type(BorderType::Plain)
+                    .title(self.spec.title)
+                    .title_alignment(Alignment::Left),
+            )
+            .fg(Color::Yellow)
+            .bg(Color::Black)
+            .alignment(Alignment::Left);
+
+        prompt.render(Self::box_area(area), buf);
+        self.render_completions(area, buf);
+    }
+
+    /// Lists the completion candidates above the prompt box while cycling through
+    /// several, showing their last component and highlighting the one in the input.
+    fn render_completions(&self, area: Rect, buf: &mut Buffer) {
+        if self.completions.len() < 2 {
+            return;
+        }
+
+        let first = self
+            .completion_index
+            .saturating_sub(MAX_LISTED_COMPLETIONS - 1);
+        let lines: Vec<Line> = self
+            .completions
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(MAX_LISTED_COMPLETIONS)
+            .map(|(index, candidate)| {
+                let line = Line::from(last_component(candidate).to_string());
+
+                if index == self.completion_index {
+                    line.style(Style::default().bg(Color::Blue).fg(Color::White))
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let box_area = Self::box_area(area);
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+        let height = (lines.len() as u16 + 2).min(box_area.y);
+        let list = Rect {
+            x: box_area.x + (self.spec.label.chars().count() as u16 + 2),
+            y: box_area.y - height,
+            width,
+            height,
+        }
+        .intersection(area);
+
+        Clear.render(list, buf);
+        Paragraph::new(lines)
+            .block(Block::bordered().border_type(BorderType::Plain))
+            .fg(Color::Gray)
+            .bg(Color::Black)
+            .render(list, buf);
+    }
+
+    /// Returns the terminal position where the cursor should be drawn while typing.
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        let box_area = Self::box_area(area);
+        // The input follows the label and `: `, below the title row.
+        let typed = (self.spec.label.chars().count() + 2 + self.input.cursor()) as u16;
+
+        Position {
+            x: box_area.x + typed.min(box_area.width.saturating_sub(1)),
+            y: box_area.y + 1,
+        }
+    }
+
+    /// Returns the area of the prompt box: 3 lines from the bottom, inset by 2 columns
+    /// on each side.
+    fn box_area(area: Rect) -> Rect {
+        Rect {
+            x: 2,
+            y: area.height.saturating_sub(3),
+            width: area.width.saturating_sub(4),
+            height: 3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::AppEvent;
+    use crate::ui::components::FocusableComponent;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::fs;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn should_append_char_to_input_on_char_key() {
+        let mut prompt = Prompt::new();
+
+        prompt.handle_key(key(KeyCode::Char('a')));
+        prompt.handle_key(key(KeyCode::Char('b')));
+
+        assert_eq!(prompt.input, "ab");
+    }
+
+    #[test]
+    fn should_remove_last_char_on_backspace() {
+        let mut prompt = Prompt::new();
+
+        prompt.input.set("abc");
+        prompt.handle_key(key(KeyCode::Backspace));
+
+        assert_eq!(prompt.input, "ab");
+    }
+
+    #[test]
+    fn should_do_nothing_on_backspace_when_input_empty() {
+        let mut prompt = Prompt::new();
+
+        prompt.input.clear();
+        prompt.handle_key(key(KeyCode::Backspace));
+
+        assert_eq!(prompt.input, "");
+    }
+
+    #[test]
+    fn should_clear_input_and_change_focus_on_esc() {
+        let mut prompt = Prompt::new();
+
+        prompt.input.set("filename.txt");
+
+        let events = prompt.handle_key(key(KeyCode::Esc));
+
+        assert!(events.contains(&AppEvent::ChangeFocus(FocusableComponent::Editor)));
+        assert_eq!(prompt.input, "");
+    }
+
+    #[test]
+    fn should_emit_saveas_and_clear_input_on_enter_with_non_empty_input() {
+        let mut prompt = Prompt::new();
+
+        prompt.input.set("file.txt");
+
+        let events = prompt.handle_key(key(KeyCode::Enter));
+
+        assert_eq!(prompt.input, "");
+        assert!(matches!(
+            events.iter().find(|e| matches!(e, AppEvent::File(_))),
+            Some(AppEvent::File(_))
+        ));
+        assert!(events.contains(&AppEvent::ChangeFocus(FocusableComponent::Editor)));
+    }
+
+    #[test]
+    fn should_refuse_invalid_input_and_explain_why() {
+        let mut prompt = Prompt::new();
+        prompt.open(PromptKind::GotoLine);
+        prompt.input.set("abc");
+
+        let events = prompt.handle_key(key(KeyCode::Enter));
+
+        assert!(events.is_empty());
+        assert_eq!(prompt.input, "abc");
+        assert_eq!(prompt.error.as_deref(), Some("Not a line number"));
+    }
+
+    #[test]
+    fn should_go_to_zero_based_line() {
+        let mut prompt = Prompt::new();
+        prompt.open(PromptKind::GotoLine);
+        prompt.input.set("12");
+
+        let events = prompt.handle_key(key(KeyCode::Enter));
+
+        assert_eq!(
+            events,
+            vec![
+                AppEvent::Cursor(CursorEvent::SetLinePosition(11)),
+                AppEvent::ChangeFocus(FocusableComponent::Editor),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_share_history_between_prompts_of_the_same_history_kind() {
+        let mut prompt = Prompt::new();
+        prompt.input.set("file.txt");
+        prompt.handle_key(key(KeyCode::Enter));
+
+        prompt.open(PromptKind::OpenFile);
+        prompt.handle_key(key(KeyCode::Up));
+
+        assert_eq!(prompt.input, "file.txt");
+    }
+
+    #[test]
+    fn should_list_recent_files_newest_first_on_open() {
+        let mut prompt = Prompt::new();
+        let recent = prompt.histories.entry(HistoryKind::Recent).or_default();
+        ["/src/main.rs", "/notes.txt", "/src/lib.rs"]
+            .iter()
+            .for_each(|file| recent.push(file));
+
+        prompt.open(PromptKind::RecentFile);
+        assert_eq!(prompt.input, "/src/lib.rs");
+        prompt.handle_key(key(KeyCode::Tab));
+        assert_eq!(prompt.input, "/notes.txt");
+
+        prompt.input.set("SR");
+        prompt.handle_key(key(KeyCode::Char('C')));
+        prompt.handle_key(key(KeyCode::Tab));
+        prompt.handle_key(key(KeyCode::Tab));
+        assert_eq!(prompt.input, "/src/main.rs");
+        assert_eq!(
+            prompt.handle_key(key(KeyCode::Enter))[0],
+            AppEvent::BufferManager(BufferManagerEvent::Open(PathBuf::from("/src/main.rs")))
+        );
+    }
+
+    #[test]
+    fn should_cycle_through_completions_on_tab() {
+        let mut prompt = Prompt::new();
+        prompt.open_with(PromptSpec {
+            complete: Some(|input, _| vec![format!("{input}1"), format!("{input}2")]),
+            ..PromptKind::OpenFile.spec()
+        });
+        prompt.input.set("a");
+
+        prompt.handle_key(key(KeyCode::Tab));
+        assert_eq!(prompt.input, "a1");
+
+        prompt.handle_key(key(KeyCode::Tab));
+        assert_eq!(prompt.input, "a2");
+
+        prompt.handle_key(key(KeyCode::Tab));
+        assert_eq!(prompt.input, "a1");
+    }
+
+    #[test]
+    fn should_complete_paths_in_the_typed_directory() {
+        let directory = PathBuf::from("test_prompt_dir");
+        fs::create_dir_all(directory.join("notes")).unwrap();
+        fs::write(directory.join("note.txt"), "").unwrap();
+        fs::write(directory.join("other.txt"), "").unwrap();
+
+        let candidates = complete_path("test_prompt_dir/no", &History::new());
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            candidates,
+            vec!["test_prompt_dir/note.txt", "test_prompt_dir/notes/"]
+        );
+        assert_eq!(last_component("test_prompt_dir/notes/"), "notes/");
+        assert_eq!(last_component("note.txt"), "note.txt");
+    }
+
+    #[test]
+    fn should_complete_again_after_a_single_candidate() {
+        let mut prompt = Prompt::new();
+        prompt.open_with(PromptSpec {
+            complete: Some(|input, _| vec![format!("{input}/")]),
+            ..PromptKind::OpenFile.spec()
+        });
+        prompt.input.set("a");
+
+        prompt.handle_key(key(KeyCode::Tab));
+        prompt.handle_key(key(KeyCode::Tab));
+
+        assert_eq!(prompt.input, "a//");
+    }
+
+    #[test]
+    fn should_list_completions_above_the_prompt() {
+        let mut prompt = Prompt::new();
+        prompt.open_with(PromptSpec {
+            complete: Some(|_, _| vec![String::from("a/one"), String::from("a/two")]),
+            ..PromptKind::SaveAs.spec()
+        });
+        let area = Rect::new(0, 0, 30, 10);
+        let mut buf = Buffer::empty(area);
+
+        prompt.handle_key(key(KeyCode::Tab));
+        prompt.render(area, &mut buf);
+
+        let row = |y| {
+            (0..area.width)
+                .map(|x| buf[(x, y)].symbol())
+                .collect::<String>()
+        };
+        assert!(row(4).contains("one"));
+        assert!(row(5).contains("two"));
+    }
+
+    #[test]
+    fn should_edit_in_the_middle_and_place_the_cursor_there() {
+        let mut prompt = Prompt::new();
+        prompt.input.set("fle.txt");
+
+        prompt.handle_key(key(KeyCode::Home));
+        prompt.handle_key(key(KeyCode::Right));
+        prompt.handle_key(key(KeyCode::Char('i')));
+
+        assert_eq!(prompt.input, "file.txt");
+        assert_eq!(
+            prompt.cursor_position(Rect::new(0, 0, 80, 24)),
+            Position {
+                x: 2 + 9 + 2,
+                y: 22
+            }
+        );
+    }
+
+    #[test]
+    fn should_do_nothing_on_other_keys() {
+        let mut prompt = Prompt::new();
+
+        let events = prompt.handle_key(key(KeyCode::F(1)));
+
+        assert!(events.is_empty());
+    }
+}
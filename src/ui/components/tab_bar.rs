@@ -0,0 +1,75 @@
+//! Tab bar rendering logic.
+//!
+//! This module defines the `TabBar` component, drawn on the top row of the editor while
+//! the windows are tab pages, naming the document of each one, or while several buffers
+//! are open, naming every buffer. Names get a dot for unsaved changes, the focused one
+//! is highlighted, and clicking a name switches to it.
+
+use crate::app::{App, windows::Arrangement};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+/// The `TabBar` component responsible for naming the tab pages or the buffers.
+pub struct TabBar;
+
+impl TabBar {
+    /// Renders the names of the tab pages or buffers of `app` inside `area`, a single row.
+    pub fn render(app: &App, area: Rect, buf: &mut Buffer) {
+        let focused = app.windows.focused_document();
+
+        let tabs: Vec<Span> = Self::labels(app)
+            .into_iter()
+            .map(|(index, label)| match index == focused {
+                true => Span::styled(
+                    label,
+                    Style::default()
+                        .fg(Color::Black)
+                        .bg(Color::Gray)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                false => Span::styled(label, Style::default().fg(Color::Gray)),
+            })
+            .collect();
+
+        buf.set_style(area, Style::default().bg(Color::Reset));
+        Line::from(tabs).render(area, buf);
+    }
+
+    /// Returns the document named at `column` of the bar at `area`, if any.
+    pub fn document_at(app: &App, area: Rect, column: u16) -> Option<usize> {
+        let mut x = area.x;
+
+        Self::labels(app).into_iter().find_map(|(index, label)| {
+            let start = x;
+            x = x.saturating_add(label.chars().count() as u16);
+            (start..x).contains(&column).then_some(index)
+        })
+    }
+
+    /// Returns the documents the bar names, in order, with their labels: the tab pages,
+    /// or else every buffer.
+    fn labels(app: &App) -> Vec<(usize, String)> {
+        let documents = app.buffers.documents();
+        let indexes: Vec<usize> = match app.windows.arrangement {
+            Arrangement::Tabs => app.windows.documents().to_vec(),
+            _ => (0..documents.len()).collect(),
+        };
+
+        indexes
+            .into_iter()
+            .filter_map(|index| Some((index, documents.get(index)?)))
+            .map(|(index, document)| {
+                let modified = if document.is_modified() { " ●" } else { "" };
+                (
+                    index,
+                    format!(" {}{modified} ", document.file.display_name()),
+                )
+            })
+            .collect()
+    }
+}
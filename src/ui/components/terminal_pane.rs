@@ -0,0 +1,376 @@
+//! Terminal pane component rendering logic.
+//!
+//! This module defines the `TerminalPane` component, opened with `:terminal`: a split at
+//! the bottom of the editor running a shell, to run tests or git commands without leaving
+//! the editor. While it has the focus, keys go to the shell; `Ctrl-W` followed by `w`,
+//! `W`, `k` or `Ctrl-W` gives the focus back to the editor windows, and `Ctrl-W w` from
+//! the last window brings it to the pane again.
+//!
+//! The screen is kept as lines of text with the cursor on the last one: printable chars,
+//! carriage returns, line feeds, backspaces and tabs move it, and the sequences erasing
+//! the line or the screen or moving the cursor sideways are understood. Colors and other
+//! escape sequences are dropped, and the shell is told its terminal is `dumb`.
+
+use crate::app::pty::Pty;
+use crate::event::{AppEvent, Event, TerminalEvent};
+use crate::ui::components::FocusableComponent;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, BorderType, Clear, Paragraph, Widget},
+};
+use std::{io, sync::mpsc};
+
+/// Lines kept above the screen.
+const SCROLLBACK: usize = 1000;
+
+/// Columns between tab stops.
+const TAB_WIDTH: usize = 8;
+
+/// Where the parser is within an escape sequence.
+#[derive(Debug, Default)]
+enum Escape {
+    #[default]
+    None,
+    /// After `ESC`.
+    Started,
+    /// In a control sequence (`ESC [`), with its parameters so far.
+    Csi(String),
+    /// In an operating system command (`ESC ]`), ended by `BEL` or `ESC \`.
+    Osc,
+}
+
+#[derive(Debug)]
+pub struct TerminalPane {
+    pty: Option<Pty>,
+    /// Lines of the screen and scrollback; the cursor is on the last one.
+    lines: Vec<Vec<char>>,
+    col: usize,
+    escape: Escape,
+    /// Bytes of a char split between two reads.
+    pending: Vec<u8>,
+    /// Set after `Ctrl-W`, which starts a window command.
+    window_command: bool,
+}
+
+impl Default for TerminalPane {
+    fn default() -> Self {
+        Self {
+            pty: None,
+            lines: vec![vec![]],
+            col: 0,
+            escape: Escape::None,
+            pending: vec![],
+            window_command: false,
+        }
+    }
+}
+
+impl TerminalPane {
+    /// Creates a new, closed `TerminalPane`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` while the shell of the pane runs.
+    pub fn is_open(&self) -> bool {
+        self.pty.is_some()
+    }
+
+    /// Starts a shell in the pane, which is drawn in `area`.
+    pub fn open(&mut self, area: Rect, sender: mpsc::Sender<Event>) -> io::Result<()> {
+        let inner = Self::inner(area);
+        let pty = Pty::spawn(inner.height, inner.width, sender)?;
+
+        *self = Self::default();
+        self.pty = Some(pty);
+        Ok(())
+    }
+
+    /// Kills the shell.
+    pub fn close(&mut self) {
+        self.pty = None;
+    }
+
+    /// Returns `true` if the shell with process id `pid` is the one of the pane.
+    pub fn runs(&self, pid: u32) -> bool {
+        self.pty.as_ref().is_some_and(|pty| pty.pid() == pid)
+    }
+
+    /// Tells the shell the pane is now drawn in `area`.
+    pub fn resize(&self, area: Rect) {
+        let inner = Self::inner(area);
+        if let Some(pty) = &self.pty {
+            pty.resize(inner.height, inner.width);
+        }
+    }
+
+    /// Writes `bytes` to the shell, as when pasting.
+    pub fn write(&mut self, bytes: &[u8]) {
+        if let Some(pty) = &mut self.pty {
+            pty.write(bytes);
+        }
+    }
+
+    /// Handles a key event while the pane is active.
+    ///
+    /// Keys are sent to the shell, except after `Ctrl-W`:
+    /// - `w`, `W`, `k`, `p` or `Ctrl-W`: Return focus to the editor.
+    /// - `c` or `q`: Kill the shell, close the pane and return focus to the editor.
+    /// - `.`: Send `Ctrl-W` to the shell.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<AppEvent> {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        if std::mem::take(&mut self.window_command) {
+            return match key.code {
+                KeyCode::Char('w' | 'W' | 'k' | 'p') => {
+                    vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+                }
+                KeyCode::Char('c' | 'q') => vec![AppEvent::Terminal(TerminalEvent::Close)],
+                KeyCode::Char('.') => {
+                    self.write(&[0x17]);
+                    vec![]
+                }
+                _ => vec![],
+            };
+        }
+
+        if control && key.code == KeyCode::Char('w') {
+            self.window_command = true;
+            return vec![];
+        }
+
+        if let Some(bytes) = key_bytes(key) {
+            self.write(&bytes);
+        }
+        vec![]
+    }
+
+    /// Puts what the shell printed on the screen.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        let pending = std::mem::take(&mut self.pending);
+
+        let (text, rest) = match std::str::from_utf8(&pending) {
+            Ok(text) => (text.to_string(), &[][..]),
+            // An incomplete char at the end waits for the next read.
+            Err(err) if err.error_len().is_none() => {
+                let (valid, rest) = pending.split_at(err.valid_up_to());
+                (String::from_utf8_lossy(valid).into_owned(), rest)
+            }
+            Err(_) => (String::from_utf8_lossy(&pending).into_owned(), &[][..]),
+        };
+        self.pending = rest.to_vec();
+
+        for c in text.chars() {
+            self.put(c);
+        }
+
+        let excess = self.lines.len().saturating_sub(SCROLLBACK);
+        self.lines.drain(..excess);
+    }
+
+    /// Returns the lines of the screen and scrollback.
+    pub fn text(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .map(|line| line.iter().collect())
+            .collect()
+    }
+
+    fn put(&mut self, c: char) {
+        match std::mem::take(&mut self.escape) {
+            Escape::None => {}
+            Escape::Started => {
+                self.escape = match c {
+                    '[' => Escape::Csi(String::new()),
+                    ']' => Escape::Osc,
+                    _ => Escape::None,
+                };
+                return;
+            }
+            Escape::Csi(mut parameters) => {
+                match c {
+                    '@'..='~' => self.control_sequence(&parameters, c),
+                    _ => {
+                        parameters.push(c);
+                        self.escape = Escape::Csi(parameters);
+                    }
+                }
+                return;
+            }
+            Escape::Osc => {
+                self.escape = match c {
+                    '\x07' => Escape::None,
+                    // Starts the `ESC \` ending the command.
+                    '\x1b' => Escape::Started,
+                    _ => Escape::Osc,
+                };
+                return;
+            }
+        }
+
+        let line = self.lines.last_mut().expect("the screen has a line");
+        match c {
+            '\x1b' => self.escape = Escape::Started,
+            '\r' => self.col = 0,
+            '\n' => {
+                self.lines.push(vec![]);
+                self.col = 0;
+            }
+            '\x08' => self.col = self.col.saturating_sub(1),
+            '\t' => self.col = (self.col / TAB_WIDTH + 1) * TAB_WIDTH,
+            c if c.is_control() => {}
+            c => {
+                if line.len() < self.col {
+                    line.resize(self.col, ' ');
+                }
+                match line.get_mut(self.col) {
+                    Some(cell) => *cell = c,
+                    None => line.push(c),
+                }
+                self.col += 1;
+            }
+        }
+    }
+
+    /// Applies the control sequence `ESC [ parameters command`.
+    fn control_sequence(&mut self, parameters: &str, command: char) {
+        let count = parameters.parse::<usize>().unwrap_or(1).max(1);
+        let line = self.lines.last_mut().expect("the screen has a line");
+
+        match (command, parameters) {
+            ('K', "" | "0") => line.truncate(self.col),
+            ('K', "1") => line.iter_mut().take(self.col + 1).for_each(|c| *c = ' '),
+            ('K', "2") => line.clear(),
+            ('J', "2" | "3") => {
+                self.lines = vec![vec![]];
+                self.col = 0;
+            }
+            ('C', _) => self.col += count,
+            ('D', _) => self.col = self.col.saturating_sub(count),
+            ('G', _) => self.col = count - 1,
+            _ => {}
+        }
+    }
+
+    /// Returns the area inside the borders of the pane drawn in `area`.
+    fn inner(area: Rect) -> Rect {
+        Block::bordered().inner(area)
+    }
+
+    /// Returns where the cursor of the shell is when the pane is drawn in `area`.
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        let inner = Self::inner(area);
+        let rows = self.lines.len().min(inner.height as usize) as u16;
+
+        Position::new(
+            inner.x + (self.col as u16).min(inner.width.saturating_sub(1)),
+            inner.y + rows.saturating_sub(1),
+        )
+    }
+
+    /// Renders the last lines of the screen in `area`, with a brighter border while the
+    /// pane has the focus.
+    pub fn render(&self, area: Rect, focused: bool, buf: &mut Buffer) {
+        let border = match focused {
+            true => Color::Cyan,
+            false => Color::DarkGray,
+        };
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(border))
+            .title(" terminal ");
+        let inner = block.inner(area);
+
+        let skip = self.lines.len().saturating_sub(inner.height as usize);
+        let lines: Vec<Line> = self.lines[skip..]
+            .iter()
+            .map(|line| Line::from(line.iter().collect::<String>()))
+            .collect();
+
+        Clear.render(area, buf);
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+}
+
+/// Returns the bytes a terminal sends for `key`, if any.
+fn key_bytes(key: KeyEvent) -> Option<Vec<u8>> {
+    let control = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+    let bytes = match key.code {
+        KeyCode::Char(c) if control && c.is_ascii() => vec![c.to_ascii_lowercase() as u8 & 0x1f],
+        KeyCode::Char(c) => {
+            let mut bytes = c.to_string().into_bytes();
+            if alt {
+                bytes.insert(0, 0x1b);
+            }
+            bytes
+        }
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        KeyCode::Home => b"\x1b[H".to_vec(),
+        KeyCode::End => b"\x1b[F".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        _ => return None,
+    };
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_put_shell_output_on_the_screen() {
+        let mut pane = TerminalPane::new();
+
+        pane.feed(b"$ ls\r\nsrc\tCargo.toml\r\n\x1b]0;title\x07\x1b[1;32m$\x1b[0m ");
+        assert_eq!(pane.text(), vec!["$ ls", "src     Cargo.toml", "$ "]);
+
+        // Readline redrawing the line after a backspace, and a char split between reads.
+        pane.feed(b"gti\x08\x08it\x1b[K \xc3");
+        pane.feed(b"\xa9");
+        assert_eq!(pane.text()[2], "$ git é");
+        assert_eq!(
+            pane.cursor_position(Rect::new(0, 0, 20, 10)),
+            Position::new(8, 3)
+        );
+
+        pane.feed(b"\x1b[H\x1b[2J$ ");
+        assert_eq!(pane.text(), vec!["$ "]);
+    }
+
+    #[test]
+    fn should_route_window_commands_back_to_the_editor() {
+        let mut pane = TerminalPane::new();
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        assert!(
+            pane.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL))
+                .is_empty()
+        );
+        assert_eq!(
+            pane.handle_key(key(KeyCode::Char('w'))),
+            vec![AppEvent::ChangeFocus(FocusableComponent::Editor)]
+        );
+        assert!(pane.handle_key(key(KeyCode::Char('w'))).is_empty());
+        assert_eq!(
+            key_bytes(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(vec![0x03])
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Single-line text input shared by the prompts.
+//!
+//! This module defines [`TextInput`], the text being typed in the command line or a
+//! prompt together with a cursor inside it. It handles the editing keys common to every
+//! prompt, so each component only deals with the keys that mean something to it.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Text typed on a single line, and the position of the cursor in it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TextInput {
+    text: String,
+    /// Position of the cursor, in chars from the start of the text.
+    cursor: usize,
+}
+
+impl TextInput {
+    /// Creates an empty input.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the typed text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the position of the cursor, in chars.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns `true` when nothing is typed.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replaces the text, moving the cursor to its end.
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.chars().count();
+    }
+
+    /// Empties the input.
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    /// Returns the text, leaving the input empty.
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    /// Applies an editing key, returning `false` when the key does not edit the text:
+    ///
+    /// - `Left` / `Right`: Moves the cursor by one char.
+    /// - `Home` / `Ctrl-A` and `End` / `Ctrl-E`: Moves the cursor to the start or end.
+    /// - `Backspace` / `Delete`: Removes the char before or under the cursor.
+    /// - `Ctrl-U`: Removes everything before the cursor.
+    /// - `Ctrl-W`: Removes the word before the cursor.
+    /// - Character keys: Inserts the char at the cursor.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let len = self.text.chars().count();
+
+        match key.code {
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(len),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::End => self.cursor = len,
+            KeyCode::Char('e') if ctrl => self.cursor = len,
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.remove(self.cursor - 1, self.cursor);
+            }
+            KeyCode::Backspace => {}
+            KeyCode::Delete if self.cursor < len => self.remove(self.cursor, self.cursor + 1),
+            KeyCode::Delete => {}
+            KeyCode::Char('u') if ctrl => self.remove(0, self.cursor),
+            KeyCode::Char('w') if ctrl => self.remove(self.word_start(), self.cursor),
+            KeyCode::Char(_) if ctrl => return false,
+            KeyCode::Char(c) => {
+                let index = self.byte_index(self.cursor);
+                self.text.insert(index, c);
+                self.cursor += 1;
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Returns where the word before the cursor starts, skipping spaces before it.
+    fn word_start(&self) -> usize {
+        let before: Vec<char> = self.text.chars().take(self.cursor).collect();
+        let spaces = before
+            .iter()
+            .rev()
+            .take_while(|c| c.is_whitespace())
+            .count();
+        let word = before[..before.len() - spaces]
+            .iter()
+            .rev()
+            .take_while(|c| !c.is_whitespace())
+            .count();
+
+        self.cursor - spaces - word
+    }
+
+    /// Removes the chars `start..end` and puts the cursor at `start`.
+    fn remove(&mut self, start: usize, end: usize) {
+        let range = self.byte_index(start)..self.byte_index(end);
+        self.text.replace_range(range, "");
+        self.cursor = start;
+    }
+
+    fn byte_index(&self, chars: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(chars)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+}
+
+impl PartialEq<&str> for TextInput {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn input(text: &str, cursor: usize) -> TextInput {
+        let mut input = TextInput::new();
+        input.set(text);
+        input.cursor = cursor;
+
+        input
+    }
+
+    #[test]
+    fn should_insert_and_delete_at_the_cursor() {
+        let mut input = input("héllo", 5);
+
+        input.handle_key(key(KeyCode::Left));
+        input.handle_key(key(KeyCode::Left));
+        input.handle_key(key(KeyCode::Char('X')));
+        input.handle_key(key(KeyCode::Backspace));
+        input.handle_key(key(KeyCode::Backspace));
+
+        assert_eq!(input, "hélo");
+        assert_eq!(input.cursor(), 2);
+
+        input.handle_key(key(KeyCode::Delete));
+        assert_eq!(input, "héo");
+    }
+
+    #[test]
+    fn should_jump_to_start_and_end() {
+        let mut input = input("abc", 1);
+
+        input.handle_key(ctrl('e'));
+        assert_eq!(input.cursor(), 3);
+
+        input.handle_key(key(KeyCode::Home));
+        assert_eq!(input.cursor(), 0);
+    }
+
+    #[test]
+    fn should_delete_word_and_line_before_cursor() {
+        let mut input = input("write some  file", 12);
+
+        input.handle_key(ctrl('w'));
+        assert_eq!(input, "write file");
+        assert_eq!(input.cursor(), 6);
+
+        input.handle_key(ctrl('u'));
+        assert_eq!(input, "file");
+        assert_eq!(input.cursor(), 0);
+    }
+
+    #[test]
+    fn should_leave_other_keys_to_the_component() {
+        let mut input = input("abc", 3);
+
+        assert!(!input.handle_key(key(KeyCode::Enter)));
+        assert!(!input.handle_key(ctrl('x')));
+        assert_eq!(input, "abc");
+    }
+}
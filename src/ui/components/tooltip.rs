@@ -0,0 +1,30 @@
+//! Tooltip rendering logic.
+//!
+//! This module defines the `TooltipBox` component, which shows a [`Tooltip`] in a
+//! [`Popup`] just below the cell it points at, over the completion menu.
+
+use crate::{
+    app::mouse::Tooltip,
+    ui::components::popup::{Anchor, Layer, Popup},
+};
+use ratatui::{layout::Position, text::Line};
+
+/// The `TooltipBox` component responsible for drawing floating tooltips.
+pub struct TooltipBox;
+
+impl TooltipBox {
+    /// Returns the popup showing `tooltip`.
+    pub fn popup(tooltip: &Tooltip) -> Popup<'static> {
+        let lines: Vec<Line> = tooltip
+            .text
+            .lines()
+            .map(|line| Line::from(line.to_string()))
+            .collect();
+
+        Popup::new(
+            lines,
+            Anchor::Cell(Position::new(tooltip.column, tooltip.row)),
+        )
+        .layer(Layer::Tooltip)
+    }
+}
@@ -0,0 +1,183 @@
+//! Undo panel component rendering logic.
+//!
+//! This module defines the `UndoPanel` component, opened with `:undotree`: a panel on the
+//! right of the editor listing the states of the undo history, newest first, with when
+//! each was reached and how its size changed. Moving the selection brings the buffer to
+//! the selected state, to preview it; `Enter` keeps it and `Esc` goes back to the state
+//! the panel was opened in.
+
+use crate::app::{file, undo::UndoState};
+use crate::event::{AppEvent, BufferEvent};
+use crate::types::position::Position;
+use crate::ui::components::FocusableComponent;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::buffer::Buffer;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, Paragraph, Widget},
+};
+use std::time::Instant;
+
+/// Width of the panel, borders included.
+const WIDTH: u16 = 36;
+
+#[derive(Debug, Default)]
+pub struct UndoPanel {
+    /// The states of the history, oldest first.
+    pub states: Vec<UndoState>,
+    /// The state shown in the buffer.
+    pub selected: usize,
+    /// The state when the panel was opened, brought back by `Esc`.
+    pub original: usize,
+}
+
+impl UndoPanel {
+    /// Creates a new, empty `UndoPanel`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists `states`, selecting the current one.
+    pub fn open(&mut self, states: Vec<UndoState>) {
+        self.selected = states.iter().position(|state| state.current).unwrap_or(0);
+        self.original = self.selected;
+        self.states = states;
+    }
+
+    /// Handles a key event while the panel is active.
+    ///
+    /// Returns a vector of `AppEvent`s that may trigger further actions:
+    /// - `k`/`Up` and `j`/`Down`: Select and show a newer or an older state.
+    /// - `g` and `G`: Select and show the newest or the oldest state.
+    /// - `Enter`: Keep the selected state and return focus to the editor.
+    /// - `q` or `Esc`: Go back to the original state and return focus to the editor.
+    ///
+    /// Other keys are ignored.
+    pub fn handle_key(&mut self, key: KeyEvent, position: Position) -> Vec<AppEvent> {
+        let oldest = 0;
+        let newest = self.states.len().saturating_sub(1);
+        let selected = match key.code {
+            KeyCode::Enter => return vec![AppEvent::ChangeFocus(FocusableComponent::Editor)],
+            KeyCode::Char('q') | KeyCode::Esc => {
+                return vec![
+                    AppEvent::Buffer(BufferEvent::UndoTo {
+                        state: self.original,
+                        position,
+                    }),
+                    AppEvent::ChangeFocus(FocusableComponent::Editor),
+                ];
+            }
+            KeyCode::Char('k') | KeyCode::Up => (self.selected + 1).min(newest),
+            KeyCode::Char('j') | KeyCode::Down => self.selected.saturating_sub(1),
+            KeyCode::Char('g') => newest,
+            KeyCode::Char('G') => oldest,
+            _ => return vec![],
+        };
+
+        if selected == self.selected {
+            return vec![];
+        }
+        self.selected = selected;
+
+        vec![AppEvent::Buffer(BufferEvent::UndoTo {
+            state: selected,
+            position,
+        })]
+    }
+
+    /// Describes a state as its number, when it was reached and how its size changed
+    /// from the state before.
+    fn describe(&self, index: usize, now: Instant) -> String {
+        let state = self.states[index];
+        let age = match state.time {
+            Some(time) => file::format_age(now.duration_since(time)),
+            None => String::from("original"),
+        };
+        let change = match index.checked_sub(1).map(|before| self.states[before]) {
+            Some(before) => format!("{:+} chars", state.chars as i64 - before.chars as i64),
+            None => format!("{} chars", state.chars),
+        };
+
+        format!("{index:>4}  {change:<12} {age}")
+    }
+
+    /// Renders the panel on the right of `area`.
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let width = WIDTH.min(area.width);
+        let panel = Rect {
+            x: area.right().saturating_sub(width),
+            width,
+            ..area
+        };
+        let now = Instant::now();
+        let visible = panel.height.saturating_sub(2) as usize;
+        // Keep the selection in view, the newest states at the top.
+        let first = (self.states.len().saturating_sub(1))
+            .saturating_sub(self.selected)
+            .saturating_sub(visible.saturating_sub(1));
+
+        let lines: Vec<Line> = (0..self.states.len())
+            .rev()
+            .skip(first)
+            .take(visible)
+            .map(|index| {
+                let style = match index == self.selected {
+                    true => Style::default().fg(Color::Black).bg(Color::Gray),
+                    false => Style::default(),
+                };
+                Line::styled(self.describe(index, now), style)
+            })
+            .collect();
+
+        Clear.render(panel, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .title(" :undotree ")
+                    .title_bottom(" Enter keeps, Esc reverts "),
+            )
+            .fg(Color::Gray)
+            .bg(Color::Reset)
+            .render(panel, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn state(chars: usize, current: bool) -> UndoState {
+        UndoState {
+            time: Some(Instant::now()),
+            lines: 1,
+            chars,
+            current,
+        }
+    }
+
+    #[test]
+    fn should_preview_the_selected_state_and_revert_on_esc() {
+        let mut panel = UndoPanel::new();
+        let position = Position::new(0, 0);
+        panel.open(vec![state(0, false), state(1, false), state(3, true)]);
+
+        assert!(panel.handle_key(key(KeyCode::Up), position).is_empty());
+        assert_eq!(
+            panel.handle_key(key(KeyCode::Char('G')), position),
+            vec![AppEvent::Buffer(BufferEvent::UndoTo { state: 0, position })]
+        );
+        assert_eq!(
+            panel.handle_key(key(KeyCode::Esc), position)[0],
+            AppEvent::Buffer(BufferEvent::UndoTo { state: 2, position })
+        );
+        assert!(panel.describe(2, Instant::now()).contains("+2 chars"));
+    }
+}
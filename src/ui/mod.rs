@@ -6,20 +6,75 @@
 
 use crate::{
     app::App,
-    ui::components::{FocusableComponent, editor::Editor},
+    ui::components::{
+        FocusableComponent, completion_menu::CompletionMenu, editor::Editor,
+        notification_bar::NotificationBar, popup::Overlay, tooltip::TooltipBox,
+    },
 };
 use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+use std::time::Instant;
 
-/// UI components such as `Editor`, `FilenamePrompt`, etc.
+/// UI components such as `Editor`, `Prompt`, etc.
 pub mod components;
 
+/// Setting the terminal up and restoring it, even on panics.
+pub mod terminal;
+
+/// The colors the components are drawn with.
+pub mod theme;
+
 impl Widget for &App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         Editor::render(self, area, buf);
+        if let Some(panel) = self.windows.panel_area(area) {
+            let focused = self.focus == FocusableComponent::TerminalPane;
+            self.terminal_pane.render(panel, focused, buf);
+        }
+        let window = self.windows.focused_area(area);
+        let mut overlay = Overlay::new();
+        let tabstop = self.buffers.tabstop(self.buffers.active(), &self.options);
+
+        if self.completion.active {
+            let anchor = Editor::screen_position(
+                self.buffers.active(),
+                &self.options,
+                tabstop,
+                window,
+                self.completion.start,
+            );
+            if let Some(popup) = CompletionMenu::popup(&self.completion, anchor) {
+                overlay.push(popup);
+            }
+        }
+
+        if let Some(tooltip) =
+            self.mouse
+                .tooltip(self.buffers.active(), &self.options, tabstop, window)
+        {
+            overlay.push(TooltipBox::popup(&tooltip));
+        }
+
+        if self.focus == FocusableComponent::Dialog {
+            overlay.push(self.dialog.popup(&self.theme));
+        }
+        overlay.render(area, buf);
 
-        if self.focus == FocusableComponent::FilenamePrompt {
-            self.filename_prompt.render(area, buf);
+        if self.latency.visible {
+            components::latency_overlay::LatencyOverlay::render(self, area, buf);
+        }
+
+        match self.focus {
+            FocusableComponent::Prompt => self.prompt.render(area, buf),
+            FocusableComponent::CommandLine => self.command_line.render(area, buf),
+            FocusableComponent::ConfirmPrompt => self.confirm_prompt.render(area, buf),
+            FocusableComponent::MessagePane => self.message_pane.render(area, buf),
+            FocusableComponent::Dialog => {}
+            FocusableComponent::UndoPanel => self.undo_panel.render(area, buf),
+            FocusableComponent::Editor | FocusableComponent::TerminalPane => {
+                if let Some(notification) = self.notifications.current(Instant::now()) {
+                    NotificationBar::render(notification, Instant::now(), area, buf);
+                }
+            }
         }
     }
 }
-
@@ -0,0 +1,155 @@
+//! Terminal setup and restoration for the Zack text editor.
+//!
+//! The editor puts the terminal in raw mode on the alternate screen, captures the mouse
+//! and bracketed pastes, and changes the cursor shape. [`restore`] undoes all of it, and
+//! runs whichever way the editor stops: when the [`TerminalGuard`] returned by [`init`]
+//! is dropped, on a normal exit or an early error return, and from a panic hook before
+//! the panic message and backtrace are printed, so they are readable and the shell is
+//! usable afterwards.
+//!
+//! The window title names the active file while the editor runs; the title the terminal
+//! had before is saved on its title stack and put back on exit.
+//!
+//! On Unix, `Ctrl-Z` and a `SIGTSTP` from another program suspend the editor like other
+//! terminal programs: the terminal is restored before the process stops, and set up and
+//! redrawn again once the shell resumes it.
+
+use crossterm::{
+    cursor::SetCursorStyle,
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    style::Print,
+    terminal::{EnterAlternateScreen, SetTitle, enable_raw_mode},
+};
+use ratatui::DefaultTerminal;
+use std::{
+    io::{self, stdout},
+    panic,
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// Saves the window title on the title stack of xterm-like terminals.
+const PUSH_TITLE: &str = "\x1b[22;0t";
+
+/// Puts back the window title saved by [`PUSH_TITLE`].
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Set when the editor should suspend itself, by `Ctrl-Z` or a `SIGTSTP`.
+static SUSPEND: LazyLock<Arc<AtomicBool>> = LazyLock::new(|| Arc::new(AtomicBool::new(false)));
+
+/// Restores the terminal when dropped.
+#[derive(Debug)]
+pub struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Sets the terminal up for the editor, restoring it on panics, and returns it with the
+/// guard restoring it when dropped.
+///
+/// Raw mode also turns off the flow control of the terminal (`IXON`), so `Ctrl-S` and
+/// `Ctrl-Q` reach the editor instead of pausing and resuming its output.
+pub fn init() -> (DefaultTerminal, TerminalGuard) {
+    let terminal = ratatui::init();
+    install_panic_hook();
+    install_suspend_handler();
+    let _ = crossterm::execute!(stdout(), EnableBracketedPaste, Print(PUSH_TITLE));
+
+    (terminal, TerminalGuard)
+}
+
+/// Leaves raw mode and the alternate screen, stops capturing the mouse and pastes, and
+/// gives the cursor its usual shape and the window its previous title back. Errors are
+/// ignored: there is nothing left to do about them.
+pub fn restore() {
+    ratatui::restore();
+    let _ = crossterm::execute!(
+        stdout(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        SetCursorStyle::DefaultUserShape,
+        Print(POP_TITLE)
+    );
+}
+
+/// Sets the window title of the terminal.
+pub fn set_title(title: &str) {
+    let _ = crossterm::execute!(stdout(), SetTitle(title));
+}
+
+/// Asks the editor to suspend itself once the current event is handled.
+pub fn request_suspend() {
+    SUSPEND.store(true, Ordering::Relaxed);
+}
+
+/// Returns `true` once after the editor was asked to suspend itself.
+pub fn take_suspend_request() -> bool {
+    SUSPEND.swap(false, Ordering::Relaxed)
+}
+
+/// Restores the terminal, stops the process until the shell resumes it, then sets the
+/// terminal up again and clears it, so the next frame is drawn in full. Mouse capture is
+/// turned back on when `mouse` is set. Does nothing but redraw where processes cannot be
+/// stopped.
+///
+/// # Errors
+///
+/// Returns an `std::io::Error` if the process cannot be stopped or the terminal cannot
+/// be set up again.
+pub fn suspend(terminal: &mut DefaultTerminal, mouse: bool) -> io::Result<()> {
+    restore();
+
+    // `SIGTSTP` is handled to get here, so the process stops with `SIGSTOP`, which cannot
+    // be handled; the shell resumes it with `SIGCONT`.
+    #[cfg(unix)]
+    signal_hook::low_level::raise(signal_hook::consts::SIGSTOP)?;
+
+    reinit(terminal, mouse)
+}
+
+/// Sets the terminal up again, capturing the mouse when `mouse` is set, and clears it
+/// so the next frame is drawn whole, as after a suspend or when reading it failed.
+///
+/// # Errors
+///
+/// Returns an `std::io::Error` if the terminal cannot be set up.
+pub fn reinit(terminal: &mut DefaultTerminal, mouse: bool) -> io::Result<()> {
+    enable_raw_mode()?;
+    crossterm::execute!(
+        stdout(),
+        EnterAlternateScreen,
+        EnableBracketedPaste,
+        Print(PUSH_TITLE)
+    )?;
+    if mouse {
+        crossterm::execute!(stdout(), EnableMouseCapture)?;
+    }
+
+    terminal.clear()
+}
+
+/// Turns a `SIGTSTP`, which would stop the process with the terminal still in raw mode,
+/// into a request to suspend.
+fn install_suspend_handler() {
+    #[cfg(unix)]
+    if let Err(err) =
+        signal_hook::flag::register(signal_hook::consts::SIGTSTP, Arc::clone(&SUSPEND))
+    {
+        tracing::warn!("Cannot handle SIGTSTP: {err}");
+    }
+}
+
+/// Restores the terminal before the current panic hook reports a panic.
+fn install_panic_hook() {
+    let report = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        report(info);
+    }));
+}
@@ -0,0 +1,147 @@
+//! Theme module for the Zack text editor.
+//!
+//! This module defines [`Theme`], the colors the UI picks from instead of hardcoding
+//! them in components. For now it holds the background of the editor for each
+//! [`BufferKind`], subtle tints telling at a glance whether the text is a file, a
+//! scratch buffer, a read-only view or a followed file, which `:set notint` turns off,
+//! the border of the editor while it is not focused, and the selected button of
+//! dialogs.
+//!
+//! Each color can be changed in the theme file of the configuration directory, one
+//! `name = color` per line, the color being a name like `dark gray`, a `#rrggbb` value
+//! or an index of the 256-color palette:
+//!
+//! ```text
+//! scratch_background = #1c1c28
+//! dialog_selected = light blue
+//! ```
+
+use crate::app::buffer_manager::BufferKind;
+use ratatui::style::Color;
+use std::str::FromStr;
+
+/// Name of the theme file in the configuration directory.
+pub const THEME_FILE: &str = "theme";
+
+/// Colors used when rendering the UI.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Background of buffers editing a file.
+    pub file_background: Color,
+    /// Background of buffers without a file.
+    pub scratch_background: Color,
+    /// Background of read-only buffers.
+    pub read_only_background: Color,
+    /// Background of buffers following their file.
+    pub following_background: Color,
+    /// Border of the editor while another component has the focus.
+    pub inactive_border: Color,
+    /// Background of the selected button of a dialog.
+    pub dialog_selected: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            file_background: Color::Reset,
+            scratch_background: Color::Rgb(28, 28, 40),
+            read_only_background: Color::Rgb(40, 28, 28),
+            following_background: Color::Rgb(26, 38, 30),
+            inactive_border: Color::DarkGray,
+            dialog_selected: Color::Yellow,
+        }
+    }
+}
+
+impl Theme {
+    /// Creates the default theme.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the editor background for a buffer of `kind`.
+    pub fn background(&self, kind: BufferKind) -> Color {
+        match kind {
+            BufferKind::File => self.file_background,
+            BufferKind::Scratch => self.scratch_background,
+            BufferKind::ReadOnly => self.read_only_background,
+            BufferKind::Following => self.following_background,
+        }
+    }
+
+    /// Reads the theme file `text`, changing the default colors it names. Returns the
+    /// theme with the errors of the lines that could not be read.
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut theme = Self::default();
+        let mut errors = vec![];
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Err(err) = theme.set(line) {
+                errors.push(format!("{THEME_FILE}:{}: {err}", index + 1));
+            }
+        }
+
+        (theme, errors)
+    }
+
+    /// Sets the color of a `name = color` line.
+    fn set(&mut self, line: &str) -> Result<(), String> {
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Expected name = color: {line}"))?;
+        let (name, value) = (name.trim(), value.trim());
+
+        let slot = match name {
+            "file_background" => &mut self.file_background,
+            "scratch_background" => &mut self.scratch_background,
+            "read_only_background" => &mut self.read_only_background,
+            "following_background" => &mut self.following_background,
+            "inactive_border" => &mut self.inactive_border,
+            "dialog_selected" => &mut self.dialog_selected,
+            _ => return Err(format!("Unknown color: {name}")),
+        };
+        *slot = Color::from_str(value).map_err(|_| format!("Invalid color: {value}"))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_leave_files_untinted_and_tint_other_kinds() {
+        let theme = Theme::new();
+
+        assert_eq!(theme.background(BufferKind::File), Color::Reset);
+        assert_ne!(theme.background(BufferKind::Scratch), Color::Reset);
+        assert_ne!(
+            theme.background(BufferKind::ReadOnly),
+            theme.background(BufferKind::Following)
+        );
+    }
+
+    #[test]
+    fn should_read_colors_from_the_theme_file() {
+        let (theme, errors) = Theme::parse(
+            "# colors\nscratch_background = #102030\ndialog_selected = light blue\nborder = red\ninactive_border = nope\n",
+        );
+
+        assert_eq!(theme.scratch_background, Color::Rgb(16, 32, 48));
+        assert_eq!(theme.dialog_selected, Color::LightBlue);
+        assert_eq!(theme.inactive_border, Color::DarkGray);
+        assert_eq!(
+            errors,
+            vec![
+                String::from("theme:4: Unknown color: border"),
+                String::from("theme:5: Invalid color: nope"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! End-to-end tests of commands working with files, registers, marks and hooks.
+
+use zack::{
+    app::{driver::Driver, modes::EditorMode},
+    ui::components::FocusableComponent,
+};
+
+#[test]
+fn should_save_with_ctrl_s_in_insert_mode() {
+    let path = std::path::PathBuf::from("test_driver_ctrl_s.txt");
+    let mut driver = Driver::new("");
+    driver.app.buffers.active_mut().file.path = Some(path.clone());
+
+    driver.keys("ihi<C-s>");
+    let saved = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(saved.unwrap(), "hi\n");
+    assert_eq!(
+        driver.app.mode.get_current_mode(),
+        EditorMode::Insert { append: false }
+    );
+}
+
+#[test]
+fn should_list_registers_and_marks() {
+    let mut driver = Driver::with_size("one\ntwo words", 60, 10);
+
+    driver.keys("jvl<Esc>/words<Enter>:marks<Enter>");
+    assert_eq!(driver.app.focus, FocusableComponent::MessagePane);
+    let screen = driver.screen();
+    assert!(screen.contains("<      2     1  two words"));
+    assert!(screen.contains(">      2     2  two words"));
+
+    driver.keys("q:registers<Enter>");
+    let screen = driver.screen();
+    assert!(screen.contains("\"/  words"));
+    assert!(screen.contains("\":  registers"));
+}
+
+#[test]
+fn should_run_autocommands_when_their_hook_fires() {
+    let mut driver = Driver::new("one\n");
+
+    driver.keys(":au InsertLeave * set wordcount<Enter>");
+    assert!(!driver.app.options.wordcount);
+
+    driver.keys("ix<Esc>");
+    assert!(driver.app.options.wordcount);
+    assert_eq!(driver.text(), "xone\n");
+}
+
+#[test]
+fn should_ask_whether_to_save_before_quitting_with_changes() {
+    let mut driver = Driver::with_size("one", 60, 12);
+    driver.keys("ix<Esc>:q<Enter>");
+    assert_eq!(driver.app.focus, FocusableComponent::Dialog);
+    let screen = driver.screen();
+    assert!(screen.contains("Save changes to [No Name] before quitting?"));
+    assert!(screen.contains("[ Yes ] [ No ] [ Cancel ]"));
+
+    driver.keys("<Esc>");
+    assert!(driver.app.running);
+    assert_eq!(driver.app.focus, FocusableComponent::Editor);
+
+    driver.keys(":q<Enter>ll<Enter>");
+    assert!(driver.app.running);
+    driver.keys(":q<Enter>n");
+    assert!(!driver.app.running);
+}
+
+#[test]
+fn should_open_relative_paths_from_the_local_directory() {
+    let directory = std::path::PathBuf::from("test_driver_lcd_dir");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("notes.txt"), "notes\n").unwrap();
+
+    let mut driver = Driver::new("text");
+    driver.keys(":lcd missing_directory<Enter>");
+    assert_eq!(driver.app.buffers.active().directory, None);
+
+    driver.keys(":lcd test_driver_lcd_dir<Enter>:e notes.txt<Enter>");
+    let canonical = directory.canonicalize().unwrap();
+    assert_eq!(
+        driver.app.buffers.active().file.path,
+        Some(canonical.join("notes.txt"))
+    );
+    assert_eq!(driver.text(), "notes\n");
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn should_pick_files_opened_before_with_oldfiles() {
+    let mut driver = Driver::new("text");
+    driver.keys(":e README.md<Enter>:bp<Enter>:oldfiles<Enter>");
+
+    assert_eq!(driver.app.focus, FocusableComponent::Prompt);
+    let picked = driver.app.prompt.input.text().to_string();
+    assert!(picked.ends_with("README.md") && picked.starts_with('/'));
+
+    driver.keys("<Enter>");
+    assert_eq!(driver.app.buffers.active_index(), 1);
+}
@@ -0,0 +1,170 @@
+//! End-to-end tests of what is drawn on the screen: the text, the status line, the
+//! buffer line, the scrollbar and the window title.
+
+use crossterm::event::{
+    Event as CrosstermEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use std::path::PathBuf;
+use zack::{
+    app::{driver::Driver, filetype},
+    event::Event,
+    types::position::Position,
+    ui::components::editor::{SCROLLBAR_MARK, SCROLLBAR_THUMB},
+};
+
+#[test]
+fn should_name_the_file_and_its_changes_in_the_window_title() {
+    let mut driver = Driver::new("hello");
+    assert_eq!(driver.app.window_title(), "[No Name] — zack");
+
+    driver.app.buffers.active_mut().file.path = Some(PathBuf::from("/tmp/notes.txt"));
+    driver.keys("ix<Esc>");
+    assert_eq!(driver.app.window_title(), "notes.txt ● — zack");
+}
+
+#[test]
+fn should_draw_tabs_up_to_the_next_tabstop() {
+    let mut driver = Driver::with_size("a\tb\nab\tc", 40, 10);
+
+    driver.keys("ll");
+    let screen = driver.screen();
+
+    assert!(screen.contains("│a   b"));
+    assert!(screen.contains("│ab  c"));
+    assert_eq!(driver.screen_cursor(), (5, 1));
+}
+
+#[test]
+fn should_draw_tabs_with_the_tabstop_of_the_file_type() {
+    let mut driver = Driver::with_size("#!/bin/sh\n\techo", 40, 10);
+
+    driver.keys(":set ts=8<Enter>");
+    assert!(driver.screen().contains("│        echo"));
+
+    let (filetypes, _) = filetype::parse_config("[filetype.sh]\ntabstop = 2\n");
+    driver.app.buffers.set_filetypes(filetypes);
+    assert!(driver.screen().contains("│  echo"));
+}
+
+#[test]
+fn should_show_the_debug_hud_with_f12() {
+    let mut driver = Driver::with_size("one\ntwo", 80, 20);
+
+    driver.keys("<F12>j");
+    let screen = driver.screen();
+    assert!(screen.contains("debug (F12)"));
+    assert!(screen.contains("rope 2 lines 7 chars 7 B"));
+    assert!(screen.contains("MoveDown"));
+
+    driver.keys("<F12>");
+    assert!(!driver.screen().contains("debug (F12)"));
+}
+
+#[test]
+fn should_fold_indented_blocks_and_step_over_them() {
+    let mut driver = Driver::with_size("fn main() {\n    one();\n    two();\n}\nend", 40, 10);
+
+    driver.keys("jzc");
+    assert_eq!(driver.cursor(), Position::new(0, 0));
+    let screen = driver.screen();
+    assert!(screen.contains("fn main() { ⋯ 2 lines"));
+    assert!(!screen.contains("one();"));
+
+    driver.keys("j");
+    assert_eq!(driver.cursor(), Position::new(3, 0));
+    driver.keys("jk");
+    assert_eq!(driver.cursor(), Position::new(3, 0));
+    driver.keys("k");
+    assert_eq!(driver.cursor(), Position::new(0, 0));
+
+    driver.keys("za");
+    assert!(driver.screen().contains("one();"));
+}
+
+#[test]
+fn should_count_the_buffer_or_the_selection_in_the_status_line() {
+    let mut driver = Driver::new("one two\nthree\n");
+
+    driver.keys(":set wordcount<Enter>");
+    assert!(driver.screen().contains(" 2 lines, 3 words, 14 chars "));
+
+    driver.keys("vl");
+    assert!(
+        driver
+            .screen()
+            .contains(" 1 line, 1 word, 2 chars selected ")
+    );
+}
+
+#[test]
+fn should_show_a_scrollbar_marking_search_matches() {
+    let text: Vec<String> = (1..=40).map(|line| format!("line {line}")).collect();
+    let mut driver = Driver::with_size(&text.join("\n"), 40, 12);
+
+    let thumb_rows = |screen: &str| {
+        screen
+            .lines()
+            .filter(|row| row.ends_with(SCROLLBAR_THUMB))
+            .count()
+    };
+    let screen = driver.screen();
+    assert!(screen.lines().nth(1).unwrap().ends_with(SCROLLBAR_THUMB));
+    assert!(thumb_rows(&screen) < 10);
+
+    driver.keys("/line 40<Enter>");
+    assert!(
+        driver
+            .screen()
+            .lines()
+            .any(|row| row.ends_with(SCROLLBAR_MARK))
+    );
+
+    driver.keys(":set noscrollbar<Enter>");
+    assert_eq!(thumb_rows(&driver.screen()), 0);
+}
+
+#[test]
+fn should_list_the_buffers_on_the_top_row_and_switch_on_click() {
+    let mut driver = Driver::with_size("one", 40, 10);
+    driver.app.options.mouse = true;
+    driver.keys(":clone<Enter>");
+    assert_eq!(driver.app.buffers.active_index(), 1);
+    let screen = driver.screen();
+    assert_eq!(screen.lines().next(), Some(" [No Name]  [No Name]"));
+
+    driver
+        .app
+        .process_event(Event::Crossterm(CrosstermEvent::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 2,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        })));
+    assert_eq!(driver.app.buffers.active_index(), 0);
+
+    driver.keys(":set nobufferline<Enter>");
+    assert!(
+        !driver
+            .screen()
+            .lines()
+            .next()
+            .unwrap()
+            .contains("[No Name]")
+    );
+}
+
+#[test]
+fn should_keep_the_cursor_visible_when_the_terminal_shrinks() {
+    let text: Vec<String> = (1..=30).map(|line| format!("line {line}")).collect();
+    let mut driver = Driver::with_size(&text.join("\n"), 40, 30);
+
+    driver.keys(":20<Enter>");
+    driver.screen();
+    assert_eq!(driver.app.buffers.active().scroll, 0);
+
+    driver.resize(40, 10);
+    let scroll = driver.app.buffers.active().scroll;
+    // Eight text rows fit between the borders.
+    assert!((scroll..scroll + 8).contains(&19));
+    assert!(driver.screen().contains("line 20"));
+}
@@ -0,0 +1,142 @@
+//! End-to-end tests of editing text: operators and motions, lists, wrapping and
+//! indentation.
+
+use zack::{
+    app::{driver::Driver, filetype, modes::EditorMode},
+    types::position::Position,
+};
+
+#[test]
+fn should_surround_a_visual_selection_in_one_undo_step() {
+    let mut driver = Driver::new("let a = b;");
+
+    driver.keys("llllllllvl(");
+    assert_eq!(driver.text(), "let a = (b;)");
+    assert_eq!(driver.app.mode.get_current_mode(), EditorMode::Normal);
+
+    driver.keys("u:set noautosurround<Enter>v\"");
+    assert_eq!(driver.text(), "let a = b;");
+}
+
+#[test]
+fn should_continue_list_bullets_on_enter() {
+    let mut driver = Driver::new("1. one");
+
+    driver.keys("llllla<Enter>two<Esc>");
+    assert_eq!(driver.text(), "1. one\n2. two");
+
+    driver.keys(":set noautocontinue<Enter>a<Enter>three");
+    assert_eq!(driver.text(), "1. one\n2. two\nthree");
+}
+
+#[test]
+fn should_end_the_list_on_enter_after_an_empty_bullet() {
+    let mut driver = Driver::new("- item");
+    driver.keys("lllllla<Enter><Enter>x");
+    assert_eq!(driver.text(), "- item\nx");
+
+    let mut driver = Driver::new("  // note");
+    driver.app.buffers.active_mut().file.path = Some(std::path::PathBuf::from("note.rs"));
+    driver.keys("llllllllla<Enter><Enter>x");
+    assert_eq!(driver.text(), "  // note\n  x");
+}
+
+#[test]
+fn should_wrap_lines_to_the_text_width() {
+    let mut driver = Driver::new("- one two three\nfour five\n\nsix");
+
+    driver.keys(":set textwidth=10<Enter>gqq");
+    assert_eq!(driver.text(), "- one two\n  three\nfour five\n\nsix");
+
+    driver.keys("u:set tw=16<Enter>jgqk");
+    assert_eq!(driver.text(), "- one two three\nfour five\n\nsix");
+    driver.keys("jjgqk");
+    assert_eq!(driver.text(), "- one two three\nfour five\n\nsix");
+}
+
+#[test]
+fn should_keep_the_cursor_on_the_last_character_outside_insert_mode() {
+    let mut driver = Driver::new("abc");
+
+    driver.keys("lllll");
+    assert_eq!(driver.cursor(), Position::new(0, 2));
+
+    driver.keys("ad");
+    assert_eq!(driver.cursor(), Position::new(0, 4));
+    driver.keys("<Esc>");
+    assert_eq!(
+        (driver.text().as_str(), driver.cursor()),
+        ("abcd", Position::new(0, 3))
+    );
+
+    driver.keys("hhd$");
+    assert_eq!(
+        (driver.text().as_str(), driver.cursor()),
+        ("a", Position::new(0, 0))
+    );
+}
+
+#[test]
+fn should_compose_operators_with_motions_and_text_objects() {
+    let mut driver = Driver::new("one two three\nfour\nfive");
+
+    driver.keys("ldiw");
+    assert_eq!(driver.text(), " two three\nfour\nfive");
+
+    driver.keys("y");
+    assert_eq!(
+        driver.app.mode.get_current_mode(),
+        EditorMode::OperatorPending {
+            operator: zack::app::operator::Operator::Yank,
+            count: None,
+        }
+    );
+    assert!(driver.screen().contains("pending y"));
+    driver.keys("<Esc>");
+    assert_eq!(driver.app.mode.get_current_mode(), EditorMode::Normal);
+
+    driver.keys("gUaw");
+    assert_eq!(driver.text(), " TWO three\nfour\nfive");
+
+    driver.keys("jyyp");
+    assert_eq!(driver.text(), " TWO three\nfour\nfour\nfive");
+
+    driver.keys("2dd");
+    assert_eq!(driver.text(), " TWO three\nfour");
+    assert_eq!(driver.cursor(), Position::new(1, 0));
+
+    driver.keys("cchey<Esc>");
+    assert_eq!(driver.text(), " TWO three\nhey");
+
+    driver.keys("u");
+    assert_eq!(driver.text(), " TWO three\nfour");
+}
+
+#[test]
+fn should_append_after_the_cursor_and_copy_whole_lines() {
+    let mut driver = Driver::new("ac\nnext");
+
+    driver.keys("ab<Esc>");
+    assert_eq!(driver.text(), "abc\nnext");
+
+    driver.keys("Vj*");
+    assert_eq!(driver.app.search.pattern.as_deref(), Some("abc\nnext"));
+}
+
+#[test]
+fn should_indent_with_tabs_or_spaces_on_tab() {
+    let mut driver = Driver::new("x");
+
+    driver.keys("i<Tab><Esc>:set et<Enter>:set ts=2<Enter>la<Tab>");
+    assert_eq!(driver.text(), "\tx  ");
+}
+
+#[test]
+fn should_indent_with_the_settings_of_the_file_type() {
+    let mut driver = Driver::new("#!/bin/sh\necho");
+    let (filetypes, _) = filetype::parse_config("[filetype.sh]\nexpandtab = true\ntabstop = 2\n");
+    driver.app.buffers.set_filetypes(filetypes);
+
+    driver.keys("ji<Tab>");
+    assert_eq!(driver.text(), "#!/bin/sh\n  echo");
+}
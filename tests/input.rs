@@ -0,0 +1,87 @@
+//! End-to-end tests of how keys are read: counts, held keys, literal keys and mappings.
+
+use crossterm::event::{Event as CrosstermEvent, KeyCode};
+use std::time::{Duration, Instant};
+use zack::{
+    app::{driver::Driver, modes::EditorMode},
+    event::{AppEvent, Event},
+    types::position::Position,
+};
+
+#[test]
+fn should_clamp_huge_counts_to_the_buffer() {
+    let mut driver = Driver::with_size("one\ntwo\nthree", 40, 10);
+
+    driver.keys("99999");
+    assert!(driver.screen().contains("normal 99999"));
+
+    driver.keys("9j");
+    assert_eq!(driver.cursor(), Position::new(2, 0));
+    assert!(!driver.screen().contains("normal 9"));
+
+    driver.keys("3l2h");
+    assert_eq!(driver.cursor(), Position::new(2, 1));
+}
+
+#[test]
+fn should_join_the_moves_of_a_held_key() {
+    let mut driver = Driver::new("one\ntwo\nthree\nfour");
+    let down = CrosstermEvent::Key(KeyCode::Char('j').into());
+    let input = driver.app.event_handler.input_sender();
+    for _ in 0..5 {
+        input.send(down.clone());
+    }
+
+    let first = driver.app.event_handler.try_next().unwrap();
+    driver.app.process_event(first);
+    let joined = driver.app.event_handler.try_next().unwrap();
+    assert!(matches!(
+        joined,
+        Event::App(AppEvent::Repeat { count: 5, .. })
+    ));
+
+    driver.app.process_event(joined);
+    assert_eq!(driver.cursor(), Position::new(3, 0));
+}
+
+#[test]
+fn should_insert_literal_keys_and_code_points_after_ctrl_v() {
+    let mut driver = Driver::new("");
+
+    driver.keys("i<C-v>u00e9<C-v><C-a><C-v><Tab>");
+    assert_eq!(driver.text(), "é\u{1}\t");
+    assert_eq!(driver.cursor(), Position::new(0, 3));
+
+    driver.keys("<C-v>u");
+    assert!(driver.screen().contains("insert ^V hex"));
+    driver.keys("41-<C-v>x7e<C-v>uz<C-v>U1F600<Esc>");
+    assert_eq!(driver.text(), "é\u{1}\tA-~uz😀");
+    assert_eq!(driver.app.mode.get_current_mode(), EditorMode::Normal);
+}
+
+#[test]
+fn should_run_keys_mapped_with_map() {
+    let mut driver = Driver::new("one\ntwo");
+
+    driver.keys(":map --buffer normal Q :2<Enter>:map n <lt>C-d> delete<Enter>");
+    driver.keys("Q<C-d>l");
+    assert_eq!(driver.text(), "one\nwo");
+    assert!(driver.app.running);
+
+    driver.keys(":map i <lt>C-l> normal<Enter>ix<C-l>");
+    assert_eq!(driver.text(), "one\nxwo");
+    assert_eq!(driver.app.mode.get_current_mode(), EditorMode::Normal);
+}
+
+#[test]
+fn should_go_idle_some_time_after_the_last_input() {
+    let mut driver = Driver::new("text");
+    let later = || Instant::now() + Duration::from_secs(3);
+
+    assert!(!driver.app.is_idle(Instant::now()));
+    assert!(driver.app.is_idle(later()));
+
+    driver.keys("l");
+    assert!(!driver.app.is_idle(Instant::now()));
+    assert!(driver.app.is_idle(later()));
+}
@@ -0,0 +1,63 @@
+//! End-to-end tests of undo steps: how edits are grouped, and moving between the states
+//! of the history with `:earlier`, `:later` and `:undotree`.
+
+use crossterm::event::Event as CrosstermEvent;
+use std::time::Duration;
+use zack::{
+    app::{driver::Driver, keymap::parse_keys},
+    event::recording::Recorded,
+    ui::components::FocusableComponent,
+};
+
+#[test]
+fn should_coalesce_a_replayed_run_into_one_undo_step() {
+    let recording: Vec<_> = parse_keys("iOne. Two. Three.<Esc>")
+        .into_iter()
+        .map(|key| Recorded {
+            at: Duration::ZERO,
+            event: CrosstermEvent::Key(key),
+        })
+        .collect();
+    let mut driver = Driver::new("");
+
+    driver.replay(&recording);
+    assert_eq!(driver.app.buffers.active().buffer.history().len(), 1);
+
+    driver.keys("u");
+    assert_eq!(driver.text(), "");
+}
+
+#[test]
+fn should_keep_the_sentence_break_points_of_fast_typing() {
+    let mut driver = Driver::new("");
+
+    driver.keys("iOne. Two. Three.<Esc>");
+    assert_eq!(driver.app.buffers.active().buffer.history().len(), 3);
+
+    driver.keys("u");
+    assert_eq!(driver.text(), "One. Two.");
+}
+
+#[test]
+fn should_preview_and_pick_undo_states() {
+    let mut driver = Driver::with_size("", 60, 10);
+
+    driver.keys("ia<Esc>ab<Esc>ac<Esc>");
+    driver.keys(":earlier 2<Enter>");
+    assert_eq!(driver.text(), "a");
+    driver.keys(":later<Enter>");
+    assert_eq!(driver.text(), "ab");
+
+    driver.keys(":undotree<Enter>");
+    assert_eq!(driver.app.focus, FocusableComponent::UndoPanel);
+    assert!(driver.screen().contains(":undotree"));
+
+    driver.keys("G");
+    assert_eq!(driver.text(), "");
+    driver.keys("<Esc>");
+    assert_eq!(driver.text(), "ab");
+
+    driver.keys(":undotree<Enter>kk<Enter>");
+    assert_eq!(driver.app.focus, FocusableComponent::Editor);
+    assert_eq!(driver.text(), "abc");
+}
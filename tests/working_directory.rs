@@ -0,0 +1,30 @@
+//! End-to-end tests of `:cd`, which moves the whole process to another directory, so
+//! they run in a test binary of their own, away from tests using relative paths.
+
+use std::fs;
+use zack::app::driver::Driver;
+
+#[test]
+fn should_keep_writing_open_files_where_they_are_after_cd() {
+    let root = std::env::current_dir().unwrap();
+    let directory = root.join("test_cd_dir");
+    fs::create_dir_all(directory.join("sub")).unwrap();
+    fs::write(directory.join("a.txt"), "one\n").unwrap();
+    std::env::set_current_dir(&directory).unwrap();
+
+    let mut driver = Driver::new("");
+    driver.keys(":e a.txt<Enter>:cd sub<Enter>ix<Esc>:w<Enter>");
+    let moved = std::env::current_dir().unwrap();
+    let name = driver.app.buffers.active().file.display_name();
+
+    std::env::set_current_dir(&root).unwrap();
+    let written = fs::read_to_string(directory.join("a.txt")).unwrap();
+    let misplaced = directory.join("sub/a.txt").exists();
+    let sub = directory.join("sub").canonicalize().unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(moved, sub);
+    assert_eq!(written, "xone\n");
+    assert!(!misplaced);
+    assert!(name.ends_with("test_cd_dir/a.txt"));
+}